@@ -41,14 +41,17 @@ else
     echo "📦 Using fallback OpenSSL paths: $OPENSSL_LIB_DIR"
 fi
 
+# pi-slideshow-rs is a workspace member, so build output lands in the workspace root
+BINARY=../target/aarch64-unknown-linux-musl/release/pi-slideshow-rs
+
 # Build statically linked ARM64 binary using musl
 cargo build --target aarch64-unknown-linux-musl --release
 
 if [ $? -eq 0 ]; then
     echo "✅ Cross-compilation successful!"
-    echo "📁 Binary location: target/aarch64-unknown-linux-musl/release/pi-slideshow-rs"
+    echo "📁 Binary location: $BINARY"
     echo "📋 Binary info:"
-    file target/aarch64-unknown-linux-musl/release/pi-slideshow-rs
+    file $BINARY
     echo ""
     echo "🚀 Copy to Raspberry Pi and run with: ./pi-slideshow-rs"
 else
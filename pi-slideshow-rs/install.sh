@@ -64,10 +64,38 @@ echo ""
 # Create directories
 echo "📁 Creating directories..."
 sudo mkdir -p /var/signage/images
+sudo mkdir -p /var/signage/bin
 sudo mkdir -p /home/pi/signage
 sudo chown -R pi:pi /var/signage
 sudo chown -R pi:pi /home/pi/signage
 
+# Settings file, also where first-boot setup saves what it is given; an existing one is kept
+if [ ! -f /var/signage/signage.toml ]; then
+    echo "📝 Writing /var/signage/signage.toml..."
+    tee /var/signage/signage.toml > /dev/null << EOF
+mqtt_broker = "mqtt://$MANAGEMENT_SERVER:1883"
+couchdb_url = "http://$MANAGEMENT_SERVER:5984"
+EOF
+    chmod 600 /var/signage/signage.toml
+fi
+
+# Add pi user to netdev for Wi-Fi setup through NetworkManager
+sudo usermod -a -G netdev pi
+
+# Let the service reboot and power off the Pi through logind, for the reboot and shutdown commands
+echo "🔐 Allowing reboot and shutdown for pi..."
+sudo tee /etc/polkit-1/rules.d/50-signage.rules > /dev/null << 'EOF'
+polkit.addRule(function(action, subject) {
+    if ((action.id == "org.freedesktop.login1.reboot" ||
+         action.id == "org.freedesktop.login1.reboot-multiple-sessions" ||
+         action.id == "org.freedesktop.login1.power-off" ||
+         action.id == "org.freedesktop.login1.power-off-multiple-sessions") &&
+        subject.user == "pi") {
+        return polkit.Result.YES;
+    }
+});
+EOF
+
 # Add pi user to video group for framebuffer access
 echo "👤 Adding pi user to video group..."
 sudo usermod -a -G video pi
@@ -76,11 +104,12 @@ sudo usermod -a -G video pi
 echo "🖥️ Setting framebuffer permissions..."
 sudo chmod 666 /dev/fb0 2>/dev/null || echo "⚠️  Could not set framebuffer permissions (normal if running remotely)"
 
-# Copy binary (assuming it's in current directory)
+# Copy binary (assuming it's in current directory). It goes under /var/signage, the one place
+# the service may write, so self-updates can keep their releases next to it.
 if [ -f "./pi-slideshow-rs" ]; then
     echo "📦 Installing binary..."
-    cp ./pi-slideshow-rs /home/pi/signage/
-    chmod +x /home/pi/signage/pi-slideshow-rs
+    cp ./pi-slideshow-rs /var/signage/bin/
+    chmod +x /var/signage/bin/pi-slideshow-rs
 else
     echo "❌ Binary 'pi-slideshow-rs' not found in current directory"
     echo "Please build the binary first with './build.sh' and copy it here"
@@ -99,13 +128,13 @@ StartLimitIntervalSec=300
 StartLimitBurst=5
 
 [Service]
-Type=simple
+Type=notify
+NotifyAccess=main
 User=pi
 Group=video
-WorkingDirectory=/home/pi/signage
-ExecStart=/home/pi/signage/pi-slideshow-rs \\
-  --mqtt-broker mqtt://$MANAGEMENT_SERVER:1883 \\
-  --couchdb-url http://$MANAGEMENT_SERVER:5984 \\
+WorkingDirectory=/var/signage
+ExecStart=/var/signage/bin/pi-slideshow-rs \\
+  --config /var/signage/signage.toml \\
   --tv-id $TV_ID \\
   --image-dir /var/signage/images \\
   --delay 30 \\
@@ -115,6 +144,8 @@ ExecStart=/home/pi/signage/pi-slideshow-rs \\
 # Restart configuration
 Restart=always
 RestartSec=10
+# Restart the endpoint if the display loop stops pinging (past the built-in --watchdog-secs)
+WatchdogSec=180
 KillMode=mixed
 TimeoutStopSec=30
 
@@ -124,7 +155,9 @@ PrivateTmp=true
 ProtectSystem=strict
 ProtectHome=true
 ReadWritePaths=/var/signage /tmp
-SupplementaryGroups=video
+# Creates /var/log/pi-slideshow for the log file, owned by the service user
+LogsDirectory=pi-slideshow
+SupplementaryGroups=video netdev
 
 # Environment
 Environment=RUST_LOG=info
@@ -143,19 +176,6 @@ echo "🔄 Enabling systemd service..."
 sudo systemctl daemon-reload
 sudo systemctl enable signage.service
 
-# Create log rotation configuration
-echo "📝 Setting up log rotation..."
-sudo tee /etc/logrotate.d/signage > /dev/null << EOF
-/var/log/signage.log {
-    daily
-    rotate 7
-    compress
-    missingok
-    notifempty
-    create 644 pi pi
-}
-EOF
-
 # Test connectivity
 echo "🔍 Testing connectivity to management server..."
 if ping -c 1 "$MANAGEMENT_SERVER" > /dev/null 2>&1; then
@@ -181,6 +201,9 @@ sudo rm -f /etc/systemd/system/signage.service
 # Remove log rotation
 sudo rm -f /etc/logrotate.d/signage
 
+# Remove the reboot and shutdown permission
+sudo rm -f /etc/polkit-1/rules.d/50-signage.rules
+
 # Remove directories (ask for confirmation)
 echo "Remove signage directories? [y/N]"
 read -r response
@@ -203,7 +226,7 @@ echo "🎉 Installation Complete!"
 echo "========================"
 echo ""
 echo "📋 Summary:"
-echo "   • Binary installed to: /home/pi/signage/pi-slideshow-rs"
+echo "   • Binary installed to: /var/signage/bin/pi-slideshow-rs"
 echo "   • Service name: signage.service"
 echo "   • TV ID: $TV_ID"
 echo "   • Management server: $MANAGEMENT_SERVER"
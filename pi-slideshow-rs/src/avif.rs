@@ -0,0 +1,114 @@
+use image::error::{LimitError, LimitErrorKind};
+use image::{DynamicImage, ImageError, ImageResult};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+// AVIF decoding takes seconds per image on a Pi's cores and scales with the pixel count, so
+// AVIF files are opened under a budget: the size in the file's header is checked before
+// decoding, and the decode itself runs on its own thread with a deadline. Decoding is only
+// compiled in with the `avif` feature; without it AVIF files fail to open like any unknown format.
+
+const DEFAULT_MAX_PIXELS: u64 = 36_000_000;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(15);
+// The image properties (and their ispe boxes) sit in the meta box at the start of the file
+const HEADER_SCAN_BYTES: u64 = 64 * 1024;
+
+struct Limits {
+    max_pixels: u64,
+    timeout: Duration,
+}
+
+static LIMITS: OnceLock<Limits> = OnceLock::new();
+// Files whose decode ran past the deadline. The abandoned decode can't be stopped, so they
+// are not tried again until the process restarts.
+static TIMED_OUT: Mutex<Option<HashSet<PathBuf>>> = Mutex::new(None);
+
+// Set once at startup (--avif-max-megapixels, --avif-decode-timeout)
+pub fn configure(max_megapixels: u32, timeout: Duration) {
+    let limits = Limits { max_pixels: max_megapixels as u64 * 1_000_000, timeout };
+    if LIMITS.set(limits).is_err() {
+        warn!("AVIF limits already configured, ignoring");
+    }
+}
+
+pub fn is_avif(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.to_string_lossy().eq_ignore_ascii_case("avif"))
+}
+
+// image::open, with the size check and decode deadline for AVIF files
+pub fn open(path: &Path) -> ImageResult<DynamicImage> {
+    if !is_avif(path) {
+        return image::open(path);
+    }
+
+    let (max_pixels, timeout) = LIMITS.get().map_or((DEFAULT_MAX_PIXELS, DEFAULT_TIMEOUT), |limits| (limits.max_pixels, limits.timeout));
+
+    if TIMED_OUT.lock().unwrap().as_ref().is_some_and(|paths| paths.contains(path)) {
+        return Err(timed_out(path, timeout));
+    }
+
+    let (width, height) = read_dimensions(path)?;
+    if width as u64 * height as u64 > max_pixels {
+        warn!("Skipping {}: {}x{} is over the {} megapixel AVIF limit", path.display(), width, height, max_pixels / 1_000_000);
+        return Err(ImageError::Limits(LimitError::from_kind(LimitErrorKind::DimensionError)));
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let owned_path = path.to_path_buf();
+    let started = Instant::now();
+    thread::spawn(move || {
+        let _ = tx.send(image::open(&owned_path));
+    });
+
+    match rx.recv_timeout(timeout) {
+        Ok(result) => {
+            info!("Decoded {}x{} AVIF {} in {}ms", width, height, path.display(), started.elapsed().as_millis());
+            result
+        }
+        Err(_) => {
+            warn!("Gave up decoding {} after {}s; it will be skipped until restart", path.display(), timeout.as_secs());
+            TIMED_OUT.lock().unwrap().get_or_insert_with(HashSet::new).insert(path.to_path_buf());
+            Err(timed_out(path, timeout))
+        }
+    }
+}
+
+fn timed_out(path: &Path, timeout: Duration) -> ImageError {
+    ImageError::IoError(std::io::Error::new(
+        std::io::ErrorKind::TimedOut,
+        format!("decoding {} took longer than {}s", path.display(), timeout.as_secs()),
+    ))
+}
+
+// Largest image spatial extent ("ispe") property in the header. Grid images carry one per
+// tile plus one for the whole picture, so the largest is the size that will be decoded.
+fn read_dimensions(path: &Path) -> ImageResult<(u32, u32)> {
+    let mut header = Vec::new();
+    File::open(path)?.take(HEADER_SCAN_BYTES).read_to_end(&mut header)?;
+
+    // Box type, then a 4-byte version and flags, then width and height as big-endian u32s
+    header
+        .windows(4)
+        .enumerate()
+        .filter(|(_, window)| *window == b"ispe")
+        .filter_map(|(start, _)| {
+            let fields = header.get(start + 8..start + 16)?;
+            let width = u32::from_be_bytes(fields[0..4].try_into().ok()?);
+            let height = u32::from_be_bytes(fields[4..8].try_into().ok()?);
+            Some((width, height))
+        })
+        .max_by_key(|&(width, height)| width as u64 * height as u64)
+        .ok_or_else(|| {
+            ImageError::IoError(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{} has no image size in its header", path.display()),
+            ))
+        })
+}
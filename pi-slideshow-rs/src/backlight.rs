@@ -0,0 +1,57 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Result as IoResult;
+use std::os::unix::fs::FileExt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::warn;
+
+// Panel backlights the kernel exposes under /sys/class/backlight (the official Pi
+// touchscreen, most DSI and SPI panels). HDMI TVs have none; they are dimmed in software.
+// The brightness file is opened once, when the backlight is found, so it can still be written
+// after the endpoint drops root (privileges.rs).
+
+const BACKLIGHT_CLASS: &str = "/sys/class/backlight";
+
+#[derive(Debug, Clone)]
+pub struct Backlight {
+    path: PathBuf,
+    max_brightness: u32,
+    brightness: Arc<File>,
+}
+
+impl Backlight {
+    // First backlight device, if any
+    pub fn find() -> Option<Self> {
+        let mut devices: Vec<PathBuf> = fs::read_dir(BACKLIGHT_CLASS)
+            .ok()?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect();
+        devices.sort();
+
+        devices.into_iter().find_map(|path| {
+            let max_brightness = fs::read_to_string(path.join("max_brightness")).ok()?.trim().parse().ok()?;
+            if max_brightness == 0 {
+                return None;
+            }
+            let brightness = match OpenOptions::new().write(true).open(path.join("brightness")) {
+                Ok(file) => file,
+                Err(e) => {
+                    warn!("Can't control backlight {}: {}", path.display(), e);
+                    return None;
+                }
+            };
+            Some(Self { path, max_brightness, brightness: Arc::new(brightness) })
+        })
+    }
+
+    pub fn name(&self) -> String {
+        self.path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+    }
+
+    pub fn set_percent(&self, percent: u8) -> IoResult<()> {
+        let percent = percent.min(100) as u32;
+        // Never round a non-zero level down to off
+        let value = ((self.max_brightness * percent + 50) / 100).max(if percent > 0 { 1 } else { 0 });
+        self.brightness.write_at(value.to_string().as_bytes(), 0).map(|_| ())
+    }
+}
@@ -0,0 +1,183 @@
+use std::fs::OpenOptions;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::{error, info, warn};
+
+// HDMI-CEC control of the attached TV, through cec-ctl (v4l-utils) on the kernel's CEC device
+// (/dev/cec0 on a Pi): power it on or to standby, and switch it to the Pi's input. Quiet hours
+// and the stop command put the TV in standby and waking turns it back on, so the panel itself
+// is off overnight rather than showing a blank input; cec commands do the same on demand. The
+// TV's power state, as it last answered, goes out with every heartbeat. Off unless
+// --cec-device is given.
+
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+// How the Pi is listed in the TV's source menu
+const OSD_NAME: &str = "Signage";
+
+static DEVICE: OnceLock<String> = OnceLock::new();
+// The Pi's HDMI physical address (e.g. "1.0.0.0"), announced when it takes over the input
+static PHYSICAL_ADDRESS: OnceLock<String> = OnceLock::new();
+// Index into POWER_STATES of the TV's last answer
+static POWER: AtomicU8 = AtomicU8::new(0);
+const POWER_STATES: [&str; 5] = ["unknown", "on", "standby", "turning_on", "turning_off"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    PowerOn,
+    Standby,
+    SelectInput,
+}
+
+impl Action {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "on" => Some(Action::PowerOn),
+            "standby" | "off" => Some(Action::Standby),
+            "input" => Some(Action::SelectInput),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::PowerOn => "on",
+            Action::Standby => "standby",
+            Action::SelectInput => "input",
+        }
+    }
+}
+
+// Set once at startup (--cec-device): registers the Pi as a playback device on the bus
+pub async fn configure(device: &str) {
+    if DEVICE.set(device.to_string()).is_err() {
+        warn!("HDMI-CEC already configured, ignoring");
+        return;
+    }
+    match cec_ctl(&["--playback", "--osd-name", OSD_NAME]).await {
+        Ok(output) => {
+            if let Some(address) = parse_physical_address(&output) {
+                info!("📺 HDMI-CEC on {} at physical address {}", device, address);
+                let _ = PHYSICAL_ADDRESS.set(address);
+            } else {
+                info!("📺 HDMI-CEC on {}, but the TV hasn't given the Pi an address yet", device);
+            }
+        }
+        Err(e) => error!("Failed to set up HDMI-CEC on {}: {}", device, e),
+    }
+}
+
+pub fn is_enabled() -> bool {
+    DEVICE.get().is_some()
+}
+
+// After dropping root (privileges.rs): cec-ctl runs as the endpoint's user from then on, so warn
+// now if that user can't open the device
+pub fn check_access() {
+    let Some(device) = DEVICE.get() else { return };
+    if let Err(e) = OpenOptions::new().read(true).write(true).open(device) {
+        warn!("HDMI-CEC commands will fail: can't open {} as this user ({}); is it in the video group?", device, e);
+    }
+}
+
+pub async fn run(action: Action) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if !is_enabled() {
+        return Err("HDMI-CEC is off (no --cec-device)".into());
+    }
+    match action {
+        Action::PowerOn => {
+            cec_ctl(&["--to", "0", "--image-view-on"]).await?;
+            select_input().await?;
+            set_power("on");
+        }
+        Action::Standby => {
+            cec_ctl(&["--to", "0", "--standby"]).await?;
+            set_power("standby");
+        }
+        Action::SelectInput => select_input().await?,
+    }
+    info!("📺 HDMI-CEC: TV {}", match action {
+        Action::PowerOn => "powered on",
+        Action::Standby => "put in standby",
+        Action::SelectInput => "switched to this input",
+    });
+    Ok(())
+}
+
+// Run `action` in the background when CEC is on, for the display loop, which can't wait on the bus
+pub fn spawn(action: Action) {
+    if !is_enabled() {
+        return;
+    }
+    tokio::spawn(async move {
+        if let Err(e) = run(action).await {
+            error!("HDMI-CEC {} failed: {}", action.name(), e);
+        }
+    });
+}
+
+// Ask the TV for its power state; a TV that doesn't answer is "unknown"
+pub async fn refresh_power_state() {
+    if !is_enabled() {
+        return;
+    }
+    let state = match cec_ctl(&["--to", "0", "--give-device-power-status"]).await {
+        Ok(output) => parse_power_state(&output).unwrap_or("unknown"),
+        Err(_) => "unknown",
+    };
+    set_power(state);
+}
+
+// The TV's power state for the heartbeat, or None when CEC is off
+pub fn power_state() -> Option<&'static str> {
+    is_enabled().then(|| POWER_STATES[POWER.load(Ordering::Relaxed) as usize % POWER_STATES.len()])
+}
+
+fn set_power(state: &str) {
+    let index = POWER_STATES.iter().position(|known| *known == state).unwrap_or(0);
+    POWER.store(index as u8, Ordering::Relaxed);
+}
+
+// Announce the Pi as the active source, which switches the TV over to its input
+async fn select_input() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let address = PHYSICAL_ADDRESS.get().ok_or("the Pi has no HDMI physical address (is the TV connected?)")?;
+    cec_ctl(&["--active-source", &format!("phys-addr={}", address)]).await?;
+    Ok(())
+}
+
+async fn cec_ctl(args: &[&str]) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let device = DEVICE.get().ok_or("HDMI-CEC is off")?;
+    let output = tokio::time::timeout(
+        COMMAND_TIMEOUT,
+        Command::new("cec-ctl").arg("-d").arg(device).args(args).kill_on_drop(true).output(),
+    )
+    .await
+    .map_err(|_| format!("cec-ctl took over {} seconds", COMMAND_TIMEOUT.as_secs()))?
+    .map_err(|e| format!("can't run cec-ctl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("cec-ctl failed: {}", String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// "Physical Address           : 1.0.0.0"; f.f.f.f means the Pi has no address yet
+fn parse_physical_address(output: &str) -> Option<String> {
+    output.lines()
+        .filter_map(|line| line.trim().strip_prefix("Physical Address"))
+        .filter_map(|rest| rest.split(':').nth(1))
+        .map(|address| address.trim().to_string())
+        .find(|address| address != "f.f.f.f")
+}
+
+// "pwr-state: on (0x00)", "standby (0x01)", "to-on (0x02)" or "to-standby (0x03)"
+fn parse_power_state(output: &str) -> Option<&'static str> {
+    let state = output.lines().find_map(|line| line.trim().strip_prefix("pwr-state:"))?.trim();
+    match state.split_whitespace().next()? {
+        "on" => Some("on"),
+        "standby" => Some("standby"),
+        "to-on" => Some("turning_on"),
+        "to-standby" => Some("turning_off"),
+        _ => None,
+    }
+}
@@ -0,0 +1,201 @@
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, Local, NaiveDateTime, Timelike, Utc};
+use signage_protocol::ClockStatus;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, AtomicU8, Ordering};
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+// Timestamps for published messages (and anything shown or scheduled by wall-clock time)
+// come from here, so an offset pushed by the management server can correct a wrong
+// system clock without changing system time.
+
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+static SERVER_OFFSET_MS: AtomicI64 = AtomicI64::new(0);
+static HAS_SERVER_OFFSET: AtomicBool = AtomicBool::new(false);
+// Anything beyond this is a bad message rather than a bad clock
+const MAX_SERVER_OFFSET_MS: i64 = 10 * 365 * 24 * 60 * 60 * 1000;
+
+// What chrony or timedatectl last said about NTP, refreshed with every heartbeat. A Pi has no
+// real-time clock: it boots at the time fake-hwclock last saved (or 1970) and only comes right
+// once NTP gets through, so the dayparting, validity windows, quiet hours, curtain hours and
+// dimming schedule all play at the wrong times until then. While the clock is clearly wrong
+// (before this binary was built, or further than MAX_SKEW_MS from NTP's time) those are
+// ignored and every assigned image plays in turn; a time_sync from the management server
+// corrects the clock, and lifts that straight away.
+const NTP_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_SKEW_MS: i64 = 5 * 60 * 1000;
+const UNKNOWN_SKEW: i64 = i64::MIN;
+// Indexes into NTP_SOURCES, 0 when neither could be asked
+static NTP_SOURCE: AtomicU8 = AtomicU8::new(0);
+const NTP_SOURCES: [&str; 3] = ["", "chrony", "timedatectl"];
+static NTP_SYNCHRONIZED: AtomicBool = AtomicBool::new(false);
+// System clock minus NTP's time, as chrony last measured it
+static NTP_SKEW_MS: AtomicI64 = AtomicI64::new(UNKNOWN_SKEW);
+// Whether schedules were trusted at the last refresh, to log when that changes
+static SCHEDULES_TRUSTED: AtomicBool = AtomicBool::new(true);
+
+// Monotonic per-process message counter; starts again at 1 when the endpoint restarts
+pub fn next_sequence() -> u64 {
+    SEQUENCE.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+pub fn now() -> DateTime<Utc> {
+    Utc::now() + ChronoDuration::milliseconds(SERVER_OFFSET_MS.load(Ordering::Relaxed))
+}
+
+// Minutes since local midnight, for daily schedules configured in local time
+pub fn local_minute_of_day() -> u32 {
+    let local = now().with_timezone(&Local);
+    local.hour() * 60 + local.minute()
+}
+
+// Local day of the week, Monday = 0
+pub fn local_weekday() -> u32 {
+    now().with_timezone(&Local).weekday().num_days_from_monday()
+}
+
+pub fn timestamp() -> String {
+    now().to_rfc3339()
+}
+
+pub fn server_offset_ms() -> Option<i64> {
+    if HAS_SERVER_OFFSET.load(Ordering::Relaxed) {
+        Some(SERVER_OFFSET_MS.load(Ordering::Relaxed))
+    } else {
+        None
+    }
+}
+
+// How far the timestamps we publish can be trusted:
+// "server" once the management server has pushed an offset, "ntp" when the kernel
+// reports a synchronized clock, otherwise "unsynchronized"
+pub fn confidence() -> &'static str {
+    if HAS_SERVER_OFFSET.load(Ordering::Relaxed) {
+        "server"
+    } else if system_clock_synchronized() {
+        "ntp"
+    } else {
+        "unsynchronized"
+    }
+}
+
+// Apply a time_sync command. The payload carries either the server's current time
+// ("server_time", RFC 3339) or a precomputed "offset_ms" to add to the local clock.
+pub fn apply_time_sync(payload: &serde_json::Value) -> Result<i64, String> {
+    let offset_ms = if let Some(server_time) = payload.get("server_time").and_then(|v| v.as_str()) {
+        let server_time = DateTime::parse_from_rfc3339(server_time)
+            .map_err(|e| format!("Invalid server_time {}: {}", server_time, e))?;
+        server_time.with_timezone(&Utc).signed_duration_since(Utc::now()).num_milliseconds()
+    } else if let Some(offset_ms) = payload.get("offset_ms").and_then(|v| v.as_i64()) {
+        offset_ms
+    } else {
+        return Err("time_sync needs server_time or offset_ms".to_string());
+    };
+
+    if offset_ms.unsigned_abs() > MAX_SERVER_OFFSET_MS as u64 {
+        return Err(format!("Refusing implausible clock offset of {}ms", offset_ms));
+    }
+
+    SERVER_OFFSET_MS.store(offset_ms, Ordering::Relaxed);
+    HAS_SERVER_OFFSET.store(true, Ordering::Relaxed);
+    Ok(offset_ms)
+}
+
+fn system_clock_synchronized() -> bool {
+    // adjtimex with modes = 0 only reads the kernel clock state
+    let mut timex: libc::timex = unsafe { std::mem::zeroed() };
+    let state = unsafe { libc::adjtimex(&mut timex) };
+    state != -1 && state != libc::TIME_ERROR && timex.status & libc::STA_UNSYNC == 0
+}
+
+fn build_time() -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(env!("BUILD_TIME"), "%Y-%m-%d %H:%M:%S UTC").ok().map(|time| time.and_utc())
+}
+
+// System clock minus the best reference known: the management server's time once it has sent
+// a time_sync, otherwise NTP's as chrony measured it
+pub fn skew_ms() -> Option<i64> {
+    server_offset_ms()
+        .map(|offset_ms| -offset_ms)
+        .or_else(|| Some(NTP_SKEW_MS.load(Ordering::Relaxed)).filter(|&skew| skew != UNKNOWN_SKEW))
+}
+
+// False while the clock is clearly wrong and wall-clock schedules should be left alone. Once
+// the management server has sent an offset, now() is right whatever the system clock says.
+pub fn schedules_trusted() -> bool {
+    if HAS_SERVER_OFFSET.load(Ordering::Relaxed) {
+        return true;
+    }
+    if build_time().is_some_and(|built| now() < built) {
+        return false;
+    }
+    skew_ms().is_none_or(|skew| skew.abs() <= MAX_SKEW_MS)
+}
+
+pub fn status() -> ClockStatus {
+    let source = NTP_SOURCE.load(Ordering::Relaxed) as usize;
+    ClockStatus {
+        ntp_synchronized: (source != 0).then(|| NTP_SYNCHRONIZED.load(Ordering::Relaxed)),
+        ntp_source: (source != 0).then(|| NTP_SOURCES[source].to_string()),
+        skew_ms: skew_ms(),
+        schedules_suspended: !schedules_trusted(),
+    }
+}
+
+// Ask chrony, or failing that timedatectl, how NTP is doing
+pub async fn refresh_ntp_status() {
+    if let Ok(output) = run("chronyc", &["tracking"]).await {
+        let (synchronized, skew_ms) = parse_chrony_tracking(&output);
+        NTP_SOURCE.store(1, Ordering::Relaxed);
+        NTP_SYNCHRONIZED.store(synchronized, Ordering::Relaxed);
+        NTP_SKEW_MS.store(skew_ms.unwrap_or(UNKNOWN_SKEW), Ordering::Relaxed);
+    } else if let Ok(output) = run("timedatectl", &["show", "--property=NTPSynchronized", "--value"]).await {
+        NTP_SOURCE.store(2, Ordering::Relaxed);
+        NTP_SYNCHRONIZED.store(output.trim() == "yes", Ordering::Relaxed);
+        NTP_SKEW_MS.store(UNKNOWN_SKEW, Ordering::Relaxed);
+    } else {
+        NTP_SOURCE.store(0, Ordering::Relaxed);
+    }
+
+    let trusted = schedules_trusted();
+    if SCHEDULES_TRUSTED.swap(trusted, Ordering::Relaxed) != trusted {
+        if trusted {
+            info!("🕰️ Clock looks right again, following schedules");
+        } else {
+            warn!("🕰️ Clock looks wrong ({}, skew {:?}ms), ignoring schedules and playing every image in turn", now().to_rfc3339(), skew_ms());
+        }
+    }
+}
+
+async fn run(program: &str, args: &[&str]) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let output = tokio::time::timeout(NTP_TIMEOUT, Command::new(program).args(args).kill_on_drop(true).output())
+        .await
+        .map_err(|_| format!("{} took over {} seconds", program, NTP_TIMEOUT.as_secs()))??;
+    if !output.status.success() {
+        return Err(format!("{} failed: {}", program, String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// "Leap status     : Normal" once synchronized ("Not synchronised" before), and
+// "System time     : 0.000012345 seconds slow of NTP time"
+fn parse_chrony_tracking(output: &str) -> (bool, Option<i64>) {
+    let field = |name: &str| {
+        output.lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(key, _)| key.trim() == name)
+            .map(|(_, value)| value.trim().to_string())
+    };
+    let synchronized = field("Leap status").is_some_and(|status| status != "Not synchronised");
+    let skew_ms = field("System time").and_then(|value| {
+        let mut words = value.split_whitespace();
+        let seconds: f64 = words.next()?.parse().ok()?;
+        let sign = match words.nth(1)? {
+            "fast" => 1.0,
+            "slow" => -1.0,
+            _ => return None,
+        };
+        Some((sign * seconds * 1000.0).round() as i64)
+    });
+    (synchronized, skew_ms)
+}
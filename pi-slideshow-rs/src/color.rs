@@ -0,0 +1,120 @@
+use std::ops::RangeInclusive;
+use tracing::info;
+
+// Output color correction applied while frames are converted for the framebuffer. All
+// adjustments are folded into one lookup table per channel, so each pixel costs three
+// table reads however many adjustments are active.
+
+pub const NEUTRAL_GAMMA: f64 = 1.0;
+// The white point source images are assumed to be mastered for (sRGB/D65)
+pub const NEUTRAL_COLOR_TEMPERATURE: u32 = 6500;
+pub const GAMMA_RANGE: RangeInclusive<f64> = 0.5..=3.0;
+pub const COLOR_TEMPERATURE_RANGE: RangeInclusive<u32> = 2000..=10000;
+// Percent of full output; 0 is left to screen blanking rather than a black frame
+pub const FULL_BRIGHTNESS: u8 = 100;
+pub const BRIGHTNESS_RANGE: RangeInclusive<u8> = 1..=100;
+
+pub struct ColorAdjustment {
+    gamma: f64,
+    color_temperature: u32,
+    brightness: u8,
+    // None while the settings are neutral, so unadjusted output skips the lookup entirely
+    lut: Option<[[u8; 256]; 3]>,
+}
+
+impl Default for ColorAdjustment {
+    fn default() -> Self {
+        Self {
+            gamma: NEUTRAL_GAMMA,
+            color_temperature: NEUTRAL_COLOR_TEMPERATURE,
+            brightness: FULL_BRIGHTNESS,
+            lut: None,
+        }
+    }
+}
+
+impl ColorAdjustment {
+    // Whether pixels pass through unchanged, so whole rows can be converted at once
+    pub fn is_neutral(&self) -> bool {
+        self.lut.is_none()
+    }
+
+    // Rebuild the tables if the settings changed; returns true when they did
+    pub fn set(&mut self, gamma: f64, color_temperature: u32, brightness: u8) -> bool {
+        let gamma = gamma.clamp(*GAMMA_RANGE.start(), *GAMMA_RANGE.end());
+        let color_temperature = color_temperature.clamp(*COLOR_TEMPERATURE_RANGE.start(), *COLOR_TEMPERATURE_RANGE.end());
+        let brightness = brightness.clamp(*BRIGHTNESS_RANGE.start(), *BRIGHTNESS_RANGE.end());
+        if gamma == self.gamma && color_temperature == self.color_temperature && brightness == self.brightness {
+            return false;
+        }
+
+        self.gamma = gamma;
+        self.color_temperature = color_temperature;
+        self.brightness = brightness;
+        self.lut = if gamma == NEUTRAL_GAMMA && color_temperature == NEUTRAL_COLOR_TEMPERATURE && brightness == FULL_BRIGHTNESS {
+            None
+        } else {
+            Some(build_lut(gamma, color_temperature, brightness))
+        };
+        info!("🎨 Color adjustment: gamma {:.2}, white point {}K, brightness {}%", gamma, color_temperature, brightness);
+        true
+    }
+
+    pub fn apply(&self, [r, g, b, a]: [u8; 4]) -> [u8; 4] {
+        match self.lut {
+            Some(ref lut) => [lut[0][r as usize], lut[1][g as usize], lut[2][b as usize], a],
+            None => [r, g, b, a],
+        }
+    }
+}
+
+// Gamma above 1.0 lifts midtones, below 1.0 deepens them; the white point gains then scale
+// each channel so white shifts toward the requested color temperature, and brightness
+// scales all three
+fn build_lut(gamma: f64, color_temperature: u32, brightness: u8) -> [[u8; 256]; 3] {
+    let scale = brightness as f64 / FULL_BRIGHTNESS as f64;
+    let gains = white_point_gains(color_temperature).map(|gain| gain * scale);
+    let mut lut = [[0u8; 256]; 3];
+    for (table, gain) in lut.iter_mut().zip(gains) {
+        for (value, entry) in table.iter_mut().enumerate() {
+            let corrected = (value as f64 / 255.0).powf(1.0 / gamma) * gain;
+            *entry = (corrected * 255.0).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    lut
+}
+
+// Per-channel gains that move white from 6500K to `kelvin`, with the strongest channel at 1.0
+fn white_point_gains(kelvin: u32) -> [f64; 3] {
+    let target = blackbody_rgb(kelvin);
+    let neutral = blackbody_rgb(NEUTRAL_COLOR_TEMPERATURE);
+    let ratios = [target[0] / neutral[0], target[1] / neutral[1], target[2] / neutral[2]];
+    let max = ratios.iter().cloned().fold(f64::MIN, f64::max);
+    [ratios[0] / max, ratios[1] / max, ratios[2] / max]
+}
+
+// Approximate sRGB color of a black body at `kelvin` (Tanner Helland's curve fit)
+fn blackbody_rgb(kelvin: u32) -> [f64; 3] {
+    let t = kelvin as f64 / 100.0;
+
+    let red = if t <= 66.0 {
+        255.0
+    } else {
+        329.698727446 * (t - 60.0).powf(-0.1332047592)
+    };
+    let green = if t <= 66.0 {
+        99.4708025861 * t.ln() - 161.1195681661
+    } else {
+        288.1221695283 * (t - 60.0).powf(-0.0755148492)
+    };
+    let blue = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.5177312231 * (t - 10.0).ln() - 305.0447927307
+    };
+
+    // Keep channels off zero so the ratios stay finite
+    [red.clamp(1.0, 255.0), green.clamp(1.0, 255.0), blue.clamp(1.0, 255.0)]
+}
@@ -0,0 +1,185 @@
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, Command};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Result as NotifyResult, Watcher};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::error;
+
+// Settings from a TOML file (--config) and SIGNAGE_* environment variables, layered under the
+// command line: a flag given on the command line wins, then the environment, then the file,
+// then the flag's built-in default. Keys are the long flag names with underscores
+// (`mqtt_broker`, `output`), and every flag can be set this way, so a new one needs no
+// changes here. The layers are applied by appending them to the command line as flags, which
+// keeps clap's parsing and validation for every source. The file is watched while running so
+// edits apply without a restart (see watch_config_file in main.rs).
+
+// Environment variables are the key in upper case after this prefix, e.g. SIGNAGE_MQTT_BROKER
+const ENV_PREFIX: &str = "SIGNAGE_";
+
+// Ids of the flags set on the command line, in the environment or in the file rather than left
+// at their defaults, as of the settings last accepted
+static EXPLICIT: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+// Command line with the environment and config file settings appended for every flag that
+// wasn't given on it
+pub fn layered_args(command: &Command) -> Result<Vec<OsString>, String> {
+    let argv: Vec<OsString> = std::env::args_os().collect();
+    let matches = command.clone().get_matches_from(&argv);
+    let on_command_line = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+    let mut layered = argv;
+    let path = match matches.get_one::<PathBuf>("config") {
+        Some(path) => Some(path.clone()),
+        // Passed on as --config so the file can be found again for reloading
+        None => std::env::var_os(format!("{}CONFIG", ENV_PREFIX)).map(|path| {
+            let mut flag = OsString::from("--config=");
+            flag.push(&path);
+            layered.push(flag);
+            PathBuf::from(path)
+        }),
+    };
+    let mut file = match &path {
+        Some(path) => read_file(path)?,
+        None => toml::Table::new(),
+    };
+
+    for arg in command.get_arguments() {
+        let Some(long) = arg.get_long() else { continue };
+        if long == "config" || matches!(arg.get_action(), ArgAction::Help | ArgAction::Version) {
+            continue;
+        }
+        let key = long.replace('-', "_");
+        let repeatable = matches!(arg.get_action(), ArgAction::Append);
+        let from_file = file.remove(&key);
+        if on_command_line(arg.get_id().as_str()) {
+            continue;
+        }
+
+        let env_name = format!("{}{}", ENV_PREFIX, key.to_uppercase());
+        let values = if let Ok(value) = std::env::var(&env_name) {
+            if repeatable {
+                value.split(',').map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).collect()
+            } else {
+                vec![value]
+            }
+        } else if let Some(value) = from_file {
+            file_values(&key, value, repeatable)?
+        } else {
+            continue;
+        };
+
+        append_flag(&mut layered, arg, &key, values)?;
+    }
+
+    // Whatever is left in the file matched no flag, most likely a typo
+    if let Some(key) = file.keys().next() {
+        return Err(format!("unknown setting `{}` in {}", key, path.unwrap_or_default().display()));
+    }
+    Ok(layered)
+}
+
+// Note which flags the layered command line `argv` sets, once its settings are accepted
+pub fn set_explicit(command: &Command, argv: &[OsString]) {
+    let Ok(matches) = command.clone().try_get_matches_from(argv) else { return };
+    *EXPLICIT.lock().unwrap() = command.get_arguments()
+        .map(|arg| arg.get_id().as_str())
+        .filter(|id| matches.value_source(id) == Some(ValueSource::CommandLine))
+        .map(str::to_string)
+        .collect();
+}
+
+// Whether the flag with id `id` was set locally; such settings win over the TV document's
+pub fn is_explicit(id: &str) -> bool {
+    EXPLICIT.lock().unwrap().iter().any(|explicit| explicit == id)
+}
+
+// Check settings before they are written to the config file, so a bad value can't stop the
+// next start: every key has to be a flag, and clap has to accept every value for it
+pub fn validate(command: &Command, settings: &toml::Table) -> Result<(), String> {
+    let mut settings: toml::Table = settings.iter().map(|(key, value)| (key.replace('-', "_"), value.clone())).collect();
+    let mut argv = vec![OsString::from(command.get_name())];
+    for arg in command.get_arguments() {
+        let Some(long) = arg.get_long() else { continue };
+        if long == "config" || matches!(arg.get_action(), ArgAction::Help | ArgAction::Version) {
+            continue;
+        }
+        let key = long.replace('-', "_");
+        let Some(value) = settings.remove(&key) else { continue };
+        let values = file_values(&key, value, matches!(arg.get_action(), ArgAction::Append))?;
+        append_flag(&mut argv, arg, &key, values)?;
+    }
+    if let Some(key) = settings.keys().next() {
+        return Err(format!("unknown setting `{}`", key));
+    }
+    // clap's message, without its "error: " prefix and the pointer to --help
+    command.clone().try_get_matches_from(argv).map(|_| ()).map_err(|e| {
+        e.to_string().lines().next().unwrap_or_default().trim_start_matches("error: ").to_string()
+    })
+}
+
+fn append_flag(argv: &mut Vec<OsString>, arg: &Arg, key: &str, values: Vec<String>) -> Result<(), String> {
+    let long = arg.get_long().unwrap_or(key);
+    match arg.get_action() {
+        ArgAction::SetTrue => match values.as_slice() {
+            [value] if value == "true" => argv.push(format!("--{}", long).into()),
+            [value] if value == "false" => {}
+            _ => return Err(format!("{} must be true or false", key)),
+        },
+        _ => argv.extend(values.into_iter().map(|value| format!("--{}={}", long, value).into())),
+    }
+    Ok(())
+}
+
+// Call `on_change` whenever the file at `path` is written, created or replaced. The directory
+// is watched rather than the file, since editors usually save by renaming a new file over it.
+pub fn watch(path: &Path, on_change: impl Fn() + Send + 'static) -> NotifyResult<RecommendedWatcher> {
+    let file_name = path.file_name().map(|name| name.to_os_string());
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let mut watcher = notify::recommended_watcher(move |res: NotifyResult<Event>| {
+        match res {
+            Ok(event) => {
+                if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                    && event.paths.iter().any(|p| p.file_name().map(|n| n.to_os_string()) == file_name)
+                {
+                    on_change();
+                }
+            }
+            Err(e) => error!("Config file watch error: {:?}", e),
+        }
+    })?;
+    watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+    Ok(watcher)
+}
+
+fn read_file(path: &Path) -> Result<toml::Table, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("can't read config file {}: {}", path.display(), e))?;
+    let table: toml::Table = toml::from_str(&text)
+        .map_err(|e| format!("invalid config file {}: {}", path.display(), e))?;
+    // Accept kebab-case keys too, as they appear on the command line
+    Ok(table.into_iter().map(|(key, value)| (key.replace('-', "_"), value)).collect())
+}
+
+// A file value as flag arguments: one per array element for repeatable flags
+fn file_values(key: &str, value: toml::Value, repeatable: bool) -> Result<Vec<String>, String> {
+    match value {
+        toml::Value::Array(items) if repeatable => items.into_iter()
+            .map(|item| scalar(key, item))
+            .collect(),
+        value => Ok(vec![scalar(key, value)?]),
+    }
+}
+
+fn scalar(key: &str, value: toml::Value) -> Result<String, String> {
+    match value {
+        toml::Value::String(s) => Ok(s),
+        toml::Value::Integer(i) => Ok(i.to_string()),
+        toml::Value::Float(f) => Ok(f.to_string()),
+        toml::Value::Boolean(b) => Ok(b.to_string()),
+        other => Err(format!("{} must be a string, number or boolean, not {}", key, other.type_str())),
+    }
+}
@@ -0,0 +1,153 @@
+use image::codecs::gif::GifDecoder;
+use image::{AnimationDecoder, ImageError, Rgba, RgbaImage};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::time::Duration;
+
+// WCAG 2.3.1 "Three Flashes or Below Threshold"
+pub const MAX_FLASHES_PER_SECOND: usize = 3;
+// A flash is a pair of opposing luminance changes of at least 10% of max luminance
+const FLASH_LUMINANCE_DELTA: f32 = 0.10;
+// Changes only count when the darker of the two states is below this luminance
+const FLASH_DARK_LUMINANCE: f32 = 0.80;
+// Shortest transition allowed while content safety is enabled
+pub const MIN_SAFE_TRANSITION: Duration = Duration::from_millis(500);
+// Only sample every Nth pixel in each direction when measuring frame luminance
+const LUMINANCE_SAMPLE_STEP: u32 = 4;
+
+#[derive(Debug, Clone)]
+pub struct FlashReport {
+    pub frame_count: usize,
+    pub max_flashes_per_second: usize,
+}
+
+impl FlashReport {
+    pub fn is_safe(&self) -> bool {
+        self.max_flashes_per_second <= MAX_FLASHES_PER_SECOND
+    }
+}
+
+// Clamp transition timing so a full-screen change can never happen faster than the flash threshold allows
+pub fn safe_transition_duration(duration: Duration) -> Duration {
+    duration.max(MIN_SAFE_TRANSITION)
+}
+
+// Check a downloaded image against the flash threshold. Still images always pass;
+// animated GIFs are decoded frame by frame and rejected if they flash too often.
+pub fn validate_image_file(path: &Path) -> Result<(), String> {
+    let is_gif = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase() == "gif")
+        .unwrap_or(false);
+
+    if !is_gif {
+        return Ok(());
+    }
+
+    let report = analyze_gif(path)
+        .map_err(|e| format!("Failed to analyze animation {}: {}", path.display(), e))?;
+
+    if report.is_safe() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} flashes {} times per second across {} frames (limit {})",
+            path.display(),
+            report.max_flashes_per_second,
+            report.frame_count,
+            MAX_FLASHES_PER_SECOND
+        ))
+    }
+}
+
+pub fn analyze_gif(path: &Path) -> Result<FlashReport, ImageError> {
+    let file = File::open(path).map_err(ImageError::IoError)?;
+    let decoder = GifDecoder::new(BufReader::new(file))?;
+
+    let mut timeline = Vec::new();
+    let mut elapsed_ms = 0u64;
+
+    for frame in decoder.into_frames() {
+        let frame = frame?;
+        let (numer, denom) = frame.delay().numer_denom_ms();
+        let delay_ms = numer.checked_div(denom).unwrap_or(0) as u64;
+
+        timeline.push((elapsed_ms, mean_relative_luminance(frame.buffer())));
+        elapsed_ms += delay_ms;
+    }
+
+    Ok(FlashReport {
+        frame_count: timeline.len(),
+        max_flashes_per_second: max_flashes_per_second(&timeline),
+    })
+}
+
+// Count flashes in the busiest one-second window of a (timestamp_ms, luminance) timeline
+pub fn max_flashes_per_second(timeline: &[(u64, f32)]) -> usize {
+    let mut changes: Vec<u64> = Vec::new();
+    let mut last_direction = 0i8;
+    let mut reference = match timeline.first() {
+        Some(&(_, luminance)) => luminance,
+        None => return 0,
+    };
+
+    for &(timestamp, luminance) in &timeline[1..] {
+        let delta = luminance - reference;
+        let darker = luminance.min(reference);
+
+        if delta.abs() >= FLASH_LUMINANCE_DELTA && darker < FLASH_DARK_LUMINANCE {
+            let direction = if delta > 0.0 { 1 } else { -1 };
+            // Only opposing changes make up a flash; a steady ramp is not flashing
+            if direction != last_direction {
+                changes.push(timestamp);
+                last_direction = direction;
+            }
+            reference = luminance;
+        }
+    }
+
+    let mut max_changes = 0;
+    let mut window_start = 0;
+    for (i, &timestamp) in changes.iter().enumerate() {
+        while timestamp - changes[window_start] >= 1000 {
+            window_start += 1;
+        }
+        max_changes = max_changes.max(i - window_start + 1);
+    }
+
+    max_changes / 2
+}
+
+// Average WCAG relative luminance of a frame (0.0 = black, 1.0 = white)
+pub fn mean_relative_luminance(frame: &RgbaImage) -> f32 {
+    let mut total = 0.0;
+    let mut samples = 0u32;
+
+    for y in (0..frame.height()).step_by(LUMINANCE_SAMPLE_STEP as usize) {
+        for x in (0..frame.width()).step_by(LUMINANCE_SAMPLE_STEP as usize) {
+            total += relative_luminance(frame.get_pixel(x, y));
+            samples += 1;
+        }
+    }
+
+    if samples == 0 {
+        0.0
+    } else {
+        total / samples as f32
+    }
+}
+
+// WCAG relative luminance of a single sRGB pixel
+pub fn relative_luminance(pixel: &Rgba<u8>) -> f32 {
+    0.2126 * linearize(pixel[0]) + 0.7152 * linearize(pixel[1]) + 0.0722 * linearize(pixel[2])
+}
+
+fn linearize(channel: u8) -> f32 {
+    let c = channel as f32 / 255.0;
+    if c <= 0.03928 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
@@ -1,100 +1,12 @@
-use couch_rs::{Client, database::Database, document::TypedCouchDocument};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::borrow::Cow;
-use crate::mqtt_client::ImageInfo;
+use couch_rs::{Client, database::Database};
+use signage_protocol::{CouchImage, CouchSchedule, CouchTextSlide, CouchTv, ImageErrorReport, ImageInfo, Release, TvConfig};
+use tracing::{error, info, instrument, warn};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CouchImage {
-    #[serde(rename = "_id")]
-    pub id: String,
-    #[serde(rename = "_rev", skip_serializing_if = "Option::is_none")]
-    pub rev: Option<String>,
-    #[serde(rename = "type")]
-    pub doc_type: String,
-    pub original_name: String,
-    pub size: u64,
-    pub metadata: ImageMetadata,
-    pub assigned_tvs: Vec<String>,
-    #[serde(alias = "upload_date")]
-    pub created_at: String,
-    #[serde(rename = "_attachments", skip_serializing_if = "Option::is_none")]
-    pub attachments: Option<HashMap<String, Attachment>>,
-}
-
-
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ImageMetadata {
-    pub width: u32,
-    pub height: u32,
-    #[serde(default = "default_format")]
-    pub format: String,
-}
-
-fn default_format() -> String {
-    "png".to_string()
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Attachment {
-    pub content_type: String,
-    pub length: u64,
-    pub digest: Option<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CouchTv {
-    #[serde(rename = "_id")]
-    pub id: String,
-    #[serde(rename = "_rev", skip_serializing_if = "Option::is_none")]
-    pub rev: Option<String>,
-    #[serde(rename = "type")]
-    pub doc_type: String,
-    pub name: String,
-    pub location: String,
-    pub ip_address: String,
-    pub status: String,
-    pub last_heartbeat: Option<String>,
-    pub config: TvConfig,
-    pub current_image: Option<String>,
-}
-
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TvConfig {
-    pub transition_effect: String,
-    pub display_duration: u64,
-    #[serde(default = "default_orientation")]
-    pub orientation: String,
-}
-
-fn default_orientation() -> String {
-    "landscape".to_string()
-}
-
-impl TypedCouchDocument for CouchTv {
-    fn get_id(&self) -> Cow<str> {
-        Cow::Borrowed(&self.id)
-    }
-
-    fn get_rev(&self) -> Cow<str> {
-        Cow::Borrowed(self.rev.as_deref().unwrap_or(""))
-    }
+use crate::clock;
+use crate::downloads;
 
-    fn set_id(&mut self, id: &str) {
-        self.id = id.to_string();
-    }
-
-    fn set_rev(&mut self, rev: &str) {
-        self.rev = Some(rev.to_string());
-    }
-
-    fn merge_ids(&mut self, other: &Self) {
-        self.id = other.id.clone();
-        self.rev = other.rev.clone();
-    }
-}
+// Image error reports kept on the TV document
+const MAX_IMAGE_ERRORS: usize = 20;
 
 pub struct CouchDbClient {
     db: Database,
@@ -118,8 +30,9 @@ impl CouchDbClient {
         })
     }
 
-    pub async fn get_images_for_tv(&self, tv_id: &str) -> Result<Vec<ImageInfo>, Box<dyn std::error::Error + Send + Sync>> {
-        println!("Fetching images for TV: {}", tv_id);
+    // Images and text slides assigned to the TV, directly or through one of its tags
+    pub async fn get_images_for_tv(&self, tv_id: &str, tags: &[String]) -> Result<Vec<ImageInfo>, Box<dyn std::error::Error + Send + Sync>> {
+        info!("Fetching images for TV: {}", tv_id);
         
         // Get all documents and filter for images assigned to this TV with timeout
         let all_docs = tokio::time::timeout(
@@ -132,57 +45,41 @@ impl CouchDbClient {
         let mut images_for_tv = Vec::new();
         
         for doc in all_docs.rows {
+            // Text slides play among the images, drawn by the TV into a PNG of their own
+            if doc["type"] == "text_slide" {
+                match serde_json::from_value::<CouchTextSlide>(doc) {
+                    Ok(text_slide) if text_slide.is_assigned_to(tv_id, tags) => {
+                        images_for_tv.push(ImageInfo {
+                            path: format!("{}.png", text_slide.id),
+                            id: text_slide.id,
+                            order: images_for_tv.len() as u32,
+                            url: None,
+                            extension: Some(".png".to_string()),
+                            letterbox_fill: None,
+                            fit_mode: None,
+                            qr_url: None,
+                            qr_corner: None,
+                            weight: None,
+                            valid_from: text_slide.valid_from,
+                            valid_until: text_slide.valid_until,
+                            web_url: None,
+                            web_refresh_secs: None,
+                            text_slide: Some(text_slide.slide),
+                            revision: None,
+                        });
+                    }
+                    Ok(_) => {}
+                    Err(e) => warn!("Skipping unreadable text slide document: {}", e),
+                }
+                continue;
+            }
+
             // Parse as CouchImage directly
             if let Ok(image_doc) = serde_json::from_value::<CouchImage>(doc) {
-                // Check if this is an image document and if this TV is in the assigned_tvs list
-                if image_doc.doc_type == "image" && image_doc.assigned_tvs.contains(&tv_id.to_string()) {
+                // Check if this is an image document assigned to this TV or one of its tags
+                if image_doc.doc_type == "image" && image_doc.is_assigned_to(tv_id, tags) {
                     // Determine file extension from attachment content_type, fallback to metadata format, then original name
-                    let extension = if let Some(attachments) = &image_doc.attachments {
-                        if let Some((_name, attachment)) = attachments.iter().next() {
-                            // Use content_type to determine extension
-                            match attachment.content_type.as_str() {
-                                "image/jpeg" => ".jpg".to_string(),
-                                "image/jpg" => ".jpg".to_string(),
-                                "image/png" => ".png".to_string(),
-                                "image/gif" => ".gif".to_string(),
-                                "image/webp" => ".webp".to_string(),
-                                _ => {
-                                    // Fallback to metadata format if content_type is unknown
-                                    if !image_doc.metadata.format.is_empty() {
-                                        format!(".{}", image_doc.metadata.format.to_lowercase())
-                                    } else {
-                                        std::path::Path::new(&image_doc.original_name)
-                                            .extension()
-                                            .and_then(|ext| ext.to_str())
-                                            .map(|ext| format!(".{}", ext))
-                                            .unwrap_or_else(|| ".png".to_string())
-                                    }
-                                }
-                            }
-                        } else {
-                            // No attachments, fallback to metadata
-                            if !image_doc.metadata.format.is_empty() {
-                                format!(".{}", image_doc.metadata.format.to_lowercase())
-                            } else {
-                                std::path::Path::new(&image_doc.original_name)
-                                    .extension()
-                                    .and_then(|ext| ext.to_str())
-                                    .map(|ext| format!(".{}", ext))
-                                    .unwrap_or_else(|| ".png".to_string())
-                            }
-                        }
-                    } else {
-                        // No attachments, fallback to metadata format, then original name
-                        if !image_doc.metadata.format.is_empty() {
-                            format!(".{}", image_doc.metadata.format.to_lowercase())
-                        } else {
-                            std::path::Path::new(&image_doc.original_name)
-                                .extension()
-                                .and_then(|ext| ext.to_str())
-                                .map(|ext| format!(".{}", ext))
-                                .unwrap_or_else(|| ".png".to_string())
-                        }
-                    };
+                    let extension = image_doc.file_extension();
                     
                     let image_info = ImageInfo {
                         id: image_doc.id.clone(),
@@ -190,6 +87,17 @@ impl CouchDbClient {
                         order: images_for_tv.len() as u32, // Use index as order for now
                         url: None, // Not needed for CouchDB attachments
                         extension: Some(extension),
+                        letterbox_fill: image_doc.metadata.letterbox_fill.clone(),
+                        fit_mode: image_doc.metadata.fit_mode.clone(),
+                        qr_url: image_doc.metadata.qr_url.clone(),
+                        qr_corner: image_doc.metadata.qr_corner.clone(),
+                        weight: image_doc.metadata.weight,
+                        valid_from: image_doc.valid_from.clone(),
+                        valid_until: image_doc.valid_until.clone(),
+                        web_url: image_doc.web_url.clone(),
+                        web_refresh_secs: image_doc.web_refresh_secs,
+                        text_slide: None,
+                        revision: image_doc.content_revision(),
                     };
                     
                     images_for_tv.push(image_info);
@@ -200,12 +108,81 @@ impl CouchDbClient {
         // Sort by order (which is currently just the index)
         images_for_tv.sort_by(|a, b| a.order.cmp(&b.order));
         
-        println!("Found {} images for TV {}", images_for_tv.len(), tv_id);
+        info!("Found {} images for TV {}", images_for_tv.len(), tv_id);
         Ok(images_for_tv)
     }
 
+    // Daypart schedules for a TV, ordered by document id
+    pub async fn get_schedules_for_tv(&self, tv_id: &str) -> Result<Vec<CouchSchedule>, Box<dyn std::error::Error + Send + Sync>> {
+        let all_docs = tokio::time::timeout(
+            std::time::Duration::from_secs(30),
+            self.db.get_all::<serde_json::Value>()
+        ).await
+            .map_err(|_| "CouchDB get_all query timeout after 30 seconds")?
+            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
+
+        let schedules: Vec<CouchSchedule> = all_docs.rows.into_iter()
+            .filter(|doc| doc["type"] == "schedule")
+            .filter_map(|doc| match serde_json::from_value::<CouchSchedule>(doc) {
+                Ok(schedule) => Some(schedule),
+                Err(e) => {
+                    warn!("Skipping unreadable schedule document: {}", e);
+                    None
+                }
+            })
+            .filter(|schedule| schedule.assigned_tv_id == tv_id)
+            .collect();
+
+        info!("Found {} schedules for TV {}", schedules.len(), tv_id);
+        Ok(schedules)
+    }
+
+    pub async fn get_image(&self, image_id: &str) -> Result<CouchImage, Box<dyn std::error::Error + Send + Sync>> {
+        let doc_value: serde_json::Value = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            self.db.get(image_id)
+        ).await
+            .map_err(|_| format!("Timeout getting image document {} after 10 seconds", image_id))?
+            .map_err(|e| format!("Failed to get image document {}: {}", image_id, e))?;
+
+        let image_doc: CouchImage = serde_json::from_value(doc_value)
+            .map_err(|e| format!("Failed to parse image document {}: {}", image_id, e))?;
+        Ok(image_doc)
+    }
+
+    // The latest release on an update channel, from its "release_{channel}" document
+    pub async fn get_release(&self, channel: &str) -> Result<Release, Box<dyn std::error::Error + Send + Sync>> {
+        let doc_id = format!("release_{}", channel);
+        let doc_value: serde_json::Value = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            self.db.get(&doc_id)
+        ).await
+            .map_err(|_| format!("Timeout getting release document {} after 10 seconds", doc_id))?
+            .map_err(|e| format!("Failed to get release document {}: {}", doc_id, e))?;
+
+        let release: Release = serde_json::from_value(doc_value)
+            .map_err(|e| format!("Failed to parse release document {}: {}", doc_id, e))?;
+        Ok(release)
+    }
+
+    #[instrument(name = "download", skip(self, local_path))]
     pub async fn download_image_attachment(&self, image_id: &str, local_path: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        println!("Downloading image attachment {} to {}", image_id, local_path);
+        let started = std::time::Instant::now();
+        match self.fetch_attachment(image_id, local_path).await {
+            Ok(bytes) => {
+                downloads::record_success(image_id, bytes, started.elapsed());
+                Ok(())
+            }
+            Err(e) => {
+                downloads::record_failure(image_id);
+                Err(e)
+            }
+        }
+    }
+
+    // Returns the size of the file written
+    async fn fetch_attachment(&self, image_id: &str, local_path: &str) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        info!("Downloading image attachment {} to {}", image_id, local_path);
         
         // First get the image document to find attachment info with timeout
         let doc_value: serde_json::Value = tokio::time::timeout(
@@ -221,7 +198,7 @@ impl CouchDbClient {
         // Find the first attachment (usually the image file)
         if let Some(attachments) = &image_doc.attachments {
             if let Some((attachment_name, _attachment_info)) = attachments.iter().next() {
-                println!("Found attachment: {}", attachment_name);
+                info!("Found attachment: {}", attachment_name);
                 
                 // Construct the attachment URL manually since couch_rs doesn't have direct attachment download
                 let db_url = format!("{}/digital_signage/{}/{}", 
@@ -229,7 +206,7 @@ impl CouchDbClient {
                     image_id, 
                     attachment_name);
                 
-                println!("Downloading attachment from URL: {}", db_url);
+                info!("Downloading attachment from URL: {}", db_url);
                 
                 // Use reqwest to download the attachment
                 let client = reqwest::Client::new();
@@ -242,13 +219,14 @@ impl CouchDbClient {
                 
                 let bytes = response.bytes().await
                     .map_err(|e| format!("Failed to read attachment bytes: {}", e))?;
+                let size = bytes.len() as u64;
                 
                 // Write to local file with the correct extension
                 std::fs::write(local_path, bytes)
                     .map_err(|e| format!("Failed to write attachment to {}: {}", local_path, e))?;
                 
-                println!("Successfully downloaded attachment {} to {}", attachment_name, local_path);
-                Ok(())
+                info!("Successfully downloaded attachment {} to {}", attachment_name, local_path);
+                Ok(size)
             } else {
                 Err(format!("No attachments found for image {}", image_id).into())
             }
@@ -258,7 +236,7 @@ impl CouchDbClient {
     }
 
     pub async fn update_tv_status(&self, tv_id: &str, status: &str, current_image: Option<&str>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        println!("Updating TV {} status to {} in CouchDB", tv_id, status);
+        info!("Updating TV {} status to {} in CouchDB", tv_id, status);
         
         // Try to get existing TV document with timeout
         let tv_doc_result = tokio::time::timeout(
@@ -274,7 +252,7 @@ impl CouchDbClient {
             }
             Ok(Err(_)) | Err(_) => {
                 // Create new TV document if it doesn't exist
-                println!("TV document {} not found, creating new one", tv_id);
+                info!("TV document {} not found, creating new one", tv_id);
                 CouchTv {
                     id: tv_id.to_string(),
                     rev: None,
@@ -283,20 +261,17 @@ impl CouchDbClient {
                     location: "Unknown".to_string(),
                     ip_address: "0.0.0.0".to_string(), // Will be updated later
                     status: status.to_string(),
-                    last_heartbeat: Some(chrono::Utc::now().to_rfc3339()),
-                    config: TvConfig {
-                        transition_effect: "fade".to_string(),
-                        display_duration: 5000,
-                        orientation: "landscape".to_string(),
-                    },
+                    last_heartbeat: Some(clock::timestamp()),
+                    config: TvConfig::default(),
                     current_image: current_image.map(|s| s.to_string()),
+                    image_errors: Vec::new(),
                 }
             }
         };
         
         // Update the status and current image
         tv_doc.status = status.to_string();
-        tv_doc.last_heartbeat = Some(chrono::Utc::now().to_rfc3339());
+        tv_doc.last_heartbeat = Some(clock::timestamp());
         if let Some(image) = current_image {
             tv_doc.current_image = Some(image.to_string());
         }
@@ -309,12 +284,42 @@ impl CouchDbClient {
             .map_err(|_| format!("Timeout saving TV document {} after 10 seconds", tv_id))?
             .map_err(|e| format!("Failed to save TV document {}: {}", tv_id, e))?;
         
-        println!("Successfully updated TV {} status to {}", tv_id, status);
+        info!("Successfully updated TV {} status to {}", tv_id, status);
+        Ok(())
+    }
+
+    // Record on the TV document that an image couldn't be decoded, replacing any earlier
+    // report for the same image. Only the latest MAX_IMAGE_ERRORS reports are kept.
+    pub async fn report_image_error(&self, tv_id: &str, image_id: &str, error: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let doc = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            self.db.get::<serde_json::Value>(tv_id)
+        ).await
+            .map_err(|_| format!("Timeout getting TV document {} after 10 seconds", tv_id))?
+            .map_err(|e| format!("Failed to get TV document {}: {}", tv_id, e))?;
+        let mut tv_doc = serde_json::from_value::<CouchTv>(doc)
+            .map_err(|e| format!("Failed to parse TV document {}: {}", tv_id, e))?;
+
+        tv_doc.image_errors.retain(|report| report.image_id != image_id);
+        tv_doc.image_errors.push(ImageErrorReport {
+            image_id: image_id.to_string(),
+            error: error.to_string(),
+            timestamp: clock::timestamp(),
+        });
+        let excess = tv_doc.image_errors.len().saturating_sub(MAX_IMAGE_ERRORS);
+        tv_doc.image_errors.drain(..excess);
+
+        tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            self.db.save(&mut tv_doc)
+        ).await
+            .map_err(|_| format!("Timeout saving TV document {} after 10 seconds", tv_id))?
+            .map_err(|e| format!("Failed to save TV document {}: {}", tv_id, e))?;
         Ok(())
     }
 
     pub async fn get_tv_config(&self, tv_id: &str) -> Result<Option<TvConfig>, Box<dyn std::error::Error + Send + Sync>> {
-        println!("Getting TV config for {} from CouchDB", tv_id);
+        info!("Getting TV config for {} from CouchDB", tv_id);
         
         // Try to get TV document from CouchDB with timeout
         match tokio::time::timeout(
@@ -325,38 +330,26 @@ impl CouchDbClient {
                 // Parse the TV document
                 match serde_json::from_value::<CouchTv>(doc_value) {
                     Ok(tv_doc) => {
-                        println!("Retrieved config for TV {}: transition_effect={}, display_duration={}", 
+                        info!("Retrieved config for TV {}: transition_effect={}, display_duration={}", 
                                 tv_id, tv_doc.config.transition_effect, tv_doc.config.display_duration);
                         Ok(Some(tv_doc.config))
                     }
                     Err(e) => {
-                        eprintln!("Failed to parse TV document {}: {}", tv_id, e);
+                        error!("Failed to parse TV document {}: {}", tv_id, e);
                         // Return default config if parsing fails
-                        Ok(Some(TvConfig {
-                            transition_effect: "fade".to_string(),
-                            display_duration: 5000,
-                            orientation: "landscape".to_string(),
-                        }))
+                        Ok(Some(TvConfig::default()))
                     }
                 }
             }
             Ok(Err(e)) => {
-                println!("TV document {} not found in CouchDB: {}, using default config", tv_id, e);
+                info!("TV document {} not found in CouchDB: {}, using default config", tv_id, e);
                 // Return default config if document doesn't exist
-                Ok(Some(TvConfig {
-                    transition_effect: "fade".to_string(),
-                    display_duration: 5000,
-                    orientation: "landscape".to_string(),
-                }))
+                Ok(Some(TvConfig::default()))
             }
             Err(_) => {
-                println!("TV document {} query timeout, using default config", tv_id);
+                info!("TV document {} query timeout, using default config", tv_id);
                 // Return default config on timeout
-                Ok(Some(TvConfig {
-                    transition_effect: "fade".to_string(),
-                    display_duration: 5000,
-                    orientation: "landscape".to_string(),
-                }))
+                Ok(Some(TvConfig::default()))
             }
         }
     }
@@ -0,0 +1,171 @@
+use signage_protocol::CrashReport;
+use std::backtrace::Backtrace;
+use std::fmt::Display;
+use std::fs;
+use std::future::Future;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+use tracing::{error, info, warn};
+
+use crate::clock;
+use crate::mqtt_client::MqttClient;
+use crate::updater;
+
+// Crashes that would otherwise only show up as a restart in the journal. A panic, a fatal error
+// out of the main loop, or a panic in one of the background tasks (which tokio would otherwise
+// swallow, leaving the endpoint half working) writes a report to crashes/ under --log-dir: the
+// message and where it happened, a backtrace, the version, the uptime and the last command
+// handled. A panicked background task then ends the process for the service manager to start
+// it again. Once MQTT is connected after the next start, each saved report is published on
+// signage/tv/{id}/crash and removed.
+
+const DIR_NAME: &str = "crashes";
+// A crash loop with no broker to take the reports mustn't fill the card
+const MAX_REPORTS: usize = 20;
+
+static DIR: OnceLock<PathBuf> = OnceLock::new();
+static STARTED: OnceLock<Instant> = OnceLock::new();
+static LAST_COMMAND: Mutex<Option<(&'static str, Instant)>> = Mutex::new(None);
+
+tokio::task_local! {
+    static TASK: &'static str;
+}
+
+// Install the panic hook, after logging's so that a panic is logged first. An empty `log_dir`
+// keeps no reports.
+pub fn install(log_dir: &Path) {
+    let _ = STARTED.set(Instant::now());
+    if !log_dir.as_os_str().is_empty() {
+        let _ = DIR.set(log_dir.join(DIR_NAME));
+    }
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        previous(info);
+        save(&panic_report(info));
+    }));
+}
+
+// Seconds since the process started
+pub fn uptime() -> u64 {
+    STARTED.get().map_or(0, |started| started.elapsed().as_secs())
+}
+
+// Noted for the report of a crash that follows
+pub fn record_command(name: &'static str) {
+    if let Ok(mut last) = LAST_COMMAND.lock() {
+        *last = Some((name, Instant::now()));
+    }
+}
+
+// A fatal error the endpoint is about to exit with
+pub fn record_error(error: &dyn Display) {
+    save(&report("error", error.to_string(), None, String::new()));
+}
+
+// Run a background task that is meant to last as long as the endpoint. If it panics the
+// endpoint exits, rather than carry on without it.
+pub fn spawn<F>(name: &'static str, task: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let handle = tokio::spawn(TASK.scope(name, task));
+    tokio::spawn(async move {
+        if let Err(e) = handle.await {
+            if e.is_panic() {
+                error!("💥 The {} task panicked, exiting for the service manager to restart", name);
+                std::process::exit(1);
+            }
+        }
+    });
+}
+
+// Publish the reports saved by earlier runs, oldest first. One that fails to go out is kept
+// for the next start.
+pub fn spawn_publisher(client: MqttClient) {
+    let Some(dir) = DIR.get() else { return };
+    let reports = saved_reports(dir);
+    if reports.is_empty() {
+        return;
+    }
+    tokio::spawn(async move {
+        for path in reports {
+            let report: CrashReport = match fs::read_to_string(&path).map_err(|e| e.to_string())
+                .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()))
+            {
+                Ok(report) => report,
+                Err(e) => {
+                    warn!("Dropping unreadable crash report {}: {}", path.display(), e);
+                    let _ = fs::remove_file(&path);
+                    continue;
+                }
+            };
+            if let Err(e) = client.publish_crash(&report).await {
+                warn!("Failed to publish crash report {}, keeping it for the next start: {}", path.display(), e);
+                return;
+            }
+            info!("💥 Published the report of a {} at {}: {}", report.kind, report.crashed_at, report.message);
+            if let Err(e) = fs::remove_file(&path) {
+                warn!("Failed to remove published crash report {}: {}", path.display(), e);
+            }
+        }
+    });
+}
+
+fn panic_report(info: &PanicHookInfo<'_>) -> CrashReport {
+    let message = info.payload().downcast_ref::<&str>().map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "panic without a message".to_string());
+    let location = info.location().map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()));
+    report("panic", message, location, Backtrace::force_capture().to_string())
+}
+
+fn report(kind: &str, message: String, location: Option<String>, backtrace: String) -> CrashReport {
+    // try_lock: the panic may have happened while it was held
+    let last = LAST_COMMAND.try_lock().ok().and_then(|last| *last);
+    CrashReport {
+        kind: kind.to_string(),
+        message,
+        location,
+        thread: std::thread::current().name().map(|name| name.to_string()),
+        task: TASK.try_with(|name| name.to_string()).ok(),
+        backtrace,
+        version: updater::VERSION.to_string(),
+        uptime: uptime(),
+        last_command: last.map(|(name, _)| name.to_string()),
+        last_command_secs: last.map(|(_, at)| at.elapsed().as_secs()),
+        crashed_at: clock::timestamp(),
+    }
+}
+
+fn save(report: &CrashReport) {
+    let Some(dir) = DIR.get() else { return };
+    let write = || -> std::io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let mut saved = saved_reports(dir);
+        while saved.len() >= MAX_REPORTS {
+            fs::remove_file(saved.remove(0))?;
+        }
+        let path = dir.join(format!("crash-{:016}.json", clock::now().timestamp_millis()));
+        // Written whole then renamed, so a crash while writing leaves no half a report
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, serde_json::to_vec_pretty(report)?)?;
+        fs::rename(&temp, &path)?;
+        Ok(path)
+    };
+    match write() {
+        Ok(path) => info!("💥 Crash report saved to {}", path.display()),
+        Err(e) => error!("❌ Failed to save the crash report: {}", e),
+    }
+}
+
+// Oldest first; the names sort by time
+fn saved_reports(dir: &Path) -> Vec<PathBuf> {
+    let mut reports: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|e| e == "json")).collect())
+        .unwrap_or_default();
+    reports.sort();
+    reports
+}
@@ -0,0 +1,66 @@
+use signage_protocol::DownloadStats;
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::health;
+
+// Image attachments downloaded from CouchDB, for the heartbeat and /api/status: how many and
+// how much, how long each took and how fast it came, and how often a download had failed and
+// was tried again (a failed image is retried at the next sync). A site on a slow or flaky link
+// stands out against the rest of the fleet.
+
+// Downloads the averages are over
+const WINDOW: usize = 20;
+
+static DOWNLOADS: AtomicU64 = AtomicU64::new(0);
+static BYTES: AtomicU64 = AtomicU64::new(0);
+static FAILURES: AtomicU64 = AtomicU64::new(0);
+static RETRIES: AtomicU64 = AtomicU64::new(0);
+// Bytes and time of the most recent downloads
+static RECENT: Mutex<VecDeque<(u64, Duration)>> = Mutex::new(VecDeque::new());
+// Images whose last download failed
+static FAILED: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+pub fn record_success(image_id: &str, bytes: u64, took: Duration) {
+    note_retry(image_id, false);
+    DOWNLOADS.fetch_add(1, Ordering::Relaxed);
+    BYTES.fetch_add(bytes, Ordering::Relaxed);
+    let mut recent = RECENT.lock().unwrap();
+    if recent.len() == WINDOW {
+        recent.pop_front();
+    }
+    recent.push_back((bytes, took));
+}
+
+pub fn record_failure(image_id: &str) {
+    note_retry(image_id, true);
+    FAILURES.fetch_add(1, Ordering::Relaxed);
+}
+
+// Count an attempt at an image that failed last time, and remember whether this one did
+fn note_retry(image_id: &str, failed: bool) {
+    let mut failed_before = FAILED.lock().unwrap();
+    let retried = if failed { !failed_before.insert(image_id.to_string()) } else { failed_before.remove(image_id) };
+    if retried {
+        RETRIES.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub fn stats() -> DownloadStats {
+    let recent = RECENT.lock().unwrap();
+    let (bytes, time) = recent.iter().fold((0u64, Duration::ZERO), |(bytes, time), (b, t)| (bytes + b, time + *t));
+    let average_download_ms = (!recent.is_empty()).then(|| time.as_secs_f32() * 1000.0 / recent.len() as f32);
+    // Bits per millisecond are kilobits per second
+    let average_kbps = (!time.is_zero()).then(|| bytes as f32 * 8.0 / (time.as_secs_f32() * 1000.0));
+    DownloadStats {
+        downloads: DOWNLOADS.load(Ordering::Relaxed),
+        bytes_downloaded: BYTES.load(Ordering::Relaxed),
+        failures: FAILURES.load(Ordering::Relaxed),
+        retries: RETRIES.load(Ordering::Relaxed),
+        average_download_ms,
+        average_kbps,
+        last_sync: health::last_sync().map(|at| at.to_rfc3339()),
+    }
+}
@@ -0,0 +1,57 @@
+use image::codecs::jpeg::JpegDecoder;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageResult};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use crate::avif;
+
+// Camera photos run to 20-50 megapixels, and decoded whole a 50 MP photo takes 200 MB, which
+// a Pi Zero 2 doesn't have to spare. Sources more than HEADROOM times the size they are drawn
+// at are reduced while loading: JPEGs are decoded straight at 1/2, 1/4 or 1/8 scale, so the
+// full-size picture never exists in memory, and other formats are box-filtered down right
+// after decoding, before the slower Lanczos pass. The headroom leaves that final resample
+// enough detail to stay sharp.
+
+const HEADROOM: u32 = 2;
+
+// Like avif::open, but reduced towards `target` (width, height) when the image is much larger.
+// None loads the image at full size.
+pub fn open(path: &Path, target: Option<(u32, u32)>) -> ImageResult<DynamicImage> {
+    let Some(target) = target else {
+        return avif::open(path);
+    };
+    if ImageFormat::from_path(path).ok() == Some(ImageFormat::Jpeg) {
+        return open_jpeg(path, target);
+    }
+    let img = avif::open(path)?;
+    Ok(match reduced_size(img.width(), img.height(), target) {
+        Some((width, height)) => img.thumbnail_exact(width, height),
+        None => img,
+    })
+}
+
+fn open_jpeg(path: &Path, target: (u32, u32)) -> ImageResult<DynamicImage> {
+    let mut decoder = JpegDecoder::new(BufReader::new(File::open(path)?))?;
+    let (width, height) = decoder.dimensions();
+    if let Some((width, height)) = reduced_size(width, height, target) {
+        // The decoder picks the smallest of its scales at least this large
+        let clamp = |side: u32| side.min(u16::MAX as u32) as u16;
+        decoder.scale(clamp(width), clamp(height))?;
+    }
+    DynamicImage::from_decoder(decoder)
+}
+
+// The smallest size in the image's proportions that still covers HEADROOM times `target`
+// however the picture ends up turned (by EXIF or the display orientation), or None when the
+// image is no larger than that
+fn reduced_size(width: u32, height: u32, (target_width, target_height): (u32, u32)) -> Option<(u32, u32)> {
+    let long = target_width.max(target_height).max(1) * HEADROOM;
+    let short = target_width.min(target_height).max(1) * HEADROOM;
+    let (needed_width, needed_height) = if width >= height { (long, short) } else { (short, long) };
+    let factor = (width as f64 / needed_width as f64).min(height as f64 / needed_height as f64);
+    if factor <= 1.0 {
+        return None;
+    }
+    Some(((width as f64 / factor).ceil() as u32, (height as f64 / factor).ceil() as u32))
+}
@@ -0,0 +1,110 @@
+// Custom transition timing (TvConfig transition_easing): a CSS-style cubic-bezier(x1, y1, x2, y2)
+// curve applied to every transition's progress in place of its effect's own easing, so motion can
+// follow a brand's guidelines. The CSS keywords for the common curves are accepted as well, and
+// "default" leaves each effect with its built-in curve.
+
+// Steps of Newton's method before falling back to bisection; plenty for a frame's worth of precision
+const NEWTON_ITERATIONS: usize = 8;
+const BISECTION_ITERATIONS: usize = 24;
+const EPSILON: f32 = 1e-5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CubicBezier {
+    x1: f32,
+    y1: f32,
+    x2: f32,
+    y2: f32,
+}
+
+impl CubicBezier {
+    // Ok(None) for "default" (or nothing), which keeps the effect's own easing. As in CSS, the x
+    // coordinates must lie within 0-1 so time only runs forward; y may overshoot for curves that
+    // swing past the end and settle back.
+    pub fn parse(spec: &str) -> Result<Option<Self>, String> {
+        let spec = spec.trim().to_lowercase();
+        let (x1, y1, x2, y2) = match spec.replace('_', "-").as_str() {
+            "" | "default" => return Ok(None),
+            "linear" => (0.0, 0.0, 1.0, 1.0),
+            "ease" => (0.25, 0.1, 0.25, 1.0),
+            "ease-in" => (0.42, 0.0, 1.0, 1.0),
+            "ease-out" => (0.0, 0.0, 0.58, 1.0),
+            "ease-in-out" => (0.42, 0.0, 0.58, 1.0),
+            _ => {
+                let arguments = spec.strip_prefix("cubic-bezier(")
+                    .and_then(|rest| rest.strip_suffix(')'))
+                    .ok_or_else(|| format!("Unknown easing: {} (expected default, linear, ease, ease-in, ease-out, ease-in-out or cubic-bezier(x1, y1, x2, y2))", spec))?;
+                let points = arguments.split(',')
+                    .map(|n| n.trim().parse::<f32>().ok().filter(|n| n.is_finite()))
+                    .collect::<Option<Vec<_>>>()
+                    .filter(|points| points.len() == 4)
+                    .ok_or_else(|| format!("cubic-bezier takes four numbers, got ({})", arguments))?;
+                (points[0], points[1], points[2], points[3])
+            }
+        };
+        if !(0.0..=1.0).contains(&x1) || !(0.0..=1.0).contains(&x2) {
+            return Err(format!("cubic-bezier x values must be between 0 and 1, got {} and {}", x1, x2));
+        }
+        Ok(Some(CubicBezier { x1, y1, x2, y2 }))
+    }
+
+    // Unknown or invalid curves fall back to the effect's own easing
+    pub fn from_config(spec: &str) -> Option<Self> {
+        Self::parse(spec).unwrap_or(None)
+    }
+
+    // Eased progress for linear progress `t`; 0 and 1 map to themselves exactly
+    pub fn at(&self, t: f32) -> f32 {
+        if t <= 0.0 {
+            return 0.0;
+        }
+        if t >= 1.0 {
+            return 1.0;
+        }
+        bezier(self.y1, self.y2, self.solve_x(t))
+    }
+
+    // The curve parameter at which the curve's x is `x`; x rises monotonically since x1 and x2
+    // are within 0-1
+    fn solve_x(&self, x: f32) -> f32 {
+        let mut s = x;
+        for _ in 0..NEWTON_ITERATIONS {
+            let error = bezier(self.x1, self.x2, s) - x;
+            if error.abs() < EPSILON {
+                return s;
+            }
+            let slope = bezier_slope(self.x1, self.x2, s);
+            if slope.abs() < EPSILON {
+                break;
+            }
+            s -= error / slope;
+        }
+
+        // Newton's method stalls where the curve is flat; bisection always gets there
+        let (mut low, mut high) = (0.0, 1.0);
+        s = x;
+        for _ in 0..BISECTION_ITERATIONS {
+            let value = bezier(self.x1, self.x2, s);
+            if (value - x).abs() < EPSILON {
+                break;
+            }
+            if value < x {
+                low = s;
+            } else {
+                high = s;
+            }
+            s = (low + high) / 2.0;
+        }
+        s
+    }
+}
+
+// One coordinate of a cubic Bézier from (0, 0) to (1, 1) with control points p1 and p2
+fn bezier(p1: f32, p2: f32, s: f32) -> f32 {
+    let inverse = 1.0 - s;
+    3.0 * inverse * inverse * s * p1 + 3.0 * inverse * s * s * p2 + s * s * s
+}
+
+fn bezier_slope(p1: f32, p2: f32, s: f32) -> f32 {
+    let inverse = 1.0 - s;
+    3.0 * inverse * inverse * p1 + 6.0 * inverse * s * (p2 - p1) + 3.0 * s * s * (1.0 - p2)
+}
@@ -0,0 +1,344 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Result as IoResult;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+// IT8951 e-paper controller (Waveshare e-Paper HAT and compatible boards) driven over
+// spidev, with the HAT's reset and HRDY lines on sysfs GPIO. Only what a slideshow needs:
+// power-up, full-frame 4bpp loads and full-panel refreshes.
+
+// Waveshare HAT wiring (BCM numbering)
+const RESET_PIN: u32 = 17;
+const HRDY_PIN: u32 = 24;
+const SPI_SPEED_HZ: u32 = 12_000_000;
+// spidev's default transfer buffer size
+const SPI_MAX_TRANSFER: usize = 4096;
+// A GC16 refresh of a 10" panel takes about a second; anything near this is a hang
+const READY_TIMEOUT: Duration = Duration::from_secs(10);
+// Every few refreshes the panel is cleared to white first to shed ghosting
+const REFRESHES_PER_CLEAR: u32 = 20;
+
+// Packet preambles
+const PREAMBLE_COMMAND: u16 = 0x6000;
+const PREAMBLE_WRITE: u16 = 0x0000;
+const PREAMBLE_READ: u16 = 0x1000;
+
+// Commands
+const CMD_SYS_RUN: u16 = 0x0001;
+const CMD_REG_RD: u16 = 0x0010;
+const CMD_REG_WR: u16 = 0x0011;
+const CMD_LD_IMG_AREA: u16 = 0x0021;
+const CMD_LD_IMG_END: u16 = 0x0022;
+const CMD_DPY_AREA: u16 = 0x0034;
+const CMD_VCOM: u16 = 0x0039;
+const CMD_GET_DEV_INFO: u16 = 0x0302;
+
+// Registers
+const REG_I80CPCR: u16 = 0x0004;
+const REG_LISAR: u16 = 0x1208;
+const REG_LUTAFSR: u16 = 0x1224;
+
+// Waveform modes
+const MODE_INIT: u16 = 0;
+const MODE_GC16: u16 = 2;
+
+// LD_IMG_AREA argument: little-endian, 4 bits per pixel, no rotation
+const IMAGE_4BPP_LITTLE_ENDIAN: u16 = 2 << 4;
+
+// _IOW('k', n, ...) requests from <linux/spi/spidev.h>
+const SPI_IOC_WR_MODE: u32 = 0x4001_6b01;
+const SPI_IOC_WR_MAX_SPEED_HZ: u32 = 0x4004_6b04;
+const SPI_IOC_MESSAGE_1: u32 = 0x4020_6b00;
+
+#[repr(C)]
+#[derive(Default)]
+struct SpiIocTransfer {
+    tx_buf: u64,
+    rx_buf: u64,
+    len: u32,
+    speed_hz: u32,
+    delay_usecs: u16,
+    bits_per_word: u8,
+    cs_change: u8,
+    tx_nbits: u8,
+    rx_nbits: u8,
+    word_delay_usecs: u8,
+    pad: u8,
+}
+
+pub struct It8951 {
+    spi: File,
+    reset: GpioPin,
+    ready: GpioPin,
+    width: u32,
+    height: u32,
+    image_buffer_address: u32,
+    refreshes_since_clear: u32,
+    last_refresh: Option<Instant>,
+}
+
+impl It8951 {
+    // `vcom_mv` is the panel's VCOM in millivolts, printed on its ribbon cable (e.g. -1.50V -> 1500)
+    pub fn open(spi_path: &Path, vcom_mv: u16) -> IoResult<Self> {
+        let spi = OpenOptions::new().read(true).write(true).open(spi_path)?;
+        let mode: u8 = 0;
+        if unsafe { libc::ioctl(spi.as_raw_fd(), SPI_IOC_WR_MODE as _, &mode as *const u8) } == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let speed = SPI_SPEED_HZ;
+        if unsafe { libc::ioctl(spi.as_raw_fd(), SPI_IOC_WR_MAX_SPEED_HZ as _, &speed as *const u32) } == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let mut panel = Self {
+            spi,
+            reset: GpioPin::export(RESET_PIN, "out")?,
+            ready: GpioPin::export(HRDY_PIN, "in")?,
+            width: 0,
+            height: 0,
+            image_buffer_address: 0,
+            refreshes_since_clear: 0,
+            last_refresh: None,
+        };
+
+        panel.reset.set(false)?;
+        std::thread::sleep(Duration::from_millis(100));
+        panel.reset.set(true)?;
+        std::thread::sleep(Duration::from_millis(100));
+
+        panel.command(CMD_SYS_RUN)?;
+        panel.command(CMD_GET_DEV_INFO)?;
+        let info = panel.read_words(20)?;
+        panel.width = info[0] as u32;
+        panel.height = info[1] as u32;
+        panel.image_buffer_address = info[2] as u32 | (info[3] as u32) << 16;
+        if panel.width == 0 || panel.height == 0 {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "no IT8951 controller answered on SPI"));
+        }
+        let firmware: String = info[4..12].iter()
+            .flat_map(|word| word.to_be_bytes())
+            .take_while(|&byte| byte != 0)
+            .map(|byte| byte as char)
+            .collect();
+        info!("📄 IT8951 e-paper panel {}x{}, firmware {}", panel.width, panel.height, firmware.trim());
+
+        // Packed pixel writes, then the panel's VCOM
+        panel.write_register(REG_I80CPCR, 0x0001)?;
+        panel.command(CMD_VCOM)?;
+        panel.write_words(&[1, vcom_mv])?;
+
+        // Start from a clean white panel
+        panel.clear()?;
+        Ok(panel)
+    }
+
+    // A panel with no controller behind it, for tests: every exchange fails waiting on HRDY
+    #[cfg(test)]
+    pub fn unconnected(width: u32, height: u32) -> IoResult<Self> {
+        let pin = || GpioPin { value_path: "/nonexistent/it8951/value".into() };
+        Ok(Self {
+            spi: File::open("/dev/null")?,
+            reset: pin(),
+            ready: pin(),
+            width,
+            height,
+            image_buffer_address: 0,
+            refreshes_since_clear: 0,
+            last_refresh: None,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    // Show a full frame of 8-bit gray levels (row-major, width x height) with a 16-level
+    // refresh. E-paper keeps the picture without power, so this is the only write a slide needs.
+    pub fn display_gray(&mut self, gray: &[u8]) -> IoResult<()> {
+        let expected = (self.width * self.height) as usize;
+        if gray.len() != expected {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("e-paper frame has {} pixels, panel needs {}", gray.len(), expected),
+            ));
+        }
+
+        if self.refreshes_since_clear >= REFRESHES_PER_CLEAR {
+            self.clear()?;
+        }
+
+        self.wait_for_display()?;
+        self.load_image(gray)?;
+        self.refresh(MODE_GC16)?;
+        self.refreshes_since_clear += 1;
+        if let Some(last) = self.last_refresh {
+            info!("📄 E-paper refreshed ({}s since the last refresh)", last.elapsed().as_secs());
+        }
+        self.last_refresh = Some(Instant::now());
+        Ok(())
+    }
+
+    // Full white refresh with the INIT waveform
+    fn clear(&mut self) -> IoResult<()> {
+        self.wait_for_display()?;
+        self.load_image(&vec![0xFF; (self.width * self.height) as usize])?;
+        self.refresh(MODE_INIT)?;
+        self.refreshes_since_clear = 0;
+        Ok(())
+    }
+
+    fn load_image(&mut self, gray: &[u8]) -> IoResult<()> {
+        self.write_register(REG_LISAR + 2, (self.image_buffer_address >> 16) as u16)?;
+        self.write_register(REG_LISAR, self.image_buffer_address as u16)?;
+
+        self.command(CMD_LD_IMG_AREA)?;
+        self.write_words(&[IMAGE_4BPP_LITTLE_ENDIAN, 0, 0, self.width as u16, self.height as u16])?;
+
+        // Four pixels per word; within each byte the first pixel takes the low nibble, and the
+        // two bytes of a word go out high byte first, as the vendor driver sends them
+        let mut data = Vec::with_capacity(gray.len() / 2 + 2);
+        for pixels in gray.chunks(4) {
+            let nibble = |i: usize| pixels.get(i).map_or(0x0F, |&level| level >> 4);
+            data.push(nibble(2) | nibble(3) << 4);
+            data.push(nibble(0) | nibble(1) << 4);
+        }
+        for chunk in data.chunks(SPI_MAX_TRANSFER - 2) {
+            self.wait_ready()?;
+            let mut packet = Vec::with_capacity(chunk.len() + 2);
+            packet.extend_from_slice(&PREAMBLE_WRITE.to_be_bytes());
+            packet.extend_from_slice(chunk);
+            self.transfer(&packet, None)?;
+        }
+
+        self.command(CMD_LD_IMG_END)
+    }
+
+    fn refresh(&mut self, mode: u16) -> IoResult<()> {
+        self.command(CMD_DPY_AREA)?;
+        self.write_words(&[0, 0, self.width as u16, self.height as u16, mode])
+    }
+
+    // The controller reports a finished refresh by clearing the LUT engine status register
+    fn wait_for_display(&mut self) -> IoResult<()> {
+        let start = Instant::now();
+        while self.read_register(REG_LUTAFSR)? != 0 {
+            if start.elapsed() > READY_TIMEOUT {
+                return Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "e-paper refresh did not finish"));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        Ok(())
+    }
+
+    // HRDY goes high when the controller can take the next packet
+    fn wait_ready(&self) -> IoResult<()> {
+        let start = Instant::now();
+        while !self.ready.get()? {
+            if start.elapsed() > READY_TIMEOUT {
+                return Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "IT8951 stayed busy"));
+            }
+            std::thread::sleep(Duration::from_micros(100));
+        }
+        Ok(())
+    }
+
+    fn command(&mut self, command: u16) -> IoResult<()> {
+        self.wait_ready()?;
+        let mut packet = PREAMBLE_COMMAND.to_be_bytes().to_vec();
+        packet.extend_from_slice(&command.to_be_bytes());
+        self.transfer(&packet, None)
+    }
+
+    fn write_words(&mut self, words: &[u16]) -> IoResult<()> {
+        self.wait_ready()?;
+        let mut packet = PREAMBLE_WRITE.to_be_bytes().to_vec();
+        for word in words {
+            packet.extend_from_slice(&word.to_be_bytes());
+        }
+        self.transfer(&packet, None)
+    }
+
+    fn read_words(&mut self, count: usize) -> IoResult<Vec<u16>> {
+        self.wait_ready()?;
+        // Preamble, one dummy word, then the data
+        let mut packet = vec![0u8; 4 + count * 2];
+        packet[..2].copy_from_slice(&PREAMBLE_READ.to_be_bytes());
+        let mut received = vec![0u8; packet.len()];
+        self.transfer(&packet, Some(&mut received))?;
+        Ok(received[4..].chunks(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect())
+    }
+
+    fn write_register(&mut self, register: u16, value: u16) -> IoResult<()> {
+        self.command(CMD_REG_WR)?;
+        self.write_words(&[register, value])
+    }
+
+    fn read_register(&mut self, register: u16) -> IoResult<u16> {
+        self.command(CMD_REG_RD)?;
+        self.write_words(&[register])?;
+        Ok(self.read_words(1)?[0])
+    }
+
+    fn transfer(&self, tx: &[u8], rx: Option<&mut [u8]>) -> IoResult<()> {
+        let transfer = SpiIocTransfer {
+            tx_buf: tx.as_ptr() as u64,
+            rx_buf: rx.map_or(0, |rx| rx.as_mut_ptr() as u64),
+            len: tx.len() as u32,
+            speed_hz: SPI_SPEED_HZ,
+            bits_per_word: 8,
+            ..Default::default()
+        };
+        if unsafe { libc::ioctl(self.spi.as_raw_fd(), SPI_IOC_MESSAGE_1 as _, &transfer as *const SpiIocTransfer) } == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+// A BCM GPIO line through /sys/class/gpio. Newer kernels number the SoC's lines from a
+// non-zero base (512 on 6.6+), so the base is read from the pin controller's chip.
+struct GpioPin {
+    value_path: std::path::PathBuf,
+}
+
+impl GpioPin {
+    fn export(bcm_pin: u32, direction: &str) -> IoResult<Self> {
+        let number = soc_gpio_base() + bcm_pin;
+        let dir = format!("/sys/class/gpio/gpio{}", number);
+        if !Path::new(&dir).exists() {
+            fs::write("/sys/class/gpio/export", number.to_string())?;
+            // udev may need a moment to make the new files writable
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        fs::write(format!("{}/direction", dir), direction)?;
+        Ok(Self { value_path: format!("{}/value", dir).into() })
+    }
+
+    fn set(&self, high: bool) -> IoResult<()> {
+        fs::write(&self.value_path, if high { "1" } else { "0" })
+    }
+
+    fn get(&self) -> IoResult<bool> {
+        Ok(fs::read_to_string(&self.value_path)?.trim() == "1")
+    }
+}
+
+fn soc_gpio_base() -> u32 {
+    let chips = match fs::read_dir("/sys/class/gpio") {
+        Ok(chips) => chips,
+        Err(_) => return 0,
+    };
+    chips
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.file_name().is_some_and(|name| name.to_string_lossy().starts_with("gpiochip")))
+        .find(|path| fs::read_to_string(path.join("label")).is_ok_and(|label| label.starts_with("pinctrl-")))
+        .and_then(|path| fs::read_to_string(path.join("base")).ok())
+        .and_then(|base| base.trim().parse().ok())
+        .unwrap_or(0)
+}
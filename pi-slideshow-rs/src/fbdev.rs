@@ -0,0 +1,338 @@
+use std::fs::File;
+use std::io::Result as IoResult;
+use std::os::unix::io::AsRawFd;
+use tracing::warn;
+
+// Linux fbdev ioctls and the structs they fill, laid out as in <linux/fb.h>
+
+const FBIOGET_VSCREENINFO: u32 = 0x4600;
+const FBIOPUT_VSCREENINFO: u32 = 0x4601;
+const FBIOGET_FSCREENINFO: u32 = 0x4602;
+const FBIOPAN_DISPLAY: u32 = 0x4606;
+const FBIOBLANK: u32 = 0x4611;
+// _IOW('F', 0x20, __u32)
+const FBIO_WAITFORVSYNC: u32 = 0x4004_4620;
+
+const FB_BLANK_UNBLANK: libc::c_ulong = 0;
+// Deepest blanking level: sync signals off, so TVs drop into standby
+const FB_BLANK_POWERDOWN: libc::c_ulong = 4;
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+#[allow(dead_code)]
+pub struct FbBitfield {
+    pub offset: u32,
+    pub length: u32,
+    pub msb_right: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+#[allow(dead_code)]
+pub struct FbVarScreeninfo {
+    pub xres: u32,
+    pub yres: u32,
+    pub xres_virtual: u32,
+    pub yres_virtual: u32,
+    pub xoffset: u32,
+    pub yoffset: u32,
+    pub bits_per_pixel: u32,
+    pub grayscale: u32,
+    pub red: FbBitfield,
+    pub green: FbBitfield,
+    pub blue: FbBitfield,
+    pub transp: FbBitfield,
+    pub nonstd: u32,
+    pub activate: u32,
+    pub height: u32,
+    pub width: u32,
+    pub accel_flags: u32,
+    pub pixclock: u32,
+    pub left_margin: u32,
+    pub right_margin: u32,
+    pub upper_margin: u32,
+    pub lower_margin: u32,
+    pub hsync_len: u32,
+    pub vsync_len: u32,
+    pub sync: u32,
+    pub vmode: u32,
+    pub rotate: u32,
+    pub colorspace: u32,
+    pub reserved: [u32; 4],
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+#[allow(dead_code)]
+pub struct FbFixScreeninfo {
+    pub id: [u8; 16],
+    pub smem_start: libc::c_ulong,
+    pub smem_len: u32,
+    pub type_: u32,
+    pub type_aux: u32,
+    pub visual: u32,
+    pub xpanstep: u16,
+    pub ypanstep: u16,
+    pub ywrapstep: u16,
+    pub line_length: u32,
+    pub mmio_start: libc::c_ulong,
+    pub mmio_len: u32,
+    pub accel: u32,
+    pub capabilities: u16,
+    pub reserved: [u16; 2],
+}
+
+// The display mode the driver is actually running, which is what every buffer and
+// scaled image has to be sized for
+#[derive(Debug, Clone, Copy)]
+pub struct FbMode {
+    pub width: u32,
+    pub height: u32,
+    pub bits_per_pixel: u32,
+    // Bytes from the start of one scanline to the next, including any padding
+    pub line_length: u32,
+    // Size of the device memory, at least one full screen
+    pub memory_len: usize,
+}
+
+impl FbMode {
+    // Used when the device can't be queried (file fallback, non-fbdev outputs)
+    pub fn assumed(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            bits_per_pixel: 32,
+            line_length: width * 4,
+            memory_len: (width * height * 4) as usize,
+        }
+    }
+
+    pub fn query(file: &File) -> IoResult<Self> {
+        let var = get_var_screeninfo(file)?;
+        let fix = get_fix_screeninfo(file)?;
+
+        if var.xres == 0 || var.yres == 0 || var.bits_per_pixel == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("driver reported an empty mode {}x{} at {}bpp", var.xres, var.yres, var.bits_per_pixel),
+            ));
+        }
+
+        // Some drivers leave line_length at 0; assume unpadded scanlines then
+        let line_length = if fix.line_length == 0 {
+            var.xres * var.bits_per_pixel.div_ceil(8)
+        } else {
+            fix.line_length
+        };
+
+        Ok(Self {
+            width: var.xres,
+            height: var.yres,
+            bits_per_pixel: var.bits_per_pixel,
+            line_length,
+            memory_len: (fix.smem_len as usize).max(line_length as usize * var.yres as usize),
+        })
+    }
+}
+
+pub fn get_var_screeninfo(file: &File) -> IoResult<FbVarScreeninfo> {
+    let mut var = FbVarScreeninfo::default();
+    if unsafe { libc::ioctl(file.as_raw_fd(), FBIOGET_VSCREENINFO as _, &mut var) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(var)
+}
+
+pub fn put_var_screeninfo(file: &File, var: &mut FbVarScreeninfo) -> IoResult<()> {
+    if unsafe { libc::ioctl(file.as_raw_fd(), FBIOPUT_VSCREENINFO as _, var as *mut FbVarScreeninfo) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+pub fn pan_display(file: &File, var: &FbVarScreeninfo) -> IoResult<()> {
+    if unsafe { libc::ioctl(file.as_raw_fd(), FBIOPAN_DISPLAY as _, var as *const FbVarScreeninfo) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+// Ask the driver to rotate the picture (0-3, quarter turns clockwise). Most fbdev drivers
+// ignore the request, so the result is read back rather than trusted.
+pub fn set_rotation(file: &File, rotate: u32) -> IoResult<()> {
+    let mut var = get_var_screeninfo(file)?;
+    if var.rotate == rotate {
+        return Ok(());
+    }
+    var.rotate = rotate;
+    put_var_screeninfo(file, &mut var)?;
+    if get_var_screeninfo(file)?.rotate != rotate {
+        return Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "driver ignored the rotate setting"));
+    }
+    Ok(())
+}
+
+// Block until the start of the next vertical blank on the first display head
+pub fn wait_for_vsync(file: &File) -> IoResult<()> {
+    let head: u32 = 0;
+    if unsafe { libc::ioctl(file.as_raw_fd(), FBIO_WAITFORVSYNC as _, &head as *const u32) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+pub fn blank(file: &File, blanked: bool) -> IoResult<()> {
+    let level = if blanked { FB_BLANK_POWERDOWN } else { FB_BLANK_UNBLANK };
+    if unsafe { libc::ioctl(file.as_raw_fd(), FBIOBLANK as _, level) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+pub fn get_fix_screeninfo(file: &File) -> IoResult<FbFixScreeninfo> {
+    let mut fix = FbFixScreeninfo::default();
+    if unsafe { libc::ioctl(file.as_raw_fd(), FBIOGET_FSCREENINFO as _, &mut fix) } == -1 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(fix)
+}
+
+// Two screen-sized pages stacked in a virtual framebuffer twice the visible height.
+// Frames are drawn into the hidden page, then FBIOPAN_DISPLAY flips it on screen in one step.
+// The virtual height and panning the driver had before are put back when it is dropped, on
+// exit or when double buffering can't be used after all, so the console isn't left on a
+// virtual screen it doesn't expect.
+pub struct DoubleBuffer {
+    // The device, opened again for Drop
+    file: File,
+    original: FbVarScreeninfo,
+    var: FbVarScreeninfo,
+    visible_page: usize,
+}
+
+impl DoubleBuffer {
+    pub fn enable(file: &File) -> IoResult<Self> {
+        let original = get_var_screeninfo(file)?;
+        let mut var = original;
+        if var.yres_virtual < var.yres * 2 {
+            var.yres_virtual = var.yres * 2;
+            var.yoffset = 0;
+            put_var_screeninfo(file, &mut var)?;
+            var = get_var_screeninfo(file)?;
+        }
+        // From here on, returning an error drops this and restores the original mode
+        let mut double_buffer = Self { file: file.try_clone()?, original, var, visible_page: 0 };
+
+        let fix = get_fix_screeninfo(file)?;
+        let unsupported = |reason: &str| Err(std::io::Error::new(std::io::ErrorKind::Unsupported, reason.to_string()));
+        if var.yres_virtual < var.yres * 2 {
+            return unsupported("driver refused a virtual height of twice the screen");
+        }
+        if (fix.smem_len as usize) < fix.line_length as usize * var.yres as usize * 2 {
+            return unsupported("not enough video memory for two pages");
+        }
+        if fix.ypanstep == 0 {
+            return unsupported("driver does not support vertical panning");
+        }
+
+        // Start from page 0 so the first flip shows page 1
+        double_buffer.var.xoffset = 0;
+        double_buffer.var.yoffset = 0;
+        pan_display(file, &double_buffer.var)?;
+
+        Ok(double_buffer)
+    }
+
+    // Byte offset of the page that is not on screen
+    pub fn back_offset(&self, page_len: usize) -> usize {
+        (1 - self.visible_page) * page_len
+    }
+
+    pub fn visible_offset(&self, page_len: usize) -> usize {
+        self.visible_page * page_len
+    }
+
+    pub fn flip(&mut self, file: &File) -> IoResult<()> {
+        let next_page = 1 - self.visible_page;
+        self.var.yoffset = next_page as u32 * self.var.yres;
+        pan_display(file, &self.var)?;
+        self.visible_page = next_page;
+        Ok(())
+    }
+}
+
+impl Drop for DoubleBuffer {
+    // Only the fields enable changed, so a rotation set since stays as it is
+    fn drop(&mut self) {
+        let restored = get_var_screeninfo(&self.file).and_then(|mut var| {
+            if var.yres_virtual == self.original.yres_virtual && var.yoffset == self.original.yoffset {
+                return Ok(());
+            }
+            var.yres_virtual = self.original.yres_virtual;
+            var.xoffset = self.original.xoffset;
+            var.yoffset = self.original.yoffset;
+            put_var_screeninfo(&self.file, &mut var)
+        });
+        if let Err(e) = restored {
+            warn!("Could not restore the framebuffer's virtual size: {}", e);
+        }
+    }
+}
+
+// How one pixel is packed in framebuffer memory (byte order as stored, little-endian)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    // 32bpp, B G R A
+    Bgra8888,
+    // 24bpp, B G R (red in the high byte of each 24-bit word)
+    Rgb888,
+    // 16bpp, 5 bits red, 6 green, 5 blue
+    Rgb565,
+}
+
+impl PixelFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "bgra8888" | "bgra" | "32" => Some(PixelFormat::Bgra8888),
+            "rgb888" | "24" => Some(PixelFormat::Rgb888),
+            "rgb565" | "16" => Some(PixelFormat::Rgb565),
+            _ => None,
+        }
+    }
+
+    pub fn from_bits_per_pixel(bits_per_pixel: u32) -> Option<Self> {
+        match bits_per_pixel {
+            32 => Some(PixelFormat::Bgra8888),
+            24 => Some(PixelFormat::Rgb888),
+            16 => Some(PixelFormat::Rgb565),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PixelFormat::Bgra8888 => "bgra8888",
+            PixelFormat::Rgb888 => "rgb888",
+            PixelFormat::Rgb565 => "rgb565",
+        }
+    }
+
+    pub fn bytes_per_pixel(&self) -> usize {
+        match self {
+            PixelFormat::Bgra8888 => 4,
+            PixelFormat::Rgb888 => 3,
+            PixelFormat::Rgb565 => 2,
+        }
+    }
+
+    pub fn push_pixel(&self, buffer: &mut Vec<u8>, [r, g, b, a]: [u8; 4]) {
+        match self {
+            PixelFormat::Bgra8888 => buffer.extend_from_slice(&[b, g, r, a]),
+            PixelFormat::Rgb888 => buffer.extend_from_slice(&[b, g, r]),
+            PixelFormat::Rgb565 => {
+                let packed = ((r as u16 >> 3) << 11) | ((g as u16 >> 2) << 5) | (b as u16 >> 3);
+                buffer.extend_from_slice(&packed.to_le_bytes());
+            }
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use ring::digest::{digest, SHA256};
+use std::fmt::Debug;
+use std::sync::Mutex;
+
+// The build and settings an endpoint runs with, for the heartbeat next to its version: the
+// commit it was built from and a hash of its effective local settings (the command line, SIGNAGE_*
+// variables and --config file as layered by config_file.rs), so a unit left on an old release
+// or started with a stale config file stands out across the fleet without its settings,
+// passwords included, leaving the device. The TV ID differs on every unit by design and is
+// left out. Settings the management server pushes aren't covered; it has those already. A new
+// release adds flags with their defaults, so hashes only compare between units on one version.
+
+// Hex digits of the SHA-256 kept, enough to tell configurations apart at a glance
+const HASH_LENGTH: usize = 12;
+
+static CONFIG_HASH: Mutex<Option<String>> = Mutex::new(None);
+
+// Set at startup and again whenever the config file is reloaded. The Debug form lists every
+// setting in declaration order, so equal settings always hash alike within a build.
+pub fn set_config(settings: &impl Debug) {
+    let hash = digest(&SHA256, format!("{:?}", settings).as_bytes());
+    let hex: String = hash.as_ref().iter().map(|byte| format!("{:02x}", byte)).collect();
+    *CONFIG_HASH.lock().unwrap() = Some(hex[..HASH_LENGTH].to_string());
+}
+
+pub fn config_hash() -> Option<String> {
+    CONFIG_HASH.lock().unwrap().clone()
+}
+
+// None when the build had no git checkout to read it from
+pub fn commit() -> Option<&'static str> {
+    Some(env!("GIT_COMMIT_SHORT")).filter(|commit| !commit.is_empty() && *commit != "unknown")
+}
@@ -0,0 +1,573 @@
+use image::RgbaImage;
+use std::cell::Cell;
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::ptr;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::TransitionType;
+
+// Transitions drawn on the GPU (--gpu-transitions): the outgoing and incoming frames are
+// uploaded as two textures and each frame is a fragment shader pass between them, read back
+// for the framebuffer. That leaves the CPU to downloads and sync while a transition plays.
+// EGL and GLES2 are opened at run time, so builds don't need them and a device without them
+// (or whose EGL won't start) keeps the software renderer. Dissolve and pixelate stay in
+// software, where their random patterns are drawn.
+
+const EGL_LIBRARIES: [&str; 3] = ["libEGL.so.1", "libEGL.so", "libbrcmEGL.so"];
+const GLES_LIBRARIES: [&str; 3] = ["libGLESv2.so.2", "libGLESv2.so", "libbrcmGLESv2.so"];
+
+const EGL_NONE: i32 = 0x3038;
+const EGL_RED_SIZE: i32 = 0x3024;
+const EGL_GREEN_SIZE: i32 = 0x3023;
+const EGL_BLUE_SIZE: i32 = 0x3022;
+const EGL_ALPHA_SIZE: i32 = 0x3021;
+const EGL_SURFACE_TYPE: i32 = 0x3033;
+const EGL_PBUFFER_BIT: i32 = 0x0001;
+const EGL_RENDERABLE_TYPE: i32 = 0x3040;
+const EGL_OPENGL_ES2_BIT: i32 = 0x0004;
+const EGL_WIDTH: i32 = 0x3057;
+const EGL_HEIGHT: i32 = 0x3056;
+const EGL_OPENGL_ES_API: u32 = 0x30A0;
+const EGL_CONTEXT_CLIENT_VERSION: i32 = 0x3098;
+// Mesa's display for rendering without a window system (KMS consoles)
+const EGL_PLATFORM_SURFACELESS_MESA: u32 = 0x31DD;
+
+const GL_TEXTURE_2D: u32 = 0x0DE1;
+const GL_TEXTURE0: u32 = 0x84C0;
+const GL_TEXTURE_MIN_FILTER: u32 = 0x2801;
+const GL_TEXTURE_MAG_FILTER: u32 = 0x2800;
+const GL_TEXTURE_WRAP_S: u32 = 0x2802;
+const GL_TEXTURE_WRAP_T: u32 = 0x2803;
+const GL_NEAREST: i32 = 0x2600;
+const GL_CLAMP_TO_EDGE: i32 = 0x812F;
+const GL_RGBA: u32 = 0x1908;
+const GL_UNSIGNED_BYTE: u32 = 0x1401;
+const GL_FLOAT: u32 = 0x1406;
+const GL_FRAMEBUFFER: u32 = 0x8D40;
+const GL_COLOR_ATTACHMENT0: u32 = 0x8CE0;
+const GL_FRAMEBUFFER_COMPLETE: u32 = 0x8CD5;
+const GL_VERTEX_SHADER: u32 = 0x8B31;
+const GL_FRAGMENT_SHADER: u32 = 0x8B30;
+const GL_COMPILE_STATUS: u32 = 0x8B81;
+const GL_LINK_STATUS: u32 = 0x8B82;
+const GL_TRIANGLE_STRIP: u32 = 0x0005;
+const GL_MAX_TEXTURE_SIZE: u32 = 0x0D33;
+const GL_PACK_ALIGNMENT: u32 = 0x0D05;
+const GL_UNPACK_ALIGNMENT: u32 = 0x0CF5;
+const GL_NO_ERROR: u32 = 0;
+
+const VERTEX_SHADER: &str = "
+attribute vec2 position;
+void main() {
+    gl_Position = vec4(position, 0.0, 1.0);
+}
+";
+
+// Mirrors ImageManager::render_transition, in pixel coordinates from the top left so the
+// geometry matches the software effects
+const FRAGMENT_SHADER: &str = "
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+#else
+precision mediump float;
+#endif
+uniform sampler2D from_image;
+uniform sampler2D to_image;
+uniform vec2 size;
+uniform float progress;
+uniform int effect;
+uniform vec2 direction;
+
+vec4 pixel(sampler2D image, vec2 at) {
+    return texture2D(image, (floor(at) + 0.5) / size);
+}
+
+bool inside(vec2 at) {
+    return at.x >= 0.0 && at.y >= 0.0 && at.x < size.x && at.y < size.y;
+}
+
+void main() {
+    vec2 at = vec2(gl_FragCoord.x - 0.5, size.y - gl_FragCoord.y - 0.5);
+    vec4 from_pixel = pixel(from_image, at);
+    vec4 to_pixel = pixel(to_image, at);
+    if (effect == 1) {
+        vec2 offset = size * progress * direction;
+        offset = sign(offset) * floor(abs(offset));
+        vec2 from_at = at - offset;
+        vec2 to_at = at - offset + size * direction;
+        if (inside(to_at)) {
+            gl_FragColor = pixel(to_image, to_at);
+        } else if (inside(from_at)) {
+            gl_FragColor = pixel(from_image, from_at);
+        } else {
+            gl_FragColor = vec4(0.0, 0.0, 0.0, 1.0);
+        }
+    } else if (effect == 2) {
+        bool show_to;
+        if (direction.x < 0.0) {
+            show_to = at.x / size.x < progress;
+        } else if (direction.x > 0.0) {
+            show_to = at.x / size.x >= 1.0 - progress;
+        } else if (direction.y < 0.0) {
+            show_to = at.y / size.y >= 1.0 - progress;
+        } else {
+            show_to = at.y / size.y < progress;
+        }
+        gl_FragColor = show_to ? to_pixel : from_pixel;
+    } else if (effect == 3) {
+        float radius = length(size) / 2.0 * progress;
+        gl_FragColor = distance(at + 0.5, size / 2.0) < radius ? to_pixel : from_pixel;
+    } else if (effect == 4) {
+        gl_FragColor = at.x + at.y < (size.x + size.y) * progress ? to_pixel : from_pixel;
+    } else if (effect == 5) {
+        float distortion = progress * 0.1;
+        vec2 wave = vec2(sin(at.y * 0.02 + progress * 6.28) * size.x, cos(at.x * 0.02 + progress * 6.28) * size.y) * distortion;
+        vec2 source = clamp(floor(at + wave), vec2(0.0), size - 1.0);
+        gl_FragColor = mix(pixel(from_image, source), to_pixel, progress);
+    } else {
+        gl_FragColor = mix(from_pixel, to_pixel, progress);
+    }
+}
+";
+
+// A full-screen quad
+static QUAD: [f32; 8] = [-1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0];
+
+struct Library(*mut c_void);
+
+impl Library {
+    fn open(names: &[&str]) -> Result<Self, String> {
+        for name in names {
+            let name = CString::new(*name).unwrap();
+            let handle = unsafe { libc::dlopen(name.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+            if !handle.is_null() {
+                return Ok(Library(handle));
+            }
+        }
+        Err(format!("none of {} could be loaded", names.join(", ")))
+    }
+
+    fn symbol(&self, name: &str) -> Result<*mut c_void, String> {
+        let c_name = CString::new(name).unwrap();
+        let symbol = unsafe { libc::dlsym(self.0, c_name.as_ptr()) };
+        if symbol.is_null() {
+            return Err(format!("{} is missing", name));
+        }
+        Ok(symbol)
+    }
+}
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        unsafe { libc::dlclose(self.0) };
+    }
+}
+
+// Declares a table of entry points and how to look them up in a library
+macro_rules! entry_points {
+    ($table:ident { $($name:ident: fn($($arg:ty),*) $(-> $ret:ty)?;)* }) => {
+        #[allow(non_snake_case)]
+        struct $table {
+            $($name: unsafe extern "C" fn($($arg),*) $(-> $ret)?,)*
+        }
+
+        impl $table {
+            fn load(library: &Library) -> Result<Self, String> {
+                Ok($table {
+                    $($name: unsafe {
+                        std::mem::transmute::<*mut c_void, unsafe extern "C" fn($($arg),*) $(-> $ret)?>(library.symbol(stringify!($name))?)
+                    },)*
+                })
+            }
+        }
+    };
+}
+
+type Handle = *mut c_void;
+
+entry_points!(Egl {
+    eglGetProcAddress: fn(*const c_char) -> *mut c_void;
+    eglGetDisplay: fn(Handle) -> Handle;
+    eglInitialize: fn(Handle, *mut i32, *mut i32) -> u32;
+    eglTerminate: fn(Handle) -> u32;
+    eglBindAPI: fn(u32) -> u32;
+    eglChooseConfig: fn(Handle, *const i32, *mut Handle, i32, *mut i32) -> u32;
+    eglCreateContext: fn(Handle, Handle, Handle, *const i32) -> Handle;
+    eglDestroyContext: fn(Handle, Handle) -> u32;
+    eglCreatePbufferSurface: fn(Handle, Handle, *const i32) -> Handle;
+    eglDestroySurface: fn(Handle, Handle) -> u32;
+    eglMakeCurrent: fn(Handle, Handle, Handle, Handle) -> u32;
+    eglGetError: fn() -> i32;
+});
+
+entry_points!(Gl {
+    glGetError: fn() -> u32;
+    glGetIntegerv: fn(u32, *mut i32);
+    glPixelStorei: fn(u32, i32);
+    glViewport: fn(i32, i32, i32, i32);
+    glGenTextures: fn(i32, *mut u32);
+    glDeleteTextures: fn(i32, *const u32);
+    glBindTexture: fn(u32, u32);
+    glActiveTexture: fn(u32);
+    glTexParameteri: fn(u32, u32, i32);
+    glTexImage2D: fn(u32, i32, i32, i32, i32, i32, u32, u32, *const c_void);
+    glGenFramebuffers: fn(i32, *mut u32);
+    glDeleteFramebuffers: fn(i32, *const u32);
+    glBindFramebuffer: fn(u32, u32);
+    glFramebufferTexture2D: fn(u32, u32, u32, u32, i32);
+    glCheckFramebufferStatus: fn(u32) -> u32;
+    glCreateShader: fn(u32) -> u32;
+    glShaderSource: fn(u32, i32, *const *const c_char, *const i32);
+    glCompileShader: fn(u32);
+    glGetShaderiv: fn(u32, u32, *mut i32);
+    glGetShaderInfoLog: fn(u32, i32, *mut i32, *mut c_char);
+    glDeleteShader: fn(u32);
+    glCreateProgram: fn() -> u32;
+    glDeleteProgram: fn(u32);
+    glAttachShader: fn(u32, u32);
+    glBindAttribLocation: fn(u32, u32, *const c_char);
+    glLinkProgram: fn(u32);
+    glGetProgramiv: fn(u32, u32, *mut i32);
+    glUseProgram: fn(u32);
+    glGetUniformLocation: fn(u32, *const c_char) -> i32;
+    glUniform1i: fn(i32, i32);
+    glUniform1f: fn(i32, f32);
+    glUniform2f: fn(i32, f32, f32);
+    glVertexAttribPointer: fn(u32, i32, u32, u8, i32, *const c_void);
+    glEnableVertexAttribArray: fn(u32);
+    glDrawArrays: fn(u32, i32, i32);
+    glReadPixels: fn(i32, i32, i32, i32, u32, u32, *mut c_void);
+});
+
+pub struct GlesRenderer {
+    egl: Egl,
+    gl: Gl,
+    display: Handle,
+    context: Handle,
+    // 1x1, only there to make the context current; frames are drawn into a texture
+    surface: Handle,
+    program: u32,
+    max_texture_size: i32,
+    // Held by the Transition that has the context current
+    current: Mutex<()>,
+    // Dropped last, after everything looked up in them
+    _libraries: (Library, Library),
+}
+
+// GL calls are only made with the context current, and the context is only current on the
+// thread whose Transition holds `current`, so transitions may be drawn from any thread
+unsafe impl Send for GlesRenderer {}
+unsafe impl Sync for GlesRenderer {}
+
+impl GlesRenderer {
+    pub fn new() -> Result<Self, String> {
+        let egl_library = Library::open(&EGL_LIBRARIES)?;
+        let gles_library = Library::open(&GLES_LIBRARIES)?;
+        let egl = Egl::load(&egl_library)?;
+        let gl = Gl::load(&gles_library)?;
+
+        unsafe {
+            let display = platform_display(&egl);
+            if display.is_null() || (egl.eglInitialize)(display, ptr::null_mut(), ptr::null_mut()) == 0 {
+                return Err(format!("no EGL display (error {:#x})", (egl.eglGetError)()));
+            }
+            let mut renderer = GlesRenderer {
+                egl,
+                gl,
+                display,
+                context: ptr::null_mut(),
+                surface: ptr::null_mut(),
+                program: 0,
+                max_texture_size: 0,
+                current: Mutex::new(()),
+                _libraries: (egl_library, gles_library),
+            };
+            renderer.create_context()?;
+            Ok(renderer)
+        }
+    }
+
+    unsafe fn create_context(&mut self) -> Result<(), String> {
+        let egl = &self.egl;
+        (egl.eglBindAPI)(EGL_OPENGL_ES_API);
+        let config_attributes = [
+            EGL_RED_SIZE, 8, EGL_GREEN_SIZE, 8, EGL_BLUE_SIZE, 8, EGL_ALPHA_SIZE, 8,
+            EGL_SURFACE_TYPE, EGL_PBUFFER_BIT,
+            EGL_RENDERABLE_TYPE, EGL_OPENGL_ES2_BIT,
+            EGL_NONE,
+        ];
+        let mut config: Handle = ptr::null_mut();
+        let mut count = 0;
+        if (egl.eglChooseConfig)(self.display, config_attributes.as_ptr(), &mut config, 1, &mut count) == 0 || count == 0 {
+            return Err(format!("no RGBA8 GLES2 config (error {:#x})", (egl.eglGetError)()));
+        }
+        let context_attributes = [EGL_CONTEXT_CLIENT_VERSION, 2, EGL_NONE];
+        self.context = (egl.eglCreateContext)(self.display, config, ptr::null_mut(), context_attributes.as_ptr());
+        if self.context.is_null() {
+            return Err(format!("no GLES2 context (error {:#x})", (egl.eglGetError)()));
+        }
+        let surface_attributes = [EGL_WIDTH, 1, EGL_HEIGHT, 1, EGL_NONE];
+        self.surface = (egl.eglCreatePbufferSurface)(self.display, config, surface_attributes.as_ptr());
+        if self.surface.is_null() {
+            return Err(format!("no pbuffer surface (error {:#x})", (egl.eglGetError)()));
+        }
+
+        self.make_current()?;
+        let result = self.build_program();
+        if result.is_ok() {
+            (self.gl.glGetIntegerv)(GL_MAX_TEXTURE_SIZE, &mut self.max_texture_size);
+        }
+        self.release();
+        result
+    }
+
+    unsafe fn build_program(&mut self) -> Result<(), String> {
+        let gl = &self.gl;
+        let vertex = compile_shader(gl, GL_VERTEX_SHADER, VERTEX_SHADER)?;
+        let fragment = match compile_shader(gl, GL_FRAGMENT_SHADER, FRAGMENT_SHADER) {
+            Ok(fragment) => fragment,
+            Err(e) => {
+                (gl.glDeleteShader)(vertex);
+                return Err(e);
+            }
+        };
+        let program = (gl.glCreateProgram)();
+        (gl.glAttachShader)(program, vertex);
+        (gl.glAttachShader)(program, fragment);
+        (gl.glBindAttribLocation)(program, 0, c"position".as_ptr());
+        (gl.glLinkProgram)(program);
+        // Attached shaders are only flagged here, and freed along with the program
+        (gl.glDeleteShader)(vertex);
+        (gl.glDeleteShader)(fragment);
+        let mut linked = 0;
+        (gl.glGetProgramiv)(program, GL_LINK_STATUS, &mut linked);
+        if linked == 0 {
+            (gl.glDeleteProgram)(program);
+            return Err("transition shader failed to link".to_string());
+        }
+        self.program = program;
+        Ok(())
+    }
+
+    // Whether `transition` can be drawn here rather than in software
+    pub fn supports(transition: &TransitionType) -> bool {
+        !matches!(
+            transition,
+            TransitionType::Dissolve
+                | TransitionType::Pixelate
+                | TransitionType::Cube
+                | TransitionType::PageCurl
+                | TransitionType::Blinds
+                | TransitionType::Checkerboard
+                | TransitionType::ZoomIn
+                | TransitionType::ZoomOut
+        )
+    }
+
+    // Upload the two ends of a transition. The context stays current on this thread until
+    // the returned Transition is dropped.
+    pub fn begin(&self, from: &RgbaImage, to: &RgbaImage) -> Result<Transition<'_>, String> {
+        let (width, height) = from.dimensions();
+        if to.dimensions() != (width, height) {
+            return Err("transition frames differ in size".to_string());
+        }
+        if width as i32 > self.max_texture_size || height as i32 > self.max_texture_size {
+            return Err(format!("{}x{} is over the GPU's {} pixel texture limit", width, height, self.max_texture_size));
+        }
+
+        let current = self.current.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        unsafe {
+            self.make_current()?;
+            let gl = &self.gl;
+            // Dropped on the way out of an error, which frees what was made so far
+            let mut transition = Transition {
+                renderer: self,
+                textures: [0; 3],
+                framebuffer: 0,
+                width,
+                height,
+                effect: Cell::new(None),
+                _current: current,
+            };
+            (gl.glGenTextures)(3, transition.textures.as_mut_ptr());
+            let textures = transition.textures;
+            (gl.glPixelStorei)(GL_UNPACK_ALIGNMENT, 1);
+            for (texture, pixels) in textures.iter().zip([Some(from), Some(to), None]) {
+                (gl.glBindTexture)(GL_TEXTURE_2D, *texture);
+                (gl.glTexParameteri)(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_NEAREST);
+                (gl.glTexParameteri)(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_NEAREST);
+                (gl.glTexParameteri)(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, GL_CLAMP_TO_EDGE);
+                (gl.glTexParameteri)(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, GL_CLAMP_TO_EDGE);
+                let data = pixels.map_or(ptr::null(), |image| image.as_raw().as_ptr() as *const c_void);
+                (gl.glTexImage2D)(GL_TEXTURE_2D, 0, GL_RGBA as i32, width as i32, height as i32, 0, GL_RGBA, GL_UNSIGNED_BYTE, data);
+            }
+
+            // Frames are drawn into the third texture
+            (gl.glGenFramebuffers)(1, &mut transition.framebuffer);
+            (gl.glBindFramebuffer)(GL_FRAMEBUFFER, transition.framebuffer);
+            (gl.glFramebufferTexture2D)(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0, GL_TEXTURE_2D, textures[2], 0);
+            if (gl.glCheckFramebufferStatus)(GL_FRAMEBUFFER) != GL_FRAMEBUFFER_COMPLETE {
+                return Err("can't render into an RGBA texture".to_string());
+            }
+
+            (gl.glUseProgram)(self.program);
+            (gl.glViewport)(0, 0, width as i32, height as i32);
+            (gl.glActiveTexture)(GL_TEXTURE0);
+            (gl.glBindTexture)(GL_TEXTURE_2D, textures[0]);
+            (gl.glActiveTexture)(GL_TEXTURE0 + 1);
+            (gl.glBindTexture)(GL_TEXTURE_2D, textures[1]);
+            (gl.glUniform1i)(self.uniform("from_image"), 0);
+            (gl.glUniform1i)(self.uniform("to_image"), 1);
+            (gl.glUniform2f)(self.uniform("size"), width as f32, height as f32);
+            (gl.glVertexAttribPointer)(0, 2, GL_FLOAT, 0, 0, QUAD.as_ptr() as *const c_void);
+            (gl.glEnableVertexAttribArray)(0);
+            check_error(gl)?;
+            Ok(transition)
+        }
+    }
+
+    unsafe fn uniform(&self, name: &str) -> i32 {
+        let name = CString::new(name).unwrap();
+        (self.gl.glGetUniformLocation)(self.program, name.as_ptr())
+    }
+
+    unsafe fn make_current(&self) -> Result<(), String> {
+        if (self.egl.eglMakeCurrent)(self.display, self.surface, self.surface, self.context) == 0 {
+            return Err(format!("can't make the GLES context current (error {:#x})", (self.egl.eglGetError)()));
+        }
+        Ok(())
+    }
+
+    unsafe fn release(&self) {
+        (self.egl.eglMakeCurrent)(self.display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+    }
+}
+
+impl Drop for GlesRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.surface.is_null() {
+                (self.egl.eglDestroySurface)(self.display, self.surface);
+            }
+            if !self.context.is_null() {
+                (self.egl.eglDestroyContext)(self.display, self.context);
+            }
+            (self.egl.eglTerminate)(self.display);
+        }
+    }
+}
+
+// A transition's frames in GPU memory, with the context current
+pub struct Transition<'a> {
+    renderer: &'a GlesRenderer,
+    // From, to, and the frame being drawn
+    textures: [u32; 3],
+    framebuffer: u32,
+    width: u32,
+    height: u32,
+    // The shader effect the uniforms are set for
+    effect: Cell<Option<(i32, (f32, f32))>>,
+    // Released after drop() has let go of the context
+    _current: MutexGuard<'a, ()>,
+}
+
+impl Transition<'_> {
+    // The frame `progress` (already eased) of the way through `transition`
+    pub fn render(&self, transition: &TransitionType, progress: f32) -> Result<RgbaImage, String> {
+        let renderer = self.renderer;
+        let gl = &renderer.gl;
+        unsafe {
+            let (effect, direction) = shader_effect(transition);
+            if self.effect.get() != Some((effect, direction)) {
+                (gl.glUniform1i)(renderer.uniform("effect"), effect);
+                (gl.glUniform2f)(renderer.uniform("direction"), direction.0, direction.1);
+                self.effect.set(Some((effect, direction)));
+            }
+            (gl.glUniform1f)(renderer.uniform("progress"), progress);
+            (gl.glDrawArrays)(GL_TRIANGLE_STRIP, 0, 4);
+
+            let mut frame = RgbaImage::new(self.width, self.height);
+            (gl.glPixelStorei)(GL_PACK_ALIGNMENT, 1);
+            (gl.glReadPixels)(0, 0, self.width as i32, self.height as i32, GL_RGBA, GL_UNSIGNED_BYTE, frame.as_mut_ptr() as *mut c_void);
+            check_error(gl)?;
+            // GL rows run bottom to top
+            let row_len = self.width as usize * 4;
+            let rows: &mut [u8] = &mut frame;
+            for y in 0..self.height as usize / 2 {
+                let (top, bottom) = rows.split_at_mut((self.height as usize - 1 - y) * row_len);
+                top[y * row_len..(y + 1) * row_len].swap_with_slice(&mut bottom[..row_len]);
+            }
+            Ok(frame)
+        }
+    }
+}
+
+impl Drop for Transition<'_> {
+    fn drop(&mut self) {
+        let renderer = self.renderer;
+        unsafe {
+            (renderer.gl.glBindFramebuffer)(GL_FRAMEBUFFER, 0);
+            if self.framebuffer != 0 {
+                (renderer.gl.glDeleteFramebuffers)(1, &self.framebuffer);
+            }
+            (renderer.gl.glDeleteTextures)(3, self.textures.as_ptr());
+            renderer.release();
+        }
+    }
+}
+
+// The shader's effect number and direction for `transition`; the easing types are all fades
+// with their curve applied to the progress
+fn shader_effect(transition: &TransitionType) -> (i32, (f32, f32)) {
+    match transition {
+        TransitionType::SlideLeft => (1, (-1.0, 0.0)),
+        TransitionType::SlideRight => (1, (1.0, 0.0)),
+        TransitionType::SlideUp => (1, (0.0, -1.0)),
+        TransitionType::SlideDown => (1, (0.0, 1.0)),
+        TransitionType::WipeLeft => (2, (-1.0, 0.0)),
+        TransitionType::WipeRight => (2, (1.0, 0.0)),
+        TransitionType::WipeUp => (2, (0.0, -1.0)),
+        TransitionType::WipeDown => (2, (0.0, 1.0)),
+        TransitionType::CircularWipe => (3, (0.0, 0.0)),
+        TransitionType::DiagonalWipe => (4, (0.0, 0.0)),
+        TransitionType::Morph => (5, (0.0, 0.0)),
+        _ => (0, (0.0, 0.0)),
+    }
+}
+
+// The surfaceless platform where Mesa offers it, since a console Pi has no window system;
+// otherwise the default display (the Broadcom driver on older firmware)
+unsafe fn platform_display(egl: &Egl) -> Handle {
+    let get_platform_display = (egl.eglGetProcAddress)(c"eglGetPlatformDisplayEXT".as_ptr());
+    if !get_platform_display.is_null() {
+        let get_platform_display: unsafe extern "C" fn(u32, Handle, *const i32) -> Handle = std::mem::transmute(get_platform_display);
+        let display = get_platform_display(EGL_PLATFORM_SURFACELESS_MESA, ptr::null_mut(), ptr::null());
+        if !display.is_null() {
+            return display;
+        }
+    }
+    (egl.eglGetDisplay)(ptr::null_mut())
+}
+
+unsafe fn compile_shader(gl: &Gl, kind: u32, source: &str) -> Result<u32, String> {
+    let shader = (gl.glCreateShader)(kind);
+    let source = CString::new(source).unwrap();
+    (gl.glShaderSource)(shader, 1, &source.as_ptr(), ptr::null());
+    (gl.glCompileShader)(shader);
+    let mut compiled = 0;
+    (gl.glGetShaderiv)(shader, GL_COMPILE_STATUS, &mut compiled);
+    if compiled == 0 {
+        let mut log = [0 as c_char; 1024];
+        (gl.glGetShaderInfoLog)(shader, log.len() as i32, ptr::null_mut(), log.as_mut_ptr());
+        (gl.glDeleteShader)(shader);
+        return Err(format!("transition shader failed to compile: {}", CStr::from_ptr(log.as_ptr()).to_string_lossy()));
+    }
+    Ok(shader)
+}
+
+unsafe fn check_error(gl: &Gl) -> Result<(), String> {
+    match (gl.glGetError)() {
+        GL_NO_ERROR => Ok(()),
+        error => Err(format!("GL error {:#x}", error)),
+    }
+}
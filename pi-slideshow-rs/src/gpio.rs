@@ -0,0 +1,118 @@
+use rppal::gpio::{Gpio, InputPin, Trigger};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+use crate::mqtt_client::SlideshowCommand;
+use crate::slideshow_controller::SlideshowController;
+
+// Physical buttons on the Pi's GPIO header, for a kiosk that has to be driven without a
+// network. Each --gpio-button PIN:ACTION wires a button between the BCM pin and ground (the
+// pin's internal pull-up holds it high) and sends its command on the same channel as MQTT and
+// the HTTP API, so a press behaves exactly like the remote command. pause and hold toggle:
+// a second press plays or resumes.
+
+// Contact bounce on a cheap button lasts a few milliseconds; a deliberate double press is
+// far slower than this
+const DEBOUNCE: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonAction {
+    Next,
+    Previous,
+    Pause,
+    Hold,
+}
+
+impl ButtonAction {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "next" => Some(ButtonAction::Next),
+            "previous" | "prev" => Some(ButtonAction::Previous),
+            "pause" => Some(ButtonAction::Pause),
+            "hold" => Some(ButtonAction::Hold),
+            _ => None,
+        }
+    }
+
+    async fn command(&self, controller: &SlideshowController) -> SlideshowCommand {
+        match self {
+            ButtonAction::Next => SlideshowCommand::Next,
+            ButtonAction::Previous => SlideshowCommand::Previous,
+            ButtonAction::Pause if controller.is_playing().await => SlideshowCommand::Pause,
+            ButtonAction::Pause => SlideshowCommand::Play,
+            ButtonAction::Hold if controller.is_held().await => SlideshowCommand::Resume,
+            ButtonAction::Hold => SlideshowCommand::Hold,
+        }
+    }
+}
+
+// "17:next" -> BCM pin 17 sends next
+fn parse_spec(spec: &str) -> Result<(u8, ButtonAction), String> {
+    let (pin, action) = spec.split_once(':').ok_or("expected PIN:ACTION")?;
+    let pin = pin.trim().parse::<u8>().map_err(|_| format!("bad pin number '{}'", pin))?;
+    let action = ButtonAction::parse(&action.trim().to_lowercase())
+        .ok_or_else(|| format!("unknown action '{}' (next, previous, pause or hold)", action))?;
+    Ok((pin, action))
+}
+
+// Start watching the buttons given by --gpio-button. The returned pins must be kept alive:
+// dropping one stops its interrupt. A button that can't be set up is skipped rather than
+// stopping the slideshow. Must be called from within the runtime.
+pub fn spawn(
+    specs: &[String],
+    controller: SlideshowController,
+    command_sender: broadcast::Sender<SlideshowCommand>,
+) -> Vec<InputPin> {
+    if specs.is_empty() {
+        return Vec::new();
+    }
+    let gpio = match Gpio::new() {
+        Ok(gpio) => gpio,
+        Err(e) => {
+            warn!("GPIO unavailable, buttons are off: {}", e);
+            return Vec::new();
+        }
+    };
+    let runtime = tokio::runtime::Handle::current();
+
+    let mut pins = Vec::new();
+    for spec in specs {
+        let (number, action) = match parse_spec(spec) {
+            Ok(button) => button,
+            Err(e) => {
+                warn!("Skipping GPIO button {}: {}", spec, e);
+                continue;
+            }
+        };
+        let mut pin = match gpio.get(number) {
+            Ok(pin) => pin.into_input_pullup(),
+            Err(e) => {
+                warn!("Skipping GPIO button {}: {}", spec, e);
+                continue;
+            }
+        };
+
+        let controller = controller.clone();
+        let command_sender = command_sender.clone();
+        let runtime = runtime.clone();
+        // Interrupts arrive on rppal's own thread, which can't wait on the controller's locks
+        let watched = pin.set_async_interrupt(Trigger::FallingEdge, Some(DEBOUNCE), move |_| {
+            let controller = controller.clone();
+            let command_sender = command_sender.clone();
+            runtime.spawn(async move {
+                let command = action.command(&controller).await;
+                info!("🔘 GPIO {} pressed: {:?}", number, command);
+                let _ = command_sender.send(command);
+            });
+        });
+        match watched {
+            Ok(()) => {
+                info!("🔘 GPIO {} button sends {:?}", number, action);
+                pins.push(pin);
+            }
+            Err(e) => warn!("Skipping GPIO button {}: {}", spec, e),
+        }
+    }
+    pins
+}
@@ -0,0 +1,133 @@
+use signage_protocol::{PiHardware, SdCardHealth};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::OnceCell;
+
+// The Pi's side of the heartbeat's system metrics, for triage across the fleet: the GPU/ARM
+// memory split and the firmware build (read once, as they only change with a reboot), the core
+// voltage, and what little an SD card gives away about its health. A card that reports wear
+// estimates has them in sysfs; otherwise errors recorded by ext4 and a card that has turned
+// itself read-only are the usual signs, and a large amount written since boot points at what is
+// wearing it out. All of it comes from sysfs rather than /proc/mounts, which under the unit's
+// ProtectSystem=strict shows / read-only whatever the card's state. Off a Pi vcgencmd is missing and only the card, if any, is reported.
+
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+const SD_CARD: &str = "/sys/block/mmcblk0";
+const EXT4: &str = "/sys/fs/ext4";
+
+// Settings that don't change while the Pi is up; None when vcgencmd isn't there
+static FIXED: OnceCell<Option<Fixed>> = OnceCell::const_new();
+static INFO: Mutex<Option<PiHardware>> = Mutex::new(None);
+
+#[derive(Clone)]
+struct Fixed {
+    gpu_memory_mb: Option<u32>,
+    arm_memory_mb: Option<u32>,
+    firmware_version: Option<String>,
+    firmware_date: Option<String>,
+}
+
+// Read everything again; called before each heartbeat
+pub async fn refresh() {
+    let fixed = FIXED.get_or_init(read_fixed).await.clone();
+    let core_volts = match fixed {
+        Some(_) => vcgencmd(&["measure_volts", "core"]).await.as_deref().and_then(parse_volts),
+        None => None,
+    };
+    let sd_card = sd_card_health();
+    let info = match fixed {
+        Some(fixed) => Some(PiHardware {
+            gpu_memory_mb: fixed.gpu_memory_mb,
+            arm_memory_mb: fixed.arm_memory_mb,
+            core_volts,
+            firmware_version: fixed.firmware_version,
+            firmware_date: fixed.firmware_date,
+            sd_card,
+        }),
+        None => sd_card.map(|card| PiHardware { sd_card: Some(card), ..PiHardware::default() }),
+    };
+    *INFO.lock().unwrap() = info;
+}
+
+// As of the last refresh, None where there is nothing to tell
+pub fn info() -> Option<PiHardware> {
+    INFO.lock().unwrap().clone()
+}
+
+async fn read_fixed() -> Option<Fixed> {
+    let version = vcgencmd(&["version"]).await?;
+    let (firmware_date, firmware_version) = parse_version(&version);
+    Some(Fixed {
+        gpu_memory_mb: vcgencmd(&["get_mem", "gpu"]).await.as_deref().and_then(parse_memory),
+        arm_memory_mb: vcgencmd(&["get_mem", "arm"]).await.as_deref().and_then(parse_memory),
+        firmware_version,
+        firmware_date,
+    })
+}
+
+// stdout of a successful vcgencmd
+async fn vcgencmd(args: &[&str]) -> Option<String> {
+    let output = tokio::time::timeout(COMMAND_TIMEOUT, Command::new("vcgencmd").args(args).kill_on_drop(true).output())
+        .await
+        .ok()?
+        .ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// "Mar 17 2023 10:52:42\nCopyright (c) 2012 Broadcom\nversion 82f3750a65fa... (clean) (release) (start)"
+fn parse_version(output: &str) -> (Option<String>, Option<String>) {
+    let date = output.lines().next().map(|line| line.trim().to_string()).filter(|line| !line.is_empty());
+    let version = output.lines()
+        .find_map(|line| line.trim().strip_prefix("version "))
+        .and_then(|rest| rest.split_whitespace().next())
+        .map(|hash| hash.to_string());
+    (date, version)
+}
+
+// "gpu=76M"
+fn parse_memory(output: &str) -> Option<u32> {
+    let (_, value) = output.trim().split_once('=')?;
+    value.trim_end_matches('M').parse().ok()
+}
+
+// "volt=0.8500V"
+fn parse_volts(output: &str) -> Option<f32> {
+    let (_, value) = output.trim().split_once('=')?;
+    value.trim_end_matches('V').parse().ok()
+}
+
+fn sd_card_health() -> Option<SdCardHealth> {
+    let card = Path::new(SD_CARD);
+    if !card.exists() {
+        return None;
+    }
+    let attribute = |name: &str| {
+        fs::read_to_string(card.join("device").join(name)).ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    // /sys/block/*/stat: the seventh field is sectors written
+    let sectors_written = fs::read_to_string(card.join("stat")).ok()
+        .and_then(|stat| stat.split_whitespace().nth(6).and_then(|field| field.parse().ok()));
+    Some(SdCardHealth {
+        name: attribute("name"),
+        manufactured: attribute("date"),
+        life_time: attribute("life_time"),
+        pre_eol: attribute("pre_eol_info"),
+        read_only: fs::read_to_string(card.join("ro")).is_ok_and(|ro| ro.trim() == "1"),
+        filesystem_errors: filesystem_errors(),
+        sectors_written,
+    })
+}
+
+// Summed over the card's mounted ext4 partitions (mmcblk0p2, ...), None when none is
+fn filesystem_errors() -> Option<u64> {
+    let partitions = fs::read_dir(EXT4).ok()?;
+    partitions.flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("mmcblk0p"))
+        .filter_map(|entry| fs::read_to_string(entry.path().join("errors_count")).ok()?.trim().parse::<u64>().ok())
+        .fold(None, |total, errors| Some(total.unwrap_or(0) + errors))
+}
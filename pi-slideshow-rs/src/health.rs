@@ -0,0 +1,167 @@
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Subsystem state for GET /api/health. Each subsystem records its own outcome here as it
+// runs, so a health check never has to wait on MQTT, CouchDB or the display thread. Times are
+// kept twice: on the clock (which a time_sync moves) for showing when something happened, and
+// as an Instant for how long ago, so ages don't jump when the clock is corrected.
+
+static FRAMEBUFFER_DEVICE: AtomicBool = AtomicBool::new(false);
+static FRAMEBUFFER_WRITE_OK: AtomicBool = AtomicBool::new(true);
+static MQTT_CONNECTED: AtomicBool = AtomicBool::new(false);
+static COUCHDB_REACHABLE: AtomicBool = AtomicBool::new(false);
+// Unix milliseconds (clock::now) of the last successful CouchDB sync, 0 if none yet
+static LAST_SYNC_MS: AtomicI64 = AtomicI64::new(0);
+static LAST_SYNC_AT: Mutex<Option<Instant>> = Mutex::new(None);
+// Size of the slides in the image directory after the last sync, and its cap (0 for none)
+static IMAGE_DIR_BYTES: AtomicU64 = AtomicU64::new(0);
+static IMAGE_DIR_LIMIT_BYTES: AtomicU64 = AtomicU64::new(0);
+// Space kept free on the image directory's filesystem (--min-free-disk-mb), and whether the
+// last attempt to get there failed, leaving downloads refused
+static MIN_FREE_DISK_BYTES: AtomicU64 = AtomicU64::new(0);
+static DISK_LOW: AtomicBool = AtomicBool::new(false);
+// Unix milliseconds since which neither MQTT nor CouchDB has been reachable, 0 while either is
+static OFFLINE_SINCE_MS: AtomicI64 = AtomicI64::new(0);
+static OFFLINE_SINCE_AT: Mutex<Option<Instant>> = Mutex::new(None);
+
+pub struct DiskSpace {
+    pub available_bytes: u64,
+    pub total_bytes: u64,
+}
+
+// false when the device could not be opened and frames go to framebuffer_output.raw
+pub fn set_framebuffer_device(opened: bool) {
+    FRAMEBUFFER_DEVICE.store(opened, Ordering::Relaxed);
+}
+
+pub fn record_framebuffer_write(ok: bool) {
+    FRAMEBUFFER_WRITE_OK.store(ok, Ordering::Relaxed);
+}
+
+pub fn set_mqtt_connected(connected: bool) {
+    MQTT_CONNECTED.store(connected, Ordering::Relaxed);
+    track_offline();
+}
+
+pub fn record_couchdb_sync(ok: bool) {
+    COUCHDB_REACHABLE.store(ok, Ordering::Relaxed);
+    if ok {
+        LAST_SYNC_MS.store(crate::clock::now().timestamp_millis(), Ordering::Relaxed);
+        *LAST_SYNC_AT.lock().unwrap() = Some(Instant::now());
+    }
+    track_offline();
+}
+
+fn track_offline() {
+    let mut offline_since = OFFLINE_SINCE_AT.lock().unwrap();
+    if mqtt_connected() || couchdb_reachable() {
+        OFFLINE_SINCE_MS.store(0, Ordering::Relaxed);
+        *offline_since = None;
+    } else if offline_since.is_none() {
+        OFFLINE_SINCE_MS.store(crate::clock::now().timestamp_millis(), Ordering::Relaxed);
+        *offline_since = Some(Instant::now());
+    }
+}
+
+pub fn record_image_dir_usage(bytes: u64, limit_bytes: u64) {
+    IMAGE_DIR_BYTES.store(bytes, Ordering::Relaxed);
+    IMAGE_DIR_LIMIT_BYTES.store(limit_bytes, Ordering::Relaxed);
+}
+
+pub fn set_min_free_disk_bytes(bytes: u64) {
+    MIN_FREE_DISK_BYTES.store(bytes, Ordering::Relaxed);
+}
+
+pub fn min_free_disk_bytes() -> u64 {
+    MIN_FREE_DISK_BYTES.load(Ordering::Relaxed)
+}
+
+// Returns whether the state changed
+pub fn set_disk_low(low: bool) -> bool {
+    DISK_LOW.swap(low, Ordering::Relaxed) != low
+}
+
+pub fn disk_low() -> bool {
+    DISK_LOW.load(Ordering::Relaxed)
+}
+
+pub fn framebuffer_device() -> bool {
+    FRAMEBUFFER_DEVICE.load(Ordering::Relaxed)
+}
+
+pub fn framebuffer_write_ok() -> bool {
+    FRAMEBUFFER_WRITE_OK.load(Ordering::Relaxed)
+}
+
+pub fn mqtt_connected() -> bool {
+    MQTT_CONNECTED.load(Ordering::Relaxed)
+}
+
+pub fn couchdb_reachable() -> bool {
+    COUCHDB_REACHABLE.load(Ordering::Relaxed)
+}
+
+pub fn last_sync() -> Option<chrono::DateTime<chrono::Utc>> {
+    match LAST_SYNC_MS.load(Ordering::Relaxed) {
+        0 => None,
+        ms => chrono::DateTime::from_timestamp_millis(ms),
+    }
+}
+
+pub fn last_sync_age() -> Option<Duration> {
+    LAST_SYNC_AT.lock().unwrap().map(|at| at.elapsed())
+}
+
+// When the endpoint lost both the broker and CouchDB, None while it can reach either
+pub fn offline_since() -> Option<chrono::DateTime<chrono::Utc>> {
+    match OFFLINE_SINCE_MS.load(Ordering::Relaxed) {
+        0 => None,
+        ms => chrono::DateTime::from_timestamp_millis(ms),
+    }
+}
+
+pub fn offline_for() -> Option<Duration> {
+    OFFLINE_SINCE_AT.lock().unwrap().map(|since| since.elapsed())
+}
+
+pub fn image_dir_usage() -> (u64, u64) {
+    (IMAGE_DIR_BYTES.load(Ordering::Relaxed), IMAGE_DIR_LIMIT_BYTES.load(Ordering::Relaxed))
+}
+
+// Space on the filesystem holding `path`, as seen by an unprivileged process
+pub fn disk_space(path: &Path) -> Option<DiskSpace> {
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+
+    let block_size = stat.f_frsize as u64;
+    Some(DiskSpace {
+        available_bytes: stat.f_bavail as u64 * block_size,
+        total_bytes: stat.f_blocks as u64 * block_size,
+    })
+}
+
+// CPU temperature in °C, where the kernel exposes one
+pub fn cpu_temperature() -> Option<f32> {
+    // Try Raspberry Pi thermal zone first
+    if let Ok(temp_str) = std::fs::read_to_string("/sys/class/thermal/thermal_zone0/temp") {
+        if let Ok(temp_millidegrees) = temp_str.trim().parse::<f32>() {
+            return Some(temp_millidegrees / 1000.0);
+        }
+    }
+
+    // Try alternative thermal sources
+    if let Ok(temp_str) = std::fs::read_to_string("/sys/devices/virtual/thermal/thermal_zone0/temp") {
+        if let Ok(temp_millidegrees) = temp_str.trim().parse::<f32>() {
+            return Some(temp_millidegrees / 1000.0);
+        }
+    }
+
+    None
+}
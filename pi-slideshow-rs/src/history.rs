@@ -0,0 +1,78 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+use sysinfo::{CpuExt, System, SystemExt};
+
+use crate::clock;
+use crate::health;
+use crate::perf;
+
+// A day of the device's vital signs at one-minute resolution, for GET /api/metrics/history: CPU
+// use over the minute, memory in use, the CPU temperature and the frame rate of the transitions
+// that played. Enough to see whether a TV gets hot every afternoon or started struggling after
+// a playlist change without setting up a monitoring stack. Kept in memory, so a restart starts
+// the history over.
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+// 24 hours of samples
+const MAX_SAMPLES: usize = 24 * 60;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Sample {
+    pub timestamp: String,
+    // Percent, averaged over all cores and the minute before the sample
+    pub cpu_usage: f32,
+    pub memory_usage: f32,
+    pub temperature: Option<f32>,
+    // Average of the transitions that finished in the minute, None when none did
+    pub transition_fps: Option<f32>,
+}
+
+static SAMPLES: Mutex<VecDeque<Sample>> = Mutex::new(VecDeque::new());
+
+pub fn spawn() {
+    tokio::spawn(async move {
+        let mut system = System::new();
+        // CPU use is measured between refreshes, so the first one only sets the baseline
+        system.refresh_cpu();
+        let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            system.refresh_cpu();
+            system.refresh_memory();
+            let cpus = system.cpus();
+            let cpu_usage = cpus.iter().map(|cpu| cpu.cpu_usage()).sum::<f32>() / cpus.len().max(1) as f32;
+            let memory_usage = if system.total_memory() > 0 {
+                system.used_memory() as f32 / system.total_memory() as f32 * 100.0
+            } else {
+                0.0
+            };
+            let sample = Sample {
+                timestamp: clock::timestamp(),
+                cpu_usage,
+                memory_usage,
+                temperature: health::cpu_temperature(),
+                transition_fps: perf::take_transition_fps(),
+            };
+
+            let mut samples = SAMPLES.lock().unwrap();
+            if samples.len() == MAX_SAMPLES {
+                samples.pop_front();
+            }
+            samples.push_back(sample);
+        }
+    });
+}
+
+// The samples of the last `minutes`, or all of them, oldest first
+pub fn recent(minutes: Option<usize>) -> Vec<Sample> {
+    let samples = SAMPLES.lock().unwrap();
+    let skip = minutes.map_or(0, |minutes| samples.len().saturating_sub(minutes));
+    samples.iter().skip(skip).cloned().collect()
+}
+
+pub fn interval_secs() -> u64 {
+    SAMPLE_INTERVAL.as_secs()
+}
@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+// Watches DRM connector status (/sys/class/drm/card*-*/status) for displays coming back.
+// Polling sysfs needs no udev and also catches a TV that was power-cycled with the cable
+// left in, as long as the driver sees the hotplug line drop.
+
+const DRM_CLASS: &str = "/sys/class/drm";
+
+pub struct HotplugMonitor {
+    // Connector directory -> connected at the last poll
+    connectors: HashMap<PathBuf, bool>,
+}
+
+impl HotplugMonitor {
+    pub fn new() -> Self {
+        let mut monitor = Self { connectors: HashMap::new() };
+        monitor.poll();
+        monitor
+    }
+
+    // False without DRM (legacy firmware driver, non-Pi hosts); polling is then pointless
+    pub fn is_available(&self) -> bool {
+        !self.connectors.is_empty()
+    }
+
+    // Connectors (e.g. "card1-HDMI-A-1") that went from disconnected to connected since the last poll
+    pub fn poll(&mut self) -> Vec<String> {
+        let mut reconnected = Vec::new();
+        for (path, connected) in read_connectors() {
+            let was_connected = self.connectors.insert(path.clone(), connected);
+            if connected && was_connected == Some(false) {
+                reconnected.push(path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default());
+            }
+        }
+        reconnected
+    }
+}
+
+fn read_connectors() -> Vec<(PathBuf, bool)> {
+    let entries = match fs::read_dir(DRM_CLASS) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        // Connectors are named card<N>-<type>-<index>; plain card<N> and renderD* are devices
+        .filter(|path| path.file_name().is_some_and(|name| name.to_string_lossy().contains('-')))
+        .filter_map(|path| {
+            let status = fs::read_to_string(path.join("status")).ok()?;
+            Some((path, status.trim() == "connected"))
+        })
+        .collect()
+}
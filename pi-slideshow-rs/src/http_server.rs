@@ -1,11 +1,36 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::convert::Infallible;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, OwnedSemaphorePermit};
+use warp::http::StatusCode;
 use warp::{reply, Filter, Rejection};
+use tracing::{error, info};
 
-use crate::mqtt_client::SlideshowCommand;
+use signage_protocol::couchdb::{parse_hex_color, parse_time_of_day, parse_weekday, MAX_SAFE_AREA_MARGIN};
+use signage_protocol::mqtt::is_safe_file_component;
+use signage_protocol::{DimmingWindow, QuietWindow, SafeArea, SlideshowConfig};
+
+use crate::cec;
+use crate::clock;
+use crate::color;
+use crate::downloads;
+use crate::health;
+use crate::history;
+use crate::logging;
+use crate::motion;
+use crate::perf;
+use crate::easing;
+use crate::ken_burns;
+use crate::quality;
+use crate::mqtt_client::{SlideshowCommand, DEFAULT_WAKE_MINUTES, MAX_EXTEND_SECONDS, MAX_PAUSE_MINUTES};
+use crate::rate_limit::RateLimiter;
 use crate::slideshow_controller::SlideshowController;
+use crate::test_pattern::TestPattern;
+use crate::text_profile::TextProfile;
+use crate::transitions;
+use crate::{Letterbox, Orientation, TransitionType};
 
 #[derive(Debug)]
 struct ControlError(#[allow(dead_code)] String);
@@ -15,6 +40,60 @@ impl warp::reject::Reject for ControlError {}
 struct ConfigError(#[allow(dead_code)] String);
 impl warp::reject::Reject for ConfigError {}
 
+#[derive(Debug)]
+struct RateLimited;
+impl warp::reject::Reject for RateLimited {}
+
+#[derive(Debug)]
+struct Busy;
+impl warp::reject::Reject for Busy {}
+
+// Limits for the routes that push commands onto the slideshow channel, so a misbehaving
+// dashboard can't queue up enough commands to make the slideshow skip slides
+const MAX_BODY_BYTES: u64 = 16 * 1024;
+const CONTROL_PER_SECOND: f64 = 2.0;
+const CONTROL_BURST: u32 = 5;
+const CONFIG_PER_SECOND: f64 = 1.0;
+const CONFIG_BURST: u32 = 3;
+const PREVIEW_PER_SECOND: f64 = 0.5;
+const PREVIEW_BURST: u32 = 2;
+// Requests each route handles at once, so slow ones can't pile up behind a stalled channel
+const CONTROL_IN_FLIGHT: usize = 4;
+const CONFIG_IN_FLIGHT: usize = 1;
+const PREVIEW_IN_FLIGHT: usize = 1;
+
+// CouchDB is synced every 5 minutes; missing three syncs in a row counts as stale
+const SYNC_STALE_AFTER_SECS: i64 = 15 * 60;
+
+struct HttpMetrics {
+    control: Arc<RateLimiter>,
+    config: Arc<RateLimiter>,
+    preview_transition: Arc<RateLimiter>,
+    payload_too_large: AtomicU64,
+}
+
+impl HttpMetrics {
+    fn new() -> Self {
+        Self {
+            control: Arc::new(RateLimiter::new(CONTROL_PER_SECOND, CONTROL_BURST, CONTROL_IN_FLIGHT)),
+            config: Arc::new(RateLimiter::new(CONFIG_PER_SECOND, CONFIG_BURST, CONFIG_IN_FLIGHT)),
+            preview_transition: Arc::new(RateLimiter::new(PREVIEW_PER_SECOND, PREVIEW_BURST, PREVIEW_IN_FLIGHT)),
+            payload_too_large: AtomicU64::new(0),
+        }
+    }
+
+    fn snapshot(&self) -> serde_json::Value {
+        serde_json::json!({
+            "routes": {
+                "control": self.control.snapshot(),
+                "config": self.config.snapshot(),
+                "preview_transition": self.preview_transition.snapshot(),
+            },
+            "payload_too_large": self.payload_too_large.load(Ordering::Relaxed),
+        })
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 struct ApiResponse<T> {
     success: bool,
@@ -27,11 +106,68 @@ struct ControlRequest {
     action: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct PauseQuery {
+    minutes: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct HistoryQuery {
+    minutes: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtendQuery {
+    seconds: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct PreviewTransitionRequest {
+    transition: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LogLevelRequest {
+    // As --log-level: a level or a RUST_LOG-style filter
+    level: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TestPatternRequest {
+    // bars, grid, overscan or off; bars when omitted
+    pattern: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct ConfigRequest {
     display_duration: Option<u64>,
     transition_duration: Option<u64>,
     transition_effect: Option<String>,
+    orientation: Option<String>,
+    content_safety: Option<bool>,
+    text_profile: Option<String>,
+    framebuffer_refresh_secs: Option<u64>,
+    skip_redundant_writes: Option<bool>,
+    gamma: Option<f64>,
+    color_temperature: Option<u32>,
+    brightness: Option<u8>,
+    dimming_schedule: Option<Vec<DimmingWindow>>,
+    quiet_hours: Option<Vec<QuietWindow>>,
+    curtain_hours: Option<Vec<QuietWindow>>,
+    curtain_color: Option<String>,
+    curtain_image: Option<String>,
+    letterbox_fill: Option<String>,
+    letterbox_color: Option<String>,
+    letterbox_image: Option<String>,
+    safe_area: Option<SafeArea>,
+    ken_burns: Option<String>,
+    ken_burns_intensity: Option<f64>,
+    smart_crop: Option<bool>,
+    transition_easing: Option<String>,
+    transition_durations: Option<HashMap<String, u64>>,
+    shuffle: Option<bool>,
+    ticker_rss_url: Option<String>,
+    tags: Option<Vec<String>>,
 }
 
 impl<T> ApiResponse<T> {
@@ -43,6 +179,52 @@ impl<T> ApiResponse<T> {
         }
     }
 
+    fn error(message: &str) -> Self {
+        Self {
+            success: false,
+            data: None,
+            message: message.to_string(),
+        }
+    }
+}
+
+// Admits a request within the route's rate and in-flight limits. The handler keeps the
+// permit until its reply is ready, which frees the slot for the next request.
+fn rate_limit(limiter: Arc<RateLimiter>) -> impl Filter<Extract = (OwnedSemaphorePermit,), Error = Rejection> + Clone {
+    warp::any().and_then(move || {
+        let limiter = limiter.clone();
+        async move {
+            let Some(permit) = limiter.try_enter() else {
+                return Err(warp::reject::custom(Busy));
+            };
+            if limiter.try_acquire() {
+                Ok(permit)
+            } else {
+                Err(warp::reject::custom(RateLimited))
+            }
+        }
+    })
+}
+
+async fn handle_rejection(err: Rejection, metrics: Arc<HttpMetrics>) -> Result<impl warp::Reply, Rejection> {
+    if err.find::<RateLimited>().is_some() {
+        let body = reply::json(&ApiResponse::<()>::error("Too many requests, slow down"));
+        let body = reply::with_header(body, "retry-after", "1");
+        return Ok(reply::with_status(body, StatusCode::TOO_MANY_REQUESTS));
+    }
+
+    if err.find::<Busy>().is_some() {
+        let body = reply::json(&ApiResponse::<()>::error("Busy with earlier requests, try again"));
+        let body = reply::with_header(body, "retry-after", "1");
+        return Ok(reply::with_status(body, StatusCode::SERVICE_UNAVAILABLE));
+    }
+
+    if err.find::<warp::reject::PayloadTooLarge>().is_some() {
+        metrics.payload_too_large.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Everything else keeps warp's default handling
+    Err(err)
 }
 
 pub async fn run_http_server(
@@ -52,12 +234,25 @@ pub async fn run_http_server(
 ) {
     let controller = Arc::new(controller);
     let command_sender = Arc::new(command_sender);
+    let metrics = Arc::new(HttpMetrics::new());
 
     // Health check endpoint
+    let health_controller = controller.clone();
     let health = warp::path("health")
         .and(warp::get())
-        .map(|| {
-            reply::json(&ApiResponse::success("healthy", "TV endpoint is running"))
+        .and_then(move || {
+            let controller = health_controller.clone();
+            async move {
+                let (verdict, report) = get_health_report(&controller).await;
+                // Degraded endpoints still show content, so only an unhealthy one fails the check
+                let status = if verdict == "unhealthy" { StatusCode::SERVICE_UNAVAILABLE } else { StatusCode::OK };
+                let response = ApiResponse {
+                    success: status == StatusCode::OK,
+                    data: Some(report),
+                    message: format!("TV endpoint is {}", verdict),
+                };
+                Ok::<_, Infallible>(reply::with_status(reply::json(&response), status))
+            }
         });
 
     // Version endpoint
@@ -90,10 +285,13 @@ pub async fn run_http_server(
     let control_sender = command_sender.clone();
     let control = warp::path("control")
         .and(warp::post())
+        .and(rate_limit(metrics.control.clone()))
+        .and(warp::body::content_length_limit(MAX_BODY_BYTES))
         .and(warp::body::json::<ControlRequest>())
-        .and_then(move |req: ControlRequest| {
+        .and_then(move |permit: OwnedSemaphorePermit, req: ControlRequest| {
             let sender = control_sender.clone();
             async move {
+                let _permit = permit;
                 match handle_control_request(req, &sender).await {
                     Ok(msg) => Ok::<_, Rejection>(warp::reply::json(&ApiResponse::success((), &msg))),
                     Err(e) => Err(warp::reject::custom(ControlError(e))),
@@ -101,14 +299,51 @@ pub async fn run_http_server(
             }
         });
 
+    // Timed pause endpoint
+    let pause_sender = command_sender.clone();
+    let pause = warp::path("pause")
+        .and(warp::post())
+        .and(rate_limit(metrics.control.clone()))
+        .and(warp::query::<PauseQuery>())
+        .and_then(move |permit: OwnedSemaphorePermit, query: PauseQuery| {
+            let sender = pause_sender.clone();
+            async move {
+                let _permit = permit;
+                match handle_pause_request(query, &sender).await {
+                    Ok(msg) => Ok::<_, Rejection>(warp::reply::json(&ApiResponse::success((), &msg))),
+                    Err(e) => Err(warp::reject::custom(ControlError(e))),
+                }
+            }
+        });
+
+    // Lengthen or shorten the current slide
+    let extend_sender = command_sender.clone();
+    let extend = warp::path("extend")
+        .and(warp::post())
+        .and(rate_limit(metrics.control.clone()))
+        .and(warp::query::<ExtendQuery>())
+        .and_then(move |permit: OwnedSemaphorePermit, query: ExtendQuery| {
+            let sender = extend_sender.clone();
+            async move {
+                let _permit = permit;
+                match handle_extend_request(query, &sender).await {
+                    Ok(msg) => Ok::<_, Rejection>(warp::reply::json(&ApiResponse::success((), &msg))),
+                    Err(e) => Err(warp::reject::custom(ControlError(e))),
+                }
+            }
+        });
+
     // Config endpoint
     let config_sender = command_sender.clone();
     let config = warp::path("config")
         .and(warp::put())
+        .and(rate_limit(metrics.config.clone()))
+        .and(warp::body::content_length_limit(MAX_BODY_BYTES))
         .and(warp::body::json::<ConfigRequest>())
-        .and_then(move |req: ConfigRequest| {
+        .and_then(move |permit: OwnedSemaphorePermit, req: ConfigRequest| {
             let sender = config_sender.clone();
             async move {
+                let _permit = permit;
                 match handle_config_request(req, &sender).await {
                     Ok(msg) => Ok::<_, Rejection>(warp::reply::json(&ApiResponse::success((), &msg))),
                     Err(e) => Err(warp::reject::custom(ConfigError(e))),
@@ -116,6 +351,68 @@ pub async fn run_http_server(
             }
         });
 
+    // Transition preview endpoint
+    let preview_sender = command_sender.clone();
+    let preview_transition = warp::path("preview_transition")
+        .and(warp::post())
+        .and(rate_limit(metrics.preview_transition.clone()))
+        .and(warp::body::content_length_limit(MAX_BODY_BYTES))
+        .and(warp::body::json::<PreviewTransitionRequest>())
+        .and_then(move |permit: OwnedSemaphorePermit, req: PreviewTransitionRequest| {
+            let sender = preview_sender.clone();
+            async move {
+                let _permit = permit;
+                match handle_preview_transition_request(req, &sender).await {
+                    Ok(msg) => Ok::<_, Rejection>(warp::reply::json(&ApiResponse::success((), &msg))),
+                    Err(e) => Err(warp::reject::custom(ControlError(e))),
+                }
+            }
+        });
+
+    // Test pattern endpoint
+    let test_pattern_sender = command_sender.clone();
+    let test_pattern = warp::path("test_pattern")
+        .and(warp::post())
+        .and(rate_limit(metrics.control.clone()))
+        .and(warp::body::content_length_limit(MAX_BODY_BYTES))
+        .and(warp::body::json::<TestPatternRequest>())
+        .and_then(move |permit: OwnedSemaphorePermit, req: TestPatternRequest| {
+            let sender = test_pattern_sender.clone();
+            async move {
+                let _permit = permit;
+                match handle_test_pattern_request(req, &sender).await {
+                    Ok(msg) => Ok::<_, Rejection>(warp::reply::json(&ApiResponse::success((), &msg))),
+                    Err(e) => Err(warp::reject::custom(ControlError(e))),
+                }
+            }
+        });
+
+    // Log level endpoints
+    let get_log_level = warp::path("log_level")
+        .and(warp::get())
+        .map(|| reply::json(&ApiResponse::success(serde_json::json!({ "level": logging::level() }), "Log level retrieved")));
+    let set_log_level = warp::path("log_level")
+        .and(warp::put())
+        .and(rate_limit(metrics.config.clone()))
+        .and(warp::body::content_length_limit(MAX_BODY_BYTES))
+        .and(warp::body::json::<LogLevelRequest>())
+        .and_then(|_permit: OwnedSemaphorePermit, req: LogLevelRequest| async move {
+            match logging::set_level(&req.level) {
+                Ok(()) => Ok::<_, Rejection>(warp::reply::json(&ApiResponse::success((), &format!("Logging at {}", req.level.trim())))),
+                Err(e) => Err(warp::reject::custom(ConfigError(e))),
+            }
+        });
+    let log_level = get_log_level.or(set_log_level);
+
+    // Current image file endpoint (must come before the image list route, which matches any /images path)
+    let raw_controller = controller.clone();
+    let current_image_raw = warp::path!("images" / "current" / "raw")
+        .and(warp::get())
+        .and_then(move || {
+            let controller = raw_controller.clone();
+            async move { serve_current_image(&controller).await }
+        });
+
     // Images endpoint
     let images_controller = controller.clone();
     let images = warp::path("images")
@@ -128,9 +425,34 @@ pub async fn run_http_server(
             }
         });
 
+    // Minute-by-minute samples of the last day
+    let metrics_history = warp::path!("metrics" / "history")
+        .and(warp::get())
+        .and(warp::query::<HistoryQuery>())
+        .map(|query: HistoryQuery| {
+            let history = serde_json::json!({
+                "interval_secs": history::interval_secs(),
+                "samples": history::recent(query.minutes),
+            });
+            reply::json(&ApiResponse::success(history, "Metrics history retrieved"))
+        });
+
+    // Metrics endpoint
+    let metrics_snapshot = metrics.clone();
+    let metrics_route = warp::path("metrics")
+        .and(warp::get())
+        .map(move || {
+            let http = metrics_snapshot.snapshot();
+            reply::json(&ApiResponse::success(serde_json::json!({ "http": http }), "Metrics retrieved"))
+        });
+
     // Combine all routes
+    let rejection_metrics = metrics.clone();
     let api = warp::path("api")
-        .and(health.or(version).or(status).or(control).or(config).or(images))
+        .and(
+            health.or(version).or(status).or(control).or(pause).or(extend).or(config).or(preview_transition).or(test_pattern).or(log_level).or(current_image_raw).or(images).or(metrics_history).or(metrics_route)
+                .recover(move |err| handle_rejection(err, rejection_metrics.clone()))
+        )
         .with(warp::cors().allow_any_origin().allow_headers(vec!["content-type"]).allow_methods(vec!["GET", "POST", "PUT"]));
 
     // Root endpoint
@@ -144,12 +466,20 @@ pub async fn run_http_server(
                 <h1>Digital Signage TV Endpoint</h1>
                 <p>API endpoints:</p>
                 <ul>
-                <li>GET /api/health - Health check</li>
+                <li>GET /api/health - Subsystem health (503 when the display is failing)</li>
                 <li>GET /api/version - Version information</li>
                 <li>GET /api/status - Get TV status</li>
-                <li>POST /api/control - Control slideshow (play, pause, next, previous)</li>
+                <li>POST /api/control - Control slideshow (play, pause, next, previous, wake, restart)</li>
+                <li>POST /api/pause?minutes=30 - Pause on the current slide, resuming automatically</li>
+                <li>POST /api/extend?seconds=120 - Keep the current slide up longer (negative to cut it short)</li>
                 <li>PUT /api/config - Update configuration</li>
+                <li>POST /api/preview_transition - Play one transition to the next image</li>
+                <li>POST /api/test_pattern - Show a calibration screen (bars, grid, overscan) or turn it off</li>
+                <li>GET/PUT /api/log_level - Get or change the log level until the next restart</li>
                 <li>GET /api/images - Get image list</li>
+                <li>GET /api/images/current/raw - Download the image file currently on screen</li>
+                <li>GET /api/metrics - Request and rate limit counters</li>
+                <li>GET /api/metrics/history?minutes=60 - CPU, memory, temperature and transition FPS each minute over the last day</li>
                 </ul>
                 </body>
                 </html>
@@ -159,20 +489,100 @@ pub async fn run_http_server(
 
     let routes = root.or(api);
 
-    println!("Starting HTTP server on port {}", port);
+    info!("Starting HTTP server on port {}", port);
     warp::serve(routes)
         .run(([0, 0, 0, 0], port))
         .await;
 }
 
+// Overall verdict plus per-subsystem detail. "unhealthy" means nothing is reaching the
+// screen; "degraded" means the slideshow runs but MQTT, CouchDB or disk need attention.
+async fn get_health_report(controller: &SlideshowController) -> (&'static str, serde_json::Value) {
+    let framebuffer_ok = health::framebuffer_device() && health::framebuffer_write_ok();
+    let mqtt_connected = health::mqtt_connected();
+    let couchdb_reachable = health::couchdb_reachable();
+
+    let last_sync = health::last_sync();
+    let sync_age_secs = health::last_sync_age().map(|age| age.as_secs() as i64);
+    let sync_fresh = sync_age_secs.is_some_and(|age| age <= SYNC_STALE_AFTER_SECS);
+
+    let disk = health::disk_space(&controller.get_image_dir().await);
+    let disk_ok = disk.as_ref().is_some_and(|d| d.available_bytes >= health::min_free_disk_bytes());
+
+    let verdict = if !framebuffer_ok {
+        "unhealthy"
+    } else if mqtt_connected && couchdb_reachable && sync_fresh && disk_ok {
+        "healthy"
+    } else {
+        "degraded"
+    };
+
+    let report = serde_json::json!({
+        "status": verdict,
+        "checks": {
+            "framebuffer": {
+                "ok": framebuffer_ok,
+                "device_open": health::framebuffer_device(),
+                "last_write_ok": health::framebuffer_write_ok()
+            },
+            "mqtt": {
+                "ok": mqtt_connected
+            },
+            "couchdb": {
+                "ok": couchdb_reachable && sync_fresh,
+                "reachable": couchdb_reachable,
+                "last_sync": last_sync.map(|at| at.to_rfc3339()),
+                "last_sync_age_seconds": sync_age_secs
+            },
+            "disk": {
+                "ok": disk_ok,
+                "available_bytes": disk.as_ref().map(|d| d.available_bytes),
+                "total_bytes": disk.as_ref().map(|d| d.total_bytes),
+                "min_free_bytes": health::min_free_disk_bytes(),
+                "downloads_refused": health::disk_low()
+            }
+        },
+        "uptime_seconds": controller.start_time.elapsed().as_secs(),
+        "timestamp": clock::timestamp()
+    });
+
+    (verdict, report)
+}
+
 async fn get_tv_status(controller: &SlideshowController) -> serde_json::Value {
     serde_json::json!({
         "state": format!("{:?}", controller.get_state().await),
         "image_count": controller.get_image_count().await,
         "current_image": controller.get_current_image_path().await
             .map(|p| p.to_string_lossy().to_string()),
+        "paused_until": controller.get_pause_remaining().await
+            .map(|remaining| (clock::now() + chrono::Duration::from_std(remaining).unwrap_or_else(|_| chrono::Duration::zero())).to_rfc3339()),
+        "held": controller.is_held().await,
+        "extended_secs": controller.get_slide_extension().await,
+        "stopped": controller.is_stopped().await,
+        "tv_power": cec::power_state(),
+        "occupancy": motion::stats(),
+        "play_counts": controller.get_play_counts().await,
+        "quiet_hours": controller.is_quiet_hours().await,
+        "curtain": controller.is_curtain_closed().await,
+        "test_pattern": controller.get_test_pattern().await.map(|pattern| pattern.name()),
+        "takeover": controller.describe_takeover().await,
+        "ticker": controller.get_ticker_text().await,
+        "last_transition": controller.get_last_transition().await,
+        "render": perf::stats(),
+        "downloads": downloads::stats(),
+        "quality": quality::mode().name(),
+        "schedule": controller.get_active_schedule().await,
+        "shuffle": controller.is_shuffle_enabled().await,
+        "awake_until": controller.get_wake_remaining().await
+            .map(|remaining| (clock::now() + chrono::Duration::from_std(remaining).unwrap_or_else(|_| chrono::Duration::zero())).to_rfc3339()),
         "uptime_seconds": controller.start_time.elapsed().as_secs(),
-        "timestamp": chrono::Utc::now().to_rfc3339()
+        "timestamp": clock::timestamp(),
+        "clock": {
+            "confidence": clock::confidence(),
+            "server_offset_ms": clock::server_offset_ms(),
+            "ntp": clock::status()
+        }
     })
 }
 
@@ -193,6 +603,40 @@ async fn get_image_list(controller: &SlideshowController) -> serde_json::Value {
     })
 }
 
+// Return the on-disk file for the image on screen, byte for byte
+async fn serve_current_image(controller: &SlideshowController) -> Result<warp::http::Response<Vec<u8>>, Rejection> {
+    let current_index = *controller.current_index.read().await;
+    let images = controller.get_image_list().await;
+    let image = images.get(current_index).ok_or_else(warp::reject::not_found)?;
+
+    let bytes = tokio::fs::read(&image.path).await.map_err(|e| {
+        error!("Failed to read current image {}: {}", image.path, e);
+        warp::reject::not_found()
+    })?;
+
+    let extension = std::path::Path::new(&image.path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let content_type = match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "mp4" | "m4v" => "video/mp4",
+        "mov" => "video/quicktime",
+        _ => "application/octet-stream",
+    };
+
+    warp::http::Response::builder()
+        .header("content-type", content_type)
+        .header("cache-control", "no-store")
+        .header("x-image-id", image.id.as_str())
+        .body(bytes)
+        .map_err(|e| warp::reject::custom(ControlError(e.to_string())))
+}
+
 async fn handle_control_request(
     req: ControlRequest,
     command_sender: &broadcast::Sender<SlideshowCommand>,
@@ -202,7 +646,20 @@ async fn handle_control_request(
         "pause" => SlideshowCommand::Pause,
         "next" => SlideshowCommand::Next,
         "previous" => SlideshowCommand::Previous,
+        "release" => SlideshowCommand::Release,
+        "hold" => SlideshowCommand::Hold,
+        "resume" => SlideshowCommand::Resume,
+        "curtain" => SlideshowCommand::Curtain { closed: true, duration: None },
+        "curtain_off" => SlideshowCommand::Curtain { closed: false, duration: None },
+        "wake" => SlideshowCommand::WakeFor { duration: std::time::Duration::from_secs(DEFAULT_WAKE_MINUTES * 60) },
+        "sync" => SlideshowCommand::Sync,
+        "restart" => SlideshowCommand::Restart,
         "reboot" => SlideshowCommand::Reboot,
+        "tv_on" => SlideshowCommand::Cec { action: cec::Action::PowerOn },
+        "tv_standby" => SlideshowCommand::Cec { action: cec::Action::Standby },
+        "tv_input" => SlideshowCommand::Cec { action: cec::Action::SelectInput },
+        "stop" => SlideshowCommand::Stop,
+        "start" => SlideshowCommand::Start,
         "shutdown" => SlideshowCommand::Shutdown,
         _ => return Err(format!("Unknown action: {}", req.action)),
     };
@@ -213,18 +670,256 @@ async fn handle_control_request(
     Ok(format!("Command '{}' sent successfully", req.action))
 }
 
+async fn handle_pause_request(
+    query: PauseQuery,
+    command_sender: &broadcast::Sender<SlideshowCommand>,
+) -> Result<String, String> {
+    if query.minutes == 0 || query.minutes > MAX_PAUSE_MINUTES {
+        return Err(format!("minutes must be between 1 and {}", MAX_PAUSE_MINUTES));
+    }
+
+    let duration = std::time::Duration::from_secs(query.minutes * 60);
+    command_sender.send(SlideshowCommand::PauseFor { duration })
+        .map_err(|e| format!("Failed to send pause: {}", e))?;
+
+    Ok(format!("Paused for {} minutes", query.minutes))
+}
+
+async fn handle_extend_request(
+    query: ExtendQuery,
+    command_sender: &broadcast::Sender<SlideshowCommand>,
+) -> Result<String, String> {
+    if query.seconds == 0 || query.seconds.abs() > MAX_EXTEND_SECONDS {
+        return Err(format!("seconds must be between -{} and {}, and not 0", MAX_EXTEND_SECONDS, MAX_EXTEND_SECONDS));
+    }
+
+    command_sender.send(SlideshowCommand::Extend { seconds: query.seconds })
+        .map_err(|e| format!("Failed to send extend: {}", e))?;
+
+    Ok(format!("Current slide extended by {}s", query.seconds))
+}
+
+async fn handle_preview_transition_request(
+    req: PreviewTransitionRequest,
+    command_sender: &broadcast::Sender<SlideshowCommand>,
+) -> Result<String, String> {
+    if let Some(ref transition) = req.transition {
+        if TransitionType::from_string(transition).is_none() {
+            return Err(format!("Unknown transition: {}", transition));
+        }
+    }
+
+    let description = req.transition.clone().unwrap_or_else(|| "configured".to_string());
+    command_sender.send(SlideshowCommand::PreviewTransition { effect: req.transition })
+        .map_err(|e| format!("Failed to send transition preview: {}", e))?;
+
+    Ok(format!("Previewing {} transition", description))
+}
+
+async fn handle_test_pattern_request(
+    req: TestPatternRequest,
+    command_sender: &broadcast::Sender<SlideshowCommand>,
+) -> Result<String, String> {
+    let pattern = match req.pattern.as_deref() {
+        Some("off") => None,
+        Some(name) => match TestPattern::parse(name) {
+            Some(pattern) => Some(pattern),
+            None => return Err(format!("Unknown test pattern: {} (expected bars, grid, overscan or off)", name)),
+        },
+        None => Some(TestPattern::Bars),
+    };
+
+    command_sender.send(SlideshowCommand::TestPattern { pattern })
+        .map_err(|e| format!("Failed to send test pattern: {}", e))?;
+
+    Ok(match pattern {
+        Some(pattern) => format!("Showing {} test pattern", pattern.name()),
+        None => "Test pattern off".to_string(),
+    })
+}
+
 async fn handle_config_request(
     req: ConfigRequest,
     command_sender: &broadcast::Sender<SlideshowCommand>,
 ) -> Result<String, String> {
-    let config = crate::mqtt_client::SlideshowConfig {
+    // Normalize to the canonical name so the controller and CouchDB see a consistent value
+    let orientation = match req.orientation {
+        Some(ref orientation) => match Orientation::parse(orientation) {
+            Some(parsed) => Some(parsed.name().to_string()),
+            None => return Err(format!("Unknown orientation: {} (expected landscape, portrait, inverted_landscape or inverted_portrait)", orientation)),
+        },
+        None => None,
+    };
+
+    if let Some(ref transition_effect) = req.transition_effect {
+        if TransitionType::from_string(transition_effect).is_none() {
+            return Err(format!("Unknown transition effect: {}", transition_effect));
+        }
+    }
+
+    if let Some(ref text_profile) = req.text_profile {
+        if !TextProfile::is_valid(text_profile) {
+            return Err(format!("Unknown text profile: {}", text_profile));
+        }
+    }
+
+    if let Some(gamma) = req.gamma {
+        if !color::GAMMA_RANGE.contains(&gamma) {
+            return Err(format!("gamma must be between {} and {}", color::GAMMA_RANGE.start(), color::GAMMA_RANGE.end()));
+        }
+    }
+
+    if let Some(color_temperature) = req.color_temperature {
+        if !color::COLOR_TEMPERATURE_RANGE.contains(&color_temperature) {
+            return Err(format!("color_temperature must be between {}K and {}K",
+                               color::COLOR_TEMPERATURE_RANGE.start(), color::COLOR_TEMPERATURE_RANGE.end()));
+        }
+    }
+
+    if let Some(brightness) = req.brightness {
+        if !color::BRIGHTNESS_RANGE.contains(&brightness) {
+            return Err(format!("brightness must be between {}% and {}%", color::BRIGHTNESS_RANGE.start(), color::BRIGHTNESS_RANGE.end()));
+        }
+    }
+
+    if let Some(ref dimming_schedule) = req.dimming_schedule {
+        for window in dimming_schedule {
+            if parse_time_of_day(&window.start).is_none() || parse_time_of_day(&window.end).is_none() {
+                return Err(format!("Dimming window {}-{} needs HH:MM start and end times", window.start, window.end));
+            }
+            if !color::BRIGHTNESS_RANGE.contains(&window.brightness) {
+                return Err(format!("Dimming window brightness must be between {}% and {}%",
+                                   color::BRIGHTNESS_RANGE.start(), color::BRIGHTNESS_RANGE.end()));
+            }
+        }
+    }
+
+    if let Some(ref quiet_hours) = req.quiet_hours {
+        for window in quiet_hours {
+            if parse_time_of_day(&window.start).is_none() || parse_time_of_day(&window.end).is_none() {
+                return Err(format!("Quiet hours {}-{} need HH:MM start and end times", window.start, window.end));
+            }
+            if let Some(day) = window.days.iter().find(|day| parse_weekday(day).is_none()) {
+                return Err(format!("Unknown day in quiet hours: {}", day));
+            }
+        }
+    }
+
+    if let Some(ref curtain_hours) = req.curtain_hours {
+        for window in curtain_hours {
+            if parse_time_of_day(&window.start).is_none() || parse_time_of_day(&window.end).is_none() {
+                return Err(format!("Curtain hours {}-{} need HH:MM start and end times", window.start, window.end));
+            }
+            if let Some(day) = window.days.iter().find(|day| parse_weekday(day).is_none()) {
+                return Err(format!("Unknown day in curtain hours: {}", day));
+            }
+        }
+    }
+
+    if let Some(ref curtain_color) = req.curtain_color {
+        if parse_hex_color(curtain_color).is_none() {
+            return Err(format!("curtain_color must be #RRGGBB, got {}", curtain_color));
+        }
+    }
+
+    if let Some(ref curtain_image) = req.curtain_image {
+        if !curtain_image.is_empty() && !is_safe_file_component(curtain_image) {
+            return Err(format!("Invalid curtain image id: {:?}", curtain_image));
+        }
+    }
+
+    if let Some(ref letterbox_fill) = req.letterbox_fill {
+        if !Letterbox::is_valid_fill(letterbox_fill) {
+            return Err(format!("Unknown letterbox fill: {} (expected color, blur or image)", letterbox_fill));
+        }
+    }
+
+    if let Some(ref letterbox_color) = req.letterbox_color {
+        if parse_hex_color(letterbox_color).is_none() {
+            return Err(format!("letterbox_color must be #RRGGBB, got {}", letterbox_color));
+        }
+    }
+
+    if let Some(ref letterbox_image) = req.letterbox_image {
+        if !letterbox_image.is_empty() && !is_safe_file_component(letterbox_image) {
+            return Err(format!("Invalid letterbox image id: {:?}", letterbox_image));
+        }
+    }
+
+    if let Some(ref safe_area) = req.safe_area {
+        if !safe_area.is_valid() {
+            return Err(format!("safe_area margins must be between 0 and {} pixels", MAX_SAFE_AREA_MARGIN));
+        }
+    }
+
+    if let Some(ref ken_burns) = req.ken_burns {
+        if !ken_burns::Settings::is_valid_direction(ken_burns) {
+            return Err(format!("Unknown Ken Burns effect: {} (expected off, zoom_in, zoom_out, pan or random)", ken_burns));
+        }
+    }
+
+    if let Some(intensity) = req.ken_burns_intensity {
+        if !ken_burns::INTENSITY_RANGE.contains(&intensity) {
+            return Err(format!("ken_burns_intensity must be between {} and {}",
+                               ken_burns::INTENSITY_RANGE.start(), ken_burns::INTENSITY_RANGE.end()));
+        }
+    }
+
+    if let Some(ref easing) = req.transition_easing {
+        easing::CubicBezier::parse(easing)?;
+    }
+
+    if let Some(ref durations) = req.transition_durations {
+        if let Some(effect) = durations.keys().find(|effect| transitions::get(effect).is_none()) {
+            return Err(format!("Unknown transition effect in transition_durations: {}", effect));
+        }
+    }
+
+    if let Some(ref url) = req.ticker_rss_url {
+        if !url.is_empty() && !url::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https")) {
+            return Err(format!("ticker_rss_url must be an http or https URL: {}", url));
+        }
+    }
+
+    if let Some(ref tags) = req.tags {
+        let is_valid = |tag: &String| !tag.is_empty() && tag.chars().all(|c| c.is_alphanumeric() || "-_.".contains(c));
+        if let Some(tag) = tags.iter().find(|tag| !is_valid(tag)) {
+            return Err(format!("Tags are letters, digits, '-', '_' and '.', got {:?}", tag));
+        }
+    }
+
+    let config = SlideshowConfig {
         display_duration: req.display_duration,
         transition_duration: req.transition_duration,
         transition_effect: req.transition_effect,
-        orientation: None,
+        orientation,
+        content_safety: req.content_safety,
+        text_profile: req.text_profile,
+        framebuffer_refresh_secs: req.framebuffer_refresh_secs,
+        skip_redundant_writes: req.skip_redundant_writes,
+        gamma: req.gamma,
+        color_temperature: req.color_temperature,
+        brightness: req.brightness,
+        dimming_schedule: req.dimming_schedule,
+        quiet_hours: req.quiet_hours,
+        curtain_hours: req.curtain_hours,
+        curtain_color: req.curtain_color,
+        curtain_image: req.curtain_image,
+        letterbox_fill: req.letterbox_fill,
+        letterbox_color: req.letterbox_color,
+        letterbox_image: req.letterbox_image,
+        safe_area: req.safe_area,
+        ken_burns: req.ken_burns,
+        ken_burns_intensity: req.ken_burns_intensity,
+        smart_crop: req.smart_crop,
+        transition_easing: req.transition_easing,
+        transition_durations: req.transition_durations,
+        shuffle: req.shuffle,
+        ticker_rss_url: req.ticker_rss_url,
+        tags: req.tags,
     };
 
-    let command = SlideshowCommand::UpdateConfig { config };
+    let command = SlideshowCommand::UpdateConfig { config: Box::new(config) };
 
     command_sender.send(command)
         .map_err(|e| format!("Failed to send config update: {}", e))?;
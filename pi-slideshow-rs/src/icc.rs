@@ -0,0 +1,63 @@
+use image::codecs::jpeg::JpegDecoder;
+use image::codecs::png::PngDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::{ImageDecoder, ImageFormat, RgbaImage};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::OnceLock;
+use tracing::warn;
+
+// Images exported in a wide-gamut space (Adobe RGB, Display P3) carry an ICC profile saying
+// so. The panel is treated as sRGB, so their pixels are converted from the embedded profile
+// before scaling; shown as they are, Adobe RGB photos look washed out. Images without a
+// profile, or with an sRGB one, are left alone.
+
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+// Set once at startup (--skip-color-profiles turns conversion off)
+pub fn configure(enabled: bool) {
+    if ENABLED.set(enabled).is_err() {
+        warn!("Color profile handling already configured, ignoring");
+    }
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.get().copied().unwrap_or(true)
+}
+
+// `img`, decoded from `path`, converted to sRGB from the file's embedded profile
+pub fn to_srgb(path: &Path, mut img: RgbaImage) -> RgbaImage {
+    if !is_enabled() {
+        return img;
+    }
+    let Some(icc) = embedded_profile(path) else {
+        return img;
+    };
+    let Some(profile) = qcms::Profile::new_from_slice(&icc, false) else {
+        warn!("Ignoring unreadable color profile in {}", path.display());
+        return img;
+    };
+    if profile.is_sRGB() {
+        return img;
+    }
+    let srgb = qcms::Profile::new_sRGB();
+    match qcms::Transform::new(&profile, &srgb, qcms::DataType::RGBA8, qcms::Intent::Perceptual) {
+        Some(transform) => transform.apply(&mut img),
+        None => warn!("Can't convert the color profile in {} to sRGB, showing it unconverted", path.display()),
+    }
+    img
+}
+
+// The ICC profile embedded in a JPEG, PNG or WebP file, read from its header without
+// decoding the pixels
+fn embedded_profile(path: &Path) -> Option<Vec<u8>> {
+    let format = ImageFormat::from_path(path).ok()?;
+    let reader = BufReader::new(File::open(path).ok()?);
+    match format {
+        ImageFormat::Jpeg => JpegDecoder::new(reader).ok()?.icc_profile(),
+        ImageFormat::Png => PngDecoder::new(reader).ok()?.icc_profile(),
+        ImageFormat::WebP => WebPDecoder::new(reader).ok()?.icc_profile(),
+        _ => None,
+    }
+}
@@ -0,0 +1,274 @@
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{ImageEncoder, ImageFormat, RgbaImage};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::SystemTime;
+use tracing::{debug, error, info, warn};
+
+// Slides as they were last drawn for a display: decoded, rotated, scaled and placed on the
+// letterbox fill. Showing a slide again, or transitioning from it, copies the frame instead
+// of decoding and resampling the file. The least recently used frames are dropped first.
+//
+// Frames are also written to FRAME_CACHE_DIR in the image directory (--frame-cache-mb), so
+// after a restart, or back in an orientation shown before, the playlist doesn't go through
+// the decode and Lanczos resample again. Files there are named by a digest of the slide's
+// contents and of how it was drawn: a slide downloaded again still hits, while a new
+// resolution, orientation or layout misses. Past the cap the least recently used go first.
+
+// Frames kept by default; a 1080p frame is about 8 MiB
+pub const DEFAULT_CAPACITY: usize = 8;
+// On-disk cap by default; a 1080p photo frame is a few MB as a quickly compressed PNG
+pub const DEFAULT_DISK_MB: u64 = 256;
+pub const FRAME_CACHE_DIR: &str = ".frame_cache";
+// Larger files (long videos, mostly) are not worth reading through for a digest and skip
+// the disk cache
+const MAX_DIGEST_BYTES: u64 = 64 * 1024 * 1024;
+// Frames waiting for the one thread that compresses and writes them. A frame arriving when
+// the queue is full isn't saved this time; it is again the next time it is drawn.
+const SAVE_QUEUE_DEPTH: usize = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    path: PathBuf,
+    // A file replaced under the same name misses the cache
+    modified: Option<SystemTime>,
+    width: u32,
+    height: u32,
+    orientation: &'static str,
+    // Video slides are cached as their first and last frames separately
+    last_frame: bool,
+    // Everything else that decides how the slide is drawn (letterbox, margins, fit, color
+    // profiles), so frames on disk from other settings aren't reused
+    style: String,
+}
+
+impl CacheKey {
+    pub fn new(path: &Path, (width, height): (u32, u32), orientation: &'static str, last_frame: bool, style: String) -> Self {
+        let modified = std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+        CacheKey { path: path.to_path_buf(), modified, width, height, orientation, last_frame, style }
+    }
+}
+
+#[derive(Clone)]
+pub struct ImageCache {
+    // Most recently used last
+    entries: Arc<Mutex<Vec<(CacheKey, RgbaImage)>>>,
+    capacity: usize,
+    disk: Option<Arc<DiskCache>>,
+    // Frames for the disk cache's writer thread
+    saves: Option<SyncSender<(CacheKey, RgbaImage)>>,
+}
+
+impl ImageCache {
+    // A capacity of 0 turns caching in memory off
+    pub fn new(capacity: usize) -> Self {
+        ImageCache { entries: Arc::new(Mutex::new(Vec::new())), capacity, disk: None, saves: None }
+    }
+
+    // Also keep frames in FRAME_CACHE_DIR under `image_dir`, up to `max_bytes` (0 for none)
+    pub fn with_disk(mut self, image_dir: &Path, max_bytes: u64) -> Self {
+        if max_bytes == 0 {
+            return self;
+        }
+        let dir = image_dir.join(FRAME_CACHE_DIR);
+        match DiskCache::open(dir.clone(), max_bytes) {
+            Ok(disk) => {
+                info!("🗄️ Caching scaled frames in {} (up to {} MB)", dir.display(), max_bytes / 1_000_000);
+                let disk = Arc::new(disk);
+                let (sender, receiver) = mpsc::sync_channel::<(CacheKey, RgbaImage)>(SAVE_QUEUE_DEPTH);
+                let writer = disk.clone();
+                let spawned = thread::Builder::new().name("frame-cache".to_string()).spawn(move || {
+                    for (key, frame) in receiver {
+                        writer.save(&key, &frame);
+                    }
+                });
+                match spawned {
+                    Ok(_) => {
+                        self.disk = Some(disk);
+                        self.saves = Some(sender);
+                    }
+                    Err(e) => warn!("Frame cache disabled, can't start its writer: {}", e),
+                }
+            }
+            Err(e) => warn!("Frame cache disabled, can't use {}: {}", dir.display(), e),
+        }
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    pub fn contains(&self, key: &CacheKey) -> bool {
+        self.entries.lock().unwrap().iter().any(|(cached, _)| cached == key)
+    }
+
+    // The cached frame for `key`, or the one `load` produces, which is then kept. The lock
+    // isn't held while loading, so a slow decode doesn't hold up other displays.
+    pub fn get_or_load<E>(&self, key: CacheKey, load: impl FnOnce() -> Result<RgbaImage, E>) -> Result<RgbaImage, E> {
+        if self.capacity > 0 {
+            let mut entries = self.entries.lock().unwrap();
+            if let Some(position) = entries.iter().position(|(cached, _)| *cached == key) {
+                let entry = entries.remove(position);
+                let image = entry.1.clone();
+                entries.push(entry);
+                return Ok(image);
+            }
+        }
+
+        let image = match self.disk.as_ref().and_then(|disk| disk.load(&key)) {
+            Some(image) => image,
+            None => {
+                let image = load()?;
+                // Compressing and writing the frame shouldn't hold up the display
+                if let Some(saves) = &self.saves {
+                    if let Err(TrySendError::Full((key, _))) = saves.try_send((key.clone(), image.clone())) {
+                        debug!("Frame cache writer busy, not saving {}", key.path.display());
+                    }
+                }
+                image
+            }
+        };
+        if self.capacity == 0 {
+            return Ok(image);
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|(cached, _)| *cached != key);
+        if entries.len() >= self.capacity {
+            entries.remove(0);
+        }
+        entries.push((key, image.clone()));
+        Ok(image)
+    }
+
+    // Drop everything, when a change (letterbox fill, margins) alters how every slide is drawn
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+struct DiskCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    // Content digests by file and modification time, so each file is read through once
+    digests: Mutex<HashMap<(PathBuf, Option<SystemTime>), u64>>,
+}
+
+impl DiskCache {
+    fn open(dir: PathBuf, max_bytes: u64) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        // Writes cut short by a power cut
+        for entry in std::fs::read_dir(&dir)?.flatten() {
+            if entry.path().extension().is_some_and(|ext| ext == "tmp") {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+        Ok(DiskCache { dir, max_bytes, digests: Mutex::new(HashMap::new()) })
+    }
+
+    fn load(&self, key: &CacheKey) -> Option<RgbaImage> {
+        let path = self.frame_path(key)?;
+        let file = File::open(&path).ok()?;
+        let frame = match image::load(BufReader::new(&file), ImageFormat::Png) {
+            Ok(frame) => frame.to_rgba8(),
+            Err(e) => {
+                warn!("Dropping unreadable cached frame {}: {}", path.display(), e);
+                let _ = std::fs::remove_file(&path);
+                return None;
+            }
+        };
+        if frame.dimensions() != (key.width, key.height) {
+            return None;
+        }
+        // The modification time doubles as the last use, for eviction
+        let _ = file.set_modified(SystemTime::now());
+        Some(frame)
+    }
+
+    fn save(&self, key: &CacheKey, frame: &RgbaImage) {
+        let Some(path) = self.frame_path(key) else {
+            return;
+        };
+        // Written aside and renamed into place, so a half-written frame is never read
+        let temp = path.with_extension(format!("{:08x}.tmp", fastrand::u32(..)));
+        let written = File::create(&temp).and_then(|file| {
+            PngEncoder::new_with_quality(BufWriter::new(file), CompressionType::Fast, FilterType::Sub)
+                .write_image(frame.as_raw(), frame.width(), frame.height(), image::ColorType::Rgba8)
+                .map_err(std::io::Error::other)
+        });
+        if let Err(e) = written.and_then(|()| std::fs::rename(&temp, &path)) {
+            error!("Failed to cache frame for {}: {}", key.path.display(), e);
+            let _ = std::fs::remove_file(&temp);
+            return;
+        }
+        self.evict();
+    }
+
+    // Delete the least recently used frames until the cache fits under its cap
+    fn evict(&self) {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return;
+        };
+        let mut frames: Vec<(PathBuf, u64, SystemTime)> = entries
+            .flatten()
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "frame"))
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                Some((entry.path(), metadata.len(), metadata.modified().ok()?))
+            })
+            .collect();
+        let mut used: u64 = frames.iter().map(|(_, size, _)| size).sum();
+        frames.sort_by_key(|(_, _, modified)| *modified);
+        for (path, size, _) in frames {
+            if used <= self.max_bytes {
+                break;
+            }
+            if std::fs::remove_file(&path).is_ok() {
+                used -= size;
+            }
+        }
+    }
+
+    // Where the frame for `key` is kept, or None for a file too large to digest
+    fn frame_path(&self, key: &CacheKey) -> Option<PathBuf> {
+        let digest = self.digest(key)?;
+        let drawn = format!("{}|{}x{}|{}|{}|{}", env!("CARGO_PKG_VERSION"), key.width, key.height, key.orientation, key.last_frame, key.style);
+        Some(self.dir.join(format!("{:016x}.frame", fnv1a(digest, drawn.as_bytes()))))
+    }
+
+    fn digest(&self, key: &CacheKey) -> Option<u64> {
+        let file_key = (key.path.clone(), key.modified);
+        if let Some(digest) = self.digests.lock().unwrap().get(&file_key) {
+            return Some(*digest);
+        }
+        let file = File::open(&key.path).ok()?;
+        if file.metadata().ok()?.len() > MAX_DIGEST_BYTES {
+            return None;
+        }
+        let mut reader = BufReader::new(file);
+        let mut buffer = [0u8; 64 * 1024];
+        let mut digest = FNV_OFFSET;
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => digest = fnv1a(digest, &buffer[..read]),
+                Err(_) => return None,
+            }
+        }
+        self.digests.lock().unwrap().insert(file_key, digest);
+        Some(digest)
+    }
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+// FNV-1a, which unlike the standard library's hasher is the same on every run, so frames
+// cached before a restart are found after it
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3))
+}
@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tracing::{error, info, warn};
+
+use crate::clock;
+use crate::health;
+use crate::slideshow_controller::{QUARANTINE_DIR, QUARANTINE_REVISION_SUFFIX};
+
+// Keeps the image directory under a size cap (--image-dir-max-mb). Images drop out of a
+// TV's assignments as campaigns end, but their files stay behind for the next sync, so
+// without a cap the directory only grows. Over the cap, files no longer assigned are
+// deleted, the ones unassigned the longest first. When each file was last assigned, and what
+// is assigned now, are kept in USAGE_FILE, so both survive restarts.
+//
+// It also keeps the SD card from filling up, whatever the cap: before a slide is downloaded,
+// make_room frees space down to --min-free-disk-mb by emptying quarantined files and then
+// deleting unassigned ones in the same order. When that isn't enough the download is refused,
+// since a card with no space left can corrupt the very files the slideshow is playing. Until
+// CouchDB has said what is assigned, and whenever the slides are the local directory's own
+// pictures, nothing counts as unassigned and only the quarantine is emptied.
+
+const USAGE_FILE: &str = ".image_usage.json";
+
+pub struct ImageStore {
+    dir: PathBuf,
+    // 0 for no cap
+    max_bytes: u64,
+    usage: Usage,
+}
+
+// USAGE_FILE
+#[derive(Default, Serialize, Deserialize)]
+struct Usage {
+    // File name -> Unix seconds it was last assigned to this TV
+    #[serde(default)]
+    last_assigned: HashMap<String, u64>,
+    // The files assigned at the last sync with CouchDB; None before the first one
+    #[serde(default)]
+    assigned: Option<HashSet<String>>,
+}
+
+impl ImageStore {
+    pub fn open(dir: &Path, max_bytes: u64) -> Self {
+        let usage = std::fs::read_to_string(dir.join(USAGE_FILE))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        ImageStore { dir: dir.to_path_buf(), max_bytes, usage }
+    }
+
+    // Note the files assigned to this TV, then delete unassigned ones until the directory
+    // fits under the cap. Returns the files deleted.
+    pub fn sync(&mut self, assigned: &[PathBuf]) -> Vec<PathBuf> {
+        let now = clock::now().timestamp().max(0) as u64;
+        let assigned: HashSet<String> = assigned
+            .iter()
+            .filter_map(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect();
+        for name in &assigned {
+            self.usage.last_assigned.insert(name.clone(), now);
+        }
+        // Only written when the assignments change, to spare the SD card
+        let mut changed = self.usage.assigned.as_ref() != Some(&assigned);
+        self.usage.assigned = Some(assigned);
+
+        let mut files = self.slide_files();
+        let mut used: u64 = files.iter().map(|(_, size, _)| size).sum();
+        let mut deleted = Vec::new();
+        if self.max_bytes > 0 && used > self.max_bytes {
+            files = self.unassigned_oldest_first(files);
+            for (path, size, _) in files {
+                if used <= self.max_bytes {
+                    break;
+                }
+                match std::fs::remove_file(&path) {
+                    Ok(()) => {
+                        info!("🧹 Removed {} ({} KiB) to keep the image directory under {} MB",
+                                 path.display(), size / 1024, self.max_bytes / 1_000_000);
+                        self.usage.last_assigned.remove(&file_name(&path));
+                        used -= size;
+                        deleted.push(path);
+                        changed = true;
+                    }
+                    Err(e) => error!("Failed to remove {}: {}", path.display(), e),
+                }
+            }
+            if used > self.max_bytes {
+                warn!("Image directory holds {} MB of assigned images, over its {} MB cap",
+                          used / 1_000_000, self.max_bytes / 1_000_000);
+            }
+        }
+
+        if changed {
+            self.save();
+        }
+        health::record_image_dir_usage(used, self.max_bytes);
+        deleted
+    }
+
+    // Least recently assigned first; files from before the usage file by age. None at all
+    // before the first sync, when every file may still be assigned.
+    fn unassigned_oldest_first(&self, mut files: Vec<(PathBuf, u64, u64)>) -> Vec<(PathBuf, u64, u64)> {
+        let Some(ref assigned) = self.usage.assigned else {
+            return Vec::new();
+        };
+        files.retain(|(path, _, _)| !assigned.contains(&file_name(path)));
+        files.sort_by_key(|(path, _, modified)| self.usage.last_assigned.get(&file_name(path)).copied().unwrap_or(*modified));
+        files
+    }
+
+    // Free space until the filesystem has its minimum free, quarantined files first, then
+    // unassigned slides unless `evict_slides` is false (the slides are the directory's own).
+    // Err with the space still available when it can't be done.
+    pub fn make_room(&mut self, evict_slides: bool) -> Result<(), u64> {
+        let min_free = health::min_free_disk_bytes();
+        let available = || health::disk_space(&self.dir).map(|space| space.available_bytes);
+        let Some(mut free) = available() else {
+            return Ok(());
+        };
+        if min_free == 0 || free >= min_free {
+            return Ok(());
+        }
+
+        // Emptied rather than deleted: an empty file still marks the image as quarantined, so
+        // it isn't downloaded again. The revision files beside them are left alone.
+        let mut quarantined: Vec<(PathBuf, u64)> = std::fs::read_dir(self.dir.join(QUARANTINE_DIR))
+            .map(|entries| entries.flatten()
+                .filter(|entry| !entry.file_name().to_string_lossy().ends_with(QUARANTINE_REVISION_SUFFIX))
+                .filter_map(|entry| Some((entry.path(), entry.metadata().ok().filter(|m| m.is_file() && m.len() > 0)?.len())))
+                .collect())
+            .unwrap_or_default();
+        quarantined.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+        for (path, size) in quarantined {
+            if free >= min_free {
+                break;
+            }
+            match std::fs::File::create(&path) {
+                Ok(_) => info!("🧹 Emptied quarantined {} ({} KiB) to free disk space", path.display(), size / 1024),
+                Err(e) => error!("Failed to empty {}: {}", path.display(), e),
+            }
+            free = available().unwrap_or(free);
+        }
+
+        let mut changed = false;
+        let evictable = if evict_slides { self.unassigned_oldest_first(self.slide_files()) } else { Vec::new() };
+        for (path, size, _) in evictable {
+            if free >= min_free {
+                break;
+            }
+            match std::fs::remove_file(&path) {
+                Ok(()) => {
+                    info!("🧹 Removed {} ({} KiB) to free disk space", path.display(), size / 1024);
+                    self.usage.last_assigned.remove(&file_name(&path));
+                    changed = true;
+                }
+                Err(e) => error!("Failed to remove {}: {}", path.display(), e),
+            }
+            free = available().unwrap_or(free);
+        }
+        if changed {
+            self.save();
+        }
+
+        if free < min_free { Err(free) } else { Ok(()) }
+    }
+
+    // Slides directly in the directory, with their size and modification time in Unix seconds
+    fn slide_files(&self) -> Vec<(PathBuf, u64, u64)> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                let ext = path.extension()?.to_string_lossy().to_lowercase();
+                let metadata = entry.metadata().ok()?;
+                if !metadata.is_file() || !crate::is_slide_extension(&ext) {
+                    return None;
+                }
+                let modified = metadata.modified().ok()
+                    .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map_or(0, |age| age.as_secs());
+                Some((path, metadata.len(), modified))
+            })
+            .collect()
+    }
+
+    fn save(&self) {
+        // Entries for files that are gone are dropped
+        let usage = Usage {
+            last_assigned: self.usage.last_assigned.iter()
+                .filter(|(name, _)| self.dir.join(name).exists())
+                .map(|(name, time)| (name.clone(), *time))
+                .collect(),
+            assigned: self.usage.assigned.clone(),
+        };
+        let path = self.dir.join(USAGE_FILE);
+        if let Err(e) = serde_json::to_string(&usage).map_err(std::io::Error::from).and_then(|json| std::fs::write(&path, json)) {
+            error!("Failed to write {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
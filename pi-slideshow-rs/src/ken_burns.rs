@@ -0,0 +1,160 @@
+use image::{Rgba, RgbaImage};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::time::Duration;
+
+// Ken Burns effect: a slow zoom or pan across a still while it is on screen (TvConfig
+// ken_burns). Each slide's motion is picked from its file name, so it moves the same way
+// every time it comes up. Frames are sampled from the slide as drawn for the screen, inside
+// the overscan margins.
+
+// Time between redraws of a moving still; devices that render slower skip ahead instead
+pub const FRAME_INTERVAL: Duration = Duration::from_millis(50);
+// How far in the view zooms, as a fraction of the screen (TvConfig ken_burns_intensity)
+pub const INTENSITY_RANGE: RangeInclusive<f64> = 0.0..=0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    ZoomIn,
+    ZoomOut,
+    // A sideways or vertical drift, picked per slide
+    Pan,
+    // Any of the above, picked per slide
+    Random,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Settings {
+    direction: Direction,
+    intensity: f32,
+}
+
+impl Settings {
+    pub fn is_valid_direction(name: &str) -> bool {
+        name.eq_ignore_ascii_case("off") || direction(name).is_some()
+    }
+
+    // None when the effect is off, unknown or has no intensity
+    pub fn from_config(name: &str, intensity: f64) -> Option<Self> {
+        let intensity = intensity.clamp(*INTENSITY_RANGE.start(), *INTENSITY_RANGE.end()) as f32;
+        direction(name)
+            .filter(|_| intensity > 0.0)
+            .map(|direction| Settings { direction, intensity })
+    }
+}
+
+fn direction(name: &str) -> Option<Direction> {
+    match name.to_lowercase().as_str() {
+        "zoom_in" => Some(Direction::ZoomIn),
+        "zoom_out" => Some(Direction::ZoomOut),
+        "pan" => Some(Direction::Pan),
+        "random" => Some(Direction::Random),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Motion {
+    ZoomIn,
+    ZoomOut,
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+}
+
+const PANS: [Motion; 4] = [Motion::PanLeft, Motion::PanRight, Motion::PanUp, Motion::PanDown];
+const MOTIONS: [Motion; 6] = [Motion::ZoomIn, Motion::ZoomOut, Motion::PanLeft, Motion::PanRight, Motion::PanUp, Motion::PanDown];
+
+pub struct KenBurns {
+    motion: Motion,
+    intensity: f32,
+    // Where in the slide a zoom closes in on, and where across it a pan runs, from 0.0 to 1.0
+    focus: (f32, f32),
+}
+
+impl KenBurns {
+    pub fn for_slide(settings: &Settings, path: &Path) -> Self {
+        let seed = slide_seed(path);
+        let motion = match settings.direction {
+            Direction::ZoomIn => Motion::ZoomIn,
+            Direction::ZoomOut => Motion::ZoomOut,
+            Direction::Pan => PANS[(seed % PANS.len() as u64) as usize],
+            Direction::Random => MOTIONS[(seed % MOTIONS.len() as u64) as usize],
+        };
+        // Kept off the edges, which are the likeliest part of a photo to be empty
+        let focus = (
+            0.2 + ((seed >> 16) % 61) as f32 / 100.0,
+            0.2 + ((seed >> 32) % 61) as f32 / 100.0,
+        );
+        KenBurns { motion, intensity: settings.intensity, focus }
+    }
+
+    // `frame` with its `area` (x, y, width, height) showing the part of that area in view
+    // `progress` of the way (0.0 to 1.0) through the slide's time on screen
+    pub fn render(&self, frame: &RgbaImage, area: (u32, u32, u32, u32), progress: f32) -> RgbaImage {
+        let (area_x, area_y, area_width, area_height) = area;
+        let (view_x, view_y, view_width, view_height) = self.view(progress.clamp(0.0, 1.0), area_width as f32, area_height as f32);
+        let step_x = view_width / area_width as f32;
+        let step_y = view_height / area_height as f32;
+
+        let mut result = frame.clone();
+        for y in 0..area_height {
+            let source_y = view_y + (y as f32 + 0.5) * step_y - 0.5;
+            for x in 0..area_width {
+                let source_x = view_x + (x as f32 + 0.5) * step_x - 0.5;
+                let pixel = sample(frame, area, source_x, source_y);
+                result.put_pixel(area_x + x, area_y + y, pixel);
+            }
+        }
+        result
+    }
+
+    // The part of a width x height area in view at `progress`, as (x, y, width, height)
+    fn view(&self, progress: f32, width: f32, height: f32) -> (f32, f32, f32, f32) {
+        let zoom = 1.0 + self.intensity * match self.motion {
+            Motion::ZoomIn => progress,
+            Motion::ZoomOut => 1.0 - progress,
+            _ => 1.0,
+        };
+        let (view_width, view_height) = (width / zoom, height / zoom);
+        let (along_x, along_y) = match self.motion {
+            Motion::PanLeft => (1.0 - progress, self.focus.1),
+            Motion::PanRight => (progress, self.focus.1),
+            Motion::PanUp => (self.focus.0, 1.0 - progress),
+            Motion::PanDown => (self.focus.0, progress),
+            Motion::ZoomIn | Motion::ZoomOut => self.focus,
+        };
+        ((width - view_width) * along_x, (height - view_height) * along_y, view_width, view_height)
+    }
+}
+
+// Bilinear sample at (x, y) within `area` of `frame`, so slow motion glides instead of
+// stepping a whole pixel at a time
+fn sample(frame: &RgbaImage, (area_x, area_y, area_width, area_height): (u32, u32, u32, u32), x: f32, y: f32) -> Rgba<u8> {
+    let x = x.clamp(0.0, (area_width - 1) as f32);
+    let y = y.clamp(0.0, (area_height - 1) as f32);
+    let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+    let (x1, y1) = ((x0 + 1).min(area_width - 1), (y0 + 1).min(area_height - 1));
+    let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+
+    let top_left = frame.get_pixel(area_x + x0, area_y + y0);
+    let top_right = frame.get_pixel(area_x + x1, area_y + y0);
+    let bottom_left = frame.get_pixel(area_x + x0, area_y + y1);
+    let bottom_right = frame.get_pixel(area_x + x1, area_y + y1);
+
+    let mut pixel = [0u8; 4];
+    for (c, channel) in pixel.iter_mut().enumerate() {
+        let top = top_left[c] as f32 + (top_right[c] as f32 - top_left[c] as f32) * fx;
+        let bottom = bottom_left[c] as f32 + (bottom_right[c] as f32 - bottom_left[c] as f32) * fx;
+        *channel = (top + (bottom - top) * fy).round() as u8;
+    }
+    Rgba(pixel)
+}
+
+// FNV-1a over the file name: the same for a slide across runs and devices, unlike the
+// standard library's hasher
+fn slide_seed(path: &Path) -> u64 {
+    let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+}
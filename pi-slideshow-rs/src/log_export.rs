@@ -0,0 +1,474 @@
+use chrono::{SecondsFormat, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tracing::field::{Field, Visit};
+use tracing::{error, info, warn, Event, Level, Subscriber};
+use tracing_subscriber::fmt::format::DefaultFields;
+use tracing_subscriber::fmt::FormattedFields;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::EnvFilter;
+
+use crate::clock;
+
+// Log lines shipped to a central server (--log-export), so a fleet can be searched in one
+// place instead of TV by TV: to a syslog server over UDP (syslog://host:514) or TCP
+// (syslog+tcp://host:601) as RFC 5424 messages, or to Grafana Loki's push API
+// (http://loki:3100). Lines go out in batches every couple of seconds from a thread of their
+// own, so a slow server never holds up the slideshow. When the server can't be reached, batches
+// are kept on disk under --log-dir (up to --log-export-buffer-mb, dropping the oldest) and sent
+// in order once it answers again. Lines carry the TV ID (as the syslog hostname, or a Loki
+// label) and the server-corrected time, so they line up across TVs. What the exporter itself
+// logs stays local, or every failure to ship would make more to ship.
+
+// Lines waiting for the exporter thread; past this new ones are dropped and counted
+const QUEUE_LINES: usize = 10_000;
+const BATCH_LINES: usize = 500;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+// Wait before trying a server that failed again
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+const APP_NAME: &str = "pi-slideshow";
+const BUFFER_DIR_NAME: &str = "export-buffer";
+
+static TV_ID: OnceLock<String> = OnceLock::new();
+// Lines lost to a full queue since the last batch
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    // Set on the exporter's thread, whose own events aren't exported
+    static IN_EXPORTER: Cell<bool> = const { Cell::new(false) };
+}
+
+pub struct Settings {
+    pub url: String,
+    // RUST_LOG-style, applied on top of --log-level
+    pub level: String,
+    // Empty keeps nothing on disk
+    pub log_dir: PathBuf,
+    pub buffer_bytes: u64,
+}
+
+// Called once the TV ID is known; lines sent before then carry the hostname
+pub fn set_tv_id(tv_id: &str) {
+    let _ = TV_ID.set(tv_id.to_string());
+}
+
+fn tv_id() -> String {
+    TV_ID.get().cloned().unwrap_or_else(|| {
+        fs::read_to_string("/proc/sys/kernel/hostname").map(|name| name.trim().to_string()).unwrap_or_else(|_| "-".to_string())
+    })
+}
+
+#[derive(Debug, Clone)]
+enum Target {
+    SyslogUdp(String),
+    SyslogTcp(String),
+    Loki(String),
+}
+
+impl Target {
+    fn parse(url: &str) -> Result<Self, String> {
+        let parsed = url::Url::parse(url).map_err(|e| format!("invalid --log-export URL '{}': {}", url, e))?;
+        let address = || -> Result<String, String> {
+            let host = parsed.host_str().ok_or_else(|| format!("no host in --log-export URL '{}'", url))?;
+            Ok(format!("{}:{}", host, parsed.port().unwrap_or(514)))
+        };
+        match parsed.scheme() {
+            "syslog" | "syslog+udp" => Ok(Target::SyslogUdp(address()?)),
+            "syslog+tcp" => Ok(Target::SyslogTcp(address()?)),
+            "http" | "https" => {
+                // The base URL is enough
+                let mut push = parsed.clone();
+                if push.path() == "/" || push.path().is_empty() {
+                    push.set_path("/loki/api/v1/push");
+                }
+                Ok(Target::Loki(push.to_string()))
+            }
+            other => Err(format!("unknown --log-export scheme '{}' (syslog, syslog+tcp, http or https)", other)),
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::SyslogUdp(address) => write!(f, "syslog at {} (UDP)", address),
+            Target::SyslogTcp(address) => write!(f, "syslog at {} (TCP)", address),
+            Target::Loki(url) => write!(f, "Loki at {}", url),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Record {
+    // Nanoseconds since the epoch
+    time: i64,
+    level: String,
+    target: String,
+    message: String,
+}
+
+impl Record {
+    fn new(level: &Level, target: &str, message: String) -> Self {
+        Record {
+            time: clock::now().timestamp_nanos_opt().unwrap_or_default(),
+            level: level.as_str().to_string(),
+            target: target.to_string(),
+            message,
+        }
+    }
+
+    // RFC 5424 severity
+    fn severity(&self) -> u8 {
+        match self.level.as_str() {
+            "ERROR" => 3,
+            "WARN" => 4,
+            "INFO" => 6,
+            _ => 7,
+        }
+    }
+
+    // RFC 5424, facility local0
+    fn syslog(&self, hostname: &str) -> String {
+        let time = Utc.timestamp_nanos(self.time).to_rfc3339_opts(SecondsFormat::Micros, true);
+        format!("<{}>1 {} {} {} {} - - {}: {}", 16 * 8 + self.severity(), time, hostname, APP_NAME, std::process::id(), self.target, self.message)
+    }
+}
+
+// What ships the lines the layer collects, started once the layer is in place so that what it
+// logs itself isn't lost
+pub struct Exporter {
+    target: Target,
+    settings: Settings,
+    receiver: mpsc::Receiver<Record>,
+}
+
+// Build the exporting layer; lines queue up until the exporter is started
+pub fn layer<S>(settings: Settings) -> Result<(impl Layer<S>, Exporter), String>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let target = Target::parse(&settings.url)?;
+    let filter = EnvFilter::try_new(&settings.level).map_err(|e| format!("invalid --log-export-level '{}': {}", settings.level, e))?;
+    let (sender, receiver) = mpsc::channel(QUEUE_LINES);
+    Ok((ExportLayer { sender }.with_filter(filter), Exporter { target, settings, receiver }))
+}
+
+impl Exporter {
+    // Ship from a thread of its own, with its own runtime, so nothing it does is exported
+    pub fn start(self) {
+        let Exporter { target, settings, receiver } = self;
+        let spawned = std::thread::Builder::new().name("log-export".to_string()).spawn(move || {
+            IN_EXPORTER.with(|flag| flag.set(true));
+            let buffer = if settings.log_dir.as_os_str().is_empty() || settings.buffer_bytes == 0 {
+                None
+            } else {
+                Some(DiskBuffer::open(&settings.log_dir.join(BUFFER_DIR_NAME), settings.buffer_bytes))
+            };
+            match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(runtime) => runtime.block_on(run(target, receiver, buffer)),
+                Err(e) => error!("❌ Can't start the log exporter: {}", e),
+            }
+        });
+        if let Err(e) = spawned {
+            error!("❌ Can't start the log exporter: {}", e);
+        }
+    }
+}
+
+struct ExportLayer {
+    sender: mpsc::Sender<Record>,
+}
+
+impl<S> Layer<S> for ExportLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if IN_EXPORTER.with(|flag| flag.get()) {
+            return;
+        }
+        // Spans first, as the console shows them: download{image_id="x"}: ...
+        let mut message = String::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                message.push_str(span.name());
+                if let Some(fields) = span.extensions().get::<FormattedFields<DefaultFields>>() {
+                    if !fields.is_empty() {
+                        let _ = write!(message, "{{{}}}", fields.as_str());
+                    }
+                }
+                message.push_str(": ");
+            }
+        }
+        event.record(&mut MessageVisitor(&mut message));
+        let metadata = event.metadata();
+        if self.sender.try_send(Record::new(metadata.level(), metadata.target(), message)).is_err() {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+struct MessageVisitor<'a>(&'a mut String);
+
+impl Visit for MessageVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, "{:?}", value);
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+async fn run(target: Target, mut receiver: mpsc::Receiver<Record>, mut buffer: Option<DiskBuffer>) {
+    info!("📤 Shipping logs to {}", target);
+    let mut shipper = Shipper::new(target);
+    let mut batch: Vec<Record> = Vec::new();
+    let mut flush = tokio::time::interval(FLUSH_INTERVAL);
+    // While the server is failing, when to try it next
+    let mut retry_at: Option<Instant> = None;
+
+    loop {
+        tokio::select! {
+            record = receiver.recv() => match record {
+                Some(record) => {
+                    batch.push(record);
+                    if batch.len() < BATCH_LINES {
+                        continue;
+                    }
+                }
+                None => return,
+            },
+            _ = flush.tick() => {}
+        }
+        let dropped = DROPPED.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            batch.push(Record::new(&Level::WARN, module_path!(), format!("{} log lines dropped, the export queue was full", dropped)));
+        }
+        if retry_at.is_some_and(|at| Instant::now() < at) {
+            if !batch.is_empty() {
+                keep(&mut buffer, std::mem::take(&mut batch));
+            }
+            continue;
+        }
+
+        // Whatever was kept on disk goes first, so the server sees lines in order
+        let mut failed = false;
+        if let Some(ref mut disk) = buffer {
+            while let Some((segment, records)) = disk.oldest() {
+                if let Err(e) = shipper.send(&records).await {
+                    warn!("Failed to ship buffered logs to {}: {}", shipper.target, e);
+                    failed = true;
+                    break;
+                }
+                disk.remove(&segment);
+            }
+        }
+        if !failed && !batch.is_empty() {
+            if let Err(e) = shipper.send(&batch).await {
+                if retry_at.is_none() {
+                    warn!("Failed to ship logs to {}, keeping them until it is back: {}", shipper.target, e);
+                }
+                failed = true;
+            } else {
+                batch.clear();
+            }
+        }
+        if failed {
+            retry_at = Some(Instant::now() + RETRY_INTERVAL);
+            if !batch.is_empty() {
+                keep(&mut buffer, std::mem::take(&mut batch));
+            }
+        } else if retry_at.take().is_some() {
+            info!("📤 Shipping logs to {} again", shipper.target);
+        }
+    }
+}
+
+// Set a batch aside for later; without a disk buffer it is lost
+fn keep(buffer: &mut Option<DiskBuffer>, records: Vec<Record>) {
+    match buffer {
+        Some(disk) => disk.store(&records),
+        None => {
+            DROPPED.fetch_add(records.len() as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+struct Shipper {
+    target: Target,
+    udp: Option<UdpSocket>,
+    tcp: Option<TcpStream>,
+    http: reqwest::Client,
+}
+
+impl Shipper {
+    fn new(target: Target) -> Self {
+        let http = reqwest::Client::builder().timeout(SEND_TIMEOUT).build().unwrap_or_default();
+        Shipper { target, udp: None, tcp: None, http }
+    }
+
+    async fn send(&mut self, records: &[Record]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let hostname = tv_id();
+        match self.target.clone() {
+            Target::SyslogUdp(address) => {
+                if self.udp.is_none() {
+                    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+                    socket.connect(&address).await?;
+                    self.udp = Some(socket);
+                }
+                let socket = self.udp.as_ref().ok_or("no socket")?;
+                for record in records {
+                    if let Err(e) = socket.send(record.syslog(&hostname).as_bytes()).await {
+                        // Connect again next time, in case the address has changed
+                        self.udp = None;
+                        return Err(e.into());
+                    }
+                }
+            }
+            Target::SyslogTcp(address) => {
+                if self.tcp.is_none() {
+                    let stream = tokio::time::timeout(SEND_TIMEOUT, TcpStream::connect(&address)).await
+                        .map_err(|_| format!("connecting took over {} seconds", SEND_TIMEOUT.as_secs()))??;
+                    self.tcp = Some(stream);
+                }
+                // RFC 6587 octet counting: each message after its length
+                let mut frames = String::new();
+                for record in records {
+                    let message = record.syslog(&hostname);
+                    let _ = write!(frames, "{} {}", message.len(), message);
+                }
+                let stream = self.tcp.as_mut().ok_or("no connection")?;
+                let written = tokio::time::timeout(SEND_TIMEOUT, stream.write_all(frames.as_bytes())).await;
+                if !matches!(written, Ok(Ok(()))) {
+                    self.tcp = None;
+                    return Err(match written {
+                        Ok(Err(e)) => e.into(),
+                        _ => format!("sending took over {} seconds", SEND_TIMEOUT.as_secs()).into(),
+                    });
+                }
+            }
+            Target::Loki(url) => {
+                // One stream per level, so it can be filtered on as a label
+                let mut streams: Vec<(String, Vec<[String; 2]>)> = Vec::new();
+                for record in records {
+                    let line = [record.time.to_string(), format!("{}: {}", record.target, record.message)];
+                    match streams.iter_mut().find(|(level, _)| *level == record.level) {
+                        Some((_, values)) => values.push(line),
+                        None => streams.push((record.level.clone(), vec![line])),
+                    }
+                }
+                let body = serde_json::json!({
+                    "streams": streams.into_iter().map(|(level, values)| serde_json::json!({
+                        "stream": { "job": APP_NAME, "tv_id": hostname, "level": level.to_lowercase() },
+                        "values": values,
+                    })).collect::<Vec<_>>()
+                });
+                let response = self.http.post(&url).json(&body).send().await?;
+                if !response.status().is_success() {
+                    return Err(format!("Loki answered {}", response.status()).into());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Batches that couldn't be sent, one JSON-lines file each, numbered in the order they came
+struct DiskBuffer {
+    dir: PathBuf,
+    max_bytes: u64,
+    next: u64,
+    // Whether dropping the oldest for room has been logged since the buffer was last empty
+    trimmed: bool,
+}
+
+impl DiskBuffer {
+    fn open(dir: &Path, max_bytes: u64) -> Self {
+        if let Err(e) = fs::create_dir_all(dir) {
+            warn!("Can't keep unsent logs in {}: {}", dir.display(), e);
+        }
+        let mut buffer = DiskBuffer { dir: dir.to_path_buf(), max_bytes, next: 0, trimmed: false };
+        buffer.next = buffer.segments().last().and_then(|path| segment_number(path)).map_or(0, |n| n + 1);
+        let kept = buffer.segments().len();
+        if kept > 0 {
+            info!("📤 {} batches of logs from before the restart are waiting to be shipped", kept);
+        }
+        buffer
+    }
+
+    fn segments(&self) -> Vec<PathBuf> {
+        let mut segments: Vec<PathBuf> = fs::read_dir(&self.dir)
+            .map(|entries| entries.flatten().map(|entry| entry.path()).filter(|path| segment_number(path).is_some()).collect())
+            .unwrap_or_default();
+        segments.sort();
+        segments
+    }
+
+    fn store(&mut self, records: &[Record]) {
+        let mut text = String::new();
+        for record in records {
+            if let Ok(line) = serde_json::to_string(record) {
+                text.push_str(&line);
+                text.push('\n');
+            }
+        }
+        let path = self.dir.join(format!("{:012}.jsonl", self.next));
+        self.next += 1;
+        if let Err(e) = fs::write(&path, text) {
+            warn!("Failed to keep {} unsent log lines: {}", records.len(), e);
+            return;
+        }
+
+        let mut segments = self.segments();
+        let mut total: u64 = segments.iter().filter_map(|path| fs::metadata(path).ok()).map(|meta| meta.len()).sum();
+        while total > self.max_bytes && segments.len() > 1 {
+            let oldest = segments.remove(0);
+            total -= fs::metadata(&oldest).map(|meta| meta.len()).unwrap_or(0);
+            let _ = fs::remove_file(&oldest);
+            if !std::mem::replace(&mut self.trimmed, true) {
+                warn!("Unsent logs are over {} MB, dropping the oldest", self.max_bytes / 1_000_000);
+            }
+        }
+    }
+
+    // The oldest batch kept, if any; one that can't be read is dropped
+    fn oldest(&mut self) -> Option<(PathBuf, Vec<Record>)> {
+        for path in self.segments() {
+            match fs::read_to_string(&path) {
+                Ok(text) => return Some((path, text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())),
+                Err(e) => {
+                    warn!("Dropping unreadable log batch {}: {}", path.display(), e);
+                    let _ = fs::remove_file(&path);
+                }
+            }
+        }
+        self.trimmed = false;
+        None
+    }
+
+    fn remove(&mut self, segment: &Path) {
+        if let Err(e) = fs::remove_file(segment) {
+            warn!("Failed to remove shipped log batch {}: {}", segment.display(), e);
+        }
+    }
+}
+
+fn segment_number(path: &Path) -> Option<u64> {
+    if path.extension()? != "jsonl" {
+        return None;
+    }
+    path.file_stem()?.to_str()?.parse().ok()
+}
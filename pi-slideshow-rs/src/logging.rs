@@ -0,0 +1,180 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tracing::{error, info};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
+
+use crate::log_export;
+
+// Log output through tracing: to stdout for the journal as before, and to a file under
+// --log-dir that survives reboots, so a crash from days ago can still be looked into on the
+// device after the journal has moved on. The file is rotated by size: pi-slideshow.log is
+// renamed to pi-slideshow.log.1 (and .1 to .2, ...) once it passes --log-max-mb, keeping
+// --log-files files in all. Panics are logged too, with where they happened. The level can be
+// changed while running (the set_log_level command, PUT /api/log_level), to look into a problem
+// at debug without restarting the endpoint and losing it; the change lasts until the next start.
+// Downloads, transitions and commands run in spans, so their lines carry the image, effect or
+// command they belong to. With --log-export the lines are also shipped to a syslog server or
+// Loki (see log_export).
+
+const FILE_NAME: &str = "pi-slideshow.log";
+
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+// The directives the filter was last built from
+static LEVEL: Mutex<String> = Mutex::new(String::new());
+// --log-dir when logging to a file; what is in it changes hands along with --user
+static DIR: OnceLock<PathBuf> = OnceLock::new();
+
+struct RotatingFile {
+    dir: PathBuf,
+    max_bytes: u64,
+    // Files kept, counting the one being written
+    keep: usize,
+    file: File,
+    written: u64,
+}
+
+impl RotatingFile {
+    fn open(dir: &Path, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(FILE_NAME);
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(RotatingFile { dir: dir.to_path_buf(), max_bytes, keep: keep.max(1), file, written })
+    }
+
+    fn path(&self, generation: usize) -> PathBuf {
+        match generation {
+            0 => self.dir.join(FILE_NAME),
+            n => self.dir.join(format!("{}.{}", FILE_NAME, n)),
+        }
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        // The oldest is overwritten by the rename; with a single file there is nothing to keep
+        for generation in (1..self.keep).rev() {
+            let from = self.path(generation - 1);
+            if from.exists() {
+                fs::rename(&from, self.path(generation))?;
+            }
+        }
+        self.file = OpenOptions::new().create(true).write(true).truncate(true).open(self.path(0))?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    // Each event arrives as one write, so a line is never split across files
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            if let Err(e) = self.rotate() {
+                // Keep appending to the file we have rather than lose the line
+                eprintln!("Failed to rotate the log file: {}", e);
+            }
+        }
+        self.file.write_all(buf)?;
+        self.written += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+// Set up logging; called once, straight after the arguments are read. `level` takes
+// RUST_LOG-style directives ("debug", "info,pi_slideshow_rs::mqtt_client=debug"). An empty
+// `dir` or a max_mb of 0 logs to stdout only, as does a directory that can't be written. An
+// export that can't be set up is reported and left out.
+pub fn init(level: &str, dir: &Path, max_mb: u64, files: usize, export: Option<log_export::Settings>) {
+    let (filter, level) = match EnvFilter::try_new(level) {
+        Ok(filter) => (filter, level),
+        Err(e) => {
+            eprintln!("Invalid --log-level '{}', logging at info: {}", level, e);
+            (EnvFilter::new("info"), "info")
+        }
+    };
+    *LEVEL.lock().unwrap() = level.to_string();
+    let (filter, handle) = reload::Layer::new(filter);
+    let _ = FILTER.set(handle);
+    let file = if dir.as_os_str().is_empty() || max_mb == 0 {
+        None
+    } else {
+        match RotatingFile::open(dir, max_mb * 1_000_000, files) {
+            Ok(file) => {
+                let _ = DIR.set(dir.to_path_buf());
+                Some(file)
+            }
+            Err(e) => {
+                eprintln!("Can't write logs to {}, logging to stdout only: {}", dir.display(), e);
+                None
+            }
+        }
+    };
+    let file_layer = file.map(|file| fmt::layer().with_ansi(false).with_writer(Mutex::new(file)));
+    let (export_layer, exporter) = match export.map(log_export::layer) {
+        Some(Ok((layer, exporter))) => (Some(layer), Some(exporter)),
+        Some(Err(e)) => {
+            eprintln!("Not exporting logs: {}", e);
+            (None, None)
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_ansi(io::stdout().is_terminal()).with_writer(io::stdout))
+        .with(file_layer)
+        .with(export_layer)
+        .init();
+    if let Some(exporter) = exporter {
+        exporter.start();
+    }
+
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        error!("💥 {}", info);
+        default_hook(info);
+    }));
+}
+
+// Give the log directory and everything in it to the --user account before root is dropped.
+// The open file keeps working either way, but rotating it renames and creates files in the
+// directory, and the export buffer and crash reports are written there too.
+pub fn hand_over(uid: u32, gid: u32) -> io::Result<()> {
+    match DIR.get() {
+        Some(dir) => chown_all(dir, uid, gid),
+        None => Ok(()),
+    }
+}
+
+fn chown_all(path: &Path, uid: u32, gid: u32) -> io::Result<()> {
+    std::os::unix::fs::lchown(path, Some(uid), Some(gid))?;
+    if fs::symlink_metadata(path)?.is_dir() {
+        for entry in fs::read_dir(path)? {
+            chown_all(&entry?.path(), uid, gid)?;
+        }
+    }
+    Ok(())
+}
+
+// The level or filter logging at now
+pub fn level() -> String {
+    LEVEL.lock().unwrap().clone()
+}
+
+// Log at `level` (RUST_LOG-style, as --log-level) from now on
+pub fn set_level(level: &str) -> Result<(), String> {
+    let level = level.trim();
+    let filter = EnvFilter::try_new(level).map_err(|e| format!("invalid log level '{}': {}", level, e))?;
+    let handle = FILTER.get().ok_or("logging isn't set up")?;
+    let mut current = LEVEL.lock().unwrap();
+    // Said at the old level, which is the one whoever asked was reading
+    info!("📝 Log level changing from {} to {}", current, level);
+    handle.reload(filter).map_err(|e| format!("can't change the log level: {}", e))?;
+    *current = level.to_string();
+    Ok(())
+}
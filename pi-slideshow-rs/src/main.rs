@@ -1,19 +1,24 @@
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 use image::{ImageError, Rgba, RgbaImage};
 use memmap2::MmapMut;
 use notify::{
     Event, EventKind, RecommendedWatcher, RecursiveMode, Result as NotifyResult, Watcher,
 };
+use rayon::prelude::*;
 use signal_hook::{consts::{SIGINT, SIGTERM}, iterator::Signals};
+use std::collections::HashMap;
 use std::fs::{File, OpenOptions};
 use std::io::{BufWriter, Result as IoResult, Seek, SeekFrom, Write};
 use std::os::unix::io::AsRawFd;
 use std::os::unix::fs::{FileTypeExt, PermissionsExt};
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::{broadcast, mpsc as async_mpsc};
+use transitions::Transition;
+use tracing::{error, info, info_span, trace, warn};
 
 #[derive(Debug, Clone, PartialEq)]
 enum Orientation {
@@ -25,16 +30,50 @@ enum Orientation {
 
 impl From<&str> for Orientation {
     fn from(s: &str) -> Self {
-        match s.to_lowercase().as_str() {
-            "portrait" => Orientation::Portrait,
-            "inverted_landscape" | "inverted-landscape" => Orientation::InvertedLandscape,
-            "inverted_portrait" | "inverted-portrait" => Orientation::InvertedPortrait,
-            _ => Orientation::Landscape,
-        }
+        Orientation::parse(s).unwrap_or(Orientation::Landscape)
     }
 }
 
 impl Orientation {
+    // Strict parse for validating user input; `From<&str>` falls back to landscape instead
+    fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "landscape" => Some(Orientation::Landscape),
+            "portrait" => Some(Orientation::Portrait),
+            "inverted_landscape" | "inverted-landscape" => Some(Orientation::InvertedLandscape),
+            "inverted_portrait" | "inverted-portrait" => Some(Orientation::InvertedPortrait),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Orientation::Landscape => "landscape",
+            Orientation::Portrait => "portrait",
+            Orientation::InvertedLandscape => "inverted_landscape",
+            Orientation::InvertedPortrait => "inverted_portrait",
+        }
+    }
+
+    // Size to draw a screen at so it is upright for the viewer once rotate_image turns it
+    // onto a `width` x `height` framebuffer
+    fn upright_size(&self, width: u32, height: u32) -> (u32, u32) {
+        match self {
+            Orientation::Portrait | Orientation::InvertedPortrait => (height, width),
+            Orientation::Landscape | Orientation::InvertedLandscape => (width, height),
+        }
+    }
+
+    // FB_ROTATE_* value giving the same picture as rotate_image
+    fn fb_rotate(&self) -> u32 {
+        match self {
+            Orientation::Landscape => 0,
+            Orientation::Portrait => 1,
+            Orientation::InvertedLandscape => 2,
+            Orientation::InvertedPortrait => 3,
+        }
+    }
+
     // Rotate an image based on the orientation
     fn rotate_image(&self, img: &RgbaImage) -> RgbaImage {
         match self {
@@ -50,25 +89,122 @@ mod mqtt_client;
 mod slideshow_controller;
 mod http_server;
 mod couchdb_client;
-
-use mqtt_client::{MqttClient, SlideshowCommand, TvStatus};
-use slideshow_controller::{ControllerConfig, SlideshowController};
-
-// Default landscape dimensions
+mod content_safety;
+mod text_profile;
+mod rate_limit;
+mod clock;
+mod health;
+mod fbdev;
+mod color;
+mod backlight;
+mod hotplug;
+mod eink;
+mod test_pattern;
+mod avif;
+mod video;
+mod image_cache;
+mod ken_burns;
+mod smart_crop;
+mod icc;
+mod image_store;
+mod downscale;
+mod simd;
+mod gles;
+mod easing;
+mod transitions;
+mod quality;
+mod shuffle;
+mod weighted;
+mod takeover;
+mod config_file;
+mod ticker;
+mod qr_code;
+mod web_page;
+mod text_slide;
+mod watchdog;
+mod cec;
+mod gpio;
+mod motion;
+mod updater;
+mod systemd;
+mod logging;
+mod log_export;
+mod offline_banner;
+mod throttle;
+mod provisioning;
+mod power;
+mod privileges;
+mod crash;
+mod perf;
+mod downloads;
+mod history;
+mod hardware;
+mod network;
+mod fingerprint;
+#[cfg(test)]
+mod transition_tests;
+
+use image_cache::{CacheKey, ImageCache};
+use mqtt_client::{MqttClient, SlideshowCommand};
+use signage_protocol::{SafeArea, SlideshowConfig, TextSlide, TransitionStats, TvStatus};
+use slideshow_controller::{ControllerConfig, Curtain, SlideStyle, SlideshowController};
+use test_pattern::TestPattern;
+use text_profile::TextProfile;
+use ticker::Ticker;
+
+// Mode assumed when a framebuffer can't report its own (--resolution)
 const DEFAULT_LANDSCAPE_WIDTH: u32 = 1920;
 const DEFAULT_LANDSCAPE_HEIGHT: u32 = 1080;
-const MAX_FRAMEBUFFER_SIZE: usize = 1920 * 1920 * 4; // Support up to 1920x1920
+// Text is sized for this many lines: scaled up in whole steps on taller screens, down on smaller ones
+const TEXT_DESIGN_HEIGHT: u32 = 1080;
+// How often DRM connector status is checked for a display being plugged back in
+const HOTPLUG_POLL_INTERVAL: Duration = Duration::from_secs(2);
+// E-paper refreshes flash the whole panel for about a second, so slides stay up far longer
+const EINK_DEFAULT_DELAY_SECS: u64 = 600;
+// Transition frame pacing when the driver can't wait for vsync (~30 FPS)
+const FALLBACK_FRAME_INTERVAL: Duration = Duration::from_millis(33);
+// Transition frames drawn ahead of the one being written; each full-screen frame is 8 MB at
+// 1080p, so the queue is kept short
+const TRANSITION_QUEUE_DEPTH: usize = 1;
+// Wait after a change to the config file before reloading it, so an editor's save is read once and whole
+const CONFIG_RELOAD_DELAY: Duration = Duration::from_millis(500);
+// Files the slideshow picks up from an image directory, by lowercase extension
+#[cfg(not(feature = "avif"))]
+const IMAGE_EXTENSIONS: [&str; 4] = ["png", "jpg", "jpeg", "webp"];
+#[cfg(feature = "avif")]
+const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "webp", "avif"];
+
+// The letterbox background image as decoded and sized for each display mode and orientation,
+// so letterboxed slides don't decode it again. Only the configured image's are kept.
+static LETTERBOX_BACKGROUNDS: Mutex<Vec<LetterboxBackground>> = Mutex::new(Vec::new());
+
+struct LetterboxBackground {
+    path: PathBuf,
+    width: u32,
+    height: u32,
+    orientation: Orientation,
+    image: RgbaImage,
+}
+
+// Images and video slides alike
+fn is_slide_extension(ext: &str) -> bool {
+    IMAGE_EXTENSIONS.contains(&ext) || video::VIDEO_EXTENSIONS.contains(&ext)
+}
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone, PartialEq)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    /// TOML file with defaults for any of these options (keys are the option names with underscores); SIGNAGE_* environment variables override it and flags override both
+    #[arg(long)]
+    config: Option<PathBuf>,
+
     /// Directory containing images to display
     #[arg(short, long, default_value = ".")]
     image_dir: PathBuf,
 
-    /// Duration in seconds to display each image
-    #[arg(short, long, default_value_t = 30)]
-    delay: u64,
+    /// Duration in seconds to display each image [default: 30, or 600 on e-paper]
+    #[arg(short, long)]
+    delay: Option<u64>,
 
     /// Transition duration in milliseconds
     #[arg(short, long, default_value_t = 1500)]
@@ -78,6 +214,18 @@ struct Args {
     #[arg(short, long, default_value = "/dev/fb0")]
     framebuffer: PathBuf,
 
+    /// Primary display: framebuffer, or it8951 for an IT8951 e-paper HAT on SPI
+    #[arg(long, default_value = "framebuffer")]
+    display: String,
+
+    /// spidev device of the e-paper HAT
+    #[arg(long, default_value = "/dev/spidev0.0")]
+    eink_spi: PathBuf,
+
+    /// E-paper panel VCOM in millivolts, as printed on the panel's ribbon cable (-1.50V is 1500)
+    #[arg(long, default_value_t = 1500)]
+    eink_vcom: u16,
+
     /// MQTT broker URL
     #[arg(long, default_value = "mqtt://192.168.1.215:1883")]
     mqtt_broker: String,
@@ -98,8 +246,8 @@ struct Args {
     #[arg(long)]
     tv_id: Option<String>,
 
-    /// Enable MQTT remote control
-    #[arg(long, default_value_t = true)]
+    /// Enable MQTT remote control (true or false)
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set, num_args = 0..=1, default_missing_value = "true")]
     enable_mqtt: bool,
 
     /// HTTP server port for local control
@@ -109,28 +257,502 @@ struct Args {
     /// Display orientation (landscape or portrait)
     #[arg(long, default_value = "landscape")]
     orientation: String,
+
+    /// Content safety mode: disable flashing transitions and reject animations above the flash threshold
+    #[arg(long, default_value_t = false)]
+    content_safety: bool,
+
+    /// Text rendering profile for generated screens (standard or high_contrast)
+    #[arg(long, default_value = "standard")]
+    text_profile: String,
+
+    /// Draw transitions with OpenGL ES on the GPU, falling back to software when EGL can't be set up
+    #[arg(long, default_value_t = false)]
+    gpu_transitions: bool,
+
+    /// Step transitions down to a lower frame rate, plain fades, then cuts as the CPU gets hot or overloaded (true or false)
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    adaptive_quality: bool,
+
+    /// Stamp the effect's name on transition frames for QA (true or false; on by default in debug builds only)
+    #[arg(long, default_value_t = cfg!(debug_assertions), action = clap::ArgAction::Set)]
+    debug_overlay: bool,
+
+    /// Rewrite the current frame every N seconds even when nothing changes (0 disables)
+    #[arg(long, default_value_t = 0)]
+    framebuffer_refresh_secs: u64,
+
+    /// Skip framebuffer writes that would not change the picture
+    #[arg(long, default_value_t = false)]
+    skip_redundant_writes: bool,
+
+    /// Framebuffer pixel format: auto (from the driver's bits per pixel), bgra8888, rgb888 or rgb565
+    #[arg(long, default_value = "auto")]
+    pixel_format: String,
+
+    /// Display mode to assume when the framebuffer can't report one: 720p, 1080p, 1440p, 4k or WIDTHxHEIGHT
+    #[arg(long, default_value = "1080p")]
+    resolution: String,
+
+    /// How images are sized to the display: fit (smooth resampling to fill the screen) or integer (whole-number factors only, for small SPI/DPI panels)
+    #[arg(long, default_value = "fit")]
+    scaling: String,
+
+    /// Largest frame in MiB the endpoint will convert and hold in memory (a 4K frame at 32bpp is about 32 MiB)
+    #[arg(long, default_value_t = 64, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=4095))]
+    frame_memory_cap_mb: usize,
+
+    /// Largest AVIF image in megapixels that will be decoded; bigger ones are skipped without decoding
+    #[arg(long, default_value_t = 36)]
+    avif_max_megapixels: u32,
+
+    /// Seconds an AVIF image may take to decode before it is skipped
+    #[arg(long, default_value_t = 15)]
+    avif_decode_timeout: u64,
+
+    /// ffmpeg decoder for H.264 video slides; "software" lets ffmpeg pick its own
+    #[arg(long, default_value = "h264_v4l2m2m")]
+    video_decoder: String,
+
+    /// ALSA device for video soundtracks (e.g. default or hdmi:CARD=vc4hdmi0); videos play muted without one
+    #[arg(long)]
+    video_audio_device: Option<String>,
+
+    /// Seconds the display loop may go without progress (e.g. a read hung on a failing SD card) before the endpoint restarts itself; 0 turns the watchdog off
+    #[arg(long, default_value_t = 120)]
+    watchdog_secs: u64,
+
+    /// How web page slides are captured: a headless Chromium binary, or the http(s) URL of a render service
+    #[arg(long, default_value = "chromium")]
+    web_renderer: String,
+
+    /// Show images with an embedded color profile (e.g. Adobe RGB) unconverted, saving the conversion time on slow devices
+    #[arg(long, default_value_t = false)]
+    skip_color_profiles: bool,
+
+    /// Scaled images kept in memory so redisplaying a slide skips decoding and resizing (0 turns the cache off)
+    #[arg(long, default_value_t = image_cache::DEFAULT_CAPACITY)]
+    image_cache_size: usize,
+
+    /// Scaled images kept on disk, in .frame_cache in the image directory, so they survive restarts; in MB (0 turns it off)
+    #[arg(long, default_value_t = image_cache::DEFAULT_DISK_MB)]
+    frame_cache_mb: u64,
+
+    /// Largest the image directory may grow in MB; past it, images no longer assigned to the TV are deleted, longest unassigned first (0 for no limit)
+    #[arg(long, default_value_t = 0)]
+    image_dir_max_mb: u64,
+
+    /// Free space in MB kept on the image directory's filesystem; below it quarantined and unassigned files are cleared, and downloads refused if that isn't enough (0 turns the check off)
+    #[arg(long, default_value_t = 100)]
+    min_free_disk_mb: u64,
+
+    /// Output gamma correction (0.5-3.0); above 1.0 lifts midtones, below deepens them
+    #[arg(long, default_value_t = 1.0)]
+    gamma: f64,
+
+    /// Output white point in kelvin (2000-10000); 6500 leaves colors unchanged, lower is warmer
+    #[arg(long, default_value_t = 6500)]
+    color_temperature: u32,
+
+    /// Output brightness in percent (1-100); a panel backlight is used when one exists
+    #[arg(long, default_value_t = 100)]
+    brightness: u8,
+
+    /// HDMI-CEC device (e.g. /dev/cec0) for powering the TV on and off and switching its input; CEC is off without one
+    #[arg(long)]
+    cec_device: Option<String>,
+
+    /// Physical button as PIN:ACTION (BCM pin to ground; next, previous, pause or hold), repeatable; pause and hold toggle on a second press
+    #[arg(long = "gpio-button")]
+    gpio_buttons: Vec<String>,
+
+    /// BCM pin of a PIR motion sensor; the display sleeps when nobody has passed it for --pir-idle-minutes and wakes on motion
+    #[arg(long)]
+    pir_pin: Option<u8>,
+
+    /// Minutes without motion before the display sleeps
+    #[arg(long, default_value_t = 5)]
+    pir_idle_minutes: u64,
+
+    /// Brightness in percent the display dims to while nobody is around; 0 blanks it instead
+    #[arg(long, default_value_t = 0)]
+    pir_idle_brightness: u8,
+
+    /// Release channel to update from: an http(s) URL serving a release manifest, or a channel name read from the release_{name} CouchDB document; updates are off without one
+    #[arg(long)]
+    update_channel: Option<String>,
+
+    /// Hex Ed25519 public key releases must be signed with
+    #[arg(long)]
+    update_public_key: Option<String>,
+
+    /// Minutes between checks of the release channel
+    #[arg(long, default_value_t = 60)]
+    update_check_minutes: u64,
+
+    /// Minutes without both MQTT and CouchDB before a small "offline since" banner is shown (0 = never)
+    #[arg(long, default_value_t = 5)]
+    offline_banner_minutes: u64,
+
+    /// Log level or RUST_LOG-style filter (error, warn, info, debug, trace; e.g. info,pi_slideshow_rs::mqtt_client=debug); RUST_LOG, then info, without it
+    #[arg(long)]
+    log_level: Option<String>,
+
+    /// Directory for the rotating log file, kept across reboots; empty logs to stdout only
+    #[arg(long, default_value = "/var/log/pi-slideshow")]
+    log_dir: PathBuf,
+
+    /// Size in MB at which the log file is rotated (0 = no log file)
+    #[arg(long, default_value_t = 10)]
+    log_max_mb: u64,
+
+    /// Log files kept, counting the current one
+    #[arg(long, default_value_t = 5)]
+    log_files: usize,
+
+    /// Also ship logs to a syslog server (syslog://host:514, syslog+tcp://host:601) or Grafana Loki (http://loki:3100)
+    #[arg(long)]
+    log_export: Option<String>,
+
+    /// Level or RUST_LOG-style filter for shipped logs, applied on top of --log-level
+    #[arg(long, default_value = "info")]
+    log_export_level: String,
+
+    /// MB of logs kept under --log-dir while the log server can't be reached, dropping the oldest (0 = none)
+    #[arg(long, default_value_t = 20)]
+    log_export_buffer_mb: u64,
+
+    /// When started as root, switch to this user once the display and backlight are open
+    #[arg(long)]
+    user: Option<String>,
+
+    /// With --config, start a Wi-Fi hotspot and setup page when no network is configured, and apply a signage-setup.toml from a USB stick (true or false)
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set, num_args = 0..=1, default_missing_value = "true")]
+    setup_mode: bool,
+
+    /// How non-landscape orientations are applied: software (rotate every frame) or hardware (framebuffer driver rotation, falling back to software)
+    #[arg(long, default_value = "software")]
+    rotation: String,
+
+    /// Extra display as DEVICE[:ORIENTATION[:IMAGE_DIR]], repeatable; mirrors the slideshow unless IMAGE_DIR gives it its own playlist
+    #[arg(long = "output")]
+    outputs: Vec<String>,
 }
 
 struct Config {
     image_dir: PathBuf,
+    display: DisplayBackend,
     display_duration: Duration,
     transition_duration: Duration,
     framebuffer_path: PathBuf,
     orientation: Orientation,
+    content_safety: bool,
+    text_profile: TextProfile,
+    debug_overlay: bool,
+    gpu_transitions: bool,
+    framebuffer_refresh: Option<Duration>,
+    skip_redundant_writes: bool,
+    pixel_format: Option<fbdev::PixelFormat>,
+    hardware_rotation: bool,
+    fallback_resolution: (u32, u32),
+    frame_memory_cap: usize,
+    scaling: Scaling,
+    gamma: f64,
+    color_temperature: u32,
+    brightness: u8,
+    image_cache_size: usize,
+    frame_cache_max_bytes: u64,
 }
 
 impl From<Args> for Config {
     fn from(args: Args) -> Self {
         Self {
+            display: DisplayBackend::from_args(&args),
+            display_duration: display_duration(&args),
             image_dir: args.image_dir,
-            display_duration: Duration::from_secs(args.delay),
             transition_duration: Duration::from_millis(args.transition),
             framebuffer_path: args.framebuffer,
             orientation: Orientation::from(args.orientation.as_str()),
+            content_safety: args.content_safety,
+            text_profile: TextProfile::from(args.text_profile.as_str()),
+            debug_overlay: args.debug_overlay,
+            gpu_transitions: args.gpu_transitions,
+            framebuffer_refresh: refresh_interval(args.framebuffer_refresh_secs),
+            skip_redundant_writes: args.skip_redundant_writes,
+            pixel_format: pixel_format_override(&args.pixel_format),
+            hardware_rotation: hardware_rotation(&args.rotation),
+            fallback_resolution: resolution_preset(&args.resolution),
+            frame_memory_cap: frame_memory_cap(args.frame_memory_cap_mb),
+            scaling: scaling_mode(&args.scaling),
+            gamma: args.gamma,
+            color_temperature: args.color_temperature,
+            brightness: args.brightness,
+            image_cache_size: args.image_cache_size,
+            frame_cache_max_bytes: args.frame_cache_mb * 1_000_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum DisplayBackend {
+    Framebuffer,
+    It8951 { spi: PathBuf, vcom_mv: u16 },
+}
+
+impl DisplayBackend {
+    fn from_args(args: &Args) -> Self {
+        match args.display.to_lowercase().as_str() {
+            "it8951" | "eink" | "epaper" => DisplayBackend::It8951 { spi: args.eink_spi.clone(), vcom_mv: args.eink_vcom },
+            "framebuffer" | "fb" => DisplayBackend::Framebuffer,
+            other => {
+                warn!("Unknown display '{}', using the framebuffer", other);
+                DisplayBackend::Framebuffer
+            }
+        }
+    }
+
+    fn is_eink(&self) -> bool {
+        matches!(self, DisplayBackend::It8951 { .. })
+    }
+}
+
+// --frame-memory-cap-mb in bytes; the range clap allows fits a 32-bit usize, and the
+// multiplication saturates for a value that arrives some other way
+fn frame_memory_cap(mb: usize) -> usize {
+    mb.saturating_mul(1024 * 1024)
+}
+
+fn display_duration(args: &Args) -> Duration {
+    let default = if DisplayBackend::from_args(args).is_eink() { EINK_DEFAULT_DELAY_SECS } else { 30 };
+    Duration::from_secs(args.delay.unwrap_or(default))
+}
+
+// The primary display. Framebuffers fall back to a file when the device is missing;
+// an e-paper panel that doesn't answer is an error.
+fn open_display(backend: &DisplayBackend, framebuffer_path: &Path, fallback: (u32, u32), pixel_format: Option<fbdev::PixelFormat>) -> IoResult<Framebuffer> {
+    match backend {
+        DisplayBackend::Framebuffer => Framebuffer::new(fallback.0, fallback.1, framebuffer_path, pixel_format),
+        DisplayBackend::It8951 { spi, vcom_mv } => Ok(Framebuffer::eink(eink::It8951::open(spi, *vcom_mv)?)),
+    }
+}
+
+// "auto" (or anything unrecognized) leaves the choice to the driver's reported bits per pixel
+fn pixel_format_override(name: &str) -> Option<fbdev::PixelFormat> {
+    if name.eq_ignore_ascii_case("auto") {
+        return None;
+    }
+    let format = fbdev::PixelFormat::parse(name);
+    if format.is_none() {
+        warn!("Unknown pixel format '{}', detecting it from the framebuffer instead", name);
+    }
+    format
+}
+
+// Named presets or WIDTHxHEIGHT; anything unrecognized falls back to 1080p
+fn resolution_preset(name: &str) -> (u32, u32) {
+    match name.to_lowercase().as_str() {
+        "720p" | "hd" => (1280, 720),
+        "1080p" | "fhd" => (DEFAULT_LANDSCAPE_WIDTH, DEFAULT_LANDSCAPE_HEIGHT),
+        "1440p" | "qhd" => (2560, 1440),
+        "2160p" | "4k" | "uhd" => (3840, 2160),
+        other => other.split_once('x')
+            .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+            .filter(|&(width, height)| width > 0 && height > 0)
+            .unwrap_or_else(|| {
+                warn!("Unknown resolution '{}', assuming {}x{}", name, DEFAULT_LANDSCAPE_WIDTH, DEFAULT_LANDSCAPE_HEIGHT);
+                (DEFAULT_LANDSCAPE_WIDTH, DEFAULT_LANDSCAPE_HEIGHT)
+            }),
+    }
+}
+
+fn hardware_rotation(name: &str) -> bool {
+    match name.to_lowercase().as_str() {
+        "hardware" => true,
+        "software" => false,
+        _ => {
+            warn!("Unknown rotation mode '{}', rotating in software", name);
+            false
+        }
+    }
+}
+
+// How a source image is sized to a display before it is centered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scaling {
+    // As large as the aspect ratio allows, resampled with Lanczos
+    Fit,
+    // Whole-number factors only: enlarged by pixel repetition, reduced by averaging blocks.
+    // Each source pixel lands on whole panel pixels, which keeps text and line art crisp on
+    // 480x320 status TFTs where fractional resampling turns it to mush.
+    Integer,
+}
+
+fn scaling_mode(name: &str) -> Scaling {
+    match name.to_lowercase().as_str() {
+        "fit" => Scaling::Fit,
+        "integer" => Scaling::Integer,
+        _ => {
+            warn!("Unknown scaling mode '{}', fitting images to the screen", name);
+            Scaling::Fit
+        }
+    }
+}
+
+// How an image is sized within the safe area, picked per image in its metadata (fit_mode)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FitMode {
+    // All of the image, letterboxed, sized by the display's Scaling
+    Contain,
+    // Fills the area at its own aspect ratio, cropping what overhangs; for full-bleed posters
+    Cover,
+    // Fills the area exactly, distorting the aspect ratio
+    Stretch,
+    // One image pixel per screen pixel, centered and cropped to the area
+    Native,
+}
+
+impl FitMode {
+    // Unknown modes keep the whole image visible
+    fn from_name(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "cover" => FitMode::Cover,
+            "stretch" => FitMode::Stretch,
+            "native" | "1:1" => FitMode::Native,
+            _ => FitMode::Contain,
+        }
+    }
+}
+
+// What fills the screen around an image that doesn't cover it (TvConfig letterbox_*)
+#[derive(Debug, Clone, PartialEq)]
+enum Letterbox {
+    Color(Rgba<u8>),
+    // The image itself, scaled to cover the screen and blurred
+    Blur,
+    // A branded background cropped to cover the screen; the color stands in until it is on disk
+    Image(PathBuf, Rgba<u8>),
+}
+
+impl Default for Letterbox {
+    fn default() -> Self {
+        Letterbox::Color(Rgba([0, 0, 0, 255]))
+    }
+}
+
+impl Letterbox {
+    fn is_valid_fill(fill: &str) -> bool {
+        matches!(fill.to_lowercase().as_str(), "color" | "blur" | "image")
+    }
+
+    // Unknown fills and colors fall back to black bars
+    fn from_config(fill: &str, color: &str, image: Option<PathBuf>) -> Self {
+        let color = signage_protocol::couchdb::parse_hex_color(color)
+            .map_or(Rgba([0, 0, 0, 255]), |[r, g, b]| Rgba([r, g, b, 255]));
+        match (fill.to_lowercase().as_str(), image) {
+            ("blur", _) => Letterbox::Blur,
+            ("image", Some(image)) => Letterbox::Image(image, color),
+            _ => Letterbox::Color(color),
+        }
+    }
+
+    // Screen-sized background for `image`, already rotated to the framebuffer's orientation
+    fn background(&self, image: &RgbaImage, width: u32, height: u32, orientation: &Orientation) -> RgbaImage {
+        match self {
+            Letterbox::Color(color) => RgbaImage::from_pixel(width, height, *color),
+            Letterbox::Blur => {
+                // Scaled to cover the screen, so the fill keeps the image's proportions. Shrink
+                // hard before blurring: the result is meant to be featureless anyway, and
+                // blurring at full resolution takes seconds on a Pi
+                let (image_width, image_height) = image.dimensions();
+                let (crop_width, crop_height) = if image_width as u64 * height as u64 > image_height as u64 * width as u64 {
+                    ((image_height as u64 * width as u64 / height as u64) as u32, image_height)
+                } else {
+                    (image_width, (image_width as u64 * height as u64 / width as u64) as u32)
+                };
+                let crop = image::imageops::crop_imm(
+                    image,
+                    (image_width - crop_width) / 2,
+                    (image_height - crop_height) / 2,
+                    crop_width.max(1),
+                    crop_height.max(1),
+                );
+                let small = image::imageops::resize(&*crop, (width / 16).max(1), (height / 16).max(1), image::imageops::FilterType::Triangle);
+                let mut background = image::imageops::resize(&image::imageops::blur(&small, 3.0), width, height, image::imageops::FilterType::Triangle);
+                // Darken so the sharp image in front stands out
+                for pixel in background.pixels_mut() {
+                    let [r, g, b, _] = pixel.0;
+                    *pixel = Rgba([r / 2, g / 2, b / 2, 255]);
+                }
+                background
+            }
+            Letterbox::Image(path, color) => {
+                let cached = LETTERBOX_BACKGROUNDS.lock().unwrap().iter()
+                    .find(|cached| cached.path == *path && cached.width == width && cached.height == height && cached.orientation == *orientation)
+                    .map(|cached| cached.image.clone());
+                if let Some(background) = cached {
+                    return background;
+                }
+
+                let loaded = image::io::Reader::open(path)
+                    .and_then(|reader| reader.with_guessed_format())
+                    .map_err(ImageError::IoError)
+                    .and_then(|reader| reader.decode());
+                match loaded {
+                    Ok(loaded) => {
+                        let rotated = orientation.rotate_image(&apply_exif_orientation(path, loaded.to_rgba8()));
+                        let background = image::DynamicImage::ImageRgba8(rotated)
+                            .resize_to_fill(width, height, image::imageops::FilterType::Triangle)
+                            .to_rgba8();
+                        let mut backgrounds = LETTERBOX_BACKGROUNDS.lock().unwrap();
+                        backgrounds.retain(|cached| cached.path == *path);
+                        backgrounds.push(LetterboxBackground {
+                            path: path.clone(),
+                            width,
+                            height,
+                            orientation: orientation.clone(),
+                            image: background.clone(),
+                        });
+                        background
+                    }
+                    Err(e) => {
+                        error!("Failed to load letterbox background {}: {}", path.display(), e);
+                        RgbaImage::from_pixel(width, height, *color)
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Try to have the driver show `orientation` and return the rotation software still has to
+// apply: none when the driver took it, all of it otherwise
+fn apply_rotation(fb: &mut Framebuffer, hardware: bool, orientation: &Orientation) -> Orientation {
+    if !hardware {
+        return orientation.clone();
+    }
+    match fb.set_hardware_rotation(orientation.fb_rotate()) {
+        Ok(()) => {
+            info!("🔄 Driver rotation set for {} ({}x{})", orientation.name(), fb.width, fb.height);
+            Orientation::Landscape
+        }
+        Err(e) => {
+            info!("Hardware rotation unavailable ({}), rotating {} in software", e, orientation.name());
+            // Don't leave an earlier driver rotation stacked on top of the software one
+            let _ = fb.set_hardware_rotation(0);
+            orientation.clone()
         }
     }
 }
 
+// 0 seconds means the keep-alive refresh is off
+fn refresh_interval(secs: u64) -> Option<Duration> {
+    if secs == 0 {
+        None
+    } else {
+        Some(Duration::from_secs(secs))
+    }
+}
+
 #[derive(Debug, Clone)]
 enum TransitionType {
     Fade,
@@ -153,11 +775,17 @@ enum TransitionType {
     CircularWipe,
     DiagonalWipe,
     Pixelate,
+    Cube,
+    PageCurl,
+    Blinds,
+    Checkerboard,
+    ZoomIn,
+    ZoomOut,
 }
 
 impl TransitionType {
-    fn get_random() -> Self {
-        let transitions = [
+    fn all() -> [Self; 26] {
+        [
             Self::Fade,
             Self::Dissolve,
             Self::SlideLeft,
@@ -178,34 +806,96 @@ impl TransitionType {
             Self::CircularWipe,
             Self::DiagonalWipe,
             Self::Pixelate,
-        ];
-        transitions[fastrand::usize(..transitions.len())].clone()
+            Self::Cube,
+            Self::PageCurl,
+            Self::Blinds,
+            Self::Checkerboard,
+            Self::ZoomIn,
+            Self::ZoomOut,
+        ]
+    }
+
+    // Any registered effect
+    fn get_random() -> Self {
+        let names = transitions::names();
+        Self::from_string(names[fastrand::usize(..names.len())]).unwrap_or(Self::Fade)
+    }
+
+    // Dissolve and pixelate change scattered pixels all over the screen at once, which can read
+    // as rapid flashing; each effect says for itself
+    fn is_flash_safe(&self) -> bool {
+        transitions::get(self.key()).is_none_or(|effect| effect.is_flash_safe())
+    }
+
+    // Substitute a plain fade for effects that are unsafe under content safety mode
+    fn flash_safe(self) -> Self {
+        if self.is_flash_safe() {
+            self
+        } else {
+            Self::Fade
+        }
+    }
+
+    fn get_random_flash_safe() -> Self {
+        loop {
+            let transition = Self::get_random();
+            if transition.is_flash_safe() {
+                return transition;
+            }
+        }
     }
 
     fn from_string(s: &str) -> Option<Self> {
-        match s.to_lowercase().as_str() {
-            "fade" => Some(Self::Fade),
-            "dissolve" => Some(Self::Dissolve),
-            "slide_left" => Some(Self::SlideLeft),
-            "slide_right" => Some(Self::SlideRight),
-            "slide_up" => Some(Self::SlideUp),
-            "slide_down" => Some(Self::SlideDown),
-            "wipe_left" => Some(Self::WipeLeft),
-            "wipe_right" => Some(Self::WipeRight),
-            "wipe_up" => Some(Self::WipeUp),
-            "wipe_down" => Some(Self::WipeDown),
-            "morph" => Some(Self::Morph),
-            "bounce" => Some(Self::Bounce),
-            "elastic" => Some(Self::Elastic),
-            "ease_in" => Some(Self::EaseIn),
-            "ease_out" => Some(Self::EaseOut),
-            "ease_in_out" => Some(Self::EaseInOut),
-            "accelerated" => Some(Self::Accelerated),
-            "circular_wipe" => Some(Self::CircularWipe),
-            "diagonal_wipe" => Some(Self::DiagonalWipe),
-            "pixelate" => Some(Self::Pixelate),
-            "random" => Some(Self::get_random()),
-            _ => None,
+        let name = s.to_lowercase();
+        match name.as_str() {
+            "random" => return Some(Self::get_random()),
+            // Names the management dashboard used to offer
+            "slide" => return Some(Self::SlideLeft),
+            "wipe" => return Some(Self::WipeLeft),
+            _ => {}
+        }
+        Self::all().into_iter().find(|transition| transition.key() == name)
+    }
+
+    // How long it plays: the TV's length for this effect (TvConfig transition_durations) when
+    // set, otherwise `global` (transition_duration) scaled by the effect's own pacing
+    fn duration(&self, global: Duration, overrides: &HashMap<String, u64>) -> Duration {
+        if let Some(&millis) = overrides.get(self.key()) {
+            return Duration::from_millis(millis);
+        }
+        let scale = transitions::get(self.key()).map_or(1.0, |effect| effect.duration_scale());
+        Duration::try_from_secs_f32(global.as_secs_f32() * scale).unwrap_or(global)
+    }
+
+    // The name it is configured by (TvConfig transition_effect) and registered under
+    fn key(&self) -> &'static str {
+        match self {
+            Self::Fade => "fade",
+            Self::Dissolve => "dissolve",
+            Self::SlideLeft => "slide_left",
+            Self::SlideRight => "slide_right",
+            Self::SlideUp => "slide_up",
+            Self::SlideDown => "slide_down",
+            Self::WipeLeft => "wipe_left",
+            Self::WipeRight => "wipe_right",
+            Self::WipeUp => "wipe_up",
+            Self::WipeDown => "wipe_down",
+            Self::Morph => "morph",
+            Self::Bounce => "bounce",
+            Self::Elastic => "elastic",
+            Self::EaseIn => "ease_in",
+            Self::EaseOut => "ease_out",
+            Self::EaseInOut => "ease_in_out",
+            Self::Accelerated => "accelerated",
+            Self::CircularWipe => "circular_wipe",
+            Self::DiagonalWipe => "diagonal_wipe",
+            Self::Pixelate => "pixelate",
+            Self::Cube => "cube",
+            Self::PageCurl => "page_curl",
+            Self::Blinds => "blinds",
+            Self::Checkerboard => "checkerboard",
+            Self::ZoomIn => "zoom_in",
+            Self::ZoomOut => "zoom_out",
         }
     }
 
@@ -231,34 +921,154 @@ impl TransitionType {
             Self::CircularWipe => "CIRCULAR WIPE",
             Self::DiagonalWipe => "DIAGONAL WIPE",
             Self::Pixelate => "PIXELATE",
+            Self::Cube => "CUBE",
+            Self::PageCurl => "PAGE CURL",
+            Self::Blinds => "BLINDS",
+            Self::Checkerboard => "CHECKERBOARD",
+            Self::ZoomIn => "ZOOM IN",
+            Self::ZoomOut => "ZOOM OUT",
         }
     }
 }
 
+// One step of a transition, drawn ahead of being written to the displays
+struct TransitionFrame {
+    // The frame interval slot it was drawn for, counted from the transition's start
+    slot: u32,
+    primary: RgbaImage,
+    // Frames for mirroring outputs, by index into the outputs
+    mirrors: Vec<(usize, RgbaImage)>,
+}
+
+// A rectangle of the screen, in framebuffer pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Region {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
 #[derive(Debug)]
 enum SlideshowEvent {
     NewImage(PathBuf),
     Shutdown,
 }
 
+// Everything fitting a slide to a display depends on, copied out of its Framebuffer so slides
+// can be prepared away from the display loop
+#[derive(Debug, Clone)]
+struct Layout {
+    width: u32,
+    height: u32,
+    scaling: Scaling,
+    letterbox: Letterbox,
+    safe_area: SafeArea,
+    fit: FitMode,
+    // Cover-fit crops follow the picture's content instead of its center (TvConfig smart_crop)
+    smart_crop: bool,
+    // Code for the link in the slide's metadata (qr_url)
+    qr: Option<qr_code::QrOverlay>,
+}
+
+impl Layout {
+    // The part of the screen images and text are drawn in, as (x, y, width, height). Margins
+    // too large for the mode are scaled back so at least half of each dimension is left.
+    fn safe_rect(&self) -> (u32, u32, u32, u32) {
+        safe_rect(self.width, self.height, &self.safe_area)
+    }
+
+    // The size large images may be reduced towards while loading, or None when every source
+    // pixel counts: native-size crops and whole-number scaling
+    fn decode_target(&self) -> Option<(u32, u32)> {
+        let (_, _, width, height) = self.safe_rect();
+        match (self.fit, self.scaling) {
+            (FitMode::Native, _) | (FitMode::Contain, Scaling::Integer) => None,
+            _ => Some((width, height)),
+        }
+    }
+}
+
+fn safe_rect(width: u32, height: u32, safe_area: &SafeArea) -> (u32, u32, u32, u32) {
+    let (left, right) = limit_margins(safe_area.left, safe_area.right, width / 2);
+    let (top, bottom) = limit_margins(safe_area.top, safe_area.bottom, height / 2);
+    (left, top, width - left - right, height - top - bottom)
+}
+
 struct Framebuffer {
     file: Option<File>,
     mmap: Option<MmapMut>,
     width: u32,
     height: u32,
+    pixel_format: fbdev::PixelFormat,
+    // Bytes between the starts of consecutive scanlines in device memory
+    line_length: usize,
     max_buffer_size: usize,
+    // Upper bound on one converted frame, whatever the mode (--frame-memory-cap-mb)
+    memory_cap: usize,
     fallback_file: Option<BufWriter<File>>,
+    // Some displays blank when the source never changes, others flicker on every write;
+    // both behaviors are opt-in and need a copy of the last frame
+    refresh_interval: Option<Duration>,
+    skip_redundant_writes: bool,
+    last_frame: Vec<u8>,
+    last_write: Instant,
+    // None when the driver can't pan; frames are then drawn straight into the visible buffer
+    double_buffer: Option<fbdev::DoubleBuffer>,
+    // Cleared the first time FBIO_WAITFORVSYNC fails
+    vsync: bool,
+    color: color::ColorAdjustment,
+    scaling: Scaling,
+    letterbox: Letterbox,
+    // Overscan margins kept clear of images and text
+    safe_area: SafeArea,
+    // Set for e-paper panels, which take whole gray frames instead of device memory writes
+    eink: Option<eink::It8951>,
+    // Scrolling text painted over every frame and redrawn on its own in between
+    ticker: Option<Ticker>,
+    // A dot in the corner of every frame while the slide is held (--debug-overlay)
+    held_marker: bool,
+    // Painted over every frame while the endpoint is offline (--offline-banner-minutes)
+    offline_banner: Option<offline_banner::Banner>,
 }
 
 impl Framebuffer {
-    fn new(width: u32, height: u32, framebuffer_path: &Path) -> IoResult<Self> {
-        println!("🔧 Initializing framebuffer with dimensions: {}x{}", width, height);
-        
-        // Validate that we're using the correct physical display dimensions
-        if width != DEFAULT_LANDSCAPE_WIDTH || height != DEFAULT_LANDSCAPE_HEIGHT {
-            println!("⚠️  WARNING: Framebuffer dimensions {}x{} don't match expected physical display dimensions {}x{}", 
-                     width, height, DEFAULT_LANDSCAPE_WIDTH, DEFAULT_LANDSCAPE_HEIGHT);
+    fn eink(panel: eink::It8951) -> Self {
+        let (width, height) = (panel.width(), panel.height());
+        Framebuffer {
+            file: None,
+            mmap: None,
+            fallback_file: None,
+            max_buffer_size: (width * height * 4) as usize,
+            memory_cap: usize::MAX,
+            refresh_interval: None,
+            skip_redundant_writes: false,
+            last_frame: Vec::new(),
+            last_write: Instant::now(),
+            width,
+            height,
+            pixel_format: fbdev::PixelFormat::Bgra8888,
+            line_length: (width * 4) as usize,
+            double_buffer: None,
+            vsync: false,
+            color: color::ColorAdjustment::default(),
+            scaling: Scaling::Fit,
+            letterbox: Letterbox::default(),
+            safe_area: SafeArea::default(),
+            eink: Some(panel),
+            ticker: None,
+            held_marker: false,
+            offline_banner: None,
         }
+    }
+
+    fn is_eink(&self) -> bool {
+        self.eink.is_some()
+    }
+
+    // `width` and `height` are only used when the device can't report its own mode;
+    // `pixel_format` overrides the format implied by the mode's bits per pixel
+    fn new(width: u32, height: u32, framebuffer_path: &Path, pixel_format: Option<fbdev::PixelFormat>) -> IoResult<Self> {
         match OpenOptions::new()
             .read(true)
             .write(true)
@@ -267,12 +1077,31 @@ impl Framebuffer {
             Ok(f) => {
                 // Get framebuffer info using ioctl
                 Self::log_framebuffer_info(&f);
+                let (mode, double_buffer) = match fbdev::FbMode::query(&f) {
+                    Ok(mode) => match fbdev::DoubleBuffer::enable(&f) {
+                        Ok(double_buffer) => {
+                            info!("🔧 Double buffering enabled, flipping pages with FBIOPAN_DISPLAY");
+                            // Growing the virtual screen can reallocate video memory
+                            (fbdev::FbMode::query(&f).unwrap_or(mode), Some(double_buffer))
+                        }
+                        Err(e) => {
+                            info!("Double buffering unavailable ({}), drawing into the visible buffer", e);
+                            (mode, None)
+                        }
+                    },
+                    Err(e) => {
+                        info!("⚠️  Could not query framebuffer mode ({}), assuming {}x{} at 32bpp", e, width, height);
+                        (fbdev::FbMode::assumed(width, height), None)
+                    }
+                };
+                Self::log_mode(&mode);
+                let pixel_format = Self::resolve_pixel_format(&mode, pixel_format);
 
                 // Try to memory map the framebuffer
                 match unsafe { MmapMut::map_mut(&f) } {
                     Ok(mmap) => {
                         if mmap.len() == 0 {
-                            println!("Memory-mapped framebuffer has 0 bytes, falling back to direct writes");
+                            info!("Memory-mapped framebuffer has 0 bytes, falling back to direct writes");
                             // Reset file to write-only mode for direct writes
                             drop(mmap);
                             drop(f);
@@ -281,12 +1110,29 @@ impl Framebuffer {
                                 file: Some(f),
                                 mmap: None,
                                 fallback_file: None,
-                                max_buffer_size: MAX_FRAMEBUFFER_SIZE,
-                                width,
-                                height,
+                                max_buffer_size: mode.memory_len,
+                                memory_cap: usize::MAX,
+                                refresh_interval: None,
+                                skip_redundant_writes: false,
+                                last_frame: Vec::new(),
+                                last_write: Instant::now(),
+                                width: mode.width,
+                                height: mode.height,
+                                pixel_format,
+                                line_length: mode.line_length as usize,
+                                double_buffer,
+                                vsync: true,
+                                color: color::ColorAdjustment::default(),
+                                scaling: Scaling::Fit,
+                                letterbox: Letterbox::default(),
+                                safe_area: SafeArea::default(),
+                                eink: None,
+                                ticker: None,
+                                held_marker: false,
+                                offline_banner: None,
                             })
                         } else {
-                            println!(
+                            info!(
                                 "Successfully memory-mapped framebuffer device (size: {} bytes)",
                                 mmap.len()
                             );
@@ -294,14 +1140,31 @@ impl Framebuffer {
                                 file: Some(f),
                                 mmap: Some(mmap),
                                 fallback_file: None,
-                                max_buffer_size: MAX_FRAMEBUFFER_SIZE,
-                                width,
-                                height,
+                                max_buffer_size: mode.memory_len,
+                                memory_cap: usize::MAX,
+                                refresh_interval: None,
+                                skip_redundant_writes: false,
+                                last_frame: Vec::new(),
+                                last_write: Instant::now(),
+                                width: mode.width,
+                                height: mode.height,
+                                pixel_format,
+                                line_length: mode.line_length as usize,
+                                double_buffer,
+                                vsync: true,
+                                color: color::ColorAdjustment::default(),
+                                scaling: Scaling::Fit,
+                                letterbox: Letterbox::default(),
+                                safe_area: SafeArea::default(),
+                                eink: None,
+                                ticker: None,
+                                held_marker: false,
+                                offline_banner: None,
                             })
                         }
                     }
                     Err(mmap_err) => {
-                        println!("Memory mapping failed ({}), trying direct writes", mmap_err);
+                        info!("Memory mapping failed ({}), trying direct writes", mmap_err);
                         // Reset file to write-only mode for direct writes
                         drop(f);
                         let f = OpenOptions::new().write(true).open(framebuffer_path)?;
@@ -309,122 +1172,713 @@ impl Framebuffer {
                             file: Some(f),
                             mmap: None,
                             fallback_file: None,
-                            max_buffer_size: MAX_FRAMEBUFFER_SIZE,
-                            width,
-                            height,
+                            max_buffer_size: mode.memory_len,
+                            memory_cap: usize::MAX,
+                            refresh_interval: None,
+                            skip_redundant_writes: false,
+                            last_frame: Vec::new(),
+                            last_write: Instant::now(),
+                            width: mode.width,
+                            height: mode.height,
+                            pixel_format,
+                            line_length: mode.line_length as usize,
+                            double_buffer,
+                            vsync: true,
+                            color: color::ColorAdjustment::default(),
+                            scaling: Scaling::Fit,
+                            letterbox: Letterbox::default(),
+                            safe_area: SafeArea::default(),
+                            eink: None,
+                            ticker: None,
+                            held_marker: false,
+                            offline_banner: None,
                         })
                     }
                 }
             }
             Err(e) => {
-                println!("Failed to open framebuffer ({}), using file fallback", e);
+                info!("Failed to open framebuffer ({}), using file fallback", e);
                 let fallback = File::create("framebuffer_output.raw")?;
+                let mode = fbdev::FbMode::assumed(width, height);
+                Self::log_mode(&mode);
+                let pixel_format = Self::resolve_pixel_format(&mode, pixel_format);
                 Ok(Framebuffer {
                     file: None,
                     mmap: None,
                     fallback_file: Some(BufWriter::new(fallback)),
-                    max_buffer_size: MAX_FRAMEBUFFER_SIZE,
-                    width,
-                    height,
+                    max_buffer_size: mode.memory_len,
+                    memory_cap: usize::MAX,
+                    refresh_interval: None,
+                    skip_redundant_writes: false,
+                    last_frame: Vec::new(),
+                    last_write: Instant::now(),
+                    width: mode.width,
+                    height: mode.height,
+                    pixel_format,
+                    line_length: mode.line_length as usize,
+                    double_buffer: None,
+                    vsync: false,
+                    color: color::ColorAdjustment::default(),
+                    scaling: Scaling::Fit,
+                    letterbox: Letterbox::default(),
+                    safe_area: SafeArea::default(),
+                    eink: None,
+                    ticker: None,
+                    held_marker: false,
+                    offline_banner: None,
                 })
             }
         }
     }
 
-    fn display_buffer(&mut self, buffer: &[u8]) -> IoResult<()> {
-        let expected_size = (self.width * self.height * 4) as usize;
-        println!("📺 Displaying buffer: {} bytes (expected: {} bytes for {}x{})", 
+    // Takes the buffer so it can be kept as the last frame without copying it
+    fn display_buffer(&mut self, buffer: Vec<u8>) -> IoResult<()> {
+        let expected_size = (self.width * self.height) as usize * self.pixel_format.bytes_per_pixel();
+        trace!("📺 Displaying buffer: {} bytes (expected: {} bytes for {}x{})", 
                  buffer.len(), expected_size, self.width, self.height);
         
         if buffer.len() != expected_size {
-            println!("⚠️  WARNING: Buffer size {} doesn't match expected size {} for framebuffer dimensions", 
+            info!("⚠️  WARNING: Buffer size {} doesn't match expected size {} for framebuffer dimensions", 
                      buffer.len(), expected_size);
         }
         
-        if buffer.len() > self.max_buffer_size {
+        if buffer.len() > self.frame_budget() {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidInput,
                 format!(
                     "Buffer size {} exceeds maximum framebuffer size {}",
                     buffer.len(),
-                    self.max_buffer_size
+                    self.frame_budget()
                 ),
             ));
         }
 
-        if let Some(ref mut mmap) = self.mmap {
-            // Use memory mapping for fast, efficient writes
-            let copy_len = std::cmp::min(buffer.len(), mmap.len());
-            if copy_len == 0 {
-                println!("Warning: mmap size is 0 bytes, cannot write to framebuffer. Buffer size: {}, mmap size: {}", buffer.len(), mmap.len());
-                return Ok(());
-            }
-            mmap[..copy_len].copy_from_slice(&buffer[..copy_len]);
-            mmap.flush()?;
-        } else if let Some(ref mut file) = self.file {
-            // Fallback to direct file writes - reset to beginning and write entire buffer
-            file.seek(SeekFrom::Start(0))?;
-            
-            // Writing buffer to framebuffer device
-            
-            // For framebuffer devices, we should write the full buffer at once for proper synchronization
-            // but break it into reasonable chunks to avoid system limits
-            const CHUNK_SIZE: usize = 4096; // 4KB chunks for better compatibility
-            let mut bytes_written = 0;
-            
-            for chunk in buffer.chunks(CHUNK_SIZE) {
-                match file.write_all(chunk) {
-                    Ok(()) => {
-                        bytes_written += chunk.len();
-                    }
-                    Err(e) => {
-                        eprintln!("Failed to write chunk to framebuffer at offset {}: {}", bytes_written, e);
-                        return Err(e);
-                    }
-                }
-            }
-            
-            // Ensure data is written to the device
-            file.flush()?;
-            // Successfully wrote framebuffer data
-        } else if let Some(ref mut fallback) = self.fallback_file {
-            fallback.write_all(buffer)?;
-            fallback.flush()?;
-            println!("Wrote {} bytes to fallback file", buffer.len());
+        if self.skip_redundant_writes && buffer == self.last_frame {
+            trace!("Frame unchanged, skipping framebuffer write");
+            return Ok(());
         }
+
+        let written = self.write_buffer(&buffer);
+        health::record_framebuffer_write(written.is_ok());
+        written?;
+
+        // Kept for the keep-alive refresh, redundant write checks and the dirty region of the
+        // next full image
+        self.last_frame = buffer;
+        self.last_write = Instant::now();
         Ok(())
     }
 
-    fn display_image(&mut self, image: &RgbaImage) -> IoResult<()> {
-        let buffer = self.image_to_bgra_buffer(image);
-        self.display_buffer(&buffer)
+    fn set_refresh_policy(&mut self, refresh_interval: Option<Duration>, skip_redundant_writes: bool) {
+        if refresh_interval != self.refresh_interval || skip_redundant_writes != self.skip_redundant_writes {
+            info!("Framebuffer refresh policy: keep-alive {:?}, skip redundant writes {}", refresh_interval, skip_redundant_writes);
+            self.refresh_interval = refresh_interval;
+            self.skip_redundant_writes = skip_redundant_writes;
+        }
+    }
+
+    // Power the display down or back up: FBIOBLANK where the driver supports it, otherwise
+    // the Pi firmware's HDMI power control
+    fn set_blanked(&mut self, blanked: bool) -> IoResult<()> {
+        let file = match self.file {
+            Some(ref file) if self.fallback_file.is_none() => file,
+            _ => return Ok(()),
+        };
+        let ioctl_error = match fbdev::blank(file, blanked) {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+
+        let output = std::process::Command::new("vcgencmd")
+            .args(["display_power", if blanked { "0" } else { "1" }])
+            .output();
+        match output {
+            Ok(output) if output.status.success() => Ok(()),
+            _ => Err(ioctl_error),
+        }
+    }
+
+    // Largest frame this output will convert or write: the detected mode's video memory,
+    // limited by the configured cap
+    fn frame_budget(&self) -> usize {
+        self.max_buffer_size.min(self.memory_cap)
+    }
+
+    fn set_memory_cap(&mut self, cap: usize) {
+        self.memory_cap = cap;
+        let frame_size = self.row_bytes() * self.height as usize;
+        if frame_size > cap {
+            info!("⚠️  A {}x{} frame needs {} MiB, over the {} MiB frame memory cap; frames will be cut off at the bottom",
+                     self.width, self.height, frame_size.div_ceil(1024 * 1024), cap / (1024 * 1024));
+        }
+    }
+
+    fn set_scaling(&mut self, scaling: Scaling) {
+        if scaling != self.scaling {
+            info!("🔍 Image scaling: {:?} for {}x{}", scaling, self.width, self.height);
+            self.scaling = scaling;
+        }
+    }
+
+    // Returns true when the fill changed and the picture on screen needs redrawing
+    fn set_letterbox(&mut self, letterbox: Letterbox) -> bool {
+        if letterbox == self.letterbox {
+            return false;
+        }
+        info!("🖼️  Letterbox fill: {:?}", letterbox);
+        self.letterbox = letterbox;
+        true
+    }
+
+    // Returns true when the margins changed and the picture on screen needs redrawing
+    fn set_safe_area(&mut self, safe_area: SafeArea) -> bool {
+        if safe_area == self.safe_area {
+            return false;
+        }
+        self.safe_area = safe_area;
+        let (x, y, width, height) = self.safe_rect();
+        info!("📐 Overscan margins: content in {}x{} at {},{} of {}x{}", width, height, x, y, self.width, self.height);
+        true
+    }
+
+    // See Layout::safe_rect
+    fn safe_rect(&self) -> (u32, u32, u32, u32) {
+        safe_rect(self.width, self.height, &self.safe_area)
+    }
+
+    fn layout(&self) -> Layout {
+        Layout {
+            width: self.width,
+            height: self.height,
+            scaling: self.scaling,
+            letterbox: self.letterbox.clone(),
+            safe_area: self.safe_area,
+            fit: FitMode::Contain,
+            smart_crop: false,
+            qr: None,
+        }
+    }
+
+    // Place a frame drawn at the safe area's size on the full screen. Its corner pixel, the
+    // background of every generated screen, carries on into the margins.
+    fn inset_frame(&self, content: RgbaImage) -> RgbaImage {
+        let (x, y, width, height) = self.safe_rect();
+        if (width, height) == (self.width, self.height) {
+            return content;
+        }
+        let background = content.get_pixel_checked(0, 0).copied().unwrap_or(Rgba([0, 0, 0, 255]));
+        let mut frame = RgbaImage::from_pixel(self.width, self.height, background);
+        image::imageops::replace(&mut frame, &content, x as i64, y as i64);
+        frame
+    }
+
+    // A generated screen (placeholder, message card) drawn upright for the viewer inside the
+    // safe area: `draw` gets the size to draw at, and the result is rotated and inset
+    fn render_screen(&self, orientation: &Orientation, draw: impl FnOnce(u32, u32) -> RgbaImage) -> RgbaImage {
+        let (_, _, width, height) = self.safe_rect();
+        let (width, height) = orientation.upright_size(width, height);
+        self.inset_frame(orientation.rotate_image(&draw(width, height)))
+    }
+
+    // Show `text` in the ticker, or take the ticker down with None. Returns true when one was
+    // taken down, since the picture under the band then needs redrawing. E-paper panels, whose
+    // every update flashes, don't get one.
+    fn set_ticker(&mut self, text: Option<String>, orientation: &Orientation, text_profile: &TextProfile) -> bool {
+        match text {
+            Some(_) if self.is_eink() => false,
+            Some(text) => {
+                if !self.ticker.as_ref().is_some_and(|ticker| ticker.shows(&text, orientation, text_profile)) {
+                    info!("📰 Ticker: {}", text);
+                    self.ticker = Some(Ticker::new(text, orientation.clone(), text_profile.clone()));
+                }
+                false
+            }
+            None => self.ticker.take().is_some(),
+        }
+    }
+
+    // When the ticker band is next due to be redrawn, None without a ticker
+    fn ticker_due(&self) -> Option<Instant> {
+        self.ticker.as_ref().map(|ticker| ticker.next_frame())
+    }
+
+    // Returns true when the marker came or went, and the picture needs redrawing
+    fn set_held_marker(&mut self, shown: bool) -> bool {
+        std::mem::replace(&mut self.held_marker, shown) != shown
+    }
+
+    // Show the offline banner with `text`, or take it down with None. Returns true when it
+    // came, went or changed, and the picture needs redrawing.
+    fn set_offline_banner(&mut self, text: Option<String>, orientation: &Orientation, text_profile: &TextProfile) -> bool {
+        let banner = text.map(|text| offline_banner::Banner::new(text, orientation.clone(), text_profile.clone()));
+        if banner == self.offline_banner {
+            return false;
+        }
+        match banner {
+            Some(ref banner) if self.offline_banner.is_none() => warn!("📴 Showing the offline banner: {}", banner.text()),
+            None => info!("📶 Back online, offline banner cleared"),
+            _ => {}
+        }
+        self.offline_banner = banner;
+        true
+    }
+
+    // A still with the held marker and the offline banner on it. The banner is also painted
+    // into every converted frame, but a still may only have its changed region converted, and
+    // e-paper panels take the image itself.
+    fn mark_overlays(&self, image: &RgbaImage) -> RgbaImage {
+        let mut marked = image.clone();
+        if self.held_marker {
+            self.mark_held(&mut marked);
+        }
+        if let Some(ref banner) = self.offline_banner {
+            let (band, region) = banner.render(self.safe_rect());
+            image::imageops::replace(&mut marked, &band, region.x as i64, region.y as i64);
+        }
+        marked
+    }
+
+    // Amber dot in the top right corner of the safe area
+    fn mark_held(&self, marked: &mut RgbaImage) {
+        let (x, y, width, height) = self.safe_rect();
+        let radius = (width.min(height) / 100).max(3) as i64;
+        let (center_x, center_y) = ((x + width) as i64 - 3 * radius, y as i64 + 3 * radius);
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let (px, py) = (center_x + dx, center_y + dy);
+                if dx * dx + dy * dy <= radius * radius && px >= 0 && py >= 0 && (px as u32) < marked.width() && (py as u32) < marked.height() {
+                    marked.put_pixel(px as u32, py as u32, Rgba([255, 176, 0, 255]));
+                }
+            }
+        }
+    }
+
+    // Redraw just the ticker band, moved along since the last frame
+    fn draw_ticker(&mut self) -> IoResult<()> {
+        let (band, region) = match self.ticker {
+            Some(ref ticker) => ticker.render(self.safe_rect()),
+            None => return Ok(()),
+        };
+        if let Some(ref mut ticker) = self.ticker {
+            ticker.mark_drawn();
+        }
+        if self.fallback_file.is_some() {
+            return Ok(());
+        }
+        let mut pixels = Vec::with_capacity((region.width * region.height) as usize * self.pixel_format.bytes_per_pixel());
+        for pixel in band.pixels() {
+            self.pixel_format.push_pixel(&mut pixels, self.color.apply(pixel.0));
+        }
+        self.write_region(region, &pixels)
+    }
+
+    // Paint the ticker band and the offline banner into a frame converted for the device
+    fn paint_overlays(&self, buffer: &mut [u8]) {
+        if let Some(ref ticker) = self.ticker {
+            self.paint_band(buffer, ticker.render(self.safe_rect()));
+        }
+        if let Some(ref banner) = self.offline_banner {
+            self.paint_band(buffer, banner.render(self.safe_rect()));
+        }
+    }
+
+    fn paint_band(&self, buffer: &mut [u8], (band, region): (RgbaImage, Region)) {
+        let bytes_per_pixel = self.pixel_format.bytes_per_pixel();
+        let row_bytes = self.row_bytes();
+        let mut row = Vec::with_capacity(region.width as usize * bytes_per_pixel);
+        for (i, band_row) in band.rows().enumerate() {
+            row.clear();
+            for pixel in band_row {
+                self.pixel_format.push_pixel(&mut row, self.color.apply(pixel.0));
+            }
+            let offset = (region.y as usize + i) * row_bytes + region.x as usize * bytes_per_pixel;
+            if let Some(target) = buffer.get_mut(offset..offset + row.len()) {
+                target.copy_from_slice(&row);
+            }
+        }
+    }
+
+    // Returns true when the correction changed and the picture on screen needs redrawing
+    fn set_color_adjustment(&mut self, gamma: f64, color_temperature: u32, brightness: u8) -> bool {
+        let changed = self.color.set(gamma, color_temperature, brightness);
+        if changed {
+            // The stored frame was converted with the old tables
+            self.last_frame = Vec::new();
+        }
+        changed
+    }
+
+    // Rewrite the last frame if the keep-alive interval has passed without a write
+    fn refresh_if_due(&mut self) -> IoResult<()> {
+        let interval = match self.refresh_interval {
+            Some(interval) => interval,
+            None => return Ok(()),
+        };
+
+        if self.last_frame.is_empty() || self.last_write.elapsed() < interval {
+            return Ok(());
+        }
+
+        let frame = std::mem::take(&mut self.last_frame);
+        let result = self.write_buffer(&frame);
+        self.last_frame = frame;
+        self.last_write = Instant::now();
+        result
+    }
+
+    // Buffers hold unpadded rows; the device may pad each scanline out to line_length
+    fn row_bytes(&self) -> usize {
+        self.width as usize * self.pixel_format.bytes_per_pixel()
+    }
+
+    fn stride(&self) -> usize {
+        self.line_length.max(self.row_bytes())
+    }
+
+    fn write_buffer(&mut self, buffer: &[u8]) -> IoResult<()> {
+        let page_len = self.stride() * self.height as usize;
+        let base = match self.double_buffer {
+            Some(ref double_buffer) => double_buffer.back_offset(page_len),
+            None => {
+                // Single buffered: start the write at the blank to keep tearing near the top
+                self.wait_for_vsync();
+                0
+            }
+        };
+        let started = Instant::now();
+        self.write_at(base, buffer)?;
+        perf::record_framebuffer_write(started.elapsed());
+
+        if self.double_buffer.is_some() {
+            self.wait_for_vsync();
+        }
+        if let (Some(ref mut double_buffer), Some(ref file)) = (&mut self.double_buffer, &self.file) {
+            if let Err(e) = double_buffer.flip(file) {
+                info!("Panning failed ({}), drawing into the visible buffer from now on", e);
+                self.double_buffer = None;
+                self.write_at(0, buffer)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Let the driver rotate the picture; the rotated mode can have different dimensions
+    fn set_hardware_rotation(&mut self, rotate: u32) -> IoResult<()> {
+        let file = match self.file {
+            Some(ref file) => file,
+            None => return Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "no framebuffer device")),
+        };
+        // Dropped first, putting back the single page, so the new one is sized for the rotated mode.
+        // It comes back whether or not the rotation took, sized for whichever mode is now set.
+        let double_buffered = self.double_buffer.take().is_some();
+        let rotated = fbdev::set_rotation(file, rotate).and_then(|()| fbdev::FbMode::query(file));
+        if double_buffered {
+            self.double_buffer = fbdev::DoubleBuffer::enable(file).ok();
+        }
+        let mode = rotated?;
+        self.width = mode.width;
+        self.height = mode.height;
+        self.line_length = mode.line_length as usize;
+        self.max_buffer_size = mode.memory_len;
+        self.last_frame = Vec::new();
+        Ok(())
+    }
+
+    fn wait_for_vsync(&mut self) {
+        if !self.vsync {
+            return;
+        }
+        if let Some(ref file) = self.file {
+            if let Err(e) = fbdev::wait_for_vsync(file) {
+                info!("FBIO_WAITFORVSYNC unavailable ({}), pacing transitions with timers", e);
+                self.vsync = false;
+            }
+        }
     }
 
-    fn image_to_bgra_buffer(&self, image: &RgbaImage) -> Vec<u8> {
-        println!("🔄 Converting {}x{} image to BGRA buffer for {}x{} framebuffer", 
-                 image.width(), image.height(), self.width, self.height);
+    // Write a frame starting `base` bytes into device memory
+    fn write_at(&mut self, base: usize, buffer: &[u8]) -> IoResult<()> {
+        let row_bytes = self.row_bytes();
+        let stride = self.stride();
+
+        if let Some(ref mut mmap) = self.mmap {
+            // Use memory mapping for fast, efficient writes
+            let copy_len = std::cmp::min(buffer.len(), mmap.len().saturating_sub(base));
+            if copy_len == 0 {
+                info!("Warning: mmap size is 0 bytes, cannot write to framebuffer. Buffer size: {}, mmap size: {}", buffer.len(), mmap.len());
+                return Ok(());
+            }
+            if stride == row_bytes {
+                mmap[base..base + copy_len].copy_from_slice(&buffer[..copy_len]);
+            } else {
+                // Padded scanlines: place each row at its own offset
+                for (y, row) in buffer.chunks(row_bytes).enumerate() {
+                    let offset = base + y * stride;
+                    if offset + row.len() > mmap.len() {
+                        break;
+                    }
+                    mmap[offset..offset + row.len()].copy_from_slice(row);
+                }
+            }
+            mmap.flush()?;
+        } else if let Some(ref mut file) = self.file {
+            if stride != row_bytes {
+                // Padded scanlines: seek to each row's offset and leave the padding untouched
+                for (y, row) in buffer.chunks(row_bytes).enumerate() {
+                    file.seek(SeekFrom::Start((base + y * stride) as u64))?;
+                    if let Err(e) = file.write_all(row) {
+                        error!("Failed to write row {} to framebuffer: {}", y, e);
+                        return Err(e);
+                    }
+                }
+                file.flush()?;
+                return Ok(());
+            }
+
+            // Fallback to direct file writes - seek to the start of the page and write entire buffer
+            file.seek(SeekFrom::Start(base as u64))?;
+            
+            // Writing buffer to framebuffer device
+            
+            // For framebuffer devices, we should write the full buffer at once for proper synchronization
+            // but break it into reasonable chunks to avoid system limits
+            const CHUNK_SIZE: usize = 4096; // 4KB chunks for better compatibility
+            let mut bytes_written = 0;
+            
+            for chunk in buffer.chunks(CHUNK_SIZE) {
+                match file.write_all(chunk) {
+                    Ok(()) => {
+                        bytes_written += chunk.len();
+                    }
+                    Err(e) => {
+                        error!("Failed to write chunk to framebuffer at offset {}: {}", bytes_written, e);
+                        return Err(e);
+                    }
+                }
+            }
+            
+            // Ensure data is written to the device
+            file.flush()?;
+            // Successfully wrote framebuffer data
+        } else if let Some(ref mut fallback) = self.fallback_file {
+            fallback.write_all(buffer)?;
+            fallback.flush()?;
+            info!("Wrote {} bytes to fallback file", buffer.len());
+        }
+        Ok(())
+    }
+
+    // Static frames only rewrite what changed since the previous static frame when that is
+    // at most half the screen; transitions go through display_buffer and always write it all
+    fn display_image(&mut self, image: &RgbaImage) -> IoResult<()> {
+        let marked;
+        let image = if self.held_marker || self.offline_banner.is_some() {
+            marked = self.mark_overlays(image);
+            &marked
+        } else {
+            image
+        };
+        if self.eink.is_some() {
+            return self.display_eink(image);
+        }
+
+        let buffer = self.image_to_buffer(image);
+
+        if let Some(region) = self.dirty_region(&buffer) {
+            if (region.width * region.height) as usize * 2 <= (self.width * self.height) as usize
+                && self.fallback_file.is_none()
+            {
+                return self.display_region(image, region);
+            }
+        }
+
+        self.display_buffer(buffer)
+    }
+
+    // Every e-paper update is a full refresh that flashes the panel, so unchanged frames
+    // (redraws after config changes, keep-alives) are never sent
+    fn display_eink(&mut self, image: &RgbaImage) -> IoResult<()> {
+        let gray: Vec<u8> = image.pixels()
+            .map(|pixel| {
+                let [r, g, b, _] = self.color.apply(pixel.0);
+                ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as u8
+            })
+            .collect();
+        if gray == self.last_frame {
+            return Ok(());
+        }
+
+        let panel = match self.eink {
+            Some(ref mut panel) => panel,
+            None => return Ok(()),
+        };
+        let written = panel.display_gray(&gray);
+        health::record_framebuffer_write(written.is_ok());
+        written?;
+        self.last_frame = gray;
+        self.last_write = Instant::now();
+        Ok(())
+    }
+
+    // Convert and write only `region` of a full-screen image, e.g. for an overlay that changed
+    fn display_region(&mut self, image: &RgbaImage, region: Region) -> IoResult<()> {
+        let region = match self.clip_region(region, image) {
+            Some(region) => region,
+            None => return Ok(()),
+        };
+        if self.fallback_file.is_some() {
+            // The fallback file is a stream of whole frames
+            return self.display_image(image);
+        }
+        info!("📺 Updating {}x{} region at ({}, {})", region.width, region.height, region.x, region.y);
+
+        let mut pixels = Vec::with_capacity((region.width * region.height) as usize * self.pixel_format.bytes_per_pixel());
+        if self.converts_by_swizzle() {
+            let row_start = region.x as usize * 4;
+            let row_len = region.width as usize * 4;
+            pixels.resize(region.height as usize * row_len, 0);
+            for (y, out) in (region.y..region.y + region.height).zip(pixels.chunks_exact_mut(row_len)) {
+                simd::rgba_to_bgra(&transitions::image_row(image, y)[row_start..row_start + row_len], out);
+            }
+            return self.write_region(region, &pixels);
+        }
+        for y in region.y..region.y + region.height {
+            for x in region.x..region.x + region.width {
+                self.pixel_format.push_pixel(&mut pixels, self.color.apply(image.get_pixel(x, y).0));
+            }
+        }
+        self.write_region(region, &pixels)
+    }
+
+    fn clip_region(&self, region: Region, image: &RgbaImage) -> Option<Region> {
+        let right = (region.x.saturating_add(region.width)).min(self.width).min(image.width());
+        let bottom = (region.y.saturating_add(region.height)).min(self.height).min(image.height());
+        if region.x >= right || region.y >= bottom {
+            return None;
+        }
+        Some(Region { x: region.x, y: region.y, width: right - region.x, height: bottom - region.y })
+    }
+
+    // Bounding box of the pixels that differ from the last full frame, None when there is
+    // no baseline to compare against or nothing changed
+    fn dirty_region(&self, buffer: &[u8]) -> Option<Region> {
+        if self.last_frame.len() != buffer.len() || buffer.is_empty() {
+            return None;
+        }
+
+        let row_bytes = self.row_bytes();
+        let bytes_per_pixel = self.pixel_format.bytes_per_pixel();
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+
+        for (y, (new_row, old_row)) in buffer.chunks(row_bytes).zip(self.last_frame.chunks(row_bytes)).enumerate() {
+            if new_row == old_row {
+                continue;
+            }
+            let first = new_row.iter().zip(old_row).position(|(a, b)| a != b).unwrap_or(0) / bytes_per_pixel;
+            let last = new_row.iter().zip(old_row).rposition(|(a, b)| a != b).unwrap_or(0) / bytes_per_pixel;
+            bounds = Some(match bounds {
+                Some((left, top, right, _)) => (left.min(first), top, right.max(last), y),
+                None => (first, y, last, y),
+            });
+        }
+
+        bounds.map(|(left, top, right, bottom)| Region {
+            x: left as u32,
+            y: top as u32,
+            width: (right - left + 1) as u32,
+            height: (bottom - top + 1) as u32,
+        })
+    }
+
+    // Write packed rows of `region` straight into the visible page
+    fn write_region(&mut self, region: Region, pixels: &[u8]) -> IoResult<()> {
+        let bytes_per_pixel = self.pixel_format.bytes_per_pixel();
+        let span = region.width as usize * bytes_per_pixel;
+        let stride = self.stride();
+        let row_bytes = self.row_bytes();
+        let base = match self.double_buffer {
+            Some(ref double_buffer) => double_buffer.visible_offset(stride * self.height as usize),
+            None => 0,
+        };
+
+        let written = self.write_rows(base, region, pixels, span, stride);
+        health::record_framebuffer_write(written.is_ok());
+        written?;
+
+        // Keep the copy of the frame in step with the screen
+        if self.last_frame.len() == row_bytes * self.height as usize {
+            for (i, row) in pixels.chunks(span).enumerate() {
+                let offset = (region.y as usize + i) * row_bytes + region.x as usize * bytes_per_pixel;
+                self.last_frame[offset..offset + span].copy_from_slice(row);
+            }
+        }
+        self.last_write = Instant::now();
+        Ok(())
+    }
+
+    fn write_rows(&mut self, base: usize, region: Region, pixels: &[u8], span: usize, stride: usize) -> IoResult<()> {
+        let column_offset = region.x as usize * self.pixel_format.bytes_per_pixel();
+        for (i, row) in pixels.chunks(span).enumerate() {
+            let offset = base + (region.y as usize + i) * stride + column_offset;
+            if let Some(ref mut mmap) = self.mmap {
+                if offset + row.len() > mmap.len() {
+                    break;
+                }
+                mmap[offset..offset + row.len()].copy_from_slice(row);
+            } else if let Some(ref mut file) = self.file {
+                file.seek(SeekFrom::Start(offset as u64))?;
+                file.write_all(row)?;
+            }
+        }
+
+        if let Some(ref mut mmap) = self.mmap {
+            mmap.flush()?;
+        } else if let Some(ref mut file) = self.file {
+            file.flush()?;
+        }
+        Ok(())
+    }
+
+    // Frames can go to the framebuffer by reordering bytes alone: BGRA, no color correction
+    fn converts_by_swizzle(&self) -> bool {
+        self.pixel_format == fbdev::PixelFormat::Bgra8888 && self.color.is_neutral()
+    }
+
+    fn image_to_buffer(&self, image: &RgbaImage) -> Vec<u8> {
+        info!("🔄 Converting {}x{} image to {} buffer for {}x{} framebuffer", 
+                 image.width(), image.height(), self.pixel_format.name(), self.width, self.height);
         
         // If image dimensions don't match framebuffer exactly, this could cause garbled display
         if image.width() != self.width || image.height() != self.height {
-            println!("❌ ERROR: Image dimensions {}x{} don't match framebuffer {}x{} - this WILL cause garbled display!", 
+            info!("❌ ERROR: Image dimensions {}x{} don't match framebuffer {}x{} - this WILL cause garbled display!", 
                      image.width(), image.height(), self.width, self.height);
-            println!("🔧 Fix: All images must be exactly {}x{} before being passed to this function", 
+            info!("🔧 Fix: All images must be exactly {}x{} before being passed to this function", 
                      self.width, self.height);
         }
         
-        let expected_size = (self.width * self.height * 4) as usize;
-        let max_pixels = self.max_buffer_size / 4;
+        let bytes_per_pixel = self.pixel_format.bytes_per_pixel();
+        let expected_size = (self.width * self.height) as usize * bytes_per_pixel;
+        let max_pixels = self.frame_budget() / bytes_per_pixel;
         let actual_pixels = (self.width * self.height) as usize;
 
         if actual_pixels > max_pixels {
-            println!(
+            info!(
                 "Warning: Image dimensions {}x{} exceed framebuffer capacity. Truncating to fit.",
                 self.width, self.height
             );
         }
 
-        let safe_size = std::cmp::min(expected_size, self.max_buffer_size);
-        let safe_pixels = safe_size / 4;
+        let safe_size = std::cmp::min(expected_size, self.frame_budget());
+        let safe_pixels = safe_size / bytes_per_pixel;
+
+        // A full frame for a 32-bit framebuffer without color correction is one swizzle
+        if self.converts_by_swizzle() && image.dimensions() == (self.width, self.height) && safe_size == expected_size {
+            let mut buffer = vec![0; expected_size];
+            simd::rgba_to_bgra(image.as_raw(), &mut buffer);
+            self.paint_overlays(&mut buffer);
+            return buffer;
+        }
+
         let mut buffer = Vec::with_capacity(safe_size);
 
         let mut pixels_written = 0;
@@ -443,11 +1897,7 @@ impl Framebuffer {
                     Rgba([0, 0, 0, 255])
                 };
 
-                // Convert RGBA to BGRA (keeping alpha channel)
-                buffer.push(pixel[2]); // B
-                buffer.push(pixel[1]); // G
-                buffer.push(pixel[0]); // R
-                buffer.push(pixel[3]); // A
+                self.pixel_format.push_pixel(&mut buffer, self.color.apply(pixel.0));
 
                 pixels_written += 1;
             }
@@ -457,421 +1907,535 @@ impl Framebuffer {
             }
         }
 
-        // Generated framebuffer buffer
+        self.paint_overlays(&mut buffer);
         buffer
     }
 
+    fn log_mode(mode: &fbdev::FbMode) {
+        info!("🔧 Framebuffer mode: {}x{} at {}bpp, {} bytes per line, {} bytes of video memory",
+                 mode.width, mode.height, mode.bits_per_pixel, mode.line_length, mode.memory_len);
+    }
+
+    fn resolve_pixel_format(mode: &fbdev::FbMode, requested: Option<fbdev::PixelFormat>) -> fbdev::PixelFormat {
+        let detected = fbdev::PixelFormat::from_bits_per_pixel(mode.bits_per_pixel);
+        let format = match (requested, detected) {
+            (Some(requested), _) => {
+                if detected != Some(requested) {
+                    info!("⚠️  Using {} output on a {}bpp framebuffer as requested", requested.name(), mode.bits_per_pixel);
+                }
+                requested
+            }
+            (None, Some(detected)) => detected,
+            (None, None) => {
+                info!("⚠️  WARNING: No pixel conversion for {}bpp, writing bgra8888 (set --pixel-format to override)", mode.bits_per_pixel);
+                fbdev::PixelFormat::Bgra8888
+            }
+        };
+        info!("🔧 Framebuffer pixel format: {}", format.name());
+        format
+    }
+
     fn log_framebuffer_info(file: &File) {
         // Try to get framebuffer information
         let fd = file.as_raw_fd();
 
         // Basic file size check
         if let Ok(metadata) = file.metadata() {
-            println!("Framebuffer device size: {} bytes", metadata.len());
-            println!("Framebuffer device type: {:?}", metadata.file_type());
-            println!("Framebuffer device permissions: {:o}", metadata.permissions().mode());
+            info!("Framebuffer device size: {} bytes", metadata.len());
+            info!("Framebuffer device type: {:?}", metadata.file_type());
+            info!("Framebuffer device permissions: {:o}", metadata.permissions().mode());
         } else {
-            println!("Failed to get framebuffer metadata");
+            info!("Failed to get framebuffer metadata");
         }
 
         // Check if the file is a character device (framebuffers are char devices)
         if let Ok(metadata) = file.metadata() {
             if metadata.file_type().is_char_device() {
-                println!("Framebuffer is a character device (correct)");
+                info!("Framebuffer is a character device (correct)");
             } else {
-                println!("WARNING: Framebuffer is NOT a character device");
+                info!("WARNING: Framebuffer is NOT a character device");
             }
         }
 
-        println!("Framebuffer device fd: {}", fd);
+        info!("Framebuffer device fd: {}", fd);
     }
 }
 
-struct ImageManager {
-    images: Vec<PathBuf>,
-    current_index: usize,
+// A display driven alongside the primary framebuffer, with its own mode and orientation
+struct Output {
+    device: String,
+    fb: Framebuffer,
+    orientation: Orientation,
+    // None mirrors the primary output
+    playlist: Option<Playlist>,
 }
 
-impl ImageManager {
-    fn new() -> Self {
-        Self {
-            images: Vec::new(),
-            current_index: 0,
+// Where a transition is drawn: the primary display as the viewer sees it, and the
+// outputs mirroring it
+struct RenderTarget<'a> {
+    fb: &'a mut Framebuffer,
+    orientation: &'a Orientation,
+    mirrors: &'a mut [Output],
+}
+
+// Images from a local directory cycled on one output, paced by the controller's
+// play/pause state and display duration
+struct Playlist {
+    image_dir: PathBuf,
+    images: ImageManager,
+    last_change: Instant,
+    shown: bool,
+    // The directory's modification time at the last scan; it only changes when files are
+    // added, removed or renamed, so the directory is listed again only then
+    scanned_at: Option<SystemTime>,
+    // The transition under way, drawn a frame per pass of the main loop so it doesn't hold
+    // up the primary display
+    transition: Option<PlaylistTransition>,
+}
+
+struct PlaylistTransition {
+    from: RgbaImage,
+    to: RgbaImage,
+    to_index: usize,
+    transition_type: TransitionType,
+    duration: Duration,
+    start: Instant,
+}
+
+impl Playlist {
+    fn new(image_dir: PathBuf) -> IoResult<Self> {
+        let mut playlist = Self {
+            image_dir,
+            images: ImageManager::new(),
+            last_change: Instant::now(),
+            shown: false,
+            scanned_at: None,
+            transition: None,
+        };
+        playlist.rescan_if_changed()?;
+        Ok(playlist)
+    }
+
+    fn rescan_if_changed(&mut self) -> IoResult<()> {
+        let modified = std::fs::metadata(&self.image_dir)?.modified().ok();
+        if modified.is_none() || modified != self.scanned_at {
+            self.images.scan_images(&self.image_dir)?;
+            self.scanned_at = modified;
         }
+        Ok(())
     }
+}
 
-    fn scan_images(&mut self, image_dir: &Path) -> IoResult<()> {
-        self.images.clear();
+impl Output {
+    // DEVICE[:ORIENTATION[:IMAGE_DIR]]
+    fn open(spec: &str) -> IoResult<Self> {
+        let invalid = |message: String| std::io::Error::new(std::io::ErrorKind::InvalidInput, message);
+        let mut parts = spec.splitn(3, ':');
+        let device = parts.next().unwrap_or_default().to_string();
+        if device.is_empty() {
+            return Err(invalid("missing device path".to_string()));
+        }
+        let orientation = match parts.next().filter(|o| !o.is_empty()) {
+            Some(name) => Orientation::parse(name).ok_or_else(|| invalid(format!("unknown orientation '{}'", name)))?,
+            None => Orientation::Landscape,
+        };
+        let image_dir = parts.next().filter(|d| !d.is_empty()).map(PathBuf::from);
 
-        for entry in std::fs::read_dir(image_dir)? {
-            let entry = entry?;
-            let path = entry.path();
+        // Framebuffer::new falls back to a file when the device is missing, which only makes
+        // sense for the primary output
+        OpenOptions::new().write(true).open(&device)?;
+        let fb = Framebuffer::new(DEFAULT_LANDSCAPE_WIDTH, DEFAULT_LANDSCAPE_HEIGHT, Path::new(&device), None)?;
 
-            if let Some(ext) = path.extension() {
-                let ext_lower = ext.to_string_lossy().to_lowercase();
-                if ext_lower == "png" || ext_lower == "jpg" || ext_lower == "jpeg" {
-                    self.images.push(path);
+        let playlist = image_dir.map(Playlist::new).transpose()?;
+
+        Ok(Self { device, fb, orientation, playlist })
+    }
+}
+
+// Draw a frame on every mirroring output; `render` builds it for an output's framebuffer and orientation
+fn show_on_mirrors<F>(outputs: &mut [Output], render: F)
+where
+    F: Fn(&Framebuffer, &Orientation) -> Result<RgbaImage, ImageError>,
+{
+    for output in outputs.iter_mut().filter(|output| output.playlist.is_none()) {
+        match render(&output.fb, &output.orientation) {
+            Ok(frame) => {
+                if let Err(e) = output.fb.display_image(&frame) {
+                    error!("Failed to display on {}: {}", output.device, e);
                 }
             }
+            Err(e) => error!("Failed to render for {}: {}", output.device, e),
         }
+    }
+}
 
-        self.images.sort();
-        println!("Found {} images (PNG/JPG/JPEG)", self.images.len());
-        Ok(())
+// Step an output's own playlist: show its first image once, then move on whenever the
+// controller's display duration has passed while playing. Transitions advance one frame per
+// call; returns whether one is still under way, so the main loop comes back without idling.
+async fn advance_playlist(output: &mut Output, controller: &SlideshowController) -> bool {
+    let playlist = match output.playlist {
+        Some(ref mut playlist) => playlist,
+        None => return false,
+    };
+
+    if let Some(ref transition) = playlist.transition {
+        let progress = if transition.duration.is_zero() {
+            1.0
+        } else {
+            (transition.start.elapsed().as_secs_f32() / transition.duration.as_secs_f32()).min(1.0)
+        };
+        let (safe_x, safe_y, _, _) = output.fb.safe_rect();
+        let frame = playlist.images.create_transition_frame(&transition.from, &transition.to, progress, &transition.transition_type, (safe_x, safe_y), &mut None);
+        let buffer = output.fb.image_to_buffer(&frame);
+        if let Err(e) = output.fb.display_buffer(buffer) {
+            error!("Failed to display transition frame on {}: {}", output.device, e);
+        }
+        if progress < 1.0 {
+            return true;
+        }
+        playlist.images.current_index = transition.to_index;
+        playlist.transition = None;
+        return false;
     }
 
-    // Removed - using load_and_scale_image_with_orientation instead
+    if playlist.shown && !controller.should_advance_playlist(playlist.last_change).await {
+        return false;
+    }
+    playlist.last_change = Instant::now();
 
-    fn apply_easing(t: f32, easing_type: &TransitionType) -> f32 {
-        match easing_type {
-            TransitionType::EaseIn => t * t,
-            TransitionType::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
-            TransitionType::EaseInOut => {
-                if t < 0.5 {
-                    2.0 * t * t
-                } else {
-                    1.0 - 2.0 * (1.0 - t) * (1.0 - t)
-                }
-            }
-            TransitionType::Bounce => {
-                if t < 0.5 {
-                    4.0 * t * t * t
-                } else {
-                    let f = 2.0 * t - 2.0;
-                    1.0 + f * f * f + 1.0
+    if let Err(e) = playlist.rescan_if_changed() {
+        error!("Failed to scan {} for {}: {}", playlist.image_dir.display(), output.device, e);
+        return false;
+    }
+    let count = playlist.images.images.len();
+    if count == 0 {
+        return false;
+    }
+
+    let from = playlist.images.current_index.min(count - 1);
+    if playlist.shown && count == 1 {
+        return false;
+    }
+    if !playlist.shown {
+        match playlist.images.load(&playlist.images.images[from], &output.fb, &output.orientation) {
+            Ok(image) => {
+                if let Err(e) = output.fb.display_image(&image) {
+                    error!("Failed to display on {}: {}", output.device, e);
                 }
             }
-            TransitionType::Elastic => {
-                if t == 0.0 {
-                    0.0
-                } else if t == 1.0 {
-                    1.0
-                } else if t < 0.5 {
-                    -(2.0_f32.powf(20.0 * t - 10.0))
-                        * ((20.0 * t - 11.125) * std::f32::consts::PI / 4.5).sin()
-                        / 2.0
-                } else {
-                    2.0_f32.powf(-20.0 * t + 10.0)
-                        * ((20.0 * t - 11.125) * std::f32::consts::PI / 4.5).sin()
-                        / 2.0
-                        + 1.0
-                }
+            Err(e) => error!("Failed to load image for {}: {}", output.device, e),
+        }
+        playlist.images.current_index = from;
+        playlist.shown = true;
+        return false;
+    }
+
+    let to = (from + 1) % count;
+    let (transition_type, transition_duration) = select_transition(controller, &controller.get_transition_effect().await).await;
+    playlist.images.easing = easing::CubicBezier::from_config(&controller.get_transition_easing().await);
+    // The same reductions play_transition makes for a hot CPU or an e-paper panel
+    let quality = quality::mode();
+    let transition_type = if quality >= quality::Mode::FadesOnly { TransitionType::Fade } else { transition_type };
+    let duration = if output.fb.is_eink() || quality == quality::Mode::NoTransitions { Duration::ZERO } else { transition_duration };
+
+    let images = &playlist.images;
+    let loaded = images.load_outgoing(&images.images[from], &output.fb, &output.orientation)
+        .and_then(|from_img| Ok((from_img, images.load(&images.images[to], &output.fb, &output.orientation)?)));
+    match loaded {
+        Ok((_, to_img)) if duration.is_zero() => {
+            if let Err(e) = output.fb.display_image(&to_img) {
+                error!("Failed to display on {}: {}", output.device, e);
             }
-            TransitionType::Accelerated => t * t * t,
-            _ => t, // Linear for other types
         }
+        Ok((from_img, to_img)) => {
+            info!("Playing {} transition on {}", transition_type.name(), output.device);
+            playlist.transition = Some(PlaylistTransition {
+                from: from_img,
+                to: to_img,
+                to_index: to,
+                transition_type,
+                duration,
+                start: Instant::now(),
+            });
+            return true;
+        }
+        Err(e) => error!("Failed to load image for {}: {}", output.device, e),
     }
+    playlist.images.current_index = to;
+    false
+}
 
-    fn create_transition_frame(
-        &self,
-        img1: &RgbaImage,
-        img2: &RgbaImage,
-        progress: f32,
-        transition_type: &TransitionType,
-        transition_name: &str,
-    ) -> RgbaImage {
-        let width = img1.width();
-        let height = img1.height();
-        let mut result = RgbaImage::new(width, height);
+// A slide to prepare ahead: path, the display's layout and orientation, and whether it is
+// the outgoing end of a transition
+type PrefetchJob = (PathBuf, Layout, Orientation, bool);
 
-        // Apply transition-specific easing
-        let eased_progress = Self::apply_easing(progress, transition_type);
+struct ImageManager {
+    images: Vec<PathBuf>,
+    current_index: usize,
+    text_profile: TextProfile,
+    // Label transition frames with the effect's name (--debug-overlay)
+    debug_overlay: bool,
+    // Draws transitions when --gpu-transitions is on and EGL came up
+    gpu: Option<gles::GlesRenderer>,
+    cache: ImageCache,
+    // Background decode of upcoming slides into the cache
+    prefetch: Option<thread::JoinHandle<()>>,
+    // Letterbox fills and fit modes slides ask for in their metadata, and the TV's color and
+    // background image those fills are drawn with
+    slide_styles: HashMap<PathBuf, SlideStyle>,
+    letterbox_color: String,
+    letterbox_image: Option<PathBuf>,
+    // Zoom or pan over stills, and how far through it the slide on screen is, so a
+    // transition away starts from the frame that was last shown
+    ken_burns: Option<ken_burns::Settings>,
+    motion_progress: f32,
+    smart_crop: bool,
+    // Replaces every effect's easing (TvConfig transition_easing)
+    easing: Option<easing::CubicBezier>,
+}
 
-        match transition_type {
-            TransitionType::Fade => {
-                self.blend_images_simple(img1, img2, eased_progress, &mut result);
-            }
-            TransitionType::Dissolve => {
-                self.dissolve_transition(img1, img2, eased_progress, &mut result);
-            }
-            TransitionType::SlideLeft => {
-                self.slide_transition(img1, img2, eased_progress, &mut result, -1, 0);
-            }
-            TransitionType::SlideRight => {
-                self.slide_transition(img1, img2, eased_progress, &mut result, 1, 0);
-            }
-            TransitionType::SlideUp => {
-                self.slide_transition(img1, img2, eased_progress, &mut result, 0, -1);
-            }
-            TransitionType::SlideDown => {
-                self.slide_transition(img1, img2, eased_progress, &mut result, 0, 1);
-            }
-            TransitionType::WipeLeft => {
-                self.wipe_transition(img1, img2, eased_progress, &mut result, 0);
-            }
-            TransitionType::WipeRight => {
-                self.wipe_transition(img1, img2, eased_progress, &mut result, 1);
-            }
-            TransitionType::WipeUp => {
-                self.wipe_transition(img1, img2, eased_progress, &mut result, 2);
-            }
-            TransitionType::WipeDown => {
-                self.wipe_transition(img1, img2, eased_progress, &mut result, 3);
-            }
-            TransitionType::CircularWipe => {
-                self.circular_wipe_transition(img1, img2, eased_progress, &mut result);
-            }
-            TransitionType::DiagonalWipe => {
-                self.diagonal_wipe_transition(img1, img2, eased_progress, &mut result);
-            }
-            TransitionType::Pixelate => {
-                self.pixelate_transition(img1, img2, eased_progress, &mut result);
-            }
-            TransitionType::Morph => {
-                self.morph_transition(img1, img2, eased_progress, &mut result);
+impl ImageManager {
+    fn new() -> Self {
+        Self {
+            images: Vec::new(),
+            current_index: 0,
+            text_profile: TextProfile::Standard,
+            debug_overlay: false,
+            gpu: None,
+            cache: ImageCache::new(0),
+            prefetch: None,
+            slide_styles: HashMap::new(),
+            letterbox_color: String::new(),
+            letterbox_image: None,
+            ken_burns: None,
+            motion_progress: 0.0,
+            smart_crop: false,
+            easing: None,
+        }
+    }
+
+    // Returns true when a slide's style changed, which leaves its cached frames out of date
+    fn set_slide_styles(&mut self, slide_styles: HashMap<PathBuf, SlideStyle>, letterbox_color: &str, letterbox_image: Option<PathBuf>) -> bool {
+        let changed = slide_styles != self.slide_styles
+            || (!slide_styles.is_empty() && (letterbox_color != self.letterbox_color || letterbox_image != self.letterbox_image));
+        self.slide_styles = slide_styles;
+        self.letterbox_color = letterbox_color.to_string();
+        self.letterbox_image = letterbox_image;
+        changed
+    }
+
+    // How `path` is laid out on `fb`: the display's own layout, with the slide's letterbox fill,
+    // fit mode and QR code when its metadata picks them
+    fn layout_for(&self, path: &Path, fb: &Framebuffer) -> Layout {
+        let mut layout = fb.layout();
+        layout.smart_crop = self.smart_crop;
+        if let Some(style) = self.slide_styles.get(path) {
+            if let Some(ref fill) = style.letterbox_fill {
+                layout.letterbox = Letterbox::from_config(fill, &self.letterbox_color, self.letterbox_image.clone());
             }
-            _ => {
-                // For easing transitions, use simple blend with the easing applied
-                self.blend_images_simple(img1, img2, eased_progress, &mut result);
+            // Video frames are scaled by ffmpeg to fit, so their stills stay contained too
+            if let Some(fit_mode) = style.fit_mode.as_ref().filter(|_| !video::is_video(path)) {
+                layout.fit = FitMode::from_name(fit_mode);
             }
+            layout.qr = style.qr_url.as_ref().map(|url| qr_code::QrOverlay {
+                url: url.clone(),
+                corner: qr_code::Corner::from_name(style.qr_corner.as_deref()),
+            });
         }
+        layout
+    }
 
-        // Add transition name text overlay
-        self.add_transition_text(&mut result, transition_name);
-
-        result
+    // load_and_scale_image_with_orientation through the cache
+    fn load(&self, path: &Path, fb: &Framebuffer, orientation: &Orientation) -> Result<RgbaImage, ImageError> {
+        load_cached(&self.cache, path, &self.layout_for(path, fb), orientation, false)
     }
 
-    fn blend_images_simple(
-        &self,
-        img1: &RgbaImage,
-        img2: &RgbaImage,
-        alpha: f32,
-        result: &mut RgbaImage,
-    ) {
-        let width = img1.width();
-        let height = img1.height();
+    // load_and_scale_outgoing through the cache
+    fn load_outgoing(&self, path: &Path, fb: &Framebuffer, orientation: &Orientation) -> Result<RgbaImage, ImageError> {
+        load_cached(&self.cache, path, &self.layout_for(path, fb), orientation, true)
+    }
 
-        for y in 0..height {
-            for x in 0..width {
-                let p1 = img1.get_pixel(x, y);
-                let p2 = img2.get_pixel(x, y);
+    // Decode and scale slides into the cache on a background thread, so the display loop
+    // finds them ready. Returns false, starting nothing, when the cache is off or an earlier
+    // prefetch is still running.
+    fn prefetch(&mut self, jobs: Vec<PrefetchJob>) -> bool {
+        if !self.cache.is_enabled() || self.prefetch.as_ref().is_some_and(|handle| !handle.is_finished()) {
+            return false;
+        }
+        let cache = self.cache.clone();
+        self.prefetch = Some(thread::spawn(move || {
+            for (path, layout, orientation, outgoing) in jobs {
+                if cache.contains(&cache_key(&path, &layout, &orientation, outgoing)) {
+                    continue;
+                }
+                let started = Instant::now();
+                if load_cached(&cache, &path, &layout, &orientation, outgoing).is_ok() {
+                    info!("Prefetched {} for {}x{} in {}ms", path.display(), layout.width, layout.height, started.elapsed().as_millis());
+                }
+            }
+        }));
+        true
+    }
 
-                let r = (p1[0] as f32 * (1.0 - alpha) + p2[0] as f32 * alpha) as u8;
-                let g = (p1[1] as f32 * (1.0 - alpha) + p2[1] as f32 * alpha) as u8;
-                let b = (p1[2] as f32 * (1.0 - alpha) + p2[2] as f32 * alpha) as u8;
-                let a = (p1[3] as f32 * (1.0 - alpha) + p2[3] as f32 * alpha) as u8;
+    // The zoom or pan `path` moves with on `fb`. Videos move by themselves, e-paper can't
+    // animate, and a slide with a QR code holds still so the code stays easy to scan.
+    fn motion_for(&self, path: &Path, fb: &Framebuffer) -> Option<ken_burns::KenBurns> {
+        let has_qr = self.slide_styles.get(path).is_some_and(|style| style.qr_url.is_some());
+        self.ken_burns.as_ref()
+            .filter(|_| !video::is_video(path) && !fb.is_eink() && !has_qr)
+            .map(|settings| ken_burns::KenBurns::for_slide(settings, path))
+    }
 
-                result.put_pixel(x, y, Rgba([r, g, b, a]));
-            }
+    // `frame`, the slide at `path` as loaded for `fb`, `progress` of the way through its motion
+    fn with_motion(&self, path: &Path, frame: RgbaImage, fb: &Framebuffer, progress: f32) -> RgbaImage {
+        match self.motion_for(path, fb) {
+            Some(motion) => motion.render(&frame, fb.safe_rect(), progress),
+            None => frame,
         }
     }
 
-    fn dissolve_transition(
-        &self,
-        img1: &RgbaImage,
-        img2: &RgbaImage,
-        progress: f32,
-        result: &mut RgbaImage,
-    ) {
-        let width = img1.width();
-        let height = img1.height();
-
-        for y in 0..height {
-            for x in 0..width {
-                let random_threshold = fastrand::f32();
-                let pixel = if random_threshold < progress {
-                    *img2.get_pixel(x, y)
-                } else {
-                    *img1.get_pixel(x, y)
-                };
-                result.put_pixel(x, y, pixel);
-            }
+    // Wait for a prefetch in progress, so a slide it is preparing isn't decoded twice
+    fn finish_prefetch(&mut self) {
+        if let Some(handle) = self.prefetch.take() {
+            let _ = handle.join();
         }
     }
 
-    fn slide_transition(
-        &self,
-        img1: &RgbaImage,
-        img2: &RgbaImage,
-        progress: f32,
-        result: &mut RgbaImage,
-        dir_x: i32,
-        dir_y: i32,
-    ) {
-        let width = img1.width() as i32;
-        let height = img1.height() as i32;
-
-        let offset_x = (width as f32 * progress * dir_x as f32) as i32;
-        let offset_y = (height as f32 * progress * dir_y as f32) as i32;
-
-        for y in 0..height {
-            for x in 0..width {
-                let img1_x = x - offset_x;
-                let img1_y = y - offset_y;
-                let img2_x = x - offset_x + width * dir_x;
-                let img2_y = y - offset_y + height * dir_y;
-
-                let pixel = if img2_x >= 0 && img2_x < width && img2_y >= 0 && img2_y < height {
-                    *img2.get_pixel(img2_x as u32, img2_y as u32)
-                } else if img1_x >= 0 && img1_x < width && img1_y >= 0 && img1_y < height {
-                    *img1.get_pixel(img1_x as u32, img1_y as u32)
-                } else {
-                    Rgba([0, 0, 0, 255])
-                };
+    fn scan_images(&mut self, image_dir: &Path) -> IoResult<()> {
+        self.images.clear();
 
-                result.put_pixel(x as u32, y as u32, pixel);
+        for entry in std::fs::read_dir(image_dir)? {
+            let entry = entry?;
+            let path = entry.path();
+
+            if let Some(ext) = path.extension() {
+                let ext_lower = ext.to_string_lossy().to_lowercase();
+                if is_slide_extension(&ext_lower) {
+                    self.images.push(path);
+                }
             }
         }
-    }
 
-    fn wipe_transition(
-        &self,
-        img1: &RgbaImage,
-        img2: &RgbaImage,
-        progress: f32,
-        result: &mut RgbaImage,
-        direction: u32,
-    ) {
-        let width = img1.width();
-        let height = img1.height();
+        self.images.sort();
+        info!("Found {} images (PNG/JPG/JPEG/WebP)", self.images.len());
+        Ok(())
+    }
 
-        for y in 0..height {
-            for x in 0..width {
-                let should_show_img2 = match direction {
-                    0 => (x as f32 / width as f32) < progress,          // Left
-                    1 => (x as f32 / width as f32) > (1.0 - progress),  // Right
-                    2 => (y as f32 / height as f32) > (1.0 - progress), // Up
-                    3 => (y as f32 / height as f32) < progress,         // Down
-                    _ => false,
-                };
+    // Removed - using load_and_scale_image_with_orientation instead
 
-                let pixel = if should_show_img2 {
-                    *img2.get_pixel(x, y)
+    // The TV's custom curve when it has one (TvConfig transition_easing), otherwise the effect's own
+    fn apply_easing(&self, t: f32, easing_type: &TransitionType) -> f32 {
+        if let Some(curve) = self.easing {
+            return curve.at(t);
+        }
+        match easing_type {
+            TransitionType::EaseIn => t * t,
+            TransitionType::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            TransitionType::EaseInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
                 } else {
-                    *img1.get_pixel(x, y)
-                };
-
-                result.put_pixel(x, y, pixel);
+                    1.0 - 2.0 * (1.0 - t) * (1.0 - t)
+                }
             }
-        }
-    }
-
-    fn circular_wipe_transition(
-        &self,
-        img1: &RgbaImage,
-        img2: &RgbaImage,
-        progress: f32,
-        result: &mut RgbaImage,
-    ) {
-        let width = img1.width() as f32;
-        let height = img1.height() as f32;
-        let center_x = width / 2.0;
-        let center_y = height / 2.0;
-        let max_radius = ((width * width + height * height) / 4.0).sqrt();
-        let current_radius = max_radius * progress;
-
-        for y in 0..height as u32 {
-            for x in 0..width as u32 {
-                let dx = x as f32 - center_x;
-                let dy = y as f32 - center_y;
-                let distance = (dx * dx + dy * dy).sqrt();
-
-                let pixel = if distance < current_radius {
-                    *img2.get_pixel(x, y)
+            TransitionType::Bounce => {
+                if t < 0.5 {
+                    4.0 * t * t * t
                 } else {
-                    *img1.get_pixel(x, y)
-                };
-
-                result.put_pixel(x, y, pixel);
+                    let f = 2.0 * t - 2.0;
+                    f * f * f / 2.0 + 1.0
+                }
+            }
+            TransitionType::Elastic => {
+                if t == 0.0 {
+                    0.0
+                } else if t == 1.0 {
+                    1.0
+                } else if t < 0.5 {
+                    -(2.0_f32.powf(20.0 * t - 10.0))
+                        * ((20.0 * t - 11.125) * std::f32::consts::PI / 4.5).sin()
+                        / 2.0
+                } else {
+                    2.0_f32.powf(-20.0 * t + 10.0)
+                        * ((20.0 * t - 11.125) * std::f32::consts::PI / 4.5).sin()
+                        / 2.0
+                        + 1.0
+                }
             }
+            TransitionType::Accelerated => t * t * t,
+            _ => t, // Linear for other types
         }
     }
 
-    fn diagonal_wipe_transition(
-        &self,
-        img1: &RgbaImage,
-        img2: &RgbaImage,
-        progress: f32,
-        result: &mut RgbaImage,
-    ) {
-        let width = img1.width() as f32;
-        let height = img1.height() as f32;
-        let diagonal_length = width + height;
-        let current_position = diagonal_length * progress;
-
-        for y in 0..height as u32 {
-            for x in 0..width as u32 {
-                let diagonal_pos = x as f32 + y as f32;
-
-                let pixel = if diagonal_pos < current_position {
-                    *img2.get_pixel(x, y)
-                } else {
-                    *img1.get_pixel(x, y)
-                };
-
-                result.put_pixel(x, y, pixel);
+    // Set up --gpu-transitions, keeping the software renderer when EGL won't start
+    fn enable_gpu(&mut self) {
+        match gles::GlesRenderer::new() {
+            Ok(renderer) => {
+                info!("🎮 Drawing transitions with OpenGL ES");
+                self.gpu = Some(renderer);
             }
+            Err(e) => warn!("GPU transitions unavailable, drawing them in software: {}", e),
         }
     }
 
-    fn pixelate_transition(
+    // `gpu` draws the frame when set; if it fails it is dropped and the rest of the
+    // transition is drawn in software
+    fn create_transition_frame(
         &self,
         img1: &RgbaImage,
         img2: &RgbaImage,
         progress: f32,
-        result: &mut RgbaImage,
-    ) {
-        let width = img1.width();
-        let height = img1.height();
-        let block_size = (1.0 + (1.0 - progress) * 15.0) as u32; // From 16x16 to 1x1 blocks
-
-        for y in (0..height).step_by(block_size as usize) {
-            for x in (0..width).step_by(block_size as usize) {
-                let use_img2 = fastrand::f32() < progress;
-                let source_img = if use_img2 { img2 } else { img1 };
-                let sample_pixel = *source_img.get_pixel(x, y);
-
-                for by in 0..block_size {
-                    for bx in 0..block_size {
-                        let px = x + bx;
-                        let py = y + by;
-                        if px < width && py < height {
-                            result.put_pixel(px, py, sample_pixel);
-                        }
-                    }
-                }
+        transition_type: &TransitionType,
+        safe_origin: (u32, u32),
+        gpu: &mut Option<gles::Transition>,
+    ) -> RgbaImage {
+        let rendered = gpu.as_ref().map(|gpu| gpu.render(transition_type, self.apply_easing(progress, transition_type)));
+        let mut result = match rendered {
+            Some(Ok(frame)) => frame,
+            Some(Err(e)) => {
+                error!("GPU transition failed, finishing in software: {}", e);
+                *gpu = None;
+                self.render_transition(img1, img2, progress, transition_type)
             }
+            None => self.render_transition(img1, img2, progress, transition_type),
+        };
+
+        // Add transition name text overlay in the top left corner of the safe area
+        if self.debug_overlay {
+            self.add_transition_text(&mut result, transition_type.name(), safe_origin);
         }
+
+        result
     }
 
-    fn morph_transition(
+    // Render a single transition frame without any overlay; progress 0.0 is img1 and 1.0 is img2
+    fn render_transition(
         &self,
         img1: &RgbaImage,
         img2: &RgbaImage,
         progress: f32,
-        result: &mut RgbaImage,
-    ) {
+        transition_type: &TransitionType,
+    ) -> RgbaImage {
         let width = img1.width();
         let height = img1.height();
-        let distortion = progress * 0.1; // Maximum 10% distortion
-
-        for y in 0..height {
-            for x in 0..width {
-                // Create wave distortion effect
-                let wave_x = (y as f32 * 0.02 + progress * 6.28).sin() * distortion * width as f32;
-                let wave_y = (x as f32 * 0.02 + progress * 6.28).cos() * distortion * height as f32;
-
-                let src_x = ((x as f32 + wave_x) as i32).max(0).min(width as i32 - 1) as u32;
-                let src_y = ((y as f32 + wave_y) as i32).max(0).min(height as i32 - 1) as u32;
-
-                let p1 = img1.get_pixel(src_x, src_y);
-                let p2 = img2.get_pixel(x, y);
+        let mut result = RgbaImage::new(width, height);
 
-                let r = (p1[0] as f32 * (1.0 - progress) + p2[0] as f32 * progress) as u8;
-                let g = (p1[1] as f32 * (1.0 - progress) + p2[1] as f32 * progress) as u8;
-                let b = (p1[2] as f32 * (1.0 - progress) + p2[2] as f32 * progress) as u8;
-                let a = (p1[3] as f32 * (1.0 - progress) + p2[3] as f32 * progress) as u8;
+        // Apply transition-specific easing
+        let eased_progress = self.apply_easing(progress, transition_type);
 
-                result.put_pixel(x, y, Rgba([r, g, b, a]));
-            }
+        // Effects missing from the registry can't be configured, but a fade keeps the show going
+        match transitions::get(transition_type.key()) {
+            Some(effect) => effect.frame(img1, img2, eased_progress, &mut result),
+            None => transitions::Fade.frame(img1, img2, eased_progress, &mut result),
         }
+
+        result
     }
 
-    fn add_transition_text(&self, image: &mut RgbaImage, transition_name: &str) {
-        let char_size = 4;
-        let text_color = Rgba([255, 255, 0, 255]); // Bright yellow
-        let bg_color = Rgba([0, 0, 0, 180]); // Semi-transparent black background
+    fn add_transition_text(&self, image: &mut RgbaImage, transition_name: &str, (left, top): (u32, u32)) {
+        let char_size = self.text_profile.char_size(text_char_size(4, image.width(), image.height()));
+        let bg_color = self.text_profile.background(Rgba([0, 0, 0, 180])); // Semi-transparent black background
+        let text_color = self.text_profile.foreground(Rgba([255, 255, 0, 255]), bg_color); // Bright yellow
 
         // Calculate text dimensions
         let char_width = 7 * char_size;
@@ -884,8 +2448,8 @@ impl ImageManager {
         let bg_width = text_width + padding * 2;
         let bg_height = text_height + padding * 2;
 
-        for y in 0..bg_height {
-            for x in 0..bg_width {
+        for y in top..top + bg_height {
+            for x in left..left + bg_width {
                 if x < image.width() && y < image.height() {
                     image.put_pixel(x, y, bg_color);
                 }
@@ -896,8 +2460,8 @@ impl ImageManager {
         draw_text(
             image,
             transition_name,
-            padding,
-            padding,
+            left + padding,
+            top + padding,
             char_size,
             text_color,
         );
@@ -907,73 +2471,180 @@ impl ImageManager {
         &self,
         from_idx: usize,
         to_idx: usize,
-        fb: &mut Framebuffer,
+        target: RenderTarget,
         transition_duration: Duration,
         transition_type: TransitionType,
-        orientation: &Orientation,
-    ) -> IoResult<()> {
+    ) -> IoResult<TransitionStats> {
+        let RenderTarget { fb, orientation, mirrors } = target;
+        // A hot or overloaded CPU gets cheaper transitions (quality::Mode)
+        let quality = quality::mode();
+        let transition_type = if quality >= quality::Mode::FadesOnly { TransitionType::Fade } else { transition_type };
         let transition_name = transition_type.name();
+        // E-paper can't animate; the transition collapses to a single refresh showing the new image
+        let transition_duration = if fb.is_eink() || quality == quality::Mode::NoTransitions { Duration::ZERO } else { transition_duration };
+        let frame_interval = FALLBACK_FRAME_INTERVAL * quality.frame_interval_multiplier();
+        let _span = info_span!("transition", effect = transition_name).entered();
 
-        println!(
+        info!(
             "Playing {} transition: {} -> {}",
             transition_name,
             self.images[from_idx].display(),
             self.images[to_idx].display()
         );
 
-        // Load source images with orientation using fixed framebuffer dimensions
-        let from_img = load_and_scale_image_with_orientation(&self.images[from_idx], DEFAULT_LANDSCAPE_WIDTH, DEFAULT_LANDSCAPE_HEIGHT, orientation)
+        // Load source images with orientation, scaled to the framebuffer's mode
+        let from_img = self.load_outgoing(&self.images[from_idx], fb, orientation)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-        let to_img = load_and_scale_image_with_orientation(&self.images[to_idx], DEFAULT_LANDSCAPE_WIDTH, DEFAULT_LANDSCAPE_HEIGHT, orientation)
+        let to_img = self.load(&self.images[to_idx], fb, orientation)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        // Ken Burns stills leave from where their motion got to and arrive at its start
+        let from_img = self.with_motion(&self.images[from_idx], from_img, fb, self.motion_progress);
+        let to_img = self.with_motion(&self.images[to_idx], to_img, fb, 0.0);
+
+        // Mirroring outputs play the same transition scaled to their own mode and orientation
+        let mut mirror_images = Vec::new();
+        for (i, output) in mirrors.iter().enumerate().filter(|(_, output)| output.playlist.is_none()) {
+            let from = self.load_outgoing(&self.images[from_idx], &output.fb, &output.orientation);
+            let to = self.load(&self.images[to_idx], &output.fb, &output.orientation);
+            let (safe_x, safe_y, _, _) = output.fb.safe_rect();
+            match (from, to) {
+                (Ok(from), Ok(to)) => mirror_images.push((i, from, to, (safe_x, safe_y))),
+                (Err(e), _) | (_, Err(e)) => warn!("Skipping transition on {}: {}", output.device, e),
+            }
+        }
 
-        let frame_count = (transition_duration.as_millis() / 33) as usize; // ~30 FPS
-        let frame_duration = transition_duration / frame_count as u32;
+        // Frames are drawn on a worker thread while this one converts and writes the previous
+        // ones, so composition overlaps framebuffer I/O. Time is divided into frame_interval
+        // slots, and each frame is drawn for a slot: the one after the last frame's, or the one
+        // after the current slot when drawing has fallen behind, the slots in between counting
+        // as dropped. Without vsync, or at a reduced frame rate, a frame is shown at the start of
+        // its slot, and its progress is that time's. Otherwise every write waits for the
+        // display, so frames are drawn with the progress of the moment they are started.
+        let start = Instant::now();
+        let mut frames_shown = 0u32;
+        let mut dropped_frames = 0u32;
+        let (safe_x, safe_y, _, _) = fb.safe_rect();
+        let paced = !fb.vsync || frame_interval > FALLBACK_FRAME_INTERVAL;
+        let progress_at = |elapsed: Duration| {
+            if transition_duration.is_zero() {
+                1.0
+            } else {
+                (elapsed.as_secs_f32() / transition_duration.as_secs_f32()).min(1.0)
+            }
+        };
 
-        println!(
-            "Generating {} transition frames at {}ms per frame",
-            frame_count,
-            frame_duration.as_millis()
-        );
+        let used_gpu = thread::scope(|scope| -> IoResult<bool> {
+            let (frame_sender, frame_receiver) = mpsc::sync_channel::<TransitionFrame>(TRANSITION_QUEUE_DEPTH);
+            let (from_img, to_img, mirror_images) = (&from_img, &to_img, &mirror_images);
+            let transition_type = &transition_type;
+            let producer = scope.spawn(move || {
+                // The GPU draws the effects it has shaders for, on the primary display
+                let mut gpu = match &self.gpu {
+                    Some(renderer) if gles::GlesRenderer::supports(transition_type) && !transition_duration.is_zero() => {
+                        match renderer.begin(from_img, to_img) {
+                            Ok(transition) => Some(transition),
+                            Err(e) => {
+                                warn!("Drawing {} transition in software: {}", transition_name, e);
+                                None
+                            }
+                        }
+                    }
+                    _ => None,
+                };
 
-        for i in 0..frame_count {
-            let start = Instant::now();
-
-            // Generate transition frame with selected effect
-            let progress = i as f32 / (frame_count - 1) as f32;
-            let transition_frame = self.create_transition_frame(
-                &from_img,
-                &to_img,
-                progress,
-                &transition_type,
-                transition_name,
-            );
-            let buffer = fb.image_to_bgra_buffer(&transition_frame);
+                let mut slot = 0;
+                loop {
+                    let progress = if paced {
+                        progress_at(frame_interval * slot)
+                    } else {
+                        progress_at(start.elapsed())
+                    };
+                    // Mirror frames are drawn alongside the primary's rather than after it, which
+                    // stays on this thread since the GPU context is current here
+                    let mut mirrors = Vec::new();
+                    let primary = rayon::in_place_scope(|scope| {
+                        scope.spawn(|_| {
+                            mirrors = mirror_images.par_iter()
+                                .map(|(i, from, to, origin)| (*i, self.create_transition_frame(from, to, progress, transition_type, *origin, &mut None)))
+                                .collect();
+                        });
+                        self.create_transition_frame(from_img, to_img, progress, transition_type, (safe_x, safe_y), &mut gpu)
+                    });
+                    // The writer has stopped when it can't take a frame
+                    if frame_sender.send(TransitionFrame { slot, primary, mirrors }).is_err() || progress >= 1.0 {
+                        break;
+                    }
 
-            fb.display_buffer(&buffer)?;
+                    let current_slot = (start.elapsed().as_nanos() / frame_interval.as_nanos()) as u32;
+                    slot = if paced { (slot + 1).max(current_slot + 1) } else { current_slot };
+                }
+                gpu.is_some()
+            });
 
-            if i % 10 == 0 {
-                println!(
-                    "Generated and played {} transition frame {}/{}",
-                    transition_name,
-                    i + 1,
-                    frame_count
-                );
-            }
+            let mut last_slot = None;
+            for frame in frame_receiver {
+                if let Some(last_slot) = last_slot {
+                    dropped_frames += frame.slot.saturating_sub(last_slot + 1);
+                }
+                last_slot = Some(frame.slot);
+                if paced {
+                    thread::sleep((start + frame_interval * frame.slot).saturating_duration_since(Instant::now()));
+                }
 
-            let elapsed = start.elapsed();
-            if elapsed < frame_duration {
-                thread::sleep(frame_duration - elapsed);
+                // Every output is written at once, each display waiting on its own vsync
+                let mut mirror_writes: Vec<(&mut Output, &RgbaImage)> = Vec::with_capacity(frame.mirrors.len());
+                let mut images = frame.mirrors.iter().peekable();
+                for (i, output) in mirrors.iter_mut().enumerate() {
+                    if let Some((_, image)) = images.next_if(|(index, _)| *index == i) {
+                        mirror_writes.push((output, image));
+                    }
+                }
+                let written = rayon::scope(|scope| {
+                    for (output, image) in mirror_writes {
+                        scope.spawn(move |_| {
+                            let buffer = output.fb.image_to_buffer(image);
+                            if let Err(e) = output.fb.display_buffer(buffer) {
+                                error!("Failed to display transition frame on {}: {}", output.device, e);
+                            }
+                        });
+                    }
+                    // An e-paper panel has no framebuffer memory to write into
+                    if fb.is_eink() {
+                        return fb.display_eink(&frame.primary);
+                    }
+                    let buffer = fb.image_to_buffer(&frame.primary);
+                    fb.display_buffer(buffer)
+                });
+                written?;
+                frames_shown += 1;
             }
-        }
-
-        println!("{} transition completed", transition_name);
-        Ok(())
+            Ok(producer.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+        })?;
+
+        let elapsed = start.elapsed();
+        let stats = TransitionStats {
+            frames: frames_shown,
+            dropped_frames,
+            fps: frames_shown as f32 / elapsed.as_secs_f32().max(0.001),
+            duration_ms: elapsed.as_millis() as u64,
+        };
+        info!(
+            "{} transition completed: {} frames in {}ms ({:.1} FPS, {} dropped{}{}{})",
+            transition_name,
+            stats.frames,
+            stats.duration_ms,
+            stats.fps,
+            stats.dropped_frames,
+            if fb.vsync { ", vsync" } else { "" },
+            if used_gpu { ", GPU" } else { "" },
+            if quality == quality::Mode::Full { String::new() } else { format!(", {} quality", quality.name()) }
+        );
+        Ok(stats)
     }
 
     fn add_new_image(&mut self, path: PathBuf) -> Option<usize> {
         if !self.images.contains(&path) {
-            println!("Added new image to queue: {}", path.display());
+            info!("Added new image to queue: {}", path.display());
             self.images.push(path.clone());
             self.images.sort();
             // Return the index of the newly added image after sorting
@@ -992,7 +2663,7 @@ fn setup_filesystem_watcher(tx: Sender<SlideshowEvent>, watch_dir: &Path) -> Not
                     for path in event.paths {
                         if let Some(ext) = path.extension() {
                             let ext_lower = ext.to_string_lossy().to_lowercase();
-                            if ext_lower == "png" || ext_lower == "jpg" || ext_lower == "jpeg" {
+                            if is_slide_extension(&ext_lower) {
                                 // Normalize the path to remove any redundant components
                                 let normalized_path = if path.is_absolute() {
                                     // Convert absolute path to relative by getting just the filename
@@ -1009,7 +2680,7 @@ fn setup_filesystem_watcher(tx: Sender<SlideshowEvent>, watch_dir: &Path) -> Not
                     }
                 }
             }
-            Err(e) => println!("Filesystem watch error: {:?}", e),
+            Err(e) => info!("Filesystem watch error: {:?}", e),
         }
     })?;
 
@@ -1022,10 +2693,11 @@ fn setup_signal_handler(tx: Sender<SlideshowEvent>) -> std::thread::JoinHandle<(
         let mut signals = Signals::new(&[SIGINT, SIGTERM]).unwrap();
         for sig in signals.forever() {
             match sig {
-                SIGINT => println!("\nReceived SIGINT, shutting down..."),
-                SIGTERM => println!("\nReceived SIGTERM, shutting down..."),
-                _ => println!("\nReceived signal {}, shutting down...", sig),
+                SIGINT => info!("Received SIGINT, shutting down..."),
+                SIGTERM => info!("Received SIGTERM, shutting down..."),
+                _ => info!("Received signal {}, shutting down...", sig),
             }
+            systemd::stopping();
             let _ = tx.send(SlideshowEvent::Shutdown);
             break;
         }
@@ -1136,6 +2808,18 @@ fn draw_simple_char(
     }
 }
 
+// Glyph size for text designed at `base` on a 1080-line screen. Above that it grows in whole
+// steps (double on a 4K screen); below it shrinks in proportion so lines still fit on small
+// panels (8 becomes 2 on a 480x320 TFT)
+fn text_char_size(base: u32, width: u32, height: u32) -> u32 {
+    let lines = width.min(height);
+    if lines >= TEXT_DESIGN_HEIGHT {
+        base * (lines / TEXT_DESIGN_HEIGHT)
+    } else {
+        (base * lines / TEXT_DESIGN_HEIGHT).max(1)
+    }
+}
+
 fn draw_text(image: &mut RgbaImage, text: &str, x: u32, y: u32, char_size: u32, color: Rgba<u8>) {
     let char_width = 7 * char_size; // Each character is 7 units wide
     let char_spacing = char_size; // Space between characters
@@ -1170,22 +2854,24 @@ fn wrap_text(text: &str, max_chars_per_line: usize) -> Vec<String> {
     lines
 }
 
-fn display_exit_joke(fb: &mut Framebuffer) -> IoResult<()> {
+fn display_exit_joke(fb: &mut Framebuffer, text_profile: &TextProfile) -> IoResult<()> {
     let joke = get_random_joke();
-    println!("\n🎭 Parting wisdom: {}", joke);
+    info!("🎭 Parting wisdom: {}", joke);
 
-    // Create a black background image
-    let mut exit_image = RgbaImage::new(fb.width, fb.height);
+    // Create a black background image the size of the safe area
+    let (_, _, width, height) = fb.safe_rect();
+    let mut exit_image = RgbaImage::new(width, height);
+    let background = text_profile.background(Rgba([0, 0, 0, 255]));
 
     // Fill with black background
     for pixel in exit_image.pixels_mut() {
-        *pixel = Rgba([0, 0, 0, 255]);
+        *pixel = background;
     }
 
     // Text rendering settings
-    let char_size = 8; // Size multiplier for characters
+    let char_size = text_profile.char_size(text_char_size(8, width, height)); // Size multiplier for characters
     let line_height = 5 * char_size + char_size; // 5 rows per char + spacing
-    let max_chars_per_line = (fb.width / (7 * char_size + char_size)) as usize; // Account for char width + spacing
+    let max_chars_per_line = (width / (7 * char_size + char_size)) as usize; // Account for char width + spacing
 
     // Wrap the joke text
     let lines = wrap_text(joke, max_chars_per_line);
@@ -1194,22 +2880,22 @@ fn display_exit_joke(fb: &mut Framebuffer) -> IoResult<()> {
     let total_text_height = lines.len() as u32 * line_height;
 
     // Center the text vertically
-    let start_y = (fb.height - total_text_height) / 2;
+    let start_y = height.saturating_sub(total_text_height) / 2;
 
     // Draw each line of text
-    let bright_color = Rgba([255, 255, 0, 255]); // Bright yellow
+    let bright_color = text_profile.foreground(Rgba([255, 255, 0, 255]), background); // Bright yellow
 
     for (line_idx, line) in lines.iter().enumerate() {
         // Center each line horizontally
         let text_width = line.len() as u32 * (7 * char_size + char_size);
-        let start_x = (fb.width - text_width) / 2;
+        let start_x = width.saturating_sub(text_width) / 2;
         let y = start_y + (line_idx as u32 * line_height);
 
         draw_text(&mut exit_image, line, start_x, y, char_size, bright_color);
     }
 
-    fb.display_image(&exit_image)?;
-    println!("Displayed joke on framebuffer: {}", joke);
+    fb.display_image(&fb.inset_frame(exit_image))?;
+    info!("Displayed joke on framebuffer: {}", joke);
     
     // Check for second SIGINT during sleep to allow immediate exit
     use std::sync::atomic::{AtomicBool, Ordering};
@@ -1223,9 +2909,9 @@ fn display_exit_joke(fb: &mut Framebuffer) -> IoResult<()> {
         let mut signals = Signals::new(&[SIGINT, SIGTERM]).unwrap();
         for sig in signals.forever() {
             match sig {
-                SIGINT => println!("Second SIGINT received, exiting immediately"),
-                SIGTERM => println!("Second SIGTERM received, exiting immediately"),
-                _ => println!("Second signal {} received, exiting immediately", sig),
+                SIGINT => info!("Second SIGINT received, exiting immediately"),
+                SIGTERM => info!("Second SIGTERM received, exiting immediately"),
+                _ => info!("Second signal {} received, exiting immediately", sig),
             }
             interrupted_clone.store(true, Ordering::Relaxed);
             std::process::exit(0); // Force immediate exit
@@ -1245,7 +2931,54 @@ fn display_exit_joke(fb: &mut Framebuffer) -> IoResult<()> {
 
 #[tokio::main]
 async fn main() -> IoResult<()> {
-    let args = Args::parse();
+    let result = run().await;
+    if let Err(ref e) = result {
+        crash::record_error(e);
+    }
+    result
+}
+
+async fn run() -> IoResult<()> {
+    let mut command = Args::command();
+    let args = match config_file::layered_args(&command) {
+        Ok(argv) => {
+            let args = Args::parse_from(&argv);
+            config_file::set_explicit(&command, &argv);
+            args
+        }
+        Err(e) => command.error(clap::error::ErrorKind::InvalidValue, e).exit(),
+    };
+    let log_level = args.log_level.clone().or_else(|| std::env::var("RUST_LOG").ok()).unwrap_or_else(|| "info".to_string());
+    let log_export = args.log_export.clone().map(|url| log_export::Settings {
+        url,
+        level: args.log_export_level.clone(),
+        log_dir: args.log_dir.clone(),
+        buffer_bytes: args.log_export_buffer_mb * 1_000_000,
+    });
+    logging::init(&log_level, &args.log_dir, args.log_max_mb, args.log_files, log_export);
+    crash::install(&args.log_dir);
+    fingerprint::set_config(&fingerprinted(&args));
+    updater::check_startup();
+    if let Err(e) = privileges::configure(args.user.as_deref()) {
+        command.error(clap::error::ErrorKind::InvalidValue, format!("--user: {}", e)).exit();
+    }
+    if args.setup_mode {
+        match provisioning::check(args.config.as_deref(), &command).await {
+            provisioning::Start::Normal => {}
+            provisioning::Start::Restart => restart_process(),
+            provisioning::Start::Portal => return run_setup_mode(&args, &command).await,
+        }
+    }
+    avif::configure(args.avif_max_megapixels, Duration::from_secs(args.avif_decode_timeout));
+    video::configure(&args.video_decoder, args.video_audio_device.clone());
+    web_page::configure(&args.web_renderer);
+    if let Some(ref device) = args.cec_device {
+        cec::configure(device).await;
+    }
+    icc::configure(!args.skip_color_profiles);
+    health::set_min_free_disk_bytes(args.min_free_disk_mb * 1_000_000);
+    quality::configure(args.adaptive_quality);
+    offline_banner::configure(args.offline_banner_minutes);
     
     // Generate TV ID if not provided
     let tv_id = args.tv_id.clone().unwrap_or_else(|| {
@@ -1253,15 +2986,16 @@ async fn main() -> IoResult<()> {
             tokio::runtime::Handle::current().block_on(mqtt_client::generate_tv_id())
         })
     });
+    log_export::set_tv_id(&tv_id);
     
-    println!("Raspberry Pi Image Slideshow with MQTT Control");
-    println!("TV ID: {}", tv_id);
-    println!("Image directory: {}", args.image_dir.display());
-    println!("Display duration: {} seconds", args.delay);
-    println!("Transition duration: {} ms", args.transition);
-    println!("Framebuffer device: {}", args.framebuffer.display());
-    println!("MQTT broker: {}", args.mqtt_broker);
-    println!("CouchDB server: {}", args.couchdb_url);
+    info!("Raspberry Pi Image Slideshow with MQTT Control");
+    info!("TV ID: {}", tv_id);
+    info!("Image directory: {}", args.image_dir.display());
+    info!("Display duration: {} seconds", display_duration(&args).as_secs());
+    info!("Transition duration: {} ms", args.transition);
+    info!("Framebuffer device: {}", args.framebuffer.display());
+    info!("MQTT broker: {}", args.mqtt_broker);
+    info!("CouchDB server: {}", args.couchdb_url);
     
     if args.enable_mqtt {
         run_with_mqtt_control(args, tv_id).await
@@ -1270,6 +3004,24 @@ async fn main() -> IoResult<()> {
     }
 }
 
+// First-boot setup (see provisioning.rs) on the primary display, then a restart into the
+// slideshow with the settings it saved
+async fn run_setup_mode(args: &Args, command: &clap::Command) -> IoResult<()> {
+    let backend = DisplayBackend::from_args(args);
+    let mut fb = open_display(&backend, &args.framebuffer, resolution_preset(&args.resolution), pixel_format_override(&args.pixel_format))?;
+    let orientation = apply_rotation(&mut fb, hardware_rotation(&args.rotation), &Orientation::from(args.orientation.as_str()));
+    privileges::drop_root();
+    let text_profile = TextProfile::from(args.text_profile.as_str());
+    let config = args.config.clone().unwrap_or_default();
+    provisioning::run_portal(args.http_port, &config, command, &args.mqtt_broker, &args.couchdb_url, |screen| {
+        let image = fb.render_screen(&orientation, |width, height| screen.render(width, height, &text_profile));
+        if let Err(e) = fb.display_image(&image) {
+            error!("Failed to show the setup screen: {}", e);
+        }
+    }).await;
+    restart_process()
+}
+
 async fn run_with_mqtt_control(args: Args, tv_id: String) -> IoResult<()> {
     // Create communication channels
     let (command_sender, command_receiver) = broadcast::channel::<SlideshowCommand>(100);
@@ -1278,7 +3030,8 @@ async fn run_with_mqtt_control(args: Args, tv_id: String) -> IoResult<()> {
     // Create controller config
     let controller_config = ControllerConfig {
         image_dir: args.image_dir.clone(),
-        display_duration: Duration::from_secs(args.delay),
+        display_duration: display_duration(&args),
+        min_display_duration: if DisplayBackend::from_args(&args).is_eink() { display_duration(&args) } else { Duration::ZERO },
         transition_duration: Duration::from_millis(args.transition),
         couchdb_url: args.couchdb_url.clone(),
         couchdb_username: args.couchdb_username.clone(),
@@ -1286,6 +3039,34 @@ async fn run_with_mqtt_control(args: Args, tv_id: String) -> IoResult<()> {
         tv_id: tv_id.clone(),
         orientation: args.orientation.clone(),
         transition_effect: "fade".to_string(), // Default transition effect
+        content_safety: args.content_safety,
+        text_profile: args.text_profile.clone(),
+        framebuffer_refresh_secs: args.framebuffer_refresh_secs,
+        skip_redundant_writes: args.skip_redundant_writes,
+        gamma: args.gamma,
+        color_temperature: args.color_temperature,
+        brightness: args.brightness,
+        dimming_schedule: Vec::new(), // Only configured from CouchDB or the config endpoint
+        quiet_hours: Vec::new(),
+        curtain_hours: Vec::new(),
+        curtain_color: "#000000".to_string(),
+        curtain_image: None,
+        letterbox_fill: "color".to_string(),
+        letterbox_color: "#000000".to_string(),
+        letterbox_image: None,
+        safe_area: SafeArea::default(),
+        ken_burns: "off".to_string(),
+        ken_burns_intensity: 0.15,
+        smart_crop: false,
+        transition_easing: "default".to_string(),
+        transition_durations: HashMap::new(),
+        shuffle: false,
+        synced_shuffle: None,
+        ticker_rss_url: None,
+        tags: Vec::new(),
+        image_cache_size: args.image_cache_size,
+        frame_cache_max_bytes: args.frame_cache_mb * 1_000_000,
+        image_dir_max_bytes: args.image_dir_max_mb * 1_000_000,
     };
     
     // Initialize slideshow controller
@@ -1306,22 +3087,23 @@ async fn run_with_mqtt_control(args: Args, tv_id: String) -> IoResult<()> {
         )
     ).await {
         Ok(Ok(mqtt_client)) => {
-            println!("Connected to MQTT broker at {}", args.mqtt_broker);
+            info!("Connected to MQTT broker at {}", args.mqtt_broker);
             controller.set_mqtt_client(mqtt_client.clone()).await;
+            crash::spawn_publisher(mqtt_client.clone());
             
             // Start heartbeat publisher only if MQTT connected
             let mut heartbeat_client = mqtt_client.clone();
-            tokio::spawn(async move {
+            crash::spawn("heartbeat", async move {
                 heartbeat_client.run_status_publisher().await;
             });
         }
         Ok(Err(e)) => {
-            eprintln!("Warning: Failed to connect to MQTT broker: {}", e);
-            println!("Continuing without MQTT remote control");
+            error!("Warning: Failed to connect to MQTT broker: {}", e);
+            info!("Continuing without MQTT remote control");
         }
         Err(_) => {
-            eprintln!("Warning: MQTT connection timeout after 5 seconds");
-            println!("Continuing without MQTT remote control");
+            warn!("Warning: MQTT connection timeout after 5 seconds");
+            info!("Continuing without MQTT remote control");
         }
     }
     
@@ -1334,13 +3116,13 @@ async fn run_with_mqtt_control(args: Args, tv_id: String) -> IoResult<()> {
     
     // Start command handler
     let mut controller_clone = controller.clone();
-    tokio::spawn(async move {
+    crash::spawn("command handler", async move {
         controller_clone.run_command_handler().await;
     });
     
     // Start periodic tasks
     let controller_clone = controller.clone();
-    tokio::spawn(async move {
+    crash::spawn("periodic tasks", async move {
         controller_clone.run_periodic_tasks().await;
     });
     
@@ -1348,93 +3130,725 @@ async fn run_with_mqtt_control(args: Args, tv_id: String) -> IoResult<()> {
     let http_controller = controller.clone();
     let http_command_sender = command_sender.clone();
     let http_port = args.http_port;
-    tokio::spawn(async move {
+    crash::spawn("http server", async move {
         http_server::run_http_server(http_port, http_controller, http_command_sender).await;
     });
     
+    let _config_watcher = watch_config_file(&args, command_sender.clone());
+    let _buttons = gpio::spawn(&args.gpio_buttons, controller.clone(), command_sender.clone());
+    let _motion_sensor = args.pir_pin
+        .and_then(|pin| motion::spawn(pin, args.pir_idle_minutes, args.pir_idle_brightness, controller.clone()));
+    let update_settings = args.update_channel.clone().map(|channel| updater::UpdateSettings {
+        channel,
+        public_key: args.update_public_key.clone(),
+        check_interval: Duration::from_secs(args.update_check_minutes.max(1) * 60),
+    });
+    updater::spawn(update_settings, controller.clone());
+    throttle::spawn(Some(controller.clone()));
+    history::spawn();
+    
     // Run main slideshow loop
     run_slideshow_loop(args, controller).await
 }
 
+// Apply edits to the --config file while running. Settings the controller can change go through
+// UpdateConfig like a change from the management server; a change to anything only read at
+// startup (the MQTT broker, CouchDB, the framebuffer, ...) restarts the process, which waits
+// for downloads to finish and picks up the new settings. Flags given on the command line still
+// win, so editing the file can't change them.
+fn watch_config_file(args: &Args, command_sender: broadcast::Sender<SlideshowCommand>) -> Option<RecommendedWatcher> {
+    let path = args.config.clone()?;
+    let (tx, mut rx) = async_mpsc::unbounded_channel();
+    let watcher = match config_file::watch(&path, move || { let _ = tx.send(()); }) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            warn!("Not watching {} for changes: {}", path.display(), e);
+            return None;
+        }
+    };
+    info!("Watching {} for configuration changes", path.display());
+    
+    let mut current = args.clone();
+    tokio::spawn(async move {
+        while rx.recv().await.is_some() {
+            tokio::time::sleep(CONFIG_RELOAD_DELAY).await;
+            while rx.try_recv().is_ok() {}
+            
+            let reloaded = match config_file::layered_args(&Args::command())
+                .and_then(|argv| Args::try_parse_from(&argv).map(|args| (args, argv)).map_err(|e| e.to_string().lines().next().unwrap_or_default().trim_start_matches("error: ").to_string()))
+            {
+                Ok((reloaded, argv)) => {
+                    config_file::set_explicit(&Args::command(), &argv);
+                    reloaded
+                }
+                Err(e) => {
+                    warn!("⚠️  Ignoring changes to {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            if reloaded == current {
+                continue;
+            }
+            
+            let mut update = SlideshowConfig::default();
+            if display_duration(&reloaded) != display_duration(&current) {
+                update.display_duration = Some(display_duration(&reloaded).as_millis() as u64);
+            }
+            if reloaded.transition != current.transition {
+                update.transition_duration = Some(reloaded.transition);
+            }
+            if reloaded.orientation != current.orientation {
+                update.orientation = Some(reloaded.orientation.clone());
+            }
+            if reloaded.content_safety != current.content_safety {
+                update.content_safety = Some(reloaded.content_safety);
+            }
+            if reloaded.text_profile != current.text_profile {
+                update.text_profile = Some(reloaded.text_profile.clone());
+            }
+            if reloaded.framebuffer_refresh_secs != current.framebuffer_refresh_secs {
+                update.framebuffer_refresh_secs = Some(reloaded.framebuffer_refresh_secs);
+            }
+            if reloaded.skip_redundant_writes != current.skip_redundant_writes {
+                update.skip_redundant_writes = Some(reloaded.skip_redundant_writes);
+            }
+            if reloaded.gamma != current.gamma {
+                update.gamma = Some(reloaded.gamma);
+            }
+            if reloaded.color_temperature != current.color_temperature {
+                update.color_temperature = Some(reloaded.color_temperature);
+            }
+            if reloaded.brightness != current.brightness {
+                update.brightness = Some(reloaded.brightness);
+            }
+            
+            // Everything else is fixed at startup
+            let startup_only_changed = Args {
+                delay: current.delay,
+                transition: current.transition,
+                orientation: current.orientation.clone(),
+                content_safety: current.content_safety,
+                text_profile: current.text_profile.clone(),
+                framebuffer_refresh_secs: current.framebuffer_refresh_secs,
+                skip_redundant_writes: current.skip_redundant_writes,
+                gamma: current.gamma,
+                color_temperature: current.color_temperature,
+                brightness: current.brightness,
+                ..reloaded.clone()
+            } != current;
+            
+            if startup_only_changed {
+                // The restart reads the file again, live settings included
+                info!("📝 {} changed settings that are only read at startup, restarting", path.display());
+                let _ = command_sender.send(SlideshowCommand::Restart);
+            } else {
+                info!("📝 {} changed, applying new settings", path.display());
+                let _ = command_sender.send(SlideshowCommand::UpdateConfig { config: Box::new(update) });
+            }
+            fingerprint::set_config(&fingerprinted(&reloaded));
+            current = reloaded;
+        }
+    });
+    Some(watcher)
+}
+
+// The settings the heartbeat's config hash covers: all of them but the TV ID, which differs on
+// every unit, and the path of the config file, whose contents are already in the rest
+fn fingerprinted(args: &Args) -> Args {
+    Args { tv_id: None, config: None, ..args.clone() }
+}
+
 async fn run_standalone_mode(args: Args) -> IoResult<()> {
-    println!("Running in standalone mode (no MQTT control)");
+    info!("Running in standalone mode (no MQTT control)");
+    throttle::spawn(None);
     
     // Convert to legacy config and run original slideshow
     let config = Config {
+        display: DisplayBackend::from_args(&args),
+        display_duration: display_duration(&args),
         image_dir: args.image_dir,
-        display_duration: Duration::from_secs(args.delay),
         transition_duration: Duration::from_millis(args.transition),
         framebuffer_path: args.framebuffer,
         orientation: Orientation::from(args.orientation.as_str()),
+        content_safety: args.content_safety,
+        text_profile: TextProfile::from(args.text_profile.as_str()),
+        debug_overlay: args.debug_overlay,
+        gpu_transitions: args.gpu_transitions,
+        framebuffer_refresh: refresh_interval(args.framebuffer_refresh_secs),
+        skip_redundant_writes: args.skip_redundant_writes,
+        pixel_format: pixel_format_override(&args.pixel_format),
+        hardware_rotation: hardware_rotation(&args.rotation),
+        fallback_resolution: resolution_preset(&args.resolution),
+        frame_memory_cap: frame_memory_cap(args.frame_memory_cap_mb),
+        scaling: scaling_mode(&args.scaling),
+        gamma: args.gamma,
+        color_temperature: args.color_temperature,
+        brightness: args.brightness,
+        image_cache_size: args.image_cache_size,
+        frame_cache_max_bytes: args.frame_cache_mb * 1_000_000,
     };
     
     run_original_slideshow(config)
 }
 
-async fn run_slideshow_loop(args: Args, controller: SlideshowController) -> IoResult<()> {
-    // Get initial orientation from controller (which may be updated from CouchDB)
-    let orientation_str = controller.get_orientation().await;
-    let mut current_orientation = Orientation::from(orientation_str.as_str());
-    
-    // Always use physical display dimensions (1920x1080) regardless of orientation
-    // Orientation is handled through image processing, not framebuffer resizing
-    let mut fb = Framebuffer::new(DEFAULT_LANDSCAPE_WIDTH, DEFAULT_LANDSCAPE_HEIGHT, &args.framebuffer)?;
-    let mut image_manager = ImageManager::new();
-    
-    // Setup event handling for filesystem and signals
-    let (tx, rx): (Sender<SlideshowEvent>, Receiver<SlideshowEvent>) = mpsc::channel();
-    let _watcher = setup_filesystem_watcher(tx.clone(), &args.image_dir)
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-    let _signal_handle = setup_signal_handler(tx);
-    
-    let mut last_image_change = Instant::now();
-    let mut running = true;
-    let mut has_displayed_placeholder = false;
-    let mut last_image_count = controller.get_image_count().await;
-    let mut last_displayed_image_path: Option<PathBuf> = None;
-    
-    // Initial display check - show placeholder immediately if no images
-    if controller.get_image_count().await == 0 {
-        let tv_id = controller.get_tv_id().await;
-        let local_ip = get_local_ip().unwrap_or_else(|| "Unknown IP".to_string());
-        let placeholder = create_info_placeholder_with_orientation(&tv_id, &local_ip, DEFAULT_LANDSCAPE_WIDTH, DEFAULT_LANDSCAPE_HEIGHT, &current_orientation);
+async fn run_slideshow_loop(args: Args, controller: SlideshowController) -> IoResult<()> {
+    // Get initial orientation from controller (which may be updated from CouchDB)
+    let orientation_str = controller.get_orientation().await;
+    let mut current_orientation = Orientation::from(orientation_str.as_str());
+    let hardware_rotation = hardware_rotation(&args.rotation);
+    let mut current_text_profile = TextProfile::from(controller.get_text_profile().await.as_str());
+    
+    // Use the display mode the driver reports (--resolution if it can't be queried) regardless of orientation
+    // Orientation is handled through image processing, not framebuffer resizing
+    let (fallback_width, fallback_height) = resolution_preset(&args.resolution);
+    let frame_memory_cap = frame_memory_cap(args.frame_memory_cap_mb);
+    let scaling = scaling_mode(&args.scaling);
+    let backend = DisplayBackend::from_args(&args);
+    let mut fb = open_display(&backend, &args.framebuffer, (fallback_width, fallback_height), pixel_format_override(&args.pixel_format))?;
+    fb.set_memory_cap(frame_memory_cap);
+    fb.set_scaling(scaling);
+    health::set_framebuffer_device(fb.fallback_file.is_none());
+    // What software still has to rotate after any driver rotation
+    let mut render_orientation = apply_rotation(&mut fb, hardware_rotation, &current_orientation);
+    let mut image_manager = ImageManager::new();
+    image_manager.text_profile = current_text_profile.clone();
+    image_manager.debug_overlay = args.debug_overlay;
+    if args.gpu_transitions {
+        image_manager.enable_gpu();
+    }
+    image_manager.cache = controller.image_cache();
+    
+    // Extra displays; one that can't be opened is skipped rather than stopping the slideshow
+    let mut outputs: Vec<Output> = Vec::new();
+    for spec in &args.outputs {
+        match Output::open(spec) {
+            Ok(mut output) => {
+                output.fb.set_memory_cap(frame_memory_cap);
+                output.fb.set_scaling(scaling);
+                info!("🖥️  Output {}: {}x{}, {:?}, {}", output.device, output.fb.width, output.fb.height, output.orientation,
+                         output.playlist.as_ref().map_or("mirroring".to_string(), |p| format!("playlist from {}", p.image_dir.display())));
+                if let Some(ref mut playlist) = output.playlist {
+                    playlist.images.cache = controller.image_cache();
+                    playlist.images.debug_overlay = args.debug_overlay;
+                }
+                outputs.push(output);
+            }
+            Err(e) => warn!("Skipping output {}: {}", spec, e),
+        }
+    }
+    // Everything that needed root is open
+    privileges::drop_root();
+    
+    // Setup event handling for filesystem and signals
+    let (tx, rx): (Sender<SlideshowEvent>, Receiver<SlideshowEvent>) = mpsc::channel();
+    let _watcher = setup_filesystem_watcher(tx.clone(), &args.image_dir)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+    let _signal_handle = setup_signal_handler(tx);
+    
+    let mut last_image_change = Instant::now();
+    let mut running = true;
+    let mut has_displayed_placeholder = false;
+    let mut placeholder_disk_low = false;
+    let mut last_image_count = controller.get_image_count().await;
+    let mut last_displayed_image_path: Option<PathBuf> = None;
+    let mut force_redraw = false;
+    let mut restarting = false;
+    let mut display_asleep = false;
+    let mut hotplug = hotplug::HotplugMonitor::new();
+    let mut last_hotplug_poll = Instant::now();
+    let mut shown_test_pattern: Option<TestPattern> = None;
+    let mut shown_takeover: Option<takeover::Screen> = None;
+    let mut shown_curtain: Option<Curtain> = None;
+    // The video slide on screen, the screen it is drawn into (the letterbox fill stays put
+    // between frames) and whether it has just played to the end
+    let mut video_playback: Option<video::Playback> = None;
+    let mut video_screen: Option<RgbaImage> = None;
+    let mut video_finished = false;
+    // A video that couldn't be started isn't retried until another slide comes up
+    let mut failed_video: Option<PathBuf> = None;
+    // The next slide as last handed to the prefetch thread, so it is prepared once
+    let mut prefetched: Option<CacheKey> = None;
+    // The still moving under the Ken Burns effect, its frame as loaded, how long it has been
+    // moving (paused time excluded) and when its next frame is due
+    let mut motion_slide: Option<PathBuf> = None;
+    let mut motion_base: Option<RgbaImage> = None;
+    let mut motion_elapsed = Duration::ZERO;
+    let mut motion_clock = Instant::now();
+    let mut next_motion_frame = Instant::now();
+    // The countdown slide as last drawn on screen, so it is drawn again once the time has moved on
+    let mut shown_countdown: Option<TextSlide> = None;
+    let mut was_held = false;
+    
+    // A previous run may have exited during quiet hours with the display blanked
+    if let Err(e) = fb.set_blanked(false) {
+        info!("Display blanking unavailable: {}", e);
+    }
+    
+    // Initial display check - show placeholder immediately if no images
+    if controller.get_image_count().await == 0 {
+        let tv_id = controller.get_tv_id().await;
+        let local_ip = get_local_ip().unwrap_or_else(|| "Unknown IP".to_string());
+        let placeholder = create_info_placeholder_with_orientation(&tv_id, &local_ip, &fb, &render_orientation, &current_text_profile);
+        
+        let _ = fb.display_image(&placeholder);
+        show_on_mirrors(&mut outputs, |fb, orientation| {
+            Ok(create_info_placeholder_with_orientation(&tv_id, &local_ip, fb, orientation, &current_text_profile))
+        });
+        has_displayed_placeholder = true;
+        info!("Displayed 'No images available' placeholder on startup");
+    }
+    
+    watchdog::spawn(Duration::from_secs(args.watchdog_secs), controller.clone());
+    systemd::ready();
+    while running {
+        watchdog::beat();
+        systemd::watchdog();
+        
+        // A restart was requested and downloads have drained
+        if controller.is_restart_requested().await {
+            restarting = true;
+            break;
+        }
+        
+        // A display that was unplugged or power-cycled can come back with its framebuffer reset
+        // or in a different mode; reopen everything and redraw the current slide
+        if hotplug.is_available() && !fb.is_eink() && last_hotplug_poll.elapsed() >= HOTPLUG_POLL_INTERVAL {
+            last_hotplug_poll = Instant::now();
+            let reconnected = hotplug.poll();
+            if !reconnected.is_empty() {
+                info!("🔌 Display reconnected ({}), reinitializing framebuffers", reconnected.join(", "));
+                match Framebuffer::new(fallback_width, fallback_height, &args.framebuffer, pixel_format_override(&args.pixel_format)) {
+                    // Falling back to a file here would only hide the display
+                    Ok(new_fb) if new_fb.fallback_file.is_none() => {
+                        fb = new_fb;
+                        fb.set_memory_cap(frame_memory_cap);
+                        fb.set_scaling(scaling);
+                        render_orientation = apply_rotation(&mut fb, hardware_rotation, &current_orientation);
+                    }
+                    Ok(_) => warn!("{} not available after reconnect, keeping the previous framebuffer", args.framebuffer.display()),
+                    Err(e) => error!("Failed to reopen {}: {}", args.framebuffer.display(), e),
+                }
+                for output in &mut outputs {
+                    match Framebuffer::new(DEFAULT_LANDSCAPE_WIDTH, DEFAULT_LANDSCAPE_HEIGHT, Path::new(&output.device), None) {
+                        Ok(new_fb) if new_fb.fallback_file.is_none() => {
+                            output.fb = new_fb;
+                            output.fb.set_memory_cap(frame_memory_cap);
+                            output.fb.set_scaling(scaling);
+                        }
+                        Ok(_) => warn!("{} not available after reconnect", output.device),
+                        Err(e) => error!("Failed to reopen {}: {}", output.device, e),
+                    }
+                    if let Some(ref mut playlist) = output.playlist {
+                        playlist.shown = false;
+                    }
+                }
+                
+                // The new framebuffers start unblanked with neutral color settings; both are
+                // reapplied below before anything is drawn
+                display_asleep = false;
+                force_redraw = true;
+                has_displayed_placeholder = false;
+                last_displayed_image_path = None;
+            }
+        }
+        
+        // Check if orientation has changed (due to MQTT config update)
+        let orientation_str = controller.get_orientation().await;
+        let new_orientation = Orientation::from(orientation_str.as_str());
+        if std::mem::discriminant(&current_orientation) != std::mem::discriminant(&new_orientation) {
+            info!("🔄 DISPLAY ORIENTATION CHANGE: {:?} -> {:?}, forcing immediate redraw", current_orientation, new_orientation);
+            current_orientation = new_orientation;
+            
+            // With software rotation the framebuffer keeps its mode and images are rotated;
+            // driver rotation may swap the framebuffer's width and height instead
+            render_orientation = apply_rotation(&mut fb, hardware_rotation, &current_orientation);
+            info!("🔄 ORIENTATION UPDATED: Framebuffer at {}x{}, {} rotation applied in software", fb.width, fb.height, render_orientation.name());
+            
+            // Redraw the current image in place rather than advancing the slideshow
+            force_redraw = true;
+            has_displayed_placeholder = false; // Force placeholder redraw if needed
+            last_displayed_image_path = None; // Force image reload with new orientation
+        }
+        
+        // Check if the text profile has changed (placeholder needs redrawing with new styling)
+        let new_text_profile = TextProfile::from(controller.get_text_profile().await.as_str());
+        if new_text_profile != current_text_profile {
+            info!("Text profile changed: {:?} -> {:?}", current_text_profile, new_text_profile);
+            current_text_profile = new_text_profile;
+            image_manager.text_profile = current_text_profile.clone();
+            has_displayed_placeholder = false;
+        }
+        
+        // The ticker scrolls over the slideshow but not over takeovers, test patterns or the
+        // curtain, and neither does the offline banner. Taking either down redraws what was
+        // under it.
+        let overlays_hidden = controller.get_takeover().await.is_some()
+            || controller.get_test_pattern().await.is_some()
+            || controller.is_curtain_closed().await;
+        let ticker_text = if overlays_hidden { None } else { controller.get_ticker_text().await };
+        if fb.set_ticker(ticker_text, &render_orientation, &current_text_profile) {
+            force_redraw = true;
+            has_displayed_placeholder = false;
+            last_displayed_image_path = None;
+        }
+        let banner_text = if overlays_hidden { None } else { offline_banner::text() };
+        if fb.set_offline_banner(banner_text, &render_orientation, &current_text_profile) {
+            force_redraw = true;
+            has_displayed_placeholder = false;
+            last_displayed_image_path = None;
+        }
+        
+        // Apply the framebuffer refresh policy for the attached display
+        let (refresh_secs, skip_redundant_writes) = controller.get_framebuffer_refresh_policy().await;
+        fb.set_refresh_policy(refresh_interval(refresh_secs), skip_redundant_writes);
+        for output in &mut outputs {
+            output.fb.set_refresh_policy(refresh_interval(refresh_secs), skip_redundant_writes);
+        }
+        
+        // Redraw with new gamma / white point / brightness settings
+        let (gamma, color_temperature, brightness) = controller.get_color_adjustment().await;
+        let mut color_changed = fb.set_color_adjustment(gamma, color_temperature, brightness);
+        for output in &mut outputs {
+            if output.fb.set_color_adjustment(gamma, color_temperature, brightness) {
+                color_changed = true;
+                // Outputs with their own playlist redraw their current image
+                if let Some(ref mut playlist) = output.playlist {
+                    playlist.shown = false;
+                }
+            }
+        }
+        if color_changed {
+            force_redraw = true;
+            has_displayed_placeholder = false;
+            last_displayed_image_path = None;
+        }
+        
+        // Redraw the current image over a new letterbox fill
+        let (letterbox_fill, letterbox_color, letterbox_image) = controller.get_letterbox().await;
+        let letterbox = Letterbox::from_config(&letterbox_fill, &letterbox_color, letterbox_image.clone());
+        if fb.set_letterbox(letterbox.clone()) {
+            image_manager.cache.clear();
+            prefetched = None;
+            force_redraw = true;
+            last_displayed_image_path = None;
+        }
+        // Images can pick their own fill and fit mode in their metadata
+        if image_manager.set_slide_styles(controller.get_slide_styles().await, &letterbox_color, letterbox_image) {
+            image_manager.cache.clear();
+            prefetched = None;
+            force_redraw = true;
+            last_displayed_image_path = None;
+        }
+        for output in &mut outputs {
+            if output.fb.set_letterbox(letterbox.clone()) {
+                if let Some(ref mut playlist) = output.playlist {
+                    playlist.shown = false;
+                }
+            }
+        }
+        
+        // Recrop cover-fit slides when smart cropping is turned on or off
+        let smart_crop = controller.get_smart_crop().await;
+        if smart_crop != image_manager.smart_crop {
+            image_manager.smart_crop = smart_crop;
+            image_manager.cache.clear();
+            prefetched = None;
+            force_redraw = true;
+            last_displayed_image_path = None;
+        }
+        
+        // Redraw the still from its new motion when the Ken Burns settings change
+        let (ken_burns_effect, ken_burns_intensity) = controller.get_ken_burns().await;
+        let ken_burns = ken_burns::Settings::from_config(&ken_burns_effect, ken_burns_intensity);
+        if ken_burns != image_manager.ken_burns {
+            image_manager.ken_burns = ken_burns;
+            force_redraw = true;
+            last_displayed_image_path = None;
+        }
+        
+        image_manager.easing = easing::CubicBezier::from_config(&controller.get_transition_easing().await);
+        
+        // Redraw inside new overscan margins
+        let safe_area = controller.get_safe_area().await;
+        if fb.set_safe_area(safe_area) {
+            image_manager.cache.clear();
+            prefetched = None;
+            force_redraw = true;
+            has_displayed_placeholder = false;
+            last_displayed_image_path = None;
+        }
+        for output in &mut outputs {
+            if output.fb.set_safe_area(safe_area) {
+                if let Some(ref mut playlist) = output.playlist {
+                    playlist.shown = false;
+                }
+            }
+        }
+        // Web pages are captured at the size they will be shown
+        let (_, _, safe_width, safe_height) = fb.safe_rect();
+        let (page_width, page_height) = render_orientation.upright_size(safe_width, safe_height);
+        web_page::set_viewport(page_width, page_height);
+        
+        // Quiet hours, a stop command or nobody in front of the PIR sensor: keep the display
+        // blanked and render nothing until they end, a wake override arrives or the slideshow is
+        // started again
+        let stopped = controller.is_stopped().await;
+        let unoccupied = !stopped && controller.is_blanked_unoccupied().await;
+        if stopped || unoccupied || controller.is_quiet_hours().await {
+            if !display_asleep {
+                if stopped {
+                    info!("⏹️ Slideshow stopped, blanking display");
+                } else if unoccupied {
+                    info!("🚶 No motion for {} minutes, blanking display", args.pir_idle_minutes.max(1));
+                } else {
+                    info!("🌙 Quiet hours started, blanking display");
+                }
+                if let Err(e) = fb.set_blanked(true) {
+                    error!("Failed to blank display: {}", e);
+                }
+                for output in &mut outputs {
+                    if let Err(e) = output.fb.set_blanked(true) {
+                        error!("Failed to blank {}: {}", output.device, e);
+                    }
+                }
+                display_asleep = true;
+                cec::spawn(cec::Action::Standby);
+            }
+            
+            match rx.try_recv() {
+                Ok(SlideshowEvent::Shutdown) | Err(mpsc::TryRecvError::Disconnected) => running = false,
+                _ => {}
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            continue;
+        } else if display_asleep {
+            info!("☀️ Quiet hours over, slideshow started or motion seen, waking display");
+            if let Err(e) = fb.set_blanked(false) {
+                error!("Failed to unblank display: {}", e);
+            }
+            for output in &mut outputs {
+                if let Err(e) = output.fb.set_blanked(false) {
+                    error!("Failed to unblank {}: {}", output.device, e);
+                }
+                if let Some(ref mut playlist) = output.playlist {
+                    playlist.shown = false;
+                }
+            }
+            display_asleep = false;
+            cec::spawn(cec::Action::PowerOn);
+            
+            // Some drivers lose the picture while powered down; redraw and give it a full display period
+            force_redraw = true;
+            has_displayed_placeholder = false;
+            last_displayed_image_path = None;
+            last_image_change = Instant::now();
+        }
+        
+        // Takeover: emergency content on every display, over test patterns too. The playlist
+        // stays where it was, so the slideshow resumes on the slide that was interrupted.
+        if let Some(screen) = controller.get_takeover().await {
+            if shown_takeover.as_ref() != Some(&screen) || force_redraw {
+                image_manager.finish_prefetch();
+                let render = |fb: &Framebuffer, orientation: &Orientation| match &screen {
+                    takeover::Screen::Image(path) => image_manager.load(path, fb, orientation),
+                    takeover::Screen::Text(text) => Ok(fb.render_screen(orientation, |width, height| takeover::render_text(text, width, height, &current_text_profile))),
+                };
+                match render(&fb, &render_orientation) {
+                    Ok(frame) => {
+                        if let Err(e) = fb.display_image(&frame) {
+                            error!("Failed to display takeover: {}", e);
+                        }
+                    }
+                    Err(e) => error!("Failed to load takeover image: {}", e),
+                }
+                for output in &mut outputs {
+                    match render(&output.fb, &output.orientation) {
+                        Ok(frame) => {
+                            if let Err(e) = output.fb.display_image(&frame) {
+                                error!("Failed to display takeover on {}: {}", output.device, e);
+                            }
+                        }
+                        Err(e) => error!("Failed to load takeover image for {}: {}", output.device, e),
+                    }
+                }
+                shown_takeover = Some(screen);
+                shown_test_pattern = None;
+                force_redraw = false;
+            }
+
+            match rx.try_recv() {
+                Ok(SlideshowEvent::Shutdown) | Err(mpsc::TryRecvError::Disconnected) => running = false,
+                _ => {}
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            continue;
+        } else if shown_takeover.take().is_some() {
+            controller.clear_expired_takeover().await;
+            for output in &mut outputs {
+                if let Some(ref mut playlist) = output.playlist {
+                    playlist.shown = false;
+                }
+            }
+            // The interrupted slide gets a full display period again
+            force_redraw = true;
+            has_displayed_placeholder = false;
+            last_displayed_image_path = None;
+            last_image_change = Instant::now();
+        }
         
-        let _ = fb.display_image(&placeholder);
-        has_displayed_placeholder = true;
-        println!("Displayed 'No images available' placeholder on startup");
-    }
-    
-    while running {
-        // Check if orientation has changed (due to MQTT config update)
-        let orientation_str = controller.get_orientation().await;
-        let new_orientation = Orientation::from(orientation_str.as_str());
-        if std::mem::discriminant(&current_orientation) != std::mem::discriminant(&new_orientation) {
-            println!("🔄 DISPLAY ORIENTATION CHANGE: {:?} -> {:?}, forcing immediate redraw", current_orientation, new_orientation);
-            current_orientation = new_orientation;
+        // Test pattern: replaces the slideshow on every display, playlists included, until turned off
+        let test_pattern = controller.get_test_pattern().await;
+        if let Some(pattern) = test_pattern {
+            if shown_test_pattern != Some(pattern) || force_redraw {
+                info!("🧪 Drawing {} test pattern at {}x{}", pattern.name(), fb.width, fb.height);
+                if let Err(e) = fb.display_image(&render_test_pattern(pattern, fb.width, fb.height, &render_orientation)) {
+                    error!("Failed to display test pattern: {}", e);
+                }
+                for output in &mut outputs {
+                    let frame = render_test_pattern(pattern, output.fb.width, output.fb.height, &output.orientation);
+                    if let Err(e) = output.fb.display_image(&frame) {
+                        error!("Failed to display test pattern on {}: {}", output.device, e);
+                    }
+                }
+                shown_test_pattern = Some(pattern);
+                force_redraw = false;
+            }
             
-            // Framebuffer dimensions remain constant at 1920x1080
-            // Orientation is handled purely through image processing
-            println!("🔄 ORIENTATION UPDATED: Framebuffer remains at {}x{}, orientation handled via image processing", DEFAULT_LANDSCAPE_WIDTH, DEFAULT_LANDSCAPE_HEIGHT);
+            match rx.try_recv() {
+                Ok(SlideshowEvent::Shutdown) | Err(mpsc::TryRecvError::Disconnected) => running = false,
+                _ => {}
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            continue;
+        } else if shown_test_pattern.take().is_some() {
+            info!("🧪 Test pattern cleared, restoring the slideshow");
+            for output in &mut outputs {
+                if let Some(ref mut playlist) = output.playlist {
+                    playlist.shown = false;
+                }
+            }
+            force_redraw = true;
+            has_displayed_placeholder = false;
+            last_displayed_image_path = None;
+        }
+        
+        // Curtain: a solid color or standby image on every display while rendering pauses, on a
+        // schedule or on command. The panel stays on, so lifting it is immediate.
+        if let Some(curtain) = controller.get_curtain().await {
+            if shown_curtain.as_ref() != Some(&curtain) || force_redraw {
+                if shown_curtain.is_none() {
+                    info!("🎭 Curtain drawn");
+                }
+                image_manager.finish_prefetch();
+                let color = signage_protocol::couchdb::parse_hex_color(&curtain.color)
+                    .map_or(Rgba([0, 0, 0, 255]), |[r, g, b]| Rgba([r, g, b, 255]));
+                let render = |fb: &Framebuffer, orientation: &Orientation| {
+                    let image = curtain.image.as_ref().and_then(|path| match image_manager.load(path, fb, orientation) {
+                        Ok(frame) => Some(frame),
+                        Err(e) => {
+                            error!("Failed to load curtain image, showing its color: {}", e);
+                            None
+                        }
+                    });
+                    image.unwrap_or_else(|| RgbaImage::from_pixel(fb.width, fb.height, color))
+                };
+                if let Err(e) = fb.display_image(&render(&fb, &render_orientation)) {
+                    error!("Failed to display curtain: {}", e);
+                }
+                for output in &mut outputs {
+                    let frame = render(&output.fb, &output.orientation);
+                    if let Err(e) = output.fb.display_image(&frame) {
+                        error!("Failed to display curtain on {}: {}", output.device, e);
+                    }
+                }
+                shown_curtain = Some(curtain);
+                force_redraw = false;
+            }
             
-            // Force a redraw by resetting the last image change time
-            last_image_change = Instant::now() - Duration::from_secs(10);
-            has_displayed_placeholder = false; // Force placeholder redraw if needed
-            last_displayed_image_path = None; // Force image reload with new orientation
+            match rx.try_recv() {
+                Ok(SlideshowEvent::Shutdown) | Err(mpsc::TryRecvError::Disconnected) => running = false,
+                _ => {}
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            continue;
+        } else if shown_curtain.take().is_some() {
+            info!("🎭 Curtain lifted, resuming the slideshow");
+            for output in &mut outputs {
+                if let Some(ref mut playlist) = output.playlist {
+                    playlist.shown = false;
+                }
+            }
+            // The slide under the curtain gets a full display period again
+            force_redraw = true;
+            has_displayed_placeholder = false;
+            last_displayed_image_path = None;
+            last_image_change = Instant::now();
         }
         
         // Check if image count has changed (due to CouchDB sync, etc)
         let current_image_count = controller.get_image_count().await;
         if current_image_count != last_image_count {
-            println!("Image count changed from {} to {}, resetting placeholder flag", last_image_count, current_image_count);
+            info!("Image count changed from {} to {}, resetting placeholder flag", last_image_count, current_image_count);
             has_displayed_placeholder = false;
             last_image_count = current_image_count;
         }
         
-        // Check if we should advance automatically based on controller state
-        let should_advance = controller.should_advance_automatically(last_image_change).await;
+        // Play a requested transition preview from the current to the next image, then restore the current image
+        if let Some(effect) = controller.take_transition_preview().await {
+            let image_count = controller.get_image_count().await;
+            if image_count > 1 {
+                let current_index = *controller.current_index.read().await;
+                let next_index = controller.get_next_index().await.unwrap_or(0);
+                
+                let controller_images = controller.get_image_list().await;
+                image_manager.images = controller_images.iter().map(|img| PathBuf::from(&img.path)).collect();
+                
+                let (transition_type, transition_duration) = select_transition(&controller, &effect).await;
+                info!("Previewing {} transition", transition_type.name());
+                image_manager.finish_prefetch();
+                match image_manager.play_transition(
+                    current_index,
+                    next_index,
+                    RenderTarget { fb: &mut fb, orientation: &render_orientation, mirrors: &mut outputs },
+                    transition_duration,
+                    transition_type,
+                ) {
+                    Ok(stats) => controller.record_transition(stats).await,
+                    Err(e) => info!("Failed to play transition preview: {}", e),
+                }
+                
+                force_redraw = true;
+                last_displayed_image_path = None;
+            } else {
+                info!("Transition preview needs at least two images, have {}", image_count);
+            }
+        }
+        
+        // A held slide stays up until resumed, then gets a full display period from there
+        let held = controller.is_held().await;
+        if fb.set_held_marker(held && image_manager.debug_overlay) {
+            force_redraw = true;
+            last_displayed_image_path = None;
+        }
+        if std::mem::replace(&mut was_held, held) && !held {
+            last_image_change = Instant::now();
+        }
+        
+        // Check if we should advance automatically based on controller state. A video slide
+        // stays up for its own length and moves on when it has played to the end; held, it
+        // plays again.
+        let should_advance = if std::mem::take(&mut video_finished) {
+            controller.is_playing().await && !held
+        } else if video_playback.is_some() {
+            false
+        } else {
+            controller.should_advance_automatically(last_image_change).await
+        };
         let _elapsed = last_image_change.elapsed();
         let _is_playing = controller.is_playing().await;
         
+        // Where the transition starts from; in shuffle mode that isn't the image before the next
+        let shown_index = *controller.current_index.read().await;
         if should_advance {
             controller.advance_to_next_image().await;
             last_image_change = Instant::now();
@@ -1445,11 +3859,7 @@ async fn run_slideshow_loop(args: Args, controller: SlideshowController) -> IoRe
         if should_advance && controller.get_image_count().await > 0 {
             // Get current and previous image indices for transition
             let current_index = *controller.current_index.read().await;
-            let previous_index = if current_index == 0 {
-                controller.get_image_count().await - 1
-            } else {
-                current_index - 1
-            };
+            let previous_index = shown_index.min(controller.get_image_count().await - 1);
             
             // Update image manager with controller's images
             let controller_images = controller.get_image_list().await;
@@ -1458,70 +3868,280 @@ async fn run_slideshow_loop(args: Args, controller: SlideshowController) -> IoRe
             
             // Get transition effect from controller
             let transition_effect_str = controller.get_transition_effect().await;
-            let transition_type = TransitionType::from_string(&transition_effect_str)
-                .unwrap_or(TransitionType::get_random());
+            let (transition_type, transition_duration) = select_transition(&controller, &transition_effect_str).await;
             
             // Play transition if we have enough images
             if image_manager.images.len() > 1 {
-                if let Err(e) = image_manager.play_transition(
+                image_manager.finish_prefetch();
+                match image_manager.play_transition(
                     previous_index, 
                     current_index, 
-                    &mut fb, 
-                    controller.get_transition_duration().await,
+                    RenderTarget { fb: &mut fb, orientation: &render_orientation, mirrors: &mut outputs },
+                    transition_duration,
                     transition_type,
-                    &current_orientation
                 ) {
-                    println!("Failed to play transition: {}", e);
+                    Ok(stats) => {
+                        controller.record_transition(stats).await;
+                        last_displayed_image_path = controller.get_current_image_path().await;
+                    }
+                    Err(e) => {
+                        info!("Failed to play transition: {}", e);
+                        // Loaded again below, where a broken file is quarantined
+                        last_displayed_image_path = None;
+                    }
                 }
-                last_displayed_image_path = controller.get_current_image_path().await;
             }
         } else if let Some(current_image_path) = controller.get_current_image_path().await {
-            if controller.is_playing().await {
+            // A forced redraw (e.g. orientation change) applies even while paused
+            if controller.is_playing().await || force_redraw {
                 // Only load and display if image has changed (for initial display)
                 let needs_reload = match &last_displayed_image_path {
                     Some(last_path) => last_path != &current_image_path,
                     None => true,
                 };
                 
-                if needs_reload {
+                // Video slides are played further down
+                if needs_reload && !video::is_video(&current_image_path) {
+                    image_manager.finish_prefetch();
+                    // A redraw of a moving still picks its motion up where it was
+                    let progress = if motion_slide.as_ref() == Some(&current_image_path) { image_manager.motion_progress } else { 0.0 };
+                    motion_base = None;
                     // Load and display the current image
-                    match load_and_scale_image_with_orientation(&current_image_path, DEFAULT_LANDSCAPE_WIDTH, DEFAULT_LANDSCAPE_HEIGHT, &current_orientation) {
+                    match image_manager.load(&current_image_path, &fb, &render_orientation) {
                         Ok(image) => {
+                            let image = image_manager.with_motion(&current_image_path, image, &fb, progress);
                             if let Err(e) = fb.display_image(&image) {
-                                eprintln!("Failed to display image: {}", e);
+                                error!("Failed to display image: {}", e);
                             } else {
                                 last_displayed_image_path = Some(current_image_path.clone());
                             }
+                            show_on_mirrors(&mut outputs, |fb, orientation| {
+                                image_manager.load(&current_image_path, fb, orientation)
+                            });
+                            force_redraw = false;
                         }
                         Err(e) => {
-                            eprintln!("Failed to load image {}: {}", current_image_path.display(), e);
+                            error!("Failed to load image {}: {}", current_image_path.display(), e);
+                            if is_undecodable(&e) {
+                                controller.quarantine_image(&current_image_path, &e.to_string()).await;
+                            }
                         }
                     }
                 }
             }
         } else if controller.get_image_count().await == 0 {
             // No images available, show a placeholder with TV ID and IP
-            // Always show placeholder when transitioning from images to no images
-            if !has_displayed_placeholder {
+            // Always show placeholder when transitioning from images to no images, and again
+            // when the storage warning on it comes or goes
+            if !has_displayed_placeholder || placeholder_disk_low != health::disk_low() {
+                placeholder_disk_low = health::disk_low();
                 let tv_id = controller.get_tv_id().await;
                 let local_ip = get_local_ip().unwrap_or_else(|| "Unknown IP".to_string());
-                let placeholder = create_info_placeholder_with_orientation(&tv_id, &local_ip, DEFAULT_LANDSCAPE_WIDTH, DEFAULT_LANDSCAPE_HEIGHT, &current_orientation);
+                let placeholder = create_info_placeholder_with_orientation(&tv_id, &local_ip, &fb, &render_orientation, &current_text_profile);
                 
                 let _ = fb.display_image(&placeholder);
+                show_on_mirrors(&mut outputs, |fb, orientation| {
+                    Ok(create_info_placeholder_with_orientation(&tv_id, &local_ip, fb, orientation, &current_text_profile))
+                });
                 has_displayed_placeholder = true;
-                println!("Displayed 'No images available' placeholder");
+                info!("Displayed 'No images available' placeholder");
             }
         } else {
             // Reset placeholder flag when images become available
             // This ensures placeholder will be shown again if images are later removed
             if has_displayed_placeholder {
                 has_displayed_placeholder = false;
-                println!("Images now available, clearing placeholder flag");
+                info!("Images now available, clearing placeholder flag");
+            }
+        }
+        
+        // Video slides: start decoding once one is the current slide, then show each frame as
+        // it comes due. Paused videos hold their frame and carry on from it.
+        let current_video = controller.get_current_image_path().await.filter(|path| video::is_video(path));
+        if force_redraw || video_playback.as_ref().map(|playback| playback.path()) != current_video.as_deref() {
+            video_playback = None;
+        }
+        if failed_video != current_video {
+            failed_video = None;
+        }
+        if let Some(path) = current_video.filter(|path| failed_video.as_ref() != Some(path)) {
+            if video_playback.is_none() {
+                let (_, _, safe_width, safe_height) = fb.safe_rect();
+                match video::Playback::start(&path, &render_orientation, safe_width, safe_height) {
+                    Ok(playback) => {
+                        video_playback = Some(playback);
+                        video_screen = None;
+                        last_displayed_image_path = Some(path.clone());
+                        // Mirrors show the first frame rather than decoding the video again
+                        show_on_mirrors(&mut outputs, |fb, orientation| {
+                            image_manager.load(&path, fb, orientation)
+                        });
+                        force_redraw = false;
+                    }
+                    Err(e) => {
+                        error!("Failed to play video {}: {}", path.display(), e);
+                        failed_video = Some(path.clone());
+                    }
+                }
+            }
+        }
+        if let Some(playback) = video_playback.as_mut() {
+            // Paused before anything is on screen, the first frame is still shown
+            let paused = !controller.is_playing().await && playback.frames_shown() > 0;
+            playback.set_paused(paused);
+            let due = playback.next_due();
+            if !paused && Instant::now() < due {
+                tokio::time::sleep(due - Instant::now()).await;
+            }
+            if !paused {
+                match playback.next_frame() {
+                    Ok(Some(frame)) => {
+                        let layout = image_manager.layout_for(playback.path(), &fb);
+                        let screen = video_screen.get_or_insert_with(|| center_on_background(&frame, &frame, &layout, &render_orientation));
+                        let (safe_x, safe_y, safe_width, safe_height) = fb.safe_rect();
+                        let x = safe_x + safe_width.saturating_sub(frame.width()) / 2;
+                        let y = safe_y + safe_height.saturating_sub(frame.height()) / 2;
+                        image::imageops::replace(screen, &frame, x as i64, y as i64);
+                        if let Some(ref qr) = layout.qr {
+                            qr.draw(screen, layout.safe_rect(), &render_orientation);
+                        }
+                        if let Err(e) = fb.display_image(screen) {
+                            error!("Failed to display video frame: {}", e);
+                        }
+                    }
+                    Ok(None) => {
+                        info!("🎬 Finished {} after {} frames", playback.path().display(), playback.frames_shown());
+                        video_playback = None;
+                        video_finished = true;
+                        last_image_change = Instant::now();
+                    }
+                    Err(e) => {
+                        warn!("Video {} stopped: {}", playback.path().display(), e);
+                        failed_video = Some(playback.path().to_path_buf());
+                        video_playback = None;
+                        last_image_change = Instant::now();
+                    }
+                }
+            }
+        }
+        // Countdown slides: once the card is up, it is drawn again each second with the time
+        // left, paused or not. E-paper panels, which flash on every update, keep the card.
+        let countdown = match controller.get_current_image_path().await {
+            Some(path) if last_displayed_image_path.as_ref() == Some(&path) && !fb.is_eink() => {
+                controller.get_countdown_slide(&path).await
+                    .and_then(|slide| text_slide::with_countdown(&slide, chrono::Utc::now()))
+            }
+            _ => None,
+        };
+        if let Some(slide) = countdown.as_ref().filter(|&slide| shown_countdown.as_ref() != Some(slide)) {
+            let render = |fb: &Framebuffer, orientation: &Orientation| {
+                fb.render_screen(orientation, |width, height| text_slide::render(slide, width, height, &current_text_profile))
+            };
+            if let Err(e) = fb.display_image(&render(&fb, &render_orientation)) {
+                error!("Failed to display countdown: {}", e);
+            }
+            show_on_mirrors(&mut outputs, |fb, orientation| Ok(render(fb, orientation)));
+        }
+        shown_countdown = countdown;
+        
+        // Ken Burns: draw the still on screen a little further along its zoom or pan. The motion
+        // follows the time the slide has been up while playing, so pausing holds it. Countdowns
+        // hold still.
+        let moving_still = controller.get_current_image_path().await.filter(|path| {
+            last_displayed_image_path.as_ref() == Some(path) && shown_countdown.is_none() && image_manager.motion_for(path, &fb).is_some()
+        });
+        if moving_still != motion_slide {
+            motion_slide = moving_still;
+            motion_base = None;
+            motion_elapsed = Duration::ZERO;
+            motion_clock = Instant::now();
+            image_manager.motion_progress = 0.0;
+        }
+        let motion_running = motion_slide.is_some() && controller.is_playing().await;
+        if motion_running {
+            motion_elapsed += motion_clock.elapsed();
+        }
+        motion_clock = Instant::now();
+        if let (Some(path), true) = (motion_slide.as_ref(), motion_running) {
+            if Instant::now() < next_motion_frame {
+                tokio::time::sleep(next_motion_frame - Instant::now()).await;
+            }
+            next_motion_frame = Instant::now() + ken_burns::FRAME_INTERVAL;
+            if motion_base.is_none() {
+                motion_base = image_manager.load(path, &fb, &render_orientation)
+                    .map_err(|e| error!("Failed to load {} for Ken Burns: {}", path.display(), e))
+                    .ok();
+            }
+            if let (Some(base), Some(motion)) = (motion_base.as_ref(), image_manager.motion_for(path, &fb)) {
+                let display_duration = controller.get_slide_duration().await;
+                image_manager.motion_progress = (motion_elapsed.as_secs_f32() / display_duration.as_secs_f32().max(0.001)).min(1.0);
+                let frame = motion.render(base, fb.safe_rect(), image_manager.motion_progress);
+                if let Err(e) = fb.display_image(&frame) {
+                    error!("Failed to display Ken Burns frame: {}", e);
+                }
+            }
+        }
+        
+        // Prepare the next slide while this one is up, so moving on doesn't wait for a decode.
+        // A video's last frame, where the transition away from it starts, is prepared too.
+        if controller.is_playing().await && image_manager.cache.is_enabled() {
+            if let (Some(current), Some(next)) = (controller.get_current_image_path().await, controller.get_next_image_path().await) {
+                let layout = image_manager.layout_for(&next, &fb);
+                let key = cache_key(&next, &layout, &render_orientation, false);
+                if current != next && prefetched.as_ref() != Some(&key) {
+                    let mut jobs = vec![(next.clone(), layout, render_orientation.clone(), false)];
+                    if video::is_video(&current) {
+                        jobs.push((current.clone(), image_manager.layout_for(&current, &fb), render_orientation.clone(), true));
+                    }
+                    for output in outputs.iter().filter(|output| output.playlist.is_none()) {
+                        jobs.push((next.clone(), image_manager.layout_for(&next, &output.fb), output.orientation.clone(), false));
+                        if video::is_video(&current) {
+                            jobs.push((current.clone(), image_manager.layout_for(&current, &output.fb), output.orientation.clone(), true));
+                        }
+                    }
+                    if image_manager.prefetch(jobs) {
+                        prefetched = Some(key);
+                    }
+                }
+            }
+        }
+        
+        // While a video plays or a still moves the loop only waits for its next frame
+        let animating = video_playback.as_ref().is_some_and(|playback| !playback.is_paused()) || motion_running;
+        
+        // Move the ticker along; over a still the loop waits for the ticker's next frame instead
+        let ticker_running = match fb.ticker_due() {
+            Some(due) => {
+                if !animating && Instant::now() < due {
+                    tokio::time::sleep(due - Instant::now()).await;
+                }
+                if let Err(e) = fb.draw_ticker() {
+                    error!("Failed to draw ticker: {}", e);
+                }
+                true
+            }
+            None => false,
+        };
+        
+        // Outputs with their own playlist follow the controller's timing and play state
+        let mut playlist_transitions = false;
+        for output in &mut outputs {
+            playlist_transitions |= advance_playlist(output, &controller).await;
+        }
+        
+        // Keep-alive for displays that blank on a static source
+        if let Err(e) = fb.refresh_if_due() {
+            error!("Failed to refresh framebuffer: {}", e);
+        }
+        for output in &mut outputs {
+            if let Err(e) = output.fb.refresh_if_due() {
+                error!("Failed to refresh {}: {}", output.device, e);
             }
         }
         
         // Handle filesystem events
-        match rx.recv_timeout(Duration::from_millis(100)) {
+        let idle = if animating || ticker_running || playlist_transitions { Duration::ZERO } else { Duration::from_millis(100) };
+        match rx.recv_timeout(idle) {
             Ok(SlideshowEvent::NewImage(_)) => {
                 // Controller will handle image updates via MQTT from management server
             }
@@ -1535,53 +4155,132 @@ async fn run_slideshow_loop(args: Args, controller: SlideshowController) -> IoRe
         }
         
         // Small delay to prevent busy waiting
-        tokio::time::sleep(Duration::from_millis(50)).await;
+        if !animating && !ticker_running {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
     }
     
-    println!("Slideshow ended");
-    if let Err(e) = display_exit_joke(&mut fb) {
-        println!("Failed to display exit joke: {}", e);
+    if display_asleep {
+        let _ = fb.set_blanked(false);
+    }
+    
+    if restarting {
+        let card = fb.render_screen(&render_orientation, |width, height| create_message_card("RESTARTING...", width, height, &current_text_profile));
+        let _ = fb.display_image(&card);
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        restart_process();
+    }
+    
+    info!("Slideshow ended");
+    if let Err(e) = display_exit_joke(&mut fb, &current_text_profile) {
+        info!("Failed to display exit joke: {}", e);
+    }
+    // The driver keeps its rotation after we exit; put the console back upright
+    if hardware_rotation {
+        let _ = fb.set_hardware_rotation(0);
     }
     
     Ok(())
 }
 
-fn _create_placeholder_image(message: &str, width: u32, height: u32) -> RgbaImage {
+// Replace this process with a fresh copy of the binary, keeping the original arguments.
+// The PID stays the same so systemd keeps tracking the service; if exec fails we exit
+// and leave it to Restart=always. After --user has dropped root the copy would start
+// unprivileged and fail to open the backlight, so then we exit straight away and let
+// systemd start the endpoint as root again.
+fn restart_process() -> ! {
+    use std::os::unix::process::CommandExt;
+    
+    if privileges::dropped() {
+        info!("🔄 Restarting: exiting for the service manager to start the endpoint as root again");
+        std::process::exit(1);
+    }
+    
+    // Run the path the endpoint was started as, which an installed update points at the new release
+    let exe = match updater::invoked_path() {
+        Some(exe) => exe,
+        None => {
+            warn!("Cannot locate own executable, exiting for the service manager to restart");
+            std::process::exit(1);
+        }
+    };
+    
+    info!("🔄 Restarting {}", exe.display());
+    let err = std::process::Command::new(&exe)
+        .args(std::env::args_os().skip(1))
+        .exec();
+    
+    error!("Failed to exec {}: {}, exiting for the service manager to restart", exe.display(), err);
+    std::process::exit(1);
+}
+
+// Resolve an effect name to the transition and duration to play, applying content safety limits
+async fn select_transition(controller: &SlideshowController, effect: &str) -> (TransitionType, Duration) {
+    let content_safety = controller.is_content_safety_enabled().await;
+    // "random" draws from the effects allowed, rather than turning the unsafe ones into fades
+    let mut transition_type = if effect.eq_ignore_ascii_case("random") && content_safety {
+        TransitionType::get_random_flash_safe()
+    } else {
+        // An unknown name was reported when the config naming it arrived
+        TransitionType::from_string(effect).unwrap_or(TransitionType::Fade)
+    };
+    let mut transition_duration = transition_type.duration(controller.get_transition_duration().await, &controller.get_transition_durations().await);
+    
+    if content_safety {
+        transition_type = transition_type.flash_safe();
+        transition_duration = content_safety::safe_transition_duration(transition_duration);
+    }
+    
+    (transition_type, transition_duration)
+}
+
+fn create_message_card(message: &str, width: u32, height: u32, text_profile: &TextProfile) -> RgbaImage {
     let mut image = RgbaImage::new(width, height);
+    let background = text_profile.background(Rgba([0, 0, 0, 255]));
     
     // Fill with black background
     for pixel in image.pixels_mut() {
-        *pixel = Rgba([0, 0, 0, 255]);
+        *pixel = background;
     }
     
     // Add text
-    let char_size = 8;
+    let char_size = text_profile.char_size(text_char_size(8, width, height));
     let text_width = message.len() as u32 * (7 * char_size + char_size);
-    let start_x = (width - text_width) / 2;
-    let start_y = (height - 5 * char_size) / 2;
+    let start_x = width.saturating_sub(text_width) / 2;
+    let start_y = height.saturating_sub(5 * char_size) / 2;
     
-    draw_text(&mut image, message, start_x, start_y, char_size, Rgba([255, 255, 255, 255]));
+    draw_text(&mut image, message, start_x, start_y, char_size, text_profile.foreground(Rgba([255, 255, 255, 255]), background));
     
     image
 }
 
-fn create_info_placeholder_with_orientation(tv_id: &str, ip_address: &str, width: u32, height: u32, orientation: &Orientation) -> RgbaImage {
-    // Create placeholder image
-    let placeholder = create_info_placeholder(tv_id, ip_address, width, height);
-    
-    // Apply rotation based on orientation
-    orientation.rotate_image(&placeholder)
+fn create_info_placeholder_with_orientation(tv_id: &str, ip_address: &str, fb: &Framebuffer, orientation: &Orientation, text_profile: &TextProfile) -> RgbaImage {
+    fb.render_screen(orientation, |width, height| create_info_placeholder(tv_id, ip_address, width, height, text_profile))
+}
+
+// Test patterns are drawn upright for the viewer, so a portrait screen gets a portrait
+// pattern that is then rotated onto the landscape framebuffer like any image. They ignore
+// the overscan margins, being what the margins are measured with.
+fn render_test_pattern(pattern: TestPattern, width: u32, height: u32, orientation: &Orientation) -> RgbaImage {
+    let (width, height) = orientation.upright_size(width, height);
+    orientation.rotate_image(&pattern.render(width, height))
 }
 
-fn create_info_placeholder(tv_id: &str, ip_address: &str, width: u32, height: u32) -> RgbaImage {
+fn create_info_placeholder(tv_id: &str, ip_address: &str, width: u32, height: u32, text_profile: &TextProfile) -> RgbaImage {
     let mut image = RgbaImage::new(width, height);
+    let background = text_profile.background(Rgba([25, 25, 50, 255]));
     
     // Fill with dark blue background
     for pixel in image.pixels_mut() {
-        *pixel = Rgba([25, 25, 50, 255]);
+        *pixel = background;
     }
     
-    let char_size = 8;
+    let title_color = text_profile.foreground(Rgba([255, 255, 255, 255]), background);
+    let tv_color = text_profile.foreground(Rgba([255, 255, 0, 255]), background);
+    let ip_color = text_profile.foreground(Rgba([0, 255, 255, 255]), background);
+    let instruction_color = text_profile.foreground(Rgba([200, 200, 200, 255]), background);
+    
+    let char_size = text_profile.char_size(text_char_size(8, width, height));
     let line_height = char_size * 7; // Slightly tighter spacing
     let center_x = width / 2;
     let center_y = height / 2;
@@ -1590,19 +4289,19 @@ fn create_info_placeholder(tv_id: &str, ip_address: &str, width: u32, height: u3
     let title = "NO IMAGES AVAILABLE";
     let title_width = title.len() as u32 * (7 * char_size + char_size);
     let max_chars_for_title_width = title.len();
-    draw_text(&mut image, title, center_x - title_width / 2, center_y - line_height * 3, char_size, Rgba([255, 255, 255, 255]));
+    draw_text(&mut image, title, center_x.saturating_sub(title_width / 2), center_y.saturating_sub(line_height * 3), char_size, title_color);
     
     // TV ID - wrap if longer than title
     let tv_line = format!("TV ID: {}", tv_id);
     if tv_line.len() <= max_chars_for_title_width {
         let tv_width = tv_line.len() as u32 * (7 * char_size + char_size);
-        draw_text(&mut image, &tv_line, center_x - tv_width / 2, center_y - line_height, char_size, Rgba([255, 255, 0, 255]));
+        draw_text(&mut image, &tv_line, center_x.saturating_sub(tv_width / 2), center_y.saturating_sub(line_height), char_size, tv_color);
     } else {
         let tv_lines = wrap_text(&tv_line, max_chars_for_title_width);
         for (i, line) in tv_lines.iter().enumerate() {
             let line_width = line.len() as u32 * (7 * char_size + char_size);
-            let y_pos = center_y - line_height + (i as u32 * (5 * char_size + char_size));
-            draw_text(&mut image, line, center_x - line_width / 2, y_pos, char_size, Rgba([255, 255, 0, 255]));
+            let y_pos = center_y.saturating_sub(line_height) + (i as u32 * (5 * char_size + char_size));
+            draw_text(&mut image, line, center_x.saturating_sub(line_width / 2), y_pos, char_size, tv_color);
         }
     }
     
@@ -1610,20 +4309,25 @@ fn create_info_placeholder(tv_id: &str, ip_address: &str, width: u32, height: u3
     let ip_line = format!("IP: {}", ip_address);
     if ip_line.len() <= max_chars_for_title_width {
         let ip_width = ip_line.len() as u32 * (7 * char_size + char_size);
-        draw_text(&mut image, &ip_line, center_x - ip_width / 2, center_y, char_size, Rgba([0, 255, 255, 255]));
+        draw_text(&mut image, &ip_line, center_x.saturating_sub(ip_width / 2), center_y, char_size, ip_color);
     } else {
         let ip_lines = wrap_text(&ip_line, max_chars_for_title_width);
         for (i, line) in ip_lines.iter().enumerate() {
             let line_width = line.len() as u32 * (7 * char_size + char_size);
             let y_pos = center_y + (i as u32 * (5 * char_size + char_size));
-            draw_text(&mut image, line, center_x - line_width / 2, y_pos, char_size, Rgba([0, 255, 255, 255]));
+            draw_text(&mut image, line, center_x.saturating_sub(line_width / 2), y_pos, char_size, ip_color);
         }
     }
     
     // Instructions - wrapped text using title width as constraint
-    let instruction_char_size = char_size - 1;
+    let instruction_char_size = text_profile.char_size(char_size.saturating_sub(1).max(1));
     let max_chars_for_instruction = (title_width / (7 * instruction_char_size + instruction_char_size)) as usize;
-    let instruction = "Contact staff to assign images to this display";
+    // Downloads refused for want of space would otherwise look like nothing being assigned
+    let (instruction, instruction_color) = if health::disk_low() {
+        ("Storage full: free space on the SD card so images can download", text_profile.foreground(Rgba([255, 140, 90, 255]), background))
+    } else {
+        ("Contact staff to assign images to this display", instruction_color)
+    };
     let instruction_lines = wrap_text(instruction, max_chars_for_instruction);
     
     let _total_instruction_height = instruction_lines.len() as u32 * (5 * instruction_char_size + instruction_char_size);
@@ -1631,9 +4335,9 @@ fn create_info_placeholder(tv_id: &str, ip_address: &str, width: u32, height: u3
     
     for (line_idx, line) in instruction_lines.iter().enumerate() {
         let line_width = line.len() as u32 * (7 * instruction_char_size + instruction_char_size);
-        let line_x = center_x - line_width / 2;
+        let line_x = center_x.saturating_sub(line_width / 2);
         let line_y = instruction_start_y + (line_idx as u32 * (5 * instruction_char_size + instruction_char_size));
-        draw_text(&mut image, line, line_x, line_y, instruction_char_size, Rgba([200, 200, 200, 255]));
+        draw_text(&mut image, line, line_x, line_y, instruction_char_size, instruction_color);
     }
     
     image
@@ -1641,23 +4345,189 @@ fn create_info_placeholder(tv_id: &str, ip_address: &str, width: u32, height: u3
 
 // Removed - no longer needed with unified rotation approach
 
-fn load_and_scale_image_with_orientation(path: &PathBuf, width: u32, height: u32, orientation: &Orientation) -> Result<RgbaImage, ImageError> {
-    let img = image::open(path).map_err(|e| {
-        eprintln!("Failed to load image {}: {}", path.display(), e);
+// Cut a pair of opposite margins back in proportion when together they exceed `limit`
+fn limit_margins(first: u32, second: u32, limit: u32) -> (u32, u32) {
+    let total = first as u64 + second as u64;
+    if total <= limit as u64 {
+        return (first, second);
+    }
+    ((first as u64 * limit as u64 / total) as u32, (second as u64 * limit as u64 / total) as u32)
+}
+
+// Everything besides the file, mode and orientation that changes how a slide is drawn: its
+// layout and the startup options that affect decoding. Frames are cached under it, so one
+// drawn under other settings, in memory or on disk from before a restart, isn't reused.
+struct FrameStyle<'a> {
+    scaling: Scaling,
+    letterbox: &'a Letterbox,
+    safe_area: SafeArea,
+    fit: FitMode,
+    smart_crop: bool,
+    qr: Option<&'a qr_code::QrOverlay>,
+    // --skip-color-profiles
+    color_profiles: bool,
+}
+
+impl<'a> FrameStyle<'a> {
+    fn new(layout: &'a Layout) -> Self {
+        // Taken apart field by field, so a new layout setting can't be left out of the key
+        let Layout { width: _, height: _, scaling, letterbox, safe_area, fit, smart_crop, qr } = layout;
+        FrameStyle {
+            scaling: *scaling,
+            letterbox,
+            safe_area: *safe_area,
+            fit: *fit,
+            smart_crop: *smart_crop,
+            qr: qr.as_ref(),
+            color_profiles: icc::is_enabled(),
+        }
+    }
+
+    fn key(&self) -> String {
+        format!(
+            "scaling={:?} letterbox={:?} safe_area={:?} fit={:?} smart_crop={} qr={:?} color_profiles={}",
+            self.scaling, self.letterbox, self.safe_area, self.fit, self.smart_crop, self.qr, self.color_profiles
+        )
+    }
+}
+
+fn cache_key(path: &Path, layout: &Layout, orientation: &Orientation, outgoing: bool) -> CacheKey {
+    let style = FrameStyle::new(layout).key();
+    CacheKey::new(path, (layout.width, layout.height), orientation.name(), outgoing && video::is_video(path), style)
+}
+
+// A slide for a display from the cache, loading and keeping it on a miss
+fn load_cached(cache: &ImageCache, path: &Path, layout: &Layout, orientation: &Orientation, outgoing: bool) -> Result<RgbaImage, ImageError> {
+    cache.get_or_load(cache_key(path, layout, orientation, outgoing), || {
+        if outgoing {
+            load_and_scale_outgoing(path, layout, orientation)
+        } else {
+            load_and_scale_image_with_orientation(path, layout, orientation)
+        }
+    })
+}
+
+// The file itself is broken, as opposed to missing, over a size limit or slow to decode
+fn is_undecodable(e: &ImageError) -> bool {
+    match e {
+        ImageError::Decoding(_) | ImageError::Unsupported(_) => true,
+        ImageError::IoError(e) => matches!(e.kind(), std::io::ErrorKind::UnexpectedEof | std::io::ErrorKind::InvalidData),
+        _ => false,
+    }
+}
+
+// Video slides load as their first frame
+fn load_and_scale_image_with_orientation(path: &Path, layout: &Layout, orientation: &Orientation) -> Result<RgbaImage, ImageError> {
+    let started = Instant::now();
+    let img = if video::is_video(path) { video::first_frame(path) } else { downscale::open(path, layout.decode_target()) };
+    let img = img.map_err(|e| {
+        error!("Failed to load image {}: {}", path.display(), e);
+        e
+    })?;
+    perf::record_decode(started.elapsed());
+    Ok(scale_loaded_image(path, img, layout, orientation))
+}
+
+// Like load_and_scale_image_with_orientation, but a video slide loads as its last frame: the
+// picture on screen when a transition away from it starts
+fn load_and_scale_outgoing(path: &Path, layout: &Layout, orientation: &Orientation) -> Result<RgbaImage, ImageError> {
+    if !video::is_video(path) {
+        return load_and_scale_image_with_orientation(path, layout, orientation);
+    }
+    let started = Instant::now();
+    let img = video::last_frame(path).map_err(|e| {
+        error!("Failed to load last frame of {}: {}", path.display(), e);
         e
     })?;
-    let original_img = img.to_rgba8();
+    perf::record_decode(started.elapsed());
+    Ok(scale_loaded_image(path, img, layout, orientation))
+}
+
+fn scale_loaded_image(path: &Path, img: image::DynamicImage, layout: &Layout, orientation: &Orientation) -> RgbaImage {
+    let started = Instant::now();
+    let frame = fit_to_layout(path, img, layout, orientation);
+    perf::record_scale(started.elapsed());
+    frame
+}
+
+fn fit_to_layout(path: &Path, img: image::DynamicImage, layout: &Layout, orientation: &Orientation) -> RgbaImage {
+    // Turn camera photos upright and into sRGB first, so the display orientation and fit work
+    // on the picture as it was meant to be seen
+    let original_img = apply_exif_orientation(path, icc::to_srgb(path, img.to_rgba8()));
     
     // Apply rotation based on orientation
     let rotated_img = orientation.rotate_image(&original_img);
     
-    // Scale and center the rotated image for the framebuffer dimensions
-    Ok(scale_and_center_image(&rotated_img, width, height))
+    // Scale the rotated image into the safe area and center it there on the letterbox fill,
+    // which covers the whole screen
+    let (_, _, safe_width, safe_height) = layout.safe_rect();
+    let scaled_img = match (layout.fit, layout.scaling) {
+        (FitMode::Contain, Scaling::Fit) => scale_image_to_fit(&rotated_img, safe_width, safe_height),
+        (FitMode::Contain, Scaling::Integer) => integer_scale_image(&rotated_img, safe_width, safe_height),
+        (FitMode::Cover, _) => scale_image_to_cover(&rotated_img, safe_width, safe_height, layout.smart_crop),
+        (FitMode::Stretch, _) => image::imageops::resize(&rotated_img, safe_width, safe_height, image::imageops::FilterType::Lanczos3),
+        (FitMode::Native, _) => crop_centered(&rotated_img, safe_width, safe_height),
+    };
+    let mut frame = center_on_background(&scaled_img, &rotated_img, layout, orientation);
+    if let Some(ref qr) = layout.qr {
+        qr.draw(&mut frame, layout.safe_rect(), orientation);
+    }
+    frame
 }
 
 // Removed - no longer needed with unified rotation approach
 
-fn scale_and_center_image(original_img: &RgbaImage, target_width: u32, target_height: u32) -> RgbaImage {
+// Phones store photos as the sensor saw them and record in the EXIF orientation tag how to turn
+// them for viewing. Files without EXIF (or an unreadable tag) are used as stored.
+fn apply_exif_orientation(path: &Path, img: RgbaImage) -> RgbaImage {
+    let orientation = match read_exif_orientation(path) {
+        Some(orientation) => orientation,
+        None => return img,
+    };
+    match orientation {
+        2 => image::imageops::flip_horizontal(&img),
+        3 => image::imageops::rotate180(&img),
+        4 => image::imageops::flip_vertical(&img),
+        // Mirrored across the main diagonal
+        5 => image::imageops::rotate270(&image::imageops::flip_horizontal(&img)),
+        6 => image::imageops::rotate90(&img),
+        // Mirrored across the other diagonal
+        7 => image::imageops::rotate90(&image::imageops::flip_horizontal(&img)),
+        8 => image::imageops::rotate270(&img),
+        _ => img,
+    }
+}
+
+fn read_exif_orientation(path: &Path) -> Option<u32> {
+    let file = std::fs::File::open(path).ok()?;
+    let exif = exif::Reader::new().read_from_container(&mut std::io::BufReader::new(file)).ok()?;
+    let field = exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)?;
+    field.value.get_uint(0)
+}
+
+// Scaled until it covers the target, keeping its aspect ratio, then cropped to it from the
+// center, or around its most salient part with `smart_crop`
+fn scale_image_to_cover(original_img: &RgbaImage, target_width: u32, target_height: u32, smart_crop: bool) -> RgbaImage {
+    let (original_width, original_height) = original_img.dimensions();
+    let scale = (target_width as f32 / original_width as f32).max(target_height as f32 / original_height as f32);
+    let scaled_width = ((original_width as f32 * scale).ceil() as u32).max(target_width);
+    let scaled_height = ((original_height as f32 * scale).ceil() as u32).max(target_height);
+    let scaled = image::imageops::resize(original_img, scaled_width, scaled_height, image::imageops::FilterType::Lanczos3);
+    if !smart_crop {
+        return crop_centered(&scaled, target_width, target_height);
+    }
+    let (x, y) = smart_crop::crop_offset(&scaled, target_width, target_height);
+    image::imageops::crop_imm(&scaled, x, y, target_width, target_height).to_image()
+}
+
+// The middle of the image, at most `max_width` x `max_height`; smaller images are returned whole
+fn crop_centered(img: &RgbaImage, max_width: u32, max_height: u32) -> RgbaImage {
+    let (width, height) = img.dimensions();
+    let (crop_width, crop_height) = (width.min(max_width), height.min(max_height));
+    image::imageops::crop_imm(img, (width - crop_width) / 2, (height - crop_height) / 2, crop_width, crop_height).to_image()
+}
+
+fn scale_image_to_fit(original_img: &RgbaImage, target_width: u32, target_height: u32) -> RgbaImage {
     // Calculate scaling factor to fit within target dimensions while preserving aspect ratio
     let original_width = original_img.width() as f32;
     let original_height = original_img.height() as f32;
@@ -1672,26 +4542,65 @@ fn scale_and_center_image(original_img: &RgbaImage, target_width: u32, target_he
     let scaled_height = (original_height * scale) as u32;
     
     // Scale the image while preserving aspect ratio
-    let scaled_img = image::imageops::resize(
+    image::imageops::resize(
         original_img,
         scaled_width,
         scaled_height,
         image::imageops::FilterType::Lanczos3,
-    );
-    
-    // Create a black background image at target resolution
-    let mut result = RgbaImage::new(target_width, target_height);
-    for pixel in result.pixels_mut() {
-        *pixel = Rgba([0, 0, 0, 255]); // Black background
+    )
+}
+
+// Largest whole multiple that fits for images smaller than the screen, smallest whole
+// divisor that fits for larger ones (a 1920x1080 slide becomes 480x270 on a 480x320 panel)
+fn integer_scale_image(original_img: &RgbaImage, target_width: u32, target_height: u32) -> RgbaImage {
+    let (width, height) = original_img.dimensions();
+    if width <= target_width && height <= target_height {
+        let factor = (target_width / width).min(target_height / height).max(1);
+        image::imageops::resize(original_img, width * factor, height * factor, image::imageops::FilterType::Nearest)
+    } else {
+        let divisor = width.div_ceil(target_width).max(height.div_ceil(target_height));
+        box_downscale(original_img, divisor)
     }
+}
+
+// Average each `divisor` x `divisor` block into one pixel
+fn box_downscale(image: &RgbaImage, divisor: u32) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let area = divisor * divisor;
+    RgbaImage::from_fn((width / divisor).max(1), (height / divisor).max(1), |x, y| {
+        let mut sum = [0u32; 4];
+        for dy in 0..divisor {
+            for dx in 0..divisor {
+                let pixel = image.get_pixel((x * divisor + dx).min(width - 1), (y * divisor + dy).min(height - 1));
+                for (total, channel) in sum.iter_mut().zip(pixel.0) {
+                    *total += channel as u32;
+                }
+            }
+        }
+        Rgba(sum.map(|total| ((total + area / 2) / area) as u8))
+    })
+}
+
+// `original_img` is the unscaled source, for fills derived from the image itself
+fn center_on_background(scaled_img: &RgbaImage, original_img: &RgbaImage, layout: &Layout, orientation: &Orientation) -> RgbaImage {
+    let (scaled_width, scaled_height) = scaled_img.dimensions();
+    let (target_width, target_height) = (layout.width, layout.height);
+    let (safe_x, safe_y, safe_width, safe_height) = layout.safe_rect();
+
+    // Only build the fill when some of it will show
+    let mut result = if scaled_width >= target_width && scaled_height >= target_height {
+        RgbaImage::new(target_width, target_height)
+    } else {
+        layout.letterbox.background(original_img, target_width, target_height, orientation)
+    };
     
-    // Center the scaled image on the black background
-    let x_offset = (target_width - scaled_width) / 2;
-    let y_offset = (target_height - scaled_height) / 2;
+    // Center the scaled image in the safe area
+    let x_offset = safe_x + safe_width.saturating_sub(scaled_width) / 2;
+    let y_offset = safe_y + safe_height.saturating_sub(scaled_height) / 2;
     
     // Copy the scaled image to the center of the result
-    for y in 0..scaled_height {
-        for x in 0..scaled_width {
+    for y in 0..scaled_height.min(target_height - y_offset) {
+        for x in 0..scaled_width.min(target_width - x_offset) {
             let pixel = *scaled_img.get_pixel(x, y);
             result.put_pixel(x + x_offset, y + y_offset, pixel);
         }
@@ -1725,15 +4634,27 @@ fn get_local_ip() -> Option<String> {
 
 fn run_original_slideshow(config: Config) -> IoResult<()> {
 
-    // Always use physical display dimensions (1920x1080) regardless of orientation
-    let mut fb = Framebuffer::new(DEFAULT_LANDSCAPE_WIDTH, DEFAULT_LANDSCAPE_HEIGHT, &config.framebuffer_path)?;
+    // Use the display mode the driver reports (--resolution if it can't be queried) regardless of orientation
+    let mut fb = open_display(&config.display, &config.framebuffer_path, config.fallback_resolution, config.pixel_format)?;
+    fb.set_memory_cap(config.frame_memory_cap);
+    fb.set_scaling(config.scaling);
+    let render_orientation = apply_rotation(&mut fb, config.hardware_rotation, &config.orientation);
+    fb.set_color_adjustment(config.gamma, config.color_temperature, config.brightness);
+    fb.set_refresh_policy(config.framebuffer_refresh, config.skip_redundant_writes);
     let mut image_manager = ImageManager::new();
+    image_manager.text_profile = config.text_profile.clone();
+    image_manager.debug_overlay = config.debug_overlay;
+    if config.gpu_transitions {
+        image_manager.enable_gpu();
+    }
+    privileges::drop_root();
+    image_manager.cache = ImageCache::new(config.image_cache_size).with_disk(&config.image_dir, config.frame_cache_max_bytes);
 
     // Initial image scan
     image_manager.scan_images(&config.image_dir)?;
 
     if image_manager.images.is_empty() {
-        println!("No images (PNG/JPG/JPEG) found in directory: {}", config.image_dir.display());
+        info!("No images (PNG/JPG/JPEG/WebP) found in directory: {}", config.image_dir.display());
         return Ok(());
     }
 
@@ -1745,7 +4666,8 @@ fn run_original_slideshow(config: Config) -> IoResult<()> {
     let _signal_handle = setup_signal_handler(tx);
 
     // No need to precompute transitions - they're generated in real-time
-    println!("Ready for real-time transitions...");
+    info!("Ready for real-time transitions...");
+    systemd::ready();
 
     // Main slideshow loop
     let mut running = true;
@@ -1755,29 +4677,36 @@ fn run_original_slideshow(config: Config) -> IoResult<()> {
         let current_idx = image_manager.current_index;
         let current_image_path = image_manager.images[current_idx].clone();
 
-        println!("Displaying: {}", current_image_path.display());
+        info!("Displaying: {}", current_image_path.display());
 
         // Load and display current image using fixed framebuffer dimensions
-        let current_image = load_and_scale_image_with_orientation(&current_image_path, DEFAULT_LANDSCAPE_WIDTH, DEFAULT_LANDSCAPE_HEIGHT, &config.orientation)
+        let current_image = image_manager.load(&current_image_path, &fb, &render_orientation)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
 
-        println!(
+        info!(
             "Loaded image {}x{} from {}",
             current_image.width(),
             current_image.height(),
             current_image_path.display()
         );
         fb.display_image(&current_image)?;
-        println!("Displayed image on framebuffer");
+        info!("Displayed image on framebuffer");
+
+        // Prepare the following image during the display period
+        if image_manager.images.len() > 1 {
+            let next = image_manager.images[(current_idx + 1) % image_manager.images.len()].clone();
+            image_manager.prefetch(vec![(next, fb.layout(), render_orientation.clone(), false)]);
+        }
 
         let display_start = Instant::now();
 
         // Display for configured duration while handling events
         while display_start.elapsed() < config.display_duration && running {
+            systemd::watchdog();
             // Check for events with timeout
             match rx.recv_timeout(Duration::from_millis(100)) {
                 Ok(SlideshowEvent::NewImage(new_path)) => {
-                    println!("New image detected: {}", new_path.display());
+                    info!("New image detected: {}", new_path.display());
                     if let Some(idx) = image_manager.add_new_image(new_path) {
                         pending_image_idx = Some(idx);
                     }
@@ -1793,7 +4722,9 @@ fn run_original_slideshow(config: Config) -> IoResult<()> {
                 }
             }
 
-            // No precomputation needed for real-time transitions
+            if let Err(e) = fb.refresh_if_due() {
+                error!("Failed to refresh framebuffer: {}", e);
+            }
         }
 
         if !running {
@@ -1824,20 +4755,34 @@ fn run_original_slideshow(config: Config) -> IoResult<()> {
         // No need to wait - transitions are generated in real-time
 
         // Play transition from the current image to next
-        let transition_type = TransitionType::get_random(); // Use random in standalone mode
-        if let Err(e) = image_manager.play_transition(actual_current_idx, next_idx, &mut fb, config.transition_duration, transition_type, &config.orientation) {
-            println!("Failed to play transition: {}", e);
+        // Use random in standalone mode, restricted to non-flashing effects under content safety
+        let transition_type = if config.content_safety {
+            TransitionType::get_random_flash_safe()
+        } else {
+            TransitionType::get_random()
+        };
+        let mut transition_duration = transition_type.duration(config.transition_duration, &HashMap::new());
+        if config.content_safety {
+            transition_duration = content_safety::safe_transition_duration(transition_duration);
+        }
+        image_manager.finish_prefetch();
+        if let Err(e) = image_manager.play_transition(actual_current_idx, next_idx, RenderTarget { fb: &mut fb, orientation: &render_orientation, mirrors: &mut [] }, transition_duration, transition_type) {
+            info!("Failed to play transition: {}", e);
         }
 
         // Update current index
         image_manager.current_index = next_idx;
     }
 
-    println!("Slideshow ended");
+    info!("Slideshow ended");
 
     // Display random joke before exiting
-    if let Err(e) = display_exit_joke(&mut fb) {
-        println!("Failed to display exit joke: {}", e);
+    if let Err(e) = display_exit_joke(&mut fb, &config.text_profile) {
+        info!("Failed to display exit joke: {}", e);
+    }
+    // The driver keeps its rotation after we exit; put the console back upright
+    if config.hardware_rotation {
+        let _ = fb.set_hardware_rotation(0);
     }
 
     Ok(())
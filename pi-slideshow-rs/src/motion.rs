@@ -0,0 +1,153 @@
+use rppal::gpio::{Gpio, InputPin, Trigger};
+use signage_protocol::OccupancyStats;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tracing::{error, info, warn};
+
+use crate::slideshow_controller::SlideshowController;
+
+// PIR motion sensor on a GPIO pin (--pir-pin), for hallway and meeting-room displays that
+// nobody is in front of most of the day. After --pir-idle-minutes without motion the display
+// sleeps: blanked as in quiet hours, with the TV put in standby over HDMI-CEC, or dimmed to
+// --pir-idle-brightness when that is above 0. Motion wakes it straight away, and a wake
+// command or a takeover keeps it up regardless. How often someone came by, and how long the
+// display slept, goes out with every heartbeat.
+
+// How often the controller checks whether the display should go to sleep; waking doesn't
+// wait for it
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+struct Settings {
+    idle_after: Duration,
+    // None blanks the display
+    idle_brightness: Option<u8>,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+static START: OnceLock<Instant> = OnceLock::new();
+// Milliseconds after START of the last edge from the sensor; startup counts as motion, so the
+// display starts awake
+static LAST_MOTION_MS: AtomicU64 = AtomicU64::new(0);
+static MOTION_SEEN: AtomicBool = AtomicBool::new(false);
+// The sensor holds its output high for as long as it sees movement
+static DETECTING: AtomicBool = AtomicBool::new(false);
+static DETECTIONS: AtomicU64 = AtomicU64::new(0);
+// Time slept in idle spells that have ended
+static IDLE_MS: AtomicU64 = AtomicU64::new(0);
+
+fn elapsed_ms() -> u64 {
+    START.get_or_init(Instant::now).elapsed().as_millis() as u64
+}
+
+fn since_last_motion() -> Duration {
+    Duration::from_millis(elapsed_ms().saturating_sub(LAST_MOTION_MS.load(Ordering::Relaxed)))
+}
+
+// How far into the current idle spell the display is; zero while someone is around
+fn current_idle() -> Duration {
+    match SETTINGS.get() {
+        Some(settings) if !DETECTING.load(Ordering::Relaxed) => since_last_motion().saturating_sub(settings.idle_after),
+        _ => Duration::ZERO,
+    }
+}
+
+// True once nobody has moved in front of the sensor for the idle time
+pub fn is_idle() -> bool {
+    !current_idle().is_zero()
+}
+
+// The level an idle display is dimmed to, or None when it is blanked (or there's no sensor)
+pub fn idle_brightness() -> Option<u8> {
+    SETTINGS.get().and_then(|settings| settings.idle_brightness)
+}
+
+pub fn is_enabled() -> bool {
+    SETTINGS.get().is_some()
+}
+
+fn on_edge(rising: bool) {
+    if rising {
+        IDLE_MS.fetch_add(current_idle().as_millis() as u64, Ordering::Relaxed);
+        DETECTIONS.fetch_add(1, Ordering::Relaxed);
+        MOTION_SEEN.store(true, Ordering::Relaxed);
+    }
+    // The idle time runs from when the movement stopped
+    DETECTING.store(rising, Ordering::Relaxed);
+    LAST_MOTION_MS.store(elapsed_ms(), Ordering::Relaxed);
+}
+
+// Occupancy for the heartbeat, or None without a sensor
+pub fn stats() -> Option<OccupancyStats> {
+    is_enabled().then(|| OccupancyStats {
+        occupied: !is_idle(),
+        last_motion_secs: MOTION_SEEN.load(Ordering::Relaxed).then(|| since_last_motion().as_secs()),
+        detections: DETECTIONS.load(Ordering::Relaxed),
+        idle_secs: (IDLE_MS.load(Ordering::Relaxed) / 1000) + current_idle().as_secs(),
+    })
+}
+
+// Start watching the sensor on BCM pin `pin`. The returned pin must be kept alive: dropping it
+// stops the interrupt. Without a usable sensor the display just never sleeps. Must be called
+// from within the runtime.
+pub fn spawn(pin: u8, idle_minutes: u64, idle_brightness: u8, controller: SlideshowController) -> Option<InputPin> {
+    let settings = Settings {
+        idle_after: Duration::from_secs(idle_minutes.max(1) * 60),
+        idle_brightness: (idle_brightness > 0).then(|| idle_brightness.min(100)),
+    };
+    // A sensor with nothing connected reads low, as no motion
+    let mut input = match Gpio::new().and_then(|gpio| gpio.get(pin)) {
+        Ok(input) => input.into_input_pulldown(),
+        Err(e) => {
+            warn!("PIR sensor on GPIO {} unavailable, the display won't sleep: {}", pin, e);
+            return None;
+        }
+    };
+
+    let woken = Arc::new(Notify::new());
+    let notify = woken.clone();
+    let watched = input.set_async_interrupt(Trigger::Both, None, move |event| {
+        let was_idle = is_idle();
+        on_edge(event.trigger == Trigger::RisingEdge);
+        if was_idle {
+            notify.notify_one();
+        }
+    });
+    if let Err(e) = watched {
+        error!("Failed to watch the PIR sensor on GPIO {}: {}", pin, e);
+        return None;
+    }
+    let idle_after = settings.idle_after;
+    let dims = settings.idle_brightness;
+    START.get_or_init(Instant::now);
+    let _ = SETTINGS.set(settings);
+    match dims {
+        Some(level) => info!("🚶 PIR sensor on GPIO {} dims the display to {}% after {} minutes without motion", pin, level, idle_after.as_secs() / 60),
+        None => info!("🚶 PIR sensor on GPIO {} blanks the display after {} minutes without motion", pin, idle_after.as_secs() / 60),
+    }
+
+    // Blanking is up to the display loop, which checks on every pass; dimming goes through the
+    // controller's brightness
+    if dims.is_none() {
+        return Some(input);
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(IDLE_CHECK_INTERVAL);
+        let mut was_unoccupied = false;
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = woken.notified() => {}
+            }
+            let unoccupied = controller.is_unoccupied().await;
+            if unoccupied == was_unoccupied {
+                continue;
+            }
+            was_unoccupied = unoccupied;
+            info!("🚶 {}", if unoccupied { "No motion, dimming display" } else { "Motion seen, restoring brightness" });
+            controller.apply_brightness_schedule().await;
+        }
+    });
+    Some(input)
+}
@@ -1,76 +1,113 @@
 use rumqttc::{AsyncClient, Event, Incoming, MqttOptions, QoS};
-use serde::{Deserialize, Serialize};
+use signage_protocol::mqtt::{is_safe_file_component, topics};
+use signage_protocol::{CrashReport, HeartbeatMessage, ImageInfo, MqttCommand, SlideshowConfig, SystemMetrics, ThrottleFlags, TickerMessage, TvStatus};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{broadcast, mpsc};
 use uuid::Uuid;
 use sysinfo::{CpuExt, DiskExt, System, SystemExt};
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MqttCommand {
-    pub command: String,
-    pub payload: serde_json::Value,
-    pub timestamp: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TvStatus {
-    pub status: String,
-    pub current_image: Option<String>,
-    pub total_images: usize,
-    pub current_index: usize,
-    pub uptime: u64,
-    pub timestamp: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SystemMetrics {
-    pub cpu_usage: f32,
-    pub memory_usage: f32,
-    pub memory_total: u64,
-    pub memory_used: u64,
-    pub disk_usage: f32,
-    pub disk_total: u64,
-    pub disk_used: u64,
-    pub temperature: Option<f32>,
-    pub load_average: Option<f32>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct HeartbeatMessage {
-    pub tv_id: String,
-    pub timestamp: String,
-    pub status: String,
-    pub system_metrics: Option<SystemMetrics>,
-}
+use tracing::{error, info};
+
+use crate::cec;
+use crate::clock;
+use crate::crash;
+use crate::downloads;
+use crate::fingerprint;
+use crate::hardware;
+use crate::health;
+use crate::logging;
+use crate::motion;
+use crate::network;
+use crate::perf;
+use crate::takeover::{self, Takeover};
+use crate::test_pattern::TestPattern;
+use crate::throttle;
+use crate::ticker;
+use crate::updater;
+
+// Longest timed pause accepted over MQTT or HTTP
+pub const MAX_PAUSE_MINUTES: u64 = 24 * 60;
+// How long a wake override keeps the screen on during quiet hours
+pub const DEFAULT_WAKE_MINUTES: u64 = 60;
+pub const MAX_WAKE_MINUTES: u64 = 24 * 60;
+// Longest timeout a takeover can be given; without one it stays up until released
+pub const MAX_TAKEOVER_MINUTES: u64 = 24 * 60;
+// Most one extend command can add to or take off the current slide's display time
+pub const MAX_EXTEND_SECONDS: i64 = 60 * 60;
+// Longest a curtain command can hold against the curtain hours
+pub const MAX_CURTAIN_MINUTES: u64 = 24 * 60;
 
 #[derive(Debug, Clone)]
 pub enum SlideshowCommand {
     Play,
     Pause,
+    PauseFor { duration: Duration },
+    // Lengthen (or with negative seconds, shorten) the current slide's remaining display time
+    Extend { seconds: i64 },
+    // Keep the current slide up, without pausing it, until Resume
+    Hold,
+    Resume,
     Next,
     Previous,
+    Shuffle { enabled: bool },
     UpdateImages { images: Vec<ImageInfo> },
-    UpdateConfig { config: SlideshowConfig },
+    // Boxed: the config is far larger than every other command
+    UpdateConfig { config: Box<SlideshowConfig> },
+    PreviewTransition { effect: Option<String> },
+    WakeFor { duration: Duration },
+    // Draw or lift the curtain, overriding the curtain hours for the duration or until they
+    // next change
+    Curtain { closed: bool, duration: Option<Duration> },
+    // None takes the pattern down and resumes the slideshow
+    TestPattern { pattern: Option<TestPattern> },
+    // Emergency content over the slideshow, until Release or the duration runs out
+    Takeover { content: Takeover, duration: Option<Duration> },
+    Release,
+    // Text for the ticker from the ticker topic; None takes it down
+    Ticker { text: Option<String> },
+    // Fetch the image list from CouchDB now instead of at the next periodic sync
+    Sync,
+    Restart,
     Reboot,
+    // Blank the screen and stop rendering, keeping the process up until Start
+    Stop,
+    Start,
+    // Power the Pi off
     Shutdown,
+    // Power the attached TV on or to standby, or switch it to the Pi's input, over HDMI-CEC
+    Cec { action: cec::Action },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ImageInfo {
-    pub id: String,
-    pub path: String,
-    pub order: u32,
-    pub url: Option<String>, // URL to download image from management server
-    pub extension: Option<String>, // File extension from server
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SlideshowConfig {
-    pub transition_effect: Option<String>,
-    pub display_duration: Option<u64>,
-    pub transition_duration: Option<u64>,
-    pub orientation: Option<String>,
+impl SlideshowCommand {
+    // For logs: the MQTT command it comes from, without the payload (an image list can be long)
+    pub fn name(&self) -> &'static str {
+        match self {
+            SlideshowCommand::Play => "play",
+            SlideshowCommand::Pause | SlideshowCommand::PauseFor { .. } => "pause",
+            SlideshowCommand::Extend { .. } => "extend",
+            SlideshowCommand::Hold => "hold",
+            SlideshowCommand::Resume => "resume",
+            SlideshowCommand::Next => "next",
+            SlideshowCommand::Previous => "previous",
+            SlideshowCommand::Shuffle { .. } => "shuffle",
+            SlideshowCommand::UpdateImages { .. } => "update_images",
+            SlideshowCommand::UpdateConfig { .. } => "update_config",
+            SlideshowCommand::PreviewTransition { .. } => "preview_transition",
+            SlideshowCommand::WakeFor { .. } => "wake",
+            SlideshowCommand::Curtain { .. } => "curtain",
+            SlideshowCommand::TestPattern { .. } => "test_pattern",
+            SlideshowCommand::Takeover { .. } => "takeover",
+            SlideshowCommand::Release => "release",
+            SlideshowCommand::Ticker { .. } => "ticker",
+            SlideshowCommand::Sync => "sync",
+            SlideshowCommand::Restart => "restart",
+            SlideshowCommand::Reboot => "reboot",
+            SlideshowCommand::Stop => "stop",
+            SlideshowCommand::Start => "start",
+            SlideshowCommand::Shutdown => "shutdown",
+            SlideshowCommand::Cec { .. } => "cec",
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -113,10 +150,12 @@ impl MqttClient {
         let (client, mut eventloop) = AsyncClient::new(mqttoptions, 10);
         
         // Subscribe to command topic
-        let command_topic = format!("signage/tv/{}/command", tv_id);
+        let command_topic = topics::command(&tv_id);
         client.subscribe(&command_topic, QoS::AtLeastOnce).await?;
+        let ticker_topic = topics::ticker(&tv_id);
+        client.subscribe(&ticker_topic, QoS::AtLeastOnce).await?;
         
-        println!("MQTT client connected, subscribed to {}", command_topic);
+        info!("MQTT client connected, subscribed to {} and {}", command_topic, ticker_topic);
 
         let mqtt_client = Self {
             client,
@@ -128,17 +167,27 @@ impl MqttClient {
         // Spawn MQTT event loop handler
         let cmd_sender = mqtt_client.command_sender.clone();
         let tv_id_clone = tv_id.clone();
+        let error_client = mqtt_client.client.clone();
         tokio::spawn(async move {
             loop {
                 match eventloop.poll().await {
                     Ok(Event::Incoming(Incoming::Publish(publish))) => {
                         if let Err(e) = Self::handle_mqtt_message(&publish.topic, &publish.payload, &cmd_sender, &tv_id_clone).await {
-                            eprintln!("Error handling MQTT message: {}", e);
+                            error!("Error handling MQTT message: {}", e);
+                            // Report rejected messages instead of silently dropping them
+                            let error = format!("Rejected message on {}: {}", publish.topic, e);
+                            if let Err(e) = Self::publish_error_with(&error_client, &tv_id_clone, &error).await {
+                                error!("Failed to publish MQTT error: {}", e);
+                            }
                         }
                     }
+                    Ok(Event::Incoming(Incoming::ConnAck(_))) => {
+                        health::set_mqtt_connected(true);
+                    }
                     Ok(_) => {}
                     Err(e) => {
-                        eprintln!("MQTT connection error: {}", e);
+                        health::set_mqtt_connected(false);
+                        error!("MQTT connection error: {}", e);
                         tokio::time::sleep(Duration::from_secs(5)).await;
                     }
                 }
@@ -154,60 +203,152 @@ impl MqttClient {
         command_sender: &broadcast::Sender<SlideshowCommand>,
         tv_id: &str,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let expected_topic = format!("signage/tv/{}/command", tv_id);
+        if topic == topics::ticker(tv_id) {
+            let text = TickerMessage::from_slice(payload)?.line();
+            if text.as_ref().is_some_and(|text| text.chars().count() > ticker::MAX_TEXT_CHARS) {
+                return Err(format!("Ticker text is over {} characters", ticker::MAX_TEXT_CHARS).into());
+            }
+            if let Err(e) = command_sender.send(SlideshowCommand::Ticker { text }) {
+                error!("Error sending command to slideshow: {}", e);
+            }
+            return Ok(());
+        }
+
+        let expected_topic = topics::command(tv_id);
         if topic != expected_topic {
             return Ok(());
         }
 
-        let payload_str = String::from_utf8(payload.to_vec())?;
-        let mqtt_command: MqttCommand = serde_json::from_str(&payload_str)?;
+        let mqtt_command = MqttCommand::from_slice(payload)?;
 
-        println!("Received MQTT command: {}", mqtt_command.command);
+        info!("Received MQTT command: {}", mqtt_command.command);
 
         let slideshow_command = match mqtt_command.command.as_str() {
             "play" => SlideshowCommand::Play,
-            "pause" => SlideshowCommand::Pause,
+            "pause" => match mqtt_command.payload.get("minutes").and_then(|v| v.as_u64()) {
+                Some(minutes) if (1..=MAX_PAUSE_MINUTES).contains(&minutes) => {
+                    SlideshowCommand::PauseFor { duration: Duration::from_secs(minutes * 60) }
+                }
+                Some(minutes) => return Err(format!("Pause of {} minutes is outside 1-{}", minutes, MAX_PAUSE_MINUTES).into()),
+                None => SlideshowCommand::Pause,
+            },
+            "extend" => match mqtt_command.payload.get("seconds").and_then(|v| v.as_i64()) {
+                Some(seconds) if seconds != 0 && seconds.abs() <= MAX_EXTEND_SECONDS => SlideshowCommand::Extend { seconds },
+                Some(seconds) => return Err(format!("Extend of {} seconds is outside ±1-{}", seconds, MAX_EXTEND_SECONDS).into()),
+                None => return Err("Extend needs seconds".into()),
+            },
+            "wake" => match mqtt_command.payload.get("minutes").and_then(|v| v.as_u64()) {
+                Some(minutes) if (1..=MAX_WAKE_MINUTES).contains(&minutes) => {
+                    SlideshowCommand::WakeFor { duration: Duration::from_secs(minutes * 60) }
+                }
+                Some(minutes) => return Err(format!("Wake of {} minutes is outside 1-{}", minutes, MAX_WAKE_MINUTES).into()),
+                None => SlideshowCommand::WakeFor { duration: Duration::from_secs(DEFAULT_WAKE_MINUTES * 60) },
+            },
+            "curtain" => {
+                let closed = match mqtt_command.payload.get("enabled") {
+                    Some(enabled) => match enabled.as_bool() {
+                        Some(enabled) => enabled,
+                        None => return Err(format!("Curtain enabled must be true or false, got {}", enabled).into()),
+                    },
+                    None => true,
+                };
+                let duration = match mqtt_command.payload.get("minutes").and_then(|v| v.as_u64()) {
+                    Some(minutes) if (1..=MAX_CURTAIN_MINUTES).contains(&minutes) => Some(Duration::from_secs(minutes * 60)),
+                    Some(minutes) => return Err(format!("Curtain of {} minutes is outside 1-{}", minutes, MAX_CURTAIN_MINUTES).into()),
+                    None => None,
+                };
+                SlideshowCommand::Curtain { closed, duration }
+            },
+            "test_pattern" => match mqtt_command.payload.get("pattern").and_then(|v| v.as_str()) {
+                Some("off") => SlideshowCommand::TestPattern { pattern: None },
+                Some(name) => match TestPattern::parse(name) {
+                    Some(pattern) => SlideshowCommand::TestPattern { pattern: Some(pattern) },
+                    None => return Err(format!("Unknown test pattern: {}", name).into()),
+                },
+                None => SlideshowCommand::TestPattern { pattern: Some(TestPattern::Bars) },
+            },
+            "takeover" => {
+                let image_id = mqtt_command.payload.get("image_id").and_then(|v| v.as_str());
+                let text = mqtt_command.payload.get("text").and_then(|v| v.as_str());
+                let content = match (image_id, text) {
+                    (Some(id), None) if is_safe_file_component(id) => Takeover::Image(id.to_string()),
+                    (Some(id), None) => return Err(format!("Unsafe takeover image id: {:?}", id).into()),
+                    (None, Some(text)) if text.trim().is_empty() => return Err("Takeover text is empty".into()),
+                    (None, Some(text)) if text.chars().count() > takeover::MAX_TEXT_CHARS => {
+                        return Err(format!("Takeover text is over {} characters", takeover::MAX_TEXT_CHARS).into());
+                    }
+                    (None, Some(text)) => Takeover::Text(text.to_string()),
+                    _ => return Err("Takeover needs exactly one of image_id or text".into()),
+                };
+                let duration = match mqtt_command.payload.get("minutes").and_then(|v| v.as_u64()) {
+                    Some(minutes) if (1..=MAX_TAKEOVER_MINUTES).contains(&minutes) => Some(Duration::from_secs(minutes * 60)),
+                    Some(minutes) => return Err(format!("Takeover of {} minutes is outside 1-{}", minutes, MAX_TAKEOVER_MINUTES).into()),
+                    None => None,
+                };
+                SlideshowCommand::Takeover { content, duration }
+            },
+            "release" => SlideshowCommand::Release,
+            "hold" => SlideshowCommand::Hold,
+            "resume" => SlideshowCommand::Resume,
             "next" => SlideshowCommand::Next,
             "previous" => SlideshowCommand::Previous,
+            "shuffle" => match mqtt_command.payload.get("enabled") {
+                Some(enabled) => match enabled.as_bool() {
+                    Some(enabled) => SlideshowCommand::Shuffle { enabled },
+                    None => return Err(format!("Shuffle enabled must be true or false, got {}", enabled).into()),
+                },
+                None => SlideshowCommand::Shuffle { enabled: true },
+            },
+            "sync" => SlideshowCommand::Sync,
+            "restart" => SlideshowCommand::Restart,
             "reboot" => SlideshowCommand::Reboot,
+            "cec" => match mqtt_command.payload.get("action").and_then(|v| v.as_str()) {
+                Some(name) => match cec::Action::parse(name) {
+                    Some(action) => SlideshowCommand::Cec { action },
+                    None => return Err(format!("Unknown CEC action: {} (expected on, standby or input)", name).into()),
+                },
+                None => return Err("CEC command needs an action".into()),
+            },
+            "stop" => SlideshowCommand::Stop,
+            "start" => SlideshowCommand::Start,
             "shutdown" => SlideshowCommand::Shutdown,
             "update_images" => {
-                let images: Vec<ImageInfo> = serde_json::from_value(mqtt_command.payload["images"].clone())?;
+                let images = mqtt_command.images()?;
                 SlideshowCommand::UpdateImages { images }
             },
+            "time_sync" => {
+                // Handled here rather than by the slideshow; only affects our own timestamps
+                let offset_ms = clock::apply_time_sync(&mqtt_command.payload)?;
+                info!("Applied server clock offset of {}ms", offset_ms);
+                return Ok(());
+            },
+            "set_log_level" => {
+                // Also handled here; only affects logging
+                let level = mqtt_command.payload.get("level").and_then(|v| v.as_str()).ok_or("set_log_level needs a level")?;
+                logging::set_level(level)?;
+                return Ok(());
+            },
             "update_config" => {
                 // The payload contains the full TV config object from the management system
-                // We need to map it to our SlideshowConfig structure
-                let config = SlideshowConfig {
-                    transition_effect: mqtt_command.payload.get("transition_effect")
-                        .and_then(|v| v.as_str())
-                        .map(|s| s.to_string()),
-                    display_duration: mqtt_command.payload.get("display_duration")
-                        .and_then(|v| v.as_u64()),
-                    transition_duration: mqtt_command.payload.get("transition_duration")
-                        .and_then(|v| v.as_u64()),
-                    orientation: mqtt_command.payload.get("orientation")
-                        .and_then(|v| v.as_str())
-                        .map(|s| s.to_string()),
-                };
-                println!("🔄 MQTT CONFIG UPDATE received: {:?}", config);
-                SlideshowCommand::UpdateConfig { config }
+                let config = SlideshowConfig::from_payload(&mqtt_command.payload);
+                info!("🔄 MQTT CONFIG UPDATE received: {:?}", config);
+                SlideshowCommand::UpdateConfig { config: Box::new(config) }
             },
             _ => {
-                println!("Unknown command: {}", mqtt_command.command);
+                info!("Unknown command: {}", mqtt_command.command);
                 return Ok(());
             }
         };
 
         if let Err(e) = command_sender.send(slideshow_command) {
-            eprintln!("Error sending command to slideshow: {}", e);
+            error!("Error sending command to slideshow: {}", e);
         }
 
         Ok(())
     }
 
     pub async fn publish_status(&self, status: &TvStatus) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let topic = format!("signage/tv/{}/status", self.tv_id);
+        let topic = topics::status(&self.tv_id);
         let payload = serde_json::to_string(status)?;
         
         self.client.publish(&topic, QoS::AtLeastOnce, false, payload).await?;
@@ -216,27 +357,145 @@ impl MqttClient {
 
 
     pub async fn publish_current_image(&self, image_id: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let topic = format!("signage/tv/{}/image/current", self.tv_id);
+        let topic = topics::current_image(&self.tv_id);
         let payload = serde_json::json!({
             "image_id": image_id,
-            "timestamp": chrono::Utc::now().to_rfc3339()
+            "timestamp": clock::timestamp(),
+            "sequence": clock::next_sequence(),
+            "clock_confidence": clock::confidence()
         });
         
         self.client.publish(&topic, QoS::AtLeastOnce, false, payload.to_string()).await?;
         Ok(())
     }
 
-    pub async fn publish_error(&self, error: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let topic = format!("signage/tv/{}/error", self.tv_id);
+    // The daypart schedule now playing, None when back on the unscheduled images
+    pub async fn publish_schedule(&self, schedule: Option<&str>, images: usize) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let topic = topics::schedule(&self.tv_id);
+        let payload = serde_json::json!({
+            "schedule": schedule,
+            "images": images,
+            "timestamp": clock::timestamp(),
+            "sequence": clock::next_sequence(),
+            "clock_confidence": clock::confidence()
+        });
+
+        self.client.publish(&topic, QoS::AtLeastOnce, false, payload.to_string()).await?;
+        Ok(())
+    }
+
+    // An error about one image, e.g. a file that couldn't be decoded
+    pub async fn publish_image_error(&self, image_id: &str, error: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let topic = topics::error(&self.tv_id);
+        let payload = serde_json::json!({
+            "error": error,
+            "image_id": image_id,
+            "timestamp": clock::timestamp(),
+            "sequence": clock::next_sequence(),
+            "clock_confidence": clock::confidence()
+        });
+        
+        self.client.publish(&topic, QoS::AtLeastOnce, false, payload.to_string()).await?;
+        Ok(())
+    }
+
+    // A step of a self-update: "downloading", "failed", "restarting", "health_check",
+    // "installed" or "rolled_back"
+    pub async fn publish_update(&self, stage: &str, version: &str, error: Option<&str>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let topic = topics::update(&self.tv_id);
         let payload = serde_json::json!({
+            "stage": stage,
+            "version": version,
+            "running_version": updater::VERSION,
             "error": error,
-            "timestamp": chrono::Utc::now().to_rfc3339()
+            "timestamp": clock::timestamp(),
+            "sequence": clock::next_sequence(),
+            "clock_confidence": clock::confidence()
+        });
+
+        self.client.publish(&topic, QoS::AtLeastOnce, false, payload.to_string()).await?;
+        Ok(())
+    }
+
+    // The SD card is below its minimum free space even after clearing what could go, so
+    // downloads are refused
+    pub async fn publish_disk_full(&self, available_bytes: u64, min_free_bytes: u64) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let topic = topics::error(&self.tv_id);
+        let payload = serde_json::json!({
+            "error": "disk_full",
+            "available_bytes": available_bytes,
+            "min_free_bytes": min_free_bytes,
+            "timestamp": clock::timestamp(),
+            "sequence": clock::next_sequence(),
+            "clock_confidence": clock::confidence()
+        });
+
+        self.client.publish(&topic, QoS::AtLeastOnce, false, payload.to_string()).await?;
+        Ok(())
+    }
+
+    // The firmware raised a flag it hadn't before: under-voltage (usually the power supply) or
+    // throttling
+    pub async fn publish_throttled(&self, flags: &ThrottleFlags, temperature: Option<f32>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let topic = topics::error(&self.tv_id);
+        let payload = serde_json::json!({
+            "error": "throttled",
+            "active": flags.active,
+            "since_boot": flags.since_boot,
+            "temperature": temperature,
+            "timestamp": clock::timestamp(),
+            "sequence": clock::next_sequence(),
+            "clock_confidence": clock::confidence()
+        });
+
+        self.client.publish(&topic, QoS::AtLeastOnce, false, payload.to_string()).await?;
+        Ok(())
+    }
+
+    // The display loop stopped making progress and the watchdog is about to restart the endpoint
+    pub async fn publish_stalled(&self, stalled_for: Duration) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let topic = topics::error(&self.tv_id);
+        let payload = serde_json::json!({
+            "error": "stalled",
+            "stalled_secs": stalled_for.as_secs(),
+            "timestamp": clock::timestamp(),
+            "sequence": clock::next_sequence(),
+            "clock_confidence": clock::confidence()
         });
         
         self.client.publish(&topic, QoS::AtLeastOnce, false, payload.to_string()).await?;
         Ok(())
     }
 
+    // A crash from before this start, as it was saved
+    pub async fn publish_crash(&self, report: &CrashReport) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let topic = topics::crash(&self.tv_id);
+        let mut payload = serde_json::to_value(report)?;
+        payload["timestamp"] = clock::timestamp().into();
+        payload["sequence"] = clock::next_sequence().into();
+        payload["clock_confidence"] = clock::confidence().into();
+
+        self.client.publish(&topic, QoS::AtLeastOnce, false, payload.to_string()).await?;
+        Ok(())
+    }
+
+    pub async fn publish_error(&self, error: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Self::publish_error_with(&self.client, &self.tv_id, error).await
+    }
+
+    async fn publish_error_with(client: &AsyncClient, tv_id: &str, error: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let topic = topics::error(tv_id);
+        let payload = serde_json::json!({
+            "error": error,
+            "timestamp": clock::timestamp(),
+            "sequence": clock::next_sequence(),
+            "clock_confidence": clock::confidence()
+        });
+        
+        client.publish(&topic, QoS::AtLeastOnce, false, payload.to_string()).await?;
+        Ok(())
+    }
+
     pub async fn run_status_publisher(&mut self) {
         let client = self.client.clone();
         let tv_id = self.tv_id.clone();
@@ -254,20 +513,34 @@ impl MqttClient {
                 
                 // Refresh system information
                 system.refresh_all();
+                hardware::refresh().await;
                 
                 let system_metrics = Self::collect_system_metrics(&system);
+                cec::refresh_power_state().await;
+                clock::refresh_ntp_status().await;
                 
                 let heartbeat = HeartbeatMessage {
                     tv_id: heartbeat_tv_id.clone(),
-                    timestamp: chrono::Utc::now().to_rfc3339(),
+                    timestamp: clock::timestamp(),
                     status: "online".to_string(),
                     system_metrics: Some(system_metrics),
+                    sequence: clock::next_sequence(),
+                    clock_confidence: clock::confidence().to_string(),
+                    tv_power: cec::power_state().map(|state| state.to_string()),
+                    occupancy: motion::stats(),
+                    clock: Some(clock::status()),
+                    render: Some(perf::stats()),
+                    downloads: Some(downloads::stats()),
+                    version: Some(updater::VERSION.to_string()),
+                    commit: fingerprint::commit().map(|commit| commit.to_string()),
+                    uptime: Some(crash::uptime()),
+                    config_hash: fingerprint::config_hash(),
                 };
                 
                 if let Ok(payload) = serde_json::to_string(&heartbeat) {
-                    let topic = format!("signage/tv/{}/heartbeat", heartbeat_tv_id);
+                    let topic = topics::heartbeat(&heartbeat_tv_id);
                     if let Err(e) = heartbeat_client.publish(&topic, QoS::AtLeastOnce, false, payload).await {
-                        eprintln!("Failed to publish heartbeat: {}", e);
+                        error!("Failed to publish heartbeat: {}", e);
                     }
                 }
             }
@@ -279,9 +552,9 @@ impl MqttClient {
             
             while let Some(status) = receiver.recv().await {
                 if let Ok(payload) = serde_json::to_string(&status) {
-                    let topic = format!("signage/tv/{}/status", tv_id);
+                    let topic = topics::status(&tv_id);
                     if let Err(e) = client.publish(&topic, QoS::AtLeastOnce, false, payload).await {
-                        eprintln!("Failed to publish status update: {}", e);
+                        error!("Failed to publish status update: {}", e);
                     }
                 }
             }
@@ -320,11 +593,13 @@ impl MqttClient {
             .unwrap_or((0, 0, 0.0));
 
         // Try to get CPU temperature (Raspberry Pi specific)
-        let temperature = Self::get_cpu_temperature();
+        let temperature = health::cpu_temperature();
 
         // Load average (1 minute)
         let load_average = system.load_average().one;
 
+        let (image_dir_bytes, image_dir_limit_bytes) = health::image_dir_usage();
+
         SystemMetrics {
             cpu_usage,
             memory_usage,
@@ -335,26 +610,13 @@ impl MqttClient {
             disk_used,
             temperature,
             load_average: Some(load_average as f32),
+            image_dir_bytes,
+            image_dir_limit_bytes,
+            throttle: throttle::flags(),
+            hardware: hardware::info(),
+            network: network::interfaces(),
         }
     }
-
-    fn get_cpu_temperature() -> Option<f32> {
-        // Try Raspberry Pi thermal zone first
-        if let Ok(temp_str) = std::fs::read_to_string("/sys/class/thermal/thermal_zone0/temp") {
-            if let Ok(temp_millidegrees) = temp_str.trim().parse::<f32>() {
-                return Some(temp_millidegrees / 1000.0);
-            }
-        }
-
-        // Try alternative thermal sources
-        if let Ok(temp_str) = std::fs::read_to_string("/sys/devices/virtual/thermal/thermal_zone0/temp") {
-            if let Ok(temp_millidegrees) = temp_str.trim().parse::<f32>() {
-                return Some(temp_millidegrees / 1000.0);
-            }
-        }
-
-        None
-    }
 }
 
 // Helper function to generate unique TV ID based on hostname or MAC address
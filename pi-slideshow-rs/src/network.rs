@@ -0,0 +1,125 @@
+use signage_protocol::NetworkInterface;
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+
+// The network interfaces for the heartbeat, since a weak or flapping link is the usual reason a
+// TV shows stale content: which interface carries the default route, its addresses, the Wi-Fi
+// signal and the traffic and error counters. Interfaces without hardware behind them (loopback,
+// bridges, VPN tunnels) are left out. Everything comes from sysfs, /proc and getifaddrs, so
+// reading it costs no more than the rest of the metrics.
+
+const SYS_NET: &str = "/sys/class/net";
+
+pub fn interfaces() -> Vec<NetworkInterface> {
+    let Ok(entries) = fs::read_dir(SYS_NET) else { return Vec::new() };
+    let mut addresses = addresses();
+    let signal = wifi_signal();
+    let default_route = default_route_interface();
+
+    let mut interfaces: Vec<NetworkInterface> = entries.flatten()
+        .filter(|entry| entry.path().join("device").exists())
+        .map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let dir = entry.path();
+            let wifi = dir.join("wireless").exists();
+            let kind = if wifi {
+                "wifi"
+            } else if read(&dir, "type").as_deref() == Some("1") {
+                // ARPHRD_ETHER
+                "ethernet"
+            } else {
+                "other"
+            };
+            let mut own_addresses = addresses.remove(&name).unwrap_or_default();
+            own_addresses.sort_by_key(|address| address.is_ipv6());
+            NetworkInterface {
+                kind: kind.to_string(),
+                up: read(&dir, "operstate").as_deref() == Some("up"),
+                default_route: default_route.as_deref() == Some(name.as_str()),
+                addresses: own_addresses.iter().map(|address| address.to_string()).collect(),
+                rssi_dbm: signal.get(&name).copied(),
+                // Reading speed fails while the link is down, and Wi-Fi reports none
+                speed_mbps: if wifi { None } else { read(&dir, "speed").and_then(|speed| speed.parse().ok()) },
+                rx_bytes: counter(&dir, "rx_bytes"),
+                tx_bytes: counter(&dir, "tx_bytes"),
+                rx_errors: counter(&dir, "rx_errors"),
+                tx_errors: counter(&dir, "tx_errors"),
+                name,
+            }
+        })
+        .collect();
+    interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+    interfaces
+}
+
+fn read(dir: &Path, attribute: &str) -> Option<String> {
+    fs::read_to_string(dir.join(attribute)).ok().map(|value| value.trim().to_string())
+}
+
+fn counter(dir: &Path, name: &str) -> u64 {
+    read(&dir.join("statistics"), name).and_then(|value| value.parse().ok()).unwrap_or(0)
+}
+
+// Addresses by interface name
+fn addresses() -> HashMap<String, Vec<IpAddr>> {
+    let mut addresses: HashMap<String, Vec<IpAddr>> = HashMap::new();
+    let mut list: *mut libc::ifaddrs = std::ptr::null_mut();
+    if unsafe { libc::getifaddrs(&mut list) } != 0 {
+        return addresses;
+    }
+    let mut entry = list;
+    while !entry.is_null() {
+        // The list stays valid until freeifaddrs
+        let ifa = unsafe { &*entry };
+        entry = ifa.ifa_next;
+        if ifa.ifa_addr.is_null() {
+            continue;
+        }
+        let address = match unsafe { (*ifa.ifa_addr).sa_family } as libc::c_int {
+            libc::AF_INET => {
+                let address = unsafe { &*(ifa.ifa_addr as *const libc::sockaddr_in) };
+                IpAddr::V4(Ipv4Addr::from(u32::from_be(address.sin_addr.s_addr)))
+            }
+            libc::AF_INET6 => {
+                let address = unsafe { &*(ifa.ifa_addr as *const libc::sockaddr_in6) };
+                let address = Ipv6Addr::from(address.sin6_addr.s6_addr);
+                // fe80::/10 is on every interface and reachable from nowhere else
+                if address.segments()[0] & 0xffc0 == 0xfe80 {
+                    continue;
+                }
+                IpAddr::V6(address)
+            }
+            _ => continue,
+        };
+        let name = unsafe { CStr::from_ptr(ifa.ifa_name) }.to_string_lossy().into_owned();
+        addresses.entry(name).or_default().push(address);
+    }
+    unsafe { libc::freeifaddrs(list) };
+    addresses
+}
+
+// Signal level in dBm by interface, from /proc/net/wireless:
+//  wlan0: 0000   53.  -57.  -256        0      0      0      0     59        0
+fn wifi_signal() -> HashMap<String, i32> {
+    let Ok(table) = fs::read_to_string("/proc/net/wireless") else { return HashMap::new() };
+    table.lines().skip(2)
+        .filter_map(|line| {
+            let (name, fields) = line.trim().split_once(':')?;
+            let level: f32 = fields.split_whitespace().nth(2)?.trim_end_matches('.').parse().ok()?;
+            Some((name.to_string(), level as i32))
+        })
+        .collect()
+}
+
+// The interface of the default route (the one to 00000000), if there is one
+pub fn default_route_interface() -> Option<String> {
+    let routes = fs::read_to_string("/proc/net/route").ok()?;
+    routes.lines().skip(1).find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let name = fields.next()?;
+        (fields.next()? == "00000000").then(|| name.to_string())
+    })
+}
@@ -0,0 +1,82 @@
+use chrono::Local;
+use image::{Rgba, RgbaImage};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::text_profile::TextProfile;
+use crate::{clock, draw_text, health, text_char_size, Orientation, Region};
+
+// A small banner in the viewer's top left corner once the endpoint has been cut off from both
+// the MQTT broker and CouchDB for --offline-banner-minutes, so staff on site can tell from
+// across the room that the screen has stopped getting updates. It goes as soon as either of
+// them is back. Like the ticker, the framebuffer paints it over every frame it writes.
+
+// Glyph size on a 1080-line screen; the banner is 9 glyph units tall
+const CHAR_SIZE: u32 = 4;
+
+const BACKGROUND: Rgba<u8> = Rgba([20, 20, 20, 255]);
+const AMBER: Rgba<u8> = Rgba([255, 176, 0, 255]);
+
+static SHOW_AFTER_SECS: AtomicU64 = AtomicU64::new(0);
+
+// 0 never shows the banner
+pub fn configure(minutes: u64) {
+    SHOW_AFTER_SECS.store(minutes * 60, Ordering::Relaxed);
+}
+
+// What the banner should say now, or None while it shouldn't be up
+pub fn text() -> Option<String> {
+    let show_after = SHOW_AFTER_SECS.load(Ordering::Relaxed);
+    if show_after == 0 {
+        return None;
+    }
+    if health::offline_for()?.as_secs() < show_after {
+        return None;
+    }
+    let since = health::offline_since()?;
+    let now = clock::now();
+    let since = since.with_timezone(&Local);
+    // The time alone would read as today after midnight
+    let format = if since.date_naive() == now.with_timezone(&Local).date_naive() { "%H:%M" } else { "%d %b %H:%M" };
+    Some(format!("Offline since {} - playing cached content", since.format(format)))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Banner {
+    text: String,
+    orientation: Orientation,
+    text_profile: TextProfile,
+}
+
+impl Banner {
+    pub fn new(text: String, orientation: Orientation, text_profile: TextProfile) -> Self {
+        Self { text, orientation, text_profile }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    // The banner rotated for the framebuffer, and where it goes on a screen whose safe area is
+    // `(x, y, width, height)`
+    pub fn render(&self, (x, y, width, height): (u32, u32, u32, u32)) -> (RgbaImage, Region) {
+        let (upright_width, upright_height) = self.orientation.upright_size(width, height);
+        let char_size = self.text_profile.char_size(text_char_size(CHAR_SIZE, upright_width, upright_height));
+        let banner_width = (self.text.chars().count() as u32 * 8 * char_size + 3 * char_size).min(upright_width);
+        let banner_height = (9 * char_size).min(upright_height);
+        let background = self.text_profile.background(BACKGROUND);
+        let color = self.text_profile.foreground(AMBER, background);
+
+        let mut banner = RgbaImage::from_pixel(banner_width, banner_height, background);
+        draw_text(&mut banner, &self.text, 2 * char_size, 2 * char_size, char_size, color);
+
+        // The viewer's top left corner, wherever that lands on the panel
+        let (rotated_width, rotated_height) = self.orientation.upright_size(banner_width, banner_height);
+        let region = match self.orientation {
+            Orientation::Landscape => Region { x, y, width: rotated_width, height: rotated_height },
+            Orientation::InvertedLandscape => Region { x: x + width - rotated_width, y: y + height - rotated_height, width: rotated_width, height: rotated_height },
+            Orientation::Portrait => Region { x: x + width - rotated_width, y, width: rotated_width, height: rotated_height },
+            Orientation::InvertedPortrait => Region { x, y: y + height - rotated_height, width: rotated_width, height: rotated_height },
+        };
+        (self.orientation.rotate_image(&banner), region)
+    }
+}
@@ -0,0 +1,71 @@
+use signage_protocol::RenderStats;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// How long rendering takes on this device, for the status and heartbeats: decoding image files,
+// scaling them to the display, the frame rate transitions reach and writing frames to the
+// framebuffer. Each is averaged over its last few measurements, so a device that has started to
+// struggle (a hot CPU, a slow card, images too big for it) shows up while it is happening.
+// Slides served from the cache aren't decoded or scaled, so they don't count.
+
+// Measurements each average is over
+const WINDOW: usize = 20;
+
+static DECODE_MS: Mutex<VecDeque<f32>> = Mutex::new(VecDeque::new());
+static SCALE_MS: Mutex<VecDeque<f32>> = Mutex::new(VecDeque::new());
+static TRANSITION_FPS: Mutex<VecDeque<f32>> = Mutex::new(VecDeque::new());
+static FRAMEBUFFER_WRITE_MS: Mutex<VecDeque<f32>> = Mutex::new(VecDeque::new());
+// Frame rates summed and counted since the metrics history last took a sample
+static FPS_SINCE_SAMPLE: Mutex<(f32, u32)> = Mutex::new((0.0, 0));
+
+pub fn record_decode(took: Duration) {
+    push(&DECODE_MS, millis(took));
+}
+
+pub fn record_scale(took: Duration) {
+    push(&SCALE_MS, millis(took));
+}
+
+pub fn record_transition(fps: f32) {
+    push(&TRANSITION_FPS, fps);
+    let mut since_sample = FPS_SINCE_SAMPLE.lock().unwrap();
+    since_sample.0 += fps;
+    since_sample.1 += 1;
+}
+
+// Average frame rate of the transitions since the last call, None if there were none
+pub fn take_transition_fps() -> Option<f32> {
+    let (sum, count) = std::mem::take(&mut *FPS_SINCE_SAMPLE.lock().unwrap());
+    (count > 0).then(|| sum / count as f32)
+}
+
+pub fn record_framebuffer_write(took: Duration) {
+    push(&FRAMEBUFFER_WRITE_MS, millis(took));
+}
+
+pub fn stats() -> RenderStats {
+    RenderStats {
+        decode_ms: average(&DECODE_MS),
+        scale_ms: average(&SCALE_MS),
+        transition_fps: average(&TRANSITION_FPS),
+        framebuffer_write_ms: average(&FRAMEBUFFER_WRITE_MS),
+    }
+}
+
+fn millis(took: Duration) -> f32 {
+    took.as_secs_f32() * 1000.0
+}
+
+fn push(samples: &Mutex<VecDeque<f32>>, value: f32) {
+    let mut samples = samples.lock().unwrap();
+    if samples.len() == WINDOW {
+        samples.pop_front();
+    }
+    samples.push_back(value);
+}
+
+fn average(samples: &Mutex<VecDeque<f32>>) -> Option<f32> {
+    let samples = samples.lock().unwrap();
+    (!samples.is_empty()).then(|| samples.iter().sum::<f32>() / samples.len() as f32)
+}
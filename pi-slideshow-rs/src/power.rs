@@ -0,0 +1,30 @@
+use tracing::info;
+
+// Reboot and power off through systemd-logind on the system D-Bus rather than `sudo`, which
+// needs a sudoers entry and can't work at all under NoNewPrivileges=. logind asks polkit
+// whether the caller may; a service has no login session, so the signage user needs a polkit
+// rule for org.freedesktop.login1.reboot and .power-off (install.sh writes one). Without it the
+// call fails with "Interactive authentication required" and the error is reported back like
+// any other failed command.
+
+const DESTINATION: &str = "org.freedesktop.login1";
+const PATH: &str = "/org/freedesktop/login1";
+const INTERFACE: &str = "org.freedesktop.login1.Manager";
+
+pub async fn reboot() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    call("Reboot").await
+}
+
+pub async fn power_off() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    call("PowerOff").await
+}
+
+async fn call(method: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let connection = zbus::Connection::system().await
+        .map_err(|e| format!("can't reach the system bus: {}", e))?;
+    // Not interactive: there is nobody to type a password
+    connection.call_method(Some(DESTINATION), PATH, Some(INTERFACE), method, &(false,)).await
+        .map_err(|e| format!("logind refused {}: {}", method, e))?;
+    info!("logind accepted {}", method);
+    Ok(())
+}
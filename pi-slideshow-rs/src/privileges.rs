@@ -0,0 +1,107 @@
+use std::ffi::CString;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use tracing::{error, info, warn};
+
+use crate::{cec, logging};
+
+// Root only for as long as it takes to open what needs it. Started as root with --user, the
+// endpoint opens the framebuffer, the extra outputs and the panel backlight, then switches for
+// good to that user, its primary group and its supplementary groups, before the display loop
+// shows anything. What is opened later goes through the groups: video for reopening a
+// framebuffer after a hotplug and for the CEC device, gpio for the GPIO buttons. Started as
+// anyone else there is nothing to drop and --user is ignored, as under systemd's User=.
+
+struct Account {
+    name: CString,
+    uid: libc::uid_t,
+    gid: libc::gid_t,
+}
+
+static TARGET: OnceLock<Account> = OnceLock::new();
+static DROPPED: AtomicBool = AtomicBool::new(false);
+
+// Look the user up at startup, so a typo stops the endpoint straight away rather than once the
+// display is open
+pub fn configure(user: Option<&str>) -> Result<(), String> {
+    let is_root = unsafe { libc::geteuid() } == 0;
+    match user {
+        None if is_root => {
+            warn!("Running as root; --user would switch to an unprivileged user once the display is open");
+            Ok(())
+        }
+        None => Ok(()),
+        Some(name) if !is_root => {
+            info!("Not running as root, so not switching to user {}", name);
+            Ok(())
+        }
+        Some(name) => {
+            let account = lookup(name)?;
+            let _ = TARGET.set(account);
+            Ok(())
+        }
+    }
+}
+
+// Switch to the --user account if there is one and it hasn't happened yet. Failing exits
+// instead, since carrying on as root is what --user is there to prevent.
+pub fn drop_root() {
+    let Some(account) = TARGET.get() else { return };
+    if DROPPED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let name = account.name.to_string_lossy();
+    // Root created the log files; the account has to be able to rotate them
+    if let Err(e) = logging::hand_over(account.uid, account.gid) {
+        warn!("⚠️ Failed to give the log directory to {}, the log may stop rotating: {}", name, e);
+    }
+    if let Err(e) = switch_to(account) {
+        error!("❌ Failed to switch to user {}: {}", name, e);
+        std::process::exit(1);
+    }
+    info!("🔒 Dropped root, running as {} (uid {}, gid {})", name, account.uid, account.gid);
+    cec::check_access();
+}
+
+// Whether this process has given up root, so a copy of it started in its place couldn't open
+// what needed root again
+pub fn dropped() -> bool {
+    DROPPED.load(Ordering::SeqCst)
+}
+
+fn lookup(name: &str) -> Result<Account, String> {
+    let c_name = CString::new(name).map_err(|_| format!("invalid user name '{}'", name))?;
+    // getpwnam isn't thread safe, but nothing else looks users up
+    let entry = unsafe { libc::getpwnam(c_name.as_ptr()) };
+    if entry.is_null() {
+        return Err(format!("no user '{}'", name));
+    }
+    let (uid, gid) = unsafe { ((*entry).pw_uid, (*entry).pw_gid) };
+    if uid == 0 {
+        return Err(format!("user '{}' is root", name));
+    }
+    Ok(Account { name: c_name, uid, gid })
+}
+
+fn switch_to(account: &Account) -> Result<(), String> {
+    let failed = |call: &str| format!("{} failed: {}", call, io::Error::last_os_error());
+    // Groups first, while changing them is still allowed. The C library applies the
+    // changes to every thread of the process, not just this one.
+    unsafe {
+        if libc::initgroups(account.name.as_ptr(), account.gid) != 0 {
+            return Err(failed("initgroups"));
+        }
+        if libc::setgid(account.gid) != 0 {
+            return Err(failed("setgid"));
+        }
+        if libc::setuid(account.uid) != 0 {
+            return Err(failed("setuid"));
+        }
+        // setuid as root replaces the saved ID too, so this should have no way back
+        if libc::setuid(0) == 0 {
+            return Err("root can still be regained".to_string());
+        }
+    }
+    Ok(())
+}
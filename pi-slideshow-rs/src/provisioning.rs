@@ -0,0 +1,476 @@
+use clap::Command;
+use image::{Rgba, RgbaImage};
+use ring::digest;
+use std::collections::HashMap;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::process::Command as Process;
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+use warp::Filter;
+
+use crate::config_file;
+use crate::network;
+use crate::qr_code::{Corner, QrOverlay};
+use crate::systemd;
+use crate::text_profile::TextProfile;
+use crate::{draw_text, text_char_size, wrap_text, Orientation};
+
+// First-boot setup. An endpoint that starts with no network configured (no saved Wi-Fi
+// network, and no default route once DHCP has had its chance) runs a Wi-Fi hotspot instead of
+// the slideshow and says on screen how to join it. The page it serves there takes the Wi-Fi
+// network, the MQTT broker and CouchDB URLs, and optionally the CouchDB login; the Pi joins the network, the rest is written to the --config file, and the endpoint restarts
+// into the slideshow. A signage-setup.toml on a USB stick, with the same keys as the config
+// file plus wifi_ssid and wifi_password, does the same without a phone. A stick is read at
+// every start as well, so one can also re-point a screen that is already installed; each file
+// is applied once. Wi-Fi goes through NetworkManager (nmcli), as on Raspberry Pi OS Bookworm.
+// Without --config there is nowhere to save the settings, so there is no setup mode either;
+// --setup-mode false turns it off.
+
+const WIFI_INTERFACE: &str = "wlan0";
+const HOTSPOT_CONNECTION: &str = "signage-setup";
+// What NetworkManager gives the Pi on a shared connection, if it can't be asked
+const DEFAULT_ADDRESS: &str = "10.42.0.1";
+const USB_FILE_NAME: &str = "signage-setup.toml";
+// Where sticks get mounted: /media/<user>/<label> by desktop automounters, /media/<label> or
+// /mnt/<label> by others
+const USB_ROOTS: [&str; 2] = ["/media", "/mnt"];
+// Beside the config file: the SHA-256 of the last stick file applied
+const APPLIED_MARKER: &str = ".signage-setup-applied";
+// How long a wired connection gets to come up after boot
+const ROUTE_WAIT: Duration = Duration::from_secs(30);
+const USB_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_FORM_BYTES: u64 = 16 * 1024;
+
+// Fields on the setup page, with their labels; the Wi-Fi pair isn't saved to the config file
+const FORM_FIELDS: [(&str, &str); 6] = [
+    ("wifi_ssid", "Wi-Fi network"),
+    ("wifi_password", "Wi-Fi password"),
+    ("mqtt_broker", "MQTT broker URL"),
+    ("couchdb_url", "CouchDB URL"),
+    ("couchdb_username", "CouchDB username (optional)"),
+    ("couchdb_password", "CouchDB password (optional)"),
+];
+
+const BACKGROUND: Rgba<u8> = Rgba([0, 48, 96, 255]);
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+// What to start as, from check()
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Start {
+    Normal,
+    // Settings were saved; restart to read them
+    Restart,
+    // Run run_portal()
+    Portal,
+}
+
+pub async fn check(config: Option<&Path>, command: &Command) -> Start {
+    let Some(config) = config else { return Start::Normal };
+    if let Some(stick) = find_usb_settings(config, &[]) {
+        match apply_usb_settings(&stick, config, command).await {
+            Ok(()) => return Start::Restart,
+            Err(e) => error!("❌ Failed to apply {}: {}", stick.path.display(), e),
+        }
+    }
+    if network_configured().await {
+        Start::Normal
+    } else {
+        Start::Portal
+    }
+}
+
+// What the screen shows in setup mode
+#[derive(Debug, Clone, PartialEq)]
+pub struct Screen {
+    lines: Vec<String>,
+    // Scanned with a phone camera, joins the hotspot
+    wifi_qr: Option<String>,
+}
+
+impl Screen {
+    fn message(text: String) -> Self {
+        Screen { lines: vec!["Setup mode".to_string(), text], wifi_qr: None }
+    }
+
+    pub fn render(&self, width: u32, height: u32, text_profile: &TextProfile) -> RgbaImage {
+        let background = text_profile.background(BACKGROUND);
+        let color = text_profile.foreground(WHITE, background);
+        let mut image = RgbaImage::from_pixel(width, height, background);
+
+        let char_size = text_profile.char_size(text_char_size(5, width, height));
+        let margin = 8 * char_size;
+        let max_chars_per_line = (width.saturating_sub(2 * margin) / (8 * char_size)).max(1) as usize;
+        let mut y = margin;
+        for line in &self.lines {
+            for wrapped in wrap_text(line, max_chars_per_line) {
+                draw_text(&mut image, &wrapped, margin, y, char_size, color);
+                y += 8 * char_size;
+            }
+            y += 4 * char_size;
+        }
+
+        if let Some(ref wifi_qr) = self.wifi_qr {
+            let overlay = QrOverlay { url: wifi_qr.clone(), corner: Corner::BottomRight };
+            overlay.draw(&mut image, (0, 0, width, height), &Orientation::Landscape);
+        }
+        image
+    }
+}
+
+// The hotspot's name and password; digits only, since the screen's font has no lower case
+struct Hotspot {
+    ssid: String,
+    password: String,
+}
+
+impl Hotspot {
+    fn new() -> Self {
+        Hotspot {
+            ssid: format!("SIGNAGE-SETUP-{:04}", fastrand::u32(..10_000)),
+            password: format!("{:08}", fastrand::u32(..100_000_000)),
+        }
+    }
+
+    // Up, returning the Pi's address on it
+    async fn start(&self) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        // One left over from a crash would otherwise be duplicated
+        let _ = nmcli(&["connection", "delete", HOTSPOT_CONNECTION]).await;
+        nmcli(&["device", "wifi", "hotspot", "ifname", WIFI_INTERFACE, "con-name", HOTSPOT_CONNECTION,
+                "ssid", &self.ssid, "password", &self.password]).await?;
+        // "10.42.0.1/24"
+        let address = nmcli(&["-g", "IP4.ADDRESS", "device", "show", WIFI_INTERFACE]).await
+            .ok()
+            .and_then(|output| output.lines().next().and_then(|line| line.split('/').next()).map(str::to_string))
+            .filter(|address| !address.is_empty())
+            .unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+        info!("📡 Setup hotspot {} up at {}", self.ssid, address);
+        Ok(address)
+    }
+
+    async fn stop(&self) {
+        if let Err(e) = nmcli(&["connection", "delete", HOTSPOT_CONNECTION]).await {
+            warn!("Failed to take down the setup hotspot: {}", e);
+        }
+    }
+
+    fn screen(&self, address: &str, port: u16, error: Option<&str>) -> Screen {
+        let mut lines = vec![
+            "Setup mode".to_string(),
+            format!("1. Join the Wi-Fi network {}", self.ssid),
+            format!("Password {} - or scan the code", self.password),
+            format!("2. Browse to {}:{}", address, port),
+            format!("Or insert a USB stick with {}", USB_FILE_NAME),
+        ];
+        if let Some(error) = error {
+            lines.push(format!("Last try failed: {}", error));
+        }
+        Screen { lines, wifi_qr: Some(format!("WIFI:T:WPA;S:{};P:{};;", self.ssid, self.password)) }
+    }
+}
+
+// The setup page, filled in with what was last entered
+struct Page {
+    values: HashMap<String, String>,
+    networks: Vec<String>,
+    error: Option<String>,
+}
+
+impl Page {
+    fn html(&self) -> String {
+        let mut fields = String::new();
+        for (name, label) in FORM_FIELDS {
+            let kind = if name.ends_with("password") { "password" } else { "text" };
+            // Passwords aren't sent back
+            let value = if kind == "password" { "" } else { self.values.get(name).map_or("", String::as_str) };
+            let list = if name == "wifi_ssid" { " list=\"networks\"" } else { "" };
+            fields.push_str(&format!(
+                "<label>{}<br><input type=\"{}\" name=\"{}\" value=\"{}\"{}></label><br>\n",
+                label, kind, name, escape(value), list
+            ));
+        }
+        let networks: String = self.networks.iter().map(|ssid| format!("<option value=\"{}\">", escape(ssid))).collect();
+        let error = self.error.as_ref().map_or(String::new(), |e| format!("<p><b>Last try failed:</b> {}</p>", escape(e)));
+        format!(
+            r#"<html>
+<head><title>Signage Setup</title><meta name="viewport" content="width=device-width, initial-scale=1"></head>
+<body>
+<h1>Digital Signage Setup</h1>
+{}
+<form method="post" action="/setup">
+{}
+<datalist id="networks">{}</datalist>
+<button type="submit">Save and connect</button>
+</form>
+</body>
+</html>
+"#,
+            error, fields, networks
+        )
+    }
+}
+
+// Run setup mode until settings have been saved, showing each screen through `show`; the caller
+// restarts afterwards. mqtt_broker and couchdb_url fill in the page to start with.
+pub async fn run_portal(port: u16, config: &Path, command: &Command, mqtt_broker: &str, couchdb_url: &str, mut show: impl FnMut(&Screen)) {
+    info!("🛠️ Starting setup mode");
+    // Setup takes as long as the installer does; systemd shouldn't hold that against the start
+    systemd::ready();
+    let values = HashMap::from([("mqtt_broker".to_string(), mqtt_broker.to_string()), ("couchdb_url".to_string(), couchdb_url.to_string())]);
+    let page = Arc::new(Mutex::new(Page { values, networks: Vec::new(), error: None }));
+    let (sender, mut receiver) = mpsc::channel(1);
+    serve_page(port, page.clone(), sender);
+
+    let hotspot = Hotspot::new();
+    // Stick files that already failed, so one left in isn't retried every few seconds
+    let mut tried = Vec::new();
+    loop {
+        // While the radio is free; as a hotspot it can't scan
+        let networks = scan_networks().await;
+        let error = {
+            let mut page = page.lock().unwrap();
+            page.networks = networks;
+            page.error.clone()
+        };
+        let hotspot_up = match hotspot.start().await {
+            Ok(address) => {
+                show(&hotspot.screen(&address, port, error.as_deref()));
+                true
+            }
+            Err(e) => {
+                error!("❌ Failed to start the setup hotspot: {}", e);
+                show(&Screen::message(format!("Insert a USB stick with {} (no Wi-Fi hotspot: {})", USB_FILE_NAME, e)));
+                false
+            }
+        };
+
+        let mut usb_check = tokio::time::interval(USB_POLL_INTERVAL);
+        let attempt = loop {
+            tokio::select! {
+                Some(settings) = receiver.recv() => break Ok(settings),
+                _ = usb_check.tick() => {
+                    systemd::watchdog();
+                    if let Some(stick) = find_usb_settings(config, &tried) {
+                        break Err(stick);
+                    }
+                }
+            }
+        };
+        if hotspot_up {
+            hotspot.stop().await;
+        }
+        show(&Screen::message("Applying settings...".to_string()));
+        let result = match attempt {
+            Ok(settings) => apply(settings, config, command).await,
+            Err(stick) => {
+                tried.push(stick.fingerprint.clone());
+                apply_usb_settings(&stick, config, command).await
+            }
+        };
+        match result {
+            Ok(()) => {
+                info!("✅ Setup saved to {}", config.display());
+                show(&Screen::message("Done - restarting".to_string()));
+                return;
+            }
+            Err(e) => {
+                error!("❌ Setup failed: {}", e);
+                page.lock().unwrap().error = Some(e.to_string());
+            }
+        }
+    }
+}
+
+fn serve_page(port: u16, page: Arc<Mutex<Page>>, sender: mpsc::Sender<toml::Table>) {
+    let form_page = page.clone();
+    let form = warp::get().map(move || warp::reply::html(form_page.lock().unwrap().html()));
+    let submit = warp::post()
+        .and(warp::path("setup"))
+        .and(warp::path::end())
+        .and(warp::body::content_length_limit(MAX_FORM_BYTES))
+        .and(warp::body::form::<HashMap<String, String>>())
+        .map(move |fields: HashMap<String, String>| {
+            let mut settings = toml::Table::new();
+            for (name, _) in FORM_FIELDS {
+                let Some(value) = fields.get(name).map(|value| value.trim()).filter(|value| !value.is_empty()) else { continue };
+                settings.insert(name.to_string(), toml::Value::String(value.to_string()));
+            }
+            page.lock().unwrap().values = fields;
+            let message = match sender.try_send(settings) {
+                Ok(()) => "Settings received. The hotspot goes down while the screen connects; watch the screen. \
+                           If it can't connect, the hotspot comes back with the reason on this page.",
+                Err(_) => "The screen is already applying settings; watch the screen.",
+            };
+            warp::reply::html(format!("<html><body><h1>Digital Signage Setup</h1><p>{}</p></body></html>", message))
+        });
+
+    match warp::serve(submit.or(form)).try_bind_ephemeral(([0, 0, 0, 0], port)) {
+        Ok((_, server)) => {
+            info!("Serving the setup page on port {}", port);
+            tokio::spawn(server);
+        }
+        Err(e) => error!("❌ Can't serve the setup page on port {}: {}", port, e),
+    }
+}
+
+// Join the Wi-Fi network in `settings`, if it names one, then save the rest to the config file
+async fn apply(settings: toml::Table, config: &Path, command: &Command) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut settings: toml::Table = settings.into_iter().map(|(key, value)| (key.replace('-', "_"), value)).collect();
+    let ssid = take_string(&mut settings, "wifi_ssid")?;
+    let password = take_string(&mut settings, "wifi_password")?;
+    for key in ["mqtt_broker", "couchdb_url"] {
+        if let Some(value) = settings.get(key).and_then(toml::Value::as_str) {
+            url::Url::parse(value).map_err(|e| format!("{} '{}' is not a URL: {}", key, value, e))?;
+        }
+    }
+    config_file::validate(command, &settings)?;
+
+    if let Some(ssid) = ssid {
+        info!("📶 Joining Wi-Fi network {}", ssid);
+        // connect only finds networks in the last scan
+        let _ = nmcli(&["device", "wifi", "rescan", "ifname", WIFI_INTERFACE]).await;
+        let mut args = vec!["device", "wifi", "connect", ssid.as_str()];
+        if let Some(ref password) = password {
+            args.extend(["password", password.as_str()]);
+        }
+        args.extend(["ifname", WIFI_INTERFACE]);
+        nmcli(&args).await.map_err(|e| format!("can't join {}: {}", ssid, e))?;
+    }
+    save(config, settings)
+}
+
+async fn apply_usb_settings(stick: &UsbSettings, config: &Path, command: &Command) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    info!("💾 Applying setup from {}", stick.path.display());
+    let settings: toml::Table = toml::from_str(&stick.text).map_err(|e| format!("invalid {}: {}", USB_FILE_NAME, e))?;
+    apply(settings, config, command).await?;
+    // Without the marker the stick would be applied, and the endpoint restarted, at every start
+    let marker = config.with_file_name(APPLIED_MARKER);
+    std::fs::write(&marker, &stick.fingerprint).map_err(|e| format!("can't write {}: {}", marker.display(), e))?;
+    Ok(())
+}
+
+// An optional string setting, with empty meaning unset
+fn take_string(settings: &mut toml::Table, key: &str) -> Result<Option<String>, String> {
+    match settings.remove(key) {
+        None => Ok(None),
+        Some(toml::Value::String(value)) => Ok(Some(value).filter(|value| !value.is_empty())),
+        Some(other) => Err(format!("{} must be a string, not {}", key, other.type_str())),
+    }
+}
+
+// Merge `settings` into the config file. It is replaced in one rename, so a power cut can't
+// leave half a file, and readable only by the service user since it can hold a password.
+fn save(path: &Path, settings: toml::Table) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut file: toml::Table = match std::fs::read_to_string(path) {
+        Ok(text) => toml::from_str(&text).map_err(|e| format!("invalid config file {}: {}", path.display(), e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => toml::Table::new(),
+        Err(e) => return Err(format!("can't read config file {}: {}", path.display(), e).into()),
+    };
+    for (key, value) in settings {
+        // The file may have the key the command line way
+        file.remove(&key.replace('_', "-"));
+        info!("⚙️ Saving {}", key);
+        file.insert(key, value);
+    }
+
+    let temp = path.with_extension("toml.tmp");
+    std::fs::write(&temp, toml::to_string(&file)?)?;
+    std::fs::set_permissions(&temp, std::fs::Permissions::from_mode(0o600))?;
+    std::fs::rename(&temp, path).map_err(|e| format!("can't replace {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+struct UsbSettings {
+    path: PathBuf,
+    text: String,
+    fingerprint: String,
+}
+
+// A stick file that hasn't been applied yet and isn't in `tried`
+fn find_usb_settings(config: &Path, tried: &[String]) -> Option<UsbSettings> {
+    let applied = std::fs::read_to_string(config.with_file_name(APPLIED_MARKER)).unwrap_or_default();
+    let mut candidates = Vec::new();
+    for root in USB_ROOTS {
+        let Ok(entries) = std::fs::read_dir(root) else { continue };
+        for dir in entries.flatten().map(|entry| entry.path()).filter(|path| path.is_dir()) {
+            candidates.push(dir.join(USB_FILE_NAME));
+            if let Ok(inner) = std::fs::read_dir(&dir) {
+                candidates.extend(inner.flatten().map(|entry| entry.path().join(USB_FILE_NAME)));
+            }
+        }
+    }
+    candidates.into_iter().find_map(|path| {
+        let text = std::fs::read_to_string(&path).ok()?;
+        let fingerprint = digest::digest(&digest::SHA256, text.as_bytes()).as_ref().iter().map(|byte| format!("{:02x}", byte)).collect::<String>();
+        (fingerprint != applied.trim() && !tried.contains(&fingerprint)).then_some(UsbSettings { path, text, fingerprint })
+    })
+}
+
+// Whether the Pi already has somewhere to connect: a saved Wi-Fi network, in range or not (a
+// screen shouldn't turn into a hotspot because its router is off), or a wired connection that
+// gets a default route
+async fn network_configured() -> bool {
+    if has_saved_wifi().await {
+        return true;
+    }
+    let deadline = Instant::now() + ROUTE_WAIT;
+    while network::default_route_interface().is_none() {
+        if Instant::now() >= deadline {
+            info!("No saved Wi-Fi network and no route after {} seconds", ROUTE_WAIT.as_secs());
+            return false;
+        }
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+    true
+}
+
+async fn has_saved_wifi() -> bool {
+    // NetworkManager profiles, as Raspberry Pi Imager and raspi-config write them on Bookworm;
+    // "name:type" with colons in the name escaped
+    if let Ok(output) = nmcli(&["-t", "-f", "NAME,TYPE", "connection", "show"]).await {
+        if output.lines()
+            .filter_map(|line| line.rsplit_once(':'))
+            .any(|(name, kind)| kind == "802-11-wireless" && name != HOTSPOT_CONNECTION)
+        {
+            return true;
+        }
+    }
+    // wpa_supplicant on earlier releases
+    std::fs::read_to_string("/etc/wpa_supplicant/wpa_supplicant.conf").is_ok_and(|conf| conf.contains("network={"))
+}
+
+// Networks in range, strongest first, for the page to suggest
+async fn scan_networks() -> Vec<String> {
+    let output = match nmcli(&["-t", "-f", "SSID", "device", "wifi", "list", "ifname", WIFI_INTERFACE, "--rescan", "yes"]).await {
+        Ok(output) => output,
+        Err(e) => {
+            warn!("Failed to scan for Wi-Fi networks: {}", e);
+            return Vec::new();
+        }
+    };
+    let mut networks: Vec<String> = Vec::new();
+    for ssid in output.lines().map(|line| line.replace("\\:", ":")) {
+        if !ssid.is_empty() && !networks.contains(&ssid) {
+            networks.push(ssid);
+        }
+    }
+    networks
+}
+
+async fn nmcli(args: &[&str]) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let output = tokio::time::timeout(COMMAND_TIMEOUT, Process::new("nmcli").args(args).kill_on_drop(true).output())
+        .await
+        .map_err(|_| format!("nmcli took over {} seconds", COMMAND_TIMEOUT.as_secs()))?
+        .map_err(|e| format!("can't run nmcli: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("nmcli failed: {}", String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,102 @@
+use image::{Rgba, RgbaImage};
+use qrcode::{Color, QrCode};
+use tracing::warn;
+
+use crate::Orientation;
+
+// A QR code drawn into a corner of a slide, for the link an image's metadata carries in qr_url
+// (a menu, a promotion, event details), so the artwork doesn't need one baked in. It goes on
+// the slide when it is scaled for the screen, so it is part of the frame the cache keeps and
+// comes along through transitions.
+
+// Side of the code, quiet zone included, as a share of the shorter side of the safe area
+const SIZE: f32 = 0.18;
+// Gap between the code and the edges of the safe area, as a share of its shorter side
+const MARGIN: f32 = 0.02;
+// Light modules scanners need around the code, on each side
+const QUIET_ZONE: u32 = 4;
+
+const DARK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const LIGHT: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    // Bottom right unless the metadata names another corner
+    pub fn from_name(name: Option<&str>) -> Self {
+        match name {
+            Some("top_left") => Corner::TopLeft,
+            Some("top_right") => Corner::TopRight,
+            Some("bottom_left") => Corner::BottomLeft,
+            _ => Corner::BottomRight,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QrOverlay {
+    pub url: String,
+    pub corner: Corner,
+}
+
+impl QrOverlay {
+    // Draw the code on `frame`, a slide already rotated for the framebuffer, in the viewer's
+    // corner of the safe area `(x, y, width, height)`. A link too long to encode is left out.
+    pub fn draw(&self, frame: &mut RgbaImage, (x, y, width, height): (u32, u32, u32, u32), orientation: &Orientation) {
+        let code = match QrCode::new(self.url.as_bytes()) {
+            Ok(code) => code,
+            Err(e) => {
+                warn!("Can't draw a QR code for {}: {}", self.url, e);
+                return;
+            }
+        };
+        let modules = code.width() as u32;
+        let shorter = width.min(height) as f32;
+        let module_size = ((shorter * SIZE) as u32 / (modules + 2 * QUIET_ZONE)).max(1);
+        let side = module_size * (modules + 2 * QUIET_ZONE);
+        let margin = (shorter * MARGIN) as u32;
+        if side + 2 * margin > width.min(height) {
+            return;
+        }
+
+        let mut tile = RgbaImage::from_pixel(side, side, LIGHT);
+        for module_y in 0..modules {
+            for module_x in 0..modules {
+                if code[(module_x as usize, module_y as usize)] != Color::Dark {
+                    continue;
+                }
+                let left = (QUIET_ZONE + module_x) * module_size;
+                let top = (QUIET_ZONE + module_y) * module_size;
+                for py in top..top + module_size {
+                    for px in left..left + module_size {
+                        tile.put_pixel(px, py, DARK);
+                    }
+                }
+            }
+        }
+
+        // Where the corner is for someone looking at the screen, then where that is on the
+        // framebuffer once the picture is turned
+        let (upright_width, upright_height) = orientation.upright_size(width, height);
+        let far = |extent: u32| extent - side - margin;
+        let (upright_x, upright_y) = match self.corner {
+            Corner::TopLeft => (margin, margin),
+            Corner::TopRight => (far(upright_width), margin),
+            Corner::BottomLeft => (margin, far(upright_height)),
+            Corner::BottomRight => (far(upright_width), far(upright_height)),
+        };
+        let (offset_x, offset_y) = match orientation {
+            Orientation::Landscape => (upright_x, upright_y),
+            Orientation::InvertedLandscape => (width - upright_x - side, height - upright_y - side),
+            Orientation::Portrait => (width - upright_y - side, upright_x),
+            Orientation::InvertedPortrait => (upright_y, height - upright_x - side),
+        };
+        image::imageops::replace(frame, &orientation.rotate_image(&tile), (x + offset_x) as i64, (y + offset_y) as i64);
+    }
+}
@@ -0,0 +1,125 @@
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::thread;
+use std::time::Duration;
+use sysinfo::{System, SystemExt};
+use tracing::{error, info};
+
+use crate::health;
+
+// Adaptive quality (--adaptive-quality): a Pi that is hot or overloaded throttles, and full-rate
+// transitions then stutter and heat it further. A monitor thread samples the CPU temperature and
+// load average, and as they climb transitions step down to a lower frame rate, then to plain
+// fades, then to straight cuts. The current mode is reported in the TV's status. Separately,
+// while the firmware reports under-voltage or throttling (throttle.rs), transitions are kept to
+// fades at most, whether or not adaptive quality is on.
+
+// Temperatures (°C) and per-core 1-minute load averages at which each lower mode starts. The
+// Pi's firmware throttles the CPU at 80°C.
+const TEMPERATURE_STEPS: [f32; 3] = [70.0, 75.0, 80.0];
+const LOAD_STEPS: [f32; 3] = [1.5, 2.0, 3.0];
+// How far below a step readings must fall before quality comes back, so a reading hovering
+// around a threshold doesn't flip the mode every sample
+const TEMPERATURE_HYSTERESIS: f32 = 5.0;
+const LOAD_HYSTERESIS: f32 = 0.5;
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+// Frames are drawn this many times further apart in the reduced frame rate mode
+const REDUCED_FRAME_RATE_DIVISOR: u32 = 2;
+
+static MODE: AtomicU8 = AtomicU8::new(Mode::Full as u8);
+static THROTTLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Mode {
+    Full,
+    ReducedFrameRate,
+    FadesOnly,
+    NoTransitions,
+}
+
+impl Mode {
+    const ALL: [Mode; 4] = [Mode::Full, Mode::ReducedFrameRate, Mode::FadesOnly, Mode::NoTransitions];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Full => "full",
+            Mode::ReducedFrameRate => "reduced_frame_rate",
+            Mode::FadesOnly => "fades_only",
+            Mode::NoTransitions => "no_transitions",
+        }
+    }
+
+    // Multiple of the usual frame interval transitions are paced at
+    pub fn frame_interval_multiplier(self) -> u32 {
+        if self >= Mode::ReducedFrameRate { REDUCED_FRAME_RATE_DIVISOR } else { 1 }
+    }
+}
+
+// Start monitoring; without it transitions always play in full
+pub fn configure(enabled: bool) {
+    if !enabled {
+        return;
+    }
+    let spawned = thread::Builder::new().name("quality-monitor".to_string()).spawn(|| {
+        let system = System::new();
+        let cores = thread::available_parallelism().map_or(1, |cores| cores.get()) as f32;
+        loop {
+            let load_per_core = system.load_average().one as f32 / cores;
+            update(health::cpu_temperature(), load_per_core);
+            thread::sleep(SAMPLE_INTERVAL);
+        }
+    });
+    if let Err(e) = spawned {
+        error!("Failed to start the adaptive quality monitor: {}", e);
+    }
+}
+
+pub fn mode() -> Mode {
+    let adaptive = adaptive_mode();
+    if THROTTLED.load(Ordering::Relaxed) { adaptive.max(Mode::FadesOnly) } else { adaptive }
+}
+
+fn adaptive_mode() -> Mode {
+    Mode::ALL[MODE.load(Ordering::Relaxed) as usize]
+}
+
+pub fn set_throttled(throttled: bool) {
+    if THROTTLED.swap(throttled, Ordering::Relaxed) != throttled {
+        if throttled {
+            info!("Transition quality held at {} or lower while the Pi is throttled", Mode::FadesOnly.name());
+        } else {
+            info!("Pi no longer throttled, transition quality back to {}", mode().name());
+        }
+    }
+}
+
+fn update(temperature: Option<f32>, load_per_core: f32) {
+    let current = adaptive_mode();
+    let next = next_mode(current, temperature, load_per_core);
+    if next != current {
+        info!(
+            "Transition quality {} -> {} ({} CPU, load {:.2} per core)",
+            current.name(),
+            next.name(),
+            temperature.map_or("unknown".to_string(), |t| format!("{:.1}°C", t)),
+            load_per_core
+        );
+        MODE.store(next as u8, Ordering::Relaxed);
+    }
+}
+
+// Quality drops as soon as a step is crossed, but only recovers once readings are clear of it
+fn next_mode(current: Mode, temperature: Option<f32>, load_per_core: f32) -> Mode {
+    let rising = level(temperature, load_per_core, 0.0, 0.0);
+    if rising >= current {
+        rising
+    } else {
+        level(temperature, load_per_core, TEMPERATURE_HYSTERESIS, LOAD_HYSTERESIS).min(current)
+    }
+}
+
+// The mode for the steps the readings are within `margin` of or past
+fn level(temperature: Option<f32>, load_per_core: f32, temperature_margin: f32, load_margin: f32) -> Mode {
+    let crossed = |value: f32, steps: &[f32; 3], margin: f32| steps.iter().filter(|&&step| value >= step - margin).count();
+    let temperature_level = temperature.map_or(0, |t| crossed(t, &TEMPERATURE_STEPS, temperature_margin));
+    Mode::ALL[temperature_level.max(crossed(load_per_core, &LOAD_STEPS, load_margin))]
+}
@@ -0,0 +1,78 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+// Token bucket for a single HTTP route: `burst` requests can arrive back to back,
+// after which requests are admitted at `per_second`. At most `max_in_flight` of them
+// are handled at once; one more is turned away rather than queued.
+pub struct RateLimiter {
+    burst: f64,
+    per_second: f64,
+    bucket: Mutex<Bucket>,
+    max_in_flight: usize,
+    in_flight: Arc<Semaphore>,
+    accepted: AtomicU64,
+    rejected: AtomicU64,
+    busy: AtomicU64,
+}
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(per_second: f64, burst: u32, max_in_flight: usize) -> Self {
+        Self {
+            burst: burst as f64,
+            per_second,
+            bucket: Mutex::new(Bucket {
+                tokens: burst as f64,
+                last_refill: Instant::now(),
+            }),
+            max_in_flight,
+            in_flight: Arc::new(Semaphore::new(max_in_flight)),
+            accepted: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+            busy: AtomicU64::new(0),
+        }
+    }
+
+    // A slot for one request, held until the permit is dropped; None when all are taken
+    pub fn try_enter(&self) -> Option<OwnedSemaphorePermit> {
+        let permit = self.in_flight.clone().try_acquire_owned().ok();
+        if permit.is_none() {
+            self.busy.fetch_add(1, Ordering::Relaxed);
+        }
+        permit
+    }
+
+    pub fn try_acquire(&self) -> bool {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let refill = now.duration_since(bucket.last_refill).as_secs_f64() * self.per_second;
+        bucket.tokens = (bucket.tokens + refill).min(self.burst);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            self.accepted.fetch_add(1, Ordering::Relaxed);
+            true
+        } else {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            false
+        }
+    }
+
+    pub fn snapshot(&self) -> serde_json::Value {
+        serde_json::json!({
+            "limit_per_second": self.per_second,
+            "burst": self.burst as u32,
+            "max_in_flight": self.max_in_flight,
+            "accepted": self.accepted.load(Ordering::Relaxed),
+            "rate_limited": self.rejected.load(Ordering::Relaxed),
+            "busy": self.busy.load(Ordering::Relaxed),
+        })
+    }
+}
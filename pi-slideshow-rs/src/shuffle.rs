@@ -0,0 +1,160 @@
+// Play order for shuffle mode (TvConfig shuffle): every image once, in random order, before
+// any repeats, then a fresh order for the next cycle. The order already played is remembered,
+// so previous steps back through what was actually on screen rather than to the image before
+// it in the playlist.
+
+#[derive(Debug, Default)]
+pub struct ShuffleOrder {
+    // Playlist indices in play order: the cycles played so far (up to one kept for previous)
+    // and, once the last image of a cycle is up, the next cycle
+    order: Vec<usize>,
+    // Where the image on screen is in `order`
+    position: usize,
+    // Playlist length the order was drawn for
+    len: usize,
+}
+
+impl ShuffleOrder {
+    // The image after `current` in a playlist of `len`, moving to it
+    pub fn next(&mut self, current: usize, len: usize) -> usize {
+        if len <= 1 {
+            return 0;
+        }
+        self.sync(current, len);
+        self.extend();
+        self.position += 1;
+        self.order[self.position]
+    }
+
+    // The image `next` would move to, without moving
+    pub fn peek(&mut self, current: usize, len: usize) -> usize {
+        if len <= 1 {
+            return 0;
+        }
+        self.sync(current, len);
+        self.extend();
+        self.order[self.position + 1]
+    }
+
+    // The image shown before `current`; before the start of what's remembered it falls back to
+    // the one before it in the playlist, and a new order starts from there
+    pub fn previous(&mut self, current: usize, len: usize) -> usize {
+        if len <= 1 {
+            return 0;
+        }
+        self.sync(current, len);
+        if self.position == 0 {
+            return (current + len - 1) % len;
+        }
+        self.position -= 1;
+        self.order[self.position]
+    }
+
+    // Start a new cycle from `current` when the playlist changed or the index was moved some
+    // other way (a schedule switch, a quarantined image, a restored playback state)
+    fn sync(&mut self, current: usize, len: usize) {
+        if self.len == len && self.order.get(self.position) == Some(&current) {
+            return;
+        }
+        let current = current.min(len - 1);
+        let mut rest: Vec<usize> = (0..len).filter(|&index| index != current).collect();
+        fastrand::shuffle(&mut rest);
+        self.order = std::iter::once(current).chain(rest).collect();
+        self.position = 0;
+        self.len = len;
+    }
+
+    // Make sure an image follows the one on screen, drawing the next cycle when this one is
+    // used up. A new cycle never opens with the image that closed the last one.
+    fn extend(&mut self) {
+        if self.position + 1 < self.order.len() {
+            return;
+        }
+        let mut cycle: Vec<usize> = (0..self.len).collect();
+        fastrand::shuffle(&mut cycle);
+        if cycle[0] == self.order[self.position] {
+            cycle.swap(0, self.len - 1);
+        }
+        self.order.extend(cycle);
+
+        // Keep the cycle just finished for previous and drop anything older
+        let keep_from = self.order.len().saturating_sub(2 * self.len);
+        self.order.drain(..keep_from);
+        self.position -= keep_from;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    // `count` steps of next from `current`, as shown
+    fn play(order: &mut ShuffleOrder, mut current: usize, len: usize, count: usize) -> Vec<usize> {
+        (0..count).map(|_| { current = order.next(current, len); current }).collect()
+    }
+
+    #[test]
+    fn every_image_plays_once_per_cycle() {
+        for _ in 0..50 {
+            let mut order = ShuffleOrder::default();
+            // The first cycle starts from the image on screen
+            let first = play(&mut order, 3, 7, 6);
+            assert_eq!(first.iter().chain([&3]).collect::<HashSet<_>>().len(), 7, "{:?}", first);
+            let current = first[5];
+            for cycle in play(&mut order, current, 7, 7 * 4).chunks(7) {
+                assert_eq!(cycle.iter().collect::<HashSet<_>>().len(), 7, "{:?}", cycle);
+            }
+        }
+    }
+
+    #[test]
+    fn no_image_plays_twice_in_a_row() {
+        for len in 2..6 {
+            let mut order = ShuffleOrder::default();
+            let shown = play(&mut order, 0, len, 200);
+            assert!(std::iter::once(&0).chain(&shown).zip(&shown).all(|(a, b)| a != b), "{:?}", shown);
+        }
+    }
+
+    #[test]
+    fn previous_steps_back_through_what_was_shown() {
+        let mut order = ShuffleOrder::default();
+        let shown = play(&mut order, 0, 5, 8);
+        let mut current = shown[7];
+        for expected in shown[..7].iter().rev() {
+            current = order.previous(current, 5);
+            assert_eq!(current, *expected);
+        }
+        // The next steps forward again along the same order
+        assert_eq!(order.next(current, 5), shown[1]);
+    }
+
+    #[test]
+    fn previous_at_the_start_falls_back_to_the_playlist() {
+        let mut order = ShuffleOrder::default();
+        assert_eq!(order.previous(3, 5), 2);
+        assert_eq!(order.previous(0, 5), 4);
+    }
+
+    #[test]
+    fn a_changed_playlist_starts_a_new_cycle() {
+        let mut order = ShuffleOrder::default();
+        let shown = play(&mut order, 0, 5, 2);
+        // Grown mid-cycle: every image of the new playlist comes up before any repeat
+        let grown = play(&mut order, shown[1], 8, 7);
+        assert_eq!(grown.iter().chain([&shown[1]]).collect::<HashSet<_>>().len(), 8, "{:?}", grown);
+        // Shrunk below the image on screen: only images that still exist come up
+        let shrunk = play(&mut order, 7, 3, 10);
+        assert!(shrunk.iter().all(|&index| index < 3), "{:?}", shrunk);
+    }
+
+    #[test]
+    fn single_images_stay_put() {
+        let mut order = ShuffleOrder::default();
+        assert_eq!(order.next(0, 1), 0);
+        assert_eq!(order.peek(0, 1), 0);
+        assert_eq!(order.previous(0, 1), 0);
+        assert_eq!(order.next(0, 0), 0);
+    }
+}
@@ -0,0 +1,69 @@
+// The two loops that dominate CPU time during a transition: blending the outgoing and
+// incoming frames, and swizzling each frame's RGBA bytes into the framebuffer's BGRA. With the
+// `simd` feature on aarch64 (a Pi 3, 4 or 5 running a 64-bit OS) both use NEON, 16 bytes at a
+// time. Elsewhere, including 32-bit ARM, whose NEON intrinsics aren't in stable Rust yet, the
+// scalar versions run.
+
+// `from` blended `t` (0.0 to 1.0) of the way to `to` into `out`; all three are RGBA bytes of
+// the same length. The NEON version works in 8-bit fixed point and can land one step below
+// the scalar one.
+pub fn blend(from: &[u8], to: &[u8], t: f32, out: &mut [u8]) {
+    #[cfg(all(feature = "simd", target_arch = "aarch64"))]
+    let done = neon::blend(from, to, (t.clamp(0.0, 1.0) * 256.0) as u16, out);
+    #[cfg(not(all(feature = "simd", target_arch = "aarch64")))]
+    let done = 0;
+
+    for ((out, &from), &to) in out[done..].iter_mut().zip(&from[done..]).zip(&to[done..]) {
+        *out = crate::transitions::lerp_channel(from, to, t);
+    }
+}
+
+// RGBA bytes as BGRA into `out`, which is the same length
+pub fn rgba_to_bgra(rgba: &[u8], out: &mut [u8]) {
+    #[cfg(all(feature = "simd", target_arch = "aarch64"))]
+    let done = neon::rgba_to_bgra(rgba, out);
+    #[cfg(not(all(feature = "simd", target_arch = "aarch64")))]
+    let done = 0;
+
+    for (out, rgba) in out[done..].chunks_exact_mut(4).zip(rgba[done..].chunks_exact(4)) {
+        out.copy_from_slice(&[rgba[2], rgba[1], rgba[0], rgba[3]]);
+    }
+}
+
+// Each returns how many bytes it handled, a whole number of blocks; the callers
+// finish the rest
+#[cfg(all(feature = "simd", target_arch = "aarch64"))]
+mod neon {
+    use std::arch::aarch64::*;
+
+    // `weight` is 0 to 256, how much of `to` to take
+    pub fn blend(from: &[u8], to: &[u8], weight: u16, out: &mut [u8]) -> usize {
+        let len = from.len().min(to.len()).min(out.len()) / 16 * 16;
+        // NEON is always present on aarch64, and every load and store is inside `len`
+        unsafe {
+            let to_weight = vdupq_n_u16(weight);
+            let from_weight = vdupq_n_u16(256 - weight);
+            for i in (0..len).step_by(16) {
+                let a = vld1q_u8(from.as_ptr().add(i));
+                let b = vld1q_u8(to.as_ptr().add(i));
+                // Widened to 16 bits: 255 * 256 still fits
+                let low = vmlaq_u16(vmulq_u16(vmovl_u8(vget_low_u8(a)), from_weight), vmovl_u8(vget_low_u8(b)), to_weight);
+                let high = vmlaq_u16(vmulq_u16(vmovl_high_u8(a), from_weight), vmovl_high_u8(b), to_weight);
+                vst1q_u8(out.as_mut_ptr().add(i), vcombine_u8(vshrn_n_u16::<8>(low), vshrn_n_u16::<8>(high)));
+            }
+        }
+        len
+    }
+
+    pub fn rgba_to_bgra(rgba: &[u8], out: &mut [u8]) -> usize {
+        let len = rgba.len().min(out.len()) / 64 * 64;
+        // As above; vld4q splits 16 pixels into one register per channel
+        unsafe {
+            for i in (0..len).step_by(64) {
+                let pixels = vld4q_u8(rgba.as_ptr().add(i));
+                vst4q_u8(out.as_mut_ptr().add(i), uint8x16x4_t(pixels.2, pixels.1, pixels.0, pixels.3));
+            }
+        }
+        len
+    }
+}
@@ -1,9 +1,76 @@
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::{broadcast, mpsc, RwLock};
-use crate::mqtt_client::{ImageInfo, MqttClient, SlideshowCommand, SlideshowConfig, TvStatus};
+use tokio::sync::{broadcast, mpsc, Mutex, OwnedMutexGuard, RwLock};
+use tracing::{error, info, instrument, warn};
+use crate::mqtt_client::{MqttClient, SlideshowCommand};
+use signage_protocol::{CouchSchedule, DimmingWindow, ImageInfo, QuietWindow, Release, SafeArea, SlideshowConfig, TextSlide, ThrottleFlags, TransitionStats, TvConfig, TvStatus};
+use signage_protocol::mqtt::is_safe_file_component;
 use crate::couchdb_client::CouchDbClient;
+use crate::backlight::Backlight;
+use crate::cec;
+use crate::clock;
+use crate::color;
+use crate::config_file;
+use crate::crash;
+use crate::health;
+use crate::motion;
+use crate::perf;
+use crate::power;
+use crate::quality;
+use crate::content_safety;
+use crate::takeover::{self, Takeover};
+use crate::test_pattern::TestPattern;
+use crate::image_cache::ImageCache;
+use crate::image_store::ImageStore;
+use crate::shuffle::ShuffleOrder;
+use crate::weighted::WeightedOrder;
+use crate::ticker;
+use crate::web_page;
+use crate::text_slide;
+use crate::text_profile::TextProfile;
+
+// A text slide with the screen size and text profile its file was drawn for
+type DrawnTextSlide = (TextSlide, (u32, u32), String);
+
+// Written on restart so the next process resumes on the same image
+const PLAYBACK_STATE_FILE: &str = ".slideshow_state.json";
+// How long a restart waits for in-flight downloads before giving up on them
+const RESTART_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+// Under image_dir; holds the letterbox background image, out of the slideshow's way
+const LETTERBOX_DIR: &str = ".letterbox";
+// Under image_dir; holds the curtain image
+const CURTAIN_DIR: &str = ".curtain";
+// Under image_dir; images that failed to decode are moved here and not downloaded again
+// while they are in it, unless their content has changed since
+pub const QUARANTINE_DIR: &str = ".quarantine";
+// Added to a quarantined file's name for the file holding the revision it was quarantined at
+pub const QUARANTINE_REVISION_SUFFIX: &str = ".revision";
+// Under image_dir; copies of takeover images that aren't assigned to the TV
+const TAKEOVER_DIR: &str = ".takeover";
+// A test pattern left up this long gives the screen back to the slideshow
+const TEST_PATTERN_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+// Emergency content on display, what it was sent as, and when it comes down by itself
+struct ActiveTakeover {
+    screen: takeover::Screen,
+    content: Takeover,
+    until: Option<Instant>,
+}
+
+// The feed behind TvConfig ticker_rss_url and its latest headlines
+struct TickerFeed {
+    url: String,
+    headlines: Option<String>,
+}
+
+impl ActiveTakeover {
+    fn is_expired(&self) -> bool {
+        self.until.is_some_and(|until| Instant::now() >= until)
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum SlideshowState {
@@ -12,10 +79,50 @@ pub enum SlideshowState {
     Stopped,
 }
 
+// How an image asks, in its metadata, to be drawn differently from the TV's defaults
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlideStyle {
+    pub letterbox_fill: Option<String>,
+    pub fit_mode: Option<String>,
+    pub qr_url: Option<String>,
+    pub qr_corner: Option<String>,
+}
+
+// What covers the screen while the curtain is drawn: the curtain image once downloaded,
+// otherwise the "#RRGGBB" color
+#[derive(Debug, Clone, PartialEq)]
+pub struct Curtain {
+    pub color: String,
+    pub image: Option<PathBuf>,
+}
+
+// A curtain command, and what the curtain hours said when it arrived
+struct CurtainOverride {
+    closed: bool,
+    until: Option<Instant>,
+    scheduled: bool,
+}
+
+// Held from the moment a restart is requested until the process is replaced,
+// keeping the download lock so no new sync starts writing files in between
+struct PendingRestart {
+    _download_guard: Option<OwnedMutexGuard<()>>,
+}
+
+// Level the dimming schedule last applied, and whether the backlight is carrying it
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AppliedBrightness {
+    level: u8,
+    backlight: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct ControllerConfig {
     pub image_dir: PathBuf,
     pub display_duration: Duration,
+    // Floor under display_duration from any source; set for e-paper, whose refreshes are slow
+    // and flash, so the management system's TV-oriented durations don't apply
+    pub min_display_duration: Duration,
     pub transition_duration: Duration,
     pub couchdb_url: String,
     pub couchdb_username: Option<String>,
@@ -23,17 +130,99 @@ pub struct ControllerConfig {
     pub tv_id: String,
     pub orientation: String,
     pub transition_effect: String,
+    pub content_safety: bool,
+    pub text_profile: String,
+    pub framebuffer_refresh_secs: u64,
+    pub skip_redundant_writes: bool,
+    pub gamma: f64,
+    pub color_temperature: u32,
+    pub brightness: u8,
+    pub dimming_schedule: Vec<DimmingWindow>,
+    pub quiet_hours: Vec<QuietWindow>,
+    pub curtain_hours: Vec<QuietWindow>,
+    pub curtain_color: String,
+    // Image document id shown in place of the color while the curtain is drawn
+    pub curtain_image: Option<String>,
+    pub letterbox_fill: String,
+    pub letterbox_color: String,
+    // Image document id of the background for the "image" fill
+    pub letterbox_image: Option<String>,
+    pub safe_area: SafeArea,
+    // Zoom or pan over stills (ken_burns::Settings)
+    pub ken_burns: String,
+    pub ken_burns_intensity: f64,
+    // Content-aware cropping for the cover fit mode
+    pub smart_crop: bool,
+    // Timing curve overriding the effects' own (easing::CubicBezier)
+    pub transition_easing: String,
+    // Per-effect transition lengths in milliseconds, by effect name
+    pub transition_durations: HashMap<String, u64>,
+    // Random play order, each image once per cycle (shuffle::ShuffleOrder)
+    pub shuffle: bool,
+    // The TV document's shuffle as last synced; a shuffle command holds until that changes
+    pub synced_shuffle: Option<bool>,
+    // RSS or Atom feed whose headlines scroll along the bottom (ticker::Ticker)
+    pub ticker_rss_url: Option<String>,
+    // Groups whose images and text slides play here too
+    pub tags: Vec<String>,
+    // Scaled frames kept for redisplay (--image-cache-size)
+    pub image_cache_size: usize,
+    // Scaled frames kept on disk, 0 for none (--frame-cache-mb)
+    pub frame_cache_max_bytes: u64,
+    // Cap on the image directory, 0 for none (--image-dir-max-mb)
+    pub image_dir_max_bytes: u64,
 }
 
 pub struct SlideshowController {
     config: Arc<RwLock<ControllerConfig>>,
     state: Arc<RwLock<SlideshowState>>,
     pub current_index: Arc<RwLock<usize>>,
+    // The playlist: the assigned images the active daypart schedule selects
     images: Arc<RwLock<Vec<ImageInfo>>>,
+    // Every image assigned to the TV, which `images` is picked from
+    assigned_images: Arc<RwLock<Vec<ImageInfo>>>,
+    schedules: Arc<RwLock<Vec<CouchSchedule>>>,
+    active_schedule: Arc<RwLock<Option<CouchSchedule>>>,
+    // Assigned images left out for being outside their validity window
+    held_back: Arc<RwLock<HashSet<String>>>,
+    shuffle_order: Arc<Mutex<ShuffleOrder>>,
+    weighted_order: Arc<Mutex<WeightedOrder>>,
+    // Proof of play: times each image id has come up since start
+    play_counts: Arc<RwLock<HashMap<String, u64>>>,
     command_receiver: broadcast::Receiver<SlideshowCommand>,
     status_sender: mpsc::Sender<TvStatus>,
     mqtt_client: Arc<RwLock<Option<MqttClient>>>,
     couchdb_client: Arc<RwLock<Option<CouchDbClient>>>,
+    transition_preview: Arc<RwLock<Option<String>>>,
+    download_lock: Arc<Mutex<()>>,
+    pending_restart: Arc<RwLock<Option<PendingRestart>>>,
+    resume_at: Arc<RwLock<Option<Instant>>>,
+    // Rotation stays on the current slide until a resume command, whether playing or paused
+    held: Arc<RwLock<bool>>,
+    // Set by a stop command: the screen stays blank until a start command, whatever the image
+    // list does in the meantime
+    stopped: Arc<RwLock<bool>>,
+    // Seconds extend commands have added to (or taken off) the current slide's display time,
+    // and the id of the slide they apply to
+    slide_extension: Arc<RwLock<Option<(String, i64)>>>,
+    backlight: Option<Backlight>,
+    brightness: Arc<RwLock<Option<AppliedBrightness>>>,
+    // Keeps the screen on through quiet hours until this instant
+    wake_until: Arc<RwLock<Option<Instant>>>,
+    // Curtain drawn or lifted by command, over the curtain hours
+    curtain_override: Arc<RwLock<Option<CurtainOverride>>>,
+    // Calibration screen on display, and when it was put up
+    test_pattern: Arc<RwLock<Option<(TestPattern, Instant)>>>,
+    takeover: Arc<RwLock<Option<ActiveTakeover>>>,
+    // What each text slide's file was last drawn from (render_text_slide)
+    rendered_text_slides: Arc<Mutex<HashMap<PathBuf, DrawnTextSlide>>>,
+    // Ticker text from the ticker topic, shown in place of the feed's headlines
+    ticker_text: Arc<RwLock<Option<String>>>,
+    ticker_feed: Arc<RwLock<Option<TickerFeed>>>,
+    last_transition: Arc<RwLock<Option<TransitionStats>>>,
+    image_cache: ImageCache,
+    // Evicts unassigned files when the image directory is over its cap
+    image_store: Arc<Mutex<ImageStore>>,
     pub start_time: Instant,
 }
 
@@ -44,10 +233,36 @@ impl Clone for SlideshowController {
             state: self.state.clone(),
             current_index: self.current_index.clone(),
             images: self.images.clone(),
+            assigned_images: self.assigned_images.clone(),
+            schedules: self.schedules.clone(),
+            active_schedule: self.active_schedule.clone(),
+            held_back: self.held_back.clone(),
+            shuffle_order: self.shuffle_order.clone(),
+            weighted_order: self.weighted_order.clone(),
+            play_counts: self.play_counts.clone(),
             command_receiver: self.command_receiver.resubscribe(),
             status_sender: self.status_sender.clone(),
             mqtt_client: self.mqtt_client.clone(),
             couchdb_client: self.couchdb_client.clone(),
+            transition_preview: self.transition_preview.clone(),
+            download_lock: self.download_lock.clone(),
+            pending_restart: self.pending_restart.clone(),
+            resume_at: self.resume_at.clone(),
+            held: self.held.clone(),
+            stopped: self.stopped.clone(),
+            slide_extension: self.slide_extension.clone(),
+            backlight: self.backlight.clone(),
+            brightness: self.brightness.clone(),
+            wake_until: self.wake_until.clone(),
+            curtain_override: self.curtain_override.clone(),
+            test_pattern: self.test_pattern.clone(),
+            takeover: self.takeover.clone(),
+            rendered_text_slides: self.rendered_text_slides.clone(),
+            ticker_text: self.ticker_text.clone(),
+            ticker_feed: self.ticker_feed.clone(),
+            last_transition: self.last_transition.clone(),
+            image_cache: self.image_cache.clone(),
+            image_store: self.image_store.clone(),
             start_time: self.start_time,
         }
     }
@@ -59,15 +274,49 @@ impl SlideshowController {
         command_receiver: broadcast::Receiver<SlideshowCommand>,
         status_sender: mpsc::Sender<TvStatus>,
     ) -> Self {
+        let backlight = Backlight::find();
+        if let Some(ref backlight) = backlight {
+            info!("💡 Using backlight {} for brightness control", backlight.name());
+        }
+
+        let image_cache = ImageCache::new(config.image_cache_size).with_disk(&config.image_dir, config.frame_cache_max_bytes);
+        let image_store = ImageStore::open(&config.image_dir, config.image_dir_max_bytes);
+
         Self {
             config: Arc::new(RwLock::new(config)),
             state: Arc::new(RwLock::new(SlideshowState::Stopped)),
             current_index: Arc::new(RwLock::new(0)),
             images: Arc::new(RwLock::new(Vec::new())),
+            assigned_images: Arc::new(RwLock::new(Vec::new())),
+            schedules: Arc::new(RwLock::new(Vec::new())),
+            active_schedule: Arc::new(RwLock::new(None)),
+            held_back: Arc::new(RwLock::new(HashSet::new())),
+            shuffle_order: Arc::new(Mutex::new(ShuffleOrder::default())),
+            weighted_order: Arc::new(Mutex::new(WeightedOrder::default())),
+            play_counts: Arc::new(RwLock::new(HashMap::new())),
             command_receiver,
             status_sender,
             mqtt_client: Arc::new(RwLock::new(None)),
             couchdb_client: Arc::new(RwLock::new(None)),
+            transition_preview: Arc::new(RwLock::new(None)),
+            download_lock: Arc::new(Mutex::new(())),
+            pending_restart: Arc::new(RwLock::new(None)),
+            resume_at: Arc::new(RwLock::new(None)),
+            held: Arc::new(RwLock::new(false)),
+            stopped: Arc::new(RwLock::new(false)),
+            slide_extension: Arc::new(RwLock::new(None)),
+            backlight,
+            brightness: Arc::new(RwLock::new(None)),
+            wake_until: Arc::new(RwLock::new(None)),
+            curtain_override: Arc::new(RwLock::new(None)),
+            test_pattern: Arc::new(RwLock::new(None)),
+            takeover: Arc::new(RwLock::new(None)),
+            rendered_text_slides: Arc::new(Mutex::new(HashMap::new())),
+            ticker_text: Arc::new(RwLock::new(None)),
+            ticker_feed: Arc::new(RwLock::new(None)),
+            last_transition: Arc::new(RwLock::new(None)),
+            image_cache,
+            image_store: Arc::new(Mutex::new(image_store)),
             start_time: Instant::now(),
         }
     }
@@ -92,24 +341,24 @@ impl SlideshowController {
             )
         ).await {
             Ok(Ok(couchdb_client)) => {
-                println!("Connected to CouchDB at {}", config.couchdb_url);
+                info!("Connected to CouchDB at {}", config.couchdb_url);
                 self.set_couchdb_client(couchdb_client).await;
             }
             Ok(Err(e)) => {
-                eprintln!("Warning: Failed to connect to CouchDB: {}", e);
-                println!("Continuing in local-only mode");
+                error!("Warning: Failed to connect to CouchDB: {}", e);
+                info!("Continuing in local-only mode");
             }
             Err(_) => {
-                eprintln!("Warning: CouchDB connection timeout after 5 seconds");
-                println!("Continuing in local-only mode");
+                warn!("Warning: CouchDB connection timeout after 5 seconds");
+                info!("Continuing in local-only mode");
             }
         }
         drop(config);
         
         // Register with management system
         if let Err(e) = self.register_with_management_system().await {
-            eprintln!("Warning: Failed to register with management system: {}", e);
-            println!("Continuing without registration - TV may not appear in management UI");
+            error!("Warning: Failed to register with management system: {}", e);
+            info!("Continuing without registration - TV may not appear in management UI");
         }
         
         // Load initial images from directory
@@ -129,29 +378,40 @@ impl SlideshowController {
             drop(config);
             
             if let Ok(Some(tv_config)) = couchdb_client.get_tv_config(&tv_id).await {
+                let brightness_error = invalid_brightness(Some(tv_config.brightness), Some(&tv_config.dimming_schedule));
                 let mut config = self.config.write().await;
-                config.display_duration = Duration::from_millis(tv_config.display_duration);
-                config.orientation = tv_config.orientation.clone();
-                config.transition_effect = tv_config.transition_effect.clone();
-                println!("Applied CouchDB config: {}ms display, {} orientation, {} transition", 
-                         tv_config.display_duration, tv_config.orientation, tv_config.transition_effect);
+                apply_tv_config(&mut config, &tv_config, brightness_error.is_none());
+                info!("Applied CouchDB config: {}ms display, {} orientation, {} transition, content safety {}", 
+                         tv_config.display_duration, tv_config.orientation, tv_config.transition_effect, config.content_safety);
+                drop(config);
+                if let Some(message) = brightness_error {
+                    self.reject_config(&message).await;
+                }
+                self.check_transition_effect(&tv_config.transition_effect).await;
             }
         }
+        self.apply_brightness_schedule().await;
+        self.sync_letterbox_image().await;
+        self.sync_curtain_image().await;
+        self.refresh_ticker_feed(true).await;
         
         // Fetch images from CouchDB
         if let Err(e) = self.fetch_images_from_couchdb().await {
-            eprintln!("Warning: Failed to fetch images from CouchDB: {}", e);
-            println!("Continuing with local images only");
+            error!("Warning: Failed to fetch images from CouchDB: {}", e);
+            info!("Continuing with local images only");
         }
 
+        // Resume where the previous process left off if this start follows a restart
+        self.restore_playback_state().await;
+
         // Update state after fetching from CouchDB
         let image_count = self.images.read().await.len();
         if image_count == 0 {
             *self.state.write().await = SlideshowState::Stopped;
-            println!("No images available - slideshow stopped");
+            info!("No images available - slideshow stopped");
         } else {
             *self.state.write().await = SlideshowState::Playing;
-            println!("Slideshow controller initialized with {} images", image_count);
+            info!("Slideshow controller initialized with {} images", image_count);
         }
         
         Ok(())
@@ -166,9 +426,7 @@ impl SlideshowController {
             for entry in entries.flatten() {
                 let path = entry.path();
                 if let Some(ext) = path.extension() {
-                    if ext.to_string_lossy().to_lowercase() == "png" || 
-                       ext.to_string_lossy().to_lowercase() == "jpg" ||
-                       ext.to_string_lossy().to_lowercase() == "jpeg" {
+                    if crate::is_slide_extension(&ext.to_string_lossy().to_lowercase()) {
                         let image_info = ImageInfo {
                             id: path.file_stem()
                                 .unwrap_or_default()
@@ -178,6 +436,17 @@ impl SlideshowController {
                             order: images.len() as u32,
                             url: None,
                             extension: path.extension().and_then(|ext| ext.to_str()).map(|s| format!(".{}", s)),
+                            letterbox_fill: None,
+                            fit_mode: None,
+                            qr_url: None,
+                            qr_corner: None,
+                            weight: None,
+                            valid_from: None,
+                            valid_until: None,
+                            web_url: None,
+                            web_refresh_secs: None,
+                            text_slide: None,
+                            revision: None,
                         };
                         images.push(image_info);
                     }
@@ -186,61 +455,110 @@ impl SlideshowController {
         }
 
         images.sort_by(|a, b| a.order.cmp(&b.order));
+        *self.assigned_images.write().await = images.clone();
         if !images.is_empty() {
-            println!("Found {} local images", images.len());
+            info!("Found {} local images", images.len());
         }
         Ok(())
     }
 
     async fn fetch_images_from_couchdb(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let _downloads = self.download_lock.lock().await;
         let config = self.config.read().await;
         let tv_id = format!("tv_{}", config.tv_id);
         
         if let Some(ref couchdb_client) = *self.couchdb_client.read().await {
-            let couchdb_images = couchdb_client.get_images_for_tv(&tv_id).await?;
+            let couchdb_images = match couchdb_client.get_images_for_tv(&tv_id, &config.tags).await {
+                Ok(images) => {
+                    health::record_couchdb_sync(true);
+                    images
+                }
+                Err(e) => {
+                    health::record_couchdb_sync(false);
+                    return Err(e);
+                }
+            };
             
             // Always clear local images when CouchDB is available - we only show what's assigned
             let mut local_images = self.images.write().await;
             local_images.clear();
             
+            // Files assigned to this TV, which the image directory cap never evicts
+            let mut assigned = Vec::new();
+            
             if !couchdb_images.is_empty() {
-                println!("Received {} images from CouchDB for {}", couchdb_images.len(), tv_id);
+                info!("Received {} images from CouchDB for {}", couchdb_images.len(), tv_id);
 
                 for image_info in couchdb_images {
-                    // Get extension from image info
-                    let original_ext = image_info.extension
-                        .as_deref()
-                        .and_then(|ext| if ext.starts_with('.') { Some(&ext[1..]) } else { Some(ext) })
-                        .unwrap_or("png");
-                    
                     // Use image ID with original extension as local filename
-                    let local_filename = format!("{}.{}", image_info.id, original_ext);
+                    let local_filename = match image_info.local_file_name() {
+                        Some(name) => name,
+                        None => {
+                            warn!("Skipping image with unsafe id or extension: {:?}", image_info.id);
+                            if let Some(ref mqtt_client) = *self.mqtt_client.read().await {
+                                let _ = mqtt_client.publish_error(&format!("Rejected image with unsafe id or extension: {:?}", image_info.id)).await;
+                            }
+                            continue;
+                        }
+                    };
                     let local_path = Path::new(&config.image_dir).join(&local_filename);
+                    assigned.push(local_path.clone());
+                    if is_quarantined(&config.image_dir, &local_filename, image_info.revision.as_deref()) {
+                        continue;
+                    }
                     
-                    // Download image attachment from CouchDB if it doesn't exist locally
-                    if !local_path.exists() {
-                        if let Err(e) = couchdb_client.download_image_attachment(&image_info.id, &local_path.to_string_lossy()).await {
-                            eprintln!("Failed to download image attachment {}: {}", image_info.id, e);
+                    // Download image attachment from CouchDB, or capture the web page, if it doesn't
+                    // exist locally; text slides are checked every time in case they changed
+                    if image_info.text_slide.is_some() || !local_path.exists() {
+                        if let Err(e) = self.fetch_slide(couchdb_client, &image_info, &local_path, &config.text_profile).await {
+                            error!("Failed to fetch image {}: {}", image_info.id, e);
                             continue;
                         }
                     }
 
+                    if config.content_safety && !self.passes_content_safety(&image_info.id, &local_path).await {
+                        continue;
+                    }
+
                     let updated_info = ImageInfo {
                         id: image_info.id,
                         path: local_path.to_string_lossy().to_string(),
                         order: image_info.order,
                         url: None, // Not needed for CouchDB attachments
                         extension: image_info.extension,
+                        letterbox_fill: image_info.letterbox_fill,
+                        fit_mode: image_info.fit_mode,
+                        qr_url: image_info.qr_url,
+                        qr_corner: image_info.qr_corner,
+                        weight: image_info.weight,
+                        valid_from: image_info.valid_from,
+                        valid_until: image_info.valid_until,
+                        web_url: image_info.web_url,
+                        web_refresh_secs: image_info.web_refresh_secs,
+                        text_slide: image_info.text_slide,
+                        revision: image_info.revision,
                     };
                     
                     local_images.push(updated_info);
                 }
 
                 local_images.sort_by(|a, b| a.order.cmp(&b.order));
-                println!("Updated to {} images from CouchDB", local_images.len());
+                info!("Updated to {} images from CouchDB", local_images.len());
             } else {
-                println!("No images assigned to {} in CouchDB", tv_id);
+                info!("No images assigned to {} in CouchDB", tv_id);
+            }
+            self.image_store.lock().await.sync(&assigned);
+            *self.assigned_images.write().await = local_images.clone();
+            drop(local_images);
+
+            // A failed fetch keeps the schedules already known rather than dropping the TV
+            // back to its unscheduled images
+            match couchdb_client.get_schedules_for_tv(&tv_id).await {
+                Ok(schedules) => *self.schedules.write().await = schedules,
+                Err(e) => error!("Failed to fetch schedules for {}, keeping the previous ones: {}", tv_id, e),
             }
+            drop(config);
+            self.refresh_playlist().await;
             
             Ok(())
         } else {
@@ -248,11 +566,112 @@ impl SlideshowController {
         }
     }
 
+    // The file for a slide: its attachment, a capture of the page for a web page slide, or the
+    // card drawn for a text slide
+    async fn fetch_slide(&self, couchdb_client: &CouchDbClient, image_info: &ImageInfo, local_path: &Path, text_profile: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.make_room_on_disk().await?;
+        if let Some(ref slide) = image_info.text_slide {
+            return self.render_text_slide(slide, local_path, text_profile).await;
+        }
+        match image_info.web_url {
+            Some(ref url) => web_page::capture(url, local_path).await,
+            None => couchdb_client.download_image_attachment(&image_info.id, &local_path.to_string_lossy()).await,
+        }
+    }
+
+    // Clear space for a slide, refusing it when the SD card can't be brought back over its
+    // minimum free space. Running low is reported once, until there is room again. In
+    // local-only mode the slides are the user's own pictures, and are never deleted.
+    pub async fn make_room_on_disk(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let evict_slides = self.couchdb_client.read().await.is_some();
+        let result = self.image_store.lock().await.make_room(evict_slides);
+        let min_free_mb = health::min_free_disk_bytes() / 1_000_000;
+        if health::set_disk_low(result.is_err()) {
+            match result {
+                Err(available) => {
+                    warn!("💾 Only {} MB free, under the {} MB kept free; downloads stop until there is room", available / 1_000_000, min_free_mb);
+                    if let Some(ref mqtt_client) = *self.mqtt_client.read().await {
+                        if let Err(e) = mqtt_client.publish_disk_full(available, health::min_free_disk_bytes()).await {
+                            error!("Failed to publish disk space error to MQTT: {}", e);
+                        }
+                    }
+                }
+                Ok(()) => info!("💾 Disk space back over {} MB, downloads resume", min_free_mb),
+            }
+        }
+        result.map_err(|available| format!("only {} MB free on the SD card, under the {} MB kept free", available / 1_000_000, min_free_mb).into())
+    }
+
+    // Draw a text slide into its file unless the file already shows it as it is now: the same
+    // document on the same screen size and text profile
+    async fn render_text_slide(&self, slide: &TextSlide, local_path: &Path, text_profile: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let (width, height) = web_page::viewport();
+        // A countdown is drawn with the time left now, so it is drawn again whenever that changed
+        let slide = &text_slide::with_countdown(slide, Utc::now()).unwrap_or_else(|| slide.clone());
+        let drawn = (slide.clone(), (width, height), text_profile.to_string());
+        let mut rendered = self.rendered_text_slides.lock().await;
+        if local_path.exists() && rendered.get(local_path) == Some(&drawn) {
+            return Ok(());
+        }
+        // Not worth a line each time a countdown's file is brought up to date
+        let ticking = slide.countdown_to.is_some() && rendered.contains_key(local_path);
+
+        let card = text_slide::render(slide, width, height, &TextProfile::from(text_profile));
+        // Written aside and renamed over the slide, so the display never reads half a file
+        let partial = local_path.with_extension("png.partial");
+        card.save_with_format(&partial, image::ImageFormat::Png)?;
+        std::fs::rename(&partial, local_path)?;
+        if !ticking {
+            info!("📝 Drew text slide {:?} at {}x{}", slide.title, width, height);
+        }
+        rendered.insert(local_path.to_path_buf(), drawn);
+        Ok(())
+    }
+
+    // Draw text slides again when the screen size or text profile has changed since
+    async fn refresh_text_slides(&self) {
+        let text_profile = self.config.read().await.text_profile.clone();
+        let slides: Vec<(TextSlide, PathBuf)> = self.assigned_images.read().await
+            .iter()
+            .filter_map(|img| img.text_slide.clone().map(|slide| (slide, PathBuf::from(&img.path))))
+            .collect();
+        for (slide, path) in slides {
+            if let Err(e) = self.render_text_slide(&slide, &path, &text_profile).await {
+                error!("Failed to draw text slide {:?}: {}", slide.title, e);
+            }
+        }
+    }
+
+    // Capture web page slides again once their refresh interval has passed. Pages without a
+    // capture yet are left to the next sync, which leaves them out of the playlist meanwhile.
+    async fn refresh_web_pages(&self) {
+        let pages: Vec<(String, PathBuf, Duration)> = self.assigned_images.read().await
+            .iter()
+            .filter_map(|img| img.web_url.as_ref().map(|url| (url.clone(), PathBuf::from(&img.path), web_page::refresh_interval(img.web_refresh_secs))))
+            .collect();
+        for (url, path, refresh) in pages {
+            let age = match std::fs::metadata(&path).and_then(|metadata| metadata.modified()) {
+                Ok(modified) => modified.elapsed().unwrap_or_default(),
+                Err(_) => continue,
+            };
+            if age < refresh {
+                continue;
+            }
+            if let Err(e) = self.make_room_on_disk().await {
+                warn!("Not refreshing web page {}: {}", url, e);
+                continue;
+            }
+            if let Err(e) = web_page::capture(&url, &path).await {
+                error!("Failed to capture web page {}, keeping the previous capture: {}", url, e);
+            }
+        }
+    }
+
     pub async fn run_command_handler(&mut self) {
         loop {
             if let Ok(command) = self.command_receiver.recv().await {
                 if let Err(e) = self.handle_command(command).await {
-                    eprintln!("Error handling command: {}", e);
+                    error!("Error handling command: {}", e);
                     
                     if let Some(ref mqtt_client) = *self.mqtt_client.read().await {
                         let _ = mqtt_client.publish_error(&format!("Command error: {}", e)).await;
@@ -262,34 +681,148 @@ impl SlideshowController {
         }
     }
 
+    #[instrument(name = "command", skip_all, fields(command = command.name()))]
     async fn handle_command(&self, command: SlideshowCommand) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        crash::record_command(command.name());
 
         match command {
             SlideshowCommand::Play => {
+                *self.resume_at.write().await = None;
                 *self.state.write().await = SlideshowState::Playing;
             }
             SlideshowCommand::Pause => {
+                *self.resume_at.write().await = None;
                 *self.state.write().await = SlideshowState::Paused;
             }
+            SlideshowCommand::Hold => {
+                info!("📌 Holding the current slide until resumed");
+                *self.held.write().await = true;
+            }
+            SlideshowCommand::Resume => {
+                if std::mem::take(&mut *self.held.write().await) {
+                    info!("📌 Hold released, rotation continues");
+                }
+            }
+            SlideshowCommand::PauseFor { duration } => {
+                info!("Pausing for {} minutes", duration.as_secs() / 60);
+                self.pause_with_auto_resume(duration).await;
+            }
+            SlideshowCommand::WakeFor { duration } => {
+                info!("☀️ Keeping the display on for {} minutes", duration.as_secs() / 60);
+                *self.wake_until.write().await = Some(Instant::now() + duration);
+            }
+            SlideshowCommand::Curtain { closed, duration } => {
+                let action = if closed { "drawn" } else { "lifted" };
+                match duration {
+                    Some(duration) => info!("🎭 Curtain {} for {} minutes", action, duration.as_secs() / 60),
+                    None => info!("🎭 Curtain {} until the curtain hours change", action),
+                }
+                let scheduled = self.is_curtain_scheduled().await;
+                let until = duration.map(|duration| Instant::now() + duration);
+                *self.curtain_override.write().await = Some(CurtainOverride { closed, until, scheduled });
+                self.send_status_update().await;
+            }
+            SlideshowCommand::TestPattern { pattern } => {
+                match pattern {
+                    Some(pattern) => info!("🧪 Showing the {} test pattern", pattern.name()),
+                    None => info!("🧪 Test pattern off, resuming the slideshow"),
+                }
+                *self.test_pattern.write().await = pattern.map(|pattern| (pattern, Instant::now()));
+            }
+            SlideshowCommand::Takeover { content, duration } => {
+                let screen = self.prepare_takeover(&content).await?;
+                match duration {
+                    Some(duration) => info!("🚨 Takeover: showing {} for {} minutes", content.describe(), duration.as_secs() / 60),
+                    None => info!("🚨 Takeover: showing {} until released", content.describe()),
+                }
+                let until = duration.map(|duration| Instant::now() + duration);
+                *self.takeover.write().await = Some(ActiveTakeover { screen, content, until });
+            }
+            SlideshowCommand::Release => {
+                if self.takeover.write().await.take().is_some() {
+                    info!("🚨 Takeover released, resuming the slideshow");
+                }
+            }
+            SlideshowCommand::Ticker { text } => {
+                match text {
+                    Some(ref text) => info!("📰 Ticker: {}", text),
+                    None => info!("📰 Ticker text cleared"),
+                }
+                *self.ticker_text.write().await = text;
+            }
             SlideshowCommand::Next => {
                 self.advance_to_next_image().await;
             }
             SlideshowCommand::Previous => {
                 self.advance_to_previous_image().await;
             }
+            SlideshowCommand::Shuffle { enabled } => {
+                let mut config = self.config.write().await;
+                info!("Updating shuffle from {} to {}", config.shuffle, enabled);
+                config.shuffle = enabled;
+            }
             SlideshowCommand::UpdateImages { images } => {
                 self.update_images(images).await?;
             }
+            SlideshowCommand::Sync => {
+                info!("🔄 Sync command received - fetching images from CouchDB");
+                self.fetch_images_from_couchdb().await?;
+            }
             SlideshowCommand::UpdateConfig { config } => {
-                self.update_config(config).await;
+                self.update_config(*config).await;
+            }
+            SlideshowCommand::PreviewTransition { effect } => {
+                let effect = match effect {
+                    Some(effect) => effect,
+                    None => self.get_transition_effect().await,
+                };
+                info!("Transition preview requested: {}", effect);
+                self.check_transition_effect(&effect).await;
+                *self.transition_preview.write().await = Some(effect);
+            }
+            SlideshowCommand::Restart => {
+                info!("Restart command received - draining downloads before restarting");
+                // The drain can take up to RESTART_DRAIN_TIMEOUT; other commands are handled meanwhile
+                let controller = self.clone();
+                tokio::spawn(async move { controller.prepare_restart().await });
             }
             SlideshowCommand::Reboot => {
-                println!("Reboot command received - rebooting system...");
-                std::process::Command::new("sudo").args(&["reboot"]).spawn()?;
+                info!("Reboot command received - rebooting system...");
+                power::reboot().await?;
+            }
+            SlideshowCommand::Extend { seconds } => {
+                let current = {
+                    let images = self.images.read().await;
+                    images.get(*self.current_index.read().await).map(|img| img.id.clone())
+                };
+                let image_id = current.ok_or("No slide on screen to extend")?;
+                let mut extension = self.slide_extension.write().await;
+                let total = match *extension {
+                    Some((ref id, extended)) if *id == image_id => extended + seconds,
+                    _ => seconds,
+                };
+                info!("⏱️ Current slide's display time changed by {:+}s ({:+}s in all)", seconds, total);
+                *extension = Some((image_id, total));
+            }
+            SlideshowCommand::Stop => {
+                info!("⏹️ Stopped, the display stays blank until started");
+                *self.stopped.write().await = true;
+            }
+            SlideshowCommand::Start => {
+                if std::mem::take(&mut *self.stopped.write().await) {
+                    info!("▶️ Started, resuming the slideshow");
+                }
+                *self.resume_at.write().await = None;
+                if !self.images.read().await.is_empty() {
+                    *self.state.write().await = SlideshowState::Playing;
+                }
+            }
+            SlideshowCommand::Cec { action } => {
+                cec::run(action).await?;
             }
             SlideshowCommand::Shutdown => {
-                println!("Shutdown command received - stopping slideshow");
-                *self.state.write().await = SlideshowState::Stopped;
+                info!("Shutdown command received - powering off...");
+                power::power_off().await?;
             }
         }
 
@@ -300,47 +833,90 @@ impl SlideshowController {
     }
 
     pub async fn advance_to_next_image(&self) {
+        let shuffle = self.config.read().await.shuffle;
         let images = self.images.read().await;
         if !images.is_empty() {
             let mut current_index = self.current_index.write().await;
-            *current_index = (*current_index + 1) % images.len();
+            *current_index = if shuffle {
+                self.shuffle_order.lock().await.next(*current_index, images.len())
+            } else if let Some(weights) = weights_of(&images) {
+                self.weighted_order.lock().await.next(*current_index, &weights)
+            } else {
+                (*current_index + 1) % images.len()
+            };
+            self.record_play(&images[*current_index].id).await;
+            *self.slide_extension.write().await = None;
         }
     }
 
     pub async fn advance_to_previous_image(&self) {
+        let shuffle = self.config.read().await.shuffle;
         let images = self.images.read().await;
         if !images.is_empty() {
             let mut current_index = self.current_index.write().await;
-            *current_index = if *current_index == 0 {
+            *current_index = if shuffle {
+                self.shuffle_order.lock().await.previous(*current_index, images.len())
+            } else if let Some(weights) = weights_of(&images) {
+                self.weighted_order.lock().await.previous(*current_index, &weights)
+            } else if *current_index == 0 {
                 images.len() - 1
             } else {
                 *current_index - 1
             };
+            self.record_play(&images[*current_index].id).await;
+            *self.slide_extension.write().await = None;
         }
     }
 
-    async fn update_images(&self, new_images: Vec<ImageInfo>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // The index advance_to_next_image moves to
+    pub async fn get_next_index(&self) -> Option<usize> {
+        let shuffle = self.config.read().await.shuffle;
+        let current_index = *self.current_index.read().await;
+        let images = self.images.read().await;
+        let len = images.len();
+        if len == 0 {
+            return None;
+        }
+        Some(if shuffle {
+            self.shuffle_order.lock().await.peek(current_index, len)
+        } else if let Some(weights) = weights_of(&images) {
+            self.weighted_order.lock().await.peek(current_index, &weights)
+        } else {
+            (current_index + 1) % len
+        })
+    }
+
+    async fn record_play(&self, image_id: &str) {
+        *self.play_counts.write().await.entry(image_id.to_string()).or_insert(0) += 1;
+    }
+
+    pub async fn get_play_counts(&self) -> HashMap<String, u64> {
+        self.play_counts.read().await.clone()
+    }
+
+    async fn update_images(&self, mut new_images: Vec<ImageInfo>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let _downloads = self.download_lock.lock().await;
+        // The list names image documents only; text slides stay as the last CouchDB sync found them
+        new_images.extend(self.assigned_images.read().await.iter().filter(|img| img.text_slide.is_some()).cloned());
         let config = self.config.read().await;
         let mut images = self.images.write().await;
         
-        println!("Updating images: received {} new images (previous count: {})", new_images.len(), images.len());
+        info!("Updating images: received {} new images (previous count: {})", new_images.len(), images.len());
 
         // Download new images from CouchDB
         if let Some(ref couchdb_client) = *self.couchdb_client.read().await {
             for image_info in &new_images {
-                // Get extension from image info
-                let original_ext = image_info.extension
-                    .as_deref()
-                    .and_then(|ext| if ext.starts_with('.') { Some(&ext[1..]) } else { Some(ext) })
-                    .unwrap_or("png");
-                
                 // Use image ID with original extension as local filename
-                let local_filename = format!("{}.{}", image_info.id, original_ext);
+                // Reported by the loop below
+                let local_filename = match image_info.local_file_name() {
+                    Some(name) => name,
+                    None => continue,
+                };
                 let local_path = Path::new(&config.image_dir).join(&local_filename);
                 
-                if !local_path.exists() {
-                    if let Err(e) = couchdb_client.download_image_attachment(&image_info.id, &local_path.to_string_lossy()).await {
-                        eprintln!("Failed to download image attachment {}: {}", image_info.id, e);
+                if (image_info.text_slide.is_some() || !local_path.exists()) && !is_quarantined(&config.image_dir, &local_filename, image_info.revision.as_deref()) {
+                    if let Err(e) = self.fetch_slide(couchdb_client, image_info, &local_path, &config.text_profile).await {
+                        error!("Failed to fetch image {}: {}", image_info.id, e);
                         continue;
                     }
                 }
@@ -349,15 +925,30 @@ impl SlideshowController {
 
         // Update image list with corrected local paths
         let mut updated_images = Vec::new();
+        // Files assigned to this TV, including any content safety turns away, which the image
+        // directory cap never evicts (they would only be downloaded again)
+        let mut assigned = Vec::new();
         for image_info in new_images {
-            // Get extension from image info
-            let original_ext = image_info.extension
-                .as_deref()
-                .and_then(|ext| if ext.starts_with('.') { Some(&ext[1..]) } else { Some(ext) })
-                .unwrap_or("png");
-            
-            let local_filename = format!("{}.{}", image_info.id, original_ext);
+            // Ids and extensions come straight from the command payload, so never let them leave image_dir
+            let local_filename = match image_info.local_file_name() {
+                Some(name) => name,
+                None => {
+                    warn!("Skipping image with unsafe id or extension: {:?}", image_info.id);
+                    if let Some(ref mqtt_client) = *self.mqtt_client.read().await {
+                        let _ = mqtt_client.publish_error(&format!("Rejected image with unsafe id or extension: {:?}", image_info.id)).await;
+                    }
+                    continue;
+                }
+            };
             let local_path = Path::new(&config.image_dir).join(&local_filename);
+            assigned.push(local_path.clone());
+            if is_quarantined(&config.image_dir, &local_filename, image_info.revision.as_deref()) {
+                continue;
+            }
+            
+            if config.content_safety && local_path.exists() && !self.passes_content_safety(&image_info.id, &local_path).await {
+                continue;
+            }
             
             let updated_info = ImageInfo {
                 id: image_info.id,
@@ -365,12 +956,26 @@ impl SlideshowController {
                 order: image_info.order,
                 url: None, // Not needed for CouchDB attachments
                 extension: image_info.extension,
+                letterbox_fill: image_info.letterbox_fill,
+                fit_mode: image_info.fit_mode,
+                qr_url: image_info.qr_url,
+                qr_corner: image_info.qr_corner,
+                weight: image_info.weight,
+                valid_from: image_info.valid_from,
+                valid_until: image_info.valid_until,
+                web_url: image_info.web_url,
+                web_refresh_secs: image_info.web_refresh_secs,
+                text_slide: image_info.text_slide,
+                revision: image_info.revision,
             };
             updated_images.push(updated_info);
         }
         
+        self.image_store.lock().await.sync(&assigned);
+        
         *images = updated_images;
         images.sort_by(|a, b| a.order.cmp(&b.order));
+        *self.assigned_images.write().await = images.clone();
 
         // Reset current index if out of bounds
         let mut current_index = self.current_index.write().await;
@@ -381,48 +986,444 @@ impl SlideshowController {
         // Update state based on image availability
         if images.is_empty() {
             *self.state.write().await = SlideshowState::Stopped;
-            println!("Image list updated: 0 images - slideshow stopped");
+            info!("Image list updated: 0 images - slideshow stopped");
         } else {
             *self.state.write().await = SlideshowState::Playing;
-            println!("Image list updated: {} images - slideshow playing", images.len());
+            info!("Image list updated: {} images - slideshow playing", images.len());
         }
+        drop((current_index, images, config));
+        self.refresh_playlist().await;
         
         Ok(())
     }
 
-    async fn update_config(&self, new_config: SlideshowConfig) {
+    async fn update_config(&self, mut new_config: SlideshowConfig) {
+        // An out-of-range level is dropped and reported, and the rest of the update applied
+        if let Some(message) = invalid_brightness(new_config.brightness, None) {
+            new_config.brightness = None;
+            self.reject_config(&message).await;
+        }
+        if let Some(message) = invalid_brightness(None, new_config.dimming_schedule.as_deref()) {
+            new_config.dimming_schedule = None;
+            self.reject_config(&message).await;
+        }
+
         let mut config = self.config.write().await;
         
         if let Some(duration) = new_config.display_duration {
-            println!("Updating display duration from {}ms to {}ms", config.display_duration.as_millis(), duration);
+            info!("Updating display duration from {}ms to {}ms", config.display_duration.as_millis(), duration);
             config.display_duration = Duration::from_millis(duration);
         }
         
         if let Some(transition) = new_config.transition_duration {
-            println!("Updating transition duration from {}ms to {}ms", config.transition_duration.as_millis(), transition);
+            info!("Updating transition duration from {}ms to {}ms", config.transition_duration.as_millis(), transition);
             config.transition_duration = Duration::from_millis(transition);
         }
         
         if let Some(orientation) = new_config.orientation {
-            println!("🔄 ORIENTATION UPDATE: Updating orientation from {} to {}", config.orientation, orientation);
+            info!("🔄 ORIENTATION UPDATE: Updating orientation from {} to {}", config.orientation, orientation);
             config.orientation = orientation.clone();
-            println!("🔄 ORIENTATION UPDATED: New orientation set to {}", orientation);
+            info!("🔄 ORIENTATION UPDATED: New orientation set to {}", orientation);
         }
         
-        if let Some(transition_effect) = new_config.transition_effect {
-            println!("🔄 TRANSITION UPDATE: Updating transition effect from {} to {}", config.transition_effect, transition_effect);
+        if let Some(ref transition_effect) = new_config.transition_effect {
+            info!("🔄 TRANSITION UPDATE: Updating transition effect from {} to {}", config.transition_effect, transition_effect);
             config.transition_effect = transition_effect.clone();
-            println!("🔄 TRANSITION UPDATED: New transition effect set to {}", transition_effect);
+            info!("🔄 TRANSITION UPDATED: New transition effect set to {}", transition_effect);
+        }
+        
+        if let Some(content_safety) = new_config.content_safety {
+            info!("Updating content safety mode from {} to {}", config.content_safety, content_safety);
+            config.content_safety = content_safety;
+        }
+        
+        if let Some(text_profile) = new_config.text_profile {
+            info!("Updating text profile from {} to {}", config.text_profile, text_profile);
+            config.text_profile = text_profile;
+        }
+        
+        if let Some(refresh_secs) = new_config.framebuffer_refresh_secs {
+            info!("Updating framebuffer keep-alive refresh from {}s to {}s", config.framebuffer_refresh_secs, refresh_secs);
+            config.framebuffer_refresh_secs = refresh_secs;
+        }
+        
+        if let Some(skip_redundant_writes) = new_config.skip_redundant_writes {
+            info!("Updating redundant framebuffer write suppression from {} to {}", config.skip_redundant_writes, skip_redundant_writes);
+            config.skip_redundant_writes = skip_redundant_writes;
+        }
+        
+        if let Some(gamma) = new_config.gamma {
+            info!("Updating output gamma from {} to {}", config.gamma, gamma);
+            config.gamma = gamma;
+        }
+        
+        if let Some(color_temperature) = new_config.color_temperature {
+            info!("Updating output white point from {}K to {}K", config.color_temperature, color_temperature);
+            config.color_temperature = color_temperature;
+        }
+        
+        if let Some(brightness) = new_config.brightness {
+            info!("Updating brightness from {}% to {}%", config.brightness, brightness);
+            config.brightness = brightness;
+        }
+        
+        if let Some(dimming_schedule) = new_config.dimming_schedule {
+            info!("Updating dimming schedule to {} window(s)", dimming_schedule.len());
+            config.dimming_schedule = dimming_schedule;
+        }
+        
+        if let Some(quiet_hours) = new_config.quiet_hours {
+            info!("Updating quiet hours to {} window(s)", quiet_hours.len());
+            config.quiet_hours = quiet_hours;
+        }
+        
+        if let Some(curtain_hours) = new_config.curtain_hours {
+            info!("Updating curtain hours to {} window(s)", curtain_hours.len());
+            config.curtain_hours = curtain_hours;
+        }
+        
+        if let Some(curtain_color) = new_config.curtain_color {
+            info!("Updating curtain color from {} to {}", config.curtain_color, curtain_color);
+            config.curtain_color = curtain_color;
+        }
+        
+        if let Some(curtain_image) = new_config.curtain_image {
+            info!("Updating curtain image to {}", if curtain_image.is_empty() { "none" } else { &curtain_image });
+            config.curtain_image = Some(curtain_image).filter(|id| !id.is_empty());
+        }
+        
+        if let Some(letterbox_fill) = new_config.letterbox_fill {
+            info!("Updating letterbox fill from {} to {}", config.letterbox_fill, letterbox_fill);
+            config.letterbox_fill = letterbox_fill;
+        }
+        
+        if let Some(letterbox_color) = new_config.letterbox_color {
+            info!("Updating letterbox color from {} to {}", config.letterbox_color, letterbox_color);
+            config.letterbox_color = letterbox_color;
+        }
+        
+        if let Some(letterbox_image) = new_config.letterbox_image {
+            info!("Updating letterbox background image to {}", letterbox_image);
+            config.letterbox_image = Some(letterbox_image).filter(|id| !id.is_empty());
+        }
+        
+        if let Some(safe_area) = new_config.safe_area {
+            info!("Updating overscan margins from {:?} to {:?}", config.safe_area, safe_area);
+            config.safe_area = safe_area;
+        }
+        
+        if let Some(ken_burns) = new_config.ken_burns {
+            info!("Updating Ken Burns effect from {} to {}", config.ken_burns, ken_burns);
+            config.ken_burns = ken_burns;
+        }
+        
+        if let Some(intensity) = new_config.ken_burns_intensity {
+            info!("Updating Ken Burns intensity from {} to {}", config.ken_burns_intensity, intensity);
+            config.ken_burns_intensity = intensity;
+        }
+        
+        if let Some(smart_crop) = new_config.smart_crop {
+            info!("Updating smart cropping from {} to {}", config.smart_crop, smart_crop);
+            config.smart_crop = smart_crop;
+        }
+        
+        if let Some(easing) = new_config.transition_easing {
+            info!("Updating transition easing from {} to {}", config.transition_easing, easing);
+            config.transition_easing = easing;
+        }
+        
+        if let Some(durations) = new_config.transition_durations {
+            info!("Updating per-effect transition durations from {:?} to {:?}", config.transition_durations, durations);
+            config.transition_durations = durations;
+        }
+        
+        if let Some(shuffle) = new_config.shuffle {
+            info!("Updating shuffle from {} to {}", config.shuffle, shuffle);
+            config.shuffle = shuffle;
+        }
+
+        if let Some(url) = new_config.ticker_rss_url {
+            info!("Updating ticker feed to {}", if url.is_empty() { "none" } else { &url });
+            config.ticker_rss_url = Some(url).filter(|url| !url.is_empty());
+        }
+        
+        let mut tags_changed = false;
+        if let Some(tags) = new_config.tags {
+            if tags != config.tags {
+                info!("Updating tags from {:?} to {:?}", config.tags, tags);
+                config.tags = tags;
+                tags_changed = true;
+            }
+        }
+        drop(config);
+        
+        if let Some(ref transition_effect) = new_config.transition_effect {
+            self.check_transition_effect(transition_effect).await;
+        }
+        self.apply_brightness_schedule().await;
+        // Downloads wait on a running sync, which mustn't hold up this command handler
+        let controller = self.clone();
+        tokio::spawn(async move {
+            controller.sync_letterbox_image().await;
+            controller.sync_curtain_image().await;
+        });
+        // Tagged content comes and goes with the tags, so the playlist is fetched again
+        if tags_changed {
+            if let Err(e) = self.fetch_images_from_couchdb().await {
+                error!("Failed to fetch images for the new tags: {}", e);
+            }
+        }
+    }
+
+    // Hold the current slide, then resume on our own unless someone plays or pauses in the meantime
+    async fn pause_with_auto_resume(&self, duration: Duration) {
+        let deadline = Instant::now() + duration;
+        *self.resume_at.write().await = Some(deadline);
+        *self.state.write().await = SlideshowState::Paused;
+
+        let controller = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep_until(deadline.into()).await;
+
+            let mut resume_at = controller.resume_at.write().await;
+            if *resume_at != Some(deadline) {
+                return;
+            }
+            *resume_at = None;
+            drop(resume_at);
+
+            if matches!(*controller.state.read().await, SlideshowState::Paused) {
+                info!("Timed pause finished - resuming slideshow");
+                *controller.state.write().await = SlideshowState::Playing;
+                controller.send_status_update().await;
+            }
+        });
+    }
+
+    // Wait for in-flight downloads, save the current position and flag the slideshow loop to restart
+    pub async fn prepare_restart(&self) {
+        let download_guard = match tokio::time::timeout(RESTART_DRAIN_TIMEOUT, self.download_lock.clone().lock_owned()).await {
+            Ok(guard) => Some(guard),
+            Err(_) => {
+                warn!("Downloads still running after {}s, restarting anyway", RESTART_DRAIN_TIMEOUT.as_secs());
+                None
+            }
+        };
+
+        if let Err(e) = self.save_playback_state().await {
+            error!("Failed to save playback state: {}", e);
+        }
+
+        *self.pending_restart.write().await = Some(PendingRestart {
+            _download_guard: download_guard,
+        });
+    }
+
+    // Report a stuck display loop and save the position for the restart the watchdog makes
+    pub async fn report_stall(&self, stalled_for: Duration) {
+        if let Some(ref mqtt_client) = *self.mqtt_client.read().await {
+            if let Err(e) = mqtt_client.publish_stalled(stalled_for).await {
+                error!("Failed to publish stall to MQTT: {}", e);
+            }
+        }
+        if let Err(e) = self.save_playback_state().await {
+            error!("Failed to save playback state: {}", e);
+        }
+    }
+
+    // A newly raised under-voltage or throttling flag, for finding bad power supplies
+    pub async fn report_throttling(&self, flags: &ThrottleFlags, temperature: Option<f32>) {
+        if let Some(ref mqtt_client) = *self.mqtt_client.read().await {
+            if let Err(e) = mqtt_client.publish_throttled(flags, temperature).await {
+                error!("Failed to publish throttling to MQTT: {}", e);
+            }
+        }
+    }
+
+    // A setting from MQTT or CouchDB that was refused, kept at its current value
+    async fn reject_config(&self, message: &str) {
+        warn!("Rejected config: {}", message);
+        if let Some(ref mqtt_client) = *self.mqtt_client.read().await {
+            let _ = mqtt_client.publish_error(&format!("Rejected config: {}", message)).await;
+        }
+    }
+
+    // Report a transition effect this build doesn't have when the config naming it arrives,
+    // rather than on every slide; it plays as a fade
+    async fn check_transition_effect(&self, effect: &str) {
+        if crate::TransitionType::from_string(effect).is_some() {
+            return;
+        }
+        warn!("Unknown transition effect {}, fades will play instead", effect);
+        if let Some(ref mqtt_client) = *self.mqtt_client.read().await {
+            let _ = mqtt_client.publish_error(&format!("Unknown transition effect {}, fades will play instead", effect)).await;
+        }
+    }
+
+    // Progress of a self-update on the update topic
+    pub async fn report_update(&self, stage: &str, version: &str, error: Option<&str>) {
+        if let Some(ref mqtt_client) = *self.mqtt_client.read().await {
+            if let Err(e) = mqtt_client.publish_update(stage, version, error).await {
+                error!("Failed to publish update progress to MQTT: {}", e);
+            }
+        }
+    }
+
+    pub async fn get_release(&self, channel: &str) -> Result<Release, Box<dyn std::error::Error + Send + Sync>> {
+        match *self.couchdb_client.read().await {
+            Some(ref couchdb_client) => couchdb_client.get_release(channel).await,
+            None => Err("no CouchDB connection to read the release channel from".into()),
+        }
+    }
+
+    async fn save_playback_state(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let current_index = *self.current_index.read().await;
+        let images = self.images.read().await;
+        let state = serde_json::json!({
+            "current_index": current_index,
+            "current_image": images.get(current_index).map(|img| &img.id),
+            "held": *self.held.read().await,
+            "stopped": *self.stopped.read().await,
+        });
+
+        let path = self.config.read().await.image_dir.join(PLAYBACK_STATE_FILE);
+        std::fs::write(&path, state.to_string())?;
+        info!("Saved playback state to {} (image {})", path.display(), current_index);
+        Ok(())
+    }
+
+    async fn restore_playback_state(&self) {
+        let path = self.config.read().await.image_dir.join(PLAYBACK_STATE_FILE);
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(_) => return,
+        };
+        // Only meant for the restart that wrote it
+        let _ = std::fs::remove_file(&path);
+
+        let state: serde_json::Value = match serde_json::from_str(&contents) {
+            Ok(state) => state,
+            Err(e) => {
+                warn!("Ignoring unreadable playback state {}: {}", path.display(), e);
+                return;
+            }
+        };
+
+        let images = self.images.read().await;
+        // Prefer the image id, since the list may have been reordered while restarting
+        let restored = state["current_image"].as_str()
+            .and_then(|id| images.iter().position(|img| img.id == id))
+            .or_else(|| {
+                state["current_index"].as_u64()
+                    .map(|index| index as usize)
+                    .filter(|&index| index < images.len())
+            });
+
+        if let Some(index) = restored {
+            *self.current_index.write().await = index;
+            info!("Restored playback position to image {} after restart", index);
+        }
+        if state["held"].as_bool() == Some(true) {
+            *self.held.write().await = true;
+            info!("📌 Still holding the current slide after restart");
+        }
+        if state["stopped"].as_bool() == Some(true) {
+            *self.stopped.write().await = true;
+            info!("⏹️ Still stopped after restart");
+        }
+    }
+
+    // Take a slide that couldn't be decoded out of the playlist, move its file to
+    // QUARANTINE_DIR and report it, so it isn't retried on every pass of the display loop
+    pub async fn quarantine_image(&self, path: &Path, error: &str) {
+        let (image_dir, tv_id) = {
+            let config = self.config.read().await;
+            (config.image_dir.clone(), config.tv_id.clone())
+        };
+        let Some(file_name) = path.file_name() else {
+            return;
+        };
+
+        let quarantine_dir = image_dir.join(QUARANTINE_DIR);
+        let moved = std::fs::create_dir_all(&quarantine_dir)
+            .and_then(|_| std::fs::rename(path, quarantine_dir.join(file_name)));
+        if let Err(e) = moved {
+            error!("Failed to move {} to {}: {}", path.display(), quarantine_dir.display(), e);
+        }
+
+        let image_id = {
+            let mut images = self.images.write().await;
+            let position = images.iter().position(|img| Path::new(&img.path) == path);
+            let removed = position.map(|position| images.remove(position));
+            // Remembered so a replacement of the image is downloaded and tried
+            if let Some(revision) = removed.as_ref().and_then(|img| img.revision.as_ref()) {
+                let mut marker = file_name.to_os_string();
+                marker.push(QUARANTINE_REVISION_SUFFIX);
+                if let Err(e) = std::fs::write(quarantine_dir.join(marker), revision) {
+                    error!("Failed to record the revision of quarantined {}: {}", path.display(), e);
+                }
+            }
+            let image_id = removed.map(|img| img.id);
+            self.assigned_images.write().await.retain(|img| Path::new(&img.path) != path);
+            // The slide after the removed one moves up into its place
+            let mut current_index = self.current_index.write().await;
+            if position.is_some_and(|position| position < *current_index) {
+                *current_index -= 1;
+            }
+            if *current_index >= images.len() {
+                *current_index = 0;
+            }
+            if images.is_empty() {
+                *self.state.write().await = SlideshowState::Stopped;
+            }
+            image_id.unwrap_or_else(|| Path::new(file_name).file_stem().unwrap_or_default().to_string_lossy().to_string())
+        };
+        info!("🚫 Quarantined image {} ({}): {}", image_id, path.display(), error);
+
+        let message = format!("Image {} could not be decoded and was quarantined: {}", image_id, error);
+        if let Some(ref mqtt_client) = *self.mqtt_client.read().await {
+            if let Err(e) = mqtt_client.publish_image_error(&image_id, &message).await {
+                error!("Failed to publish image error to MQTT: {}", e);
+            }
+        }
+        if let Some(ref couchdb_client) = *self.couchdb_client.read().await {
+            if let Err(e) = couchdb_client.report_image_error(&format!("tv_{}", tv_id), &image_id, error).await {
+                error!("Failed to report image error to CouchDB: {}", e);
+            }
+        }
+    }
+
+    // Reject animations that exceed the flash threshold; they stay on disk but are never scheduled
+    async fn passes_content_safety(&self, image_id: &str, local_path: &Path) -> bool {
+        match content_safety::validate_image_file(local_path) {
+            Ok(()) => true,
+            Err(reason) => {
+                warn!("Content safety: skipping image {}: {}", image_id, reason);
+                if let Some(ref mqtt_client) = *self.mqtt_client.read().await {
+                    let _ = mqtt_client.publish_error(&format!("Content safety rejected image {}: {}", image_id, reason)).await;
+                }
+                false
+            }
         }
     }
 
     async fn send_status_update(&self) {
+        let takeover = self.get_takeover().await.is_some();
+        let stopped = self.is_stopped().await;
+        let quiet_hours = self.is_quiet_hours().await || self.is_blanked_unoccupied().await;
+        let curtain = self.is_curtain_closed().await;
+        let held = self.is_held().await;
         let state = self.state.read().await;
         let current_index = *self.current_index.read().await;
         let images = self.images.read().await;
         
         let current_image = images.get(current_index).map(|img| img.id.clone());
         let status_str = match *state {
+            _ if takeover => "takeover".to_string(),
+            _ if stopped => "stopped".to_string(),
+            _ if quiet_hours => "standby".to_string(),
+            _ if curtain => "curtain".to_string(),
+            SlideshowState::Playing if held => "held".to_string(),
             SlideshowState::Playing => "playing".to_string(),
             SlideshowState::Paused => "paused".to_string(),
             SlideshowState::Stopped => "stopped".to_string(),
@@ -434,17 +1435,24 @@ impl SlideshowController {
             total_images: images.len(),
             current_index,
             uptime: self.start_time.elapsed().as_secs(),
-            timestamp: chrono::Utc::now().to_rfc3339(),
+            timestamp: clock::timestamp(),
+            sequence: clock::next_sequence(),
+            clock_confidence: clock::confidence().to_string(),
+            last_transition: *self.last_transition.read().await,
+            quality: quality::mode().name().to_string(),
+            schedule: self.get_active_schedule().await,
+            play_counts: self.play_counts.read().await.clone(),
+            render: Some(perf::stats()),
         };
 
         if let Err(e) = self.status_sender.send(status.clone()).await {
-            eprintln!("Failed to send status update: {}", e);
+            error!("Failed to send status update: {}", e);
         }
 
         // Also publish to MQTT if available
         if let Some(ref mqtt_client) = *self.mqtt_client.read().await {
             if let Err(e) = mqtt_client.publish_status(&status).await {
-                eprintln!("Failed to publish status to MQTT: {}", e);
+                error!("Failed to publish status to MQTT: {}", e);
             }
         }
 
@@ -453,7 +1461,7 @@ impl SlideshowController {
             let config = self.config.read().await;
             let tv_id = format!("tv_{}", config.tv_id);
             if let Err(e) = couchdb_client.update_tv_status(&tv_id, &status_str, current_image.as_deref()).await {
-                eprintln!("Failed to update TV status in CouchDB: {}", e);
+                error!("Failed to update TV status in CouchDB: {}", e);
             }
         }
     }
@@ -465,6 +1473,12 @@ impl SlideshowController {
         images.get(current_index).map(|img| PathBuf::from(&img.path))
     }
 
+    // The image advance_to_next_image moves to
+    pub async fn get_next_image_path(&self) -> Option<PathBuf> {
+        let next_index = self.get_next_index().await?;
+        self.images.read().await.get(next_index).map(|img| PathBuf::from(&img.path))
+    }
+
     pub async fn get_state(&self) -> SlideshowState {
         self.state.read().await.clone()
     }
@@ -473,13 +1487,28 @@ impl SlideshowController {
         matches!(*self.state.read().await, SlideshowState::Playing)
     }
 
+    pub async fn is_held(&self) -> bool {
+        *self.held.read().await
+    }
+
+    // True while a stop command has the screen blank; a takeover still shows over it
+    pub async fn is_stopped(&self) -> bool {
+        *self.stopped.read().await && self.get_takeover().await.is_none()
+    }
+
+    // Whether the slide on the main display has been up long enough to move on
     pub async fn should_advance_automatically(&self, last_change: Instant) -> bool {
-        if !self.is_playing().await {
-            return false;
-        }
+        self.may_advance().await && last_change.elapsed() >= self.get_slide_duration().await
+    }
 
-        let config = self.config.read().await;
-        last_change.elapsed() >= config.display_duration
+    // As should_advance_automatically, for a display with a playlist of its own; extend
+    // commands don't apply to it
+    pub async fn should_advance_playlist(&self, last_change: Instant) -> bool {
+        self.may_advance().await && last_change.elapsed() >= self.get_display_duration().await
+    }
+
+    async fn may_advance(&self) -> bool {
+        self.is_playing().await && !self.is_held().await && !self.is_stopped().await
     }
 
 
@@ -490,7 +1519,7 @@ impl SlideshowController {
             
             if let Some(current_image) = images.get(current_index) {
                 if let Err(e) = mqtt_client.publish_current_image(&current_image.id).await {
-                    eprintln!("Failed to publish current image to MQTT: {}", e);
+                    error!("Failed to publish current image to MQTT: {}", e);
                 }
             }
         }
@@ -504,6 +1533,34 @@ impl SlideshowController {
         self.images.read().await.clone()
     }
 
+    // Letterbox fills, fit modes and QR codes images ask for in their metadata, by local path.
+    // Images without any of them are left out.
+    pub async fn get_slide_styles(&self) -> HashMap<PathBuf, SlideStyle> {
+        self.images.read().await
+            .iter()
+            .filter(|img| img.letterbox_fill.is_some() || img.fit_mode.is_some() || img.qr_url.is_some())
+            .map(|img| (PathBuf::from(&img.path), SlideStyle {
+                letterbox_fill: img.letterbox_fill.clone(),
+                fit_mode: img.fit_mode.clone(),
+                qr_url: img.qr_url.clone(),
+                qr_corner: img.qr_corner.clone(),
+            }))
+            .collect()
+    }
+
+    // The slide at `path` when it is a countdown, which the display loop keeps redrawing
+    pub async fn get_countdown_slide(&self, path: &Path) -> Option<TextSlide> {
+        self.images.read().await
+            .iter()
+            .find(|img| Path::new(&img.path) == path)
+            .and_then(|img| img.text_slide.clone())
+            .filter(|slide| slide.countdown_to.is_some())
+    }
+
+    pub async fn get_image_dir(&self) -> PathBuf {
+        self.config.read().await.image_dir.clone()
+    }
+
     pub async fn get_tv_id(&self) -> String {
         self.config.read().await.tv_id.clone()
     }
@@ -520,12 +1577,511 @@ impl SlideshowController {
         self.config.read().await.transition_duration
     }
 
+    pub async fn get_transition_durations(&self) -> HashMap<String, u64> {
+        self.config.read().await.transition_durations.clone()
+    }
+
+    pub async fn record_transition(&self, stats: TransitionStats) {
+        // A cut or an e-paper refresh is a single frame, with no frame rate to speak of
+        if stats.frames > 1 {
+            perf::record_transition(stats.fps);
+        }
+        *self.last_transition.write().await = Some(stats);
+    }
+
+    pub async fn get_last_transition(&self) -> Option<TransitionStats> {
+        *self.last_transition.read().await
+    }
+
+    // Taken by the slideshow loop, which plays the preview on its next iteration
+    pub async fn take_transition_preview(&self) -> Option<String> {
+        self.transition_preview.write().await.take()
+    }
+
+    // The test pattern to show instead of the slideshow. One left up by a forgotten
+    // installer times out rather than replacing the content for good.
+    pub async fn get_test_pattern(&self) -> Option<TestPattern> {
+        self.test_pattern.read().await
+            .filter(|(_, shown_at)| shown_at.elapsed() < TEST_PATTERN_TIMEOUT)
+            .map(|(pattern, _)| pattern)
+    }
+
+    // Emergency content to show instead of the slideshow, until it is released or times out
+    pub async fn get_takeover(&self) -> Option<takeover::Screen> {
+        self.takeover.read().await.as_ref()
+            .filter(|takeover| !takeover.is_expired())
+            .map(|takeover| takeover.screen.clone())
+    }
+
+    // Let the management server know when a takeover ran out rather than being released
+    pub async fn clear_expired_takeover(&self) {
+        let expired = {
+            let mut takeover = self.takeover.write().await;
+            let expired = takeover.as_ref().is_some_and(ActiveTakeover::is_expired);
+            if expired {
+                *takeover = None;
+            }
+            expired
+        };
+        if expired {
+            info!("🚨 Takeover timed out, resuming the slideshow");
+            self.send_status_update().await;
+        }
+    }
+
+    // What the active takeover was sent as, for status reports
+    pub async fn describe_takeover(&self) -> Option<String> {
+        self.takeover.read().await.as_ref()
+            .filter(|takeover| !takeover.is_expired())
+            .map(|takeover| takeover.content.describe())
+    }
+
+    // A local file for a takeover image: the slide itself when the image is assigned to this TV,
+    // otherwise a copy fetched into TAKEOVER_DIR (replacing any earlier one). Doesn't wait for
+    // the download lock, since a sync in progress could hold it for minutes.
+    async fn prepare_takeover(&self, content: &Takeover) -> Result<takeover::Screen, Box<dyn std::error::Error + Send + Sync>> {
+        let image_id = match content {
+            Takeover::Text(text) => return Ok(takeover::Screen::Text(text.clone())),
+            Takeover::Image(image_id) => image_id,
+        };
+
+        let assigned = self.assigned_images.read().await.iter()
+            .find(|img| &img.id == image_id)
+            .map(|img| PathBuf::from(&img.path))
+            .filter(|path| path.exists());
+        if let Some(path) = assigned {
+            return Ok(takeover::Screen::Image(path));
+        }
+
+        let dir = self.config.read().await.image_dir.join(TAKEOVER_DIR);
+        let couchdb_guard = self.couchdb_client.read().await;
+        let couchdb_client = couchdb_guard.as_ref()
+            .ok_or_else(|| format!("Takeover image {} isn't on this TV and CouchDB is not available", image_id))?;
+        let image_doc = couchdb_client.get_image(image_id).await?;
+        let file_name = format!("{}{}", image_id, image_doc.file_extension());
+        if !is_safe_file_component(&file_name) {
+            return Err(format!("Takeover image {} has an unsafe file name", image_id).into());
+        }
+
+        let local_path = dir.join(&file_name);
+        if !local_path.exists() {
+            if let Ok(entries) = std::fs::read_dir(&dir) {
+                for entry in entries.flatten() {
+                    let _ = std::fs::remove_file(entry.path());
+                }
+            }
+            std::fs::create_dir_all(&dir)?;
+            couchdb_client.download_image_attachment(image_id, &local_path.to_string_lossy()).await?;
+        }
+        Ok(takeover::Screen::Image(local_path))
+    }
+
+    // True while the quiet-hours schedule has the screen off and neither a wake override nor a
+    // takeover is active
+    pub async fn is_quiet_hours(&self) -> bool {
+        if self.get_wake_remaining().await.is_some() || self.get_takeover().await.is_some() {
+            return false;
+        }
+
+        let config = self.config.read().await;
+        if config.quiet_hours.is_empty() || !clock::schedules_trusted() {
+            return false;
+        }
+        let (weekday, minute) = (clock::local_weekday(), clock::local_minute_of_day());
+        config.quiet_hours.iter().any(|window| window.contains(weekday, minute))
+    }
+
+    // True while the PIR sensor has seen nobody for its idle time; a wake command or a takeover
+    // keeps the display up regardless
+    pub async fn is_unoccupied(&self) -> bool {
+        motion::is_idle() && self.get_wake_remaining().await.is_none() && self.get_takeover().await.is_none()
+    }
+
+    // Unoccupied, with the display blanked rather than dimmed
+    pub async fn is_blanked_unoccupied(&self) -> bool {
+        motion::idle_brightness().is_none() && self.is_unoccupied().await
+    }
+
+    async fn is_curtain_scheduled(&self) -> bool {
+        if !clock::schedules_trusted() {
+            return false;
+        }
+        let (weekday, minute) = (clock::local_weekday(), clock::local_minute_of_day());
+        self.config.read().await.curtain_hours.iter().any(|window| window.contains(weekday, minute))
+    }
+
+    // True while the curtain is drawn: by a curtain command, or otherwise by the curtain hours.
+    // A command holds until its minutes run out or the curtain hours next open or close, so
+    // lifting the curtain early doesn't keep it up past the next scheduled closing.
+    pub async fn is_curtain_closed(&self) -> bool {
+        let scheduled = self.is_curtain_scheduled().await;
+        let mut curtain_override = self.curtain_override.write().await;
+        if let Some(ref current) = *curtain_override {
+            let expired = current.until.is_some_and(|until| Instant::now() >= until);
+            if !expired && current.scheduled == scheduled {
+                return current.closed;
+            }
+            info!("🎭 Curtain command over, following the curtain hours again");
+            *curtain_override = None;
+        }
+        scheduled
+    }
+
+    // What covers the screen while the curtain is drawn; the color stands in until the image
+    // has been downloaded
+    pub async fn get_curtain(&self) -> Option<Curtain> {
+        if !self.is_curtain_closed().await {
+            return None;
+        }
+        let config = self.config.read().await;
+        let image = config.curtain_image.as_ref()
+            .filter(|id| is_safe_file_component(id))
+            .map(|id| config.image_dir.join(CURTAIN_DIR).join(id))
+            .filter(|path| path.exists());
+        Some(Curtain { color: config.curtain_color.clone(), image })
+    }
+
+    // Name of the daypart schedule playing, if any
+    pub async fn get_active_schedule(&self) -> Option<String> {
+        self.active_schedule.read().await.as_ref().map(|schedule| schedule.name.clone())
+    }
+
+    // Build the playlist for the current time. Images outside their valid_from/valid_until
+    // window are left out entirely. Of the rest, the playlist is the images of the first
+    // schedule whose window is open, in the schedule's order, or outside every schedule the
+    // images none of them lists; one that comes out empty falls back to every valid image.
+    // Switching schedules starts the new playlist from its first image and is announced over
+    // MQTT; otherwise the image on screen stays current if it is still in the playlist. While
+    // the clock is clearly wrong, windows and schedules are ignored and every image plays.
+    async fn refresh_playlist(&self) {
+        let now = clock::now();
+        let trusted = clock::schedules_trusted();
+        let (assigned, held_back): (Vec<ImageInfo>, Vec<ImageInfo>) = self.assigned_images.read().await.iter()
+            .cloned()
+            .partition(|img| !trusted || is_within_validity(img, now));
+        {
+            let held_back: HashSet<String> = held_back.into_iter().map(|img| img.id).collect();
+            let mut previous = self.held_back.write().await;
+            for id in held_back.difference(&previous) {
+                info!("⏳ Image {} is outside its validity window, leaving it out of the rotation", id);
+            }
+            for id in previous.difference(&held_back) {
+                if assigned.iter().any(|img| &img.id == id) {
+                    info!("⏳ Image {} is within its validity window, adding it to the rotation", id);
+                }
+            }
+            *previous = held_back;
+        }
+
+        let (weekday, minute) = (clock::local_weekday(), clock::local_minute_of_day());
+        let active = self.schedules.read().await.iter()
+            .find(|schedule| trusted && schedule.window.contains(weekday, minute))
+            .cloned();
+
+        let mut playlist: Vec<ImageInfo> = match active {
+            Some(ref schedule) => schedule.image_ids.iter()
+                .filter_map(|id| assigned.iter().find(|img| &img.id == id).cloned())
+                .collect(),
+            None if !trusted => assigned.clone(),
+            None => {
+                let schedules = self.schedules.read().await;
+                assigned.iter()
+                    .filter(|img| !schedules.iter().any(|schedule| schedule.image_ids.contains(&img.id)))
+                    .cloned()
+                    .collect()
+            }
+        };
+        if playlist.is_empty() {
+            playlist = assigned;
+        }
+
+        let switched = {
+            let mut current = self.active_schedule.write().await;
+            let switched = current.as_ref().map(|s| &s.id) != active.as_ref().map(|s| &s.id);
+            *current = active.clone();
+            switched
+        };
+        let count = playlist.len();
+        {
+            let mut images = self.images.write().await;
+            let mut current_index = self.current_index.write().await;
+            let on_screen = images.get(*current_index).map(|img| img.id.clone());
+            *images = playlist;
+            if let Some(position) = on_screen.and_then(|id| images.iter().position(|img| img.id == id)) {
+                *current_index = position;
+            }
+            if switched || *current_index >= count {
+                *current_index = 0;
+            }
+        }
+        if !switched {
+            return;
+        }
+
+        let name = active.as_ref().map(|schedule| schedule.name.as_str());
+        info!("🗓️ Daypart schedule {} now playing ({} images)", name.unwrap_or("(none)"), count);
+        if let Some(ref mqtt_client) = *self.mqtt_client.read().await {
+            if let Err(e) = mqtt_client.publish_schedule(name, count).await {
+                error!("Failed to publish schedule change to MQTT: {}", e);
+            }
+        }
+        self.send_status_update().await;
+    }
+
+    // Time left on a wake override, if one is active
+    pub async fn get_wake_remaining(&self) -> Option<Duration> {
+        self.wake_until.read().await
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    // Time left on a timed pause, if one is active
+    pub async fn get_pause_remaining(&self) -> Option<Duration> {
+        self.resume_at.read().await.map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    pub async fn is_restart_requested(&self) -> bool {
+        self.pending_restart.read().await.is_some()
+    }
+
+    pub async fn is_content_safety_enabled(&self) -> bool {
+        self.config.read().await.content_safety
+    }
+
+    pub async fn get_text_profile(&self) -> String {
+        self.config.read().await.text_profile.clone()
+    }
+
+    // (keep-alive refresh seconds, skip redundant writes)
+    pub async fn get_framebuffer_refresh_policy(&self) -> (u64, bool) {
+        let config = self.config.read().await;
+        (config.framebuffer_refresh_secs, config.skip_redundant_writes)
+    }
+
+    // (gamma, white point in kelvin, software brightness percent). Brightness stays at 100
+    // while a backlight is dimming the panel instead.
+    pub async fn get_color_adjustment(&self) -> (f64, u32, u8) {
+        let config = self.config.read().await;
+        let brightness = match *self.brightness.read().await {
+            Some(AppliedBrightness { backlight: true, .. }) => 100,
+            Some(AppliedBrightness { level, .. }) => level,
+            None => config.brightness,
+        };
+        (config.gamma, config.color_temperature, brightness)
+    }
+
+    // (fill mode, "#RRGGBB" color, background image once it has been downloaded)
+    pub async fn get_letterbox(&self) -> (String, String, Option<PathBuf>) {
+        let config = self.config.read().await;
+        let image = config.letterbox_image.as_ref()
+            .filter(|id| is_safe_file_component(id))
+            .map(|id| config.image_dir.join(LETTERBOX_DIR).join(id))
+            .filter(|path| path.exists());
+        (config.letterbox_fill.clone(), config.letterbox_color.clone(), image)
+    }
+
+    pub async fn get_safe_area(&self) -> SafeArea {
+        self.config.read().await.safe_area
+    }
+
+    pub async fn get_ken_burns(&self) -> (String, f64) {
+        let config = self.config.read().await;
+        (config.ken_burns.clone(), config.ken_burns_intensity)
+    }
+
+    pub async fn get_smart_crop(&self) -> bool {
+        self.config.read().await.smart_crop
+    }
+
+    pub async fn is_shuffle_enabled(&self) -> bool {
+        self.config.read().await.shuffle
+    }
+
+    pub async fn get_transition_easing(&self) -> String {
+        self.config.read().await.transition_easing.clone()
+    }
+
+    // How long a still stays up before the slideshow moves on
+    pub async fn get_display_duration(&self) -> Duration {
+        let config = self.config.read().await;
+        config.display_duration.max(config.min_display_duration)
+    }
+
+    // Seconds extend commands have added to the current slide, if any
+    pub async fn get_slide_extension(&self) -> Option<i64> {
+        let images = self.images.read().await;
+        let current_id = &images.get(*self.current_index.read().await)?.id;
+        match *self.slide_extension.read().await {
+            Some((ref id, seconds)) if id == current_id => Some(seconds),
+            _ => None,
+        }
+    }
+
+    // The display duration as lengthened or shortened for the current slide by extend commands
+    pub async fn get_slide_duration(&self) -> Duration {
+        let base = self.get_display_duration().await;
+        match self.get_slide_extension().await {
+            Some(seconds) => {
+                let millis = base.as_millis() as i64 + seconds * 1000;
+                Duration::from_millis(millis.max(0) as u64)
+            }
+            None => base,
+        }
+    }
+
+    // Shared by every display, so a mirror of the same mode reuses the main display's frames
+    pub fn image_cache(&self) -> ImageCache {
+        self.image_cache.clone()
+    }
+
+    // Fetch the background for the "image" letterbox fill if it isn't on disk yet, and drop
+    // backgrounds that are no longer configured
+    async fn sync_letterbox_image(&self) {
+        let config = self.config.read().await;
+        let dir = config.image_dir.join(LETTERBOX_DIR);
+        let wanted = config.letterbox_image.clone()
+            .filter(|_| config.letterbox_fill.eq_ignore_ascii_case("image"));
+        drop(config);
+        self.sync_side_image(&dir, wanted, "letterbox background").await;
+    }
+
+    async fn sync_curtain_image(&self) {
+        let config = self.config.read().await;
+        let dir = config.image_dir.join(CURTAIN_DIR);
+        let wanted = config.curtain_image.clone();
+        drop(config);
+        self.sync_side_image(&dir, wanted, "curtain image").await;
+    }
+
+    // Keep `dir` holding just the image document `wanted`, downloading it if it isn't there
+    // yet. Files are named by document id alone, since the decoder detects the format from
+    // the contents.
+    async fn sync_side_image(&self, dir: &Path, wanted: Option<String>, what: &str) {
+        let _downloads = self.download_lock.lock().await;
+        let wanted = wanted.filter(|id| is_safe_file_component(id));
+
+        if let Ok(entries) = std::fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                if wanted.as_deref() != Some(&*entry.file_name().to_string_lossy()) {
+                    let _ = std::fs::remove_file(entry.path());
+                }
+            }
+        }
+
+        let image_id = match wanted {
+            Some(image_id) => image_id,
+            None => return,
+        };
+        let local_path = dir.join(&image_id);
+        if local_path.exists() {
+            return;
+        }
+        if let Some(ref couchdb_client) = *self.couchdb_client.read().await {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                error!("Failed to create {}: {}", dir.display(), e);
+                return;
+            }
+            if let Err(e) = couchdb_client.download_image_attachment(&image_id, &local_path.to_string_lossy()).await {
+                error!("Failed to download {} {}: {}", what, image_id, e);
+            }
+        }
+    }
+
+    // What the ticker should show: text sent on the ticker topic, otherwise the feed's headlines
+    pub async fn get_ticker_text(&self) -> Option<String> {
+        if let Some(text) = self.ticker_text.read().await.clone() {
+            return Some(text);
+        }
+        self.ticker_feed.read().await.as_ref().and_then(|feed| feed.headlines.clone())
+    }
+
+    // Fetch the ticker feed when its URL changed, or regardless with `force`. A feed that
+    // can't be fetched keeps its last headlines.
+    async fn refresh_ticker_feed(&self, force: bool) {
+        let url = match self.config.read().await.ticker_rss_url.clone() {
+            Some(url) => url,
+            None => {
+                *self.ticker_feed.write().await = None;
+                return;
+            }
+        };
+        let same_url = self.ticker_feed.read().await.as_ref().is_some_and(|feed| feed.url == url);
+        if same_url && !force {
+            return;
+        }
+
+        match ticker::fetch_headlines(&url).await {
+            Ok(headlines) => {
+                if headlines.is_none() {
+                    info!("📰 Ticker feed {} has no headlines", url);
+                }
+                *self.ticker_feed.write().await = Some(TickerFeed { url, headlines });
+            }
+            Err(e) => {
+                error!("Failed to fetch ticker feed {}: {}", url, e);
+                if !same_url {
+                    *self.ticker_feed.write().await = Some(TickerFeed { url, headlines: None });
+                }
+            }
+        }
+    }
+
+    // Bring the panel to the brightness the dimming schedule calls for right now, or lower
+    // while the PIR sensor sees nobody: through the backlight when there is one, otherwise by
+    // scaling the output in the display loop
+    pub async fn apply_brightness_schedule(&self) {
+        let mut level = {
+            let config = self.config.read().await;
+            let minute = clock::local_minute_of_day();
+            config.dimming_schedule.iter()
+                .find(|window| clock::schedules_trusted() && window.contains(minute))
+                .map_or(config.brightness, |window| window.brightness)
+        };
+        if let Some(idle_level) = motion::idle_brightness() {
+            if self.is_unoccupied().await {
+                level = level.min(idle_level);
+            }
+        }
+
+        let mut applied = self.brightness.write().await;
+        if applied.is_some_and(|applied| applied.level == level) {
+            return;
+        }
+
+        let backlight = match self.backlight {
+            Some(ref backlight) => match backlight.set_percent(level) {
+                Ok(()) => true,
+                Err(e) => {
+                    error!("Failed to set backlight {}, dimming in software: {}", backlight.name(), e);
+                    false
+                }
+            },
+            None => false,
+        };
+        info!("💡 Brightness set to {}%{}", level, if backlight { " (backlight)" } else { "" });
+        *applied = Some(AppliedBrightness { level, backlight });
+    }
+
     pub async fn run_periodic_tasks(&self) {
         let mut interval = tokio::time::interval(Duration::from_secs(300)); // 5 minutes
+        let mut schedule_interval = tokio::time::interval(Duration::from_secs(30));
         
         loop {
-            interval.tick().await;
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = schedule_interval.tick() => {
+                    self.apply_brightness_schedule().await;
+                    self.refresh_playlist().await;
+                    self.refresh_ticker_feed(false).await;
+                    self.refresh_web_pages().await;
+                    self.refresh_text_slides().await;
+                    continue;
+                }
+            }
             
+            // Free space if something else has been filling the SD card
+            let _ = self.make_room_on_disk().await;
+
             // Periodically sync config from CouchDB
             if let Some(ref couchdb_client) = *self.couchdb_client.read().await {
                 let config = self.config.read().await;
@@ -533,25 +2089,36 @@ impl SlideshowController {
                 drop(config);
                 
                 if let Ok(Some(tv_config)) = couchdb_client.get_tv_config(&tv_id).await {
+                    let brightness_error = invalid_brightness(Some(tv_config.brightness), Some(&tv_config.dimming_schedule));
                     let mut config = self.config.write().await;
                     let old_orientation = config.orientation.clone();
                     let old_transition = config.transition_effect.clone();
-                    config.display_duration = Duration::from_millis(tv_config.display_duration);
-                    config.orientation = tv_config.orientation.clone();
-                    config.transition_effect = tv_config.transition_effect.clone();
+                    apply_tv_config(&mut config, &tv_config, brightness_error.is_none());
                     
                     if old_orientation != tv_config.orientation {
-                        println!("🔄 COUCHDB CONFIG SYNC: Orientation changed from {} to {}", old_orientation, tv_config.orientation);
+                        info!("🔄 COUCHDB CONFIG SYNC: Orientation changed from {} to {}", old_orientation, tv_config.orientation);
+                    }
+                    let transition_changed = old_transition != tv_config.transition_effect;
+                    if transition_changed {
+                        info!("🔄 COUCHDB CONFIG SYNC: Transition effect changed from {} to {}", old_transition, tv_config.transition_effect);
+                    }
+                    drop(config);
+                    if let Some(message) = brightness_error {
+                        self.reject_config(&message).await;
                     }
-                    if old_transition != tv_config.transition_effect {
-                        println!("🔄 COUCHDB CONFIG SYNC: Transition effect changed from {} to {}", old_transition, tv_config.transition_effect);
+                    if transition_changed {
+                        self.check_transition_effect(&tv_config.transition_effect).await;
                     }
                 }
             }
             
+            self.sync_letterbox_image().await;
+            self.sync_curtain_image().await;
+            self.refresh_ticker_feed(true).await;
+            
             // Periodically sync with CouchDB
             if let Err(e) = self.fetch_images_from_couchdb().await {
-                eprintln!("Failed to sync with CouchDB: {}", e);
+                error!("Failed to sync with CouchDB: {}", e);
             }
             
             // Send status update
@@ -566,14 +2133,14 @@ impl SlideshowController {
         let existing_orientation = if let Some(ref couchdb_client) = *self.couchdb_client.read().await {
             let tv_id = format!("tv_{}", config.tv_id);
             if let Ok(Some(tv_config)) = couchdb_client.get_tv_config(&tv_id).await {
-                println!("Found existing TV config, preserving orientation: {}", tv_config.orientation);
+                info!("Found existing TV config, preserving orientation: {}", tv_config.orientation);
                 tv_config.orientation
             } else {
-                println!("No existing TV config found, using default orientation: {}", config.orientation);
+                info!("No existing TV config found, using default orientation: {}", config.orientation);
                 config.orientation.clone()
             }
         } else {
-            println!("No CouchDB client available, using current orientation: {}", config.orientation);
+            info!("No CouchDB client available, using current orientation: {}", config.orientation);
             config.orientation.clone()
         };
         
@@ -623,7 +2190,7 @@ impl SlideshowController {
             .build()?;
             
         let registration_url = format!("{}/api/tvs/register", management_url);
-        println!("Registering TV with management system at {}", registration_url);
+        info!("Registering TV with management system at {}", registration_url);
         
         let response = client
             .post(&registration_url)
@@ -635,9 +2202,9 @@ impl SlideshowController {
             let result: serde_json::Value = response.json().await?;
             let is_new = result["isNew"].as_bool().unwrap_or(false);
             if is_new {
-                println!("Successfully registered as new TV: {}", config.tv_id);
+                info!("Successfully registered as new TV: {}", config.tv_id);
             } else {
-                println!("Successfully re-registered existing TV: {} (preserved orientation: {})", config.tv_id, existing_orientation);
+                info!("Successfully re-registered existing TV: {} (preserved orientation: {})", config.tv_id, existing_orientation);
             }
         } else {
             let status = response.status();
@@ -686,4 +2253,112 @@ impl SlideshowController {
         
         None
     }
-}
\ No newline at end of file
+}
+
+// The playlist's weights for the weighted order, or None when every image has the default
+// weight and the playlist simply plays in order
+fn weights_of(images: &[ImageInfo]) -> Option<Vec<u32>> {
+    let weights: Vec<u32> = images.iter().map(|img| img.weight.unwrap_or(1).max(1)).collect();
+    weights.iter().any(|&weight| weight != 1).then_some(weights)
+}
+
+// The TV document's settings, but for those set locally (on the command line, in SIGNAGE_*
+// variables or in the config file), which win over it. Content safety set locally can still
+// be turned on from CouchDB but never off, so an accessibility requirement on the device holds.
+fn apply_tv_config(config: &mut ControllerConfig, tv_config: &TvConfig, apply_brightness: bool) {
+    let local = config_file::is_explicit;
+    config.display_duration = Duration::from_millis(tv_config.display_duration);
+    config.orientation = tv_config.orientation.clone();
+    config.transition_effect = tv_config.transition_effect.clone();
+    if local("content_safety") {
+        config.content_safety |= tv_config.content_safety;
+    } else {
+        config.content_safety = tv_config.content_safety;
+    }
+    if !local("text_profile") {
+        config.text_profile = tv_config.text_profile.clone();
+    }
+    if !local("framebuffer_refresh_secs") {
+        config.framebuffer_refresh_secs = tv_config.framebuffer_refresh_secs;
+    }
+    if !local("skip_redundant_writes") {
+        config.skip_redundant_writes = tv_config.skip_redundant_writes;
+    }
+    if !local("gamma") {
+        config.gamma = tv_config.gamma;
+    }
+    if !local("color_temperature") {
+        config.color_temperature = tv_config.color_temperature;
+    }
+    if apply_brightness {
+        if !local("brightness") {
+            config.brightness = tv_config.brightness;
+        }
+        config.dimming_schedule = tv_config.dimming_schedule.clone();
+    }
+    config.quiet_hours = tv_config.quiet_hours.clone();
+    config.curtain_hours = tv_config.curtain_hours.clone();
+    config.curtain_color = tv_config.curtain_color.clone();
+    config.curtain_image = tv_config.curtain_image.clone();
+    config.letterbox_fill = tv_config.letterbox_fill.clone();
+    config.letterbox_color = tv_config.letterbox_color.clone();
+    config.letterbox_image = tv_config.letterbox_image.clone();
+    config.safe_area = tv_config.safe_area;
+    config.ken_burns = tv_config.ken_burns.clone();
+    config.ken_burns_intensity = tv_config.ken_burns_intensity;
+    config.smart_crop = tv_config.smart_crop;
+    config.transition_easing = tv_config.transition_easing.clone();
+    config.transition_durations = tv_config.transition_durations.clone();
+    if config.synced_shuffle != Some(tv_config.shuffle) {
+        config.shuffle = tv_config.shuffle;
+        config.synced_shuffle = Some(tv_config.shuffle);
+    }
+    config.ticker_rss_url = tv_config.ticker_rss_url.clone();
+    config.tags = tv_config.tags.clone();
+}
+
+// Brightness levels from MQTT or CouchDB, held to the range the HTTP API accepts
+fn invalid_brightness(brightness: Option<u8>, dimming_schedule: Option<&[DimmingWindow]>) -> Option<String> {
+    let range = color::BRIGHTNESS_RANGE;
+    if let Some(brightness) = brightness.filter(|brightness| !range.contains(brightness)) {
+        return Some(format!("brightness {}% is not between {}% and {}%", brightness, range.start(), range.end()));
+    }
+    dimming_schedule.unwrap_or_default().iter()
+        .find(|window| !range.contains(&window.brightness))
+        .map(|window| format!("dimming window {}-{} brightness {}% is not between {}% and {}%",
+                              window.start, window.end, window.brightness, range.start(), range.end()))
+}
+
+// A quarantined image whose revision has moved on since is taken out of quarantine, so the
+// new content is downloaded and tried
+fn is_quarantined(image_dir: &Path, file_name: &str, revision: Option<&str>) -> bool {
+    let quarantined = image_dir.join(QUARANTINE_DIR).join(file_name);
+    if !quarantined.exists() {
+        return false;
+    }
+    let marker = image_dir.join(QUARANTINE_DIR).join(format!("{}{}", file_name, QUARANTINE_REVISION_SUFFIX));
+    match (revision, std::fs::read_to_string(&marker)) {
+        (Some(revision), Ok(quarantined_at)) if quarantined_at != revision => {
+            info!("🔁 Image {} changed since it was quarantined, trying it again", file_name);
+            let _ = std::fs::remove_file(&quarantined);
+            let _ = std::fs::remove_file(&marker);
+            false
+        }
+        _ => true,
+    }
+}
+
+// Whether `now` falls within the image's valid_from/valid_until window. A bound that isn't an
+// RFC 3339 time is ignored rather than taking the image off the screen.
+fn is_within_validity(image: &ImageInfo, now: DateTime<Utc>) -> bool {
+    let bound = |value: &Option<String>| {
+        value.as_deref().and_then(|value| match DateTime::parse_from_rfc3339(value) {
+            Ok(time) => Some(time.with_timezone(&Utc)),
+            Err(e) => {
+                warn!("Ignoring validity time {:?} on image {}: {}", value, image.id, e);
+                None
+            }
+        })
+    };
+    bound(&image.valid_from).is_none_or(|from| now >= from) && bound(&image.valid_until).is_none_or(|until| now < until)
+}
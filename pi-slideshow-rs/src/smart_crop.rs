@@ -0,0 +1,99 @@
+use image::RgbaImage;
+
+// Content-aware placement of a cover-fit crop (TvConfig smart_crop). Cropping from the
+// center cuts the heads off portrait photos shown on landscape screens; instead the crop
+// goes where the picture is busiest. Saliency is estimated on a small copy of the image as
+// edge strength plus how far each color stands out from the image's average color.
+
+// Longest side of the copy saliency is measured on
+const SALIENCY_SIZE: u32 = 64;
+// The crop only leaves the center when somewhere else is this much more salient, so
+// evenly busy pictures (and flat ones) stay centered
+const CENTER_PREFERENCE: f32 = 1.1;
+
+// Top-left corner of the `crop_width` x `crop_height` window of `img` to keep
+pub fn crop_offset(img: &RgbaImage, crop_width: u32, crop_height: u32) -> (u32, u32) {
+    let (width, height) = img.dimensions();
+    let centered = ((width - crop_width) / 2, (height - crop_height) / 2);
+    if width <= crop_width && height <= crop_height {
+        return centered;
+    }
+
+    let scale = SALIENCY_SIZE as f32 / width.max(height) as f32;
+    let small_width = ((width as f32 * scale).round() as u32).max(1);
+    let small_height = ((height as f32 * scale).round() as u32).max(1);
+    let small = image::imageops::resize(img, small_width, small_height, image::imageops::FilterType::Triangle);
+    let saliency = saliency_map(&small);
+
+    // Cover fit only ever crops one axis, so the saliency is collapsed onto it
+    if width > crop_width {
+        let columns: Vec<f32> = (0..small_width)
+            .map(|x| (0..small_height).map(|y| saliency[(y * small_width + x) as usize]).sum())
+            .collect();
+        let window = ((crop_width as f32 * scale).round() as usize).clamp(1, columns.len());
+        let start = best_window(&columns, window);
+        let x = (start as f32 / scale).round() as u32;
+        (x.min(width - crop_width), centered.1)
+    } else {
+        let rows: Vec<f32> = (0..small_height)
+            .map(|y| (0..small_width).map(|x| saliency[(y * small_width + x) as usize]).sum())
+            .collect();
+        let window = ((crop_height as f32 * scale).round() as usize).clamp(1, rows.len());
+        let start = best_window(&rows, window);
+        let y = (start as f32 / scale).round() as u32;
+        (centered.0, y.min(height - crop_height))
+    }
+}
+
+fn saliency_map(img: &RgbaImage) -> Vec<f32> {
+    let (width, height) = img.dimensions();
+    let pixel_count = (width * height) as f32;
+    let mut mean = [0.0f32; 3];
+    for pixel in img.pixels() {
+        for (c, mean) in mean.iter_mut().enumerate() {
+            *mean += pixel[c] as f32 / pixel_count;
+        }
+    }
+
+    let luma = |x: u32, y: u32| {
+        let p = img.get_pixel(x, y);
+        0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32
+    };
+
+    let mut saliency = Vec::with_capacity((width * height) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let pixel = img.get_pixel(x, y);
+            let contrast = (0..3)
+                .map(|c| (pixel[c] as f32 - mean[c]).powi(2))
+                .sum::<f32>()
+                .sqrt();
+            let here = luma(x, y);
+            let edge = (luma((x + 1).min(width - 1), y) - here).abs() + (luma(x, (y + 1).min(height - 1)) - here).abs();
+            saliency.push(contrast + edge);
+        }
+    }
+    saliency
+}
+
+// Start of the `window`-long run of `values` with the largest sum, or of the centered run
+// unless another beats it by CENTER_PREFERENCE
+fn best_window(values: &[f32], window: usize) -> usize {
+    let centered = (values.len() - window) / 2;
+    let mut sum: f32 = values[..window].iter().sum();
+    let mut sums = vec![sum];
+    for start in 1..=values.len() - window {
+        sum += values[start + window - 1] - values[start - 1];
+        sums.push(sum);
+    }
+
+    let (best, best_sum) = sums
+        .iter()
+        .enumerate()
+        .fold((centered, sums[centered]), |best, (start, &sum)| if sum > best.1 { (start, sum) } else { best });
+    if best_sum > sums[centered] * CENTER_PREFERENCE {
+        best
+    } else {
+        centered
+    }
+}
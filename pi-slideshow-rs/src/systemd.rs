@@ -0,0 +1,59 @@
+use sd_notify::NotifyState;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+// Notifications for a Type=notify systemd unit: READY=1 once the slideshow is up, WATCHDOG=1
+// from the display loop, and STOPPING=1 on shutdown. With WatchdogSec= set, systemd kills and
+// restarts an endpoint whose display loop stops pinging, a backstop for a hang the in-process
+// watchdog can't get out of (one holding up its restart, say). Outside systemd, or in a unit
+// without them, these do nothing.
+
+static START: OnceLock<Instant> = OnceLock::new();
+// Milliseconds after START of the last watchdog ping
+static LAST_PING_MS: AtomicU64 = AtomicU64::new(NEVER);
+const NEVER: u64 = u64::MAX;
+// Half of WatchdogSec=, as sd_watchdog_enabled(3) suggests; None without a watchdog
+static PING_INTERVAL: OnceLock<Option<Duration>> = OnceLock::new();
+
+fn notify(state: NotifyState, name: &str) {
+    if let Err(e) = sd_notify::notify(false, &[state]) {
+        error!("Failed to send {} to systemd: {}", name, e);
+    }
+}
+
+fn ping_interval() -> Option<Duration> {
+    *PING_INTERVAL.get_or_init(|| {
+        let mut usec = 0;
+        sd_notify::watchdog_enabled(false, &mut usec).then(|| Duration::from_micros(usec / 2))
+    })
+}
+
+fn elapsed_ms() -> u64 {
+    START.get_or_init(Instant::now).elapsed().as_millis() as u64
+}
+
+pub fn ready() {
+    notify(NotifyState::Ready, "READY=1");
+    if let Some(interval) = ping_interval() {
+        info!("🐕 systemd watchdog on, pinging every {}s", interval.as_secs());
+    }
+    watchdog();
+}
+
+// Called by the display loop on every pass; pings no more often than needed
+pub fn watchdog() {
+    let Some(interval) = ping_interval() else { return };
+    let now = elapsed_ms();
+    let last = LAST_PING_MS.load(Ordering::Relaxed);
+    if last != NEVER && now.saturating_sub(last) < interval.as_millis() as u64 {
+        return;
+    }
+    LAST_PING_MS.store(now, Ordering::Relaxed);
+    notify(NotifyState::Watchdog, "WATCHDOG=1");
+}
+
+pub fn stopping() {
+    notify(NotifyState::Stopping, "STOPPING=1");
+}
@@ -0,0 +1,69 @@
+use image::{Rgba, RgbaImage};
+use std::path::PathBuf;
+
+use crate::text_profile::TextProfile;
+use crate::{draw_text, text_char_size, wrap_text};
+
+// Emergency takeover: content sent with the `takeover` command replaces the slideshow on every
+// display, ahead of test patterns and quiet hours, until a `release` command or its timeout.
+// The playlist doesn't move in the meantime, so the slideshow comes back on the slide it left.
+
+// Longest message accepted, in characters; more would not fit on a small panel anyway
+pub const MAX_TEXT_CHARS: usize = 500;
+
+const ALERT_RED: Rgba<u8> = Rgba([176, 0, 0, 255]);
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+// What a takeover command asks for
+#[derive(Debug, Clone, PartialEq)]
+pub enum Takeover {
+    // An uploaded image, by id
+    Image(String),
+    // A message drawn on an alert background
+    Text(String),
+}
+
+impl Takeover {
+    pub fn describe(&self) -> String {
+        match self {
+            Takeover::Image(id) => format!("image {}", id),
+            Takeover::Text(text) => format!("message {:?}", text),
+        }
+    }
+}
+
+// What the display loop draws for a takeover
+#[derive(Debug, Clone, PartialEq)]
+pub enum Screen {
+    // A local copy of the image
+    Image(PathBuf),
+    Text(String),
+}
+
+// The message in large type, wrapped and centered on red (black in high contrast)
+pub fn render_text(text: &str, width: u32, height: u32, text_profile: &TextProfile) -> RgbaImage {
+    let background = text_profile.background(ALERT_RED);
+    let color = text_profile.foreground(WHITE, background);
+    let mut image = RgbaImage::from_pixel(width, height, background);
+
+    // Start large and shrink until the wrapped message fits the screen
+    let min_char_size = text_profile.char_size(1);
+    let mut char_size = text_profile.char_size(text_char_size(16, width, height));
+    let lines = loop {
+        let max_chars_per_line = (width / (8 * char_size)).max(1) as usize;
+        let lines = wrap_text(text, max_chars_per_line);
+        if char_size <= min_char_size || lines.len() as u32 * 6 * char_size <= height {
+            break lines;
+        }
+        char_size -= 1;
+    };
+
+    let line_height = 6 * char_size;
+    let start_y = height.saturating_sub(lines.len() as u32 * line_height) / 2;
+    for (index, line) in lines.iter().enumerate() {
+        let line_width = line.chars().count() as u32 * 8 * char_size;
+        let x = width.saturating_sub(line_width) / 2;
+        draw_text(&mut image, line, x, start_y + index as u32 * line_height, char_size, color);
+    }
+    image
+}
@@ -0,0 +1,191 @@
+use image::{Rgba, RgbaImage};
+
+use crate::{draw_text, text_char_size};
+
+// Calibration screens for installers, rendered at the display's own resolution so color,
+// scaling, orientation and overscan can be checked without loading any content
+
+const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const GRAY: Rgba<u8> = Rgba([128, 128, 128, 255]);
+
+// 75% bars, left to right
+const SMPTE_BARS: [Rgba<u8>; 7] = [
+    Rgba([191, 191, 191, 255]),
+    Rgba([191, 191, 0, 255]),
+    Rgba([0, 191, 191, 255]),
+    Rgba([0, 191, 0, 255]),
+    Rgba([191, 0, 191, 255]),
+    Rgba([191, 0, 0, 255]),
+    Rgba([0, 0, 191, 255]),
+];
+// The strip under the bars: each bar's complement for checking hue and chroma
+const SMPTE_CASTELLATIONS: [Rgba<u8>; 7] = [
+    Rgba([0, 0, 191, 255]),
+    BLACK,
+    Rgba([191, 0, 191, 255]),
+    BLACK,
+    Rgba([0, 191, 191, 255]),
+    BLACK,
+    Rgba([191, 191, 191, 255]),
+];
+// -I, 100% white, +Q, black
+const SMPTE_BOTTOM: [Rgba<u8>; 4] = [Rgba([0, 33, 76, 255]), WHITE, Rgba([50, 0, 106, 255]), BLACK];
+// PLUGE: below black, black, 4% above black. Full-range RGB can't go below black, so with
+// brightness set right the first two match and the third is just visible.
+const SMPTE_PLUGE: [Rgba<u8>; 3] = [BLACK, BLACK, Rgba([10, 10, 10, 255])];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestPattern {
+    // SMPTE color bars, for color and black level
+    Bars,
+    // One-pixel grid and checkerboard, which any scaling on the way to the panel smears
+    Grid,
+    // Title-safe and action-safe outlines, for overscan
+    Overscan,
+}
+
+impl TestPattern {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "bars" | "smpte" => Some(TestPattern::Bars),
+            "grid" => Some(TestPattern::Grid),
+            "overscan" | "corners" | "safe_area" => Some(TestPattern::Overscan),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TestPattern::Bars => "bars",
+            TestPattern::Grid => "grid",
+            TestPattern::Overscan => "overscan",
+        }
+    }
+
+    // Every pattern gets a one-pixel white border, colored corner markers and its size
+    // printed in the middle, so cropping and orientation show whatever is being checked
+    pub fn render(&self, width: u32, height: u32) -> RgbaImage {
+        let mut image = match self {
+            TestPattern::Bars => smpte_bars(width, height),
+            TestPattern::Grid => pixel_grid(width, height),
+            TestPattern::Overscan => safe_areas(width, height),
+        };
+        draw_corner_markers(&mut image);
+        draw_label(&mut image, &format!("{} {}x{}", self.name(), width, height));
+        image
+    }
+}
+
+fn smpte_bars(width: u32, height: u32) -> RgbaImage {
+    let castellation_top = height * 67 / 100;
+    let bottom_top = height * 75 / 100;
+    RgbaImage::from_fn(width, height, |x, y| {
+        // Position in bar widths, 0.0 to 7.0
+        let bar = x as f32 * 7.0 / width as f32;
+        if y < castellation_top {
+            SMPTE_BARS[(bar as usize).min(6)]
+        } else if y < bottom_top {
+            SMPTE_CASTELLATIONS[(bar as usize).min(6)]
+        } else if bar < 5.0 {
+            // Four blocks of 1.25 bars, then PLUGE under the fifth bar
+            SMPTE_BOTTOM[((bar / 1.25) as usize).min(3)]
+        } else if bar < 6.0 {
+            SMPTE_PLUGE[(((bar - 5.0) * 3.0) as usize).min(2)]
+        } else {
+            BLACK
+        }
+    })
+}
+
+fn pixel_grid(width: u32, height: u32) -> RgbaImage {
+    let mut image = RgbaImage::from_fn(width, height, |x, y| {
+        if x % 100 == 0 || y % 100 == 0 {
+            WHITE
+        } else if x % 10 == 0 || y % 10 == 0 {
+            GRAY
+        } else {
+            BLACK
+        }
+    });
+
+    // Alternating single pixels read as flat gray at a glance, and turn into moiré or blur
+    // when anything between here and the panel rescales the picture
+    let size = (width.min(height) / 5).max(1);
+    let (left, top) = ((width - size) / 2, (height - size) / 2);
+    for y in top..top + size {
+        for x in left..left + size {
+            image.put_pixel(x, y, if (x + y) % 2 == 0 { WHITE } else { BLACK });
+        }
+    }
+    image
+}
+
+// Outlines 5% (action safe) and 10% (title safe) in from each edge
+fn safe_areas(width: u32, height: u32) -> RgbaImage {
+    let mut image = RgbaImage::from_pixel(width, height, Rgba([32, 32, 32, 255]));
+    for (percent, color) in [(5, Rgba([255, 255, 0, 255])), (10, Rgba([0, 255, 255, 255]))] {
+        let (inset_x, inset_y) = (width * percent / 100, height * percent / 100);
+        draw_rect_outline(&mut image, inset_x, inset_y, width - 2 * inset_x, height - 2 * inset_y, color);
+    }
+    image
+}
+
+fn draw_rect_outline(image: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: Rgba<u8>) {
+    if width == 0 || height == 0 {
+        return;
+    }
+    for dx in 0..width {
+        image.put_pixel(x + dx, y, color);
+        image.put_pixel(x + dx, y + height - 1, color);
+    }
+    for dy in 0..height {
+        image.put_pixel(x, y + dy, color);
+        image.put_pixel(x + width - 1, y + dy, color);
+    }
+}
+
+// White outermost pixels, plus an L in each corner: red top left, green top right, blue
+// bottom left, white bottom right. A mirrored or rotated picture swaps the colors around.
+fn draw_corner_markers(image: &mut RgbaImage) {
+    let (width, height) = image.dimensions();
+    draw_rect_outline(image, 0, 0, width, height, WHITE);
+
+    let length = (width.min(height) / 8).max(1);
+    let thickness = (width.min(height) / 100).max(2).min(length);
+    let corners = [
+        (0, 0, Rgba([255, 0, 0, 255])),
+        (width - length, 0, Rgba([0, 255, 0, 255])),
+        (0, height - length, Rgba([0, 0, 255, 255])),
+        (width - length, height - length, WHITE),
+    ];
+    for (left, top, color) in corners {
+        // Both arms of the L run along the screen edges
+        let arm_x = if left == 0 { 0 } else { length - thickness };
+        let arm_y = if top == 0 { 0 } else { length - thickness };
+        for y in 0..length {
+            for x in 0..length {
+                if x >= arm_x && x < arm_x + thickness || y >= arm_y && y < arm_y + thickness {
+                    image.put_pixel(left + x, top + y, color);
+                }
+            }
+        }
+    }
+}
+
+fn draw_label(image: &mut RgbaImage, text: &str) {
+    let (width, height) = image.dimensions();
+    let char_size = text_char_size(8, width, height);
+    let text_width = text.len() as u32 * 8 * char_size;
+    let padding = char_size * 2;
+    let box_width = (text_width + padding * 2).min(width);
+    let box_height = (5 * char_size + padding * 2).min(height);
+    let (left, top) = ((width - box_width) / 2, (height - box_height) / 2);
+
+    for y in top..top + box_height {
+        for x in left..left + box_width {
+            image.put_pixel(x, y, BLACK);
+        }
+    }
+    draw_text(image, text, left + padding, top + padding, char_size, WHITE);
+}
@@ -0,0 +1,75 @@
+use image::Rgba;
+use crate::content_safety::relative_luminance;
+
+// WCAG AAA contrast ratio for normal-size text
+pub const HIGH_CONTRAST_MIN_RATIO: f32 = 7.0;
+// Smallest glyph block size (pixels per font cell) in the high-contrast profile
+pub const HIGH_CONTRAST_MIN_CHAR_SIZE: u32 = 10;
+
+const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextProfile {
+    Standard,
+    HighContrast,
+}
+
+impl From<&str> for TextProfile {
+    fn from(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "high_contrast" | "high-contrast" | "accessibility" => TextProfile::HighContrast,
+            _ => TextProfile::Standard,
+        }
+    }
+}
+
+impl TextProfile {
+    pub fn is_valid(s: &str) -> bool {
+        matches!(
+            s.to_lowercase().as_str(),
+            "standard" | "high_contrast" | "high-contrast" | "accessibility"
+        )
+    }
+
+    // Glyph size to use for text that was designed at `requested`
+    pub fn char_size(&self, requested: u32) -> u32 {
+        match self {
+            TextProfile::Standard => requested,
+            TextProfile::HighContrast => requested.max(HIGH_CONTRAST_MIN_CHAR_SIZE),
+        }
+    }
+
+    // Backgrounds behind generated text are forced to solid black in high contrast
+    pub fn background(&self, requested: Rgba<u8>) -> Rgba<u8> {
+        match self {
+            TextProfile::Standard => requested,
+            TextProfile::HighContrast => BLACK,
+        }
+    }
+
+    // Keep the requested text color if it already meets the contrast target against
+    // `background`, otherwise fall back to whichever of black or white contrasts best
+    pub fn foreground(&self, requested: Rgba<u8>, background: Rgba<u8>) -> Rgba<u8> {
+        match self {
+            TextProfile::Standard => requested,
+            TextProfile::HighContrast => {
+                if contrast_ratio(&requested, &background) >= HIGH_CONTRAST_MIN_RATIO {
+                    requested
+                } else if contrast_ratio(&WHITE, &background) >= contrast_ratio(&BLACK, &background) {
+                    WHITE
+                } else {
+                    BLACK
+                }
+            }
+        }
+    }
+}
+
+// WCAG 2.x contrast ratio between two colors, from 1.0 (identical) to 21.0 (black on white)
+pub fn contrast_ratio(a: &Rgba<u8>, b: &Rgba<u8>) -> f32 {
+    let la = relative_luminance(a);
+    let lb = relative_luminance(b);
+    let (lighter, darker) = if la > lb { (la, lb) } else { (lb, la) };
+    (lighter + 0.05) / (darker + 0.05)
+}
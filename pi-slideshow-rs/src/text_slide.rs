@@ -0,0 +1,146 @@
+use chrono::{DateTime, Utc};
+use image::{Rgba, RgbaImage};
+use signage_protocol::couchdb::parse_hex_color;
+use signage_protocol::TextSlide;
+
+use crate::text_profile::TextProfile;
+use crate::{draw_text, text_char_size, wrap_text};
+
+// Text slides: announcements posted as "text_slide" documents instead of image files. The TV
+// draws each one as a card the size of its screen and keeps it as a PNG in the image
+// directory, where it plays like any image. A changed document, screen size or text profile
+// draws it again. Countdown slides are also drawn straight to the screen each second while
+// they are up, by the display loop.
+
+// Stands for the time left (or since) in a countdown slide's title or body
+const COUNTDOWN_PLACEHOLDER: &str = "{countdown}";
+
+// Space kept clear around the text, as a share of the shorter side
+const MARGIN: f32 = 0.06;
+// Glyph sizes on a 1080-line screen, before shrinking to fit
+const TITLE_CHAR_SIZE: u32 = 12;
+const BODY_CHAR_SIZE: u32 = 6;
+// Most of the height the title may take
+const TITLE_SHARE: f32 = 0.4;
+
+const DEFAULT_BACKGROUND: Rgba<u8> = Rgba([30, 58, 95, 255]);
+const DEFAULT_TEXT: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Template {
+    Centered,
+    Left,
+    Banner,
+}
+
+impl Template {
+    fn from_name(name: &str) -> Self {
+        match name {
+            "left" => Template::Left,
+            "banner" => Template::Banner,
+            _ => Template::Centered,
+        }
+    }
+}
+
+pub fn render(slide: &TextSlide, width: u32, height: u32, text_profile: &TextProfile) -> RgbaImage {
+    let background = text_profile.background(color(&slide.background_color, DEFAULT_BACKGROUND));
+    let foreground = text_profile.foreground(color(&slide.text_color, DEFAULT_TEXT), background);
+    let template = Template::from_name(&slide.template);
+    let mut image = RgbaImage::from_pixel(width, height, background);
+
+    let margin = (width.min(height) as f32 * MARGIN) as u32;
+    let text_width = width.saturating_sub(2 * margin).max(1);
+    let text_height = height.saturating_sub(2 * margin).max(1);
+    let min_char_size = text_profile.char_size(1);
+
+    let title_start = text_profile.char_size(text_char_size(TITLE_CHAR_SIZE, width, height));
+    let (title_size, title) = fit(&slide.title, title_start, min_char_size, text_width, (text_height as f32 * TITLE_SHARE) as u32);
+    let title_height = title.len() as u32 * 6 * title_size;
+
+    // The body gets what the title leaves, and is never set larger than the title
+    let body_top = match template {
+        Template::Banner if !title.is_empty() => title_height + 3 * margin,
+        _ if !title.is_empty() => margin + title_height + margin / 2,
+        _ => margin,
+    };
+    let body_start = text_profile.char_size(text_char_size(BODY_CHAR_SIZE, width, height)).min(title_size);
+    let (body_size, body) = fit(&slide.body, body_start, min_char_size, text_width, height.saturating_sub(body_top + margin));
+    let body_height = body.len() as u32 * 6 * body_size;
+
+    match template {
+        Template::Banner => {
+            // The title on a band of the text color, reversed out of it
+            if !title.is_empty() {
+                let band = RgbaImage::from_pixel(width, title_height + 2 * margin, foreground);
+                image::imageops::replace(&mut image, &band, 0, 0);
+                draw_lines(&mut image, &title, title_size, margin, margin, None, background);
+            }
+            draw_lines(&mut image, &body, body_size, margin, body_top, None, foreground);
+        }
+        Template::Centered | Template::Left => {
+            // Title and body as one block, centered vertically
+            let gap = body_top - margin - title_height;
+            let top = margin + text_height.saturating_sub(title_height + gap + body_height) / 2;
+            let centered_in = (template == Template::Centered).then_some(text_width);
+            draw_lines(&mut image, &title, title_size, margin, top, centered_in, foreground);
+            draw_lines(&mut image, &body, body_size, margin, top + title_height + gap, centered_in, foreground);
+        }
+    }
+    image
+}
+
+// A countdown slide as it reads at `now`, or None when the slide isn't a countdown (or its
+// countdown_to doesn't parse). Without the placeholder, the time goes under the title.
+pub fn with_countdown(slide: &TextSlide, now: DateTime<Utc>) -> Option<TextSlide> {
+    let target = DateTime::parse_from_rfc3339(slide.countdown_to.as_deref()?).ok()?;
+    let time = format_countdown((target.with_timezone(&Utc) - now).num_seconds().unsigned_abs());
+    let mut shown = slide.clone();
+    if slide.title.contains(COUNTDOWN_PLACEHOLDER) || slide.body.contains(COUNTDOWN_PLACEHOLDER) {
+        shown.title = slide.title.replace(COUNTDOWN_PLACEHOLDER, &time);
+        shown.body = slide.body.replace(COUNTDOWN_PLACEHOLDER, &time);
+    } else if slide.title.is_empty() {
+        shown.title = time;
+    } else {
+        shown.title = format!("{}\n{}", slide.title, time);
+    }
+    Some(shown)
+}
+
+// "00:42:13", with the days in front from a day on: "3d 04:05:06"
+fn format_countdown(seconds: u64) -> String {
+    let (days, seconds) = (seconds / 86_400, seconds % 86_400);
+    let clock = format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if days > 0 {
+        format!("{}d {}", days, clock)
+    } else {
+        clock
+    }
+}
+
+// `text` wrapped at the largest glyph size from `start` down whose lines fit `width` by
+// `height`, keeping its own line breaks
+fn fit(text: &str, start: u32, min: u32, width: u32, height: u32) -> (u32, Vec<String>) {
+    let mut char_size = start.max(min);
+    loop {
+        let max_chars_per_line = (width / (8 * char_size)).max(1) as usize;
+        let lines: Vec<String> = text.lines().flat_map(|paragraph| wrap_text(paragraph, max_chars_per_line)).collect();
+        if char_size <= min || lines.len() as u32 * 6 * char_size <= height {
+            return (char_size, lines);
+        }
+        char_size -= 1;
+    }
+}
+
+// Lines from (x, y) down, each centered in `centered_in` pixels when given
+fn draw_lines(image: &mut RgbaImage, lines: &[String], char_size: u32, x: u32, y: u32, centered_in: Option<u32>, color: Rgba<u8>) {
+    for (index, line) in lines.iter().enumerate() {
+        let line_width = line.chars().count() as u32 * 8 * char_size;
+        let line_x = x + centered_in.map_or(0, |width| width.saturating_sub(line_width) / 2);
+        draw_text(image, line, line_x, y + index as u32 * 6 * char_size, char_size, color);
+    }
+}
+
+fn color(hex: &str, fallback: Rgba<u8>) -> Rgba<u8> {
+    parse_hex_color(hex).map_or(fallback, |[r, g, b]| Rgba([r, g, b, 255]))
+}
@@ -0,0 +1,93 @@
+use signage_protocol::ThrottleFlags;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+use crate::health;
+use crate::quality;
+use crate::slideshow_controller::SlideshowController;
+
+// Under-voltage and throttling as the Pi's firmware reports them through vcgencmd
+// get_throttled. A weak power supply browns the Pi out under load and a hot one slows its CPU,
+// and full-rate transitions then stutter and make both worse, so while any flag is up
+// transitions drop to plain fades (quality::Mode::FadesOnly), with or without
+// --adaptive-quality. Each flag the first time it is raised is published on the error topic,
+// so the TVs with bad power supplies can be picked out of the fleet, and the flags go out with
+// every heartbeat.
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+static AVAILABLE: AtomicBool = AtomicBool::new(false);
+static BITS: AtomicU32 = AtomicU32::new(0);
+
+// None until vcgencmd has answered
+pub fn flags() -> Option<ThrottleFlags> {
+    AVAILABLE.load(Ordering::Relaxed).then(|| ThrottleFlags::from_bits(BITS.load(Ordering::Relaxed)))
+}
+
+// Start watching the flags. Off a Pi, or without vcgencmd, this stops after the first try.
+// Warnings are published through the controller when there is one.
+pub fn spawn(controller: Option<SlideshowController>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        // Bits already reported, so each is published once per run
+        let mut reported = 0;
+        loop {
+            interval.tick().await;
+            let bits = match get_throttled().await {
+                Ok(bits) => bits,
+                Err(e) if !AVAILABLE.load(Ordering::Relaxed) => {
+                    info!("Not watching for under-voltage or throttling: {}", e);
+                    return;
+                }
+                Err(e) => {
+                    warn!("Failed to read the throttle flags: {}", e);
+                    continue;
+                }
+            };
+            BITS.store(bits, Ordering::Relaxed);
+            AVAILABLE.store(true, Ordering::Relaxed);
+
+            let flags = ThrottleFlags::from_bits(bits);
+            quality::set_throttled(flags.is_active());
+            if bits & !reported == 0 {
+                continue;
+            }
+            reported |= bits;
+            let temperature = health::cpu_temperature();
+            warn!(
+                "🌡️ Firmware reports {} now, {} since boot (CPU {})",
+                describe(&flags.active),
+                describe(&flags.since_boot),
+                temperature.map_or("unknown".to_string(), |t| format!("{:.1}°C", t))
+            );
+            if let Some(ref controller) = controller {
+                controller.report_throttling(&flags, temperature).await;
+            }
+        }
+    });
+}
+
+fn describe(names: &[String]) -> String {
+    if names.is_empty() { "nothing".to_string() } else { names.join(", ") }
+}
+
+async fn get_throttled() -> Result<u32, Box<dyn std::error::Error + Send + Sync>> {
+    let output = tokio::time::timeout(COMMAND_TIMEOUT, Command::new("vcgencmd").arg("get_throttled").kill_on_drop(true).output())
+        .await
+        .map_err(|_| format!("vcgencmd took over {} seconds", COMMAND_TIMEOUT.as_secs()))?
+        .map_err(|e| format!("can't run vcgencmd: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("vcgencmd failed: {}", String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+    parse_throttled(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| format!("unexpected vcgencmd output: {}", String::from_utf8_lossy(&output.stdout).trim()).into())
+}
+
+// "throttled=0x50005"
+fn parse_throttled(output: &str) -> Option<u32> {
+    let hex = output.trim().strip_prefix("throttled=")?;
+    u32::from_str_radix(hex.trim_start_matches("0x"), 16).ok()
+}
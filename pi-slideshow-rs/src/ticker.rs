@@ -0,0 +1,131 @@
+use image::{Rgba, RgbaImage};
+use std::time::{Duration, Instant};
+
+use crate::text_profile::TextProfile;
+use crate::{draw_text, text_char_size, Orientation, Region};
+
+// Scrolling text along the bottom of the screen, from the ticker topic or the headlines of
+// TvConfig ticker_rss_url. The framebuffer paints the band over every full frame it writes
+// and, in between, redraws just the band, so it keeps moving over stills, transitions and
+// videos without holding up anything else.
+
+// Time between band redraws (~30 FPS)
+pub const FRAME_INTERVAL: Duration = Duration::from_millis(33);
+// Longest text accepted on the ticker topic, in characters
+pub const MAX_TEXT_CHARS: usize = 2000;
+// Headlines taken from the top of a feed
+const MAX_HEADLINES: usize = 10;
+const FEED_TIMEOUT: Duration = Duration::from_secs(15);
+// Feeds larger than this are refused rather than parsed
+const MAX_FEED_BYTES: usize = 2 * 1024 * 1024;
+// Between headlines (the built-in font has no bullet)
+const SEPARATOR: &str = "   -   ";
+// Glyph size on a 1080-line screen; the band is 9 glyph units tall
+const CHAR_SIZE: u32 = 5;
+const CHARS_PER_SECOND: f32 = 5.0;
+
+const BACKGROUND: Rgba<u8> = Rgba([20, 20, 20, 255]);
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+#[derive(Debug, Clone)]
+pub struct Ticker {
+    text: String,
+    orientation: Orientation,
+    text_profile: TextProfile,
+    started: Instant,
+    next_frame: Instant,
+}
+
+impl Ticker {
+    pub fn new(text: String, orientation: Orientation, text_profile: TextProfile) -> Self {
+        Self { text, orientation, text_profile, started: Instant::now(), next_frame: Instant::now() }
+    }
+
+    // Whether this ticker already shows `text` this way; otherwise a new one starts from the right
+    pub fn shows(&self, text: &str, orientation: &Orientation, text_profile: &TextProfile) -> bool {
+        self.text == text && self.orientation == *orientation && self.text_profile == *text_profile
+    }
+
+    // When the band is due to be redrawn on its own
+    pub fn next_frame(&self) -> Instant {
+        self.next_frame
+    }
+
+    pub fn mark_drawn(&mut self) {
+        self.next_frame = Instant::now() + FRAME_INTERVAL;
+    }
+
+    // The band as it looks now, rotated for the framebuffer, and where it goes on a screen
+    // whose safe area is `(x, y, width, height)`: along the viewer's bottom edge
+    pub fn render(&self, (x, y, width, height): (u32, u32, u32, u32)) -> (RgbaImage, Region) {
+        let (upright_width, upright_height) = self.orientation.upright_size(width, height);
+        let char_size = self.text_profile.char_size(text_char_size(CHAR_SIZE, upright_width, upright_height));
+        let band_height = (9 * char_size).min(upright_height);
+        let background = self.text_profile.background(BACKGROUND);
+        let color = self.text_profile.foreground(WHITE, background);
+
+        // The text enters on the right, leaves on the left, then comes round again
+        let advance = 8 * char_size;
+        let text_width = self.text.chars().count() as u32 * advance;
+        let cycle = (upright_width + text_width) as f32;
+        let scrolled = (self.started.elapsed().as_secs_f32() * CHARS_PER_SECOND * advance as f32) % cycle;
+        let start = upright_width as i64 - scrolled as i64;
+
+        // Only the characters in view are drawn. One character's width of slack on the left
+        // lets the first of them be cut off by the edge.
+        let skipped = if start < 0 { (-start) as u32 / advance } else { 0 };
+        let first_x = (start + (skipped * advance) as i64 + advance as i64) as u32;
+        let visible: String = self.text.chars()
+            .skip(skipped as usize)
+            .take((upright_width / advance + 2) as usize)
+            .collect();
+        let mut band = RgbaImage::from_pixel(upright_width + advance, band_height, background);
+        draw_text(&mut band, &visible, first_x, 2 * char_size, char_size, color);
+        let band = image::imageops::crop_imm(&band, advance, 0, upright_width, band_height).to_image();
+
+        let region = match self.orientation {
+            Orientation::Landscape => Region { x, y: y + height - band_height, width, height: band_height },
+            Orientation::InvertedLandscape => Region { x, y, width, height: band_height },
+            Orientation::Portrait => Region { x, y, width: band_height, height },
+            Orientation::InvertedPortrait => Region { x: x + width - band_height, y, width: band_height, height },
+        };
+        (self.orientation.rotate_image(&band), region)
+    }
+}
+
+// Headlines of an RSS or Atom feed on one line, or None when it has none
+pub async fn fetch_headlines(url: &str) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+    let client = reqwest::Client::builder().timeout(FEED_TIMEOUT).build()?;
+    let response = client.get(url).send().await?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()).into());
+    }
+    if response.content_length().is_some_and(|len| len as usize > MAX_FEED_BYTES) {
+        return Err(format!("feed is over {} bytes", MAX_FEED_BYTES).into());
+    }
+    let body = response.bytes().await?;
+    if body.len() > MAX_FEED_BYTES {
+        return Err(format!("feed is over {} bytes", MAX_FEED_BYTES).into());
+    }
+    let xml = String::from_utf8_lossy(&body);
+    Ok(headlines(&xml)?)
+}
+
+// Titles of the first items (RSS) or entries (Atom), whitespace collapsed, joined into one line
+fn headlines(xml: &str) -> Result<Option<String>, roxmltree::Error> {
+    // Some feeds still carry a DOCTYPE; roxmltree bounds entity expansion either way
+    let options = roxmltree::ParsingOptions { allow_dtd: true, ..Default::default() };
+    let document = roxmltree::Document::parse_with_options(xml, options)?;
+    let titles: Vec<String> = document.descendants()
+        .filter(|node| node.has_tag_name("item") || node.has_tag_name("entry"))
+        .filter_map(|item| item.children().find(|child| child.has_tag_name("title")))
+        .map(|title| title.descendants().filter(|node| node.is_text()).filter_map(|node| node.text()).collect::<String>())
+        .map(|title| title.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|title| !title.is_empty())
+        .take(MAX_HEADLINES)
+        .collect();
+    if titles.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(titles.join(SEPARATOR).chars().take(MAX_TEXT_CHARS).collect()))
+}
@@ -0,0 +1,194 @@
+// Regression tests for the transition render path.
+//
+// Golden images live in testdata/transitions/golden. After an intentional visual change,
+// regenerate them with:
+//
+//     UPDATE_GOLDEN=1 cargo test -p pi-slideshow-rs transition_tests
+//
+// and review the new PNGs before committing them.
+
+use super::{easing, eink, Framebuffer, ImageManager, Orientation, RenderTarget, TransitionType};
+use image::{Rgba, RgbaImage};
+use proptest::prelude::*;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const GOLDEN_PROGRESS: [f32; 3] = [0.25, 0.5, 0.75];
+// Per-channel difference still treated as identical (float rounding across platforms)
+const CHANNEL_TOLERANCE: u8 = 2;
+// Share of pixels allowed to exceed CHANNEL_TOLERANCE before a golden comparison fails
+const MAX_MISMATCHED_FRACTION: f64 = 0.01;
+
+fn testdata_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata").join("transitions")
+}
+
+fn load_fixture(name: &str) -> RgbaImage {
+    let path = testdata_dir().join(name);
+    image::open(&path)
+        .unwrap_or_else(|e| panic!("failed to load fixture {}: {}", path.display(), e))
+        .to_rgba8()
+}
+
+fn render(transition: &TransitionType, from: &RgbaImage, to: &RgbaImage, progress: f32) -> RgbaImage {
+    ImageManager::new().render_transition(from, to, progress, transition)
+}
+
+fn golden_name(transition: &TransitionType, progress: f32) -> String {
+    let key = transition.name().to_lowercase().replace([' ', '-'], "_");
+    format!("{}_{:03}.png", key, (progress * 100.0).round() as u32)
+}
+
+// Number of pixels where any channel differs by more than CHANNEL_TOLERANCE
+fn mismatched_pixels(actual: &RgbaImage, expected: &RgbaImage) -> usize {
+    actual
+        .pixels()
+        .zip(expected.pixels())
+        .filter(|(a, e)| a.0.iter().zip(e.0.iter()).any(|(x, y)| x.abs_diff(*y) > CHANNEL_TOLERANCE))
+        .count()
+}
+
+#[test]
+fn transitions_match_golden_images() {
+    let from = load_fixture("from.png");
+    let to = load_fixture("to.png");
+    let golden_dir = testdata_dir().join("golden");
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let mut failures = Vec::new();
+
+    for transition in TransitionType::all() {
+        for progress in GOLDEN_PROGRESS {
+            let frame = render(&transition, &from, &to, progress);
+            let path = golden_dir.join(golden_name(&transition, progress));
+
+            if update {
+                std::fs::create_dir_all(&golden_dir).unwrap();
+                frame.save(&path).unwrap();
+                continue;
+            }
+
+            let expected = match image::open(&path) {
+                Ok(golden) => golden.to_rgba8(),
+                Err(e) => {
+                    failures.push(format!("{}: {} (run with UPDATE_GOLDEN=1 to create it)", path.display(), e));
+                    continue;
+                }
+            };
+
+            if expected.dimensions() != frame.dimensions() {
+                failures.push(format!("{}: size {:?}, expected {:?}", path.display(), frame.dimensions(), expected.dimensions()));
+                continue;
+            }
+
+            let mismatched = mismatched_pixels(&frame, &expected);
+            let allowed = (frame.pixels().len() as f64 * MAX_MISMATCHED_FRACTION) as usize;
+            if mismatched > allowed {
+                failures.push(format!("{}: {} pixels differ (allowed {})", path.display(), mismatched, allowed));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "golden image mismatches:\n{}", failures.join("\n"));
+}
+
+// E-paper has no framebuffer memory, so a transition has to end in a panel refresh
+#[test]
+fn eink_transition_reaches_the_panel() {
+    let panel = eink::It8951::unconnected(64, 48).expect("failed to open /dev/null");
+    let mut fb = Framebuffer::eink(panel);
+    let mut images = ImageManager::new();
+    images.images = vec![testdata_dir().join("from.png"), testdata_dir().join("to.png")];
+
+    let result = images.play_transition(0, 1, RenderTarget { fb: &mut fb, orientation: &Orientation::Landscape, mirrors: &mut [] }, Duration::from_millis(500), TransitionType::Fade);
+    // The stand-in panel has no HRDY line to read, which only fails once a frame is sent to it
+    let error = result.expect_err("the transition never reached the e-paper panel");
+    assert_eq!(error.kind(), std::io::ErrorKind::NotFound, "unexpected error: {}", error);
+}
+
+// Curves that swing past the end hand effects progress outside 0.0-1.0
+#[test]
+fn overshooting_easing_stays_in_bounds() {
+    let from = load_fixture("from.png");
+    let to = load_fixture("to.png");
+    let mut images = ImageManager::new();
+    images.easing = easing::CubicBezier::parse("cubic-bezier(0.5, -0.8, 0.5, 1.8)").unwrap();
+    for transition in TransitionType::all() {
+        for step in 0..=20 {
+            let frame = images.render_transition(&from, &to, step as f32 / 20.0, &transition);
+            assert_eq!(frame.dimensions(), from.dimensions(), "{} at step {}", transition.name(), step);
+        }
+    }
+}
+
+fn arb_transition() -> impl Strategy<Value = TransitionType> {
+    (0..TransitionType::all().len()).prop_map(|i| TransitionType::all()[i].clone())
+}
+
+// Two same-sized images; pixels are arbitrary unless `opaque` forces alpha to 255
+fn arb_image_pair(opaque: bool) -> impl Strategy<Value = (RgbaImage, RgbaImage)> {
+    (1u32..24, 1u32..24).prop_flat_map(move |(width, height)| {
+        let len = (width * height) as usize;
+        let pixels = prop::collection::vec(any::<[u8; 4]>(), len);
+        (pixels.clone(), pixels).prop_map(move |(a, b)| (to_image(width, height, a, opaque), to_image(width, height, b, opaque)))
+    })
+}
+
+fn to_image(width: u32, height: u32, pixels: Vec<[u8; 4]>, opaque: bool) -> RgbaImage {
+    let mut image = RgbaImage::new(width, height);
+    for (pixel, [r, g, b, a]) in image.pixels_mut().zip(pixels) {
+        *pixel = Rgba([r, g, b, if opaque { 255 } else { a }]);
+    }
+    image
+}
+
+proptest! {
+    #[test]
+    fn output_matches_input_dimensions(
+        transition in arb_transition(),
+        (from, to) in arb_image_pair(false),
+        progress in 0.0f32..=1.0,
+    ) {
+        let frame = render(&transition, &from, &to, progress);
+        prop_assert_eq!(frame.dimensions(), from.dimensions());
+    }
+
+    #[test]
+    fn opaque_inputs_stay_opaque(
+        transition in arb_transition(),
+        (from, to) in arb_image_pair(true),
+        progress in 0.0f32..=1.0,
+    ) {
+        let frame = render(&transition, &from, &to, progress);
+        prop_assert!(frame.pixels().all(|p| p[3] == 255), "{} produced a translucent pixel at {}", transition.name(), progress);
+    }
+
+    #[test]
+    fn endpoints_equal_inputs(
+        transition in arb_transition(),
+        (from, to) in arb_image_pair(false),
+    ) {
+        // Pixelate opens on the source image in its largest blocks
+        if !matches!(transition, TransitionType::Pixelate) {
+            prop_assert!(render(&transition, &from, &to, 0.0) == from, "{} at 0.0 differs from the source image", transition.name());
+        }
+        prop_assert!(render(&transition, &from, &to, 1.0) == to, "{} at 1.0 differs from the target image", transition.name());
+    }
+
+    // Dissolved and pixelated pixels switch to the new image once and stay there rather than
+    // flickering
+    #[test]
+    fn reveal_only_grows(
+        transition in prop::sample::select(vec![TransitionType::Dissolve, TransitionType::Pixelate]),
+        (width, height) in (1u32..48, 1u32..48),
+        earlier in 0.0f32..=1.0,
+        later in 0.0f32..=1.0,
+    ) {
+        let (earlier, later) = (earlier.min(later), earlier.max(later));
+        let from = RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 255]));
+        let to = RgbaImage::from_pixel(width, height, Rgba([255, 255, 255, 255]));
+        let before = render(&transition, &from, &to, earlier);
+        let after = render(&transition, &from, &to, later);
+        prop_assert!(before.pixels().zip(after.pixels()).all(|(b, a)| b[0] == 0 || a[0] == 255),
+                     "a {} pixel revealed at {} went back by {}", transition.name(), earlier, later);
+    }
+}
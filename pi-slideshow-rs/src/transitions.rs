@@ -0,0 +1,587 @@
+use image::RgbaImage;
+use rayon::prelude::*;
+use std::sync::{Arc, OnceLock};
+
+use crate::{simd, TransitionType};
+
+// Transition effects. Each draws single frames of the change from one slide to the next, and the
+// slideshow looks them up by their configured name (TvConfig transition_effect) in a registry
+// of the built-in effects, so the render path never matches on the effect itself. A new effect
+// implements `Transition` and is listed in `builtin`.
+
+pub trait Transition: Send + Sync {
+    // The frame `progress` of the way from `from` to `to`, drawn into `out`; all three are the
+    // same size. Progress is already eased, so it may run a little outside 0.0-1.0, but 1.0 must
+    // draw `to` exactly, and 0.0 must draw `from` exactly unless the effect starts from a
+    // distorted `from` (pixelate opens on its largest blocks).
+    fn frame(&self, from: &RgbaImage, to: &RgbaImage, progress: f32, out: &mut RgbaImage);
+
+    // Effects that change scattered pixels all over the screen at once can read as rapid
+    // flashing, and are swapped for a fade under content safety mode
+    fn is_flash_safe(&self) -> bool {
+        true
+    }
+
+    // How long the effect plays relative to the TV's transition_duration, for TVs that don't
+    // give it a length of its own; sweeps across the whole screen need longer to follow
+    fn duration_scale(&self) -> f32 {
+        1.0
+    }
+}
+
+struct Entry {
+    name: &'static str,
+    effect: Arc<dyn Transition>,
+}
+
+static REGISTRY: OnceLock<Vec<Entry>> = OnceLock::new();
+
+fn registry() -> &'static [Entry] {
+    REGISTRY.get_or_init(|| {
+        TransitionType::all().iter()
+            .map(|transition| Entry { name: transition.key(), effect: builtin(transition) })
+            .collect()
+    })
+}
+
+pub fn get(name: &str) -> Option<Arc<dyn Transition>> {
+    registry().iter().find(|entry| entry.name == name).map(|entry| entry.effect.clone())
+}
+
+pub fn names() -> Vec<&'static str> {
+    registry().iter().map(|entry| entry.name).collect()
+}
+
+// The easing types are all fades; their curves are applied to the progress before drawing
+fn builtin(transition: &TransitionType) -> Arc<dyn Transition> {
+    match transition {
+        TransitionType::Dissolve => Arc::new(Dissolve),
+        TransitionType::SlideLeft => Arc::new(Slide { dir_x: -1, dir_y: 0 }),
+        TransitionType::SlideRight => Arc::new(Slide { dir_x: 1, dir_y: 0 }),
+        TransitionType::SlideUp => Arc::new(Slide { dir_x: 0, dir_y: -1 }),
+        TransitionType::SlideDown => Arc::new(Slide { dir_x: 0, dir_y: 1 }),
+        TransitionType::WipeLeft => Arc::new(Wipe(0)),
+        TransitionType::WipeRight => Arc::new(Wipe(1)),
+        TransitionType::WipeUp => Arc::new(Wipe(2)),
+        TransitionType::WipeDown => Arc::new(Wipe(3)),
+        TransitionType::CircularWipe => Arc::new(CircularWipe),
+        TransitionType::DiagonalWipe => Arc::new(DiagonalWipe),
+        TransitionType::Pixelate => Arc::new(Pixelate),
+        TransitionType::Morph => Arc::new(Morph),
+        TransitionType::Cube => Arc::new(Cube),
+        TransitionType::PageCurl => Arc::new(PageCurl),
+        TransitionType::Blinds => Arc::new(Blinds),
+        TransitionType::Checkerboard => Arc::new(Checkerboard),
+        TransitionType::ZoomIn => Arc::new(Zoom(ZOOM_IN_GROWTH)),
+        TransitionType::ZoomOut => Arc::new(Zoom(-ZOOM_OUT_SHRINK)),
+        _ => Arc::new(Fade),
+    }
+}
+
+// How far the outer edge of a cube face shrinks, and darkens, once it has turned fully away
+const CUBE_DEPTH: f32 = 0.3;
+const CUBE_SHADE: f32 = 0.4;
+// Darkening of the new image right at a page curl's fold, and how close to white the back of
+// the page is
+const PAGE_CURL_SHADOW: f32 = 0.5;
+const PAGE_CURL_BACK: f32 = 0.75;
+const BLINDS_SLATS: u32 = 12;
+// Side of the largest pixelate block, and of the cells that reveal the new image together
+const PIXELATE_MAX_BLOCK: usize = 16;
+// Squares across the longer side of the screen
+const CHECKERBOARD_SQUARES: u32 = 8;
+// Zoom transitions end with the outgoing image at twice and at a quarter of its size
+const ZOOM_IN_GROWTH: f32 = 1.0;
+const ZOOM_OUT_SHRINK: f32 = 0.75;
+// Pixels along each image's diagonal that pick a dissolve's pattern
+const SEED_SAMPLES: u32 = 16;
+
+pub struct Fade;
+
+impl Transition for Fade {
+    fn frame(&self, from: &RgbaImage, to: &RgbaImage, progress: f32, out: &mut RgbaImage) {
+        for_each_row(out, |y, row| simd::blend(image_row(from, y), image_row(to, y), progress, row));
+    }
+}
+
+pub struct Dissolve;
+
+impl Transition for Dissolve {
+    fn frame(&self, from: &RgbaImage, to: &RgbaImage, progress: f32, out: &mut RgbaImage) {
+        let seed = transition_seed(from, to);
+        for_each_row(out, |y, row| {
+            let (row1, row2) = (image_row(from, y), image_row(to, y));
+            for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+                let x = x as u32;
+                let source = if reveal_threshold(seed, x, y) < progress { row2 } else { row1 };
+                let i = x as usize * 4;
+                pixel.copy_from_slice(&source[i..i + 4]);
+            }
+        });
+    }
+
+    fn is_flash_safe(&self) -> bool {
+        false
+    }
+
+    fn duration_scale(&self) -> f32 {
+        0.8
+    }
+}
+
+// Moves both images across the screen, the new one following the old
+pub struct Slide {
+    pub dir_x: i32,
+    pub dir_y: i32,
+}
+
+impl Transition for Slide {
+    fn frame(&self, from: &RgbaImage, to: &RgbaImage, progress: f32, out: &mut RgbaImage) {
+        let (dir_x, dir_y) = (self.dir_x, self.dir_y);
+        let width = from.width() as i32;
+        let height = from.height() as i32;
+
+        let offset_x = (width as f32 * progress * dir_x as f32) as i32;
+        let offset_y = (height as f32 * progress * dir_y as f32) as i32;
+
+        for_each_row(out, |y, row| {
+            let y = y as i32;
+            let img1_y = y - offset_y;
+            let img2_y = y - offset_y + height * dir_y;
+            for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+                let x = x as i32;
+                let img1_x = x - offset_x;
+                let img2_x = x - offset_x + width * dir_x;
+
+                if img2_x >= 0 && img2_x < width && img2_y >= 0 && img2_y < height {
+                    pixel.copy_from_slice(pixel_at(to, img2_x as u32, img2_y as u32));
+                } else if img1_x >= 0 && img1_x < width && img1_y >= 0 && img1_y < height {
+                    pixel.copy_from_slice(pixel_at(from, img1_x as u32, img1_y as u32));
+                } else {
+                    pixel.copy_from_slice(&[0, 0, 0, 255]);
+                }
+            }
+        });
+    }
+}
+
+// 0 left, 1 right, 2 up, 3 down
+pub struct Wipe(pub u32);
+
+impl Transition for Wipe {
+    fn frame(&self, from: &RgbaImage, to: &RgbaImage, progress: f32, out: &mut RgbaImage) {
+        let direction = self.0;
+        let width = from.width();
+        let height = from.height();
+
+        for_each_row(out, |y, row| {
+            let (row1, row2) = (image_row(from, y), image_row(to, y));
+            for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+                let should_show_img2 = match direction {
+                    0 => (x as f32 / width as f32) < progress,          // Left
+                    1 => (x as f32 / width as f32) >= (1.0 - progress), // Right
+                    2 => (y as f32 / height as f32) >= (1.0 - progress), // Up
+                    3 => (y as f32 / height as f32) < progress,         // Down
+                    _ => false,
+                };
+
+                let source = if should_show_img2 { row2 } else { row1 };
+                pixel.copy_from_slice(&source[x * 4..x * 4 + 4]);
+            }
+        });
+    }
+}
+
+pub struct CircularWipe;
+
+impl Transition for CircularWipe {
+    fn frame(&self, from: &RgbaImage, to: &RgbaImage, progress: f32, out: &mut RgbaImage) {
+        let width = from.width() as f32;
+        let height = from.height() as f32;
+        let center_x = width / 2.0;
+        let center_y = height / 2.0;
+        let max_radius = ((width * width + height * height) / 4.0).sqrt();
+        let current_radius = max_radius * progress;
+
+        for_each_row(out, |y, row| {
+            let (row1, row2) = (image_row(from, y), image_row(to, y));
+            for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+                // Measure from pixel centers so the corners are covered at full progress
+                let dx = x as f32 + 0.5 - center_x;
+                let dy = y as f32 + 0.5 - center_y;
+                let distance = (dx * dx + dy * dy).sqrt();
+
+                let source = if distance < current_radius { row2 } else { row1 };
+                pixel.copy_from_slice(&source[x * 4..x * 4 + 4]);
+            }
+        });
+    }
+
+    fn duration_scale(&self) -> f32 {
+        2.0
+    }
+}
+
+pub struct DiagonalWipe;
+
+impl Transition for DiagonalWipe {
+    fn frame(&self, from: &RgbaImage, to: &RgbaImage, progress: f32, out: &mut RgbaImage) {
+        let width = from.width() as f32;
+        let height = from.height() as f32;
+        let diagonal_length = width + height;
+        let current_position = diagonal_length * progress;
+
+        for_each_row(out, |y, row| {
+            let (row1, row2) = (image_row(from, y), image_row(to, y));
+            for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+                let diagonal_pos = x as f32 + y as f32;
+
+                let source = if diagonal_pos < current_position { row2 } else { row1 };
+                pixel.copy_from_slice(&source[x * 4..x * 4 + 4]);
+            }
+        });
+    }
+
+    fn duration_scale(&self) -> f32 {
+        1.5
+    }
+}
+
+pub struct Pixelate;
+
+impl Transition for Pixelate {
+    fn frame(&self, from: &RgbaImage, to: &RgbaImage, progress: f32, out: &mut RgbaImage) {
+        let width = from.width() as usize;
+        // From PIXELATE_MAX_BLOCK blocks down to single pixels. Eased progress can overshoot
+        // 1.0, so they never shrink below a pixel.
+        let block_size = ((1.0 + (1.0 - progress) * (PIXELATE_MAX_BLOCK - 1) as f32) as usize).max(1);
+        if width == 0 {
+            return;
+        }
+
+        // Pixels switch over a whole cell of the largest block size at a time, once progress
+        // passes the cell's threshold as in a dissolve. The cells stay put while the blocks
+        // change size, so a revealed pixel never goes back; it shows its block of the new image.
+        let seed = transition_seed(from, to);
+        let cells = width.div_ceil(PIXELATE_MAX_BLOCK);
+        out.par_chunks_mut(width * 4 * block_size).enumerate().for_each(|(band, rows)| {
+            let top = (band * block_size) as u32;
+            let blocks = (0..width).step_by(block_size);
+            let samples_from: Vec<&[u8]> = blocks.clone().map(|x| pixel_at(from, x as u32, top)).collect();
+            let samples_to: Vec<&[u8]> = blocks.map(|x| pixel_at(to, x as u32, top)).collect();
+            for (offset, row) in rows.chunks_exact_mut(width * 4).enumerate() {
+                let cell_y = (top as usize + offset) / PIXELATE_MAX_BLOCK;
+                let revealed: Vec<bool> = (0..cells)
+                    .map(|cell_x| reveal_threshold(seed, cell_x as u32, cell_y as u32) < progress)
+                    .collect();
+                for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+                    let samples = if revealed[x / PIXELATE_MAX_BLOCK] { &samples_to } else { &samples_from };
+                    pixel.copy_from_slice(samples[x / block_size]);
+                }
+            }
+        });
+    }
+
+    fn is_flash_safe(&self) -> bool {
+        false
+    }
+}
+
+pub struct Morph;
+
+impl Transition for Morph {
+    fn frame(&self, from: &RgbaImage, to: &RgbaImage, progress: f32, out: &mut RgbaImage) {
+        let width = from.width();
+        let height = from.height();
+        let distortion = progress * 0.1; // Maximum 10% distortion
+
+        for_each_row(out, |y, row| {
+            let row2 = image_row(to, y);
+            for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+                // Create wave distortion effect
+                let wave_x = (y as f32 * 0.02 + progress * 6.28).sin() * distortion * width as f32;
+                let wave_y = (x as f32 * 0.02 + progress * 6.28).cos() * distortion * height as f32;
+
+                let src_x = ((x as f32 + wave_x) as i32).max(0).min(width as i32 - 1) as u32;
+                let src_y = ((y as f32 + wave_y) as i32).max(0).min(height as i32 - 1) as u32;
+
+                let p1 = pixel_at(from, src_x, src_y);
+                let p2 = &row2[x * 4..x * 4 + 4];
+                for (c, channel) in pixel.iter_mut().enumerate() {
+                    *channel = lerp_channel(p1[c], p2[c], progress);
+                }
+            }
+        });
+    }
+}
+
+// The two images as neighbouring faces of a cube turning to the left. Each face is squeezed
+// to its visible width, its far edge shrinks with perspective, and the face turning away
+// darkens.
+pub struct Cube;
+
+impl Transition for Cube {
+    fn frame(&self, from: &RgbaImage, to: &RgbaImage, progress: f32, out: &mut RgbaImage) {
+        let width = from.width() as f32;
+        let height = from.height() as f32;
+        let center_y = height / 2.0;
+        // The shared edge, moving from the right of the screen to the left
+        let edge = width * (1.0 - progress);
+
+        for_each_row(out, |y, row| {
+            let dy = y as f32 + 0.5 - center_y;
+            for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+                let x = x as f32 + 0.5;
+                // Which face, how far across it (0.0 at its outer edge, 1.0 at the shared one),
+                // and how far it has turned from facing the viewer
+                let (image, across, turned) = if x < edge {
+                    (from, x / edge, progress)
+                } else {
+                    (to, 1.0 - (x - edge) / (width - edge), 1.0 - progress)
+                };
+                let scale = 1.0 - CUBE_DEPTH * turned * (1.0 - across);
+                let src_y = center_y + dy / scale;
+                if src_y < 0.0 || src_y >= height {
+                    pixel.copy_from_slice(&[0, 0, 0, 255]);
+                    continue;
+                }
+
+                // The outgoing face is read from its left, the incoming one from its right
+                let src_x = if x < edge { across * width } else { (1.0 - across) * width };
+                let src_x = (src_x as u32).min(from.width() - 1);
+                let source = pixel_at(image, src_x, src_y as u32);
+                let shade = 1.0 - CUBE_SHADE * turned;
+                for (channel, &value) in pixel[..3].iter_mut().zip(&source[..3]) {
+                    *channel = (value as f32 * shade) as u8;
+                }
+                pixel[3] = source[3];
+            }
+        });
+    }
+
+    fn duration_scale(&self) -> f32 {
+        1.5
+    }
+}
+
+// The outgoing image is a page folded back from the bottom-right corner: the fold sweeps
+// to the top-left, the flap shows the pale back of the page, and the new image underneath
+// is shaded along the fold
+pub struct PageCurl;
+
+impl Transition for PageCurl {
+    fn frame(&self, from: &RgbaImage, to: &RgbaImage, progress: f32, out: &mut RgbaImage) {
+        let width = from.width() as f32;
+        let height = from.height() as f32;
+        // Distances are measured along the diagonal from the top-left corner
+        let length = (width * width + height * height).sqrt();
+        let (dir_x, dir_y) = (width / length, height / length);
+        let fold = length * (1.0 - progress);
+        let shadow_width = length * 0.05;
+
+        for_each_row(out, |y, row| {
+            let row2 = image_row(to, y);
+            let py = y as f32 + 0.5;
+            for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+                let px = x as f32 + 0.5;
+                let along = px * dir_x + py * dir_y;
+
+                if along > fold {
+                    let source = &row2[x * 4..x * 4 + 4];
+                    let shade = 1.0 - PAGE_CURL_SHADOW * (1.0 - progress) * (-(along - fold) / shadow_width).exp();
+                    for (channel, &value) in pixel[..3].iter_mut().zip(&source[..3]) {
+                        *channel = (value as f32 * shade) as u8;
+                    }
+                    pixel[3] = source[3];
+                    continue;
+                }
+
+                // The flap is the page beyond the fold, mirrored across it
+                let flap_x = px + 2.0 * (fold - along) * dir_x;
+                let flap_y = py + 2.0 * (fold - along) * dir_y;
+                if flap_x < width && flap_y < height {
+                    let source = pixel_at(from, flap_x as u32, flap_y as u32);
+                    for (channel, &value) in pixel[..3].iter_mut().zip(&source[..3]) {
+                        *channel = lerp_channel(value, 255, PAGE_CURL_BACK);
+                    }
+                    pixel[3] = source[3];
+                } else {
+                    pixel.copy_from_slice(pixel_at(from, x as u32, y));
+                }
+            }
+        });
+    }
+
+    fn duration_scale(&self) -> f32 {
+        1.5
+    }
+}
+
+// Horizontal slats that each open from the top
+pub struct Blinds;
+
+impl Transition for Blinds {
+    fn frame(&self, from: &RgbaImage, to: &RgbaImage, progress: f32, out: &mut RgbaImage) {
+        let slat_height = from.height() as f32 / BLINDS_SLATS as f32;
+
+        for_each_row(out, |y, row| {
+            let within_slat = ((y as f32 + 0.5) / slat_height).fract();
+            let source = if within_slat < progress { image_row(to, y) } else { image_row(from, y) };
+            row.copy_from_slice(source);
+        });
+    }
+}
+
+// Squares that wipe open from the left, one color of the board in the first half of the
+// transition and the other in the second
+pub struct Checkerboard;
+
+impl Transition for Checkerboard {
+    fn frame(&self, from: &RgbaImage, to: &RgbaImage, progress: f32, out: &mut RgbaImage) {
+        let square = from.width().max(from.height()) as f32 / CHECKERBOARD_SQUARES as f32;
+        let first_half = (progress * 2.0).min(1.0);
+        let second_half = (progress * 2.0 - 1.0).max(0.0);
+
+        for_each_row(out, |y, row| {
+            let square_row = ((y as f32 + 0.5) / square) as u32;
+            let (row1, row2) = (image_row(from, y), image_row(to, y));
+            for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+                let across = (x as f32 + 0.5) / square;
+                let opened = if (across as u32 + square_row).is_multiple_of(2) { first_half } else { second_half };
+
+                let source = if across.fract() < opened { row2 } else { row1 };
+                pixel.copy_from_slice(&source[x * 4..x * 4 + 4]);
+            }
+        });
+    }
+}
+
+// The outgoing image scaled about the center of the screen and fading out over the incoming
+// one
+// How much larger the outgoing image ends up, as a fraction of its size; negative shrinks it
+pub struct Zoom(pub f32);
+
+impl Transition for Zoom {
+    fn frame(&self, from: &RgbaImage, to: &RgbaImage, progress: f32, out: &mut RgbaImage) {
+        let scale = 1.0 + progress * self.0;
+        let center_x = from.width() as f32 / 2.0;
+        let center_y = from.height() as f32 / 2.0;
+
+        for_each_row(out, |y, row| {
+            let row2 = image_row(to, y);
+            let src_y = center_y + (y as f32 + 0.5 - center_y) / scale;
+            for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+                let incoming = &row2[x * 4..x * 4 + 4];
+                let src_x = center_x + (x as f32 + 0.5 - center_x) / scale;
+                match sample_scaled(from, src_x, src_y, 1.0 / scale) {
+                    Some(outgoing) => {
+                        for (c, channel) in pixel.iter_mut().enumerate() {
+                            *channel = lerp_channel(incoming[c], outgoing[c], 1.0 - progress);
+                        }
+                    }
+                    None => pixel.copy_from_slice(incoming),
+                }
+            }
+        });
+    }
+}
+
+// Blend two channel values; equal inputs stay exact at any t, and overshoot saturates
+pub fn lerp_channel(from: u8, to: u8, t: f32) -> u8 {
+    (from as f32 + (to as f32 - from as f32) * t) as u8
+}
+
+// The color of `img` around the point (x, y), in pixels from its top-left corner, when it is
+// drawn at 1/`footprint` of its size; None outside the image. Shrunk images average the
+// block of pixels each screen pixel covers, since point samples would shimmer on detail
+// finer than the screen can show; enlarged ones interpolate between the nearest four.
+pub fn sample_scaled(img: &RgbaImage, x: f32, y: f32, footprint: f32) -> Option<[u8; 4]> {
+    let (width, height) = img.dimensions();
+    if x < 0.0 || y < 0.0 || x >= width as f32 || y >= height as f32 {
+        return None;
+    }
+
+    if footprint > 1.0 {
+        let half = footprint / 2.0;
+        let left = (x - half).max(0.0) as u32;
+        let top = (y - half).max(0.0) as u32;
+        let right = ((x + half).ceil() as u32).clamp(left + 1, width);
+        let bottom = ((y + half).ceil() as u32).clamp(top + 1, height);
+        let mut sum = [0u32; 4];
+        for sy in top..bottom {
+            for sx in left..right {
+                for (total, &channel) in sum.iter_mut().zip(pixel_at(img, sx, sy)) {
+                    *total += channel as u32;
+                }
+            }
+        }
+        let count = (right - left) * (bottom - top);
+        return Some(sum.map(|total| ((total + count / 2) / count) as u8));
+    }
+
+    // Pixel centers sit at half-pixel offsets, so an unscaled image samples exactly
+    let (fx, fy) = (x - 0.5, y - 0.5);
+    let (left, top) = (fx.floor().max(0.0), fy.floor().max(0.0));
+    let (tx, ty) = ((fx - left).clamp(0.0, 1.0), (fy - top).clamp(0.0, 1.0));
+    let (left, top) = (left as u32, top as u32);
+    let (right, bottom) = ((left + 1).min(width - 1), (top + 1).min(height - 1));
+    let (p00, p10) = (pixel_at(img, left, top), pixel_at(img, right, top));
+    let (p01, p11) = (pixel_at(img, left, bottom), pixel_at(img, right, bottom));
+    Some(std::array::from_fn(|c| {
+        let upper = p00[c] as f32 + (p10[c] as f32 - p00[c] as f32) * tx;
+        let lower = p01[c] as f32 + (p11[c] as f32 - p01[c] as f32) * tx;
+        (upper + (lower - upper) * ty).round() as u8
+    }))
+}
+
+// Transition frames are drawn a row at a time across the CPU's cores: `fill` gets each row's
+// y and its RGBA bytes
+pub fn for_each_row(result: &mut RgbaImage, fill: impl Fn(u32, &mut [u8]) + Sync) {
+    let row_len = result.width() as usize * 4;
+    if row_len == 0 {
+        return;
+    }
+    result.par_chunks_exact_mut(row_len).enumerate().for_each(|(y, row)| fill(y as u32, row));
+}
+
+// Random dissolves must not re-roll every frame, or pixels flicker back and forth between the
+// two images. Instead each pixel gets a fixed threshold in 0.0-1.0 and shows the new image once
+// progress passes it, so the reveal only ever grows. The pattern comes from the images
+// themselves: fixed for the length of a transition, different from one transition to the next,
+// and the same however the frame is split across threads.
+pub fn transition_seed(from: &RgbaImage, to: &RgbaImage) -> u64 {
+    let mut seed = mix(((from.width() as u64) << 32) | from.height() as u64);
+    for img in [from, to].into_iter().filter(|img| img.width() > 0 && img.height() > 0) {
+        for i in 0..SEED_SAMPLES {
+            let x = (img.width() - 1) * i / (SEED_SAMPLES - 1);
+            let y = (img.height() - 1) * i / (SEED_SAMPLES - 1);
+            seed = mix(seed ^ u32::from_le_bytes(img.get_pixel(x, y).0) as u64);
+        }
+    }
+    seed
+}
+
+// The progress at which pixel (x, y) switches to the new image, evenly spread over 0.0-1.0
+pub fn reveal_threshold(seed: u64, x: u32, y: u32) -> f32 {
+    let hash = mix(seed ^ (((y as u64) << 32) | x as u64));
+    (hash >> 40) as f32 / (1u64 << 24) as f32
+}
+
+// SplitMix64's finalizer: every input bit affects every output bit
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// The RGBA bytes of row `y` of `img`
+pub fn image_row(img: &RgbaImage, y: u32) -> &[u8] {
+    let row_len = img.width() as usize * 4;
+    &img.as_raw()[y as usize * row_len..(y as usize + 1) * row_len]
+}
+
+pub fn pixel_at(img: &RgbaImage, x: u32, y: u32) -> &[u8] {
+    let start = (y as usize * img.width() as usize + x as usize) * 4;
+    &img.as_raw()[start..start + 4]
+}
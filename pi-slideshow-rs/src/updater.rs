@@ -0,0 +1,317 @@
+use ring::digest::{digest, SHA256};
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use signage_protocol::Release;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::slideshow_controller::SlideshowController;
+use crate::watchdog;
+
+// Self-update from a release channel (--update-channel): an http(s) URL serving a release
+// manifest, or the name of a channel whose manifest is the "release_{channel}" CouchDB
+// document. A newer release the TV is in the rollout for is downloaded, checked against its
+// SHA-256 and the Ed25519 release key (--update-public-key), which signs the version and the
+// SHA-256 together, and run once with --version, which has to report that version.
+// Only then is it put in place: releases live in releases/{version}/ next to the binary (so
+// the binary has to sit somewhere the service can write, /var/signage/bin as installed), and
+// the binary's own path becomes a symlink swapped over to the new one, so the service
+// definition never changes. The endpoint then restarts into it the way the restart command
+// does. A release that crashes or hangs MAX_BOOT_ATTEMPTS times before running cleanly for
+// HEALTH_CHECK is rolled back by pointing the symlink at the previous binary, and never
+// retried. Each step goes out on the update topic.
+
+const RELEASES_DIR: &str = "releases";
+const STATE_FILE: &str = "update.json";
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+// How long a new release must run, with the display loop going, to be kept
+const HEALTH_CHECK: Duration = Duration::from_secs(120);
+// Starts of a new release without it passing the health check before it is rolled back
+const MAX_BOOT_ATTEMPTS: u32 = 3;
+const MANIFEST_TIMEOUT: Duration = Duration::from_secs(15);
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(600);
+const PREFLIGHT_TIMEOUT: Duration = Duration::from_secs(15);
+
+pub struct UpdateSettings {
+    pub channel: String,
+    pub public_key: Option<String>,
+    pub check_interval: Duration,
+}
+
+// Kept in releases/update.json across the restarts of an update
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct UpdateState {
+    #[serde(default)]
+    pending: Option<PendingRelease>,
+    // Releases that were rolled back; the same version isn't tried again
+    #[serde(default)]
+    failed: Vec<String>,
+    // A rollback to report once the old version is back up and connected
+    #[serde(default)]
+    rolled_back: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingRelease {
+    version: String,
+    // The binary to go back to
+    previous: PathBuf,
+    boots: u32,
+}
+
+// The path the endpoint was started as: the symlink an installed update swaps, and what a
+// restart runs again, so it picks up the new release
+pub fn invoked_path() -> Option<PathBuf> {
+    let arg0 = PathBuf::from(std::env::args_os().next()?);
+    if arg0.components().count() > 1 {
+        return std::env::current_dir().ok().map(|dir| dir.join(arg0));
+    }
+    // Found on the PATH
+    std::env::current_exe().ok()
+}
+
+fn releases_dir(link: &Path) -> Option<PathBuf> {
+    link.parent().map(|dir| dir.join(RELEASES_DIR))
+}
+
+fn load_state(releases: &Path) -> UpdateState {
+    std::fs::read_to_string(releases.join(STATE_FILE)).ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(releases: &Path, state: &UpdateState) -> std::io::Result<()> {
+    std::fs::create_dir_all(releases)?;
+    std::fs::write(releases.join(STATE_FILE), serde_json::to_string_pretty(state)?)
+}
+
+// Point `link` at `target` in one step, so a crash midway leaves either binary in place
+fn swap_link(link: &Path, target: &Path) -> std::io::Result<()> {
+    let staged = link.with_extension("new");
+    let _ = std::fs::remove_file(&staged);
+    std::os::unix::fs::symlink(target, &staged)?;
+    std::fs::rename(&staged, link)
+}
+
+// Called first thing at startup, before anything a broken release could crash in: counts the
+// starts of a release on trial and rolls it back once it has used them up
+pub fn check_startup() {
+    let Some(link) = invoked_path() else { return };
+    let Some(releases) = releases_dir(&link) else { return };
+    let mut state = load_state(&releases);
+    let Some(mut pending) = state.pending.take() else { return };
+
+    if pending.version != VERSION {
+        // The link was put back by hand, or the swap never happened
+        info!("⬆️ Release {} is no longer installed, dropping its trial", pending.version);
+    } else if pending.boots >= MAX_BOOT_ATTEMPTS {
+        error!("⬆️ Release {} failed {} starts, rolling back to {}", VERSION, pending.boots, pending.previous.display());
+        if let Err(e) = swap_link(&link, &pending.previous) {
+            error!("Failed to roll back: {}, keeping {}", e, VERSION);
+            state.pending = Some(pending);
+            let _ = save_state(&releases, &state);
+            return;
+        }
+        state.failed.push(VERSION.to_string());
+        state.rolled_back = Some(VERSION.to_string());
+        if let Err(e) = save_state(&releases, &state) {
+            error!("Failed to save the update state: {}", e);
+        }
+        crate::restart_process();
+    } else {
+        pending.boots += 1;
+        info!("⬆️ Trying release {} (start {} of {})", VERSION, pending.boots, MAX_BOOT_ATTEMPTS);
+        state.pending = Some(pending);
+    }
+    if let Err(e) = save_state(&releases, &state) {
+        error!("Failed to save the update state: {}", e);
+    }
+}
+
+// Confirm or report what happened to the last update, then with a channel check it every
+// check_interval. Must be called from within the runtime.
+pub fn spawn(settings: Option<UpdateSettings>, controller: SlideshowController) {
+    let Some(link) = invoked_path() else { return };
+    let confirm_link = link.clone();
+    let confirm_controller = controller.clone();
+    tokio::spawn(async move { confirm_last_update(&confirm_link, &confirm_controller).await });
+
+    let Some(settings) = settings else { return };
+    let public_key = match settings.public_key.as_deref().map(decode_hex) {
+        Some(Some(key)) if key.len() == 32 => key,
+        Some(_) => {
+            warn!("Updates are off: --update-public-key must be a hex Ed25519 public key");
+            return;
+        }
+        None => {
+            warn!("Updates from {} are off: releases can't be verified without --update-public-key", settings.channel);
+            return;
+        }
+    };
+    info!("⬆️ Checking {} for updates every {} minutes (running {})", settings.channel, settings.check_interval.as_secs() / 60, VERSION);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(settings.check_interval);
+        loop {
+            interval.tick().await;
+            match check_for_update(&settings.channel, &public_key, &link, &controller).await {
+                Ok(Some(version)) => {
+                    controller.report_update("restarting", &version, None).await;
+                    controller.prepare_restart().await;
+                    return;
+                }
+                Ok(None) => {}
+                Err(e) => error!("Update check failed: {}", e),
+            }
+        }
+    });
+}
+
+async fn confirm_last_update(link: &Path, controller: &SlideshowController) {
+    let Some(releases) = releases_dir(link) else { return };
+    let mut state = load_state(&releases);
+    if let Some(version) = state.rolled_back.take() {
+        controller.report_update("rolled_back", &version, Some("the release didn't stay up")).await;
+        let _ = save_state(&releases, &state);
+    }
+    if state.pending.is_none() {
+        return;
+    }
+
+    controller.report_update("health_check", VERSION, None).await;
+    tokio::time::sleep(HEALTH_CHECK).await;
+    // A stalled display loop is the watchdog's to restart, which counts against the release
+    if watchdog::since_last_beat() >= HEALTH_CHECK {
+        return;
+    }
+    let mut state = load_state(&releases);
+    let previous = state.pending.take().map(|pending| pending.previous);
+    if let Err(e) = save_state(&releases, &state) {
+        error!("Failed to save the update state: {}", e);
+        return;
+    }
+    info!("⬆️ Release {} passed its health check", VERSION);
+    controller.report_update("installed", VERSION, None).await;
+    prune_releases(&releases, link, previous.as_deref());
+}
+
+// Keep the running release and the one before it
+fn prune_releases(releases: &Path, link: &Path, previous: Option<&Path>) {
+    let kept: Vec<PathBuf> = [Some(link), previous].into_iter().flatten()
+        .filter_map(|binary| std::fs::canonicalize(binary).ok())
+        .collect();
+    let Ok(entries) = std::fs::read_dir(releases) else { return };
+    for entry in entries.flatten() {
+        let dir = entry.path();
+        if !dir.is_dir() || kept.iter().any(|binary| binary.starts_with(&dir)) {
+            continue;
+        }
+        match std::fs::remove_dir_all(&dir) {
+            Ok(()) => info!("⬆️ Removed old release {}", dir.display()),
+            Err(e) => error!("Failed to remove old release {}: {}", dir.display(), e),
+        }
+    }
+}
+
+// Install the channel's release if it is newer, meant for this TV and not already failed.
+// Its version once it is in place and the endpoint should restart into it.
+async fn check_for_update(
+    channel: &str,
+    public_key: &[u8],
+    link: &Path,
+    controller: &SlideshowController,
+) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+    let release = if channel.starts_with("http://") || channel.starts_with("https://") {
+        let client = reqwest::Client::builder().timeout(MANIFEST_TIMEOUT).build()?;
+        client.get(channel).send().await?.error_for_status()?.json::<Release>().await?
+    } else {
+        controller.get_release(channel).await?
+    };
+    let releases = releases_dir(link).ok_or("the binary has no directory to keep releases in")?;
+    if !release.is_newer_than(VERSION)
+        || load_state(&releases).failed.contains(&release.version)
+        || !release.is_rolled_out_to(&controller.get_tv_id().await)
+    {
+        return Ok(None);
+    }
+
+    info!("⬆️ Release {} available, downloading {}", release.version, release.url);
+    controller.report_update("downloading", &release.version, None).await;
+    match install(&release, public_key, link, &releases).await {
+        Ok(()) => Ok(Some(release.version)),
+        Err(e) => {
+            error!("Failed to install release {}: {}", release.version, e);
+            controller.report_update("failed", &release.version, Some(&e.to_string())).await;
+            Ok(None)
+        }
+    }
+}
+
+async fn install(release: &Release, public_key: &[u8], link: &Path, releases: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if !signage_protocol::mqtt::is_safe_file_component(&release.version) {
+        return Err(format!("unusable version '{}'", release.version).into());
+    }
+    let name = link.file_name().ok_or("the binary has no file name")?;
+
+    let client = reqwest::Client::builder().timeout(DOWNLOAD_TIMEOUT).build()?;
+    let binary = client.get(&release.url).send().await?.error_for_status()?.bytes().await?;
+    let expected = decode_hex(&release.sha256).ok_or("the release's sha256 isn't hex")?;
+    if digest(&SHA256, &binary).as_ref() != expected.as_slice() {
+        return Err("the download doesn't match the release's sha256".into());
+    }
+    let signature = decode_hex(&release.signature).ok_or("the release's signature isn't hex")?;
+    UnparsedPublicKey::new(&ED25519, public_key).verify(release.signed_message().as_bytes(), &signature)
+        .map_err(|_| "the release isn't signed with the release key")?;
+
+    let dir = releases.join(&release.version);
+    std::fs::create_dir_all(&dir)?;
+    let target = dir.join(name);
+    let staged = target.with_extension("part");
+    std::fs::write(&staged, &binary)?;
+    std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))?;
+    std::fs::rename(&staged, &target)?;
+
+    // A binary built for the wrong architecture, or missing a library, fails here rather than
+    // after the swap
+    let preflight = tokio::time::timeout(
+        PREFLIGHT_TIMEOUT,
+        tokio::process::Command::new(&target).arg("--version").kill_on_drop(true).output(),
+    )
+    .await
+    .map_err(|_| "the new binary hung on --version")??;
+    if !preflight.status.success() {
+        return Err(format!("the new binary failed to run: {}", String::from_utf8_lossy(&preflight.stderr).trim()).into());
+    }
+    // "pi-slideshow-rs 0.3.0"; a binary of another version would start outside its trial
+    let reported = String::from_utf8_lossy(&preflight.stdout);
+    if reported.split_whitespace().last() != Some(release.version.as_str()) {
+        return Err(format!("the new binary reports version '{}', not {}", reported.trim(), release.version).into());
+    }
+
+    // The first update moves the installed binary in with the releases, leaving a symlink
+    let previous = if link.is_symlink() {
+        std::fs::canonicalize(link)?
+    } else {
+        let kept = releases.join(VERSION).join(name);
+        std::fs::create_dir_all(releases.join(VERSION))?;
+        std::fs::rename(link, &kept)?;
+        std::os::unix::fs::symlink(&kept, link)?;
+        kept
+    };
+    let mut state = load_state(releases);
+    state.pending = Some(PendingRelease { version: release.version.clone(), previous, boots: 0 });
+    save_state(releases, &state)?;
+    swap_link(link, &target)?;
+    info!("⬆️ Release {} installed at {}, restarting into it", release.version, target.display());
+    Ok(())
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    let s = s.trim();
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok()).collect()
+}
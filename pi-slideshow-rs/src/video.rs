@@ -0,0 +1,322 @@
+use image::{DynamicImage, ImageError, ImageResult, RgbaImage};
+use std::io::{ErrorKind, Read, Result as IoResult};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::Orientation;
+
+// Video slides are decoded by ffmpeg, which hands back raw RGBA frames already turned for the
+// display and scaled into the safe area; the slideshow only places them on the letterbox fill
+// and writes them out. Stills of the first and last frame stand in wherever a slide has to be
+// a single image: transitions, mirroring outputs and output playlists.
+
+// Files picked up as video slides, by lowercase extension
+pub const VIDEO_EXTENSIONS: [&str; 3] = ["mp4", "m4v", "mov"];
+// Used when the file doesn't state its frame rate
+const DEFAULT_FRAME_RATE: f64 = 30.0;
+// The Pi's V4L2 hardware H.264 decoder
+const DEFAULT_DECODER: &str = "h264_v4l2m2m";
+
+struct Settings {
+    // ffmpeg decoder for H.264 streams; None decodes in software
+    decoder: Option<String>,
+    // ALSA device for the soundtrack; None plays videos muted
+    audio_device: Option<String>,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+// Numbers the scratch files for frame grabs, which can run on the prefetch thread and the
+// display loop at once
+static STILL_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+// Set once at startup (--video-decoder, --video-audio-device)
+pub fn configure(decoder: &str, audio_device: Option<String>) {
+    let decoder = match decoder.to_lowercase().as_str() {
+        "" | "software" | "none" => None,
+        _ => Some(decoder.to_string()),
+    };
+    if SETTINGS.set(Settings { decoder, audio_device }).is_err() {
+        warn!("Video settings already configured, ignoring");
+    }
+}
+
+fn settings() -> (Option<&'static str>, Option<&'static str>) {
+    match SETTINGS.get() {
+        Some(settings) => (settings.decoder.as_deref(), settings.audio_device.as_deref()),
+        None => (Some(DEFAULT_DECODER), None),
+    }
+}
+
+pub fn is_video(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| VIDEO_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+}
+
+#[derive(Debug, Clone)]
+pub struct VideoInfo {
+    pub codec: String,
+    // Display size, after any rotation the file asks for
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate: f64,
+    pub duration: Option<Duration>,
+}
+
+pub fn probe(path: &Path) -> IoResult<VideoInfo> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "v:0"])
+        .args(["-show_entries", "stream=codec_name,width,height,avg_frame_rate:stream_side_data=rotation:format=duration"])
+        .args(["-of", "default=noprint_wrappers=1"])
+        .arg(path)
+        .output()?;
+    if !output.status.success() {
+        return Err(std::io::Error::new(
+            ErrorKind::InvalidData,
+            format!("ffprobe failed on {}: {}", path.display(), String::from_utf8_lossy(&output.stderr).trim()),
+        ));
+    }
+
+    let mut info = VideoInfo { codec: String::new(), width: 0, height: 0, frame_rate: DEFAULT_FRAME_RATE, duration: None };
+    let mut rotation = 0i32;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let (key, value) = match line.split_once('=') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        match key {
+            "codec_name" => info.codec = value.to_string(),
+            "width" => info.width = value.parse().unwrap_or(0),
+            "height" => info.height = value.parse().unwrap_or(0),
+            "avg_frame_rate" => {
+                // A fraction such as 30000/1001; 0/0 when unknown
+                if let Some((num, den)) = value.split_once('/') {
+                    if let (Ok(num), Ok(den)) = (num.parse::<f64>(), den.parse::<f64>()) {
+                        if num > 0.0 && den > 0.0 {
+                            info.frame_rate = num / den;
+                        }
+                    }
+                }
+            }
+            "rotation" => rotation = value.parse().unwrap_or(0),
+            "duration" => info.duration = value.parse::<f64>().ok().filter(|secs| *secs > 0.0).map(Duration::from_secs_f64),
+            _ => {}
+        }
+    }
+
+    if info.width == 0 || info.height == 0 {
+        return Err(std::io::Error::new(ErrorKind::InvalidData, format!("{} has no video stream", path.display())));
+    }
+    // Phones record portrait clips as landscape with a rotation, which ffmpeg applies on decode
+    if rotation.rem_euclid(180) == 90 {
+        std::mem::swap(&mut info.width, &mut info.height);
+    }
+    Ok(info)
+}
+
+// First frame, used where a video slide is shown as a still
+pub fn first_frame(path: &Path) -> ImageResult<DynamicImage> {
+    grab_frame(path, &[], &["-frames:v", "1"])
+}
+
+// Last frame, where a transition away from a finished video starts
+pub fn last_frame(path: &Path) -> ImageResult<DynamicImage> {
+    // Decode the final half second and keep overwriting the same file, leaving the last frame
+    grab_frame(path, &["-sseof", "-0.5"], &["-update", "1"])
+}
+
+fn grab_frame(path: &Path, input_args: &[&str], output_args: &[&str]) -> ImageResult<DynamicImage> {
+    let still = std::env::temp_dir().join(format!(
+        "pi-slideshow-still-{}-{}.png",
+        std::process::id(),
+        STILL_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let output = Command::new("ffmpeg")
+        .args(["-v", "error", "-y"])
+        .args(input_args)
+        .arg("-i")
+        .arg(path)
+        .args(["-an"])
+        .args(output_args)
+        .arg(&still)
+        .output()?;
+    if !output.status.success() {
+        return Err(ImageError::IoError(std::io::Error::new(
+            ErrorKind::InvalidData,
+            format!("ffmpeg could not read a frame from {}: {}", path.display(), String::from_utf8_lossy(&output.stderr).trim()),
+        )));
+    }
+    let frame = image::open(&still);
+    let _ = std::fs::remove_file(&still);
+    frame
+}
+
+// ffmpeg filter turning frames for the display, matching Orientation::rotate_image
+fn rotation_filter(orientation: &Orientation) -> Option<&'static str> {
+    match orientation {
+        Orientation::Landscape => None,
+        Orientation::Portrait => Some("transpose=clock"),
+        Orientation::InvertedLandscape => Some("hflip,vflip"),
+        Orientation::InvertedPortrait => Some("transpose=cclock"),
+    }
+}
+
+// A video being decoded. Frames are read when they are due, so the pipe fills and ffmpeg
+// waits while the slideshow is paused or busy, and playback picks up where it stopped.
+pub struct Playback {
+    path: PathBuf,
+    orientation: Orientation,
+    child: Child,
+    stdout: ChildStdout,
+    hardware: bool,
+    // Size of each decoded frame
+    width: u32,
+    height: u32,
+    frame_interval: Duration,
+    clock_start: Instant,
+    frames_read: u32,
+    paused: bool,
+}
+
+impl Playback {
+    // Decode `path` for a display `orientation`, scaled to fit within max_width x max_height
+    pub fn start(path: &Path, orientation: &Orientation, max_width: u32, max_height: u32) -> IoResult<Self> {
+        let info = probe(path)?;
+        let (source_width, source_height) = orientation.upright_size(info.width, info.height);
+        let scale = (max_width as f64 / source_width as f64).min(max_height as f64 / source_height as f64);
+        let width = ((source_width as f64 * scale).round() as u32).clamp(1, max_width);
+        let height = ((source_height as f64 * scale).round() as u32).clamp(1, max_height);
+
+        let (decoder, _) = settings();
+        let hardware = decoder.is_some() && info.codec == "h264";
+        let (child, stdout) = spawn_decoder(path, orientation, width, height, hardware)?;
+
+        info!(
+            "🎬 Playing {} ({}x{} {}, {:.2} fps, {}) at {}x{}{}",
+            path.display(),
+            info.width,
+            info.height,
+            info.codec,
+            info.frame_rate,
+            info.duration.map_or("unknown length".to_string(), |duration| format!("{:.1}s", duration.as_secs_f64())),
+            width,
+            height,
+            if hardware { ", hardware decode" } else { "" }
+        );
+
+        Ok(Playback {
+            path: path.to_path_buf(),
+            orientation: orientation.clone(),
+            child,
+            stdout,
+            hardware,
+            frame_interval: Duration::from_secs_f64(1.0 / info.frame_rate),
+            width,
+            height,
+            clock_start: Instant::now(),
+            frames_read: 0,
+            paused: false,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn frames_shown(&self) -> u32 {
+        self.frames_read
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // Resuming restarts the clock at the next frame instead of rushing to catch up
+    pub fn set_paused(&mut self, paused: bool) {
+        if self.paused && !paused {
+            self.clock_start = Instant::now() - self.frame_interval * self.frames_read;
+        }
+        self.paused = paused;
+    }
+
+    pub fn next_due(&self) -> Instant {
+        self.clock_start + self.frame_interval * self.frames_read
+    }
+
+    // The next frame, or None once the video has ended. Frames that are already late are
+    // skipped so a slow display keeps time with the soundtrack.
+    pub fn next_frame(&mut self) -> IoResult<Option<RgbaImage>> {
+        loop {
+            let frame = match self.read_frame()? {
+                Some(frame) => frame,
+                None => return Ok(None),
+            };
+            self.frames_read += 1;
+            if Instant::now() < self.next_due() + self.frame_interval {
+                return Ok(Some(frame));
+            }
+        }
+    }
+
+    fn read_frame(&mut self) -> IoResult<Option<RgbaImage>> {
+        let mut buffer = vec![0u8; self.width as usize * self.height as usize * 4];
+        match self.stdout.read_exact(&mut buffer) {
+            Ok(()) => Ok(RgbaImage::from_raw(self.width, self.height, buffer)),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                // The hardware decoder isn't on every Pi (the Pi 5 has none); try once more in software
+                if self.hardware && self.frames_read == 0 {
+                    warn!("Hardware decode of {} produced nothing, falling back to software", self.path.display());
+                    let _ = self.child.kill();
+                    let _ = self.child.wait();
+                    let (child, stdout) = spawn_decoder(&self.path, &self.orientation, self.width, self.height, false)?;
+                    self.child = child;
+                    self.stdout = stdout;
+                    self.hardware = false;
+                    return self.read_frame();
+                }
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl Drop for Playback {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn spawn_decoder(path: &Path, orientation: &Orientation, width: u32, height: u32, hardware: bool) -> IoResult<(Child, ChildStdout)> {
+    let (decoder, audio_device) = settings();
+
+    let mut filters = Vec::new();
+    if let Some(rotation) = rotation_filter(orientation) {
+        filters.push(rotation.to_string());
+    }
+    filters.push(format!("scale={}:{}", width, height));
+
+    let mut command = Command::new("ffmpeg");
+    command.args(["-v", "error", "-nostdin"]);
+    if let (true, Some(decoder)) = (hardware, decoder) {
+        command.args(["-c:v", decoder]);
+    }
+    command
+        .arg("-i")
+        .arg(path)
+        .args(["-map", "0:v:0", "-vf", &filters.join(","), "-f", "rawvideo", "-pix_fmt", "rgba", "pipe:1"]);
+    if let Some(device) = audio_device {
+        // Optional stream: clips without a soundtrack still play
+        command.args(["-map", "0:a:0?", "-f", "alsa", device]);
+    }
+
+    let mut child = command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::inherit()).spawn()?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| std::io::Error::new(ErrorKind::BrokenPipe, "ffmpeg has no output pipe"))?;
+    Ok((child, stdout))
+}
@@ -0,0 +1,65 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+use crate::slideshow_controller::SlideshowController;
+
+// Watchdog for the display loop, which beats once per pass. Decoding an image or writing the
+// framebuffer can hang outright on a failing SD card, and nothing else would notice. When the
+// beats stop for --watchdog-secs, the stall is reported on the error topic as "stalled" and
+// the process replaces itself as the restart command does, resuming on the same slide. A
+// thread stuck in a read can't be unwound from outside, so restarting the loop on its own
+// isn't an option. The watchdog runs on a thread of its own, so a loop blocking one of the
+// runtime's threads can't hold it up too.
+
+// Time the report and saving the position may take; the stuck loop may be holding locks
+// they need, and the restart goes ahead regardless
+const REPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+static START: OnceLock<Instant> = OnceLock::new();
+// Milliseconds after START of the last beat
+static LAST_BEAT_MS: AtomicU64 = AtomicU64::new(0);
+
+// Called by the display loop on every pass
+pub fn beat() {
+    LAST_BEAT_MS.store(elapsed_ms(), Ordering::Relaxed);
+}
+
+fn elapsed_ms() -> u64 {
+    START.get_or_init(Instant::now).elapsed().as_millis() as u64
+}
+
+pub fn since_last_beat() -> Duration {
+    Duration::from_millis(elapsed_ms().saturating_sub(LAST_BEAT_MS.load(Ordering::Relaxed)))
+}
+
+// Start watching; a zero timeout leaves the watchdog off. Must be called from within the runtime.
+pub fn spawn(timeout: Duration, controller: SlideshowController) {
+    if timeout.is_zero() {
+        return;
+    }
+    beat();
+    let runtime = tokio::runtime::Handle::current();
+    let check_every = (timeout / 4).max(Duration::from_secs(1));
+    let spawned = std::thread::Builder::new().name("watchdog".to_string()).spawn(move || loop {
+        std::thread::sleep(check_every);
+        let stalled_for = since_last_beat();
+        if stalled_for < timeout {
+            continue;
+        }
+
+        warn!("🐕 Display loop stuck for {}s, restarting", stalled_for.as_secs());
+        let reported = runtime.block_on(async {
+            tokio::time::timeout(REPORT_TIMEOUT, controller.report_stall(stalled_for)).await
+        });
+        if reported.is_err() {
+            warn!("Couldn't report the stall within {}s, restarting anyway", REPORT_TIMEOUT.as_secs());
+        }
+        crate::restart_process();
+    });
+    match spawned {
+        Ok(_) => info!("🐕 Watchdog restarts the endpoint if the display loop stalls for {}s", timeout.as_secs()),
+        Err(e) => error!("Failed to start the watchdog: {}", e),
+    }
+}
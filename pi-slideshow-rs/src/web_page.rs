@@ -0,0 +1,143 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+// Web page slides: an image document with a web_url has no attachment; the endpoint captures
+// the page to a PNG at the size of the screen instead, and captures it again every
+// web_refresh_secs, so a live dashboard (Grafana, a KPI board) plays like any other slide.
+// Pages are captured by headless Chromium, or fetched from a render service when
+// --web-renderer is an http(s) URL.
+
+// Used when the image document doesn't say how often to capture the page
+pub const DEFAULT_REFRESH: Duration = Duration::from_secs(5 * 60);
+// Shortest refresh honoured, so a page can't keep the browser running back to back
+pub const MIN_REFRESH: Duration = Duration::from_secs(30);
+// Under image_dir; captures are written here, then renamed over the slide
+pub const CAPTURE_DIR: &str = ".web";
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(60);
+// Time scripts get to draw charts before the screenshot is taken
+const RENDER_BUDGET_MS: u32 = 10_000;
+const DEFAULT_RENDERER: &str = "chromium";
+// Most a render service may send back, and how far past the screen its image may go (a
+// service rendering at twice the scale for sharpness still fits)
+const MAX_RENDER_BYTES: usize = 32 * 1024 * 1024;
+const MAX_RENDER_SCALE: u32 = 2;
+
+static RENDERER: OnceLock<String> = OnceLock::new();
+// Capture size as width << 32 | height: the safe area as the viewer sees it
+static VIEWPORT: AtomicU64 = AtomicU64::new(1920 << 32 | 1080);
+
+// Set once at startup (--web-renderer)
+pub fn configure(renderer: &str) {
+    if RENDERER.set(renderer.to_string()).is_err() {
+        warn!("Web page renderer already configured, ignoring");
+    }
+}
+
+// Kept up to date by the display loop, so pages are captured pixel for pixel
+pub fn set_viewport(width: u32, height: u32) {
+    VIEWPORT.store((width as u64) << 32 | height as u64, Ordering::Relaxed);
+}
+
+// The screen as the viewer sees it; text slides are drawn at this size too
+pub fn viewport() -> (u32, u32) {
+    let packed = VIEWPORT.load(Ordering::Relaxed);
+    ((packed >> 32) as u32, packed as u32)
+}
+
+// How long a capture of the page stays up before it is taken again
+pub fn refresh_interval(refresh_secs: Option<u64>) -> Duration {
+    refresh_secs.map_or(DEFAULT_REFRESH, Duration::from_secs).max(MIN_REFRESH)
+}
+
+// Capture `url` into `path`, replacing the file only once the new capture is complete. Only
+// http(s) pages are captured; the browser would read file: URLs off the device just as well.
+pub async fn capture(url: &str, path: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let parsed = url::Url::parse(url).map_err(|e| format!("invalid web_url '{}': {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("web_url '{}' isn't an http(s) URL", url).into());
+    }
+    let url = parsed.as_str();
+    let dir = path.parent().unwrap_or(Path::new(".")).join(CAPTURE_DIR);
+    std::fs::create_dir_all(&dir)?;
+    let partial = dir.join(path.file_name().ok_or("capture path has no file name")?);
+    let (width, height) = viewport();
+    let renderer = RENDERER.get().map_or(DEFAULT_RENDERER, |renderer| renderer.as_str());
+
+    let captured = if renderer.starts_with("http://") || renderer.starts_with("https://") {
+        fetch_render(renderer, url, width, height, &partial).await
+    } else {
+        screenshot(renderer, url, width, height, &partial).await
+    };
+    if let Err(e) = captured {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+    // A blank or truncated file would otherwise end up quarantined as a broken image
+    if let Err(e) = image::open(&partial) {
+        let _ = std::fs::remove_file(&partial);
+        return Err(format!("capture isn't a readable image: {}", e).into());
+    }
+    std::fs::rename(&partial, path)?;
+    info!("🌐 Captured {} at {}x{}", url, width, height);
+    Ok(())
+}
+
+async fn screenshot(browser: &str, url: &str, width: u32, height: u32, output: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Chromium only starts as root with its sandbox off, which no page off the network should get
+    if unsafe { libc::geteuid() } == 0 {
+        return Err(format!("not running {} as root; start the endpoint with --user to capture web pages", browser).into());
+    }
+    let mut command = Command::new(browser);
+    command
+        .args(["--headless=new", "--disable-gpu", "--hide-scrollbars", "--mute-audio", "--no-first-run"])
+        .arg(format!("--window-size={},{}", width, height))
+        .arg(format!("--virtual-time-budget={}", RENDER_BUDGET_MS))
+        .arg(format!("--screenshot={}", output.display()))
+        .kill_on_drop(true);
+    // Ends the switches, so the page can't pass for one
+    command.arg("--").arg(url);
+    let result = tokio::time::timeout(CAPTURE_TIMEOUT, command.output()).await
+        .map_err(|_| format!("{} took over {} seconds", browser, CAPTURE_TIMEOUT.as_secs()))?
+        .map_err(|e| format!("can't run {}: {}", browser, e))?;
+    if !result.status.success() {
+        return Err(format!("{} failed: {}", browser, String::from_utf8_lossy(&result.stderr).trim()).into());
+    }
+    Ok(())
+}
+
+// GET the render service with the page and size as query parameters; it answers with an image
+// in any format the decoder reads, stored as PNG like a browser capture. The body and the
+// decoded image are both capped, so a misbehaving service can't exhaust memory.
+async fn fetch_render(endpoint: &str, url: &str, width: u32, height: u32, output: &Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let client = reqwest::Client::builder().timeout(CAPTURE_TIMEOUT).build()?;
+    let mut response = client.get(endpoint)
+        .query(&[("url", url.to_string()), ("width", width.to_string()), ("height", height.to_string())])
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(format!("render service answered HTTP {}", response.status()).into());
+    }
+    if response.content_length().is_some_and(|len| len as usize > MAX_RENDER_BYTES) {
+        return Err(format!("render is over {} bytes", MAX_RENDER_BYTES).into());
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > MAX_RENDER_BYTES {
+            return Err(format!("render is over {} bytes", MAX_RENDER_BYTES).into());
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    let mut limits = image::io::Limits::default();
+    limits.max_image_width = Some(width.saturating_mul(MAX_RENDER_SCALE));
+    limits.max_image_height = Some(height.saturating_mul(MAX_RENDER_SCALE));
+    let mut reader = image::io::Reader::new(std::io::Cursor::new(body)).with_guessed_format()?;
+    reader.limits(limits);
+    let image = reader.decode()?;
+    image.save_with_format(output, image::ImageFormat::Png)?;
+    Ok(())
+}
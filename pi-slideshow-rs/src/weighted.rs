@@ -0,0 +1,180 @@
+use std::cmp::Reverse;
+
+// Play order when images carry weights (metadata weight, 1 by default): a smooth weighted
+// round robin, so an image of weight 3 comes up three times as often as one of weight 1,
+// spread through the rotation rather than bunched together. The image on screen never comes
+// straight back while there is another to show, so no image gets more than every other slot.
+// Ties go to the image that comes first after the one on screen, so equal weights play in
+// playlist order. Steps taken are remembered, so previous goes back through what was actually
+// on screen, as in shuffle mode.
+
+// Steps remembered for previous
+const HISTORY: usize = 100;
+
+#[derive(Debug, Default)]
+pub struct WeightedOrder {
+    // Credit per playlist index, built up by its weight every step; the highest plays next
+    credit: Vec<i64>,
+    // Weights the credit was built up for
+    weights: Vec<u32>,
+    // Playlist indices as they came up, the one on screen last
+    history: Vec<usize>,
+}
+
+impl WeightedOrder {
+    // The image after `current` in a playlist with these weights, moving to it
+    pub fn next(&mut self, current: usize, weights: &[u32]) -> usize {
+        if weights.len() <= 1 {
+            return 0;
+        }
+        self.sync(current, weights);
+        let next = step(&mut self.credit, weights, current);
+        self.history.push(next);
+        if self.history.len() > HISTORY {
+            self.history.remove(0);
+        }
+        next
+    }
+
+    // The image `next` would move to, without moving
+    pub fn peek(&mut self, current: usize, weights: &[u32]) -> usize {
+        if weights.len() <= 1 {
+            return 0;
+        }
+        self.sync(current, weights);
+        step(&mut self.credit.clone(), weights, current)
+    }
+
+    // The image shown before `current`; before the start of what's remembered it falls back to
+    // the one before it in the playlist
+    pub fn previous(&mut self, current: usize, weights: &[u32]) -> usize {
+        let len = weights.len();
+        if len <= 1 {
+            return 0;
+        }
+        self.sync(current, weights);
+        if self.history.len() < 2 {
+            return (current + len - 1) % len;
+        }
+        self.history.pop();
+        self.history[self.history.len() - 1]
+    }
+
+    // Start over from `current` when the weights changed, and forget the steps taken when the
+    // index was moved some other way (a schedule switch, a quarantined image, a restored
+    // playback state)
+    fn sync(&mut self, current: usize, weights: &[u32]) {
+        if self.weights != weights {
+            self.credit = vec![0; weights.len()];
+            self.weights = weights.to_vec();
+            self.history.clear();
+        }
+        if self.history.last() != Some(&current) {
+            self.history = vec![current.min(weights.len() - 1)];
+        }
+    }
+}
+
+fn step(credit: &mut [i64], weights: &[u32], current: usize) -> usize {
+    let len = weights.len();
+    let total: i64 = weights.iter().map(|&weight| weight as i64).sum();
+    for (credit, &weight) in credit.iter_mut().zip(weights) {
+        *credit += weight as i64;
+    }
+    let next = (0..len)
+        .filter(|&index| index != current)
+        .max_by_key(|&index| (credit[index], Reverse((index + len - current) % len)))
+        .unwrap_or(0);
+    credit[next] -= total;
+    // An image held back from playing twice in a row would otherwise bank credit without end
+    for credit in credit.iter_mut() {
+        *credit = (*credit).min(total);
+    }
+    next
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `count` steps of next from `current`, as shown
+    fn play(order: &mut WeightedOrder, mut current: usize, weights: &[u32], count: usize) -> Vec<usize> {
+        (0..count).map(|_| { current = order.next(current, weights); current }).collect()
+    }
+
+    fn plays(shown: &[usize], index: usize) -> usize {
+        shown.iter().filter(|&&shown| shown == index).count()
+    }
+
+    #[test]
+    fn weight_three_plays_three_times_as_often() {
+        let weights = [3, 1, 1, 1];
+        let mut order = WeightedOrder::default();
+        let shown = play(&mut order, 0, &weights, 600);
+        // Every window of one round (the weights' total) holds each image its weight's worth
+        for window in shown.windows(6).skip(6) {
+            assert_eq!(plays(window, 0), 3, "{:?}", window);
+            for index in 1..4 {
+                assert_eq!(plays(window, index), 1, "{:?}", window);
+            }
+        }
+    }
+
+    #[test]
+    fn no_image_plays_twice_in_a_row() {
+        for weights in [&[1, 1][..], &[5, 1], &[10, 1, 1], &[1, 0, 7, 2]] {
+            let mut order = WeightedOrder::default();
+            let shown = play(&mut order, 0, weights, 200);
+            assert!(std::iter::once(&0).chain(&shown).zip(&shown).all(|(a, b)| a != b), "{:?}: {:?}", weights, shown);
+        }
+    }
+
+    #[test]
+    fn equal_and_zero_weights_play_in_playlist_order() {
+        for weights in [[1, 1, 1, 1], [0, 0, 0, 0]] {
+            let mut order = WeightedOrder::default();
+            assert_eq!(play(&mut order, 1, &weights, 6), vec![2, 3, 0, 1, 2, 3], "{:?}", weights);
+        }
+    }
+
+    #[test]
+    fn previous_steps_back_through_what_was_shown() {
+        let weights = [3, 1, 2];
+        let mut order = WeightedOrder::default();
+        let shown = play(&mut order, 0, &weights, 8);
+        let mut current = shown[7];
+        for expected in shown[..7].iter().rev() {
+            current = order.previous(current, &weights);
+            assert_eq!(current, *expected);
+        }
+        // Before the first step there is nothing remembered, so the playlist decides
+        assert_eq!(order.previous(current, &weights), 0);
+        assert_eq!(order.previous(0, &weights), 2);
+    }
+
+    #[test]
+    fn changed_weights_start_over_from_the_image_on_screen() {
+        let mut order = WeightedOrder::default();
+        let shown = play(&mut order, 0, &[3, 1, 1, 1], 5);
+        let current = shown[4];
+        // Under these weights previous would go back to 0
+        assert_eq!((shown[3], current), (0, 3));
+
+        let weights = [3, 1, 1, 2];
+        let mut fresh = WeightedOrder::default();
+        assert_eq!(order.peek(current, &weights), fresh.peek(current, &weights));
+        let replay = order.previous(current, &weights);
+        // What was shown under the old weights is forgotten, so previous goes by the playlist
+        assert_eq!(replay, 2);
+        assert_eq!(play(&mut order, replay, &weights, 14), play(&mut fresh, replay, &weights, 14));
+    }
+
+    #[test]
+    fn single_images_stay_put() {
+        let mut order = WeightedOrder::default();
+        assert_eq!(order.next(0, &[3]), 0);
+        assert_eq!(order.peek(0, &[3]), 0);
+        assert_eq!(order.previous(0, &[3]), 0);
+        assert_eq!(order.next(0, &[]), 0);
+    }
+}
@@ -491,6 +491,9 @@ class DigitalSignageApp {
             document.getElementById('tv-transition').value = tv.config?.transition_effect || 'fade';
             document.getElementById('tv-duration').value = tv.config?.display_duration || 5000;
             document.getElementById('tv-orientation').value = tv.config?.orientation || 'landscape';
+            // Stored resolutions follow the orientation; the select lists landscape presets
+            const isUhd = /3840|2160/.test(tv.config?.resolution || '');
+            document.getElementById('tv-resolution').value = isUhd ? '3840x2160' : '1920x1080';
             form.dataset.tvId = tv._id;
         } else {
             title.textContent = 'Add TV';
@@ -508,7 +511,8 @@ class DigitalSignageApp {
         const isEdit = !!form.dataset.tvId;
         
         const orientation = document.getElementById('tv-orientation').value;
-        const resolution = orientation === 'portrait' ? '1080x1920' : '1920x1080';
+        const [width, height] = document.getElementById('tv-resolution').value.split('x');
+        const resolution = orientation.endsWith('portrait') ? `${height}x${width}` : `${width}x${height}`;
         
         const tvData = {
             name: document.getElementById('tv-name').value,
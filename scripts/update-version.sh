@@ -16,8 +16,9 @@ echo "Updating version to $VERSION..."
 # Update package.json
 npm version $VERSION --no-git-tag-version
 
-# Update Rust Cargo.toml
+# Update Rust Cargo.toml files (device binary and shared protocol crate)
 sed -i "s/^version = \".*\"/version = \"$VERSION\"/" pi-slideshow-rs/Cargo.toml
+sed -i "s/^version = \".*\"/version = \"$VERSION\"/" signage-protocol/Cargo.toml
 
 # Update UI version display
 sed -i "s/this.updateVersionDisplay('.*')/this.updateVersionDisplay('$VERSION')/" public/js/app.js
@@ -0,0 +1,28 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use signage_protocol::{CouchImage, CouchTv, ImageInfo};
+
+// Documents come back from CouchDB as serde_json::Value, so parse the same way the endpoint does
+fuzz_target!(|data: &[u8]| {
+    let Ok(doc) = serde_json::from_slice::<serde_json::Value>(data) else {
+        return;
+    };
+
+    if let Ok(image) = serde_json::from_value::<CouchImage>(doc.clone()) {
+        let info = ImageInfo {
+            path: String::new(),
+            order: 0,
+            url: None,
+            extension: Some(image.file_extension()),
+            letterbox_fill: image.metadata.letterbox_fill,
+            fit_mode: image.metadata.fit_mode,
+            id: image.id,
+        };
+        let _ = info.local_file_name();
+    }
+
+    if let Ok(tv) = serde_json::from_value::<CouchTv>(doc) {
+        let _ = serde_json::to_value(&tv);
+    }
+});
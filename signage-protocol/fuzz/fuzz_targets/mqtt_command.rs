@@ -0,0 +1,21 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use signage_protocol::{MqttCommand, SlideshowConfig};
+
+// Same decode path the endpoint runs on every message from signage/tv/{tv_id}/command
+fuzz_target!(|data: &[u8]| {
+    let Ok(command) = MqttCommand::from_slice(data) else {
+        return;
+    };
+
+    let _ = SlideshowConfig::from_payload(&command.payload);
+
+    if let Ok(images) = command.images() {
+        for image in &images {
+            if let Some(name) = image.local_file_name() {
+                assert!(!name.contains('/') && !name.contains('\\'), "unsafe file name {:?}", name);
+            }
+        }
+    }
+});
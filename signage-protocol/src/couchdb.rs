@@ -0,0 +1,892 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CouchImage {
+    #[serde(rename = "_id")]
+    pub id: String,
+    #[serde(rename = "_rev", skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    #[serde(rename = "type")]
+    pub doc_type: String,
+    pub original_name: String,
+    pub size: u64,
+    pub metadata: ImageMetadata,
+    pub assigned_tvs: Vec<String>,
+    // Also shown on every TV whose config lists one of these tags (e.g. "lobby", "floor-3")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assigned_tags: Vec<String>,
+    #[serde(alias = "upload_date")]
+    pub created_at: String,
+    // RFC 3339 times the image is shown from and until, for campaigns that start or end on
+    // their own; either may be left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_from: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<String>,
+    // A web page slide: no attachment, the TV captures this page as a PNG and captures it
+    // again every web_refresh_secs (5 minutes when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub web_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub web_refresh_secs: Option<u64>,
+    #[serde(rename = "_attachments", skip_serializing_if = "Option::is_none")]
+    pub attachments: Option<HashMap<String, Attachment>>,
+}
+
+impl CouchImage {
+    pub fn is_assigned_to(&self, tv_id: &str, tags: &[String]) -> bool {
+        is_targeted(&self.assigned_tvs, &self.assigned_tags, tv_id, tags)
+    }
+
+    // Extension (with leading dot) for the stored file: the attachment content type wins,
+    // then the metadata format, then the original file name
+    pub fn file_extension(&self) -> String {
+        let from_content_type = self.attachments
+            .as_ref()
+            .and_then(|attachments| attachments.values().next())
+            .and_then(|attachment| format_extension(&attachment.content_type));
+
+        if let Some(extension) = from_content_type {
+            extension.to_string()
+        } else if !self.metadata.format.is_empty() {
+            format_extension(&self.metadata.format)
+                .map(|extension| extension.to_string())
+                .unwrap_or_else(|| format!(".{}", self.metadata.format.to_lowercase()))
+        } else {
+            Path::new(&self.original_name)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(|ext| format!(".{}", ext))
+                .unwrap_or_else(|| ".png".to_string())
+        }
+    }
+
+    // The attachment's digest, or the document revision when CouchDB gave no digest
+    pub fn content_revision(&self) -> Option<String> {
+        self.attachments
+            .as_ref()
+            .and_then(|attachments| attachments.values().next())
+            .and_then(|attachment| attachment.digest.clone())
+            .or_else(|| self.rev.clone())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageMetadata {
+    // 0 when the uploader doesn't know them, as for videos
+    #[serde(default)]
+    pub width: u32,
+    #[serde(default)]
+    pub height: u32,
+    #[serde(default = "default_format")]
+    pub format: String,
+    // Letterbox fill for this image ("color", "blur" or "image"), over the TV's letterbox_fill
+    #[serde(default)]
+    pub letterbox_fill: Option<String>,
+    // How the image is sized on screen ("contain", "cover", "stretch" or "native");
+    // contain when unset
+    #[serde(default)]
+    pub fit_mode: Option<String>,
+    // Link drawn as a QR code on the slide, in qr_corner ("top_left", "top_right",
+    // "bottom_left" or "bottom_right"); bottom right when the corner is unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qr_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qr_corner: Option<String>,
+    // How often the image comes up relative to the others on a TV, e.g. 3 for three times as
+    // often as the default of 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<u32>,
+}
+
+fn default_format() -> String {
+    "png".to_string()
+}
+
+// Extension for an image or video format name as upload tools report it ("jpeg", "WEBP", "image/png", "video/mp4")
+pub fn format_extension(format: &str) -> Option<&'static str> {
+    let format = format.to_lowercase();
+    let format = format.strip_prefix("image/").or_else(|| format.strip_prefix("video/")).unwrap_or(&format);
+    match format {
+        "jpeg" | "jpg" => Some(".jpg"),
+        "png" => Some(".png"),
+        "gif" => Some(".gif"),
+        "webp" => Some(".webp"),
+        "avif" => Some(".avif"),
+        "mp4" => Some(".mp4"),
+        "quicktime" | "mov" => Some(".mov"),
+        "x-m4v" | "m4v" => Some(".m4v"),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attachment {
+    pub content_type: String,
+    pub length: u64,
+    pub digest: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CouchTv {
+    #[serde(rename = "_id")]
+    pub id: String,
+    #[serde(rename = "_rev", skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    #[serde(rename = "type")]
+    pub doc_type: String,
+    pub name: String,
+    pub location: String,
+    pub ip_address: String,
+    pub status: String,
+    pub last_heartbeat: Option<String>,
+    pub config: TvConfig,
+    pub current_image: Option<String>,
+    // Images the TV couldn't decode and took out of its playlist, most recent last
+    #[serde(default)]
+    pub image_errors: Vec<ImageErrorReport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageErrorReport {
+    pub image_id: String,
+    pub error: String,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TvConfig {
+    pub transition_effect: String,
+    pub display_duration: u64,
+    #[serde(default = "default_orientation")]
+    pub orientation: String,
+    #[serde(default)]
+    pub content_safety: bool,
+    #[serde(default = "default_text_profile")]
+    pub text_profile: String,
+    // Keep-alive rewrite of a static frame for displays that blank without updates; 0 disables
+    #[serde(default)]
+    pub framebuffer_refresh_secs: u64,
+    #[serde(default)]
+    pub skip_redundant_writes: bool,
+    // Output color correction; 1.0 and 6500K leave colors unchanged
+    #[serde(default = "default_gamma")]
+    pub gamma: f64,
+    #[serde(default = "default_color_temperature")]
+    pub color_temperature: u32,
+    // Panel brightness in percent outside any dimming window
+    #[serde(default = "default_brightness")]
+    pub brightness: u8,
+    #[serde(default)]
+    pub dimming_schedule: Vec<DimmingWindow>,
+    // Times the display is blanked and rendering stops, e.g. outside business hours
+    #[serde(default)]
+    pub quiet_hours: Vec<QuietWindow>,
+    // Times the curtain is drawn: the screen shows curtain_color, or the image document named
+    // by curtain_image (e.g. a "back soon" card), and rendering pauses while the panel stays on
+    #[serde(default)]
+    pub curtain_hours: Vec<QuietWindow>,
+    #[serde(default = "default_curtain_color")]
+    pub curtain_color: String,
+    #[serde(default)]
+    pub curtain_image: Option<String>,
+    // What fills the bars around an image whose aspect ratio doesn't match the screen:
+    // "color" (letterbox_color), "blur" (a blurred copy of the image stretched to the screen)
+    // or "image" (the image document named by letterbox_image, e.g. a branded backdrop)
+    #[serde(default = "default_letterbox_fill")]
+    pub letterbox_fill: String,
+    // "#RRGGBB"; also used while a background image is missing
+    #[serde(default = "default_letterbox_color")]
+    pub letterbox_color: String,
+    #[serde(default)]
+    pub letterbox_image: Option<String>,
+    #[serde(default)]
+    pub safe_area: SafeArea,
+    // Slow zoom or pan over stills while they are up: "off", "zoom_in", "zoom_out", "pan" or
+    // "random"; pans and random motions are picked per image
+    #[serde(default = "default_ken_burns")]
+    pub ken_burns: String,
+    // How far the view zooms in, as a fraction of the screen
+    #[serde(default = "default_ken_burns_intensity")]
+    pub ken_burns_intensity: f64,
+    // Crop images shown with the "cover" fit mode around their most detailed part rather than
+    // their center, so subjects aren't cut off
+    #[serde(default)]
+    pub smart_crop: bool,
+    // Timing curve for every transition: "default" (each effect's own), a CSS keyword such as
+    // "ease-in-out", or "cubic-bezier(x1, y1, x2, y2)"
+    #[serde(default = "default_transition_easing")]
+    pub transition_easing: String,
+    // Milliseconds to play particular effects for, by effect name (e.g. "circular_wipe": 2000);
+    // effects not listed play for transition_duration scaled by their own pacing
+    #[serde(default)]
+    pub transition_durations: HashMap<String, u64>,
+    // Play the images in random order, each once per cycle, instead of in playlist order
+    #[serde(default)]
+    pub shuffle: bool,
+    // RSS or Atom feed whose headlines scroll along the bottom of the screen while no ticker
+    // text has been published on the ticker topic
+    #[serde(default)]
+    pub ticker_rss_url: Option<String>,
+    // Groups the TV belongs to; images and text slides assigned to any of them play here as
+    // if assigned to the TV itself
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+// Brightness to use between two local times of day ("HH:MM"), e.g. 40% from 20:00 to 07:00.
+// Windows may wrap past midnight; the first one containing the current time wins.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DimmingWindow {
+    pub start: String,
+    pub end: String,
+    pub brightness: u8,
+}
+
+impl DimmingWindow {
+    pub fn contains(&self, minute_of_day: u32) -> bool {
+        in_daily_window(&self.start, &self.end, minute_of_day)
+    }
+}
+
+// A local time span ("HH:MM") during which the screen is off. `days` ("mon".."sun") limits
+// the window to the days it starts on, so a Friday 20:00-07:00 window runs into Saturday
+// morning; empty means every day.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuietWindow {
+    pub start: String,
+    pub end: String,
+    #[serde(default)]
+    pub days: Vec<String>,
+}
+
+impl QuietWindow {
+    // `weekday` counts from Monday = 0
+    pub fn contains(&self, weekday: u32, minute_of_day: u32) -> bool {
+        let (start, end) = match (parse_time_of_day(&self.start), parse_time_of_day(&self.end)) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return false,
+        };
+
+        if start <= end {
+            self.applies_on(weekday) && (start..end).contains(&minute_of_day)
+        } else if minute_of_day >= start {
+            self.applies_on(weekday)
+        } else {
+            // Early-morning tail of a window that started the day before
+            minute_of_day < end && self.applies_on((weekday + 6) % 7)
+        }
+    }
+
+    fn applies_on(&self, weekday: u32) -> bool {
+        self.days.is_empty() || self.days.iter().any(|day| parse_weekday(day) == Some(weekday))
+    }
+}
+
+// A daypart ("schedule" document): while its window is open the TV plays only `image_ids`, in
+// that order, e.g. the breakfast menu from 06:00 to 11:00. The window follows the quiet hours
+// rules for wrapping past midnight and for `days`. The images must also be assigned to the TV.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CouchSchedule {
+    #[serde(rename = "_id")]
+    pub id: String,
+    #[serde(rename = "_rev", skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    #[serde(rename = "type")]
+    pub doc_type: String,
+    // TV document id ("tv_{tv_id}")
+    pub assigned_tv_id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub window: QuietWindow,
+    pub image_ids: Vec<String>,
+}
+
+// An announcement ("text_slide" document) the TV draws itself as a full-screen card and plays
+// among the images assigned to it, so posting one needs no image file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CouchTextSlide {
+    #[serde(rename = "_id")]
+    pub id: String,
+    #[serde(rename = "_rev", skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    #[serde(rename = "type")]
+    pub doc_type: String,
+    #[serde(flatten)]
+    pub slide: TextSlide,
+    #[serde(default)]
+    pub assigned_tvs: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assigned_tags: Vec<String>,
+    // Shown from and until these RFC 3339 times, as for images
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_from: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<String>,
+}
+
+impl CouchTextSlide {
+    pub fn is_assigned_to(&self, tv_id: &str, tags: &[String]) -> bool {
+        is_targeted(&self.assigned_tvs, &self.assigned_tags, tv_id, tags)
+    }
+}
+
+// Whether content goes to a TV: by its document id ("tv_{tv_id}") in assigned_tvs, or by one
+// of the TV's tags in assigned_tags. Tags match regardless of case.
+fn is_targeted(assigned_tvs: &[String], assigned_tags: &[String], tv_id: &str, tags: &[String]) -> bool {
+    assigned_tvs.iter().any(|id| id == tv_id)
+        || assigned_tags.iter().any(|tag| tags.iter().any(|own| own.eq_ignore_ascii_case(tag)))
+}
+
+// A release of the endpoint on an update channel: the "release_{channel}" document, or the
+// same JSON served from an update URL. The version and the binary's SHA-256 are signed together
+// with the release key (see signed_message), and rollout_percent releases it to a share of the TVs first, picked by a hash of the TV id so
+// the same TVs lead every rollout.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Release {
+    pub version: String,
+    pub url: String,
+    // Hex SHA-256 of the binary, and the hex Ed25519 signature of signed_message()
+    pub sha256: String,
+    pub signature: String,
+    #[serde(default = "default_rollout_percent")]
+    pub rollout_percent: u8,
+}
+
+impl Release {
+    pub fn is_rolled_out_to(&self, tv_id: &str) -> bool {
+        rollout_bucket(tv_id) < u32::from(self.rollout_percent.min(100))
+    }
+
+    pub fn is_newer_than(&self, version: &str) -> bool {
+        compare_versions(&self.version, version) == std::cmp::Ordering::Greater
+    }
+
+    // "{version}\n{sha256}", the digest in lowercase hex. Signing the version along with the
+    // binary keeps an old signed binary from being served up again as a newer release.
+    pub fn signed_message(&self) -> String {
+        format!("{}\n{}", self.version, self.sha256.trim().to_lowercase())
+    }
+}
+
+fn default_rollout_percent() -> u8 {
+    100
+}
+
+// 0-99 from an FNV-1a hash, which unlike the std hasher is the same on every build
+fn rollout_bucket(tv_id: &str) -> u32 {
+    let hash = tv_id.bytes().fold(0x811c9dc5u32, |hash, byte| (hash ^ u32::from(byte)).wrapping_mul(0x01000193));
+    hash % 100
+}
+
+// Dotted versions compared part by part as numbers, so 1.10.0 is newer than 1.9.2; a
+// pre-release suffix ("1.2.0-rc1") sorts before the release
+pub fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    fn parse(version: &str) -> (Vec<u64>, bool) {
+        let version = version.trim().trim_start_matches('v');
+        let (numbers, pre_release) = match version.split_once('-') {
+            Some((numbers, _)) => (numbers, true),
+            None => (version, false),
+        };
+        (numbers.split('.').map(|part| part.parse().unwrap_or(0)).collect(), pre_release)
+    }
+    let ((mut a, a_pre), (mut b, b_pre)) = (parse(a), parse(b));
+    let len = a.len().max(b.len());
+    a.resize(len, 0);
+    b.resize(len, 0);
+    a.cmp(&b).then(b_pre.cmp(&a_pre))
+}
+
+// What a text slide shows. Colors are "#rrggbb"; the template is "centered" (title over body,
+// both centered), "left" (aligned to the left edge) or "banner" (the title on a band across
+// the top in the text color, the body below). With countdown_to (RFC 3339) the slide is a
+// countdown: "{countdown}" in the title or body reads as the time left, or the time since
+// once it has passed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextSlide {
+    pub title: String,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default = "default_slide_background")]
+    pub background_color: String,
+    #[serde(default = "default_slide_text")]
+    pub text_color: String,
+    #[serde(default = "default_slide_template")]
+    pub template: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub countdown_to: Option<String>,
+}
+
+fn default_slide_background() -> String {
+    "#1e3a5f".to_string()
+}
+
+fn default_slide_text() -> String {
+    "#ffffff".to_string()
+}
+
+fn default_slide_template() -> String {
+    "centered".to_string()
+}
+
+// Overscan compensation: pixels kept clear of content along each edge for TVs that crop the
+// picture. Edges are the panel's own, so they stay put when the orientation changes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SafeArea {
+    #[serde(default)]
+    pub top: u32,
+    #[serde(default)]
+    pub right: u32,
+    #[serde(default)]
+    pub bottom: u32,
+    #[serde(default)]
+    pub left: u32,
+}
+
+// Largest margin accepted on any one edge
+pub const MAX_SAFE_AREA_MARGIN: u32 = 500;
+
+impl SafeArea {
+    pub fn is_valid(&self) -> bool {
+        [self.top, self.right, self.bottom, self.left].iter().all(|&margin| margin <= MAX_SAFE_AREA_MARGIN)
+    }
+}
+
+// Monday = 0; accepts "mon" or "monday" in any case
+pub fn parse_weekday(s: &str) -> Option<u32> {
+    let day = s.trim().to_lowercase();
+    ["mon", "tue", "wed", "thu", "fri", "sat", "sun"]
+        .iter()
+        .position(|prefix| day.starts_with(prefix))
+        .map(|index| index as u32)
+}
+
+// Minutes since midnight for an "HH:MM" time of day
+pub fn parse_time_of_day(s: &str) -> Option<u32> {
+    let (hours, minutes) = s.trim().split_once(':')?;
+    let hours: u32 = hours.parse().ok()?;
+    let minutes: u32 = minutes.parse().ok()?;
+    if hours < 24 && minutes < 60 {
+        Some(hours * 60 + minutes)
+    } else {
+        None
+    }
+}
+
+// RGB from a "#RRGGBB" color (the leading # is optional)
+pub fn parse_hex_color(s: &str) -> Option<[u8; 3]> {
+    let hex = s.trim().strip_prefix('#').unwrap_or(s.trim());
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+// Whether `minute_of_day` falls in [start, end), wrapping past midnight when end is earlier
+// than start. Unparseable bounds never match.
+pub fn in_daily_window(start: &str, end: &str, minute_of_day: u32) -> bool {
+    match (parse_time_of_day(start), parse_time_of_day(end)) {
+        (Some(start), Some(end)) if start <= end => (start..end).contains(&minute_of_day),
+        (Some(start), Some(end)) => minute_of_day >= start || minute_of_day < end,
+        _ => false,
+    }
+}
+
+fn default_orientation() -> String {
+    "landscape".to_string()
+}
+
+fn default_text_profile() -> String {
+    "standard".to_string()
+}
+
+fn default_gamma() -> f64 {
+    1.0
+}
+
+fn default_color_temperature() -> u32 {
+    6500
+}
+
+fn default_brightness() -> u8 {
+    100
+}
+
+fn default_letterbox_fill() -> String {
+    "color".to_string()
+}
+
+fn default_letterbox_color() -> String {
+    "#000000".to_string()
+}
+
+fn default_curtain_color() -> String {
+    "#000000".to_string()
+}
+
+fn default_ken_burns() -> String {
+    "off".to_string()
+}
+
+fn default_ken_burns_intensity() -> f64 {
+    0.15
+}
+
+fn default_transition_easing() -> String {
+    "default".to_string()
+}
+
+impl Default for TvConfig {
+    fn default() -> Self {
+        Self {
+            transition_effect: "fade".to_string(),
+            display_duration: 5000,
+            orientation: default_orientation(),
+            content_safety: false,
+            text_profile: default_text_profile(),
+            framebuffer_refresh_secs: 0,
+            skip_redundant_writes: false,
+            gamma: default_gamma(),
+            color_temperature: default_color_temperature(),
+            brightness: default_brightness(),
+            dimming_schedule: Vec::new(),
+            quiet_hours: Vec::new(),
+            curtain_hours: Vec::new(),
+            curtain_color: default_curtain_color(),
+            curtain_image: None,
+            letterbox_fill: default_letterbox_fill(),
+            letterbox_color: default_letterbox_color(),
+            letterbox_image: None,
+            safe_area: SafeArea::default(),
+            ken_burns: default_ken_burns(),
+            ken_burns_intensity: default_ken_burns_intensity(),
+            smart_crop: false,
+            transition_easing: default_transition_easing(),
+            transition_durations: HashMap::new(),
+            shuffle: false,
+            ticker_rss_url: None,
+            tags: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "couch")]
+mod typed_document {
+    use super::CouchTv;
+    use couch_rs::document::TypedCouchDocument;
+    use std::borrow::Cow;
+
+    impl TypedCouchDocument for CouchTv {
+        fn get_id(&self) -> Cow<str> {
+            Cow::Borrowed(&self.id)
+        }
+
+        fn get_rev(&self) -> Cow<str> {
+            Cow::Borrowed(self.rev.as_deref().unwrap_or(""))
+        }
+
+        fn set_id(&mut self, id: &str) {
+            self.id = id.to_string();
+        }
+
+        fn set_rev(&mut self, rev: &str) {
+            self.rev = Some(rev.to_string());
+        }
+
+        fn merge_ids(&mut self, other: &Self) {
+            self.id = other.id.clone();
+            self.rev = other.rev.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tv_config_fills_missing_fields_with_defaults() {
+        let config: TvConfig = serde_json::from_value(serde_json::json!({
+            "transition_effect": "fade",
+            "display_duration": 5000
+        }))
+        .unwrap();
+        assert_eq!(config.orientation, "landscape");
+        assert!(!config.content_safety);
+        assert_eq!(config.text_profile, "standard");
+        assert_eq!(config.brightness, 100);
+        assert!(config.dimming_schedule.is_empty());
+        assert!(config.curtain_hours.is_empty());
+        assert_eq!(config.curtain_color, "#000000");
+        assert_eq!(config.letterbox_fill, "color");
+        assert_eq!(config.letterbox_color, "#000000");
+        assert_eq!(config.safe_area, SafeArea::default());
+        assert_eq!(config.ken_burns, "off");
+        assert_eq!(config.transition_easing, "default");
+        assert!(config.transition_durations.is_empty());
+        assert!(!config.shuffle);
+        assert_eq!(config.ticker_rss_url, None);
+        assert!(config.tags.is_empty());
+    }
+
+    #[test]
+    fn safe_area_fills_missing_edges_with_zero() {
+        let area: SafeArea = serde_json::from_value(serde_json::json!({ "top": 24, "left": 32 })).unwrap();
+        assert_eq!(area, SafeArea { top: 24, right: 0, bottom: 0, left: 32 });
+        assert!(area.is_valid());
+        assert!(!SafeArea { bottom: MAX_SAFE_AREA_MARGIN + 1, ..area }.is_valid());
+    }
+
+    #[test]
+    fn hex_colors_parse() {
+        assert_eq!(parse_hex_color("#1a2B3c"), Some([0x1a, 0x2b, 0x3c]));
+        assert_eq!(parse_hex_color("ffffff"), Some([255, 255, 255]));
+        assert_eq!(parse_hex_color("#fff"), None);
+        assert_eq!(parse_hex_color("#gg0000"), None);
+        assert_eq!(parse_hex_color("#ü0000"), None);
+    }
+
+    #[test]
+    fn dimming_window_wraps_past_midnight() {
+        let night = DimmingWindow { start: "20:00".to_string(), end: "07:00".to_string(), brightness: 40 };
+        assert!(night.contains(20 * 60));
+        assert!(night.contains(23 * 60 + 59));
+        assert!(night.contains(0));
+        assert!(night.contains(6 * 60 + 59));
+        assert!(!night.contains(7 * 60));
+        assert!(!night.contains(12 * 60));
+
+        let lunch = DimmingWindow { start: "12:00".to_string(), end: "13:30".to_string(), brightness: 70 };
+        assert!(lunch.contains(12 * 60 + 45));
+        assert!(!lunch.contains(13 * 60 + 30));
+
+        assert_eq!(parse_time_of_day("7:05"), Some(7 * 60 + 5));
+        assert_eq!(parse_time_of_day("24:00"), None);
+        assert!(!in_daily_window("late", "07:00", 0));
+    }
+
+    #[test]
+    fn quiet_window_days_follow_the_start_day() {
+        let friday_night = QuietWindow {
+            start: "20:00".to_string(),
+            end: "07:00".to_string(),
+            days: vec!["fri".to_string()],
+        };
+        assert!(friday_night.contains(4, 21 * 60));
+        assert!(friday_night.contains(5, 6 * 60));
+        assert!(!friday_night.contains(5, 21 * 60));
+        assert!(!friday_night.contains(4, 6 * 60));
+
+        let weekend: QuietWindow = serde_json::from_value(serde_json::json!({
+            "start": "00:00", "end": "23:59", "days": ["Saturday", "sun"]
+        }))
+        .unwrap();
+        assert!(weekend.contains(6, 12 * 60));
+        assert!(!weekend.contains(0, 12 * 60));
+        assert_eq!(parse_weekday("someday"), None);
+    }
+
+    #[test]
+    fn schedule_window_is_flattened() {
+        let schedule: CouchSchedule = serde_json::from_value(serde_json::json!({
+            "_id": "schedule_1",
+            "type": "schedule",
+            "assigned_tv_id": "tv_lobby",
+            "name": "Breakfast",
+            "start": "06:00",
+            "end": "11:00",
+            "image_ids": ["image_2", "image_1"]
+        }))
+        .unwrap();
+        assert!(schedule.window.days.is_empty());
+        assert!(schedule.window.contains(0, 6 * 60));
+        assert!(!schedule.window.contains(3, 11 * 60));
+
+        let value = serde_json::to_value(&schedule).unwrap();
+        assert_eq!(value["start"], "06:00");
+        assert!(value.get("window").is_none());
+    }
+
+    #[test]
+    fn text_slide_fills_in_defaults() {
+        let doc: CouchTextSlide = serde_json::from_value(serde_json::json!({
+            "_id": "text_slide_1",
+            "type": "text_slide",
+            "title": "Fire drill at 3pm",
+            "assigned_tvs": ["tv_lobby"]
+        }))
+        .unwrap();
+        assert_eq!(doc.slide.body, "");
+        assert_eq!(doc.slide.background_color, "#1e3a5f");
+        assert_eq!(doc.slide.text_color, "#ffffff");
+        assert_eq!(doc.slide.template, "centered");
+        assert_eq!(doc.slide.countdown_to, None);
+
+        let value = serde_json::to_value(&doc).unwrap();
+        assert_eq!(value["title"], "Fire drill at 3pm");
+        assert!(value.get("slide").is_none());
+    }
+
+    #[test]
+    fn content_targets_tvs_by_id_or_tag() {
+        let image: CouchImage = serde_json::from_value(serde_json::json!({
+            "_id": "image_1",
+            "type": "image",
+            "original_name": "menu.jpg",
+            "size": 1024,
+            "metadata": { "width": 1920, "height": 1080 },
+            "assigned_tvs": ["tv_kiosk"],
+            "assigned_tags": ["lobby", "floor-3"],
+            "created_at": "2024-01-01T12:00:00Z"
+        }))
+        .unwrap();
+        let tags = |tags: &[&str]| tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>();
+        assert!(image.is_assigned_to("tv_kiosk", &[]));
+        assert!(image.is_assigned_to("tv_front", &tags(&["entrance", "Lobby"])));
+        assert!(!image.is_assigned_to("tv_front", &tags(&["floor-2"])));
+        assert!(!image.is_assigned_to("tv_front", &[]));
+
+        let untagged: CouchTextSlide = serde_json::from_value(serde_json::json!({
+            "_id": "text_slide_1",
+            "type": "text_slide",
+            "title": "Welcome",
+            "assigned_tvs": ["tv_kiosk"]
+        }))
+        .unwrap();
+        assert!(untagged.assigned_tags.is_empty());
+        assert!(!untagged.is_assigned_to("tv_front", &tags(&["lobby"])));
+        assert!(serde_json::to_value(&untagged).unwrap().get("assigned_tags").is_none());
+    }
+
+    #[test]
+    fn image_accepts_legacy_upload_date() {
+        let image: CouchImage = serde_json::from_value(serde_json::json!({
+            "_id": "image_1",
+            "_rev": "1-abc",
+            "type": "image",
+            "original_name": "lobby.jpg",
+            "size": 1024,
+            "metadata": { "width": 1920, "height": 1080 },
+            "assigned_tvs": ["tv_lobby"],
+            "upload_date": "2024-01-01T12:00:00Z",
+            "_attachments": {
+                "lobby.jpg": { "content_type": "image/jpeg", "length": 1024, "digest": null }
+            }
+        }))
+        .unwrap();
+        assert_eq!(image.created_at, "2024-01-01T12:00:00Z");
+        assert_eq!(image.valid_from, None);
+        assert_eq!(image.valid_until, None);
+        assert_eq!(image.metadata.qr_url, None);
+        assert_eq!(image.metadata.weight, None);
+        assert_eq!(image.web_url, None);
+        assert_eq!(image.metadata.format, "png");
+        assert_eq!(image.file_extension(), ".jpg");
+        assert_eq!(image.content_revision().as_deref(), Some("1-abc"));
+        assert_eq!(image.attachments.unwrap()["lobby.jpg"].content_type, "image/jpeg");
+    }
+
+    #[test]
+    fn image_extension_follows_metadata_format() {
+        let image = |format: &str| -> CouchImage {
+            serde_json::from_value(serde_json::json!({
+                "_id": "image_1",
+                "type": "image",
+                "original_name": "banner",
+                "size": 1024,
+                "metadata": { "width": 1920, "height": 1080, "format": format },
+                "assigned_tvs": ["tv_lobby"],
+                "created_at": "2024-01-01T12:00:00Z"
+            }))
+            .unwrap()
+        };
+        assert_eq!(image("webp").file_extension(), ".webp");
+        assert_eq!(image("WEBP").file_extension(), ".webp");
+        assert_eq!(image("image/webp").file_extension(), ".webp");
+        assert_eq!(image("jpeg").file_extension(), ".jpg");
+        assert_eq!(image("image/avif").file_extension(), ".avif");
+        assert_eq!(image("video/mp4").file_extension(), ".mp4");
+        assert_eq!(image("video/quicktime").file_extension(), ".mov");
+        assert_eq!(image("tiff").file_extension(), ".tiff");
+    }
+
+    #[test]
+    fn video_document_without_dimensions() {
+        let video: CouchImage = serde_json::from_value(serde_json::json!({
+            "_id": "image_2",
+            "type": "image",
+            "original_name": "promo.mp4",
+            "size": 4096,
+            "metadata": { "format": "mp4" },
+            "assigned_tvs": ["tv_lobby"],
+            "created_at": "2024-01-01T12:00:00Z",
+            "_attachments": {
+                "promo.mp4": { "content_type": "video/mp4", "length": 4096, "digest": null }
+            }
+        }))
+        .unwrap();
+        assert_eq!((video.metadata.width, video.metadata.height), (0, 0));
+        assert_eq!(video.metadata.letterbox_fill, None);
+        assert_eq!(video.metadata.fit_mode, None);
+        assert_eq!(video.file_extension(), ".mp4");
+    }
+
+    #[test]
+    fn tv_round_trips_without_rev() {
+        let tv = CouchTv {
+            id: "tv_lobby".to_string(),
+            rev: None,
+            doc_type: "tv".to_string(),
+            name: "Lobby".to_string(),
+            location: "Entrance".to_string(),
+            ip_address: "10.0.0.5".to_string(),
+            status: "online".to_string(),
+            last_heartbeat: None,
+            config: TvConfig::default(),
+            current_image: None,
+            image_errors: Vec::new(),
+        };
+        let value = serde_json::to_value(&tv).unwrap();
+        assert!(value.get("_rev").is_none());
+        assert_eq!(value["type"], "tv");
+        let parsed: CouchTv = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.config.transition_effect, "fade");
+    }
+
+    #[test]
+    fn versions_compare_numerically() {
+        use std::cmp::Ordering;
+        assert_eq!(compare_versions("1.10.0", "1.9.2"), Ordering::Greater);
+        assert_eq!(compare_versions("0.2", "0.2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("v0.3.0", "0.2.9"), Ordering::Greater);
+        assert_eq!(compare_versions("1.2.0-rc1", "1.2.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.2.0-rc1", "1.1.9"), Ordering::Greater);
+    }
+
+    #[test]
+    fn release_rolls_out_to_a_growing_share_of_tvs() {
+        let release = |rollout_percent| Release {
+            version: "0.3.0".to_string(),
+            url: "https://updates.example/pi-slideshow-rs".to_string(),
+            sha256: String::new(),
+            signature: String::new(),
+            rollout_percent,
+        };
+        let tvs: Vec<String> = (0..200).map(|n| format!("tv{}", n)).collect();
+        let reached = |percent| tvs.iter().filter(|tv| release(percent).is_rolled_out_to(tv)).count();
+        assert_eq!(reached(0), 0);
+        assert_eq!(reached(100), tvs.len());
+        assert!(reached(10) > 0 && reached(10) < reached(50));
+        // A TV in an early stage stays in every later one
+        assert!(tvs.iter().all(|tv| !release(10).is_rolled_out_to(tv) || release(50).is_rolled_out_to(tv)));
+
+        let manifest: Release = serde_json::from_str(r#"{"version":"0.3.0","url":"u","sha256":"","signature":""}"#).unwrap();
+        assert_eq!(manifest.rollout_percent, 100);
+        assert!(manifest.is_newer_than("0.2.0") && !manifest.is_newer_than("0.3.0"));
+        let signed: Release = serde_json::from_str(r#"{"version":"0.3.0","url":"u","sha256":" 9F86D0 ","signature":""}"#).unwrap();
+        assert_eq!(signed.signed_message(), "0.3.0\n9f86d0");
+    }
+}
@@ -0,0 +1,10 @@
+//! Wire types shared between the TV endpoint and server-side tooling.
+//!
+//! Everything that crosses MQTT or lives in the `digital_signage` CouchDB database
+//! is defined here so both ends serialize the same JSON.
+
+pub mod couchdb;
+pub mod mqtt;
+
+pub use couchdb::{Attachment, CouchImage, CouchSchedule, CouchTextSlide, CouchTv, DimmingWindow, ImageErrorReport, ImageMetadata, QuietWindow, Release, SafeArea, TextSlide, TvConfig};
+pub use mqtt::{ClockStatus, CrashReport, DownloadStats, HeartbeatMessage, ImageInfo, MqttCommand, NetworkInterface, OccupancyStats, PiHardware, RenderStats, SdCardHealth, SlideshowConfig, SystemMetrics, ThrottleFlags, TickerMessage, TransitionStats, TvStatus};
@@ -0,0 +1,738 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::couchdb::{DimmingWindow, QuietWindow, SafeArea, TextSlide};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MqttCommand {
+    pub command: String,
+    pub payload: serde_json::Value,
+    pub timestamp: String,
+}
+
+impl MqttCommand {
+    // Malformed JSON or non-UTF-8 input is an error, never a panic
+    pub fn from_slice(payload: &[u8]) -> Result<Self, serde_json::Error> {
+        serde_json::from_slice(payload)
+    }
+
+    // Image list carried by an update_images command
+    pub fn images(&self) -> Result<Vec<ImageInfo>, serde_json::Error> {
+        Vec::<ImageInfo>::deserialize(&self.payload["images"])
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TvStatus {
+    pub status: String,
+    pub current_image: Option<String>,
+    pub total_images: usize,
+    pub current_index: usize,
+    pub uptime: u64,
+    pub timestamp: String,
+    // Increases with every message a device publishes; order by this rather than timestamp
+    #[serde(default)]
+    pub sequence: u64,
+    // "server", "ntp" or "unsynchronized": how far `timestamp` can be trusted
+    #[serde(default = "default_clock_confidence")]
+    pub clock_confidence: String,
+    // How smoothly the most recent transition played; None until one has
+    #[serde(default)]
+    pub last_transition: Option<TransitionStats>,
+    // "full", or how transitions are being cut back while the device is hot or overloaded:
+    // "reduced_frame_rate", "fades_only" or "no_transitions"
+    #[serde(default = "default_quality")]
+    pub quality: String,
+    // Name of the daypart schedule playing, None outside every schedule
+    #[serde(default)]
+    pub schedule: Option<String>,
+    // Proof of play: times each image id has come up since the endpoint started
+    #[serde(default)]
+    pub play_counts: HashMap<String, u64>,
+    // How long rendering has been taking lately; None from endpoints that don't measure it
+    #[serde(default)]
+    pub render: Option<RenderStats>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TransitionStats {
+    pub frames: u32,
+    // Frame slots at the target rate that passed while an earlier frame was still rendering
+    pub dropped_frames: u32,
+    pub fps: f32,
+    pub duration_ms: u64,
+}
+
+// Rolling averages over the most recent slides, transitions and frames, to pick out the devices
+// that are struggling before the stutter is noticed; each is None until there has been
+// something to measure
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct RenderStats {
+    // Reading an image file into pixels
+    pub decode_ms: Option<f32>,
+    // Orienting and fitting a decoded image to the display
+    pub scale_ms: Option<f32>,
+    // Frame rate transitions achieved
+    pub transition_fps: Option<f32>,
+    // Writing a full frame into the framebuffer
+    pub framebuffer_write_ms: Option<f32>,
+}
+
+// Image downloads from CouchDB since the endpoint started, to find the sites on slow or
+// unreliable links
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct DownloadStats {
+    pub downloads: u64,
+    pub bytes_downloaded: u64,
+    pub failures: u64,
+    // Attempts at an image whose last download had failed
+    pub retries: u64,
+    // Over the most recent downloads; None until one has finished
+    pub average_download_ms: Option<f32>,
+    pub average_kbps: Option<f32>,
+    // The last time the image list was fetched from CouchDB, None if it hasn't been yet
+    pub last_sync: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemMetrics {
+    pub cpu_usage: f32,
+    pub memory_usage: f32,
+    pub memory_total: u64,
+    pub memory_used: u64,
+    pub disk_usage: f32,
+    pub disk_total: u64,
+    pub disk_used: u64,
+    pub temperature: Option<f32>,
+    pub load_average: Option<f32>,
+    // Slides stored in the image directory, and the cap they are kept under (0 for none)
+    #[serde(default)]
+    pub image_dir_bytes: u64,
+    #[serde(default)]
+    pub image_dir_limit_bytes: u64,
+    // Under-voltage and throttling from the Pi's firmware; left out where it can't be read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub throttle: Option<ThrottleFlags>,
+    // Raspberry Pi firmware and SD card details; left out off a Pi
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hardware: Option<PiHardware>,
+    // Network interfaces with hardware behind them; loopback and virtual ones are left out
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub network: Vec<NetworkInterface>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkInterface {
+    pub name: String,
+    // "ethernet", "wifi" or "other"
+    pub kind: String,
+    pub up: bool,
+    // Carries the default route, so content comes over it
+    pub default_route: bool,
+    // IPv4 first; IPv6 link-local addresses are left out
+    pub addresses: Vec<String>,
+    // Wi-Fi signal in dBm: around -50 is excellent, below -75 unreliable
+    pub rssi_dbm: Option<i32>,
+    // Negotiated speed of a wired link in Mb/s
+    pub speed_mbps: Option<u32>,
+    // Counted since the interface came up
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+}
+
+// What vcgencmd and the kernel say about a Pi's firmware and SD card. Each field is None where
+// it couldn't be read.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct PiHardware {
+    // The memory split set in config.txt
+    pub gpu_memory_mb: Option<u32>,
+    pub arm_memory_mb: Option<u32>,
+    pub core_volts: Option<f32>,
+    // The firmware's commit hash and build date
+    pub firmware_version: Option<String>,
+    pub firmware_date: Option<String>,
+    pub sd_card: Option<SdCardHealth>,
+}
+
+// Hints at an SD card on its way out; cards report very little about their own wear
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SdCardHealth {
+    pub name: Option<String>,
+    // Month and year of manufacture, "mm/yyyy"
+    pub manufactured: Option<String>,
+    // Wear estimates from cards that give them, as reported: 0x01 is up to 10% of the rated
+    // life used, 0x0a up to 100%, 0x0b past it
+    pub life_time: Option<String>,
+    // 0x01 normal, 0x02 reserved blocks 80% used, 0x03 urgent
+    pub pre_eol: Option<String>,
+    // The card has made itself read-only, as many do at the end of their life
+    pub read_only: bool,
+    // Errors ext4 has recorded on the card's partitions, kept until the next fsck
+    pub filesystem_errors: Option<u64>,
+    // Written since boot, to spot something wearing the card out
+    pub sectors_written: Option<u64>,
+}
+
+// The bits of `vcgencmd get_throttled` by name; each is also kept, 16 bits up, as having
+// happened since boot
+const THROTTLE_FLAGS: [(u32, &str); 4] = [
+    (0, "under_voltage"),
+    (1, "frequency_capped"),
+    (2, "throttled"),
+    (3, "soft_temperature_limit"),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThrottleFlags {
+    // Raised right now
+    pub active: Vec<String>,
+    // Raised at some point since the Pi booted, including now
+    pub since_boot: Vec<String>,
+}
+
+impl ThrottleFlags {
+    pub fn from_bits(bits: u32) -> Self {
+        let named = |offset: u32| THROTTLE_FLAGS.iter()
+            .filter(|(bit, _)| bits & (1 << (bit + offset)) != 0)
+            .map(|(_, name)| name.to_string())
+            .collect();
+        Self { active: named(0), since_boot: named(16) }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.active.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeartbeatMessage {
+    pub tv_id: String,
+    pub timestamp: String,
+    pub status: String,
+    pub system_metrics: Option<SystemMetrics>,
+    #[serde(default)]
+    pub sequence: u64,
+    #[serde(default = "default_clock_confidence")]
+    pub clock_confidence: String,
+    // The attached TV's power as it answered over HDMI-CEC ("on", "standby", "turning_on",
+    // "turning_off" or "unknown"); left out when the endpoint doesn't use CEC
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tv_power: Option<String>,
+    // What the PIR motion sensor has seen; left out when the endpoint has none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub occupancy: Option<OccupancyStats>,
+    // NTP state and skew of the endpoint's clock; left out by endpoints that don't check it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clock: Option<ClockStatus>,
+    // As in TvStatus; left out by endpoints that don't measure it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub render: Option<RenderStats>,
+    // Left out by endpoints that don't count them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub downloads: Option<DownloadStats>,
+    // Which build is running and with what settings, so outdated or differently configured
+    // endpoints stand out; each is left out by endpoints that don't report it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    // Short git hash of the build
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    // Seconds since the endpoint started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uptime: Option<u64>,
+    // Short hash of the endpoint's own settings, equal on endpoints of one version set up alike
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClockStatus {
+    // As chrony or timedatectl reports it; None when neither could be asked
+    pub ntp_synchronized: Option<bool>,
+    // Which of them answered: "chrony" or "timedatectl"
+    pub ntp_source: Option<String>,
+    // How far the system clock is ahead of the management server's time (after a time_sync)
+    // or NTP's, negative when it is behind; None when nothing to compare against is known
+    pub skew_ms: Option<i64>,
+    // The clock is clearly wrong, so dayparting, validity windows, quiet hours and the other
+    // schedules are ignored and every assigned image plays in turn
+    pub schedules_suspended: bool,
+}
+
+// Presence in front of the display, counted since the endpoint started
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OccupancyStats {
+    // Motion within the idle time, so the display is awake for someone
+    pub occupied: bool,
+    // None until the sensor first fires
+    pub last_motion_secs: Option<u64>,
+    pub detections: u64,
+    // Time the display has spent asleep or dimmed for want of motion
+    pub idle_secs: u64,
+}
+
+fn default_clock_confidence() -> String {
+    "unsynchronized".to_string()
+}
+
+fn default_quality() -> String {
+    "full".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageInfo {
+    pub id: String,
+    pub path: String,
+    pub order: u32,
+    pub url: Option<String>, // URL to download image from management server
+    pub extension: Option<String>, // File extension from server
+    // The image's own letterbox fill, from its metadata
+    #[serde(default)]
+    pub letterbox_fill: Option<String>,
+    // How the image is sized on screen, from its metadata
+    #[serde(default)]
+    pub fit_mode: Option<String>,
+    // Link shown as a QR code on the slide, and the corner it goes in, from its metadata
+    #[serde(default)]
+    pub qr_url: Option<String>,
+    #[serde(default)]
+    pub qr_corner: Option<String>,
+    // Relative play frequency, from its metadata; 1 when unset
+    #[serde(default)]
+    pub weight: Option<u32>,
+    // When the image is shown from and until (RFC 3339), from the image document
+    #[serde(default)]
+    pub valid_from: Option<String>,
+    #[serde(default)]
+    pub valid_until: Option<String>,
+    // Page captured in place of downloading the image, and how often, from the image document
+    #[serde(default)]
+    pub web_url: Option<String>,
+    #[serde(default)]
+    pub web_refresh_secs: Option<u64>,
+    // A text slide the TV draws instead of downloading an image
+    #[serde(default)]
+    pub text_slide: Option<TextSlide>,
+    // Changes whenever the image's content does (see CouchImage::content_revision), so an
+    // image quarantined as undecodable is tried again once it has been replaced
+    #[serde(default)]
+    pub revision: Option<String>,
+}
+
+impl ImageInfo {
+    // Name the image is stored under in the local image directory, or None when the id or
+    // extension could point outside of it
+    pub fn local_file_name(&self) -> Option<String> {
+        let extension = self.extension
+            .as_deref()
+            .map(|ext| ext.strip_prefix('.').unwrap_or(ext))
+            .unwrap_or("png");
+
+        if is_safe_file_component(&self.id) && is_safe_file_component(extension) {
+            Some(format!("{}.{}", self.id, extension))
+        } else {
+            None
+        }
+    }
+}
+
+// Usable as a single file name inside a directory: no separators, no . or ..
+pub fn is_safe_file_component(s: &str) -> bool {
+    !s.is_empty()
+        && s != "."
+        && s != ".."
+        && !s.chars().any(|c| c == '/' || c == '\\' || c.is_control())
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SlideshowConfig {
+    pub transition_effect: Option<String>,
+    pub display_duration: Option<u64>,
+    pub transition_duration: Option<u64>,
+    pub orientation: Option<String>,
+    pub content_safety: Option<bool>,
+    pub text_profile: Option<String>,
+    pub framebuffer_refresh_secs: Option<u64>,
+    pub skip_redundant_writes: Option<bool>,
+    pub gamma: Option<f64>,
+    pub color_temperature: Option<u32>,
+    pub brightness: Option<u8>,
+    pub dimming_schedule: Option<Vec<DimmingWindow>>,
+    pub quiet_hours: Option<Vec<QuietWindow>>,
+    pub curtain_hours: Option<Vec<QuietWindow>>,
+    pub curtain_color: Option<String>,
+    pub curtain_image: Option<String>,
+    pub letterbox_fill: Option<String>,
+    pub letterbox_color: Option<String>,
+    pub letterbox_image: Option<String>,
+    pub safe_area: Option<SafeArea>,
+    pub ken_burns: Option<String>,
+    pub ken_burns_intensity: Option<f64>,
+    pub smart_crop: Option<bool>,
+    pub transition_easing: Option<String>,
+    pub transition_durations: Option<HashMap<String, u64>>,
+    pub shuffle: Option<bool>,
+    pub ticker_rss_url: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+impl SlideshowConfig {
+    // The update_config payload is the full TV config object from the management system;
+    // pick out the fields the endpoint understands and ignore the rest
+    pub fn from_payload(payload: &serde_json::Value) -> Self {
+        Self {
+            transition_effect: payload.get("transition_effect")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            display_duration: payload.get("display_duration")
+                .and_then(|v| v.as_u64()),
+            transition_duration: payload.get("transition_duration")
+                .and_then(|v| v.as_u64()),
+            orientation: payload.get("orientation")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            content_safety: payload.get("content_safety")
+                .and_then(|v| v.as_bool()),
+            text_profile: payload.get("text_profile")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            framebuffer_refresh_secs: payload.get("framebuffer_refresh_secs")
+                .and_then(|v| v.as_u64()),
+            skip_redundant_writes: payload.get("skip_redundant_writes")
+                .and_then(|v| v.as_bool()),
+            gamma: payload.get("gamma")
+                .and_then(|v| v.as_f64()),
+            color_temperature: payload.get("color_temperature")
+                .and_then(|v| v.as_u64())
+                .and_then(|v| u32::try_from(v).ok()),
+            brightness: payload.get("brightness")
+                .and_then(|v| v.as_u64())
+                .and_then(|v| u8::try_from(v).ok()),
+            dimming_schedule: payload.get("dimming_schedule")
+                .and_then(|v| Vec::<DimmingWindow>::deserialize(v).ok()),
+            quiet_hours: payload.get("quiet_hours")
+                .and_then(|v| Vec::<QuietWindow>::deserialize(v).ok()),
+            curtain_hours: payload.get("curtain_hours")
+                .and_then(|v| Vec::<QuietWindow>::deserialize(v).ok()),
+            curtain_color: payload.get("curtain_color")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            // null clears the curtain image, as for letterbox_image
+            curtain_image: payload.get("curtain_image")
+                .and_then(|v| if v.is_null() { Some("") } else { v.as_str() })
+                .map(|s| s.to_string()),
+            letterbox_fill: payload.get("letterbox_fill")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            letterbox_color: payload.get("letterbox_color")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            // null clears the background image, which shows up here as an empty id
+            letterbox_image: payload.get("letterbox_image")
+                .and_then(|v| if v.is_null() { Some("") } else { v.as_str() })
+                .map(|s| s.to_string()),
+            safe_area: payload.get("safe_area")
+                .and_then(|v| SafeArea::deserialize(v).ok()),
+            ken_burns: payload.get("ken_burns")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            ken_burns_intensity: payload.get("ken_burns_intensity")
+                .and_then(|v| v.as_f64()),
+            smart_crop: payload.get("smart_crop")
+                .and_then(|v| v.as_bool()),
+            transition_easing: payload.get("transition_easing")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            transition_durations: payload.get("transition_durations")
+                .and_then(|v| HashMap::<String, u64>::deserialize(v).ok()),
+            shuffle: payload.get("shuffle")
+                .and_then(|v| v.as_bool()),
+            // null turns the feed off, which shows up here as an empty URL
+            ticker_rss_url: payload.get("ticker_rss_url")
+                .and_then(|v| if v.is_null() { Some("") } else { v.as_str() })
+                .map(|s| s.to_string()),
+            tags: payload.get("tags")
+                .and_then(|v| Vec::<String>::deserialize(v).ok()),
+        }
+    }
+}
+
+// Payload on the ticker topic: the text to scroll along the bottom of the screen, or no text
+// to take the ticker down. Published retained, so a TV picks it up again when it reconnects.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TickerMessage {
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+impl TickerMessage {
+    // An empty payload, which is how a retained message is deleted, takes the ticker down too
+    pub fn from_slice(payload: &[u8]) -> Result<Self, serde_json::Error> {
+        if payload.iter().all(u8::is_ascii_whitespace) {
+            return Ok(Self::default());
+        }
+        serde_json::from_slice(payload)
+    }
+
+    // The text on one line with runs of whitespace collapsed, None when there is nothing to show
+    pub fn line(&self) -> Option<String> {
+        let line = self.text.as_deref()?.split_whitespace().collect::<Vec<_>>().join(" ");
+        Some(line).filter(|line| !line.is_empty())
+    }
+}
+
+// What a device was doing when it went down: written to disk as it happens and published on
+// the crash topic once the device is back up
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    // "panic", or "error" for a fatal error that ended the endpoint
+    pub kind: String,
+    pub message: String,
+    // file:line:column of a panic
+    #[serde(default)]
+    pub location: Option<String>,
+    #[serde(default)]
+    pub thread: Option<String>,
+    // The background task that panicked, where known
+    #[serde(default)]
+    pub task: Option<String>,
+    #[serde(default)]
+    pub backtrace: String,
+    pub version: String,
+    // Seconds the endpoint had been running
+    pub uptime: u64,
+    // The last command handled and how many seconds before the crash
+    #[serde(default)]
+    pub last_command: Option<String>,
+    #[serde(default)]
+    pub last_command_secs: Option<u64>,
+    // By the device's clock at the time, which may have been wrong
+    pub crashed_at: String,
+}
+
+// MQTT topic layout, rooted at signage/tv/{tv_id}
+pub mod topics {
+    pub fn command(tv_id: &str) -> String {
+        format!("signage/tv/{}/command", tv_id)
+    }
+
+    pub fn status(tv_id: &str) -> String {
+        format!("signage/tv/{}/status", tv_id)
+    }
+
+    pub fn heartbeat(tv_id: &str) -> String {
+        format!("signage/tv/{}/heartbeat", tv_id)
+    }
+
+    pub fn current_image(tv_id: &str) -> String {
+        format!("signage/tv/{}/image/current", tv_id)
+    }
+
+    pub fn error(tv_id: &str) -> String {
+        format!("signage/tv/{}/error", tv_id)
+    }
+
+    pub fn schedule(tv_id: &str) -> String {
+        format!("signage/tv/{}/schedule", tv_id)
+    }
+
+    pub fn ticker(tv_id: &str) -> String {
+        format!("signage/tv/{}/ticker", tv_id)
+    }
+
+    pub fn update(tv_id: &str) -> String {
+        format!("signage/tv/{}/update", tv_id)
+    }
+
+    pub fn crash(tv_id: &str) -> String {
+        format!("signage/tv/{}/crash", tv_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_round_trips() {
+        let json = r#"{"command":"next","payload":{},"timestamp":"2024-01-01T12:00:00Z"}"#;
+        let command: MqttCommand = serde_json::from_str(json).unwrap();
+        assert_eq!(command.command, "next");
+        assert_eq!(serde_json::to_string(&command).unwrap(), json);
+    }
+
+    #[test]
+    fn status_without_transition_stats_parses() {
+        let json = r#"{"status":"playing","current_image":null,"total_images":3,"current_index":0,"uptime":60,"timestamp":"2024-01-01T12:00:00Z"}"#;
+        let status: TvStatus = serde_json::from_str(json).unwrap();
+        assert_eq!(status.last_transition, None);
+        assert_eq!(status.quality, "full");
+        assert_eq!(status.schedule, None);
+        assert_eq!(status.render, None);
+    }
+
+    #[test]
+    fn heartbeat_leaves_out_missing_sensors() {
+        let json = r#"{"tv_id":"lobby","timestamp":"2024-01-01T12:00:00Z","status":"online","system_metrics":null}"#;
+        let heartbeat: HeartbeatMessage = serde_json::from_str(json).unwrap();
+        assert_eq!(heartbeat.tv_power, None);
+        assert_eq!(heartbeat.occupancy, None);
+        assert_eq!(heartbeat.clock, None);
+        assert_eq!(heartbeat.render, None);
+        assert_eq!(heartbeat.downloads, None);
+        assert_eq!(heartbeat.config_hash, None);
+        let serialized = serde_json::to_value(&heartbeat).unwrap();
+        assert!(serialized.get("tv_power").is_none());
+        assert!(serialized.get("occupancy").is_none());
+        assert!(serialized.get("clock").is_none());
+        assert!(serialized.get("render").is_none());
+        assert!(serialized.get("downloads").is_none());
+        assert!(serialized.get("version").is_none());
+        assert!(serialized.get("config_hash").is_none());
+    }
+
+    #[test]
+    fn system_metrics_without_pi_details_parse() {
+        let json = r#"{"cpu_usage":12.5,"memory_usage":40.0,"memory_total":1024,"memory_used":410,"disk_usage":50.0,"disk_total":100,"disk_used":50,"temperature":null,"load_average":0.5}"#;
+        let metrics: SystemMetrics = serde_json::from_str(json).unwrap();
+        assert_eq!(metrics.throttle, None);
+        assert_eq!(metrics.hardware, None);
+        assert!(metrics.network.is_empty());
+        let serialized = serde_json::to_value(&metrics).unwrap();
+        assert!(serialized.get("hardware").is_none());
+        assert!(serialized.get("network").is_none());
+    }
+
+    #[test]
+    fn throttle_flags_separate_now_from_since_boot() {
+        // Under-voltage and throttling now, after a soft temperature limit earlier on
+        let flags = ThrottleFlags::from_bits(0x000d_0005);
+        assert_eq!(flags.active, vec!["under_voltage", "throttled"]);
+        assert_eq!(flags.since_boot, vec!["under_voltage", "throttled", "soft_temperature_limit"]);
+        assert!(flags.is_active());
+        assert!(!ThrottleFlags::from_bits(0x0005_0000).is_active());
+    }
+
+    #[test]
+    fn crash_report_without_details_parses() {
+        let json = r#"{"kind":"error","message":"no framebuffer device","version":"0.1.0","uptime":3,"crashed_at":"2024-01-01T12:00:00Z"}"#;
+        let report: CrashReport = serde_json::from_str(json).unwrap();
+        assert_eq!(report.location, None);
+        assert_eq!(report.last_command, None);
+        assert!(report.backtrace.is_empty());
+    }
+
+    #[test]
+    fn config_from_payload_ignores_unknown_fields() {
+        let payload = serde_json::json!({
+            "transition_effect": "wipe_left",
+            "display_duration": 10000,
+            "orientation": "portrait",
+            "resolution": "1920x1080"
+        });
+        let config = SlideshowConfig::from_payload(&payload);
+        assert_eq!(config.transition_effect.as_deref(), Some("wipe_left"));
+        assert_eq!(config.display_duration, Some(10000));
+        assert_eq!(config.transition_duration, None);
+        assert_eq!(config.orientation.as_deref(), Some("portrait"));
+    }
+
+    #[test]
+    fn config_from_payload_drops_mistyped_fields() {
+        let payload = serde_json::json!({ "display_duration": "10000", "content_safety": "yes", "shuffle": 1 });
+        let config = SlideshowConfig::from_payload(&payload);
+        assert_eq!(config.display_duration, None);
+        assert_eq!(config.content_safety, None);
+        assert_eq!(config.shuffle, None);
+    }
+
+    #[test]
+    fn config_from_payload_reads_per_effect_durations() {
+        let config = SlideshowConfig::from_payload(&serde_json::json!({ "transition_durations": { "circular_wipe": 2000 } }));
+        assert_eq!(config.transition_durations.unwrap().get("circular_wipe"), Some(&2000));
+        let config = SlideshowConfig::from_payload(&serde_json::json!({ "transition_durations": { "circular_wipe": "slow" } }));
+        assert_eq!(config.transition_durations, None);
+    }
+
+    #[test]
+    fn config_from_payload_clears_letterbox_image_on_null() {
+        let config = SlideshowConfig::from_payload(&serde_json::json!({ "letterbox_image": null }));
+        assert_eq!(config.letterbox_image.as_deref(), Some(""));
+        let config = SlideshowConfig::from_payload(&serde_json::json!({ "letterbox_fill": "blur" }));
+        assert_eq!(config.letterbox_image, None);
+    }
+
+    #[test]
+    fn update_images_payload_parses() {
+        let payload = serde_json::json!({
+            "images": [{"id": "img1", "path": "/var/signage/img1.jpg", "order": 0, "url": null, "extension": ".jpg"}]
+        });
+        let images: Vec<ImageInfo> = serde_json::from_value(payload["images"].clone()).unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].extension.as_deref(), Some(".jpg"));
+    }
+
+    #[test]
+    fn malformed_commands_are_errors() {
+        assert!(MqttCommand::from_slice(b"").is_err());
+        assert!(MqttCommand::from_slice(b"\xff\xfe").is_err());
+        assert!(MqttCommand::from_slice(br#"{"command":"next"}"#).is_err());
+
+        let command = MqttCommand::from_slice(br#"{"command":"update_images","payload":{"images":"none"},"timestamp":""}"#).unwrap();
+        assert!(command.images().is_err());
+    }
+
+    #[test]
+    fn local_file_name_rejects_path_traversal() {
+        let image = |id: &str, extension: Option<&str>| ImageInfo {
+            id: id.to_string(),
+            path: String::new(),
+            order: 0,
+            url: None,
+            extension: extension.map(|s| s.to_string()),
+            letterbox_fill: None,
+            fit_mode: None,
+            qr_url: None,
+            qr_corner: None,
+            weight: None,
+            valid_from: None,
+            valid_until: None,
+            web_url: None,
+            web_refresh_secs: None,
+            text_slide: None,
+            revision: None,
+        };
+        assert_eq!(image("img1", Some(".jpg")).local_file_name().as_deref(), Some("img1.jpg"));
+        assert_eq!(image("img1", None).local_file_name().as_deref(), Some("img1.png"));
+        assert_eq!(image("../../etc/passwd", Some(".png")).local_file_name(), None);
+        assert_eq!(image("img1", Some("./../x")).local_file_name(), None);
+        assert_eq!(image("..", Some("png")).local_file_name(), None);
+        assert_eq!(image("img1", Some(".")).local_file_name(), None);
+        assert_eq!(image("a\\b", Some("png")).local_file_name(), None);
+    }
+
+    #[test]
+    fn topics_match_management_server() {
+        assert_eq!(topics::command("lobby"), "signage/tv/lobby/command");
+        assert_eq!(topics::current_image("lobby"), "signage/tv/lobby/image/current");
+        assert_eq!(topics::ticker("lobby"), "signage/tv/lobby/ticker");
+        assert_eq!(topics::update("lobby"), "signage/tv/lobby/update");
+        assert_eq!(topics::crash("lobby"), "signage/tv/lobby/crash");
+    }
+
+    #[test]
+    fn ticker_message_text_is_one_line() {
+        let message = TickerMessage::from_slice(br#"{"text":"  Storm warning\n\tschools close at noon "}"#).unwrap();
+        assert_eq!(message.line().as_deref(), Some("Storm warning schools close at noon"));
+    }
+
+    #[test]
+    fn ticker_message_without_text_clears() {
+        for payload in [&b""[..], b" \n", br#"{}"#, br#"{"text":null}"#, br#"{"text":"  "}"#] {
+            assert_eq!(TickerMessage::from_slice(payload).unwrap().line(), None);
+        }
+        assert!(TickerMessage::from_slice(b"not json").is_err());
+    }
+}
@@ -98,17 +98,22 @@ async function createDesignDocuments() {
     {
       _id: '_design/schedules',
       views: {
-        by_tv_and_time: {
+        by_tv: {
           map: function(doc) {
             if (doc.type === 'schedule') {
-              emit([doc.assigned_tv_id, doc.scheduled_time], doc);
+              emit(doc.assigned_tv_id, doc);
             }
           }.toString()
-        },
-        active: {
+        }
+      }
+    },
+    {
+      _id: '_design/text_slides',
+      views: {
+        all: {
           map: function(doc) {
-            if (doc.type === 'schedule' && doc.active) {
-              emit(doc.scheduled_time, doc);
+            if (doc.type === 'text_slide') {
+              emit(doc._id, doc);
             }
           }.toString()
         }
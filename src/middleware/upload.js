@@ -3,14 +3,15 @@ const multer = require('multer');
 // Use memory storage instead of disk storage for CouchDB attachments
 const storage = multer.memoryStorage();
 
-// File filter for images only
+// File filter for images and video slides
 const fileFilter = (req, file, cb) => {
-  const allowedTypes = ['image/jpeg', 'image/jpg', 'image/png', 'image/gif', 'image/webp'];
+  // AVIF only displays on TVs built with the avif feature; videos need ffmpeg on the TV
+  const allowedTypes = ['image/jpeg', 'image/jpg', 'image/png', 'image/gif', 'image/webp', 'image/avif', 'video/mp4', 'video/quicktime'];
   
   if (allowedTypes.includes(file.mimetype)) {
     cb(null, true);
   } else {
-    cb(new Error('Invalid file type. Only JPEG, PNG, GIF, and WebP images are allowed.'), false);
+    cb(new Error('Invalid file type. Only JPEG, PNG, GIF, WebP, and AVIF images and MP4 or MOV videos are allowed.'), false);
   }
 };
 
@@ -10,14 +10,36 @@ class Image {
     this.size = data.size;
     this.mimetype = data.mimetype;
     this.assigned_tvs = data.assigned_tvs || []; // Array of TV IDs this image is assigned to
+    // TV tags this image is assigned to; every TV carrying one of them plays it too
+    this.assigned_tags = data.assigned_tags || [];
     this.tv_orders = data.tv_orders || {}; // Object mapping TV ID to order position
     this.status = data.status || 'active';
     this.metadata = {
       width: data.metadata?.width,
       height: data.metadata?.height,
+      // Decoder format as sharp reports it ('jpeg', 'png', 'webp'); TVs name the downloaded file after it
+      format: data.metadata?.format,
+      // Overrides the TV's letterbox_fill for this image ('color', 'blur' or 'image')
+      letterbox_fill: data.metadata?.letterbox_fill,
+      // How the image is sized on screen ('contain', 'cover', 'stretch' or 'native'); contain when unset
+      fit_mode: data.metadata?.fit_mode,
+      // Link the TVs draw as a QR code on the slide, in qr_corner ('top_left', 'top_right',
+      // 'bottom_left' or 'bottom_right'); bottom right when unset
+      qr_url: data.metadata?.qr_url,
+      qr_corner: data.metadata?.qr_corner,
+      // How often the image comes up relative to the others on a TV (1 when unset, 3 for
+      // three times as often)
+      weight: data.metadata?.weight,
       description: data.metadata?.description || '',
       tags: data.metadata?.tags || []
     };
+    // ISO 8601 times the image is shown from and until on its TVs; null for no limit
+    this.valid_from = data.valid_from || null;
+    this.valid_until = data.valid_until || null;
+    // Web page slides have no attachment: the TVs capture this page as a PNG every
+    // web_refresh_secs seconds
+    this.web_url = data.web_url || null;
+    this.web_refresh_secs = data.web_refresh_secs || null;
     this.schedule = {
       start_time: data.schedule?.start_time,
       end_time: data.schedule?.end_time,
@@ -128,6 +150,9 @@ class Image {
   }
 
   getFileExtension() {
+    if (this.web_url) {
+      return '.png';
+    }
     const ext = this.original_name ? require('path').extname(this.original_name) : '.png';
     return ext || '.png';
   }
@@ -0,0 +1,83 @@
+const { getDatabase } = require('../config/database');
+const { v4: uuidv4 } = require('uuid');
+
+// A daypart: while its window is open the TV plays only image_ids, in that order
+class Schedule {
+  constructor(data) {
+    this._id = data._id || `schedule_${uuidv4()}`;
+    this._rev = data._rev; // Include _rev for CouchDB updates
+    this.type = 'schedule';
+    this.assigned_tv_id = data.assigned_tv_id;
+    this.name = data.name;
+    this.start = data.start;
+    this.end = data.end;
+    this.days = data.days || [];
+    this.image_ids = data.image_ids || [];
+    this.created_at = data.created_at || new Date().toISOString();
+    this.updated_at = new Date().toISOString();
+  }
+
+  static async findByTv(tvId) {
+    const db = getDatabase();
+    try {
+      const result = await db.view('schedules', 'by_tv', { key: tvId });
+      return result.rows.map(row => row.value);
+    } catch (error) {
+      console.error('Error finding schedules by TV:', error);
+      throw error;
+    }
+  }
+
+  static async findById(id) {
+    const db = getDatabase();
+    try {
+      const doc = await db.get(id);
+      return doc.type === 'schedule' ? new Schedule(doc) : null;
+    } catch (error) {
+      if (error.statusCode === 404) {
+        return null;
+      }
+      throw error;
+    }
+  }
+
+  async save() {
+    const db = getDatabase();
+    try {
+      this.updated_at = new Date().toISOString();
+      const result = await db.insert(this);
+      this._rev = result.rev;
+      return this;
+    } catch (error) {
+      console.error('Error saving schedule:', error);
+      throw error;
+    }
+  }
+
+  async update(updates) {
+    const db = getDatabase();
+    try {
+      const existing = await db.get(this._id);
+      const updated = { ...existing, ...updates, updated_at: new Date().toISOString() };
+      const result = await db.insert(updated);
+      return { ...updated, _rev: result.rev };
+    } catch (error) {
+      console.error('Error updating schedule:', error);
+      throw error;
+    }
+  }
+
+  async delete() {
+    const db = getDatabase();
+    try {
+      const existing = await db.get(this._id);
+      await db.destroy(existing._id, existing._rev);
+      return true;
+    } catch (error) {
+      console.error('Error deleting schedule:', error);
+      throw error;
+    }
+  }
+}
+
+module.exports = Schedule;
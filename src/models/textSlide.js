@@ -0,0 +1,89 @@
+const { getDatabase } = require('../config/database');
+const { v4: uuidv4 } = require('uuid');
+
+// An announcement drawn by the TVs themselves: a title and body on a colored card, played
+// among the images of every TV in assigned_tvs, or carrying one of assigned_tags
+class TextSlide {
+  constructor(data) {
+    this._id = data._id || `text_slide_${uuidv4()}`;
+    this._rev = data._rev; // Include _rev for CouchDB updates
+    this.type = 'text_slide';
+    this.title = data.title;
+    this.body = data.body || '';
+    this.background_color = data.background_color || '#1e3a5f';
+    this.text_color = data.text_color || '#ffffff';
+    this.template = data.template || 'centered';
+    // Makes it a countdown: {countdown} in the title or body reads as the time left
+    this.countdown_to = data.countdown_to || null;
+    this.assigned_tvs = data.assigned_tvs || [];
+    this.assigned_tags = data.assigned_tags || [];
+    this.valid_from = data.valid_from || null;
+    this.valid_until = data.valid_until || null;
+    this.created_at = data.created_at || new Date().toISOString();
+    this.updated_at = new Date().toISOString();
+  }
+
+  static async findAll() {
+    const db = getDatabase();
+    try {
+      const result = await db.view('text_slides', 'all');
+      return result.rows.map(row => row.value);
+    } catch (error) {
+      console.error('Error finding all text slides:', error);
+      throw error;
+    }
+  }
+
+  static async findById(id) {
+    const db = getDatabase();
+    try {
+      const doc = await db.get(id);
+      return doc.type === 'text_slide' ? new TextSlide(doc) : null;
+    } catch (error) {
+      if (error.statusCode === 404) {
+        return null;
+      }
+      throw error;
+    }
+  }
+
+  async save() {
+    const db = getDatabase();
+    try {
+      this.updated_at = new Date().toISOString();
+      const result = await db.insert(this);
+      this._rev = result.rev;
+      return this;
+    } catch (error) {
+      console.error('Error saving text slide:', error);
+      throw error;
+    }
+  }
+
+  async update(updates) {
+    const db = getDatabase();
+    try {
+      const existing = await db.get(this._id);
+      const updated = { ...existing, ...updates, updated_at: new Date().toISOString() };
+      const result = await db.insert(updated);
+      return { ...updated, _rev: result.rev };
+    } catch (error) {
+      console.error('Error updating text slide:', error);
+      throw error;
+    }
+  }
+
+  async delete() {
+    const db = getDatabase();
+    try {
+      const existing = await db.get(this._id);
+      await db.destroy(existing._id, existing._rev);
+      return true;
+    } catch (error) {
+      console.error('Error deleting text slide:', error);
+      throw error;
+    }
+  }
+}
+
+module.exports = TextSlide;
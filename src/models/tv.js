@@ -12,11 +12,48 @@ class TV {
     this.status = data.status || 'offline';
     this.current_image = data.current_image || null;
     this.last_heartbeat = data.last_heartbeat || null;
+    // Images the TV couldn't decode and quarantined: { image_id, error, timestamp }
+    this.image_errors = data.image_errors || [];
+    // Proof of play from the TV's last status: times each image id has come up since it started
+    this.play_counts = data.play_counts || {};
+    // The attached TV's power from the last heartbeat ('on', 'standby', ...); null without HDMI-CEC
+    this.tv_power = data.tv_power || null;
+    // PIR sensor presence from the last heartbeat: { occupied, last_motion_secs, detections, idle_secs }; null without a sensor
+    this.occupancy = data.occupancy || null;
+    // Last self-update step the TV reported: { stage, version, running_version, error, timestamp }
+    this.update_status = data.update_status || null;
+    // Clock check from the last heartbeat: { ntp_synchronized, ntp_source, skew_ms, schedules_suspended }
+    this.clock = data.clock || null;
     this.config = {
       transition_effect: data.config?.transition_effect || 'fade',
       display_duration: data.config?.display_duration || 5000,
       resolution: data.config?.resolution || '1920x1080',
-      orientation: data.config?.orientation || 'landscape'
+      orientation: data.config?.orientation || 'landscape',
+      content_safety: data.config?.content_safety || false,
+      text_profile: data.config?.text_profile || 'standard',
+      framebuffer_refresh_secs: data.config?.framebuffer_refresh_secs || 0,
+      skip_redundant_writes: data.config?.skip_redundant_writes || false,
+      gamma: data.config?.gamma || 1.0,
+      color_temperature: data.config?.color_temperature || 6500,
+      brightness: data.config?.brightness || 100,
+      dimming_schedule: data.config?.dimming_schedule || [],
+      quiet_hours: data.config?.quiet_hours || [],
+      curtain_hours: data.config?.curtain_hours || [],
+      curtain_color: data.config?.curtain_color || '#000000',
+      curtain_image: data.config?.curtain_image || null,
+      letterbox_fill: data.config?.letterbox_fill || 'color',
+      letterbox_color: data.config?.letterbox_color || '#000000',
+      letterbox_image: data.config?.letterbox_image || null,
+      safe_area: data.config?.safe_area || { top: 0, right: 0, bottom: 0, left: 0 },
+      ken_burns: data.config?.ken_burns || 'off',
+      ken_burns_intensity: data.config?.ken_burns_intensity ?? 0.15,
+      smart_crop: data.config?.smart_crop || false,
+      transition_easing: data.config?.transition_easing || 'default',
+      transition_durations: data.config?.transition_durations || {},
+      shuffle: data.config?.shuffle || false,
+      ticker_rss_url: data.config?.ticker_rss_url || null,
+      // Groups the TV belongs to; images and text slides assigned to one of them play here too
+      tags: data.config?.tags || []
     };
     this.created_at = data.created_at || new Date().toISOString();
     this.updated_at = new Date().toISOString();
@@ -46,6 +83,16 @@ class TV {
     }
   }
 
+  // TVs carrying any of these tags (case-insensitive, as the TVs match them)
+  static async findByTags(tags) {
+    const wanted = new Set(tags.map(tag => tag.toLowerCase()));
+    if (wanted.size === 0) {
+      return [];
+    }
+    const tvs = await TV.findAll();
+    return tvs.filter(tv => (tv.config?.tags || []).some(tag => wanted.has(tag.toLowerCase())));
+  }
+
   static async findByStatus(status) {
     const db = getDatabase();
     try {
@@ -95,10 +142,21 @@ class TV {
     }
   }
 
-  async updateHeartbeat() {
+  // Keeps what the heartbeat reports from the endpoint's attached hardware: the TV's power over
+  // HDMI-CEC and the PIR sensor's occupancy, when it has them, and how its clock is doing
+  async updateHeartbeat(heartbeat = {}) {
     return this.update({ 
       last_heartbeat: new Date().toISOString(),
-      status: 'online'
+      status: 'online',
+      ...(heartbeat.tv_power ? { tv_power: heartbeat.tv_power } : {}),
+      ...(heartbeat.occupancy ? { occupancy: heartbeat.occupancy } : {}),
+      ...(heartbeat.clock ? { clock: heartbeat.clock } : {}),
+      ...(heartbeat.render ? { render: heartbeat.render } : {}),
+      ...(heartbeat.downloads ? { downloads: heartbeat.downloads } : {}),
+      ...(heartbeat.version ? { version: heartbeat.version } : {}),
+      ...(heartbeat.commit ? { commit: heartbeat.commit } : {}),
+      ...(heartbeat.uptime !== undefined ? { uptime: heartbeat.uptime } : {}),
+      ...(heartbeat.config_hash ? { config_hash: heartbeat.config_hash } : {})
     });
   }
 }
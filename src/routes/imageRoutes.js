@@ -13,8 +13,19 @@ const imageUpdateSchema = Joi.object({
   status: Joi.string().valid('active', 'inactive'),
   metadata: Joi.object({
     description: Joi.string().allow(''),
-    tags: Joi.array().items(Joi.string())
+    tags: Joi.array().items(Joi.string()),
+    letterbox_fill: Joi.string().valid('color', 'blur', 'image').allow(null),
+    fit_mode: Joi.string().valid('contain', 'cover', 'stretch', 'native').allow(null),
+    qr_url: Joi.string().uri({ scheme: ['http', 'https'] }).max(1000).allow(null),
+    qr_corner: Joi.string().valid('top_left', 'top_right', 'bottom_left', 'bottom_right').allow(null),
+    weight: Joi.number().integer().min(1).max(100).allow(null)
   }),
+  // TV tags the image plays on, besides the TVs it is assigned to
+  assigned_tags: Joi.array().items(Joi.string().pattern(/^[A-Za-z0-9._-]+$/).max(64)),
+  valid_from: Joi.string().isoDate().allow(null),
+  valid_until: Joi.string().isoDate().allow(null),
+  web_url: Joi.string().uri({ scheme: ['http', 'https'] }),
+  web_refresh_secs: Joi.number().integer().min(30).max(86400).allow(null),
   schedule: Joi.object({
     start_time: Joi.string().isoDate().allow(null),
     end_time: Joi.string().isoDate().allow(null),
@@ -22,6 +33,15 @@ const imageUpdateSchema = Joi.object({
   })
 });
 
+// A web page slide: the TVs capture `url` at their screen size every `refresh_secs`
+const webPageSchema = Joi.object({
+  url: Joi.string().uri({ scheme: ['http', 'https'] }).required(),
+  name: Joi.string().max(200),
+  refresh_secs: Joi.number().integer().min(30).max(86400).default(300),
+  description: Joi.string().allow(''),
+  tags: Joi.array().items(Joi.string())
+});
+
 const assignmentSchema = Joi.object({
   tv_ids: Joi.array().items(Joi.string()).required(),
   order: Joi.number().min(0).default(0)
@@ -65,7 +85,11 @@ router.get('/', async (req, res) => {
         path: `api/images/${img._id}/attachment`,
         order: img.tv_orders[tv_id] || 0,
         url: `${req.protocol}://${req.get('host')}/api/images/${img._id}/attachment`,
-        extension: img.getFileExtension()
+        extension: img.getFileExtension(),
+        valid_from: img.valid_from,
+        valid_until: img.valid_until,
+        web_url: img.web_url,
+        web_refresh_secs: img.web_refresh_secs
       }));
       return res.json(transformedImages);
     }
@@ -126,8 +150,10 @@ router.post('/upload', upload.array('images', 10), handleUploadErrors, async (re
     
     for (const file of req.files) {
       try {
-        // Get image metadata using sharp from buffer
-        const metadata = await sharp(file.buffer).metadata();
+        // Get image metadata using sharp from buffer; videos are probed on the TV instead
+        const metadata = file.mimetype.startsWith('video/')
+          ? { format: file.mimetype === 'video/quicktime' ? 'mov' : 'mp4' }
+          : await sharp(file.buffer).metadata();
         
         const image = new Image({
           original_name: file.originalname,
@@ -136,6 +162,7 @@ router.post('/upload', upload.array('images', 10), handleUploadErrors, async (re
           metadata: {
             width: metadata.width,
             height: metadata.height,
+            format: metadata.format,
             description: req.body.description || '',
             tags: req.body.tags ? req.body.tags.split(',').map(tag => tag.trim()) : []
           }
@@ -165,6 +192,35 @@ router.post('/upload', upload.array('images', 10), handleUploadErrors, async (re
   }
 });
 
+// POST /api/images/web - Add a web page (a dashboard, a KPI board) as a slide
+router.post('/web', async (req, res) => {
+  try {
+    const { error, value } = webPageSchema.validate(req.body);
+    if (error) {
+      return res.status(400).json({ error: error.details[0].message });
+    }
+
+    const image = new Image({
+      original_name: value.name || value.url,
+      size: 0,
+      mimetype: 'image/png',
+      metadata: {
+        format: 'png',
+        description: value.description || '',
+        tags: value.tags || []
+      },
+      web_url: value.url,
+      web_refresh_secs: value.refresh_secs
+    });
+    await image.save();
+
+    res.status(201).json({ message: 'Web page slide created', image });
+  } catch (error) {
+    console.error('Error creating web page slide:', error);
+    res.status(500).json({ error: 'Failed to create web page slide' });
+  }
+});
+
 // PUT /api/images/:id - Update image metadata
 router.put('/:id', async (req, res) => {
   try {
@@ -178,7 +234,24 @@ router.put('/:id', async (req, res) => {
       return res.status(400).json({ error: error.details[0].message });
     }
 
+    // Metadata fields not in the request (dimensions, format) are kept
+    if (value.metadata) {
+      value.metadata = { ...image.metadata, ...value.metadata };
+    }
+
     const updatedImage = await image.update(value);
+
+    // TVs gaining or losing the image through a tag fetch their playlists again
+    if (value.assigned_tags) {
+      const tagged = await TV.findByTags([...image.assigned_tags, ...updatedImage.assigned_tags]);
+      for (const tv of tagged) {
+        try {
+          await mqttService.syncTv(tv._id.replace('tv_', ''));
+        } catch (mqttError) {
+          console.error(`Error syncing TV ${tv._id} after tag change:`, mqttError);
+        }
+      }
+    }
     res.json(updatedImage);
   } catch (error) {
     console.error('Error updating image:', error);
@@ -204,7 +277,11 @@ router.delete('/:id', async (req, res) => {
             id: img._id,
             path: `api/images/${img._id}/attachment`,
             order: img.tv_orders[tvId] || 0,
-            extension: img.getFileExtension()
+            extension: img.getFileExtension(),
+            valid_from: img.valid_from,
+            valid_until: img.valid_until,
+            web_url: img.web_url,
+            web_refresh_secs: img.web_refresh_secs
           }));
         
         // Get TV document to extract TV ID for MQTT (remove tv_ prefix)
@@ -258,7 +335,11 @@ router.post('/:id/assign', async (req, res) => {
           id: img._id,
           path: `api/images/${img._id}/attachment`,
           order: img.tv_orders[tvId] || 0,
-          extension: img.getFileExtension()
+          extension: img.getFileExtension(),
+          valid_from: img.valid_from,
+          valid_until: img.valid_until,
+          web_url: img.web_url,
+          web_refresh_secs: img.web_refresh_secs
         }));
         
         if (mqttService.isConnected) {
@@ -305,7 +386,11 @@ router.delete('/:id/assign/:tvId', async (req, res) => {
         id: img._id,
         path: `api/images/${img._id}/attachment`,
         order: img.tv_orders[tvId] || 0,
-        extension: img.getFileExtension()
+        extension: img.getFileExtension(),
+        valid_from: img.valid_from,
+        valid_until: img.valid_until,
+        web_url: img.web_url,
+        web_refresh_secs: img.web_refresh_secs
       }));
       
       // Get TV document to extract TV ID for MQTT (remove tv_ prefix)
@@ -359,7 +444,11 @@ router.post('/reorder/:tvId', async (req, res) => {
         id: img._id,
         path: `api/images/${img._id}/attachment`,
         order: img.tv_orders[tvId] || 0,
-        extension: img.getFileExtension()
+        extension: img.getFileExtension(),
+        valid_from: img.valid_from,
+        valid_until: img.valid_until,
+        web_url: img.web_url,
+        web_refresh_secs: img.web_refresh_secs
       }));
       
       // Get TV document to extract TV ID for MQTT (remove tv_ prefix)
@@ -411,7 +500,11 @@ router.post('/shuffle/:tvId', async (req, res) => {
         id: img._id,
         path: `api/images/${img._id}/attachment`,
         order: img.tv_orders[tvId] || 0,
-        extension: img.getFileExtension()
+        extension: img.getFileExtension(),
+        valid_from: img.valid_from,
+        valid_until: img.valid_until,
+        web_url: img.web_url,
+        web_refresh_secs: img.web_refresh_secs
       }));
       
       // Get TV document to extract TV ID for MQTT (remove tv_ prefix)
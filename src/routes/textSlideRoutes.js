@@ -0,0 +1,130 @@
+const express = require('express');
+const router = express.Router();
+const TextSlide = require('../models/textSlide');
+const TV = require('../models/tv');
+const mqttService = require('../services/mqttService');
+const Joi = require('joi');
+
+const hexColor = Joi.string().pattern(/^#[0-9a-fA-F]{6}$/).messages({
+  'string.pattern.base': '{{#label}} must be a color like #1e3a5f'
+});
+
+// Validation schemas
+const textSlideSchema = Joi.object({
+  title: Joi.string().max(200).required(),
+  body: Joi.string().max(2000).allow('').default(''),
+  background_color: hexColor.default('#1e3a5f'),
+  text_color: hexColor.default('#ffffff'),
+  template: Joi.string().valid('centered', 'left', 'banner').default('centered'),
+  countdown_to: Joi.string().isoDate().allow(null),
+  assigned_tvs: Joi.array().items(Joi.string()).default([]),
+  assigned_tags: Joi.array().items(Joi.string().pattern(/^[A-Za-z0-9._-]+$/).max(64)).default([]),
+  valid_from: Joi.string().isoDate().allow(null),
+  valid_until: Joi.string().isoDate().allow(null)
+});
+
+const textSlideUpdateSchema = textSlideSchema.fork(['title'], field => field.optional());
+
+// Tell each TV, and each TV carrying one of the tags, to fetch its slides again, so the change
+// shows without waiting for the next sync
+async function syncTvs(tvIds, tags = []) {
+  const tagged = await TV.findByTags(tags);
+  for (const tvId of new Set([...tvIds, ...tagged.map(tv => tv._id)])) {
+    try {
+      const tv = await TV.findById(tvId);
+      if (tv) {
+        await mqttService.syncTv(tv._id.replace('tv_', ''));
+      }
+    } catch (mqttError) {
+      console.error(`Error syncing TV ${tvId} after text slide change:`, mqttError);
+    }
+  }
+}
+
+// GET /api/text-slides - Get all text slides
+router.get('/', async (req, res) => {
+  try {
+    const textSlides = await TextSlide.findAll();
+    res.json(textSlides);
+  } catch (error) {
+    console.error('Error fetching text slides:', error);
+    res.status(500).json({ error: 'Failed to fetch text slides' });
+  }
+});
+
+// GET /api/text-slides/:id - Get a text slide
+router.get('/:id', async (req, res) => {
+  try {
+    const textSlide = await TextSlide.findById(req.params.id);
+    if (!textSlide) {
+      return res.status(404).json({ error: 'Text slide not found' });
+    }
+    res.json(textSlide);
+  } catch (error) {
+    console.error('Error fetching text slide:', error);
+    res.status(500).json({ error: 'Failed to fetch text slide' });
+  }
+});
+
+// POST /api/text-slides - Create a text slide
+router.post('/', async (req, res) => {
+  try {
+    const { error, value } = textSlideSchema.validate(req.body);
+    if (error) {
+      return res.status(400).json({ error: error.details[0].message });
+    }
+
+    const textSlide = new TextSlide(value);
+    await textSlide.save();
+    await syncTvs(textSlide.assigned_tvs, textSlide.assigned_tags);
+    res.status(201).json(textSlide);
+  } catch (error) {
+    console.error('Error creating text slide:', error);
+    res.status(500).json({ error: 'Failed to create text slide' });
+  }
+});
+
+// PUT /api/text-slides/:id - Update a text slide
+router.put('/:id', async (req, res) => {
+  try {
+    const textSlide = await TextSlide.findById(req.params.id);
+    if (!textSlide) {
+      return res.status(404).json({ error: 'Text slide not found' });
+    }
+
+    const { error, value } = textSlideUpdateSchema.validate(req.body, { noDefaults: true });
+    if (error) {
+      return res.status(400).json({ error: error.details[0].message });
+    }
+
+    const updatedTextSlide = await textSlide.update(value);
+    // TVs it was taken off need to drop it too
+    await syncTvs(
+      [...textSlide.assigned_tvs, ...updatedTextSlide.assigned_tvs],
+      [...textSlide.assigned_tags, ...(updatedTextSlide.assigned_tags || [])]
+    );
+    res.json(updatedTextSlide);
+  } catch (error) {
+    console.error('Error updating text slide:', error);
+    res.status(500).json({ error: 'Failed to update text slide' });
+  }
+});
+
+// DELETE /api/text-slides/:id - Delete a text slide
+router.delete('/:id', async (req, res) => {
+  try {
+    const textSlide = await TextSlide.findById(req.params.id);
+    if (!textSlide) {
+      return res.status(404).json({ error: 'Text slide not found' });
+    }
+
+    await textSlide.delete();
+    await syncTvs(textSlide.assigned_tvs, textSlide.assigned_tags);
+    res.status(204).send();
+  } catch (error) {
+    console.error('Error deleting text slide:', error);
+    res.status(500).json({ error: 'Failed to delete text slide' });
+  }
+});
+
+module.exports = router;
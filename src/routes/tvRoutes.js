@@ -1,27 +1,135 @@
 const express = require('express');
 const router = express.Router();
 const TV = require('../models/tv');
+const Schedule = require('../models/schedule');
 const mqttService = require('../services/mqttService');
 const Joi = require('joi');
 
 // Validation schemas
+// Effects the TVs know by name; 'random' picks one per slide change. 'slide' and 'wipe' are
+// older names for slide_left and wipe_left.
+const transitionEffects = [
+  'fade', 'dissolve', 'slide_left', 'slide_right', 'slide_up', 'slide_down',
+  'wipe_left', 'wipe_right', 'wipe_up', 'wipe_down', 'morph', 'bounce', 'elastic',
+  'ease_in', 'ease_out', 'ease_in_out', 'accelerated', 'circular_wipe', 'diagonal_wipe',
+  'pixelate', 'cube', 'page_curl', 'blinds', 'checkerboard',
+  'zoom_in', 'zoom_out', 'random', 'slide', 'wipe'
+];
+
+// 'default' (each effect's own curve), a CSS easing keyword, or cubic-bezier(x1, y1, x2, y2);
+// TVs ignore curves with x values outside 0-1
+const transitionEasing = Joi.alternatives().try(
+  Joi.string().valid('default', 'linear', 'ease', 'ease-in', 'ease-out', 'ease-in-out'),
+  Joi.string().pattern(/^cubic-bezier\(\s*-?[\d.]+\s*(,\s*-?[\d.]+\s*){3}\)$/)
+);
+
+// Milliseconds to play particular effects for, in place of transition_duration
+const transitionDurations = Joi.object().pattern(
+  Joi.string().valid(...transitionEffects.filter((effect) => !['random', 'slide', 'wipe'].includes(effect))),
+  Joi.number().integer().min(0)
+);
+
+// Brightness to use between two local times of day; windows may wrap past midnight
+const dimmingWindowSchema = Joi.object({
+  start: Joi.string().pattern(/^([01]?\d|2[0-3]):[0-5]\d$/).required(),
+  end: Joi.string().pattern(/^([01]?\d|2[0-3]):[0-5]\d$/).required(),
+  brightness: Joi.number().integer().min(1).max(100).required()
+});
+
+// Local time span with the screen off; days limit it to the days it starts on
+const quietWindowSchema = Joi.object({
+  start: Joi.string().pattern(/^([01]?\d|2[0-3]):[0-5]\d$/).required(),
+  end: Joi.string().pattern(/^([01]?\d|2[0-3]):[0-5]\d$/).required(),
+  days: Joi.array().items(Joi.string().valid('mon', 'tue', 'wed', 'thu', 'fri', 'sat', 'sun')).default([])
+});
+
+// Group name for targeting content at every TV that carries it (e.g. 'lobby', 'floor-3')
+const tagSchema = Joi.string().pattern(/^[A-Za-z0-9._-]+$/).max(64);
+
+// Daypart: the images a TV plays, in order, while the window is open
+const scheduleSchema = Joi.object({
+  name: Joi.string().required(),
+  start: Joi.string().pattern(/^([01]?\d|2[0-3]):[0-5]\d$/).required(),
+  end: Joi.string().pattern(/^([01]?\d|2[0-3]):[0-5]\d$/).required(),
+  days: Joi.array().items(Joi.string().valid('mon', 'tue', 'wed', 'thu', 'fri', 'sat', 'sun')).default([]),
+  image_ids: Joi.array().items(Joi.string()).min(1).required()
+});
+
+// Overscan margins in pixels along each edge of the panel, for TVs that crop the picture
+const safeAreaSchema = Joi.object({
+  top: Joi.number().integer().min(0).max(500).default(0),
+  right: Joi.number().integer().min(0).max(500).default(0),
+  bottom: Joi.number().integer().min(0).max(500).default(0),
+  left: Joi.number().integer().min(0).max(500).default(0)
+});
+
 const tvSchema = Joi.object({
   name: Joi.string().required(),
   location: Joi.string().required(),
   ip_address: Joi.string().ip().required(),
   config: Joi.object({
-    transition_effect: Joi.string().valid('fade', 'slide', 'wipe', 'dissolve').default('fade'),
+    transition_effect: Joi.string().valid(...transitionEffects).default('fade'),
     display_duration: Joi.number().min(1000).max(60000).default(5000),
     resolution: Joi.string().default('1920x1080'),
-    orientation: Joi.string().valid('landscape', 'portrait', 'inverted_landscape', 'inverted_portrait').default('landscape')
+    orientation: Joi.string().valid('landscape', 'portrait', 'inverted_landscape', 'inverted_portrait').default('landscape'),
+    content_safety: Joi.boolean().default(false),
+    text_profile: Joi.string().valid('standard', 'high_contrast').default('standard'),
+    framebuffer_refresh_secs: Joi.number().integer().min(0).max(3600).default(0),
+    skip_redundant_writes: Joi.boolean().default(false),
+    gamma: Joi.number().min(0.5).max(3.0).default(1.0),
+    color_temperature: Joi.number().integer().min(2000).max(10000).default(6500),
+    brightness: Joi.number().integer().min(1).max(100).default(100),
+    dimming_schedule: Joi.array().items(dimmingWindowSchema).default([]),
+    quiet_hours: Joi.array().items(quietWindowSchema).default([]),
+    curtain_hours: Joi.array().items(quietWindowSchema).default([]),
+    curtain_color: Joi.string().pattern(/^#[0-9a-fA-F]{6}$/).default('#000000'),
+    curtain_image: Joi.string().allow(null).default(null),
+    letterbox_fill: Joi.string().valid('color', 'blur', 'image').default('color'),
+    letterbox_color: Joi.string().pattern(/^#[0-9a-fA-F]{6}$/).default('#000000'),
+    letterbox_image: Joi.string().allow(null).default(null),
+    safe_area: safeAreaSchema.default({ top: 0, right: 0, bottom: 0, left: 0 }),
+    ken_burns: Joi.string().valid('off', 'zoom_in', 'zoom_out', 'pan', 'random').default('off'),
+    ken_burns_intensity: Joi.number().min(0).max(0.5).default(0.15),
+    smart_crop: Joi.boolean().default(false),
+    transition_easing: transitionEasing.default('default'),
+    transition_durations: transitionDurations.default({}),
+    shuffle: Joi.boolean().default(false),
+    ticker_rss_url: Joi.string().uri({ scheme: ['http', 'https'] }).allow(null).default(null),
+    tags: Joi.array().items(tagSchema).default([])
   }).default({})
 });
 
 const configUpdateSchema = Joi.object({
-  transition_effect: Joi.string().valid('fade', 'slide', 'wipe', 'dissolve'),
+  transition_effect: Joi.string().valid(...transitionEffects),
   display_duration: Joi.number().min(1000).max(60000),
   resolution: Joi.string(),
-  orientation: Joi.string().valid('landscape', 'portrait', 'inverted_landscape', 'inverted_portrait')
+  orientation: Joi.string().valid('landscape', 'portrait', 'inverted_landscape', 'inverted_portrait'),
+  content_safety: Joi.boolean(),
+  text_profile: Joi.string().valid('standard', 'high_contrast'),
+  framebuffer_refresh_secs: Joi.number().integer().min(0).max(3600),
+  skip_redundant_writes: Joi.boolean(),
+  gamma: Joi.number().min(0.5).max(3.0),
+  color_temperature: Joi.number().integer().min(2000).max(10000),
+  brightness: Joi.number().integer().min(1).max(100),
+  dimming_schedule: Joi.array().items(dimmingWindowSchema),
+  quiet_hours: Joi.array().items(quietWindowSchema),
+  curtain_hours: Joi.array().items(quietWindowSchema),
+  curtain_color: Joi.string().pattern(/^#[0-9a-fA-F]{6}$/),
+  curtain_image: Joi.string().allow(null),
+  letterbox_fill: Joi.string().valid('color', 'blur', 'image'),
+  letterbox_color: Joi.string().pattern(/^#[0-9a-fA-F]{6}$/),
+  // Id of an uploaded image to show behind letterboxed content; null removes it
+  letterbox_image: Joi.string().allow(null),
+  safe_area: safeAreaSchema,
+  ken_burns: Joi.string().valid('off', 'zoom_in', 'zoom_out', 'pan', 'random'),
+  ken_burns_intensity: Joi.number().min(0).max(0.5),
+  smart_crop: Joi.boolean(),
+  transition_easing: transitionEasing,
+  transition_durations: transitionDurations,
+  shuffle: Joi.boolean(),
+  // RSS or Atom feed whose headlines scroll along the bottom of the screen; null removes it
+  ticker_rss_url: Joi.string().uri({ scheme: ['http', 'https'] }).allow(null),
+  tags: Joi.array().items(tagSchema)
 });
 
 // GET /api/tvs - Get all TVs
@@ -198,8 +306,74 @@ router.post('/:id/control/:action', async (req, res) => {
       case 'play':
         await mqttService.playSlideshow(tvId);
         break;
-      case 'pause':
-        await mqttService.pauseSlideshow(tvId);
+      case 'pause': {
+        const minutes = req.body && req.body.minutes;
+        if (minutes !== undefined && (!Number.isInteger(minutes) || minutes < 1 || minutes > 1440)) {
+          return res.status(400).json({ error: 'minutes must be an integer between 1 and 1440' });
+        }
+        await mqttService.pauseSlideshow(tvId, minutes);
+        break;
+      }
+      case 'extend': {
+        const seconds = req.body && req.body.seconds;
+        if (!Number.isInteger(seconds) || seconds === 0 || Math.abs(seconds) > 3600) {
+          return res.status(400).json({ error: 'seconds must be a non-zero integer between -3600 and 3600' });
+        }
+        await mqttService.extendSlide(tvId, seconds);
+        break;
+      }
+      case 'wake': {
+        const minutes = req.body && req.body.minutes;
+        if (minutes !== undefined && (!Number.isInteger(minutes) || minutes < 1 || minutes > 1440)) {
+          return res.status(400).json({ error: 'minutes must be an integer between 1 and 1440' });
+        }
+        await mqttService.wakeTv(tvId, minutes);
+        break;
+      }
+      case 'curtain': {
+        const { enabled, minutes } = req.body || {};
+        if (enabled !== undefined && typeof enabled !== 'boolean') {
+          return res.status(400).json({ error: 'enabled must be true or false' });
+        }
+        if (minutes !== undefined && (!Number.isInteger(minutes) || minutes < 1 || minutes > 1440)) {
+          return res.status(400).json({ error: 'minutes must be an integer between 1 and 1440' });
+        }
+        await mqttService.setCurtain(tvId, enabled !== false, minutes);
+        break;
+      }
+      case 'test_pattern': {
+        const pattern = req.body && req.body.pattern;
+        if (pattern !== undefined && !['bars', 'grid', 'overscan', 'off'].includes(pattern)) {
+          return res.status(400).json({ error: 'pattern must be one of bars, grid, overscan or off' });
+        }
+        await mqttService.showTestPattern(tvId, pattern);
+        break;
+      }
+      case 'takeover': {
+        const { image_id: imageId, text, minutes } = req.body || {};
+        if ((imageId === undefined) === (text === undefined)) {
+          return res.status(400).json({ error: 'takeover needs exactly one of image_id or text' });
+        }
+        if (imageId !== undefined && (typeof imageId !== 'string' || imageId === '')) {
+          return res.status(400).json({ error: 'image_id must be an image id' });
+        }
+        if (text !== undefined && (typeof text !== 'string' || text.trim() === '' || text.length > 500)) {
+          return res.status(400).json({ error: 'text must be between 1 and 500 characters' });
+        }
+        if (minutes !== undefined && (!Number.isInteger(minutes) || minutes < 1 || minutes > 1440)) {
+          return res.status(400).json({ error: 'minutes must be an integer between 1 and 1440' });
+        }
+        await mqttService.startTakeover(tvId, { image_id: imageId, text, minutes });
+        break;
+      }
+      case 'release':
+        await mqttService.releaseTakeover(tvId);
+        break;
+      case 'hold':
+        await mqttService.holdSlide(tvId);
+        break;
+      case 'resume':
+        await mqttService.resumeRotation(tvId);
         break;
       case 'next':
         await mqttService.nextImage(tvId);
@@ -207,9 +381,58 @@ router.post('/:id/control/:action', async (req, res) => {
       case 'previous':
         await mqttService.previousImage(tvId);
         break;
+      case 'shuffle': {
+        const enabled = req.body && req.body.enabled;
+        if (enabled !== undefined && typeof enabled !== 'boolean') {
+          return res.status(400).json({ error: 'enabled must be true or false' });
+        }
+        // Stored in the config too, so the TV's next CouchDB sync doesn't undo it
+        await tv.update({ config: { ...tv.config, shuffle: enabled !== false } });
+        await mqttService.setShuffle(tvId, enabled !== false);
+        break;
+      }
+      case 'ticker': {
+        const text = req.body && req.body.text;
+        if (text !== undefined && text !== null && (typeof text !== 'string' || text.length > 2000)) {
+          return res.status(400).json({ error: 'text must be at most 2000 characters' });
+        }
+        await mqttService.setTicker(tvId, text);
+        break;
+      }
+      case 'sync':
+        await mqttService.syncTv(tvId);
+        break;
+      case 'restart':
+        await mqttService.restartTv(tvId);
+        break;
       case 'reboot':
         await mqttService.rebootTv(tvId);
         break;
+      case 'cec': {
+        const cecAction = req.body && req.body.action;
+        if (!['on', 'standby', 'input'].includes(cecAction)) {
+          return res.status(400).json({ error: 'action must be one of on, standby or input' });
+        }
+        await mqttService.controlTvPower(tvId, cecAction);
+        break;
+      }
+      case 'stop':
+        await mqttService.stopSlideshow(tvId);
+        break;
+      case 'start':
+        await mqttService.startSlideshow(tvId);
+        break;
+      case 'shutdown':
+        await mqttService.shutdownTv(tvId);
+        break;
+      case 'set_log_level': {
+        const level = req.body && req.body.level;
+        if (typeof level !== 'string' || !level.trim() || level.length > 500) {
+          return res.status(400).json({ error: 'level must be a log level or filter of at most 500 characters' });
+        }
+        await mqttService.setLogLevel(tvId, level.trim());
+        break;
+      }
       default:
         return res.status(400).json({ error: 'Invalid action' });
     }
@@ -251,6 +474,81 @@ router.put('/:id/config', async (req, res) => {
   }
 });
 
+// GET /api/tvs/:id/schedules - Get a TV's daypart schedules
+router.get('/:id/schedules', async (req, res) => {
+  try {
+    const tv = await TV.findById(req.params.id);
+    if (!tv) {
+      return res.status(404).json({ error: 'TV not found' });
+    }
+
+    const schedules = await Schedule.findByTv(tv._id);
+    res.json(schedules);
+  } catch (error) {
+    console.error('Error fetching schedules:', error);
+    res.status(500).json({ error: 'Failed to fetch schedules' });
+  }
+});
+
+// POST /api/tvs/:id/schedules - Add a daypart schedule
+router.post('/:id/schedules', async (req, res) => {
+  try {
+    const tv = await TV.findById(req.params.id);
+    if (!tv) {
+      return res.status(404).json({ error: 'TV not found' });
+    }
+
+    const { error, value } = scheduleSchema.validate(req.body);
+    if (error) {
+      return res.status(400).json({ error: error.details[0].message });
+    }
+
+    const schedule = new Schedule({ ...value, assigned_tv_id: tv._id });
+    await schedule.save();
+    res.status(201).json(schedule);
+  } catch (error) {
+    console.error('Error creating schedule:', error);
+    res.status(500).json({ error: 'Failed to create schedule' });
+  }
+});
+
+// PUT /api/tvs/:id/schedules/:scheduleId - Update a daypart schedule
+router.put('/:id/schedules/:scheduleId', async (req, res) => {
+  try {
+    const schedule = await Schedule.findById(req.params.scheduleId);
+    if (!schedule || schedule.assigned_tv_id !== req.params.id) {
+      return res.status(404).json({ error: 'Schedule not found' });
+    }
+
+    const { error, value } = scheduleSchema.validate(req.body);
+    if (error) {
+      return res.status(400).json({ error: error.details[0].message });
+    }
+
+    const updatedSchedule = await schedule.update(value);
+    res.json(updatedSchedule);
+  } catch (error) {
+    console.error('Error updating schedule:', error);
+    res.status(500).json({ error: 'Failed to update schedule' });
+  }
+});
+
+// DELETE /api/tvs/:id/schedules/:scheduleId - Delete a daypart schedule
+router.delete('/:id/schedules/:scheduleId', async (req, res) => {
+  try {
+    const schedule = await Schedule.findById(req.params.scheduleId);
+    if (!schedule || schedule.assigned_tv_id !== req.params.id) {
+      return res.status(404).json({ error: 'Schedule not found' });
+    }
+
+    await schedule.delete();
+    res.status(204).send();
+  } catch (error) {
+    console.error('Error deleting schedule:', error);
+    res.status(500).json({ error: 'Failed to delete schedule' });
+  }
+});
+
 // GET /api/tvs/status/:status - Get TVs by status
 router.get('/status/:status', async (req, res) => {
   try {
@@ -12,6 +12,7 @@ const mqttService = require('./services/mqttService');
 const tvRoutes = require('./routes/tvRoutes');
 const imageRoutes = require('./routes/imageRoutes');
 const dashboardRoutes = require('./routes/dashboardRoutes');
+const textSlideRoutes = require('./routes/textSlideRoutes');
 
 require('dotenv').config();
 
@@ -45,6 +46,7 @@ app.use('/images', express.static(path.join(__dirname, '../public/images')));
 app.use('/api/tvs', tvRoutes);
 app.use('/api/images', imageRoutes);
 app.use('/api/dashboard', dashboardRoutes);
+app.use('/api/text-slides', textSlideRoutes);
 
 // Health check endpoint
 app.get('/api/health', (req, res) => {
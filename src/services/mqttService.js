@@ -10,6 +10,7 @@ class MQTTService {
     this.heartbeatTimeout = 90000; // 90 seconds (30s heartbeat + 60s grace)
     this.offlineCheckInterval = 30000; // Check every 30 seconds
     this.offlineCheckTimer = null;
+    this.maxClockSkew = 5000; // Push our time to TVs whose heartbeat timestamps drift further than this
   }
 
   async connect() {
@@ -62,6 +63,9 @@ class MQTTService {
     this.client.subscribe('signage/tv/+/heartbeat');
     this.client.subscribe('signage/tv/+/error');
     this.client.subscribe('signage/tv/+/image/current');
+    this.client.subscribe('signage/tv/+/schedule');
+    this.client.subscribe('signage/tv/+/update');
+    this.client.subscribe('signage/tv/+/crash');
     
     // Subscribe to all MQTT messages for activity monitoring
     this.client.subscribe('#'); // This subscribes to all topics
@@ -96,6 +100,15 @@ class MQTTService {
             case 'error':
               await this.handleError(tvId, payload);
               break;
+            case 'schedule':
+              this.handleScheduleChange(tvId, payload);
+              break;
+            case 'update':
+              await this.handleUpdateProgress(tvId, payload);
+              break;
+            case 'crash':
+              await this.handleCrash(tvId, payload);
+              break;
             case 'image':
               if (parts[4] === 'current') {
                 await this.handleCurrentImageUpdate(tvId, payload);
@@ -118,7 +131,8 @@ class MQTTService {
       if (tv) {
         await tv.update({
           status: payload.status,
-          last_heartbeat: new Date().toISOString()
+          last_heartbeat: new Date().toISOString(),
+          ...(payload.play_counts && { play_counts: payload.play_counts })
         });
         console.log(`TV ${tvId} status updated to ${payload.status}`);
       }
@@ -127,11 +141,13 @@ class MQTTService {
     }
   }
 
-  async handleHeartbeat(tvId, _payload) {
+  async handleHeartbeat(tvId, payload) {
+    await this.checkClockSkew(tvId, payload);
+
     try {
       const tv = await TV.findById(`tv_${tvId}`);
       if (tv) {
-        await tv.updateHeartbeat();
+        await tv.updateHeartbeat(payload || {});
       } else {
         // Auto-create TV from heartbeat if it doesn't exist
         console.log(`Auto-creating TV ${tvId} from heartbeat`);
@@ -157,11 +173,78 @@ class MQTTService {
     }
   }
 
+  // TVs with a wrong clock can't fix it themselves; send them our time so their
+  // published timestamps line up with the dashboard
+  async checkClockSkew(tvId, payload) {
+    const reported = Date.parse(payload && payload.timestamp);
+    if (Number.isNaN(reported)) {
+      return;
+    }
+
+    const skew = reported - Date.now();
+    if (Math.abs(skew) > this.maxClockSkew) {
+      console.log(`TV ${tvId} clock is off by ${skew}ms, sending time sync`);
+      try {
+        await this.syncTvClock(tvId);
+      } catch (error) {
+        console.error(`Error sending time sync to TV ${tvId}:`, error);
+      }
+    }
+  }
+
   async handleError(tvId, payload) {
     console.error(`TV ${tvId} reported error:`, payload);
     // Could store errors in database or send alerts
   }
 
+  // Self-update steps: downloading, failed, restarting, health_check, installed, rolled_back
+  async handleUpdateProgress(tvId, payload) {
+    console.log(`TV ${tvId} update to ${payload.version}: ${payload.stage}${payload.error ? ` (${payload.error})` : ''}`);
+    try {
+      const tv = await TV.findById(`tv_${tvId}`);
+      if (tv) {
+        await tv.update({
+          update_status: {
+            stage: payload.stage,
+            version: payload.version,
+            running_version: payload.running_version,
+            error: payload.error || null,
+            timestamp: payload.timestamp
+          }
+        });
+      }
+    } catch (error) {
+      console.error(`Error updating TV ${tvId} update status:`, error);
+    }
+  }
+
+  // Sent once a TV is back up after a panic or fatal error; the backtrace stays in the message
+  // log, the TV document keeps what's needed to spot a TV that keeps crashing
+  async handleCrash(tvId, payload) {
+    console.error(`TV ${tvId} crashed at ${payload.crashed_at} (${payload.kind}, version ${payload.version}): ${payload.message}`);
+    try {
+      const tv = await TV.findById(`tv_${tvId}`);
+      if (tv) {
+        await tv.update({
+          last_crash: {
+            kind: payload.kind,
+            message: payload.message,
+            location: payload.location || null,
+            version: payload.version,
+            last_command: payload.last_command || null,
+            crashed_at: payload.crashed_at
+          }
+        });
+      }
+    } catch (error) {
+      console.error(`Error recording TV ${tvId} crash:`, error);
+    }
+  }
+
+  handleScheduleChange(tvId, payload) {
+    console.log(`TV ${tvId} switched to schedule ${payload.schedule || '(unscheduled)'} with ${payload.images} images`);
+  }
+
   async handleCurrentImageUpdate(tvId, payload) {
     try {
       const tv = await TV.findById(`tv_${tvId}`);
@@ -207,8 +290,51 @@ class MQTTService {
     return this.sendCommand(tvId, 'play');
   }
 
-  async pauseSlideshow(tvId) {
-    return this.sendCommand(tvId, 'pause');
+  // With minutes, the TV resumes by itself once the time is up
+  async pauseSlideshow(tvId, minutes) {
+    return this.sendCommand(tvId, 'pause', minutes ? { minutes } : {});
+  }
+
+  // Lengthen the current slide's time on screen by seconds, or shorten it when negative
+  async extendSlide(tvId, seconds) {
+    return this.sendCommand(tvId, 'extend', { seconds });
+  }
+
+  // Turns the screen back on during quiet hours; without minutes the TV picks the duration
+  async wakeTv(tvId, minutes) {
+    return this.sendCommand(tvId, 'wake', minutes ? { minutes } : {});
+  }
+
+  // Draw (or with closed false, lift) the curtain until the TV's curtain hours next change, or
+  // for minutes when given
+  async setCurtain(tvId, closed, minutes) {
+    const payload = { enabled: closed };
+    return this.sendCommand(tvId, 'curtain', minutes ? { ...payload, minutes } : payload);
+  }
+
+  // Calibration screen (bars, grid, overscan) in place of the slideshow; 'off' restores it
+  async showTestPattern(tvId, pattern) {
+    return this.sendCommand(tvId, 'test_pattern', pattern ? { pattern } : {});
+  }
+
+  // Emergency content (an image_id or a text message) over the slideshow until released, or
+  // for minutes when given
+  async startTakeover(tvId, { image_id, text, minutes }) {
+    const payload = image_id !== undefined ? { image_id } : { text };
+    return this.sendCommand(tvId, 'takeover', minutes ? { ...payload, minutes } : payload);
+  }
+
+  async releaseTakeover(tvId) {
+    return this.sendCommand(tvId, 'release');
+  }
+
+  // Keep the current slide up until resumeRotation; unlike pause, videos and countdowns keep playing
+  async holdSlide(tvId) {
+    return this.sendCommand(tvId, 'hold');
+  }
+
+  async resumeRotation(tvId) {
+    return this.sendCommand(tvId, 'resume');
   }
 
   async nextImage(tvId) {
@@ -219,6 +345,38 @@ class MQTTService {
     return this.sendCommand(tvId, 'previous');
   }
 
+  // Random play order, every image once per cycle; false goes back to playlist order
+  async setShuffle(tvId, enabled) {
+    return this.sendCommand(tvId, 'shuffle', { enabled });
+  }
+
+  // Scrolling text along the bottom of the screen, in place of any ticker feed headlines.
+  // Retained, so a TV that reconnects picks it up; empty text clears it.
+  async setTicker(tvId, text) {
+    if (!this.isConnected) {
+      throw new Error('MQTT client not connected');
+    }
+
+    const topic = `signage/tv/${tvId}/ticker`;
+    const message = text ? JSON.stringify({ text }) : '';
+
+    return new Promise((resolve, reject) => {
+      this.client.publish(topic, message, { retain: true }, (error) => {
+        if (error) {
+          reject(error);
+        } else {
+          console.log(`Ticker ${text ? 'set' : 'cleared'} on TV ${tvId}`);
+          resolve();
+        }
+      });
+    });
+  }
+
+  // Have the TV fetch its slides from CouchDB now, e.g. after a text slide changed
+  async syncTv(tvId) {
+    return this.sendCommand(tvId, 'sync');
+  }
+
   async updateImages(tvId, imageList) {
     return this.sendCommand(tvId, 'update_images', { images: imageList });
   }
@@ -228,10 +386,43 @@ class MQTTService {
     return this.sendCommand(tvId, 'update_config', config);
   }
 
+  async syncTvClock(tvId) {
+    return this.sendCommand(tvId, 'time_sync', { server_time: new Date().toISOString() });
+  }
+
+  async restartTv(tvId) {
+    return this.sendCommand(tvId, 'restart');
+  }
+
   async rebootTv(tvId) {
     return this.sendCommand(tvId, 'reboot');
   }
 
+  // Power the TV attached to the Pi 'on' or to 'standby', or switch it to the Pi's 'input',
+  // over HDMI-CEC
+  async controlTvPower(tvId, action) {
+    return this.sendCommand(tvId, 'cec', { action });
+  }
+
+  // Blank the screen and stop rendering, leaving the endpoint running until startSlideshow
+  async stopSlideshow(tvId) {
+    return this.sendCommand(tvId, 'stop');
+  }
+
+  async startSlideshow(tvId) {
+    return this.sendCommand(tvId, 'start');
+  }
+
+  // Powers the Pi off; it stays off until power is cycled
+  async shutdownTv(tvId) {
+    return this.sendCommand(tvId, 'shutdown');
+  }
+
+  // Log level or RUST_LOG-style filter for the endpoint, until it restarts
+  async setLogLevel(tvId, level) {
+    return this.sendCommand(tvId, 'set_log_level', { level });
+  }
+
   // WebSocket notification system
   addSubscriber(id, callback) {
     this.subscribers.set(id, callback);